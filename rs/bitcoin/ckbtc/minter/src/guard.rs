@@ -100,6 +100,9 @@ mod tests {
             retrieve_btc_min_amount: 0,
             pending_retrieve_btc_requests: Default::default(),
             ledger_id: CanisterId::from_u64(42),
+            min_confirmations: 0,
+            minted_utxos: Default::default(),
+            pending_mints: Default::default(),
         }
     }
 
@@ -4,6 +4,10 @@ use ic_base_types::CanisterId;
 use ic_btc_types::Network;
 use serde::Serialize;
 
+/// The number of confirmations after which the minter considers a deposit
+/// final unless the init arguments say otherwise.
+pub const DEFAULT_MIN_CONFIRMATIONS: u32 = 6;
+
 #[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct InitArgs {
     /// The bitcoin network that the minter will connect to
@@ -21,6 +25,10 @@ pub struct InitArgs {
 
     /// The CanisterId of the ckBTC Ledger
     pub ledger_id: CanisterId,
+
+    /// Minimum number of confirmations a deposit needs before the minter
+    /// mints ckBTC for it, [DEFAULT_MIN_CONFIRMATIONS] if not set
+    pub min_confirmations: Option<u32>,
 }
 
 pub fn init(args: InitArgs) {
@@ -34,5 +42,8 @@ pub fn init(args: InitArgs) {
         retrieve_btc_min_amount: args.retrieve_btc_min_amount,
         pending_retrieve_btc_requests: Default::default(),
         ledger_id: args.ledger_id,
+        min_confirmations: args.min_confirmations.unwrap_or(DEFAULT_MIN_CONFIRMATIONS),
+        minted_utxos: Default::default(),
+        pending_mints: Default::default(),
     });
 }
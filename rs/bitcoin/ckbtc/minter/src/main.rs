@@ -3,6 +3,9 @@ use ic_cdk_macros::{init, post_upgrade, pre_upgrade, update};
 use ic_ckbtc_minter::lifecycle::{self, init::InitArgs, upgrade::UpgradeArgs};
 use ic_ckbtc_minter::metrics::encode_metrics;
 use ic_ckbtc_minter::updates::retrieve_btc::{RetrieveBtcArgs, RetrieveBtcErr, RetrieveBtcOk};
+use ic_ckbtc_minter::updates::update_balance::{
+    UpdateBalanceArgs, UpdateBalanceError, UpdateBalanceResult,
+};
 use ic_ckbtc_minter::updates::{
     self,
     get_btc_address::{GetBtcAddressArgs, GetBtcAddressResult},
@@ -42,6 +45,14 @@ async fn retrieve_btc(args: RetrieveBtcArgs) -> Result<RetrieveBtcOk, RetrieveBt
     updates::retrieve_btc::retrieve_btc(args).await
 }
 
+#[candid_method(update)]
+#[update]
+async fn update_balance(
+    args: UpdateBalanceArgs,
+) -> Result<UpdateBalanceResult, UpdateBalanceError> {
+    updates::update_balance::update_balance(args).await
+}
+
 #[export_name = "canister_query http_request"]
 fn http_request() {
     dfn_http_metrics::serve_metrics(encode_metrics);
@@ -5,12 +5,13 @@
 ///! code should use those functions instead of touching `__STATE` directly.
 use std::{
     cell::RefCell,
-    collections::{BTreeSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, VecDeque},
 };
 
 use candid::Principal;
 use ic_base_types::CanisterId;
-use ic_btc_types::Network;
+use ic_btc_types::{Network, Utxo};
+use ic_icrc1::Account;
 
 use crate::ECDSAPublicKey;
 
@@ -27,6 +28,14 @@ pub struct RetrieveBtcRequest {
     pub block_index: u64,
 }
 
+/// A mint of ckBTC for deposits that the minter started but did not record
+/// as completed yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingMint {
+    pub utxos: Vec<Utxo>,
+    pub created_at_time: u64,
+}
+
 /// The state of the ckBTC Minter.
 ///
 /// Every piece of state of the Minter should be stored as field of this struct.
@@ -59,6 +68,38 @@ pub struct CkBtcMinterState {
 
     /// The CanisterId of the ckBTC Ledger
     pub ledger_id: CanisterId,
+
+    /// Minimum number of confirmations a deposit needs before the minter
+    /// mints ckBTC for it
+    pub min_confirmations: u32,
+
+    /// The deposits for which the minter already minted ckBTC, per account
+    pub minted_utxos: BTreeMap<Account, Vec<Utxo>>,
+
+    /// The mints that were started but not recorded as completed yet, per
+    /// account. Retrying such a mint with the same arguments lets the ledger
+    /// deduplicate it.
+    pub pending_mints: BTreeMap<Account, PendingMint>,
+}
+
+impl CkBtcMinterState {
+    /// Returns true if the minter already minted ckBTC for the specified
+    /// deposit to the account.
+    ///
+    /// Deposits are identified by their outpoint only, because the height
+    /// of a deposit can change if the bitcoin network reorganizes.
+    pub fn is_minted(&self, account: &Account, utxo: &Utxo) -> bool {
+        self.minted_utxos
+            .get(account)
+            .map(|utxos| utxos.iter().any(|u| u.outpoint == utxo.outpoint))
+            .unwrap_or(false)
+    }
+
+    /// Records that the minter minted ckBTC for the specified deposits to the
+    /// account.
+    pub fn add_minted_utxos(&mut self, account: Account, utxos: Vec<Utxo>) {
+        self.minted_utxos.entry(account).or_default().extend(utxos);
+    }
 }
 
 /// Take the current state.
@@ -76,15 +76,21 @@ fn network_and_public_key_to_p2wpkh(network: Network, public_key: Vec<u8>) -> St
     bech32::encode(hrp, data, Variant::Bech32).unwrap()
 }
 
+/// Returns the p2wpkh address that receives the deposits of the specified account.
+///
+/// The Minter ECDSA public key must be initialized.
+pub fn account_to_p2wpkh_address(account: Account) -> String {
+    let public_key = derive_public_key(account).public_key;
+    network_and_public_key_to_p2wpkh(read_state(|s| s.btc_network), public_key)
+}
+
 pub async fn get_btc_address(args: GetBtcAddressArgs) -> GetBtcAddressResult {
     let caller = PrincipalId(ic_cdk::caller());
     init_ecdsa_public_key().await;
-    let public_key = derive_public_key(Account {
+    let address = account_to_p2wpkh_address(Account {
         owner: caller,
         subaccount: args.subaccount,
-    })
-    .public_key;
-    let address = network_and_public_key_to_p2wpkh(read_state(|s| s.btc_network), public_key);
+    });
     GetBtcAddressResult { address }
 }
 
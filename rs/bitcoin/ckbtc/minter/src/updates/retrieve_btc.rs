@@ -163,6 +163,9 @@ mod tests {
             retrieve_btc_min_amount: 0,
             pending_retrieve_btc_requests: Default::default(),
             ledger_id: ic_base_types::CanisterId::from_u64(42),
+            min_confirmations: 0,
+            minted_utxos: Default::default(),
+            pending_mints: Default::default(),
         });
         assert_eq!(
             Ok(()),
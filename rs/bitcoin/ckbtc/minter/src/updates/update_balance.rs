@@ -0,0 +1,244 @@
+use candid::{CandidType, Deserialize, Nat, Principal};
+use ic_base_types::PrincipalId;
+use ic_btc_types::{
+    GetUtxosRequest, GetUtxosResponse, Network, NetworkInRequest, Utxo, UtxosFilterInRequest,
+};
+use ic_crypto_sha::Sha256;
+use ic_icrc1::{
+    endpoints::{TransferArg, TransferError},
+    Account, Memo, Subaccount,
+};
+use ic_icrc1_client_cdk::{CdkRuntime, ICRC1Client};
+use num_traits::ToPrimitive;
+use serde::Serialize;
+
+use crate::{
+    guard::{balance_update_guard, GuardError},
+    state::{mutate_state, read_state, PendingMint},
+};
+
+use super::get_btc_address::{account_to_p2wpkh_address, init_ecdsa_public_key};
+
+/// The number of cycles attached to a `bitcoin_get_utxos` call.
+const GET_UTXOS_COST_CYCLES: u64 = 100_000_000;
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct UpdateBalanceArgs {
+    pub subaccount: Option<Subaccount>,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct UpdateBalanceResult {
+    // the amount of ckBTC minted in satoshi
+    pub amount: u64,
+
+    // the index of the mint block on the ckbtc ledger
+    pub block_index: u64,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq)]
+pub enum UpdateBalanceError {
+    /// There is another request for this principal
+    AlreadyProcessing,
+
+    /// There are no new deposits with enough confirmations
+    NoNewUtxos,
+
+    /// The call to the Bitcoin canister failed
+    BitcoinConnectionError(i32, String),
+
+    /// The mint call to the Ledger failed
+    LedgerConnectionError(i32, String),
+
+    /// The Ledger rejected the mint operation
+    LedgerError(TransferError),
+
+    /// There are too many concurrent requests, retry later
+    TooManyConcurrentRequests,
+}
+
+impl From<GuardError> for UpdateBalanceError {
+    fn from(e: GuardError) -> Self {
+        match e {
+            GuardError::AlreadyProcessing => Self::AlreadyProcessing,
+            GuardError::TooManyConcurrentRequests => Self::TooManyConcurrentRequests,
+        }
+    }
+}
+
+impl From<TransferError> for UpdateBalanceError {
+    fn from(e: TransferError) -> Self {
+        Self::LedgerError(e)
+    }
+}
+
+/// Mints ckBTC for the deposits to the caller's bitcoin address that have
+/// enough confirmations and were not minted yet.
+pub async fn update_balance(
+    args: UpdateBalanceArgs,
+) -> Result<UpdateBalanceResult, UpdateBalanceError> {
+    let caller = ic_cdk::caller();
+    init_ecdsa_public_key().await;
+    // The guard makes sure that the minter does not mint the same deposits
+    // twice if the caller sends concurrent requests.
+    let _guard = balance_update_guard(caller)?;
+
+    let account = Account {
+        owner: PrincipalId(caller),
+        subaccount: args.subaccount,
+    };
+
+    // If the minter did not record the outcome of the last mint for this
+    // account, retry that mint first so that the ledger deduplicates it.
+    let pending_mint = match read_state(|s| s.pending_mints.get(&account).cloned()) {
+        Some(pending_mint) => pending_mint,
+        None => {
+            let address = account_to_p2wpkh_address(account.clone());
+            let (btc_network, min_confirmations) =
+                read_state(|s| (s.btc_network, s.min_confirmations));
+
+            let utxos = get_utxos(btc_network, address, min_confirmations).await?;
+            let new_utxos: Vec<Utxo> = read_state(|s| {
+                utxos
+                    .into_iter()
+                    .filter(|utxo| !s.is_minted(&account, utxo))
+                    .collect()
+            });
+            if new_utxos.is_empty() {
+                return Err(UpdateBalanceError::NoNewUtxos);
+            }
+
+            let pending_mint = PendingMint {
+                utxos: new_utxos,
+                created_at_time: ic_cdk::api::time(),
+            };
+            mutate_state(|s| {
+                s.pending_mints
+                    .insert(account.clone(), pending_mint.clone())
+            });
+            pending_mint
+        }
+    };
+
+    let amount = pending_mint.utxos.iter().map(|utxo| utxo.value).sum();
+    let result = mint_ckbtcs(account.clone(), amount, &pending_mint).await;
+    mutate_state(|s| {
+        s.pending_mints.remove(&account);
+        if result.is_ok() {
+            s.add_minted_utxos(account, pending_mint.utxos);
+        }
+    });
+    let block_index = result?;
+    Ok(UpdateBalanceResult {
+        amount,
+        block_index,
+    })
+}
+
+/// Fetches all UTXOs of the address that have at least `min_confirmations`
+/// confirmations.
+async fn get_utxos(
+    network: Network,
+    address: String,
+    min_confirmations: u32,
+) -> Result<Vec<Utxo>, UpdateBalanceError> {
+    let network = match network {
+        Network::Mainnet => NetworkInRequest::Mainnet,
+        Network::Testnet => NetworkInRequest::Testnet,
+        Network::Regtest => NetworkInRequest::Regtest,
+    };
+    let mut filter = Some(UtxosFilterInRequest::MinConfirmations(min_confirmations));
+    let mut utxos = vec![];
+    loop {
+        let (response,): (GetUtxosResponse,) = ic_cdk::api::call::call_with_payment(
+            Principal::management_canister(),
+            "bitcoin_get_utxos",
+            (GetUtxosRequest {
+                address: address.clone(),
+                network,
+                filter,
+            },),
+            GET_UTXOS_COST_CYCLES,
+        )
+        .await
+        .map_err(|(code, msg)| UpdateBalanceError::BitcoinConnectionError(code as i32, msg))?;
+
+        utxos.extend(response.utxos);
+        match response.next_page {
+            Some(page) => filter = Some(UtxosFilterInRequest::Page(page)),
+            None => return Ok(utxos),
+        }
+    }
+}
+
+/// Derives the memo of the mint for the deposits from their outpoints, so
+/// that retrying the mint for the same deposits yields the same transaction.
+fn mint_memo(utxos: &[Utxo]) -> Memo {
+    let mut hasher = Sha256::new();
+    for utxo in utxos {
+        hasher.write(&utxo.outpoint.txid);
+        hasher.write(&utxo.outpoint.vout.to_be_bytes());
+    }
+    Memo::from(hasher.finish())
+}
+
+/// Mints `amount` ckBTC to the account for the deposits of the pending mint.
+/// If the ledger already executed the same mint, returns the index of its
+/// block.
+async fn mint_ckbtcs(
+    to: Account,
+    amount: u64,
+    pending_mint: &PendingMint,
+) -> Result<u64, UpdateBalanceError> {
+    let client = ICRC1Client {
+        runtime: CdkRuntime,
+        ledger_canister_id: read_state(|s| s.ledger_id.get().into()),
+    };
+    let result = client
+        .transfer(TransferArg {
+            from_subaccount: None,
+            to,
+            fee: None,
+            created_at_time: Some(pending_mint.created_at_time),
+            memo: Some(mint_memo(&pending_mint.utxos)),
+            amount: Nat::from(amount),
+        })
+        .await
+        .map_err(|(code, msg)| UpdateBalanceError::LedgerConnectionError(code, msg))?;
+    match result {
+        Ok(block_index) => Ok(block_index),
+        Err(TransferError::Duplicate { duplicate_of }) => Ok(duplicate_of
+            .0
+            .to_u64()
+            .expect("block index does not fit into u64")),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_btc_types::{OutPoint, Utxo};
+
+    use crate::updates::update_balance::mint_memo;
+
+    fn utxo(txid: u8, vout: u32, height: u32) -> Utxo {
+        Utxo {
+            outpoint: OutPoint {
+                txid: vec![txid; 32],
+                vout,
+            },
+            value: 100_000,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_mint_memo_depends_only_on_outpoints() {
+        assert_eq!(
+            mint_memo(&[utxo(1, 0, 10), utxo(2, 1, 10)]),
+            mint_memo(&[utxo(1, 0, 11), utxo(2, 1, 12)])
+        );
+        assert_ne!(mint_memo(&[utxo(1, 0, 10)]), mint_memo(&[utxo(1, 1, 10)]));
+        assert_ne!(mint_memo(&[utxo(1, 0, 10)]), mint_memo(&[utxo(2, 0, 10)]));
+    }
+}
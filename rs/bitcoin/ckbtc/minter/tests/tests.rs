@@ -1,15 +1,28 @@
-use candid::{Decode, Encode};
+use std::path::PathBuf;
+
+use candid::{Decode, Encode, Nat};
 // use canister_test::PrincipalId;
 // use canister_test::{PrincipalId, Project};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_btc_types::Network;
 use ic_ckbtc_minter::{
     lifecycle::init::InitArgs as CkbtcMinterInitArgs,
-    updates::get_btc_address::{GetBtcAddressArgs, GetBtcAddressResult},
+    updates::{
+        get_btc_address::{GetBtcAddressArgs, GetBtcAddressResult},
+        update_balance::{UpdateBalanceArgs, UpdateBalanceError, UpdateBalanceResult},
+    },
+};
+use ic_icrc1::Account;
+use ic_icrc1_ledger::InitArgs as LedgerInitArgs;
+use ic_ledger_canister_core::archive::ArchiveOptions;
+use ic_state_machine_tests::{
+    BitcoinNetwork, CanisterInstallMode, Cycles, EcdsaCurve, EcdsaKeyId, StateMachine,
+    StateMachineConfig,
 };
-use ic_state_machine_tests::StateMachine;
 use ic_test_utilities_load_wasm::load_wasm;
 
+const MIN_CONFIRMATIONS: u32 = 3;
+
 fn minter_wasm() -> Vec<u8> {
     load_wasm(
         std::env::var("CARGO_MANIFEST_DIR").unwrap(),
@@ -18,6 +31,23 @@ fn minter_wasm() -> Vec<u8> {
     )
 }
 
+fn ledger_wasm() -> Vec<u8> {
+    load_wasm(
+        PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("rosetta-api")
+            .join("icrc1")
+            .join("ledger"),
+        "ic-icrc1-ledger",
+        &[],
+    )
+}
+
 fn install_minter(env: &StateMachine) -> CanisterId {
     let args = CkbtcMinterInitArgs {
         btc_network: Network::Regtest,
@@ -27,6 +57,7 @@ fn install_minter(env: &StateMachine) -> CanisterId {
         retrieve_btc_min_fee: 0,
         retrieve_btc_min_amount: 0,
         ledger_id: CanisterId::from_u64(42),
+        min_confirmations: None,
     };
     env.install_canister(minter_wasm(), Encode!(&args).unwrap(), None)
         .unwrap()
@@ -38,7 +69,58 @@ fn test_install_ckbtc_minter_canister() {
     install_minter(&env);
 }
 
-fn _get_btc_address(
+/// Installs a minter that mints on a freshly installed ckBTC ledger and
+/// returns the IDs of the minter and of the ledger.
+fn install_minter_and_ledger(env: &StateMachine) -> (CanisterId, CanisterId) {
+    let ledger_id = env.create_canister(None);
+    let minter_args = CkbtcMinterInitArgs {
+        btc_network: Network::Regtest,
+        ecdsa_key_name: "dfx_test_key".to_string(),
+        retrieve_btc_min_fee: 0,
+        retrieve_btc_min_amount: 0,
+        ledger_id,
+        min_confirmations: Some(MIN_CONFIRMATIONS),
+    };
+    // The minter pays for the bitcoin_get_utxos calls.
+    let minter_id = env
+        .install_canister_with_cycles(
+            minter_wasm(),
+            Encode!(&minter_args).unwrap(),
+            None,
+            Cycles::new(100_000_000_000_000),
+        )
+        .unwrap();
+
+    let ledger_args = LedgerInitArgs {
+        minting_account: Account {
+            owner: minter_id.get(),
+            subaccount: None,
+        },
+        initial_balances: vec![],
+        transfer_fee: 10,
+        token_name: "ckBTC".to_string(),
+        token_symbol: "ckBTC".to_string(),
+        metadata: vec![],
+        archive_options: ArchiveOptions {
+            trigger_threshold: 2000,
+            num_blocks_to_archive: 1000,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: PrincipalId::new_user_test_id(100),
+            cycles_for_archive_creation: None,
+        },
+    };
+    env.install_wasm_in_mode(
+        ledger_id,
+        CanisterInstallMode::Install,
+        ledger_wasm(),
+        Encode!(&ledger_args).unwrap(),
+    )
+    .unwrap();
+    (minter_id, ledger_id)
+}
+
+fn get_btc_address(
     env: &StateMachine,
     ckbtc_minter: CanisterId,
     sender: PrincipalId,
@@ -58,19 +140,101 @@ fn _get_btc_address(
     }
 }
 
-// TODO: enable this test when StateMachine has support for ecdsa_public_key
-// #[test]
-// fn get_btc_address_get_withdrawal_account() {
-// let env = StateMachine::new();
-// //env.install_wasm_in_mode(canister_id, mode, wasm, payload);
-// let ckbtc_minter = install_ckbtc_minter(&env);
-// let user = PrincipalId::new_user_test_id(42);
-// println!("ckBTC Minter id: {}  user: {}", &ckbtc_minter, user);
-// let GetBtcAddressResult { address } = get_btc_address(
-//     &env,
-//     ckbtc_minter,
-//     user,
-//     GetBtcAddressArgs { subaccount: None },
-// );
-// assert_eq!(address, "");
-// }
+fn update_balance(
+    env: &StateMachine,
+    ckbtc_minter: CanisterId,
+    sender: PrincipalId,
+    arg: UpdateBalanceArgs,
+) -> Result<UpdateBalanceResult, UpdateBalanceError> {
+    let res = env
+        .execute_ingress_as(
+            sender,
+            ckbtc_minter,
+            "update_balance",
+            Encode!(&arg).unwrap(),
+        )
+        .unwrap();
+    match res {
+        canister_test::WasmResult::Reply(r) => Decode!(
+            &r,
+            Result<UpdateBalanceResult, UpdateBalanceError>
+        )
+        .unwrap(),
+        canister_test::WasmResult::Reject(e) => panic!("{}", e),
+    }
+}
+
+fn balance_of(env: &StateMachine, ledger: CanisterId, account: Account) -> Nat {
+    let res = env
+        .query(ledger, "icrc1_balance_of", Encode!(&account).unwrap())
+        .unwrap();
+    match res {
+        canister_test::WasmResult::Reply(r) => Decode!(&r, Nat).unwrap(),
+        canister_test::WasmResult::Reject(e) => panic!("{}", e),
+    }
+}
+
+/// Mines a block with the specified outputs followed by `confirmations - 1`
+/// empty blocks and lets the bitcoin canister ingest them.
+fn mine_blocks(env: &StateMachine, outputs: &[(&str, u64)], confirmations: u32) {
+    env.mine_bitcoin_block(outputs);
+    for _ in 1..confirmations {
+        env.mine_bitcoin_block(&[]);
+    }
+    // The bitcoin canister requests the blocks in one round and receives them
+    // in the next.
+    env.tick();
+    env.tick();
+}
+
+#[test]
+fn test_update_balance_mints_confirmed_deposits() {
+    let env = StateMachine::new_with_config(
+        StateMachineConfig::default()
+            .with_ecdsa_key(EcdsaKeyId {
+                curve: EcdsaCurve::Secp256k1,
+                name: "dfx_test_key".to_string(),
+            })
+            .with_bitcoin(BitcoinNetwork::Regtest),
+    );
+    let (minter, ledger) = install_minter_and_ledger(&env);
+    let user = PrincipalId::new_user_test_id(1);
+    let user_account = Account {
+        owner: user,
+        subaccount: None,
+    };
+
+    let GetBtcAddressResult { address } =
+        get_btc_address(&env, minter, user, GetBtcAddressArgs { subaccount: None });
+
+    assert_eq!(
+        update_balance(&env, minter, user, UpdateBalanceArgs { subaccount: None }),
+        Err(UpdateBalanceError::NoNewUtxos)
+    );
+
+    // The deposit does not have enough confirmations yet.
+    mine_blocks(&env, &[(&address, 100_000)], MIN_CONFIRMATIONS - 1);
+    assert_eq!(
+        update_balance(&env, minter, user, UpdateBalanceArgs { subaccount: None }),
+        Err(UpdateBalanceError::NoNewUtxos)
+    );
+
+    mine_blocks(&env, &[(&address, 50_000)], MIN_CONFIRMATIONS);
+    let result =
+        update_balance(&env, minter, user, UpdateBalanceArgs { subaccount: None }).unwrap();
+    assert_eq!(result.amount, 150_000);
+    assert_eq!(
+        balance_of(&env, ledger, user_account.clone()),
+        Nat::from(150_000_u64)
+    );
+
+    // The minter does not mint the same deposits twice.
+    assert_eq!(
+        update_balance(&env, minter, user, UpdateBalanceArgs { subaccount: None }),
+        Err(UpdateBalanceError::NoNewUtxos)
+    );
+    assert_eq!(
+        balance_of(&env, ledger, user_account),
+        Nat::from(150_000_u64)
+    );
+}
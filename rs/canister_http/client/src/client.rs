@@ -10,13 +10,13 @@ use ic_interfaces::execution_environment::AnonymousQueryService;
 use ic_interfaces_canister_http_adapter_client::{NonBlockingChannel, SendError, TryReceiveError};
 use ic_metrics::MetricsRegistry;
 use ic_types::{
-    batch::MAX_CANISTER_HTTP_PAYLOAD_SIZE,
     canister_http::{
         CanisterHttpMethod, CanisterHttpReject, CanisterHttpRequest, CanisterHttpRequestContext,
-        CanisterHttpResponse, CanisterHttpResponseContent,
+        CanisterHttpResponse, CanisterHttpResponseContent, CANISTER_HTTP_ADAPTER_MAX_RESPONSE_SIZE,
+        CANISTER_HTTP_RESPONSE_LIMIT,
     },
     messages::{AnonymousQuery, AnonymousQueryResponse, Request},
-    CanisterId, NumBytes,
+    CanisterId,
 };
 use std::time::Instant;
 use tokio::{
@@ -30,11 +30,6 @@ use tokio::{
 use tonic::{transport::Channel, Code};
 use tower::util::Oneshot;
 
-// Substract 50Kb for consensus overhead (CallbackID, Time, CanisterId, CanisterHttpResponseProof)
-const CANISTER_HTTP_RESPONSE_LIMIT: usize = MAX_CANISTER_HTTP_PAYLOAD_SIZE - 50 * 1024;
-// Hard limit that the canister http adapter enforces on the response.
-const CANISTER_HTTP_ADAPTER_MAX_RESPONSE_SIZE: NumBytes = NumBytes::new(2 * 1024 * 1024);
-
 /// This client is returend if we fail to make connection to canister http adapter.
 pub struct BrokenCanisterHttpClient {}
 
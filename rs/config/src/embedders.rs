@@ -16,6 +16,8 @@ pub(crate) const MAX_CUSTOM_SECTIONS: usize = 16;
 // The total size of the exported custom sections in bytes.
 // The size should not exceed 1MiB.
 pub(crate) const MAX_CUSTOM_SECTIONS_SIZE: NumBytes = NumBytes::new(1048576);
+/// The maximum size of the heap of a canister with a 64-bit Wasm memory.
+pub(crate) const MAX_WASM64_MEMORY_SIZE: NumBytes = NumBytes::new(8 * 1024 * 1024 * 1024);
 /// The number of threads to use for query execution.
 pub(crate) const QUERY_EXECUTION_THREADS: usize = 2;
 
@@ -32,6 +34,8 @@ const DEFAULT_WASMTIME_RAYON_COMPILATION_THREADS: usize = 10;
 pub struct FeatureFlags {
     pub rate_limiting_of_debug_prints: FlagStatus,
     pub module_sharing: FlagStatus,
+    /// Allows canisters to declare a 64-bit memory (the memory64 proposal).
+    pub wasm64: FlagStatus,
 }
 
 impl Default for FeatureFlags {
@@ -39,6 +43,7 @@ impl Default for FeatureFlags {
         Self {
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
             module_sharing: FlagStatus::Enabled,
+            wasm64: FlagStatus::Disabled,
         }
     }
 }
@@ -67,6 +72,9 @@ pub struct Config {
     /// The number of rayon threads used by wasmtime to compile wasm binaries
     pub num_rayon_compilation_threads: usize,
 
+    /// Maximum size of the heap of a canister with a 64-bit Wasm memory.
+    pub max_wasm64_memory_size: NumBytes,
+
     /// Flags to enable or disable features that are still experimental.
     pub feature_flags: FeatureFlags,
 }
@@ -82,6 +90,7 @@ impl Config {
             max_custom_sections_size: MAX_CUSTOM_SECTIONS_SIZE,
             cost_to_compile_wasm_instruction: DEFAULT_COST_TO_COMPILE_WASM_INSTRUCTION,
             num_rayon_compilation_threads: DEFAULT_WASMTIME_RAYON_COMPILATION_THREADS,
+            max_wasm64_memory_size: MAX_WASM64_MEMORY_SIZE,
             feature_flags: FeatureFlags::default(),
         }
     }
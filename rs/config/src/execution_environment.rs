@@ -23,6 +23,10 @@ const GB: u64 = 1024 * 1024 * 1024;
 /// canister's data and the deltas.
 const SUBNET_MEMORY_CAPACITY: NumBytes = NumBytes::new(350 * GB);
 
+/// The subnet memory usage above which newly allocated memory reserves cycles
+/// for its future storage fees.
+const SUBNET_MEMORY_THRESHOLD: NumBytes = NumBytes::new(250 * GB);
+
 /// This is the upper limit on how much memory can be used by all canister
 /// messages on a given subnet.
 ///
@@ -51,6 +55,19 @@ const INGRESS_HISTORY_MEMORY_CAPACITY: NumBytes = NumBytes::new(10 * GB);
 /// memory can succeed.
 pub(crate) const SUBNET_HEAP_DELTA_CAPACITY: NumBytes = NumBytes::new(140 * GB);
 
+/// The maximum depth of a call graph of a composite query. The depth of the
+/// composite query called by the user is 1, the depth of queries it calls is
+/// 2, and so on.
+const MAX_QUERY_CALL_DEPTH: usize = 6;
+
+/// The maximum number of instructions that can be executed by all queries in
+/// the call graph of a composite query combined.
+const MAX_QUERY_CALL_GRAPH_INSTRUCTIONS: NumInstructions = NumInstructions::new(5_000_000_000);
+
+/// The number of block heights during which replicas collect query statistics
+/// before reporting them via consensus.
+const QUERY_STATS_EPOCH_LENGTH: u64 = 2000;
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default)]
 pub struct Config {
@@ -66,6 +83,11 @@ pub struct Config {
     /// the subnet.
     pub subnet_memory_capacity: NumBytes,
 
+    /// The subnet memory usage above which canisters that allocate memory
+    /// reserve cycles for storage. The amount of reserved cycles grows as the
+    /// usage approaches `subnet_memory_capacity`.
+    pub subnet_memory_threshold: NumBytes,
+
     /// The maximum amount of logical storage available to canister messages
     /// across the whole subnet.
     pub subnet_message_memory_capacity: NumBytes,
@@ -121,6 +143,25 @@ pub struct Config {
 
     /// Principals of Bitcoin canisters, which can access privileged APIs.
     pub bitcoin_canisters: Vec<PrincipalId>,
+
+    /// The maximum depth of the call graph of a composite query.
+    pub max_query_call_depth: usize,
+
+    /// The maximum number of instructions executed by all messages in the
+    /// call graph of a composite query.
+    pub max_query_call_graph_instructions: NumInstructions,
+
+    /// Indicates whether canisters may use a 64-bit Wasm memory.
+    pub wasm64: FlagStatus,
+
+    /// The maximum size of the Wasm heap of a canister with a 64-bit memory.
+    /// Note that the total memory of a canister is still bounded by
+    /// `max_canister_memory_size`.
+    pub max_wasm64_memory_size: NumBytes,
+
+    /// The number of block heights during which query statistics are
+    /// collected locally before they are reported via consensus.
+    pub query_stats_epoch_length: u64,
 }
 
 impl Default for Config {
@@ -129,6 +170,7 @@ impl Default for Config {
             create_funds_whitelist: String::default(),
             max_instructions_for_message_acceptance_calls: MAX_INSTRUCTIONS_PER_MESSAGE,
             subnet_memory_capacity: SUBNET_MEMORY_CAPACITY,
+            subnet_memory_threshold: SUBNET_MEMORY_THRESHOLD,
             subnet_message_memory_capacity: SUBNET_MESSAGE_MEMORY_CAPACITY,
             ingress_history_memory_capacity: INGRESS_HISTORY_MEMORY_CAPACITY,
             max_canister_memory_size: NumBytes::new(
@@ -152,6 +194,11 @@ impl Default for Config {
             module_sharing: FlagStatus::Enabled,
             cost_to_compile_wasm_instruction: embedders::DEFAULT_COST_TO_COMPILE_WASM_INSTRUCTION,
             bitcoin_canisters: Vec::default(),
+            max_query_call_depth: MAX_QUERY_CALL_DEPTH,
+            max_query_call_graph_instructions: MAX_QUERY_CALL_GRAPH_INSTRUCTIONS,
+            wasm64: FlagStatus::Disabled,
+            max_wasm64_memory_size: embedders::MAX_WASM64_MEMORY_SIZE,
+            query_stats_epoch_length: QUERY_STATS_EPOCH_LENGTH,
         }
     }
 }
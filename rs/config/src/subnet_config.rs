@@ -120,6 +120,12 @@ const DEFAULT_REFERENCE_SUBNET_SIZE: usize = 13;
 /// Equal or above subnet size does not have storage cost subsidised.
 const FAIR_STORAGE_COST_SUBNET_SIZE: usize = 20;
 
+/// The storage reservation period at full subnet memory usage.
+///
+/// Memory allocated when the subnet is full reserves cycles for 10 years of
+/// storage. Below the usage threshold nothing is reserved.
+const MAX_STORAGE_RESERVATION_PERIOD: Duration = Duration::from_secs(10 * 365 * 24 * 3600);
+
 /// The per subnet type configuration for the scheduler component
 #[derive(Clone)]
 pub struct SchedulerConfig {
@@ -335,6 +341,11 @@ pub struct CyclesAccountManagerConfig {
 
     /// Fee per byte for networking and consensus work done for a http request or response.
     pub http_request_per_byte_fee: Cycles,
+
+    /// The upper bound on the storage reservation period. When the subnet
+    /// memory usage reaches the capacity, newly allocated memory reserves
+    /// cycles to pay for its storage for this long.
+    pub max_storage_reservation_period: Duration,
 }
 
 impl CyclesAccountManagerConfig {
@@ -365,6 +376,7 @@ impl CyclesAccountManagerConfig {
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
             http_request_baseline_fee: Cycles::new(400_000_000),
             http_request_per_byte_fee: Cycles::new(100_000),
+            max_storage_reservation_period: MAX_STORAGE_RESERVATION_PERIOD,
         }
     }
 
@@ -391,6 +403,7 @@ impl CyclesAccountManagerConfig {
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
             http_request_baseline_fee: Cycles::new(0),
             http_request_per_byte_fee: Cycles::new(0),
+            max_storage_reservation_period: Duration::from_secs(0),
         }
     }
 
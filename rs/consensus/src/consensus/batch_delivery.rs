@@ -233,24 +233,7 @@ pub fn generate_execution_responses_for_canister_http_responses(
     canister_http_payload
         .responses
         .iter()
-        .map(|canister_http_response| {
-            let content = &canister_http_response.content;
-            Response {
-                // NOTE originator and respondent are not needed for these types of calls
-                originator: CanisterId::ic_00(),
-                respondent: CanisterId::ic_00(),
-                originator_reply_callback: content.id,
-                refund: Cycles::zero(),
-                response_payload: match &content.content {
-                    CanisterHttpResponseContent::Success(data) => {
-                        ic_types::messages::Payload::Data(data.clone())
-                    }
-                    CanisterHttpResponseContent::Reject(canister_http_reject) => {
-                        ic_types::messages::Payload::Reject((canister_http_reject).into())
-                    }
-                },
-            }
-        })
+        .map(|canister_http_response| Response::from(&canister_http_response.content))
         // Deliver timeout responses
         .chain(
             canister_http_payload
@@ -84,7 +84,13 @@ fn proptest_round(
 
         let wrapped_payload = wrap_batch_payload(0, payload);
         payload_builder
-            .validate_payload(Height::from(0), &wrapped_payload, &[], &context)
+            .validate_payload(
+                Height::from(0),
+                node_test_id(0),
+                &wrapped_payload,
+                &[],
+                &context,
+            )
             .unwrap();
 
         // Check that no critical errors occured during the run.
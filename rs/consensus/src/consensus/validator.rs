@@ -944,6 +944,7 @@ impl Validator {
 
         let parent = get_notarized_parent(pool_reader, proposal)?;
         self.verify_signature(pool_reader, proposal)?;
+        let proposer = proposal.signature.signer;
 
         // Ensure registry_version, certified_height and time are non-decreasing.
         let proposal = proposal.as_ref();
@@ -979,6 +980,7 @@ impl Validator {
         self.payload_builder
             .validate_payload(
                 proposal.height,
+                proposer,
                 &proposal.payload,
                 &payloads,
                 &proposal.context,
@@ -1941,12 +1943,12 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .withf(move |_, _, payloads, _| {
+                .withf(move |_, _, _, payloads, _| {
                     // Assert that payloads are from blocks between:
                     // `certified_height` and the current height (`prior_height`)
                     payloads.len() as u64 == (prior_height - certified_height).get()
                 })
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2054,7 +2056,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2151,7 +2153,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2273,7 +2275,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2349,7 +2351,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2713,7 +2715,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| {
+                .returning(|_, _, _, _, _| {
                     Err(ValidationError::Transient(
                         PayloadTransientError::XNetPayloadValidationError(
                             XNetTransientValidationError::StateNotCommittedYet(Height::from(0)),
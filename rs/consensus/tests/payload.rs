@@ -10,6 +10,7 @@ use ic_interfaces_state_manager::Labeled;
 use ic_logger::replica_logger::no_op_logger;
 use ic_metrics::MetricsRegistry;
 use ic_test_utilities::canister_http::FakeCanisterHttpPayloadBuilder;
+use ic_test_utilities::query_stats::FakeQueryStatsPayloadBuilder;
 use ic_test_utilities::{
     consensus::make_genesis,
     crypto::CryptoReturningOk,
@@ -53,6 +54,9 @@ fn consensus_produces_expected_batches() {
         let canister_http_payload_builder = FakeCanisterHttpPayloadBuilder::new();
         let canister_http_payload_builder = Arc::new(canister_http_payload_builder);
 
+        let query_stats_payload_builder = FakeQueryStatsPayloadBuilder::new();
+        let query_stats_payload_builder = Arc::new(query_stats_payload_builder);
+
         let mut state_manager = MockStateManager::new();
         state_manager.expect_remove_states_below().return_const(());
         state_manager
@@ -139,6 +143,7 @@ fn consensus_produces_expected_batches() {
             Arc::clone(&xnet_payload_builder) as Arc<_>,
             Arc::clone(&self_validating_payload_builder) as Arc<_>,
             Arc::clone(&canister_http_payload_builder) as Arc<_>,
+            Arc::clone(&query_stats_payload_builder) as Arc<_>,
             Arc::clone(&dkg_pool) as Arc<_>,
             Arc::clone(&ecdsa_pool) as Arc<_>,
             dkg_key_manager.clone(),
@@ -1,11 +1,12 @@
 use ic_crypto_internal_threshold_sig_ecdsa::{
-    DerivationPath as DerivationPathImpl, EccCurveType, EccPoint, ThresholdEcdsaError,
+    DerivationPath as DerivationPathImpl, EccCurveType, EccPoint, EccScalar, ThresholdEcdsaError,
 };
 
 #[derive(Debug, Clone)]
 pub enum ExtendedBip32DerivationError {
     InvalidChainCodeLength,
     InvalidPublicKeyEncoding,
+    InvalidPrivateKeyEncoding,
     InternalError(ThresholdEcdsaError),
 }
 
@@ -37,6 +38,13 @@ impl ExtendedBip32DerivationOutput {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ExtendedBip32PrivateKeyDerivationOutput {
+    pub derived_private_key: Vec<u8>,
+    pub derived_public_key: Vec<u8>,
+    pub derived_chain_code: Vec<u8>,
+}
+
 // We wrap DerivationPath in order to add an additional method
 #[derive(Debug, Clone)]
 pub struct DerivationPath {
@@ -91,4 +99,47 @@ impl DerivationPath {
 
         Ok(ExtendedBip32DerivationOutput::new(new_key, chain_code))
     }
+
+    /// Perform extended BIP32 key derivation of a private key on the specified path
+    ///
+    /// This is the counterpart of [`DerivationPath::key_derivation`] for the holder
+    /// of the master private key: the public key of the derived private key is the
+    /// derived public key.
+    ///
+    /// # Arguments
+    ///  * `private_key` is the big-endian encoding of a secp256k1 scalar, which must
+    ///    be a 32 byte value.
+    ///  * `chain_code` is the BIP32 chain code, which must be a 32 byte value.
+    ///
+    /// Returns a result struct containing the big-endian encoding of the derived
+    /// private key, the SEC1 compressed secp256k1 of the derived public key, and
+    /// the new chain code.
+    pub fn private_key_derivation(
+        &self,
+        private_key: &[u8],
+        chain_code: &[u8],
+    ) -> ExtendedBip32DerivationResult<ExtendedBip32PrivateKeyDerivationOutput> {
+        if chain_code.len() != 32 {
+            return Err(ExtendedBip32DerivationError::InvalidChainCodeLength);
+        }
+
+        let curve_type = EccCurveType::K256;
+
+        let private_key = EccScalar::deserialize(curve_type, private_key)
+            .map_err(|_| ExtendedBip32DerivationError::InvalidPrivateKeyEncoding)?;
+        let public_key = EccPoint::mul_by_g(&private_key)?;
+
+        let (offset, chain_code) = self
+            .path
+            .derive_tweak_with_chain_code(&public_key, chain_code)?;
+
+        let new_private_key = private_key.add(&offset)?;
+        let new_public_key = EccPoint::mul_by_g(&new_private_key)?;
+
+        Ok(ExtendedBip32PrivateKeyDerivationOutput {
+            derived_private_key: new_private_key.serialize(),
+            derived_public_key: new_public_key.serialize(),
+            derived_chain_code: chain_code,
+        })
+    }
 }
@@ -101,3 +101,27 @@ fn verify_bip32_standard_key_derivation() -> ExtendedBip32DerivationResult<()> {
 
     Ok(())
 }
+
+#[test]
+fn private_key_derivation_matches_public_key_derivation() -> ExtendedBip32DerivationResult<()> {
+    let private_key =
+        hex::decode("e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35").unwrap();
+    let chain_code =
+        hex::decode("873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508").unwrap();
+    let path = DerivationPath::new(vec![
+        DerivationIndex(vec![1, 2, 3, 4, 5]),
+        DerivationIndex(vec![0, 0, 0, 7]),
+    ]);
+
+    let master_public_key = DerivationPath::new(vec![])
+        .private_key_derivation(&private_key, &chain_code)?
+        .derived_public_key;
+    let private = path.private_key_derivation(&private_key, &chain_code)?;
+    let public = path.key_derivation(&master_public_key, &chain_code)?;
+
+    assert_ne!(private.derived_private_key, private_key);
+    assert_eq!(private.derived_public_key, public.derived_public_key);
+    assert_eq!(private.derived_chain_code, public.derived_chain_code);
+
+    Ok(())
+}
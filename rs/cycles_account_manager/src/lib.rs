@@ -20,7 +20,7 @@ use ic_interfaces::execution_environment::CanisterOutOfCyclesError;
 use ic_logger::{error, info, ReplicaLogger};
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::{CanisterState, SystemState};
+use ic_replicated_state::{CanisterState, ReservationError, SystemState};
 use ic_types::messages::MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64;
 use ic_types::{
     messages::{Request, Response, SignedIngressContent, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
@@ -57,6 +57,57 @@ impl std::fmt::Display for CyclesAccountManagerError {
     }
 }
 
+/// Describes the usage of a subnet-wide resource, such as memory, relative to
+/// a threshold and the capacity of the resource.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceSaturation {
+    usage: u64,
+    threshold: u64,
+    capacity: u64,
+}
+
+impl ResourceSaturation {
+    /// Creates a new instance. The threshold and the usage are capped at the
+    /// capacity.
+    pub fn new(usage: u64, threshold: u64, capacity: u64) -> Self {
+        let threshold = threshold.min(capacity);
+        let usage = usage.min(capacity);
+        Self {
+            usage,
+            threshold,
+            capacity,
+        }
+    }
+
+    pub fn usage(&self) -> u64 {
+        self.usage
+    }
+
+    /// Returns the part of the usage that is above the threshold.
+    pub fn usage_above_threshold(&self) -> u64 {
+        self.usage.saturating_sub(self.threshold)
+    }
+
+    /// Returns the saturation after `delta` more of the resource is used.
+    pub fn add(&self, delta: u64) -> Self {
+        Self::new(
+            self.usage.saturating_add(delta),
+            self.threshold,
+            self.capacity,
+        )
+    }
+
+    // Returns `value` scaled by the fraction of the range between the
+    // threshold and the capacity that is used at the given usage.
+    fn scale_by_usage_above_threshold(&self, value: u64, usage_above_threshold: u64) -> u128 {
+        let range = self.capacity - self.threshold;
+        if range == 0 {
+            return 0;
+        }
+        value as u128 * usage_above_threshold as u128 / range as u128
+    }
+}
+
 /// Handles any operation related to cycles accounting, such as charging (due to
 /// using system resources) or refunding unused cycles.
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
@@ -481,8 +532,94 @@ impl CyclesAccountManager {
     ) -> Result<(), CanisterOutOfCyclesError> {
         let cycles_amount = self.memory_cost(bytes, duration, subnet_size);
 
-        // Can charge all the way to the empty account (zero cycles)
-        self.consume_with_threshold(system_state, cycles_amount, Cycles::zero())
+        // Storage is paid from the reserved balance first and the rest from
+        // the main balance, which can be charged all the way to zero.
+        let from_reserved_balance = cycles_amount.min(system_state.reserved_balance());
+        self.consume_with_threshold(
+            system_state,
+            cycles_amount - from_reserved_balance,
+            Cycles::zero(),
+        )?;
+        let removed = system_state.remove_reserved_cycles(from_reserved_balance);
+        self.observe_consumed_cycles(system_state, removed);
+        Ok(())
+    }
+
+    /// Returns the amount of cycles to reserve when a canister allocates
+    /// `allocated_bytes` of memory on a subnet with the given memory
+    /// saturation.
+    ///
+    /// Nothing is reserved while the subnet memory usage stays below the
+    /// threshold. Above it, each allocated byte reserves the cost of storing
+    /// it for a period that grows linearly from zero at the threshold to
+    /// `max_storage_reservation_period` at the capacity.
+    pub fn storage_reservation_cycles(
+        &self,
+        allocated_bytes: NumBytes,
+        subnet_memory_saturation: &ResourceSaturation,
+        subnet_size: usize,
+    ) -> Cycles {
+        let max_period = self.config.max_storage_reservation_period.as_secs();
+        let usage_before = subnet_memory_saturation.usage_above_threshold();
+        let usage_after = subnet_memory_saturation
+            .add(allocated_bytes.get())
+            .usage_above_threshold();
+        // The reservation period changes linearly with the usage, so all
+        // allocated bytes are charged the average of the periods before and
+        // after the allocation.
+        let period = (subnet_memory_saturation
+            .scale_by_usage_above_threshold(max_period, usage_before)
+            + subnet_memory_saturation.scale_by_usage_above_threshold(max_period, usage_after))
+            / 2;
+        self.memory_cost(
+            allocated_bytes,
+            Duration::from_secs(period as u64),
+            subnet_size,
+        )
+    }
+
+    /// Moves the cycles required for the storage reservation of newly
+    /// allocated memory from the main balance to the reserved balance of the
+    /// canister. See `storage_reservation_cycles()` for the amount.
+    ///
+    /// Like other charges, the reservation must leave the cycles of the
+    /// freezing threshold in the main balance, so `canister_current_memory_usage`
+    /// must include the newly allocated memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ReservationError` if the main balance does not have enough
+    /// cycles above the freezing threshold or if the reserved balance would
+    /// exceed its limit.
+    pub fn reserve_storage_cycles(
+        &self,
+        system_state: &mut SystemState,
+        allocated_bytes: NumBytes,
+        subnet_memory_saturation: &ResourceSaturation,
+        canister_current_memory_usage: NumBytes,
+        canister_compute_allocation: ComputeAllocation,
+        subnet_size: usize,
+    ) -> Result<(), ReservationError> {
+        let cycles =
+            self.storage_reservation_cycles(allocated_bytes, subnet_memory_saturation, subnet_size);
+        if cycles == Cycles::zero() {
+            return Ok(());
+        }
+        let threshold = self.freeze_threshold_cycles(
+            system_state.freeze_threshold,
+            system_state.memory_allocation,
+            canister_current_memory_usage,
+            canister_compute_allocation,
+            subnet_size,
+        );
+        let available = system_state.balance() - threshold;
+        if cycles > available {
+            return Err(ReservationError::InsufficientCycles {
+                requested: cycles,
+                available,
+            });
+        }
+        system_state.reserve_cycles(cycles)
     }
 
     /// The cost of using `bytes` worth of memory.
@@ -754,10 +891,14 @@ impl CyclesAccountManager {
     /// Charges a canister for its resource allocation and usage for the
     /// duration specified. If fees were successfully charged, then returns
     /// Ok(CanisterState) else returns Err(CanisterState).
+    ///
+    /// `snapshots_memory_usage` is the size of the canister's snapshots. It is
+    /// charged on top of the memory allocation or usage of the canister.
     pub fn charge_canister_for_resource_allocation_and_usage(
         &self,
         log: &ReplicaLogger,
         canister: &mut CanisterState,
+        snapshots_memory_usage: NumBytes,
         duration_between_blocks: Duration,
         subnet_size: usize,
     ) -> Result<(), CanisterOutOfCyclesError> {
@@ -767,7 +908,7 @@ impl CyclesAccountManager {
             MemoryAllocation::Reserved(bytes) => bytes,
             // The canister uses best-effort memory allocation, so charge based on current usage.
             MemoryAllocation::BestEffort => canister.memory_usage(self.own_subnet_type),
-        };
+        } + snapshots_memory_usage;
         if let Err(err) = self.charge_for_memory(
             &mut canister.system_state,
             bytes_to_charge,
@@ -1,11 +1,11 @@
 use ic_base_types::NumSeconds;
 use ic_config::subnet_config::SubnetConfigs;
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
-use ic_cycles_account_manager::IngressInductionCost;
+use ic_cycles_account_manager::{IngressInductionCost, ResourceSaturation};
 use ic_ic00_types::{CanisterIdRecord, Payload, IC_00};
 use ic_interfaces::execution_environment::CanisterOutOfCyclesError;
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::SystemState;
+use ic_replicated_state::{ReservationError, SystemState};
 use ic_test_utilities::{
     cycles_account_manager::CyclesAccountManagerBuilder,
     state::{new_canister_state, SystemStateBuilder},
@@ -68,6 +68,7 @@ fn test_can_charge_application_subnets() {
                         .charge_canister_for_resource_allocation_and_usage(
                             &log,
                             &mut canister,
+                            NumBytes::from(0),
                             duration,
                             subnet_size,
                         )
@@ -336,6 +337,190 @@ fn canister_charge_for_memory_until_zero_works() {
         .is_err());
 }
 
+#[test]
+fn storage_reservation_is_zero_below_threshold() {
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let saturation = ResourceSaturation::new(1 << 30, 2 << 30, 4 << 30);
+    assert_eq!(
+        cycles_account_manager.storage_reservation_cycles(
+            NumBytes::from(1 << 30),
+            &saturation,
+            subnet_size
+        ),
+        Cycles::zero()
+    );
+}
+
+#[test]
+fn storage_reservation_grows_with_usage_above_threshold() {
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let bytes = NumBytes::from(1 << 20);
+    let mut previous = Cycles::zero();
+    for usage in [2 << 30, 3 << 30, 4 << 30] {
+        let saturation = ResourceSaturation::new(usage, 2 << 30, 5 << 30);
+        let reservation =
+            cycles_account_manager.storage_reservation_cycles(bytes, &saturation, subnet_size);
+        assert!(reservation > previous);
+        previous = reservation;
+    }
+}
+
+#[test]
+fn reserve_storage_cycles_moves_cycles_to_reserved_balance() {
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let mut system_state = SystemStateBuilder::new()
+        .initial_cycles(INITIAL_CYCLES)
+        .build();
+    let bytes = NumBytes::from(1 << 20);
+    let saturation = ResourceSaturation::new(3 << 30, 2 << 30, 4 << 30);
+    let reservation =
+        cycles_account_manager.storage_reservation_cycles(bytes, &saturation, subnet_size);
+    assert!(reservation > Cycles::zero());
+
+    cycles_account_manager
+        .reserve_storage_cycles(
+            &mut system_state,
+            bytes,
+            &saturation,
+            bytes,
+            ComputeAllocation::zero(),
+            subnet_size,
+        )
+        .unwrap();
+    assert_eq!(system_state.reserved_balance(), reservation);
+    assert_eq!(system_state.balance(), INITIAL_CYCLES - reservation);
+}
+
+#[test]
+fn reserve_storage_cycles_respects_reserved_balance_limit() {
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let mut system_state = SystemStateBuilder::new()
+        .initial_cycles(INITIAL_CYCLES)
+        .build();
+    system_state.reserved_balance_limit = Some(Cycles::new(1));
+    let bytes = NumBytes::from(1 << 20);
+    let saturation = ResourceSaturation::new(3 << 30, 2 << 30, 4 << 30);
+    let reservation =
+        cycles_account_manager.storage_reservation_cycles(bytes, &saturation, subnet_size);
+
+    assert_eq!(
+        cycles_account_manager.reserve_storage_cycles(
+            &mut system_state,
+            bytes,
+            &saturation,
+            bytes,
+            ComputeAllocation::zero(),
+            subnet_size
+        ),
+        Err(ReservationError::ReservedLimitExceeded {
+            requested: reservation,
+            limit: Cycles::new(1),
+        })
+    );
+    assert_eq!(system_state.reserved_balance(), Cycles::zero());
+    assert_eq!(system_state.balance(), INITIAL_CYCLES);
+}
+
+#[test]
+fn reserve_storage_cycles_keeps_freezing_threshold() {
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let bytes = NumBytes::from(1 << 20);
+    let saturation = ResourceSaturation::new(3 << 30, 2 << 30, 4 << 30);
+    let reservation =
+        cycles_account_manager.storage_reservation_cycles(bytes, &saturation, subnet_size);
+    let mut system_state = SystemStateBuilder::new()
+        .initial_cycles(reservation)
+        .build();
+    system_state.freeze_threshold = NumSeconds::from(100_000);
+    let threshold = cycles_account_manager.freeze_threshold_cycles(
+        system_state.freeze_threshold,
+        system_state.memory_allocation,
+        bytes,
+        ComputeAllocation::zero(),
+        subnet_size,
+    );
+    assert!(threshold > Cycles::zero());
+
+    // The main balance covers the reservation, but not the freezing threshold.
+    assert_eq!(
+        cycles_account_manager.reserve_storage_cycles(
+            &mut system_state,
+            bytes,
+            &saturation,
+            bytes,
+            ComputeAllocation::zero(),
+            subnet_size
+        ),
+        Err(ReservationError::InsufficientCycles {
+            requested: reservation,
+            available: reservation - threshold,
+        })
+    );
+    assert_eq!(system_state.reserved_balance(), Cycles::zero());
+
+    cycles_account_manager.add_cycles(system_state.balance_mut(), threshold);
+    cycles_account_manager
+        .reserve_storage_cycles(
+            &mut system_state,
+            bytes,
+            &saturation,
+            bytes,
+            ComputeAllocation::zero(),
+            subnet_size,
+        )
+        .unwrap();
+    assert_eq!(system_state.reserved_balance(), reservation);
+    assert_eq!(system_state.balance(), threshold);
+}
+
+#[test]
+fn charge_for_memory_uses_reserved_balance_first() {
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let mut system_state = SystemStateBuilder::new()
+        .initial_cycles(INITIAL_CYCLES)
+        .build();
+    let bytes = NumBytes::from(1 << 30);
+    let duration = Duration::from_secs(1);
+    let fee = cycles_account_manager.memory_cost(bytes, duration, subnet_size);
+    system_state.reserve_cycles(fee * 3_u64 / 2_u64).unwrap();
+    let balance = system_state.balance();
+
+    // The first charge is fully paid from the reserved balance.
+    cycles_account_manager
+        .charge_for_memory(&mut system_state, bytes, duration, subnet_size)
+        .unwrap();
+    assert_eq!(system_state.balance(), balance);
+    assert_eq!(system_state.reserved_balance(), fee * 1_u64 / 2_u64);
+
+    // The second charge takes the rest from the main balance.
+    cycles_account_manager
+        .charge_for_memory(&mut system_state, bytes, duration, subnet_size)
+        .unwrap();
+    assert_eq!(system_state.reserved_balance(), Cycles::zero());
+    assert_eq!(
+        system_state.balance(),
+        balance - (fee - fee * 1_u64 / 2_u64)
+    );
+}
+
 #[test]
 fn ingress_induction_cost_valid_subnet_message() {
     let subnet_id = subnet_test_id(0);
@@ -387,6 +572,7 @@ fn charging_removes_canisters_with_insufficient_balance() {
             .charge_canister_for_resource_allocation_and_usage(
                 &log,
                 &mut canister,
+                NumBytes::from(0),
                 Duration::from_secs(1),
                 subnet_size,
             )
@@ -405,6 +591,7 @@ fn charging_removes_canisters_with_insufficient_balance() {
             .charge_canister_for_resource_allocation_and_usage(
                 &log,
                 &mut canister,
+                NumBytes::from(0),
                 Duration::from_secs(1),
                 subnet_size,
             )
@@ -423,6 +610,7 @@ fn charging_removes_canisters_with_insufficient_balance() {
             .charge_canister_for_resource_allocation_and_usage(
                 &log,
                 &mut canister,
+                NumBytes::from(0),
                 Duration::from_secs(1),
                 subnet_size,
             )
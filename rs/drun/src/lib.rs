@@ -1,11 +1,11 @@
 //! Standalone interface for testing application canisters.
 
 use crate::message::{msg_stream_from_file, Message};
-use hex::encode;
+use crate::output::{MessageKind, MessageResult};
 use ic_config::{subnet_config::SubnetConfigs, Config};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
-use ic_execution_environment::ExecutionServices;
+use ic_execution_environment::{ExecutionServices, MessageInstructionsCounter};
 use ic_interfaces::{execution_environment::IngressHistoryReader, messaging::MessageRouting};
 use ic_interfaces_state_manager::StateReader;
 use ic_messaging::MessageRoutingImpl;
@@ -36,7 +36,7 @@ use ic_types::{
     messages::{MessageId, SignedIngress},
     replica_config::ReplicaConfig,
     time::UNIX_EPOCH,
-    CanisterId, NodeId, PrincipalId, Randomness, RegistryVersion, SubnetId,
+    CanisterId, NodeId, NumInstructions, PrincipalId, Randomness, RegistryVersion, SubnetId, Time,
 };
 use slog::{Drain, Logger};
 use std::collections::BTreeMap;
@@ -46,6 +46,9 @@ use std::sync::{Arc, Mutex};
 use std::{thread::sleep, time::Duration};
 
 mod message;
+mod output;
+
+pub use output::OutputFormat;
 
 // drun will panic if it takes more than this many batches
 // until a response for a message is received
@@ -58,22 +61,24 @@ pub struct DrunOptions {
     pub cfg: Config,
     pub extra_batches: u64,
     pub log_file: Option<PathBuf>,
+    pub output_format: OutputFormat,
 }
 
-/// Deliver a single message to the Message Routing layer
+/// Deliver a single message to the Message Routing layer and return its result
 fn deliver_message(
     msg: SignedIngress,
     message_routing: &dyn MessageRouting,
     ingress_hist_reader: &dyn IngressHistoryReader,
     extra_batches: u64,
-) {
+    time: Time,
+) -> Result<WasmResult, UserError> {
     let message_id = msg.id();
 
-    let _ = execute_ingress_message(message_routing, msg, &message_id, ingress_hist_reader);
-    // print result after waiting, to not interleave the result
+    let _ = execute_ingress_message(message_routing, msg, &message_id, ingress_hist_reader, time);
+    // fetch result after waiting, to not interleave the result
     // with debug.print messages from subsequent calls. revise after DFN-1269.
-    wait_extra_batches(message_routing, extra_batches);
-    print_ingress_result(&message_id, ingress_hist_reader);
+    wait_extra_batches(message_routing, extra_batches, time);
+    ingress_result(&message_id, ingress_hist_reader)
 }
 
 fn setup_logger(log_file: PathBuf) -> Logger {
@@ -155,6 +160,7 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         cfg,
         extra_batches,
         log_file,
+        output_format,
     } = uo;
     // Hardcoded magic values to create a ReplicaConfig that parses.
     let subnet_type = SubnetType::System;
@@ -198,18 +204,19 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         None,
         ic_types::malicious_flags::MaliciousFlags::default(),
     ));
+    let execution_services = ExecutionServices::setup_execution(
+        log.clone().into(),
+        &metrics_registry,
+        replica_config.subnet_id,
+        subnet_type,
+        subnet_config.scheduler_config,
+        cfg.hypervisor.clone(),
+        Arc::clone(&cycles_account_manager),
+        Arc::clone(&state_manager) as Arc<_>,
+    );
+    let message_instructions = execution_services.message_instructions.clone();
     let (_, ingress_history_writer, ingress_hist_reader, query_handler, _, _, scheduler) =
-        ExecutionServices::setup_execution(
-            log.clone().into(),
-            &metrics_registry,
-            replica_config.subnet_id,
-            subnet_type,
-            subnet_config.scheduler_config,
-            cfg.hypervisor.clone(),
-            Arc::clone(&cycles_account_manager),
-            Arc::clone(&state_manager) as Arc<_>,
-        )
-        .into_parts();
+        execution_services.into_parts();
 
     let _metrics_runtime = MetricsRuntimeImpl::new_insecure(
         tokio::runtime::Handle::current(),
@@ -231,85 +238,157 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         Arc::clone(&registry) as _,
     );
 
-    msg_stream.try_for_each(|parse_result| {
-        parse_result.map(|msg| match msg {
-            Message::Install(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
-            }
+    // The time of the delivered batches. It only changes on `advance_time`
+    // directives, so that the output of drun is deterministic.
+    let mut time = UNIX_EPOCH;
+    // The result of the last message, checked by `expect` directives.
+    let mut last_result: Option<MessageResult> = None;
+    let mut failed_expectations = 0;
 
+    msg_stream.try_for_each(|parse_result| {
+        match parse_result? {
             Message::Query(q) => {
                 // NOTE: Data certificates aren't supported in drun yet.
                 // To support them, we'd need to do something similar to
                 // http_handler::get_latest_certified_state_and_data_certificate
-                print_query_result(query_handler.query(
-                    q,
-                    state_manager.get_latest_state().take(),
-                    Vec::new(),
-                ));
+                let instructions_before = message_instructions.get();
+                let result =
+                    query_handler.query(q, state_manager.get_latest_state().take(), Vec::new());
+                let instructions_used = message_instructions.get() - instructions_before;
+                // Queries don't persist any state changes, so no cycles are
+                // charged for them.
+                let result = MessageResult {
+                    kind: MessageKind::Query,
+                    result,
+                    instructions_used: instructions_used.get(),
+                    cycles_charged: 0,
+                };
+                result.print(output_format);
+                last_result = Some(result);
             }
 
-            Message::Ingress(msg) => {
-                deliver_message(
+            Message::Install(msg)
+            | Message::Ingress(msg)
+            | Message::Create(msg)
+            | Message::Management(msg) => {
+                let counters =
+                    ExecutionCounters::fetch(&message_instructions, state_manager.as_ref());
+                let message_id = msg.id();
+                let result = deliver_message(
                     msg,
                     &message_routing,
                     ingress_hist_reader.as_ref(),
                     extra_batches,
+                    time,
                 );
-            }
-            Message::Create(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
+                let result = counters.into_result(
+                    MessageKind::Ingress(message_id),
+                    result,
+                    &message_instructions,
+                    state_manager.as_ref(),
                 );
+                result.print(output_format);
+                last_result = Some(result);
             }
-        })
-    })
+
+            Message::AdvanceTime(duration) => {
+                time += duration;
+                // Deliver an empty batch so that the new time becomes visible
+                // to queries and timers fire before the next message.
+                wait_extra_batches(&message_routing, 1, time);
+            }
+
+            Message::Expect(expectation) => {
+                let result = last_result
+                    .as_ref()
+                    .ok_or_else(|| "`expect` must follow a message.".to_string())?;
+                if let Err(err) = result.check(&expectation) {
+                    eprintln!("{}", err);
+                    failed_expectations += 1;
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    if failed_expectations > 0 {
+        return Err(format!("{} expectation(s) failed.", failed_expectations));
+    }
+    Ok(())
+}
+
+/// Snapshot of the counters from which the instructions used and cycles
+/// charged by an ingress message are derived.
+struct ExecutionCounters {
+    instructions: NumInstructions,
+    consumed_cycles: u128,
 }
 
-fn print_query_result(res: Result<WasmResult, UserError>) {
-    match res {
-        Ok(payload) => {
-            print!("Ok: ");
-            print_wasm_result(payload);
+impl ExecutionCounters {
+    fn fetch(
+        message_instructions: &MessageInstructionsCounter,
+        state_manager: &StateManagerImpl,
+    ) -> Self {
+        let state = state_manager.get_latest_state().take();
+        let consumed_cycles = state
+            .canisters_iter()
+            .map(|canister| {
+                canister
+                    .system_state
+                    .canister_metrics
+                    .consumed_cycles_since_replica_started
+                    .get()
+            })
+            .sum();
+        Self {
+            instructions: message_instructions.get(),
+            consumed_cycles,
+        }
+    }
+
+    /// Builds the result of an ingress message from the counters fetched
+    /// before and after it was executed.
+    fn into_result(
+        self,
+        kind: MessageKind,
+        result: Result<WasmResult, UserError>,
+        message_instructions: &MessageInstructionsCounter,
+        state_manager: &StateManagerImpl,
+    ) -> MessageResult {
+        let after = Self::fetch(message_instructions, state_manager);
+        // Cycles consumed by deleted canisters are no longer accounted for.
+        MessageResult {
+            kind,
+            result,
+            instructions_used: (after.instructions - self.instructions).get(),
+            cycles_charged: after.consumed_cycles.saturating_sub(self.consumed_cycles),
         }
-        Err(e) => println!("Err: {}", e),
     }
 }
 
-fn print_ingress_result(message_id: &MessageId, ingress_hist_reader: &dyn IngressHistoryReader) {
+fn ingress_result(
+    message_id: &MessageId,
+    ingress_hist_reader: &dyn IngressHistoryReader,
+) -> Result<WasmResult, UserError> {
     let status = (ingress_hist_reader.get_latest_status())(message_id);
-    print!("ingress ");
     match status {
         IngressStatus::Known {
             state: IngressState::Completed(result),
             ..
-        } => {
-            print!("Completed: ");
-            print_wasm_result(result)
-        }
+        } => Ok(result),
         IngressStatus::Known {
             state: IngressState::Failed(error),
             ..
-        } => println!("Err: {}", error),
+        } => Err(error),
         _ => panic!("Ingress message has not finished processing."),
-    };
-}
-
-fn print_wasm_result(wasm_result: WasmResult) {
-    match wasm_result {
-        WasmResult::Reply(v) => println!("Reply: 0x{}", encode(v)),
-        WasmResult::Reject(e) => println!("Reject: {}", e),
     }
 }
 
-fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -> Batch {
+fn build_batch(
+    message_routing: &dyn MessageRouting,
+    msgs: Vec<SignedIngress>,
+    time: Time,
+) -> Batch {
     Batch {
         batch_number: message_routing.expected_batch_height(),
         requires_full_state_hash: !msgs.is_empty(),
@@ -320,7 +399,7 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
         randomness: Randomness::from([0; 32]),
         ecdsa_subnet_public_keys: BTreeMap::new(),
         registry_version: RegistryVersion::from(1),
-        time: UNIX_EPOCH,
+        time,
         consensus_responses: vec![],
     }
 }
@@ -333,8 +412,9 @@ fn execute_ingress_message(
     msg: SignedIngress,
     msg_id: &MessageId,
     ingress_history: &dyn IngressHistoryReader,
+    time: Time,
 ) -> Result<WasmResult, UserError> {
-    let mut batch = build_batch(message_routing, vec![msg]);
+    let mut batch = build_batch(message_routing, vec![msg], time);
     for _ in 0..MAX_BATCHES_UNTIL_RESPONSE {
         // In the first batch we try to send the ingress message itself. If it fails, we
         // repeat with the same batch.
@@ -345,7 +425,7 @@ fn execute_ingress_message(
         // potential inter-canister messages that the ingress message may have
         // triggered.
         if message_routing.deliver_batch(batch.clone()).is_ok() {
-            batch = build_batch(message_routing, vec![])
+            batch = build_batch(message_routing, vec![], time)
         }
         sleep(WAIT_PER_BATCH);
 
@@ -391,10 +471,10 @@ fn execute_ingress_message(
 ///
 /// This is a temporary measure until DFN-1269 is resolved. In that ticket, we
 /// will actually try to wait until all messages have been executed.
-fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64) {
+fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64, time: Time) {
     for _ in 0..extra_batches {
         loop {
-            let batch = build_batch(message_routing, vec![]);
+            let batch = build_batch(message_routing, vec![], time);
             let ok = message_routing.deliver_batch(batch).is_ok();
             sleep(WAIT_PER_BATCH);
             if ok {
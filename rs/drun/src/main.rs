@@ -4,7 +4,7 @@ use ic_canister_sandbox_backend_lib::{
 };
 use ic_canister_sandbox_launcher::sandbox_launcher_main;
 use ic_config::{Config, ConfigSource};
-use ic_drun::{run_drun, DrunOptions};
+use ic_drun::{run_drun, DrunOptions, OutputFormat};
 use std::path::PathBuf;
 
 const DEFAULT_CONFIG_FILE: &str = "ic.toml";
@@ -13,6 +13,7 @@ const ARG_CONF: &str = "config";
 const ARG_LOG_FILE: &str = "log-file";
 const ARG_MESSAGES: &str = "messages";
 const ARG_EXTRA_BATCHES: &str = "extra-batches";
+const ARG_OUTPUT_FORMAT: &str = "output-format";
 
 fn main() -> Result<(), String> {
     // Check if `drun` is running in the canister sandbox mode where it waits
@@ -55,11 +56,22 @@ async fn drun_main() -> Result<(), String> {
             })
             .unwrap_or(DEFAULT_EXTRA_BATCHES);
 
+        let output_format: OutputFormat = matches
+            .value_of(ARG_OUTPUT_FORMAT)
+            .map(|arg| {
+                arg.parse().unwrap_or_else(|err| {
+                    eprintln!("Failed to parse ARG_OUTPUT_FORMAT\n  {}", err);
+                    std::process::exit(1);
+                })
+            })
+            .unwrap_or_default();
+
         let uo = DrunOptions {
             msg_filename: matches.value_of(ARG_MESSAGES).unwrap().to_string(),
             cfg,
             extra_batches,
             log_file,
+            output_format,
         };
         run_drun(uo)
     })
@@ -108,5 +120,13 @@ fn get_arg_matches() -> ArgMatches {
                 .help("Log file for the run (default: None).")
                 .takes_value(true),
         )
+        .arg(
+            Arg::new(ARG_OUTPUT_FORMAT)
+                .long(ARG_OUTPUT_FORMAT)
+                .value_name("format")
+                .possible_values(["text", "json"])
+                .help("Format of the printed message results (default: text).")
+                .takes_value(true),
+        )
         .get_matches()
 }
@@ -1,7 +1,13 @@
 use super::CanisterId;
+use crate::output::Expectation;
 
 use hex::decode;
-use ic_ic00_types::{self as ic00, CanisterInstallMode, Payload};
+use ic_error_types::RejectCode;
+use ic_ic00_types::{
+    self as ic00, CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, Payload,
+    UpdateSettingsArgs,
+};
+use ic_test_utilities::types::messages::SignedIngressBuilder;
 use ic_types::{
     messages::{SignedIngress, UserQuery},
     time::current_time_and_expiry_time,
@@ -13,8 +19,9 @@ use std::{
     fmt,
     fs::File,
     io::{self, Read},
-    str::Chars,
+    str::{Chars, FromStr},
     string::FromUtf8Error,
+    time::Duration,
 };
 
 #[derive(Debug, PartialEq)]
@@ -23,6 +30,13 @@ pub(crate) enum Message {
     Query(UserQuery),
     Install(SignedIngress),
     Create(SignedIngress),
+    /// A call to a method of the management canister other than
+    /// `install_code` and `provisional_create_canister_with_cycles`.
+    Management(SignedIngress),
+    /// Advances the time of the subsequent batches by the given duration.
+    AdvanceTime(Duration),
+    /// Checks the result of the preceding message.
+    Expect(Expectation),
 }
 
 #[derive(Debug)]
@@ -146,13 +160,23 @@ pub(crate) fn msg_stream_from_file(
 
 fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
     let s = s.trim_end();
+
+    // Any message can be prefixed with `from <principal>` to set its sender.
+    // Messages are sent by the anonymous principal otherwise.
+    let (sender, s) = match s.split_once(char::is_whitespace) {
+        Some(("from", rest)) => match rest.trim_start().split_once(char::is_whitespace) {
+            Some((sender, rest)) => (parse_principal_id(sender)?, rest.trim_start()),
+            None => return Err("Missing message after the sender.".to_string()),
+        },
+        _ => (PrincipalId::new_anonymous(), s),
+    };
+    let sender = UserId::from(sender);
+
     let tokens: Vec<&str> = s.splitn(4, char::is_whitespace).collect();
 
     match &tokens[..] {
         [] => Err("Too few arguments.".to_string()),
         ["ingress", canister_id, method_name, payload] => {
-            use ic_test_utilities::types::messages::SignedIngressBuilder;
-
             let canister_id = parse_canister_id(canister_id)?;
             let method_name = validate_method_name(method_name)?;
             let method_payload = parse_octet_string(payload)?;
@@ -160,6 +184,7 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
             let signed_ingress = SignedIngressBuilder::new()
                 // `source` should become a self-authenticating id according
                 // to https://sdk.dfinity.org/docs/interface-spec/index.html#id-classes
+                .sender(sender)
                 .canister_id(canister_id)
                 .method_name(method_name)
                 .method_payload(method_payload)
@@ -168,23 +193,85 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
             Ok(Message::Ingress(signed_ingress))
         }
         ["query", canister_id, method_name, payload] => Ok(Message::Query(UserQuery {
-            source: UserId::from(PrincipalId::new_anonymous()),
+            source: sender,
             receiver: parse_canister_id(canister_id)?,
             method_name: validate_method_name(method_name)?,
             method_payload: parse_octet_string(payload)?,
             ingress_expiry: current_time_and_expiry_time().1.as_nanos_since_unix_epoch(),
             nonce: Some(nonce.to_le_bytes().to_vec()),
         })),
-        ["create"] => parse_create(nonce),
+        ["create"] => parse_create(nonce, sender, None),
+        ["create_with_cycles", amount] => parse_create(nonce, sender, Some(parse_cycles(amount)?)),
         ["install", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "install")
+            parse_install(nonce, sender, canister_id, payload, wasm_file, "install")
         }
         ["reinstall", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "reinstall")
+            parse_install(nonce, sender, canister_id, payload, wasm_file, "reinstall")
         }
         ["upgrade", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "upgrade")
+            parse_install(nonce, sender, canister_id, payload, wasm_file, "upgrade")
         }
+        ["update_settings", canister_id, settings @ ..] => {
+            let canister_id = parse_canister_id(canister_id)?;
+            // The last token contains all remaining settings.
+            let settings = parse_settings(&settings.join(" "))?;
+            Ok(management_message(
+                nonce,
+                sender,
+                ic00::Method::UpdateSettings,
+                UpdateSettingsArgs {
+                    canister_id: canister_id.get(),
+                    settings,
+                }
+                .encode(),
+            ))
+        }
+        ["stop", canister_id] => {
+            parse_canister_id_record(nonce, sender, ic00::Method::StopCanister, canister_id)
+        }
+        ["start", canister_id] => {
+            parse_canister_id_record(nonce, sender, ic00::Method::StartCanister, canister_id)
+        }
+        ["delete", canister_id] => {
+            parse_canister_id_record(nonce, sender, ic00::Method::DeleteCanister, canister_id)
+        }
+        ["canister_status", canister_id] => {
+            parse_canister_id_record(nonce, sender, ic00::Method::CanisterStatus, canister_id)
+        }
+        ["add_cycles", canister_id, amount] => Ok(management_message(
+            nonce,
+            sender,
+            ic00::Method::ProvisionalTopUpCanister,
+            ic00::ProvisionalTopUpCanisterArgs::new(
+                parse_canister_id(canister_id)?,
+                parse_cycles(amount)?,
+            )
+            .encode(),
+        )),
+        ["advance_time", seconds] => {
+            let seconds = u64::from_str(seconds)
+                .map_err(|e| format!("Failed to parse {} as seconds: {}", seconds, e))?;
+            Ok(Message::AdvanceTime(Duration::from_secs(seconds)))
+        }
+        ["expect", "reply"] => Ok(Message::Expect(Expectation::Reply(None))),
+        ["expect", "reply", payload] => Ok(Message::Expect(Expectation::Reply(Some(
+            parse_octet_string(payload)?,
+        )))),
+        ["expect", "reject"] => Ok(Message::Expect(Expectation::Reject {
+            code: None,
+            message: None,
+        })),
+        ["expect", "reject", code] => Ok(Message::Expect(Expectation::Reject {
+            code: Some(parse_reject_code(code)?),
+            message: None,
+        })),
+        ["expect", "reject", code, message] => Ok(Message::Expect(Expectation::Reject {
+            code: Some(parse_reject_code(code)?),
+            message: Some(
+                String::from_utf8(parse_octet_string(message)?)
+                    .map_err(|e| format!("Reject message is not valid UTF-8: {}", e))?,
+            ),
+        })),
         _ => Err(format!(
             "Failed to parse line {}, don't have a pattern to match this with",
             s
@@ -192,30 +279,113 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
     }
 }
 
-fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
-    use std::str::FromStr;
-    match PrincipalId::from_str(canister_id) {
-        Ok(id) => match CanisterId::new(id) {
-            Ok(id) => Ok(id),
-            Err(err) => Err(format!(
-                "Failed to convert {} to canister id with {}",
-                canister_id, err
-            )),
-        },
-        Err(err) => Err(format!(
+fn parse_principal_id(principal_id: &str) -> Result<PrincipalId, String> {
+    PrincipalId::from_str(principal_id).map_err(|err| {
+        format!(
             "Failed to convert {} to principal id with {}",
+            principal_id, err
+        )
+    })
+}
+
+fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
+    CanisterId::new(parse_principal_id(canister_id)?).map_err(|err| {
+        format!(
+            "Failed to convert {} to canister id with {}",
             canister_id, err
-        )),
+        )
+    })
+}
+
+fn parse_reject_code(code: &str) -> Result<RejectCode, String> {
+    u64::from_str(code)
+        .ok()
+        .and_then(|code| RejectCode::try_from(code).ok())
+        .ok_or_else(|| {
+            format!(
+                "Invalid reject code {}, expected a number from 1 to 5",
+                code
+            )
+        })
+}
+
+fn parse_cycles(amount: &str) -> Result<u128, String> {
+    u128::from_str(amount).map_err(|e| format!("Failed to parse {} as cycles: {}", amount, e))
+}
+
+fn parse_u64_setting(key: &str, value: &str) -> Result<u64, String> {
+    u64::from_str(value).map_err(|e| format!("Failed to parse {}={}: {}", key, value, e))
+}
+
+/// Parses settings of the form `key=value`, separated by whitespace. The
+/// value of `controllers` is a comma-separated list of principals.
+fn parse_settings(settings: &str) -> Result<CanisterSettingsArgs, String> {
+    let mut result = CanisterSettingsArgs::default();
+    for setting in settings.split_whitespace() {
+        let (key, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("Setting {} is not of the form key=value.", setting))?;
+        match key {
+            "controllers" => {
+                let controllers = value
+                    .split(',')
+                    .filter(|controller| !controller.is_empty())
+                    .map(parse_principal_id)
+                    .collect::<Result<Vec<_>, _>>()?;
+                result.controllers = Some(controllers);
+            }
+            "compute_allocation" => {
+                result.compute_allocation = Some(parse_u64_setting(key, value)?.into());
+            }
+            "memory_allocation" => {
+                result.memory_allocation = Some(parse_u64_setting(key, value)?.into());
+            }
+            "freezing_threshold" => {
+                result.freezing_threshold = Some(parse_u64_setting(key, value)?.into());
+            }
+            _ => return Err(format!("Unknown setting {}.", key)),
+        }
     }
+    Ok(result)
 }
 
-fn parse_create(nonce: u64) -> Result<Message, String> {
-    use ic_test_utilities::types::messages::SignedIngressBuilder;
+fn management_message(
+    nonce: u64,
+    sender: UserId,
+    method: ic00::Method,
+    payload: Vec<u8>,
+) -> Message {
+    let signed_ingress = SignedIngressBuilder::new()
+        .sender(sender)
+        .canister_id(ic00::IC_00)
+        .method_name(method)
+        .method_payload(payload)
+        .nonce(nonce)
+        .build();
+    Message::Management(signed_ingress)
+}
+
+fn parse_canister_id_record(
+    nonce: u64,
+    sender: UserId,
+    method: ic00::Method,
+    canister_id: &str,
+) -> Result<Message, String> {
+    let canister_id = parse_canister_id(canister_id)?;
+    Ok(management_message(
+        nonce,
+        sender,
+        method,
+        CanisterIdRecord::from(canister_id).encode(),
+    ))
+}
 
+fn parse_create(nonce: u64, sender: UserId, amount: Option<u128>) -> Result<Message, String> {
     let signed_ingress = SignedIngressBuilder::new()
+        .sender(sender)
         .method_name(ic00::Method::ProvisionalCreateCanisterWithCycles)
         .canister_id(ic00::IC_00)
-        .method_payload(ic00::ProvisionalCreateCanisterWithCyclesArgs::new(None).encode())
+        .method_payload(ic00::ProvisionalCreateCanisterWithCyclesArgs::new(amount).encode())
         .nonce(nonce)
         .build();
 
@@ -224,13 +394,12 @@ fn parse_create(nonce: u64) -> Result<Message, String> {
 
 fn parse_install(
     nonce: u64,
+    sender: UserId,
     canister_id: &str,
     payload: &str,
     wasm_file: &str,
     mode: &str,
 ) -> Result<Message, String> {
-    use ic_test_utilities::types::messages::SignedIngressBuilder;
-
     let mut wasm_data = Vec::new();
     let mut wasm_file = File::open(wasm_file)
         .map_err(|e| format!("Could not open wasm file: {} - Error: {}", wasm_file, e))?;
@@ -244,6 +413,7 @@ fn parse_install(
     let signed_ingress = SignedIngressBuilder::new()
         // `source` should become a self-authenticating id according
         // to https://sdk.dfinity.org/docs/interface-spec/index.html#id-classes
+        .sender(sender)
         .canister_id(ic00::IC_00)
         .method_name(ic00::Method::InstallCode)
         .method_payload(
@@ -371,7 +541,7 @@ enum Radix {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ic_test_utilities::types::{ids::canister_test_id, messages::SignedIngressBuilder};
+    use ic_test_utilities::types::ids::canister_test_id;
     use std::io::Cursor;
 
     const APP_CANISTER_URL: &str = "ryjl3-tyaaa-aaaaa-aaaba-cai";
@@ -470,6 +640,189 @@ mod tests {
         assert!(parse_message(s, 0).is_err());
     }
 
+    #[test]
+    fn test_parse_message_with_sender_succeeds() {
+        let sender = PrincipalId::new_user_test_id(1);
+        let s = &format!("from {} ingress {} write 0x01", sender, APP_CANISTER_URL);
+        let parsed_message = parse_message(s, 0).unwrap();
+        let expiry_time = match &parsed_message {
+            Message::Ingress(signed_ingress) => signed_ingress.expiry_time(),
+            _ => panic!(
+                "parse_message() returned an unexpected message type: {:?}",
+                parsed_message
+            ),
+        };
+        let expected = Message::Ingress(
+            SignedIngressBuilder::new()
+                .sender(UserId::from(sender))
+                .canister_id(canister_test_id(APP_CANISTER_ID))
+                .method_name("write".to_string())
+                .method_payload(vec![1])
+                .nonce(0)
+                .expiry_time(expiry_time)
+                .build(),
+        );
+        assert_eq!(expected, parsed_message);
+
+        let s = &format!("from {} query {} read 0x01", sender, APP_CANISTER_URL);
+        match parse_message(s, 0).unwrap() {
+            Message::Query(query) => assert_eq!(query.source, UserId::from(sender)),
+            msg => panic!(
+                "parse_message() returned an unexpected message type: {:?}",
+                msg
+            ),
+        }
+
+        assert!(parse_message(&format!("from {}", sender), 0).is_err());
+        assert!(parse_message("from invalid create", 0).is_err());
+    }
+
+    fn management_payload(msg: Message, expected_method: ic00::Method) -> Vec<u8> {
+        match msg {
+            Message::Management(signed_ingress) => {
+                let content = signed_ingress.content();
+                assert_eq!(content.canister_id(), ic00::IC_00);
+                assert_eq!(content.method_name(), expected_method.to_string());
+                content.arg().to_vec()
+            }
+            msg => panic!(
+                "parse_message() returned an unexpected message type: {:?}",
+                msg
+            ),
+        }
+    }
+
+    #[test]
+    fn test_parse_canister_lifecycle_messages_succeeds() {
+        for (command, method) in [
+            ("stop", ic00::Method::StopCanister),
+            ("start", ic00::Method::StartCanister),
+            ("delete", ic00::Method::DeleteCanister),
+            ("canister_status", ic00::Method::CanisterStatus),
+        ] {
+            let s = &format!("{} {}", command, APP_CANISTER_URL);
+            let payload = management_payload(parse_message(s, 0).unwrap(), method);
+            assert_eq!(
+                CanisterIdRecord::decode(&payload)
+                    .unwrap()
+                    .get_canister_id(),
+                canister_test_id(APP_CANISTER_ID)
+            );
+        }
+
+        assert!(parse_message("stop", 0).is_err());
+        assert!(parse_message(&format!("stop {} 1", APP_CANISTER_URL), 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_cycles_messages_succeeds() {
+        match parse_message("create_with_cycles 1000000", 0).unwrap() {
+            Message::Create(signed_ingress) => {
+                let args = ic00::ProvisionalCreateCanisterWithCyclesArgs::decode(
+                    signed_ingress.content().arg(),
+                )
+                .unwrap();
+                assert_eq!(args.to_u128(), Some(1_000_000));
+            }
+            msg => panic!(
+                "parse_message() returned an unexpected message type: {:?}",
+                msg
+            ),
+        }
+
+        let s = &format!("add_cycles {} 42", APP_CANISTER_URL);
+        let payload = management_payload(
+            parse_message(s, 0).unwrap(),
+            ic00::Method::ProvisionalTopUpCanister,
+        );
+        let args = ic00::ProvisionalTopUpCanisterArgs::decode(&payload).unwrap();
+        assert_eq!(args.get_canister_id(), canister_test_id(APP_CANISTER_ID));
+        assert_eq!(args.to_u128(), Some(42));
+
+        assert!(parse_message("create_with_cycles -1", 0).is_err());
+        assert!(parse_message(&format!("add_cycles {} many", APP_CANISTER_URL), 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_update_settings_succeeds() {
+        let controller_1 = PrincipalId::new_user_test_id(1);
+        let controller_2 = PrincipalId::new_user_test_id(2);
+        let s = &format!(
+            "update_settings {} controllers={},{} compute_allocation=10 memory_allocation=4096 freezing_threshold=100",
+            APP_CANISTER_URL, controller_1, controller_2
+        );
+        let payload =
+            management_payload(parse_message(s, 0).unwrap(), ic00::Method::UpdateSettings);
+        let args = UpdateSettingsArgs::decode(&payload).unwrap();
+        assert_eq!(args.get_canister_id(), canister_test_id(APP_CANISTER_ID));
+        assert_eq!(
+            args.settings.controllers,
+            Some(vec![controller_1, controller_2])
+        );
+        assert_eq!(args.settings.compute_allocation, Some(10_u64.into()));
+        assert_eq!(args.settings.memory_allocation, Some(4096_u64.into()));
+        assert_eq!(args.settings.freezing_threshold, Some(100_u64.into()));
+
+        let s = &format!("update_settings {} controllers=", APP_CANISTER_URL);
+        let payload =
+            management_payload(parse_message(s, 0).unwrap(), ic00::Method::UpdateSettings);
+        let args = UpdateSettingsArgs::decode(&payload).unwrap();
+        assert_eq!(args.settings.controllers, Some(vec![]));
+        assert_eq!(args.settings.compute_allocation, None);
+
+        let s = &format!("update_settings {} unknown=1", APP_CANISTER_URL);
+        assert!(parse_message(s, 0).is_err());
+        let s = &format!("update_settings {} compute_allocation", APP_CANISTER_URL);
+        assert!(parse_message(s, 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_advance_time_succeeds() {
+        assert_eq!(
+            parse_message("advance_time 60", 0).unwrap(),
+            Message::AdvanceTime(Duration::from_secs(60))
+        );
+        assert!(parse_message("advance_time", 0).is_err());
+        assert!(parse_message("advance_time -1", 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_expect_succeeds() {
+        assert_eq!(
+            parse_message("expect reply", 0).unwrap(),
+            Message::Expect(Expectation::Reply(None))
+        );
+        assert_eq!(
+            parse_message("expect reply 0x0102", 0).unwrap(),
+            Message::Expect(Expectation::Reply(Some(vec![1, 2])))
+        );
+        assert_eq!(
+            parse_message("expect reject", 0).unwrap(),
+            Message::Expect(Expectation::Reject {
+                code: None,
+                message: None
+            })
+        );
+        assert_eq!(
+            parse_message("expect reject 5", 0).unwrap(),
+            Message::Expect(Expectation::Reject {
+                code: Some(RejectCode::CanisterError),
+                message: None
+            })
+        );
+        assert_eq!(
+            parse_message("expect reject 4 \"not allowed\"", 0).unwrap(),
+            Message::Expect(Expectation::Reject {
+                code: Some(RejectCode::CanisterReject),
+                message: Some("not allowed".to_string())
+            })
+        );
+
+        assert!(parse_message("expect", 0).is_err());
+        assert!(parse_message("expect reject 6", 0).is_err());
+        assert!(parse_message("expect replied", 0).is_err());
+    }
+
     #[test]
     fn test_line_iterator() {
         let text = Cursor::new(
@@ -0,0 +1,302 @@
+//! Reporting of message results and checking of expected results.
+
+use hex::encode;
+use ic_error_types::{RejectCode, UserError};
+use ic_types::{ingress::WasmResult, messages::MessageId};
+use serde::Serialize;
+use std::{fmt, str::FromStr};
+
+/// The format in which drun prints the results of messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Free-form text, one line per message.
+    Text,
+    /// One JSON object per line and message.
+    Json,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Text
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "Unknown output format {}, expected one of: text, json",
+                s
+            )),
+        }
+    }
+}
+
+/// The result expected by an `expect` directive for the preceding message.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Expectation {
+    /// The message is replied to, with the given payload if any.
+    Reply(Option<Vec<u8>>),
+    /// The message is rejected, with the given reject code if any and a
+    /// reject message that contains the given string if any.
+    Reject {
+        code: Option<RejectCode>,
+        message: Option<String>,
+    },
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expectation::Reply(None) => write!(f, "reply"),
+            Expectation::Reply(Some(payload)) => write!(f, "reply 0x{}", encode(payload)),
+            Expectation::Reject { code, message } => {
+                write!(f, "reject")?;
+                if let Some(code) = code {
+                    write!(f, " {}", *code as u64)?;
+                }
+                if let Some(message) = message {
+                    write!(f, " containing {:?}", message)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The kind of message a result belongs to.
+#[derive(Clone, Debug)]
+pub(crate) enum MessageKind {
+    Ingress(MessageId),
+    Query,
+}
+
+/// The result of executing a single message.
+#[derive(Clone, Debug)]
+pub(crate) struct MessageResult {
+    pub(crate) kind: MessageKind,
+    pub(crate) result: Result<WasmResult, UserError>,
+    /// The number of instructions executed while processing the message,
+    /// including the messages it triggered.
+    pub(crate) instructions_used: u64,
+    /// The number of cycles charged to canisters while processing the
+    /// message, including the messages it triggered.
+    pub(crate) cycles_charged: u128,
+}
+
+/// The JSON representation of a `MessageResult`.
+#[derive(Serialize)]
+struct JsonMessageResult {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_id: Option<String>,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reject_code: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reject_message: Option<String>,
+    instructions_used: u64,
+    cycles_charged: u128,
+}
+
+impl MessageResult {
+    /// Returns the reject code and message if the message was rejected.
+    fn reject(&self) -> Option<(RejectCode, &str)> {
+        match &self.result {
+            Ok(WasmResult::Reply(_)) => None,
+            Ok(WasmResult::Reject(message)) => Some((RejectCode::CanisterReject, message)),
+            Err(error) => Some((error.reject_code(), error.description())),
+        }
+    }
+
+    /// Prints the result to stdout in the given format.
+    pub(crate) fn print(&self, format: OutputFormat) {
+        match format {
+            OutputFormat::Text => self.print_text(),
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string(&self.to_json()).expect("Failed to serialize result.")
+            ),
+        }
+    }
+
+    fn print_text(&self) {
+        match (&self.kind, &self.result) {
+            (MessageKind::Ingress(_), Ok(wasm_result)) => {
+                print!("ingress Completed: ");
+                print_wasm_result(wasm_result);
+            }
+            (MessageKind::Ingress(_), Err(error)) => println!("ingress Err: {}", error),
+            (MessageKind::Query, Ok(wasm_result)) => {
+                print!("Ok: ");
+                print_wasm_result(wasm_result);
+            }
+            (MessageKind::Query, Err(error)) => println!("Err: {}", error),
+        }
+    }
+
+    fn to_json(&self) -> JsonMessageResult {
+        let (kind, message_id) = match &self.kind {
+            MessageKind::Ingress(message_id) => ("ingress", Some(message_id.to_string())),
+            MessageKind::Query => ("query", None),
+        };
+        let reject = self.reject();
+        JsonMessageResult {
+            kind,
+            message_id,
+            status: if reject.is_some() { "reject" } else { "reply" },
+            reply: match &self.result {
+                Ok(WasmResult::Reply(payload)) => Some(format!("0x{}", encode(payload))),
+                _ => None,
+            },
+            reject_code: reject.map(|(code, _)| code as u64),
+            error_code: self
+                .result
+                .as_ref()
+                .err()
+                .map(|error| error.code().to_string()),
+            reject_message: reject.map(|(_, message)| message.to_string()),
+            instructions_used: self.instructions_used,
+            cycles_charged: self.cycles_charged,
+        }
+    }
+
+    /// Checks the result against `expectation` and returns a description of
+    /// the deviation if it does not match.
+    pub(crate) fn check(&self, expectation: &Expectation) -> Result<(), String> {
+        let matches = match (expectation, &self.result, self.reject()) {
+            (Expectation::Reply(expected), Ok(WasmResult::Reply(payload)), _) => expected
+                .as_ref()
+                .map_or(true, |expected| expected == payload),
+            (Expectation::Reject { code, message }, _, Some((actual_code, actual_message))) => {
+                code.map_or(true, |code| code == actual_code)
+                    && message
+                        .as_ref()
+                        .map_or(true, |message| actual_message.contains(message.as_str()))
+            }
+            _ => false,
+        };
+        if matches {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected {}, got {}",
+                expectation,
+                self.describe_result()
+            ))
+        }
+    }
+
+    fn describe_result(&self) -> String {
+        match (&self.result, self.reject()) {
+            (_, Some((code, message))) => format!("reject {} {:?}", code as u64, message),
+            (Ok(WasmResult::Reply(payload)), None) => format!("reply 0x{}", encode(payload)),
+            (_, None) => unreachable!("Only replies have no reject code."),
+        }
+    }
+}
+
+fn print_wasm_result(wasm_result: &WasmResult) {
+    match wasm_result {
+        WasmResult::Reply(v) => println!("Reply: 0x{}", encode(v)),
+        WasmResult::Reject(e) => println!("Reject: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_error_types::ErrorCode;
+
+    fn query_result(result: Result<WasmResult, UserError>) -> MessageResult {
+        MessageResult {
+            kind: MessageKind::Query,
+            result,
+            instructions_used: 0,
+            cycles_charged: 0,
+        }
+    }
+
+    #[test]
+    fn test_check_reply() {
+        let result = query_result(Ok(WasmResult::Reply(vec![1, 2, 3])));
+        assert!(result.check(&Expectation::Reply(None)).is_ok());
+        assert!(result
+            .check(&Expectation::Reply(Some(vec![1, 2, 3])))
+            .is_ok());
+        assert!(result.check(&Expectation::Reply(Some(vec![1, 2]))).is_err());
+        assert!(result
+            .check(&Expectation::Reject {
+                code: None,
+                message: None
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_check_reject() {
+        let result = query_result(Err(UserError::new(
+            ErrorCode::CanisterTrapped,
+            "Canister trapped: unreachable",
+        )));
+        assert!(result
+            .check(&Expectation::Reject {
+                code: Some(RejectCode::CanisterError),
+                message: Some("unreachable".to_string()),
+            })
+            .is_ok());
+        assert!(result
+            .check(&Expectation::Reject {
+                code: Some(RejectCode::CanisterReject),
+                message: None,
+            })
+            .is_err());
+        assert!(result
+            .check(&Expectation::Reject {
+                code: None,
+                message: Some("out of cycles".to_string()),
+            })
+            .is_err());
+        assert!(result.check(&Expectation::Reply(None)).is_err());
+
+        let result = query_result(Ok(WasmResult::Reject("no".to_string())));
+        assert!(result
+            .check(&Expectation::Reject {
+                code: Some(RejectCode::CanisterReject),
+                message: Some("no".to_string()),
+            })
+            .is_ok());
+    }
+
+    #[test]
+    fn test_json_output() {
+        let result = MessageResult {
+            kind: MessageKind::Query,
+            result: Ok(WasmResult::Reply(vec![1, 2, 3])),
+            instructions_used: 10,
+            cycles_charged: 20,
+        };
+        assert_eq!(
+            serde_json::to_string(&result.to_json()).unwrap(),
+            r#"{"type":"query","status":"reply","reply":"0x010203","instructions_used":10,"cycles_charged":20}"#
+        );
+
+        let result = query_result(Err(UserError::new(
+            ErrorCode::CanisterTrapped,
+            "Canister trapped",
+        )));
+        assert_eq!(
+            serde_json::to_string(&result.to_json()).unwrap(),
+            r#"{"type":"query","status":"reject","reject_code":5,"error_code":"IC0502","reject_message":"Canister trapped","instructions_used":0,"cycles_charged":0}"#
+        );
+    }
+}
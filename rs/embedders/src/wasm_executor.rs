@@ -453,6 +453,7 @@ pub fn wasm_execution_error(
                 accessed_pages: 0,
                 dirty_pages: 0,
             },
+            canister_log: Default::default(),
         },
         None,
     )
@@ -615,6 +616,7 @@ pub fn process(
                         accessed_pages: 0,
                         dirty_pages: 0,
                     },
+                    canister_log: Default::default(),
                 },
                 None,
                 Err(system_api),
@@ -658,13 +660,19 @@ pub fn process(
         .take_execution_result(run_result.as_ref().err());
 
     let wasm_heap_size_after = instance.heap_size();
-    let wasm_heap_limit =
-        NumWasmPages::from(wasmtime_environ::WASM32_MAX_PAGES as usize) - wasm_reserved_pages;
+    let wasm_heap_max_pages = if instance.is_memory64() {
+        embedder.max_wasm64_pages()
+    } else {
+        wasmtime_environ::WASM32_MAX_PAGES
+    };
+    let wasm_heap_limit = NumWasmPages::from(wasm_heap_max_pages as usize) - wasm_reserved_pages;
 
     if wasm_heap_size_after > wasm_heap_limit {
         wasm_result = Err(HypervisorError::WasmReservedPages);
     }
 
+    let canister_log = instance.store_data_mut().system_api.take_canister_log();
+
     let mut allocated_bytes = NumBytes::from(0);
     let mut allocated_message_bytes = NumBytes::from(0);
 
@@ -714,6 +722,7 @@ pub fn process(
             allocated_bytes,
             allocated_message_bytes,
             instance_stats,
+            canister_log,
         },
         wasm_state_changes,
         Ok(instance),
@@ -11,9 +11,14 @@ use crate::{serialized_module::SerializedModule, CompilationResult, WasmtimeEmbe
 pub mod decoding;
 pub mod errors;
 pub mod instrumentation;
+mod memory64;
 pub mod validation;
 mod wasm_module_builder;
 
+/// Only exposed for tests that need to build modules with a 64-bit memory.
+#[doc(hidden)]
+pub use memory64::set_memory64_flags;
+
 use instrumentation::instrument;
 use validation::validate_wasm_binary;
 
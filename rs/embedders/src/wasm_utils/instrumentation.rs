@@ -7,7 +7,8 @@
 //!    This quantity is approximated by the sum of cost of instructions executed
 //!    on the taken execution path.
 //!  * Verify that no successful `memory.grow` results in exceeding the
-//!    available memory allocated to the canister.
+//!    available memory allocated to the canister or the Wasm memory limit in
+//!    the canister settings.
 //!
 //! Moreover, it exports the function referred to by the `start` section under
 //! the name `canister_start` and removes the section. (This is needed so that
@@ -93,8 +94,25 @@
 //! blocks to optimize for performance. The maximal overflow in that case is
 //! bound by the length of the longest execution path consisting of
 //! non-reentrant basic blocks.
+//!
+//! Modules with a 64-bit memory are instrumented in the same way, except that
+//! `memory.grow` is followed by a call to
+//!
+//! ```wasm
+//! (import "__" "update_available_memory_64" (func (;1;) ((param i64 i64) (result i64))))
+//! ```
+//!
+//! and a second function to update the instruction counter taking an `i64` is
+//! used for `memory.fill` and `memory.copy`. The runtime provides System API
+//! functions that access the memory with `i64` addresses and sizes. Imports
+//! whose declared parameters differ from the signature provided by the
+//! runtime are called through a generated function that converts the
+//! arguments.
 
-use super::{errors::into_parity_wasm_error, wasm_module_builder::WasmModuleBuilder};
+use super::{
+    errors::into_parity_wasm_error, memory64, validation::memory64_system_api_param_types,
+    wasm_module_builder::WasmModuleBuilder,
+};
 use ic_replicated_state::canister_state::WASM_PAGE_SIZE_IN_BYTES;
 use ic_replicated_state::NumWasmPages;
 use ic_sys::{PageBytes, PageIndex, PAGE_SIZE};
@@ -104,8 +122,9 @@ use ic_wasm_types::{BinaryEncodedWasm, WasmInstrumentationError};
 
 use parity_wasm::builder;
 use parity_wasm::elements::{
-    BlockType, BulkInstruction, ExportEntry, FuncBody, FunctionType, GlobalEntry, GlobalType,
-    InitExpr, Instruction, Instructions, Internal, Local, Module, Section, Type, ValueType,
+    BlockType, BulkInstruction, ExportEntry, External, FuncBody, FunctionType, GlobalEntry,
+    GlobalType, InitExpr, Instruction, Instructions, Internal, Local, Module, Section, Type,
+    ValueType,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeSet, HashMap};
@@ -139,14 +158,23 @@ fn instruction_to_cost(i: &Instruction) -> u64 {
 //     out of instructions.
 //   * `update_available_memory` which is called after a native `memory.grow` to
 //     check whether the canister has enough available memory according to its
-//     memory allocation.
+//     memory allocation and whether the new Wasm memory size stays within the
+//     `wasm_memory_limit` of the canister settings.
 //
 // Note that these functions are injected as the first two imports, so that we
 // can increment all function indices unconditionally by two. (If they would be
 // added as the last two imports, we'd need to increment only non imported
 // functions, since imported functions precede all others in the function index
 // space, but this would be error-prone).
-fn inject_helper_functions(module: Module) -> Module {
+//
+// Modules with a 64-bit memory import `update_available_memory_64` instead,
+// which takes and returns `i64` values like `memory.grow` on such memories.
+fn inject_helper_functions(module: Module, is_memory64: bool) -> Module {
+    let (update_available_memory, memory_index_type) = if is_memory64 {
+        ("update_available_memory_64", ValueType::I64)
+    } else {
+        ("update_available_memory", ValueType::I32)
+    };
     let mut builder = builder::from_module(module);
     let import_sig = builder.push_signature(builder::signature().build_sig());
 
@@ -161,15 +189,15 @@ fn inject_helper_functions(module: Module) -> Module {
 
     let import_sig = builder.push_signature(
         builder::signature()
-            .with_param(ValueType::I32)
-            .with_param(ValueType::I32)
-            .with_result(ValueType::I32)
+            .with_param(memory_index_type)
+            .with_param(memory_index_type)
+            .with_result(memory_index_type)
             .build_sig(),
     );
     builder.push_import(
         builder::import()
             .module("__")
-            .field("update_available_memory")
+            .field(update_available_memory)
             .external()
             .func(import_sig)
             .build(),
@@ -181,7 +209,7 @@ fn inject_helper_functions(module: Module) -> Module {
     // increase all other function indices unconditionally.
     let entries = module.import_section_mut().unwrap().entries_mut();
     let last = entries.pop().unwrap();
-    debug_assert!(last.module() == "__" && last.field() == "update_available_memory");
+    debug_assert!(last.module() == "__" && last.field() == update_available_memory);
     entries.insert(0, last);
     let last = entries.pop().unwrap();
     debug_assert!(last.module() == "__" && last.field() == "out_of_instructions");
@@ -192,7 +220,7 @@ fn inject_helper_functions(module: Module) -> Module {
     );
     debug_assert!(
         entries[InjectedImports::UpdateAvailableMemoryFn as usize].field()
-            == "update_available_memory"
+            == update_available_memory
     );
 
     // We lift all call references by 2
@@ -229,6 +257,149 @@ fn inject_helper_functions(module: Module) -> Module {
     module
 }
 
+// In modules with a 64-bit memory, System API functions may be imported with
+// `i64` or `i32` parameters, while the runtime provides the signatures
+// returned by `memory64_system_api_param_types()`. The type of an import that
+// differs is changed accordingly and all references to it are redirected to a
+// generated function with the original signature. That function zero-extends
+// `i32` arguments that the runtime takes as `i64` (addresses and sizes). The
+// remaining `i64` arguments are not addresses (e.g. the `fun` argument of
+// `call_on_cleanup`), so the function traps if they do not fit into 32 bits
+// and otherwise wraps them.
+fn wrap_memory64_system_api_imports(module: Module) -> Module {
+    // The position in the import section, the function index, the original
+    // type, and the parameters expected by the runtime of the imports to wrap.
+    let mut imports_to_wrap: Vec<(usize, u32, FunctionType, Vec<ValueType>)> = vec![];
+    if let Some(section) = module.import_section() {
+        let types = module.type_section().unwrap().types();
+        let mut func_index = 0;
+        for (entry_index, entry) in section.entries().iter().enumerate() {
+            if let External::Function(type_index) = entry.external() {
+                let Type::Function(func_type) = &types[*type_index as usize];
+                if let Some(expected) =
+                    memory64_system_api_param_types(entry.module(), entry.field())
+                {
+                    if func_type.params() != expected.as_slice() {
+                        imports_to_wrap.push((
+                            entry_index,
+                            func_index,
+                            func_type.clone(),
+                            expected,
+                        ));
+                    }
+                }
+                func_index += 1;
+            }
+        }
+    }
+    if imports_to_wrap.is_empty() {
+        return module;
+    }
+
+    let first_wrapper_index = module.functions_space() as u32;
+    let mut redirects = HashMap::new();
+    let mut retyped_imports = vec![];
+    let mut mbuilder = builder::from_module(module);
+    for (wrapper_index, (entry_index, import_index, func_type, expected)) in
+        imports_to_wrap.into_iter().enumerate()
+    {
+        let import_type = mbuilder.push_signature(
+            builder::signature()
+                .with_params(expected.clone())
+                .with_results(func_type.results().to_vec())
+                .build_sig(),
+        );
+        retyped_imports.push((entry_index, import_type));
+
+        let mut body = vec![];
+        for (param_index, (actual, expected)) in
+            func_type.params().iter().zip(expected.iter()).enumerate()
+        {
+            let param_index = param_index as u32;
+            if (*actual, *expected) == (ValueType::I64, ValueType::I32) {
+                body.extend_from_slice(&[
+                    Instruction::GetLocal(param_index),
+                    Instruction::I64Const(u32::MAX as i64),
+                    Instruction::I64GtU,
+                    Instruction::If(BlockType::NoResult),
+                    Instruction::Unreachable,
+                    Instruction::End,
+                    Instruction::GetLocal(param_index),
+                    Instruction::I32WrapI64,
+                ]);
+            } else if (*actual, *expected) == (ValueType::I32, ValueType::I64) {
+                body.extend_from_slice(&[
+                    Instruction::GetLocal(param_index),
+                    Instruction::I64ExtendUI32,
+                ]);
+            } else {
+                body.push(Instruction::GetLocal(param_index));
+            }
+        }
+        body.extend_from_slice(&[Instruction::Call(import_index), Instruction::End]);
+        mbuilder.push_function(
+            builder::function()
+                .with_signature(
+                    builder::signature()
+                        .with_params(func_type.params().to_vec())
+                        .with_results(func_type.results().to_vec())
+                        .build_sig(),
+                )
+                .body()
+                .with_instructions(Instructions::new(body))
+                .build()
+                .build(),
+        );
+        redirects.insert(import_index, first_wrapper_index + wrapper_index as u32);
+    }
+    let mut module = mbuilder.build();
+
+    let entries = module.import_section_mut().unwrap().entries_mut();
+    for (entry_index, import_type) in retyped_imports {
+        *entries[entry_index].external_mut() = External::Function(import_type);
+    }
+
+    let redirect = |func_index: &mut u32| {
+        if let Some(wrapper_index) = redirects.get(func_index) {
+            *func_index = *wrapper_index;
+        }
+    };
+    let num_wrappers = redirects.len();
+    for section in module.sections_mut() {
+        match section {
+            Section::Code(ref mut code_section) => {
+                let bodies = code_section.bodies_mut();
+                // The wrappers are the last functions and must keep calling
+                // the imports.
+                let num_bodies = bodies.len() - num_wrappers;
+                for func_body in bodies.iter_mut().take(num_bodies) {
+                    for instr in func_body.code_mut().elements_mut().iter_mut() {
+                        if let Instruction::Call(ref mut call_index) = instr {
+                            redirect(call_index);
+                        }
+                    }
+                }
+            }
+            Section::Export(ref mut export_section) => {
+                for export in export_section.entries_mut() {
+                    if let Internal::Function(ref mut func_index) = export.internal_mut() {
+                        redirect(func_index);
+                    }
+                }
+            }
+            Section::Element(ref mut elements_section) => {
+                for segment in elements_section.entries_mut() {
+                    for func_index in segment.members_mut() {
+                        redirect(func_index);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    module
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct Segment {
     offset: usize,
@@ -332,6 +503,7 @@ pub struct InstrumentationOutput {
     /// All exported methods that are relevant to the IC.
     /// Methods relevant to the IC are:
     ///     - Queries (e.g. canister_query ___)
+    ///     - Composite queries (e.g. canister_composite_query ___)
     ///     - Updates (e.g. canister_update ___)
     ///     - System methods (e.g. canister_init)
     /// Other methods are assumed to be private to the module and are ignored.
@@ -352,6 +524,10 @@ pub struct InstrumentationOutput {
 pub struct ExportModuleData {
     pub instructions_counter_ix: u32,
     pub decr_instruction_counter_fn: u32,
+    /// The function to decrement the instruction counter by an `i64`. Only
+    /// present in modules with a 64-bit memory, where the size arguments of
+    /// `memory.fill` and `memory.copy` are `i64` values.
+    pub decr_instruction_counter_fn_64: Option<u32>,
     pub start_fn_ix: Option<u32>,
 }
 
@@ -364,10 +540,17 @@ pub(super) fn instrument(
     wasm: &BinaryEncodedWasm,
     cost_to_compile_wasm_instruction: NumInstructions,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
-    let module = parity_wasm::deserialize_buffer::<Module>(wasm.as_slice()).map_err(|err| {
-        WasmInstrumentationError::ParityDeserializeError(into_parity_wasm_error(err))
-    })?;
-    let mut module = inject_helper_functions(module);
+    let is_memory64 = memory64::is_memory64(wasm.as_slice());
+    let module = if is_memory64 {
+        parity_wasm::deserialize_buffer::<Module>(&memory64::clear_memory64_flags(wasm.as_slice()))
+    } else {
+        parity_wasm::deserialize_buffer::<Module>(wasm.as_slice())
+    }
+    .map_err(|err| WasmInstrumentationError::ParityDeserializeError(into_parity_wasm_error(err)))?;
+    let mut module = inject_helper_functions(module, is_memory64);
+    if is_memory64 {
+        module = wrap_memory64_system_api_imports(module);
+    }
     module = export_table(module);
     module = export_memory(module);
     module = export_mutable_globals(module);
@@ -377,6 +560,11 @@ pub(super) fn instrument(
     let export_module_data = ExportModuleData {
         instructions_counter_ix: num_globals,
         decr_instruction_counter_fn: num_functions,
+        decr_instruction_counter_fn_64: if is_memory64 {
+            Some(num_functions + 1)
+        } else {
+            None
+        },
         start_fn_ix: module.start_section(),
     };
 
@@ -415,7 +603,7 @@ pub(super) fn instrument(
         if !func_types.is_empty() {
             let func_bodies = module.code_section_mut().unwrap().bodies_mut();
             for (func_ix, func_type) in func_types.into_iter().enumerate() {
-                inject_update_available_memory(&mut func_bodies[func_ix], &func_type);
+                inject_update_available_memory(&mut func_bodies[func_ix], &func_type, is_memory64);
             }
         }
     }
@@ -469,9 +657,12 @@ pub(super) fn instrument(
             })
             .unwrap_or(0)) as u64;
 
-    let result = parity_wasm::serialize(module).map_err(|err| {
+    let mut result = parity_wasm::serialize(module).map_err(|err| {
         WasmInstrumentationError::ParitySerializeError(into_parity_wasm_error(err))
     })?;
+    if is_memory64 {
+        memory64::set_memory64_flags(&mut result);
+    }
     Ok(InstrumentationOutput {
         exported_functions,
         data,
@@ -491,36 +682,16 @@ pub fn export_additional_symbols(
     let mut mbuilder = WasmModuleBuilder::new(builder::from_module(module));
 
     // push function to decrement the instruction counter
-    mbuilder.push_function(
-        builder::function()
-            .with_signature(
-                builder::signature()
-                    .with_param(ValueType::I32) // amount to decrement by
-                    .with_result(ValueType::I32) // argument is returned so stack remains unchanged
-                    .build_sig(),
-            )
-            .body()
-            .with_instructions(Instructions::new(vec![
-                // Subtract the parameter amount from the instruction counter
-                Instruction::GetGlobal(export_module_data.instructions_counter_ix),
-                Instruction::GetLocal(0),
-                Instruction::I64ExtendUI32,
-                Instruction::I64Sub,
-                Instruction::SetGlobal(export_module_data.instructions_counter_ix),
-                // Call out_of_instructions() if `counter < 0`.
-                Instruction::GetGlobal(export_module_data.instructions_counter_ix),
-                Instruction::I64Const(0),
-                Instruction::I64LtS,
-                Instruction::If(BlockType::NoResult),
-                Instruction::Call(InjectedImports::OutOfInstructionsFn as u32),
-                Instruction::End,
-                // Return the original param so this function doesn't alter the stack
-                Instruction::GetLocal(0),
-                Instruction::End,
-            ]))
-            .build()
-            .build(),
-    );
+    mbuilder.push_function(decr_instruction_counter_fn(
+        export_module_data.instructions_counter_ix,
+        ValueType::I32,
+    ));
+    if export_module_data.decr_instruction_counter_fn_64.is_some() {
+        mbuilder.push_function(decr_instruction_counter_fn(
+            export_module_data.instructions_counter_ix,
+            ValueType::I64,
+        ));
+    }
 
     // globals must be exported to be accessible to hypervisor or persisted
     mbuilder.push_export(
@@ -544,6 +715,47 @@ pub fn export_additional_symbols(
     Ok(module)
 }
 
+// Returns a function that decrements the instruction counter by its argument of
+// the given type and returns the argument.
+fn decr_instruction_counter_fn(
+    instructions_counter_ix: u32,
+    amount_type: ValueType,
+) -> builder::FunctionDefinition {
+    let mut instructions = vec![
+        // Subtract the parameter amount from the instruction counter
+        Instruction::GetGlobal(instructions_counter_ix),
+        Instruction::GetLocal(0),
+    ];
+    if amount_type == ValueType::I32 {
+        instructions.push(Instruction::I64ExtendUI32);
+    }
+    instructions.extend_from_slice(&[
+        Instruction::I64Sub,
+        Instruction::SetGlobal(instructions_counter_ix),
+        // Call out_of_instructions() if `counter < 0`.
+        Instruction::GetGlobal(instructions_counter_ix),
+        Instruction::I64Const(0),
+        Instruction::I64LtS,
+        Instruction::If(BlockType::NoResult),
+        Instruction::Call(InjectedImports::OutOfInstructionsFn as u32),
+        Instruction::End,
+        // Return the original param so this function doesn't alter the stack
+        Instruction::GetLocal(0),
+        Instruction::End,
+    ]);
+    builder::function()
+        .with_signature(
+            builder::signature()
+                .with_param(amount_type) // amount to decrement by
+                .with_result(amount_type) // argument is returned so stack remains unchanged
+                .build_sig(),
+        )
+        .body()
+        .with_instructions(Instructions::new(instructions))
+        .build()
+        .build()
+}
+
 // Represents a hint about the context of each static cost injection point in
 // wasm.
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -641,9 +853,20 @@ fn inject_metering(code: &mut Instructions, export_data_module: &ExportModuleDat
                 }
             }
             InjectionPointCostDetail::DynamicCost => {
-                elems.extend_from_slice(&[Instruction::Call(
-                    export_data_module.decr_instruction_counter_fn,
-                )]);
+                // The size of `memory.fill` and `memory.copy` on a 64-bit
+                // memory is an `i64`.
+                let decr_fn = match (
+                    &orig_elems[point.position],
+                    export_data_module.decr_instruction_counter_fn_64,
+                ) {
+                    (
+                        Instruction::Bulk(BulkInstruction::MemoryFill)
+                        | Instruction::Bulk(BulkInstruction::MemoryCopy),
+                        Some(decr_fn_64),
+                    ) => decr_fn_64,
+                    _ => export_data_module.decr_instruction_counter_fn,
+                };
+                elems.extend_from_slice(&[Instruction::Call(decr_fn)]);
             }
         }
         last_injection_position = point.position;
@@ -656,7 +879,11 @@ fn inject_metering(code: &mut Instructions, export_data_module: &ExportModuleDat
 // instruction to make sure that there's enough available memory left to support
 // the requested extra memory. If no `memory.grow` instructions are present then
 // the function's code remains unchanged.
-fn inject_update_available_memory(func_body: &mut FuncBody, func_type: &FunctionType) {
+fn inject_update_available_memory(
+    func_body: &mut FuncBody,
+    func_type: &FunctionType,
+    is_memory64: bool,
+) {
     let mut injection_points: Vec<usize> = Vec::new();
     {
         let code = func_body.code();
@@ -674,7 +901,14 @@ fn inject_update_available_memory(func_body: &mut FuncBody, func_type: &Function
         // We inject a local to cache the argument to `memory.grow`.
         let n_locals: u32 = func_body.locals().iter().map(Local::count).sum();
         let memory_local_ix = func_type.params().len() as u32 + n_locals;
-        func_body.locals_mut().push(Local::new(1, ValueType::I32));
+        let memory_index_type = if is_memory64 {
+            ValueType::I64
+        } else {
+            ValueType::I32
+        };
+        func_body
+            .locals_mut()
+            .push(Local::new(1, memory_index_type));
         let code = func_body.code_mut();
         let orig_elems = code.elements_mut();
         let mut elems: Vec<Instruction> = Vec::new();
@@ -773,6 +1007,11 @@ fn get_data(sections: &mut Vec<Section>) -> Segments {
                                     Instruction::I32Const(val),
                                     Instruction::End
                                ] => ((*val) as u32) as usize, // Convert via `u32` to avoid 64-bit sign-extension.
+                                // Offsets into a 64-bit memory.
+                                [
+                                    Instruction::I64Const(val),
+                                    Instruction::End
+                               ] => (*val) as u64 as usize,
                                 _ => panic!(
                                     "complex initialization expressions for data segments are not supported!"
                                     ),
@@ -0,0 +1,276 @@
+//! Helpers for Wasm modules that declare a 64-bit memory (the memory64
+//! proposal).
+//!
+//! `parity_wasm` does not know about the memory64 encoding of memory limits,
+//! so before a module is handed to it the flag marking a memory as 64-bit is
+//! cleared, and after instrumentation the flag is set again on the serialized
+//! module. The limits themselves are LEB128-encoded in both cases, so
+//! flipping the flag bit is the only change required and can be done in place.
+//!
+//! The offsets of loads, stores and data segments are 64-bit numbers in such
+//! modules, but `parity_wasm` only supports 32-bit offsets. Validation
+//! therefore rejects memory64 modules with offsets of 4GiB or more before
+//! they are parsed by `parity_wasm`.
+
+use std::ops::Range;
+
+/// The bit in the flags of a memory type that marks the memory as 64-bit.
+const MEMORY64_FLAG: u8 = 0x04;
+/// The bit in the flags of a memory type that indicates a maximum is present.
+const HAS_MAXIMUM_FLAG: u8 = 0x01;
+
+const IMPORT_SECTION_ID: u8 = 2;
+const MEMORY_SECTION_ID: u8 = 5;
+const CODE_SECTION_ID: u8 = 10;
+
+const IMPORT_KIND_FUNCTION: u8 = 0x00;
+const IMPORT_KIND_TABLE: u8 = 0x01;
+const IMPORT_KIND_MEMORY: u8 = 0x02;
+const IMPORT_KIND_GLOBAL: u8 = 0x03;
+
+/// The limits of a memory declared or imported by a module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct MemoryLimits {
+    /// Offset of the flags byte in the binary.
+    flags_offset: usize,
+    pub is_64: bool,
+    pub initial: u64,
+    pub maximum: Option<u64>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], pos: usize) -> Self {
+        Self { bytes, pos }
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn leb_u64(&mut self) -> Option<u64> {
+        let mut result: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= 64 {
+                return None;
+            }
+            result |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(result);
+            }
+            shift += 7;
+        }
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        let end = self.pos.checked_add(len)?;
+        if end > self.bytes.len() {
+            return None;
+        }
+        self.pos = end;
+        Some(())
+    }
+
+    fn skip_name(&mut self) -> Option<()> {
+        let len = self.leb_u64()? as usize;
+        self.skip(len)
+    }
+
+    fn limits(&mut self) -> Option<MemoryLimits> {
+        let flags_offset = self.pos;
+        let flags = self.byte()?;
+        let initial = self.leb_u64()?;
+        let maximum = if flags & HAS_MAXIMUM_FLAG != 0 {
+            Some(self.leb_u64()?)
+        } else {
+            None
+        };
+        Some(MemoryLimits {
+            flags_offset,
+            is_64: flags & MEMORY64_FLAG != 0,
+            initial,
+            maximum,
+        })
+    }
+
+    // Skips the next instruction and returns the offset of its memory
+    // argument, or 0 if it has none. Returns `None` for instructions that
+    // are not supported by `parity_wasm`, e.g. SIMD and atomics.
+    fn instruction(&mut self) -> Option<u64> {
+        match self.byte()? {
+            // Instructions without immediates.
+            0x00 | 0x01 | 0x05 | 0x0b | 0x0f | 0x1a | 0x1b | 0xd1 | 0x45..=0xc4 => {}
+            // Instructions with a single LEB128 immediate: block types,
+            // branches, calls, variable and table accesses, memory indices,
+            // integer constants and reference types.
+            0x02..=0x04 | 0x0c | 0x0d | 0x10 | 0x20..=0x26 | 0x3f..=0x42 | 0xd0 | 0xd2 => {
+                self.leb_u64()?;
+            }
+            // br_table
+            0x0e => {
+                let num_targets = self.leb_u64()?;
+                for _ in 0..=num_targets {
+                    self.leb_u64()?;
+                }
+            }
+            // call_indirect
+            0x11 => {
+                self.leb_u64()?;
+                self.leb_u64()?;
+            }
+            // select with value types
+            0x1c => {
+                let num_types = self.leb_u64()? as usize;
+                self.skip(num_types)?;
+            }
+            // Loads and stores: the alignment followed by the offset.
+            0x28..=0x3e => {
+                self.leb_u64()?;
+                return self.leb_u64();
+            }
+            0x43 => self.skip(4)?,
+            0x44 => self.skip(8)?,
+            0xfc => match self.leb_u64()? {
+                // Saturating truncations.
+                0..=7 => {}
+                // memory.init, memory.copy, table.init, table.copy
+                8 | 10 | 12 | 14 => {
+                    self.leb_u64()?;
+                    self.leb_u64()?;
+                }
+                // data.drop, memory.fill, elem.drop, table.grow, table.size,
+                // table.fill
+                9 | 11 | 13 | 15..=17 => {
+                    self.leb_u64()?;
+                }
+                _ => return None,
+            },
+            _ => return None,
+        }
+        Some(0)
+    }
+}
+
+// Returns the ids and the byte ranges of the import, memory and code
+// sections.
+fn sections(wasm: &[u8]) -> Option<Vec<(u8, Range<usize>)>> {
+    if wasm.len() < 8 {
+        return None;
+    }
+    // Skip the magic number and the version.
+    let mut reader = Reader::new(wasm, 8);
+    let mut result = vec![];
+    while reader.pos < wasm.len() {
+        let id = reader.byte()?;
+        let size = reader.leb_u64()? as usize;
+        let start = reader.pos;
+        reader.skip(size)?;
+        if id == IMPORT_SECTION_ID || id == MEMORY_SECTION_ID || id == CODE_SECTION_ID {
+            result.push((id, start..reader.pos));
+        }
+    }
+    Some(result)
+}
+
+/// Returns the limits of all memories that the module imports or declares.
+///
+/// Returns `None` if the module cannot be parsed. In that case the regular
+/// validation will report a proper error.
+pub(crate) fn memory_limits(wasm: &[u8]) -> Option<Vec<MemoryLimits>> {
+    let mut result = vec![];
+    for (id, range) in sections(wasm)? {
+        if id == CODE_SECTION_ID {
+            continue;
+        }
+        let mut reader = Reader::new(&wasm[..range.end], range.start);
+        let count = reader.leb_u64()?;
+        for _ in 0..count {
+            if id == MEMORY_SECTION_ID {
+                result.push(reader.limits()?);
+                continue;
+            }
+            reader.skip_name()?;
+            reader.skip_name()?;
+            match reader.byte()? {
+                IMPORT_KIND_FUNCTION => {
+                    reader.leb_u64()?;
+                }
+                IMPORT_KIND_TABLE => {
+                    reader.byte()?;
+                    reader.limits()?;
+                }
+                IMPORT_KIND_MEMORY => result.push(reader.limits()?),
+                IMPORT_KIND_GLOBAL => reader.skip(2)?,
+                _ => return None,
+            }
+        }
+    }
+    Some(result)
+}
+
+/// Returns the largest offset of the loads and stores in the code section of
+/// the module, or 0 if there are none.
+///
+/// Returns `None` if the code cannot be decoded. In that case the regular
+/// validation will report a proper error.
+pub(crate) fn max_memarg_offset(wasm: &[u8]) -> Option<u64> {
+    let mut max_offset = 0;
+    for (id, range) in sections(wasm)? {
+        if id != CODE_SECTION_ID {
+            continue;
+        }
+        let mut reader = Reader::new(&wasm[..range.end], range.start);
+        let count = reader.leb_u64()?;
+        for _ in 0..count {
+            let size = reader.leb_u64()? as usize;
+            let end = reader.pos.checked_add(size)?;
+            let mut body = Reader::new(wasm.get(..end)?, reader.pos);
+            let num_local_entries = body.leb_u64()?;
+            for _ in 0..num_local_entries {
+                body.leb_u64()?;
+                body.byte()?;
+            }
+            while body.pos < end {
+                max_offset = max_offset.max(body.instruction()?);
+            }
+            reader.pos = end;
+        }
+    }
+    Some(max_offset)
+}
+
+/// Returns true if the module imports or declares a 64-bit memory.
+pub(crate) fn is_memory64(wasm: &[u8]) -> bool {
+    memory_limits(wasm)
+        .map(|limits| limits.iter().any(|l| l.is_64))
+        .unwrap_or(false)
+}
+
+/// Returns a copy of the module in which all memories are declared as 32-bit
+/// memories, so that it can be parsed by `parity_wasm`.
+///
+/// The module must have passed validation, which guarantees that all of its
+/// offsets fit into 32 bits.
+pub(crate) fn clear_memory64_flags(wasm: &[u8]) -> Vec<u8> {
+    let mut result = wasm.to_vec();
+    for limits in memory_limits(wasm).unwrap_or_default() {
+        result[limits.flags_offset] &= !MEMORY64_FLAG;
+    }
+    result
+}
+
+/// Marks all memories of the module as 64-bit memories. Used to undo
+/// `clear_memory64_flags()` after the module has been instrumented.
+pub fn set_memory64_flags(wasm: &mut [u8]) {
+    for limits in memory_limits(wasm).unwrap_or_default() {
+        wasm[limits.flags_offset] |= MEMORY64_FLAG;
+    }
+}
@@ -1,16 +1,17 @@
 //! This module is responsible for validating the wasm binaries that are
 //! installed on the Internet Computer.
 
-use super::errors::into_parity_wasm_error;
+use super::{errors::into_parity_wasm_error, memory64};
 
-use ic_config::embedders::Config as EmbeddersConfig;
-use ic_replicated_state::canister_state::execution_state::{
-    CustomSection, CustomSectionType, WasmMetadata,
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
+use ic_replicated_state::canister_state::{
+    execution_state::{CustomSection, CustomSectionType, WasmMetadata},
+    WASM_PAGE_SIZE_IN_BYTES,
 };
 use ic_types::{NumBytes, NumInstructions};
 use ic_wasm_types::{BinaryEncodedWasm, WasmValidationError};
 use parity_wasm::elements::{
-    DataSegment, External, ImportCountType,
+    DataSegment, External, FunctionType, ImportCountType,
     Instruction::{self},
     Internal, Module, Section, Type, ValueType,
 };
@@ -530,6 +531,36 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 },
             )],
         ),
+        (
+            "global_timer_set",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64],
+                    return_type: vec![ValueType::I64],
+                },
+            )],
+        ),
+        (
+            "is_controller",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I32, ValueType::I32],
+                    return_type: vec![ValueType::I32],
+                },
+            )],
+        ),
+        (
+            "canister_version",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ValueType::I64],
+                },
+            )],
+        ),
         (
             "call_cycles_add128",
             vec![(
@@ -596,6 +627,65 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
         .collect()
 }
 
+// Returns the expected parameter types of the given System API function or
+// `None` if there is no such function.
+fn system_api_param_types(import_module: &str, field: &str) -> Option<Vec<ValueType>> {
+    get_valid_system_apis()
+        .remove(field)
+        .and_then(|mut signatures| signatures.remove(import_module))
+        .map(|signature| signature.param_types)
+}
+
+// The System API functions that read from or write to the memory of the
+// module.
+const MEMORY_ACCESSING_SYSTEM_API_FUNCTIONS: &[&str] = &[
+    "msg_caller_copy",
+    "msg_arg_data_copy",
+    "msg_method_name_copy",
+    "msg_reply_data_append",
+    "msg_reject",
+    "msg_reject_msg_copy",
+    "canister_self_copy",
+    "controller_copy",
+    "debug_print",
+    "trap",
+    "call_simple",
+    "call_new",
+    "call_data_append",
+    "stable_read",
+    "stable_write",
+    "canister_cycle_balance128",
+    "msg_cycles_available128",
+    "msg_cycles_refunded128",
+    "msg_cycles_accept128",
+    "certified_data_set",
+    "data_certificate_copy",
+    "is_controller",
+];
+
+// Returns the parameter types of the given System API function as provided
+// by the runtime to modules with a 64-bit memory: functions that access the
+// memory take all their `i32` parameters as `i64`, so that addresses above
+// 4GiB can be passed.
+pub(super) fn memory64_system_api_param_types(
+    import_module: &str,
+    field: &str,
+) -> Option<Vec<ValueType>> {
+    let param_types = system_api_param_types(import_module, field)?;
+    if import_module != API_VERSION_IC0 || !MEMORY_ACCESSING_SYSTEM_API_FUNCTIONS.contains(&field) {
+        return Some(param_types);
+    }
+    Some(
+        param_types
+            .into_iter()
+            .map(|param_type| match param_type {
+                ValueType::I32 => ValueType::I64,
+                _ => param_type,
+            })
+            .collect(),
+    )
+}
+
 // Constructs a map of function name -> `FunctionSignature` based on the
 // special user exported functions allowed in the interface spec.
 fn get_valid_exported_functions() -> HashMap<String, FunctionSignature> {
@@ -621,6 +711,13 @@ fn get_valid_exported_functions() -> HashMap<String, FunctionSignature> {
                 return_type: vec![],
             },
         ),
+        (
+            "canister_composite_query",
+            FunctionSignature {
+                param_types: vec![],
+                return_type: vec![],
+            },
+        ),
         (
             "canister_pre_upgrade",
             FunctionSignature {
@@ -649,6 +746,13 @@ fn get_valid_exported_functions() -> HashMap<String, FunctionSignature> {
                 return_type: vec![],
             },
         ),
+        (
+            "canister_global_timer",
+            FunctionSignature {
+                param_types: vec![],
+                return_type: vec![],
+            },
+        ),
     ];
 
     valid_exported_functions
@@ -685,6 +789,37 @@ fn validate_function_signature(
     Ok(())
 }
 
+// Validates the signature of an imported System API function. Modules with a
+// 64-bit memory may declare `i64` for any `i32` parameter, which allows them
+// to pass pointers and sizes without wrapping them first. The instrumentation
+// converts the arguments to the types provided by the runtime, see
+// `memory64_system_api_param_types()`.
+fn validate_system_api_signature(
+    expected_signature: &FunctionSignature,
+    field: &str,
+    function_type: &Type,
+    is_memory64: bool,
+) -> Result<(), WasmValidationError> {
+    let Type::Function(actual) = function_type;
+    if !is_memory64 || actual.params().len() != expected_signature.param_types.len() {
+        return validate_function_signature(expected_signature, field, function_type);
+    }
+    let params = actual
+        .params()
+        .iter()
+        .zip(expected_signature.param_types.iter())
+        .map(|(actual, expected)| match (actual, expected) {
+            (ValueType::I64, ValueType::I32) => ValueType::I32,
+            _ => *actual,
+        })
+        .collect();
+    validate_function_signature(
+        expected_signature,
+        field,
+        &Type::Function(FunctionType::new(params, actual.results().to_vec())),
+    )
+}
+
 fn set_imports_details(import_details: &mut WasmImportsDetails, import_module: &str, field: &str) {
     if import_module != API_VERSION_IC0 {
         return;
@@ -708,7 +843,10 @@ fn set_imports_details(import_details: &mut WasmImportsDetails, import_module: &
 //
 // Returns information about what IC0 methods are imported via
 // `WasmImportsDetails`.
-fn validate_import_section(module: &Module) -> Result<WasmImportsDetails, WasmValidationError> {
+fn validate_import_section(
+    module: &Module,
+    is_memory64: bool,
+) -> Result<WasmImportsDetails, WasmValidationError> {
     let mut imports_details = WasmImportsDetails::default();
 
     if let Some(section) = module.import_section() {
@@ -723,10 +861,11 @@ fn validate_import_section(module: &Module) -> Result<WasmImportsDetails, WasmVa
                         Some(signatures) => {
                             match signatures.get(import_module) {
                                 Some(signature) => {
-                                    validate_function_signature(
+                                    validate_system_api_signature(
                                         signature,
                                         field,
                                         &module.type_section().unwrap().types()[*index as usize],
+                                        is_memory64,
                                     )?;
                                 },
                                 None => {return Err(WasmValidationError::InvalidImportSection(format!(
@@ -770,8 +909,8 @@ fn validate_import_section(module: &Module) -> Result<WasmImportsDetails, WasmVa
 }
 
 // Performs the following checks:
-// * Validates signatures of exported canister_update and canister_query
-//   methods.
+// * Validates signatures of exported canister_update, canister_query and
+//   canister_composite_query methods.
 // * Validates the signatures of other allowed exported functions (like
 //   `canister_init` or `canister_pre_upgrade`) if present.
 // * Validates that the canister doesn't export any reserved symbols
@@ -795,8 +934,10 @@ fn validate_export_section(module: &Module) -> Result<usize, WasmValidationError
                 let mut func_name = export.field();
                 // func_name holds either:
                 // - the entire exported non-IC function names, or
-                // - canister_query or canister_update part in case of the IC functions.
+                // - canister_query, canister_composite_query or canister_update part in case
+                //   of the IC functions.
                 if func_name.starts_with("canister_query ")
+                    || func_name.starts_with("canister_composite_query ")
                     || func_name.starts_with("canister_update ")
                 {
                     let parts: Vec<&str> = func_name.splitn(2, ' ').collect();
@@ -848,20 +989,33 @@ fn validate_export_section(module: &Module) -> Result<usize, WasmValidationError
 // Checks that offset-expressions in data sections consist of only one constant
 // expression. Required because of OP. See also:
 // src/hypervisor/metering_injector/mod.rs
-fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
-    fn validate_segment(s: &DataSegment) -> Result<(), WasmValidationError> {
+//
+// Offsets into a 64-bit memory are `i64` constants.
+fn validate_data_section(module: &Module, is_memory64: bool) -> Result<(), WasmValidationError> {
+    let validate_segment = |s: &DataSegment| -> Result<(), WasmValidationError> {
         match s.offset() {
             None => Err(WasmValidationError::InvalidDataSection(
                 "Empty offset in data segment.".to_string(),
             )),
-            Some(expr) => match expr.code() {
-                [Instruction::I32Const(_), Instruction::End] => Ok(()),
+            Some(expr) => match (expr.code(), is_memory64) {
+                ([Instruction::I32Const(_), Instruction::End], false) => Ok(()),
+                ([Instruction::I64Const(offset), Instruction::End], true) => {
+                    if (0..=u32::MAX as i64).contains(offset) {
+                        Ok(())
+                    } else {
+                        Err(WasmValidationError::InvalidDataSection(format!(
+                            "Data segment offset {} is outside of the supported range [0, {}].",
+                            offset,
+                            u32::MAX
+                        )))
+                    }
+                }
                 _ => Err(WasmValidationError::InvalidDataSection(
                     "Invalid offset expression in data segment.".to_string(),
                 )),
             },
         }
-    }
+    };
 
     module
         .sections()
@@ -1105,9 +1259,13 @@ pub fn ensure_determinism(config: &mut Config) {
         .cranelift_nan_canonicalization(true);
 }
 
-fn can_compile(wasm: &BinaryEncodedWasm) -> Result<(), WasmValidationError> {
+fn can_compile(
+    wasm: &BinaryEncodedWasm,
+    embedders_config: &EmbeddersConfig,
+) -> Result<(), WasmValidationError> {
     let mut config = wasmtime::Config::default();
     ensure_determinism(&mut config);
+    config.wasm_memory64(embedders_config.feature_flags.wasm64 == FlagStatus::Enabled);
     let engine = wasmtime::Engine::new(&config).map_err(|_| {
         WasmValidationError::WasmtimeValidation(String::from("Failed to initialize Wasm engine"))
     })?;
@@ -1119,6 +1277,45 @@ fn can_compile(wasm: &BinaryEncodedWasm) -> Result<(), WasmValidationError> {
     })
 }
 
+// Checks that a module with a 64-bit memory is allowed on this subnet, that
+// its initial memory fits into the maximum heap size of such canisters and
+// that the offsets of its loads and stores fit into 32 bits.
+//
+// Returns true if the module has a 64-bit memory.
+fn validate_memory64(
+    wasm: &BinaryEncodedWasm,
+    config: &EmbeddersConfig,
+) -> Result<bool, WasmValidationError> {
+    let memories = memory64::memory_limits(wasm.as_slice()).unwrap_or_default();
+    if !memories.iter().any(|memory| memory.is_64) {
+        return Ok(false);
+    }
+    if config.feature_flags.wasm64 == FlagStatus::Disabled {
+        return Err(WasmValidationError::InvalidMemorySection(
+            "64-bit memories are not enabled on this subnet.".to_string(),
+        ));
+    }
+    let max_pages = config.max_wasm64_memory_size.get() / WASM_PAGE_SIZE_IN_BYTES as u64;
+    for memory in memories.iter() {
+        if memory.initial > max_pages {
+            return Err(WasmValidationError::InvalidMemorySection(format!(
+                "The initial memory size of {} Wasm pages exceeds the maximum of {} Wasm pages.",
+                memory.initial, max_pages
+            )));
+        }
+    }
+    if let Some(offset) = memory64::max_memarg_offset(wasm.as_slice()) {
+        if offset > u32::MAX as u64 {
+            return Err(WasmValidationError::InvalidCodeSection(format!(
+                "Memory access offset {} exceeds the maximum offset of {}.",
+                offset,
+                u32::MAX
+            )));
+        }
+    }
+    Ok(true)
+}
+
 #[derive(Debug, PartialEq, Eq, Default)]
 pub struct WasmImportsDetails {
     // True if the module imports these IC0 methods.
@@ -1150,6 +1347,7 @@ pub struct WasmValidationDetails {
 /// It constructs a module by parsing the input Wasm binary and then calls into
 /// more specific methods that validate different sections of the Wasm binary.
 /// Currently, the sections we verify are:
+/// * Memory
 /// * Import
 /// * Export
 /// * Code
@@ -1163,12 +1361,17 @@ pub(super) fn validate_wasm_binary(
     wasm: &BinaryEncodedWasm,
     config: &EmbeddersConfig,
 ) -> Result<WasmValidationDetails, WasmValidationError> {
-    can_compile(wasm)?;
-    let module = parity_wasm::deserialize_buffer::<Module>(wasm.as_slice())
-        .map_err(|err| WasmValidationError::ParityDeserializeError(into_parity_wasm_error(err)))?;
-    let imports_details = validate_import_section(&module)?;
+    let is_memory64 = validate_memory64(wasm, config)?;
+    can_compile(wasm, config)?;
+    let module = if is_memory64 {
+        parity_wasm::deserialize_buffer::<Module>(&memory64::clear_memory64_flags(wasm.as_slice()))
+    } else {
+        parity_wasm::deserialize_buffer::<Module>(wasm.as_slice())
+    }
+    .map_err(|err| WasmValidationError::ParityDeserializeError(into_parity_wasm_error(err)))?;
+    let imports_details = validate_import_section(&module, is_memory64)?;
     let reserved_exports = validate_export_section(&module)?;
-    validate_data_section(&module)?;
+    validate_data_section(&module, is_memory64)?;
     validate_global_section(&module, config.max_globals)?;
     validate_function_section(&module, config.max_functions)?;
     let largest_function_instruction_count = validate_code_section(&module)?;
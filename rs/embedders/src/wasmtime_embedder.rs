@@ -14,7 +14,7 @@ use wasmtime::{unix::StoreExt, Engine, Memory, Module, Mutability, OptLevel, Sto
 
 use host_memory::MmapMemoryCreator;
 pub use host_memory::WasmtimeMemoryCreator;
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_interfaces::execution_environment::{
     HypervisorError, HypervisorResult, InstanceStats, SystemApi, TrapCode,
 };
@@ -127,10 +127,12 @@ impl WasmtimeEmbedder {
         let mut config = wasmtime::Config::default();
         config.cranelift_opt_level(OptLevel::None);
         ensure_determinism(&mut config);
+        config.wasm_memory64(self.config.feature_flags.wasm64 == FlagStatus::Enabled);
         let raw_creator = MmapMemoryCreator {};
         let mem_creator = Arc::new(WasmtimeMemoryCreator::new(
             raw_creator,
             Arc::clone(&self.created_memories),
+            self.max_wasm64_pages(),
         ));
         config.with_host_memory(mem_creator);
 
@@ -205,11 +207,16 @@ impl WasmtimeEmbedder {
             },
         );
 
+        let is_memory64 = module.exports().any(|export| match export.ty() {
+            wasmtime::ExternType::Memory(memory) => memory.is_64(),
+            _ => false,
+        });
         let linker = system_api::syscalls(
             self.log.clone(),
             canister_id,
             &store,
             self.config.feature_flags.rate_limiting_of_debug_prints,
+            is_memory64,
         );
 
         let instance = match linker.instantiate(&mut store, module) {
@@ -361,6 +368,11 @@ impl WasmtimeEmbedder {
     pub fn config(&self) -> &EmbeddersConfig {
         &self.config
     }
+
+    /// Returns the maximum number of Wasm pages of a 64-bit memory.
+    pub fn max_wasm64_pages(&self) -> u64 {
+        self.config.max_wasm64_memory_size.get() / wasmtime_environ::WASM_PAGE_SIZE as u64
+    }
 }
 
 struct StoreRef(*mut wasmtime::Store<()>);
@@ -587,6 +599,12 @@ impl<S: SystemApi> WasmtimeInstance<S> {
         NumWasmPages::from(self.memory().map_or(0, |mem| mem.size(&self.store)) as usize)
     }
 
+    /// Returns true if the heap is a 64-bit memory.
+    pub fn is_memory64(&mut self) -> bool {
+        self.memory()
+            .map_or(false, |mem| mem.ty(&self.store).is_64())
+    }
+
     /// Returns a list of exported globals.
     pub fn get_exported_globals(&mut self) -> Vec<Global> {
         let globals: Vec<_> = self
@@ -6,7 +6,7 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::{error, ReplicaLogger};
 use ic_registry_subnet_type::SubnetType;
-use ic_types::{CanisterId, Cycles, NumBytes, NumInstructions, NumPages};
+use ic_types::{CanisterId, Cycles, NumBytes, NumInstructions, NumPages, Time};
 
 use wasmtime::{AsContextMut, Caller, Global, Linker, Store, Trap, Val};
 
@@ -116,15 +116,18 @@ fn charge_for_system_api_call<S: SystemApi>(
     canister_id: CanisterId,
     caller: &mut Caller<'_, StoreData<S>>,
     system_api_overhead: NumInstructions,
-    num_bytes: u32,
+    num_bytes: u64,
     complexity: &ExecutionComplexity,
 ) -> Result<(), Trap> {
     observe_execution_complexity(log, canister_id, caller, complexity)?;
-    let num_instructions = caller
-        .data()
-        .system_api
-        .get_num_instructions_from_bytes(NumBytes::from(num_bytes as u64))
-        + system_api_overhead;
+    let num_instructions = NumInstructions::from(
+        caller
+            .data()
+            .system_api
+            .get_num_instructions_from_bytes(NumBytes::from(num_bytes))
+            .get()
+            .saturating_add(system_api_overhead.get()),
+    );
     charge_direct_fee(log, canister_id, caller, num_instructions)
 }
 
@@ -187,8 +190,8 @@ fn charge_direct_fee<S: SystemApi>(
     }
 
     // Now we can subtract the fee and store the new instruction counter.
-    let fee = num_instructions.get() as i64;
-    instruction_counter -= fee;
+    let fee = i64::try_from(num_instructions.get()).unwrap_or(i64::MAX);
+    instruction_counter = instruction_counter.saturating_sub(fee);
     store_value(
         &num_instructions_global,
         instruction_counter,
@@ -286,77 +289,753 @@ fn ic0_performance_counter_helper<S: SystemApi>(
     }
 }
 
-pub(crate) fn syscalls<S: SystemApi>(
-    log: ReplicaLogger,
-    canister_id: CanisterId,
-    store: &Store<StoreData<S>>,
-    rate_limiting_of_debug_prints: FlagStatus,
-) -> Linker<StoreData<S>> {
-    fn with_system_api<S, T>(caller: &mut Caller<'_, StoreData<S>>, f: impl Fn(&mut S) -> T) -> T {
-        f(&mut caller.as_context_mut().data_mut().system_api)
+fn with_system_api<S, T>(caller: &mut Caller<'_, StoreData<S>>, f: impl Fn(&mut S) -> T) -> T {
+    f(&mut caller.as_context_mut().data_mut().system_api)
+}
+
+fn with_memory_and_system_api<S: SystemApi, T>(
+    mut caller: &mut Caller<'_, StoreData<S>>,
+    f: impl Fn(&mut S, &mut [u8]) -> HypervisorResult<T>,
+) -> Result<T, wasmtime::Trap> {
+    let result = caller
+        .get_export("memory")
+        .ok_or_else(|| {
+            HypervisorError::ContractViolation("WebAssembly module must define memory".to_string())
+        })
+        .and_then(|ext| {
+            ext.into_memory().ok_or_else(|| {
+                HypervisorError::ContractViolation("export 'memory' is not a memory".to_string())
+            })
+        })
+        .and_then(|mem| {
+            let mem = mem.data_mut(&mut caller);
+            let ptr = mem.as_mut_ptr();
+            let len = mem.len();
+            // SAFETY: The memory array is valid for the duration of our borrow of the
+            // `SystemApi` and the mutating the `SystemApi` cannot change the memory array
+            // so it's safe to mutate both at once.  If the memory and system_api were two
+            // fields of the `caller` struct then this would be allowed, but
+            // since we access them through opaque functions the
+            // compiler can't know that they are unrelated objects.
+            f(&mut caller.as_context_mut().data_mut().system_api, unsafe {
+                std::slice::from_raw_parts_mut(ptr, len)
+            })
+        });
+    match result {
+        Err(e) => Err(process_err(caller, e)),
+        Ok(r) => Ok(r),
     }
+}
 
-    fn with_memory_and_system_api<S: SystemApi, T>(
-        mut caller: &mut Caller<'_, StoreData<S>>,
-        f: impl Fn(&mut S, &mut [u8]) -> HypervisorResult<T>,
-    ) -> Result<T, wasmtime::Trap> {
-        let result = caller
-            .get_export("memory")
-            .ok_or_else(|| {
-                HypervisorError::ContractViolation(
-                    "WebAssembly module must define memory".to_string(),
-                )
+/// An address or a size of a buffer in the Wasm memory as passed by the
+/// module: `i32` for a 32-bit memory and `i64` for a 64-bit memory.
+trait WasmAddress: Copy {
+    fn to_u64(self) -> u64;
+}
+
+impl WasmAddress for i32 {
+    fn to_u64(self) -> u64 {
+        self as u32 as u64
+    }
+}
+
+impl WasmAddress for i64 {
+    fn to_u64(self) -> u64 {
+        self as u64
+    }
+}
+
+/// Converts an argument that is not an address in the memory, but that is
+/// passed as `i64` by modules with a 64-bit memory, to the type expected by
+/// the `SystemApi`.
+fn closure_arg(name: &str, value: impl WasmAddress) -> HypervisorResult<u32> {
+    let value = value.to_u64();
+    u32::try_from(value).map_err(|_| {
+        HypervisorError::ContractViolation(format!("{}={} does not fit into 32 bits", name, value))
+    })
+}
+
+/// Registers the System API functions that access the Wasm memory. Their
+/// `i32` parameters have the type `$ptr`: `i32` for modules with a 32-bit
+/// memory and `i64` for modules with a 64-bit memory, for which the
+/// instrumentation converts the arguments of these imports, see
+/// `validation::memory64_system_api_param_types()`.
+macro_rules! memory_syscalls {
+    ($linker:ident, $ptr:ty, $log:ident, $canister_id:ident, $rate_limiting_of_debug_prints:ident) => {
+        $linker
+            .func_wrap("ic0", "msg_caller_copy", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, dst: $ptr, offset: $ptr, size: $ptr| {
+                    observe_execution_complexity(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::MSG_CALLER_COPY,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_msg_caller_copy(
+                            dst.to_u64(),
+                            offset.to_u64(),
+                            size.to_u64(),
+                            memory,
+                        )
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "msg_arg_data_copy", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, dst: $ptr, offset: $ptr, size: $ptr| {
+                    charge_for_system_api_call(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        system_api_complexity::overhead::MSG_ARG_DATA_COPY,
+                        size.to_u64(),
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::MSG_ARG_DATA_COPY,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_msg_arg_data_copy(
+                            dst.to_u64(),
+                            offset.to_u64(),
+                            size.to_u64(),
+                            memory,
+                        )
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "msg_method_name_copy", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, dst: $ptr, offset: $ptr, size: $ptr| {
+                    charge_for_system_api_call(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        system_api_complexity::overhead::MSG_METHOD_NAME_COPY,
+                        size.to_u64(),
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::MSG_METHOD_NAME_COPY,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_msg_method_name_copy(
+                            dst.to_u64(),
+                            offset.to_u64(),
+                            size.to_u64(),
+                            memory,
+                        )
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "msg_reply_data_append", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, src: $ptr, size: $ptr| {
+                    charge_for_system_api_call(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        system_api_complexity::overhead::MSG_REPLY_DATA_APPEND,
+                        size.to_u64(),
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::MSG_REPLY_DATA_APPEND,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: size.to_u64().into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_msg_reply_data_append(src.to_u64(), size.to_u64(), memory)
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "msg_reject", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, src: $ptr, size: $ptr| {
+                    charge_for_system_api_call(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        system_api_complexity::overhead::MSG_REJECT,
+                        size.to_u64(),
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::MSG_REJECT,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: size.to_u64().into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_msg_reject(src.to_u64(), size.to_u64(), memory)
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "msg_reject_msg_copy", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, dst: $ptr, offset: $ptr, size: $ptr| {
+                    charge_for_system_api_call(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        system_api_complexity::overhead::MSG_REJECT_MSG_COPY,
+                        size.to_u64(),
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::MSG_REJECT_MSG_COPY,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_msg_reject_msg_copy(
+                            dst.to_u64(),
+                            offset.to_u64(),
+                            size.to_u64(),
+                            memory,
+                        )
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "canister_self_copy", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, dst: $ptr, offset: $ptr, size: $ptr| {
+                    observe_execution_complexity(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::CANISTER_SELF_COPY,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_canister_self_copy(
+                            dst.to_u64(),
+                            offset.to_u64(),
+                            size.to_u64(),
+                            memory,
+                        )
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "controller_copy", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, dst: $ptr, offset: $ptr, size: $ptr| {
+                    observe_execution_complexity(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::CONTROLLER_COPY,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_controller_copy(
+                            dst.to_u64(),
+                            offset.to_u64(),
+                            size.to_u64(),
+                            memory,
+                        )
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "debug_print", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                let rate_limiting_of_debug_prints = $rate_limiting_of_debug_prints;
+                move |mut caller: Caller<'_, StoreData<S>>, offset: $ptr, length: $ptr| {
+                    charge_for_system_api_call(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        system_api_complexity::overhead::DEBUG_PRINT,
+                        length.to_u64(),
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::DEBUG_PRINT,
+                            memory: length.to_u64().into(),
+                            disk: length.to_u64().into(),
+                            network: length.to_u64().into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.save_log_message(
+                            false,
+                            offset.to_u64(),
+                            length.to_u64(),
+                            memory,
+                        );
+                        Ok(())
+                    })?;
+                    match (
+                        caller.data().system_api.subnet_type(),
+                        rate_limiting_of_debug_prints,
+                    ) {
+                        // Debug print is a no-op on non-system subnets with rate limiting.
+                        (SubnetType::Application, FlagStatus::Enabled) => Ok(()),
+                        (SubnetType::VerifiedApplication, FlagStatus::Enabled) => Ok(()),
+                        // If rate limiting is disabled or the subnet is a system subnet, then
+                        // debug print produces output.
+                        (_, FlagStatus::Disabled) | (SubnetType::System, FlagStatus::Enabled) => {
+                            with_memory_and_system_api(&mut caller, |system_api, memory| {
+                                system_api.ic0_debug_print(offset.to_u64(), length.to_u64(), memory)
+                            })
+                        }
+                    }
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "trap", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>,
+                      offset: $ptr,
+                      length: $ptr|
+                      -> Result<(), _> {
+                    charge_for_system_api_call(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        system_api_complexity::overhead::TRAP,
+                        length.to_u64(),
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::TRAP,
+                            memory: length.to_u64().into(),
+                            disk: length.to_u64().into(),
+                            network: length.to_u64().into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.save_log_message(true, offset.to_u64(), length.to_u64(), memory);
+                        system_api.ic0_trap(offset.to_u64(), length.to_u64(), memory)
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "call_simple", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>,
+                      callee_src: $ptr,
+                      callee_size: $ptr,
+                      name_src: $ptr,
+                      name_len: $ptr,
+                      reply_fun: $ptr,
+                      reply_env: $ptr,
+                      reject_fun: $ptr,
+                      reject_env: $ptr,
+                      src: $ptr,
+                      len: $ptr| {
+                    let total_len = callee_size
+                        .to_u64()
+                        .saturating_add(name_len.to_u64())
+                        .saturating_add(len.to_u64());
+                    charge_for_system_api_call(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        system_api_complexity::overhead::CALL_SIMPLE,
+                        len.to_u64(),
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::CALL_SIMPLE,
+                            memory: total_len.into(),
+                            disk: 0.into(),
+                            network: total_len.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_call_simple(
+                            callee_src.to_u64(),
+                            callee_size.to_u64(),
+                            name_src.to_u64(),
+                            name_len.to_u64(),
+                            closure_arg("reply_fun", reply_fun)?,
+                            closure_arg("reply_env", reply_env)?,
+                            closure_arg("reject_fun", reject_fun)?,
+                            closure_arg("reject_env", reject_env)?,
+                            src.to_u64(),
+                            len.to_u64(),
+                            memory,
+                        )
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "call_new", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>,
+                      callee_src: $ptr,
+                      callee_size: $ptr,
+                      name_src: $ptr,
+                      name_len: $ptr,
+                      reply_fun: $ptr,
+                      reply_env: $ptr,
+                      reject_fun: $ptr,
+                      reject_env: $ptr| {
+                    let total_len = callee_size.to_u64().saturating_add(name_len.to_u64());
+                    observe_execution_complexity(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::CALL_NEW,
+                            memory: total_len.into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_call_new(
+                            callee_src.to_u64(),
+                            callee_size.to_u64(),
+                            name_src.to_u64(),
+                            name_len.to_u64(),
+                            closure_arg("reply_fun", reply_fun)?,
+                            closure_arg("reply_env", reply_env)?,
+                            closure_arg("reject_fun", reject_fun)?,
+                            closure_arg("reject_env", reject_env)?,
+                            memory,
+                        )
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "call_data_append", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, src: $ptr, size: $ptr| {
+                    charge_for_system_api_call(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        system_api_complexity::overhead::CALL_DATA_APPEND,
+                        size.to_u64(),
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::CALL_DATA_APPEND,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: size.to_u64().into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_call_data_append(src.to_u64(), size.to_u64(), memory)
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "stable_read", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, dst: $ptr, offset: $ptr, size: $ptr| {
+                    charge_for_system_api_call(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        system_api_complexity::overhead::STABLE_READ,
+                        size.to_u64(),
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::STABLE_READ,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_stable_read(
+                            dst.to_u64(),
+                            offset.to_u64(),
+                            size.to_u64(),
+                            memory,
+                        )
+                    })
+                }
             })
-            .and_then(|ext| {
-                ext.into_memory().ok_or_else(|| {
-                    HypervisorError::ContractViolation(
-                        "export 'memory' is not a memory".to_string(),
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "stable_write", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, offset: $ptr, src: $ptr, size: $ptr| {
+                    charge_for_system_api_call(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        system_api_complexity::overhead::STABLE_WRITE,
+                        size.to_u64(),
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::STABLE_WRITE,
+                            memory: size.to_u64().into(),
+                            disk: size.to_u64().into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    let dirty_pages =
+                        with_memory_and_system_api(&mut caller, |system_api, memory| {
+                            system_api.ic0_stable_write(
+                                offset.to_u64(),
+                                src.to_u64(),
+                                size.to_u64(),
+                                memory,
+                            )
+                        })?;
+                    charge_for_stable_memory_dirty_pages(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        dirty_pages,
                     )
-                })
+                }
             })
-            .and_then(|mem| {
-                let mem = mem.data_mut(&mut caller);
-                let ptr = mem.as_mut_ptr();
-                let len = mem.len();
-                // SAFETY: The memory array is valid for the duration of our borrow of the
-                // `SystemApi` and the mutating the `SystemApi` cannot change the memory array
-                // so it's safe to mutate both at once.  If the memory and system_api were two
-                // fields of the `caller` struct then this would be allowed, but
-                // since we access them through opaque functions the
-                // compiler can't know that they are unrelated objects.
-                f(&mut caller.as_context_mut().data_mut().system_api, unsafe {
-                    std::slice::from_raw_parts_mut(ptr, len)
-                })
-            });
-        match result {
-            Err(e) => Err(process_err(caller, e)),
-            Ok(r) => Ok(r),
-        }
-    }
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "canister_cycle_balance128", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, dst: $ptr| {
+                    observe_execution_complexity(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::CANISTER_CYCLES_BALANCE128,
+                            memory: (std::mem::size_of::<u64>() as u64).into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_canister_cycles_balance128(dst.to_u64(), memory)
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "msg_cycles_available128", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, dst: $ptr| {
+                    observe_execution_complexity(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::MSG_CYCLES_AVAILABLE128,
+                            memory: (std::mem::size_of::<u64>() as u64).into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_msg_cycles_available128(dst.to_u64(), memory)
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "msg_cycles_refunded128", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, dst: $ptr| {
+                    observe_execution_complexity(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::MSG_CYCLES_REFUNDED128,
+                            memory: (std::mem::size_of::<u64>() as u64).into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_msg_cycles_refunded128(dst.to_u64(), memory)
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "msg_cycles_accept128", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>,
+                      amount_high: i64,
+                      amount_low: i64,
+                      dst: $ptr| {
+                    observe_execution_complexity(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::MSG_CYCLES_ACCEPT128,
+                            memory: (std::mem::size_of::<u64>() as u64).into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_msg_cycles_accept128(
+                            Cycles::from_parts(amount_high as u64, amount_low as u64),
+                            dst.to_u64(),
+                            memory,
+                        )
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "certified_data_set", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, src: $ptr, size: $ptr| {
+                    observe_execution_complexity(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::CERTIFIED_DATA_SET,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_certified_data_set(src.to_u64(), size.to_u64(), memory)
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "data_certificate_copy", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, dst: $ptr, offset: $ptr, size: $ptr| {
+                    observe_execution_complexity(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::DATA_CERTIFICATE_COPY,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_data_certificate_copy(
+                            dst.to_u64(),
+                            offset.to_u64(),
+                            size.to_u64(),
+                            memory,
+                        )
+                    })
+                }
+            })
+            .unwrap();
+
+        $linker
+            .func_wrap("ic0", "is_controller", {
+                let log = $log.clone();
+                let canister_id = $canister_id;
+                move |mut caller: Caller<'_, StoreData<S>>, src: $ptr, size: $ptr| {
+                    observe_execution_complexity(
+                        &log,
+                        canister_id,
+                        &mut caller,
+                        &ExecutionComplexity {
+                            cpu: system_api_complexity::cpu::IS_CONTROLLER,
+                            memory: size.to_u64().into(),
+                            disk: 0.into(),
+                            network: 0.into(),
+                        },
+                    )?;
+                    with_memory_and_system_api(&mut caller, |system_api, memory| {
+                        system_api.ic0_is_controller(src.to_u64(), size.to_u64(), memory)
+                    })
+                }
+            })
+            .unwrap();
+    };
+}
 
+pub(crate) fn syscalls<S: SystemApi>(
+    log: ReplicaLogger,
+    canister_id: CanisterId,
+    store: &Store<StoreData<S>>,
+    rate_limiting_of_debug_prints: FlagStatus,
+    is_memory64: bool,
+) -> Linker<StoreData<S>> {
     let mut linker = Linker::new(store.engine());
 
-    linker
-        .func_wrap("ic0", "msg_caller_copy", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
-                observe_execution_complexity(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::MSG_CALLER_COPY,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_caller_copy(dst as u32, offset as u32, size as u32, memory)
-                })
-            }
-        })
-        .unwrap();
+    if is_memory64 {
+        memory_syscalls!(linker, i64, log, canister_id, rate_limiting_of_debug_prints);
+    } else {
+        memory_syscalls!(linker, i32, log, canister_id, rate_limiting_of_debug_prints);
+    }
 
     linker
         .func_wrap("ic0", "msg_caller_size", {
@@ -386,30 +1065,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "msg_arg_data_copy", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
-                charge_for_system_api_call(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    system_api_complexity::overhead::MSG_ARG_DATA_COPY,
-                    size as u32,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::MSG_ARG_DATA_COPY,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, mem| {
-                    system_api.ic0_msg_arg_data_copy(dst as u32, offset as u32, size as u32, mem)
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "msg_method_name_size", {
             move |mut caller: Caller<'_, StoreData<S>>| {
@@ -424,35 +1079,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "msg_method_name_copy", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
-                charge_for_system_api_call(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    system_api_complexity::overhead::MSG_METHOD_NAME_COPY,
-                    size as u32,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::MSG_METHOD_NAME_COPY,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_method_name_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
-                        memory,
-                    )
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "accept_message", {
             move |mut caller: Caller<'_, StoreData<S>>| {
@@ -462,30 +1088,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "msg_reply_data_append", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
-                charge_for_system_api_call(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    system_api_complexity::overhead::MSG_REPLY_DATA_APPEND,
-                    size as u32,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::MSG_REPLY_DATA_APPEND,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: (size as u64).into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_reply_data_append(src as u32, size as u32, memory)
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "msg_reply", {
             move |mut caller: Caller<'_, StoreData<S>>| {
@@ -504,30 +1106,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "msg_reject", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
-                charge_for_system_api_call(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    system_api_complexity::overhead::MSG_REJECT,
-                    size as u32,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::MSG_REJECT,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: (size as u64).into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_reject(src as u32, size as u32, memory)
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "msg_reject_msg_size", {
             move |mut caller: Caller<'_, StoreData<S>>| {
@@ -542,35 +1120,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "msg_reject_msg_copy", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
-                charge_for_system_api_call(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    system_api_complexity::overhead::MSG_REJECT_MSG_COPY,
-                    size as u32,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::MSG_REJECT_MSG_COPY,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_reject_msg_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
-                        memory,
-                    )
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "canister_self_size", {
             move |mut caller: Caller<'_, StoreData<S>>| {
@@ -585,33 +1134,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "canister_self_copy", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
-                observe_execution_complexity(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::CANISTER_SELF_COPY,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_canister_self_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
-                        memory,
-                    )
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "controller_size", {
             move |mut caller: Caller<'_, StoreData<S>>| {
@@ -626,200 +1148,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "controller_copy", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
-                observe_execution_complexity(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::CONTROLLER_COPY,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_controller_copy(dst as u32, offset as u32, size as u32, memory)
-                })
-            }
-        })
-        .unwrap();
-
-    linker
-        .func_wrap("ic0", "debug_print", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, offset: i32, length: i32| {
-                charge_for_system_api_call(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    system_api_complexity::overhead::DEBUG_PRINT,
-                    length as u32,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::DEBUG_PRINT,
-                        memory: (length as u64).into(),
-                        disk: (length as u64).into(),
-                        network: (length as u64).into(),
-                    },
-                )?;
-                match (
-                    caller.data().system_api.subnet_type(),
-                    rate_limiting_of_debug_prints,
-                ) {
-                    // Debug print is a no-op on non-system subnets with rate limiting.
-                    (SubnetType::Application, FlagStatus::Enabled) => Ok(()),
-                    (SubnetType::VerifiedApplication, FlagStatus::Enabled) => Ok(()),
-                    // If rate limiting is disabled or the subnet is a system subnet, then
-                    // debug print produces output.
-                    (_, FlagStatus::Disabled) | (SubnetType::System, FlagStatus::Enabled) => {
-                        with_memory_and_system_api(&mut caller, |system_api, memory| {
-                            system_api.ic0_debug_print(offset as u32, length as u32, memory)
-                        })
-                    }
-                }
-            }
-        })
-        .unwrap();
-
-    linker
-        .func_wrap("ic0", "trap", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, offset: i32, length: i32| -> Result<(), _> {
-                charge_for_system_api_call(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    system_api_complexity::overhead::TRAP,
-                    length as u32,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::TRAP,
-                        memory: (length as u64).into(),
-                        disk: (length as u64).into(),
-                        network: (length as u64).into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_trap(offset as u32, length as u32, memory)
-                })
-            }
-        })
-        .unwrap();
-
-    linker
-        .func_wrap("ic0", "call_simple", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>,
-                  callee_src: i32,
-                  callee_size: i32,
-                  name_src: i32,
-                  name_len: i32,
-                  reply_fun: i32,
-                  reply_env: i32,
-                  reject_fun: i32,
-                  reject_env: i32,
-                  src: i32,
-                  len: i32| {
-                let total_len = callee_size as u64 + name_len as u64 + len as u64;
-                charge_for_system_api_call(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    system_api_complexity::overhead::CALL_SIMPLE,
-                    len as u32,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::CALL_SIMPLE,
-                        memory: (total_len as u64).into(),
-                        disk: 0.into(),
-                        network: (total_len as u64).into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_call_simple(
-                        callee_src as u32,
-                        callee_size as u32,
-                        name_src as u32,
-                        name_len as u32,
-                        reply_fun as u32,
-                        reply_env as u32,
-                        reject_fun as u32,
-                        reject_env as u32,
-                        src as u32,
-                        len as u32,
-                        memory,
-                    )
-                })
-            }
-        })
-        .unwrap();
-
-    linker
-        .func_wrap("ic0", "call_new", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>,
-                  callee_src: i32,
-                  callee_size: i32,
-                  name_src: i32,
-                  name_len: i32,
-                  reply_fun: i32,
-                  reply_env: i32,
-                  reject_fun: i32,
-                  reject_env: i32| {
-                let total_len = callee_size as u64 + name_len as u64;
-                observe_execution_complexity(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::CALL_NEW,
-                        memory: (total_len).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_call_new(
-                        callee_src as u32,
-                        callee_size as u32,
-                        name_src as u32,
-                        name_len as u32,
-                        reply_fun as u32,
-                        reply_env as u32,
-                        reject_fun as u32,
-                        reject_env as u32,
-                        memory,
-                    )
-                })
-            }
-        })
-        .unwrap();
-
-    linker
-        .func_wrap("ic0", "call_data_append", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
-                charge_for_system_api_call(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    system_api_complexity::overhead::CALL_DATA_APPEND,
-                    size as u32,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::CALL_DATA_APPEND,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: (size as u64).into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_call_data_append(src as u32, size as u32, memory)
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "call_on_cleanup", {
             move |mut caller: Caller<'_, StoreData<S>>, fun: i32, env: i32| {
@@ -910,55 +1238,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "stable_read", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
-                charge_for_system_api_call(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    system_api_complexity::overhead::STABLE_READ,
-                    size as u32,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::STABLE_READ,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_stable_read(dst as u32, offset as u32, size as u32, memory)
-                })
-            }
-        })
-        .unwrap();
-
-    linker
-        .func_wrap("ic0", "stable_write", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, offset: i32, src: i32, size: i32| {
-                charge_for_system_api_call(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    system_api_complexity::overhead::STABLE_WRITE,
-                    size as u32,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::STABLE_WRITE,
-                        memory: (size as u64).into(),
-                        disk: (size as u64).into(),
-                        network: 0.into(),
-                    },
-                )?;
-                let dirty_pages = with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_stable_write(offset as u32, src as u32, size as u32, memory)
-                })?;
-                charge_for_stable_memory_dirty_pages(&log, canister_id, &mut caller, dirty_pages)
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "stable64_size", {
             move |mut caller: Caller<'_, StoreData<S>>| {
@@ -1005,7 +1284,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::STABLE64_READ,
-                    size as u32,
+                    size as u64,
                     &ExecutionComplexity {
                         cpu: system_api_complexity::cpu::STABLE64_READ,
                         memory: (size as u64).into(),
@@ -1029,7 +1308,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::STABLE64_WRITE,
-                    size as u32,
+                    size as u64,
                     &ExecutionComplexity {
                         cpu: system_api_complexity::cpu::STABLE64_WRITE,
                         memory: (size as u64).into(),
@@ -1091,28 +1370,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "canister_cycle_balance128", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32| {
-                observe_execution_complexity(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::CANISTER_CYCLES_BALANCE128,
-                        memory: (std::mem::size_of::<u64>() as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_canister_cycles_balance128(dst, memory)
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "msg_cycles_available", {
             move |mut caller: Caller<'_, StoreData<S>>| {
@@ -1127,28 +1384,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "msg_cycles_available128", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32| {
-                observe_execution_complexity(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::MSG_CYCLES_AVAILABLE128,
-                        memory: (std::mem::size_of::<u64>() as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_cycles_available128(dst, memory)
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "msg_cycles_refunded", {
             move |mut caller: Caller<'_, StoreData<S>>| {
@@ -1163,28 +1398,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "msg_cycles_refunded128", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32| {
-                observe_execution_complexity(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::MSG_CYCLES_REFUNDED128,
-                        memory: (std::mem::size_of::<u64>() as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_cycles_refunded128(dst, memory)
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "msg_cycles_accept", {
             move |mut caller: Caller<'_, StoreData<S>>, amount: i64| {
@@ -1194,35 +1407,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "msg_cycles_accept128", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>,
-                  amount_high: i64,
-                  amount_low: i64,
-                  dst: u32| {
-                observe_execution_complexity(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::MSG_CYCLES_ACCEPT128,
-                        memory: (std::mem::size_of::<u64>() as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_msg_cycles_accept128(
-                        Cycles::from_parts(amount_high as u64, amount_low as u64),
-                        dst,
-                        memory,
-                    )
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("__", "out_of_instructions", {
             let log = log.clone();
@@ -1251,32 +1435,33 @@ pub(crate) fn syscalls<S: SystemApi>(
         .unwrap();
 
     linker
-        .func_wrap("ic0", "canister_status", {
-            move |mut caller: Caller<'_, StoreData<S>>| {
-                with_system_api(&mut caller, |s| s.ic0_canister_status())
-                    .map_err(|e| process_err(&mut caller, e))
+        .func_wrap("__", "update_available_memory_64", {
+            move |mut caller: Caller<'_, StoreData<S>>,
+                  native_memory_grow_res: i64,
+                  additional_pages: i64| {
+                with_system_api(&mut caller, |s| {
+                    if native_memory_grow_res == -1 {
+                        return Ok(-1);
+                    }
+                    // The size of a 64-bit memory is bounded by the maximum
+                    // configured for the subnet, so page counts fit into 32 bits.
+                    let native_memory_grow_res = i32::try_from(native_memory_grow_res)
+                        .map_err(|_| HypervisorError::OutOfMemory)?;
+                    let additional_pages = u32::try_from(additional_pages)
+                        .map_err(|_| HypervisorError::OutOfMemory)?;
+                    s.update_available_memory(native_memory_grow_res, additional_pages)
+                        .map(i64::from)
+                })
+                .map_err(|e| process_err(&mut caller, e))
             }
         })
         .unwrap();
 
     linker
-        .func_wrap("ic0", "certified_data_set", {
-            let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: u32, size: u32| {
-                observe_execution_complexity(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::CERTIFIED_DATA_SET,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_certified_data_set(src, size, memory)
-                })
+        .func_wrap("ic0", "canister_status", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                with_system_api(&mut caller, |s| s.ic0_canister_status())
+                    .map_err(|e| process_err(&mut caller, e))
             }
         })
         .unwrap();
@@ -1299,27 +1484,6 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
-    linker
-        .func_wrap("ic0", "data_certificate_copy", {
-            move |mut caller: Caller<'_, StoreData<S>>, dst: u32, offset: u32, size: u32| {
-                observe_execution_complexity(
-                    &log,
-                    canister_id,
-                    &mut caller,
-                    &ExecutionComplexity {
-                        cpu: system_api_complexity::cpu::DATA_CERTIFICATE_COPY,
-                        memory: (size as u64).into(),
-                        disk: 0.into(),
-                        network: 0.into(),
-                    },
-                )?;
-                with_memory_and_system_api(&mut caller, |system_api, memory| {
-                    system_api.ic0_data_certificate_copy(dst, offset, size, memory)
-                })
-            }
-        })
-        .unwrap();
-
     linker
         .func_wrap("ic0", "mint_cycles", {
             move |mut caller: Caller<'_, StoreData<S>>, amount: i64| {
@@ -1334,5 +1498,26 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "global_timer_set", {
+            move |mut caller: Caller<'_, StoreData<S>>, time: i64| {
+                with_system_api(&mut caller, |s| {
+                    s.ic0_global_timer_set(Time::from_nanos_since_unix_epoch(time as u64))
+                })
+                .map_err(|e| process_err(&mut caller, e))
+                .map(|s| s.as_nanos_since_unix_epoch())
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "canister_version", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                with_system_api(&mut caller, |s| s.ic0_canister_version())
+                    .map_err(|e| process_err(&mut caller, e))
+            }
+        })
+        .unwrap();
+
     linker
 }
@@ -51,6 +51,7 @@ fn test_wasmtime_system_api() {
                 MAX_NUM_INSTRUCTIONS,
             ),
             canister_memory_limit,
+            wasm_memory_limit: None,
             compute_allocation: ComputeAllocation::default(),
             subnet_type: SubnetType::Application,
             execution_mode: ExecutionMode::Replicated,
@@ -92,7 +93,13 @@ fn test_wasmtime_system_api() {
     let module = Module::new(&engine, instrumentation_output.binary.as_slice())
         .expect("failed to instantiate module");
 
-    let linker = system_api::syscalls(no_op_logger(), canister_id, &store, FlagStatus::Enabled);
+    let linker = system_api::syscalls(
+        no_op_logger(),
+        canister_id,
+        &store,
+        FlagStatus::Enabled,
+        false,
+    );
     let instance = linker
         .instantiate(&mut store, &module)
         .expect("failed to instantiate instance");
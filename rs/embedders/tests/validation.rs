@@ -1,5 +1,5 @@
 use assert_matches::assert_matches;
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_embedders::{
     wasm_utils::{
         validate_and_instrument_for_testing,
@@ -12,6 +12,7 @@ use ic_embedders::{
 };
 use ic_interfaces::execution_environment::HypervisorError;
 use ic_logger::replica_logger::no_op_logger;
+use ic_test_utilities::wasmtime_instance::wat2wasm_memory64;
 use ic_wasm_types::{BinaryEncodedWasm, WasmValidationError};
 
 fn wat2wasm(wat: &str) -> Result<BinaryEncodedWasm, wabt::Error> {
@@ -269,6 +270,52 @@ fn can_validate_duplicate_method_for_canister_query_and_canister_update() {
     );
 }
 
+#[test]
+fn can_validate_valid_canister_composite_query() {
+    let wasm = wat2wasm(
+        r#"(module
+                    (func $x)
+                    (export "canister_composite_query read" (func $x)))"#,
+    )
+    .unwrap();
+    assert_eq!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Ok(WasmValidationDetails {
+            largest_function_instruction_count: NumInstructions::new(1),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn can_validate_invalid_canister_composite_query() {
+    let wasm = wat2wasm(
+        r#"(module
+                    (func $read (param i64 i32) (result i32) (local.get 1))
+                    (export "canister_composite_query read" (func $read)))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidFunctionSignature(_))
+    );
+}
+
+#[test]
+fn can_validate_duplicate_method_for_canister_query_and_canister_composite_query() {
+    let wasm = wat2wasm(
+        r#"(module
+                    (func $x)
+                    (export "canister_query read" (func $x))
+                    (export "canister_composite_query read" (func $x)))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidExportSection(_))
+    );
+}
+
 #[test]
 fn can_validate_canister_query_update_method_name_with_whitespace() {
     let wasm = wat2wasm(
@@ -791,3 +838,121 @@ fn complex_function_rejected() {
         })
     )
 }
+
+fn memory64_config() -> EmbeddersConfig {
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.wasm64 = FlagStatus::Enabled;
+    config
+}
+
+#[test]
+fn memory64_is_rejected_when_disabled() {
+    let wasm = BinaryEncodedWasm::new(wat2wasm_memory64(r#"(module (memory 1))"#));
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidMemorySection(_))
+    );
+}
+
+#[test]
+fn can_validate_memory64_when_enabled() {
+    let wasm = BinaryEncodedWasm::new(wat2wasm_memory64(
+        r#"(module
+            (memory 1)
+            (data (i64.const 16) "abc"))"#,
+    ));
+    assert_eq!(
+        validate_wasm_binary(&wasm, &memory64_config()),
+        Ok(WasmValidationDetails::default())
+    );
+}
+
+#[test]
+fn memory64_initial_size_above_limit_is_rejected() {
+    let mut config = memory64_config();
+    config.max_wasm64_memory_size = NumBytes::new(2 * 64 * 1024);
+    let wasm = BinaryEncodedWasm::new(wat2wasm_memory64(r#"(module (memory 3))"#));
+    assert_matches!(
+        validate_wasm_binary(&wasm, &config),
+        Err(WasmValidationError::InvalidMemorySection(_))
+    );
+}
+
+#[test]
+fn memory64_allows_i64_system_api_arguments() {
+    let wat = r#"(module
+        (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i64 i64)))
+        (memory 1))"#;
+    assert_eq!(
+        validate_wasm_binary(
+            &BinaryEncodedWasm::new(wat2wasm_memory64(wat)),
+            &memory64_config()
+        ),
+        Ok(WasmValidationDetails::default())
+    );
+    // The same module with a 32-bit memory must use `i32` arguments.
+    assert_matches!(
+        validate_wasm_binary(&wat2wasm(wat).unwrap(), &memory64_config()),
+        Err(WasmValidationError::InvalidFunctionSignature(_))
+    );
+}
+
+// Returns a module with a 64-bit memory and a function that loads from the
+// given offset. The module is assembled by hand because `wabt` does not
+// support offsets of 4GiB or more.
+fn memory64_load_module(offset: u64) -> BinaryEncodedWasm {
+    // i64.const 0; i64.load align=8 offset=<offset>; drop; end
+    let mut body = vec![0x00, 0x42, 0x00, 0x29, 0x03];
+    let mut value = offset;
+    while value >= 0x80 {
+        body.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    body.push(value as u8);
+    body.extend([0x1a, 0x0b]);
+
+    let mut wasm = vec![
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // Magic number and version.
+        0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // Type section: [] -> [].
+        0x03, 0x02, 0x01, 0x00, // Function section.
+        0x05, 0x03, 0x01, 0x04, 0x01, // Memory section: a 64-bit memory of 1 page.
+    ];
+    wasm.extend([0x0a, body.len() as u8 + 2, 0x01, body.len() as u8]);
+    wasm.extend(body);
+    BinaryEncodedWasm::new(wasm)
+}
+
+#[test]
+fn memory64_load_offset_of_4gib_is_rejected() {
+    assert_matches!(
+        validate_wasm_binary(&memory64_load_module(1 << 32), &memory64_config()),
+        Err(WasmValidationError::InvalidCodeSection(_))
+    );
+}
+
+#[test]
+fn memory64_load_offset_below_4gib_survives_instrumentation() {
+    let wasm = memory64_load_module(u32::MAX as u64);
+    let embedder = WasmtimeEmbedder::new(memory64_config(), no_op_logger());
+    let (_, output) = validate_and_instrument_for_testing(&embedder, &wasm).unwrap();
+    // i64.load align=8 offset=4294967295
+    let load = [0x29, 0x03, 0xff, 0xff, 0xff, 0xff, 0x0f];
+    assert!(output
+        .binary
+        .as_slice()
+        .windows(load.len())
+        .any(|window| window == load));
+}
+
+#[test]
+fn memory64_data_segment_offset_of_4gib_is_rejected() {
+    let wasm = BinaryEncodedWasm::new(wat2wasm_memory64(
+        r#"(module
+            (memory 1)
+            (data (i64.const 4294967296) "abc"))"#,
+    ));
+    assert_matches!(
+        validate_wasm_binary(&wasm, &memory64_config()),
+        Err(WasmValidationError::InvalidDataSection(_))
+    );
+}
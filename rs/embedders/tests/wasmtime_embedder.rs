@@ -11,9 +11,14 @@ use ic_types::{
 
 #[cfg(test)]
 mod test {
+    use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
+    use ic_embedders::wasmtime_embedder::WasmtimeInstance;
     use ic_interfaces::execution_environment::HypervisorError;
     use ic_registry_subnet_type::SubnetType;
-    use ic_test_utilities::wasmtime_instance::DEFAULT_NUM_INSTRUCTIONS;
+    use ic_replicated_state::{NumWasmPages, PageIndex};
+    use ic_sys::PAGE_SIZE;
+    use ic_system_api::SystemApiImpl;
+    use ic_test_utilities::wasmtime_instance::{wat2wasm_memory64, DEFAULT_NUM_INSTRUCTIONS};
     use ic_types::{methods::WasmClosure, PrincipalId};
 
     use super::*;
@@ -614,4 +619,122 @@ mod test {
             HypervisorError::CalledTrap(std::str::from_utf8(&[0; 6]).unwrap().to_string())
         );
     }
+
+    const MEMORY64_WAT: &str = r#"
+        (module
+            (import "ic0" "trap" (func $ic_trap (param i64 i64)))
+            (import "ic0" "trap" (func $ic_trap_i32 (param i32 i32)))
+            (memory (export "memory") 65536)
+            (func (export "canister_update write_above_4gib")
+                (drop (memory.grow (i64.const 1)))
+                (i64.store (i64.const 4294967296) (i64.const 42))
+            )
+            (func (export "canister_update trap_with_low_pointer")
+                ;; "hello"
+                (i64.store (i64.const 0) (i64.const 0x6f6c6c6568))
+                (call $ic_trap (i64.const 0) (i64.const 5))
+            )
+            (func (export "canister_update trap_with_high_pointer")
+                (drop (memory.grow (i64.const 1)))
+                ;; "hello"
+                (i64.store (i64.const 4294967296) (i64.const 0x6f6c6c6568))
+                (call $ic_trap (i64.const 4294967296) (i64.const 5))
+            )
+            (func (export "canister_update trap_with_pointer_out_of_bounds")
+                (call $ic_trap (i64.const 4294967296) (i64.const 5))
+            )
+            (func (export "canister_update trap_with_message_across_4gib")
+                (drop (memory.grow (i64.const 1)))
+                ;; "hello"
+                (i64.store (i64.const 4294967294) (i64.const 0x6f6c6c6568))
+                (call $ic_trap (i64.const 4294967294) (i64.const 5))
+            )
+            (func (export "canister_update trap_with_i32_arguments")
+                ;; "hello"
+                (i64.store (i64.const 0) (i64.const 0x6f6c6c6568))
+                (call $ic_trap_i32 (i32.const 0) (i32.const 5))
+            )
+        )"#;
+
+    fn memory64_instance() -> WasmtimeInstance<SystemApiImpl> {
+        let mut config = EmbeddersConfig::default();
+        config.feature_flags.wasm64 = FlagStatus::Enabled;
+        WasmtimeInstanceBuilder::new()
+            .with_wasm(wat2wasm_memory64(MEMORY64_WAT))
+            .with_config(config)
+            .build()
+    }
+
+    #[test]
+    fn memory64_tracks_dirty_pages_above_4gib() {
+        let mut instance = memory64_instance();
+        let result = instance
+            .run(FuncRef::Method(WasmMethod::Update(
+                "write_above_4gib".to_string(),
+            )))
+            .unwrap();
+        assert_eq!(instance.heap_size(), NumWasmPages::from(65537));
+        assert!(instance.is_memory64());
+        assert!(result
+            .dirty_pages
+            .contains(&PageIndex::new((4 << 30) / PAGE_SIZE as u64)));
+    }
+
+    #[test]
+    fn memory64_accepts_i64_system_api_pointers() {
+        let mut instance = memory64_instance();
+        let err = instance
+            .run(FuncRef::Method(WasmMethod::Update(
+                "trap_with_low_pointer".to_string(),
+            )))
+            .unwrap_err();
+        assert_eq!(err, HypervisorError::CalledTrap("hello".to_string()));
+    }
+
+    #[test]
+    fn memory64_accepts_system_api_pointers_above_4gib() {
+        let mut instance = memory64_instance();
+        let err = instance
+            .run(FuncRef::Method(WasmMethod::Update(
+                "trap_with_high_pointer".to_string(),
+            )))
+            .unwrap_err();
+        assert_eq!(err, HypervisorError::CalledTrap("hello".to_string()));
+    }
+
+    #[test]
+    fn memory64_rejects_system_api_pointers_out_of_bounds() {
+        let mut instance = memory64_instance();
+        let err = instance
+            .run(FuncRef::Method(WasmMethod::Update(
+                "trap_with_pointer_out_of_bounds".to_string(),
+            )))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            HypervisorError::CalledTrap("(trap message out of memory bounds)".to_string())
+        );
+    }
+
+    #[test]
+    fn memory64_accepts_system_api_buffers_across_4gib() {
+        let mut instance = memory64_instance();
+        let err = instance
+            .run(FuncRef::Method(WasmMethod::Update(
+                "trap_with_message_across_4gib".to_string(),
+            )))
+            .unwrap_err();
+        assert_eq!(err, HypervisorError::CalledTrap("hello".to_string()));
+    }
+
+    #[test]
+    fn memory64_accepts_i32_system_api_arguments() {
+        let mut instance = memory64_instance();
+        let err = instance
+            .run(FuncRef::Method(WasmMethod::Update(
+                "trap_with_i32_arguments".to_string(),
+            )))
+            .unwrap_err();
+        assert_eq!(err, HypervisorError::CalledTrap("hello".to_string()));
+    }
 }
@@ -73,6 +73,7 @@ fn test_api_for_update(
                 instruction_limit,
             ),
             canister_memory_limit,
+            wasm_memory_limit: None,
             compute_allocation: ComputeAllocation::default(),
             subnet_type: SubnetType::Application,
             execution_mode: ExecutionMode::Replicated,
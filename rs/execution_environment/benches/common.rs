@@ -142,6 +142,7 @@ where
             MAX_NUM_INSTRUCTIONS,
         ),
         canister_memory_limit: canister_state.memory_limit(NumBytes::new(std::u64::MAX)),
+        wasm_memory_limit: None,
         compute_allocation: canister_state.scheduler_state.compute_allocation,
         subnet_type: hypervisor.subnet_type(),
         execution_mode: ExecutionMode::Replicated,
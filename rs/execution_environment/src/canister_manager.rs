@@ -1,5 +1,6 @@
 use crate::execution::install_code::{
-    validate_compute_allocation, validate_controller, validate_memory_allocation, OriginalContext,
+    canister_layout, validate_compute_allocation, validate_controller, validate_memory_allocation,
+    OriginalContext,
 };
 use crate::execution::{install::execute_install, upgrade::execute_upgrade};
 use crate::execution_environment::{CompilationCostHandling, RoundContext, RoundLimits};
@@ -14,8 +15,9 @@ use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterInstallMode, CanisterStatusResultV2, CanisterStatusType, InstallCodeArgs,
-    Method as Ic00Method,
+    CanisterInfoResponse, CanisterInstallMode, CanisterSnapshotResponse, CanisterStatusResultV2,
+    CanisterStatusType, FetchCanisterLogsResponse, InstallCodeArgs, ListCanisterSnapshotsResponse,
+    LogVisibility, Method as Ic00Method, QueryStatsResponse,
 };
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, HypervisorError, IngressHistoryWriter, SubnetAvailableMemory,
@@ -25,8 +27,9 @@ use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    CallOrigin, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState, SchedulerState,
-    SystemState,
+    CallOrigin, CanisterSnapshot, CanisterState, CanisterStatus, CanisterTimer, Memory,
+    NetworkTopology, ReplicatedState, SchedulerState, SnapshotId, SystemState,
+    MAX_CANISTER_HISTORY_CHANGES,
 };
 use ic_system_api::ExecutionParameters;
 use ic_types::messages::SignedIngressContent;
@@ -45,6 +48,10 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::{collections::BTreeSet, convert::TryFrom, str::FromStr, sync::Arc};
 
+/// The maximum number of snapshots a canister can have at any time. Taking a
+/// further snapshot requires replacing an existing one.
+pub(crate) const MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER: usize = 1;
+
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) struct InstallCodeResult {
     pub heap_delta: NumBytes,
@@ -306,6 +313,8 @@ impl CanisterManager {
             | Ok(Ic00Method::HttpRequest)
             // Nobody pays for `raw_rand`, so this cannot be used via ingress messages
             | Ok(Ic00Method::RawRand)
+            // `canister_info` is only exposed to canisters.
+            | Ok(Ic00Method::CanisterInfo)
             // Bitcoin messages require cycles, so we reject all ingress messages.
             | Ok(Ic00Method::BitcoinGetBalance)
             | Ok(Ic00Method::BitcoinGetUtxos)
@@ -326,7 +335,11 @@ impl CanisterManager {
             | Ok(Ic00Method::DeleteCanister) |
             Ok(Ic00Method::UpdateSettings)|
             Ok(Ic00Method::InstallCode) |
-            Ok(Ic00Method::SetController) => {
+            Ok(Ic00Method::SetController) |
+            Ok(Ic00Method::TakeCanisterSnapshot) |
+            Ok(Ic00Method::LoadCanisterSnapshot) |
+            Ok(Ic00Method::ListCanisterSnapshots) |
+            Ok(Ic00Method::DeleteCanisterSnapshot) => {
                 match effective_canister_id {
                     Some(canister_id) => {
                         let canister = state.canister_state(&canister_id).ok_or_else(|| UserError::new(
@@ -351,6 +364,36 @@ impl CanisterManager {
                 }
             },
 
+            // Canister logs can be fetched by anyone if they are public and
+            // only by the controllers of the canister otherwise.
+            Ok(Ic00Method::FetchCanisterLogs) => {
+                match effective_canister_id {
+                    Some(canister_id) => {
+                        let canister = state.canister_state(&canister_id).ok_or_else(|| UserError::new(
+                            ErrorCode::CanisterNotFound,
+                            format!("Canister {} not found", canister_id),
+                        ))?;
+                        match canister.system_state.log_visibility {
+                            LogVisibility::Public => Ok(()),
+                            LogVisibility::Controllers => match canister.controllers().contains(&sender.get()) {
+                                true => Ok(()),
+                                false => Err(UserError::new(
+                                    ErrorCode::CanisterInvalidController,
+                                    format!(
+                                        "Only controllers of canister {} can call ic00 method {}",
+                                        canister_id, method_name,
+                                    ),
+                                )),
+                            },
+                        }
+                    },
+                    None =>  Err(UserError::new(
+                        ErrorCode::InvalidManagementPayload,
+                        format!("Failed to decode payload for ic00 method: {}", method_name),
+                    )),
+                }
+            },
+
             Ok(Ic00Method::ProvisionalCreateCanisterWithCycles)
             | Ok(Ic00Method::BitcoinGetSuccessors)
             | Ok(Ic00Method::ProvisionalTopUpCanister) => {
@@ -447,6 +490,17 @@ impl CanisterManager {
         if let Some(freezing_threshold) = settings.freezing_threshold {
             canister.system_state.freeze_threshold = freezing_threshold;
         }
+        if let Some(log_visibility) = settings.log_visibility {
+            canister.system_state.log_visibility = log_visibility;
+        }
+        if let Some(wasm_memory_limit) = settings.wasm_memory_limit {
+            // A limit of zero removes the limit.
+            canister.system_state.wasm_memory_limit =
+                Some(wasm_memory_limit).filter(|limit| limit.get() > 0);
+        }
+        if let Some(reserved_cycles_limit) = settings.reserved_cycles_limit {
+            canister.system_state.reserved_balance_limit = Some(reserved_cycles_limit);
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
@@ -472,6 +526,15 @@ impl CanisterManager {
             settings.memory_allocation(),
             &self.config,
         )?;
+        if let Some(limit) = settings.reserved_cycles_limit() {
+            let reserved_cycles = canister.system_state.reserved_balance();
+            if limit < reserved_cycles {
+                return Err(CanisterManagerError::ReservedCyclesLimitIsTooLow {
+                    cycles: reserved_cycles,
+                    limit,
+                });
+            }
+        }
 
         let validated_settings =
             ValidatedCanisterSettings::try_from((settings, self.config.max_controllers))?;
@@ -485,6 +548,7 @@ impl CanisterManager {
         let old_compute_allocation = canister.scheduler_state.compute_allocation.as_percent();
 
         self.do_update_settings(validated_settings, canister);
+        canister.system_state.canister_version += 1;
 
         let new_compute_allocation = canister.scheduler_state.compute_allocation.as_percent();
         if old_compute_allocation < new_compute_allocation {
@@ -883,6 +947,7 @@ impl CanisterManager {
         let compute_allocation = canister.scheduler_state.compute_allocation;
         let memory_allocation = canister.memory_allocation();
         let freeze_threshold = canister.system_state.freeze_threshold;
+        let query_stats = &canister.system_state.total_query_stats;
 
         Ok(CanisterStatusResultV2::new(
             canister.status(),
@@ -905,9 +970,329 @@ impl CanisterManager {
                     subnet_size,
                 )
                 .get(),
+            canister.system_state.canister_version,
+            canister
+                .system_state
+                .wasm_memory_limit
+                .map(|limit| limit.get()),
+            canister.system_state.reserved_balance().get(),
+            canister
+                .system_state
+                .reserved_balance_limit
+                .map(|limit| limit.get()),
+            QueryStatsResponse::new(
+                query_stats.num_calls,
+                query_stats.num_instructions,
+                query_stats.ingress_payload_size,
+                query_stats.egress_payload_size,
+            ),
+        ))
+    }
+
+    /// Returns the log records of a canister. Only the controllers of the
+    /// canister can fetch them, unless the log visibility is public.
+    pub(crate) fn fetch_canister_logs(
+        &self,
+        sender: PrincipalId,
+        canister: &CanisterState,
+    ) -> Result<FetchCanisterLogsResponse, CanisterManagerError> {
+        match canister.system_state.log_visibility {
+            LogVisibility::Public => {}
+            LogVisibility::Controllers => validate_controller(canister, &sender)?,
+        }
+        Ok(FetchCanisterLogsResponse {
+            canister_log_records: canister
+                .system_state
+                .canister_log
+                .records()
+                .iter()
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Returns up to `num_requested_changes` most recent changes of the
+    /// canister together with its current module hash and controllers. Any
+    /// canister can request this information.
+    pub(crate) fn get_canister_info(
+        &self,
+        canister: &CanisterState,
+        num_requested_changes: Option<u64>,
+    ) -> CanisterInfoResponse {
+        let canister_history = &canister.system_state.canister_history;
+        let num_requested_changes = num_requested_changes
+            .unwrap_or(0)
+            .min(MAX_CANISTER_HISTORY_CHANGES as u64) as usize;
+        CanisterInfoResponse::new(
+            canister_history.get_total_num_changes(),
+            canister_history
+                .get_changes(num_requested_changes)
+                .cloned()
+                .collect(),
+            canister
+                .execution_state
+                .as_ref()
+                .map(|es| es.wasm_binary.binary.module_hash().to_vec()),
+            canister.system_state.controllers.iter().copied().collect(),
+        )
+    }
+
+    /// Takes a snapshot of the canister's current state. Only the controllers
+    /// of the canister can take snapshots.
+    ///
+    /// If `replace_snapshot` is given, the referenced snapshot is deleted once
+    /// the new one has been taken. Otherwise, the canister must have fewer
+    /// than `MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER` snapshots.
+    pub(crate) fn take_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        replace_snapshot: Option<&[u8]>,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<CanisterSnapshotResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let replace_snapshot = match replace_snapshot {
+            Some(snapshot_id) => {
+                Some(self.validate_snapshot_exists(state, canister_id, snapshot_id)?)
+            }
+            None => {
+                let num_snapshots = state
+                    .canister_snapshots
+                    .canister_snapshots(canister_id)
+                    .count();
+                if num_snapshots >= MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER {
+                    return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                        canister_id,
+                        limit: MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER,
+                    });
+                }
+                None
+            }
+        };
+
+        let snapshot = CanisterSnapshot::from_canister(canister, state.time()).ok_or(
+            CanisterManagerError::Hypervisor(canister_id, HypervisorError::WasmModuleNotFound),
+        )?;
+
+        // The replaced snapshot is released only after the new one is taken,
+        // so the new snapshot needs to fit into the subnet memory on its own.
+        let snapshot_size = snapshot.size();
+        if round_limits
+            .subnet_available_memory
+            .try_decrement(snapshot_size, NumBytes::from(0))
+            .is_err()
+        {
+            return Err(CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                requested: snapshot_size,
+                available: NumBytes::from(
+                    round_limits
+                        .subnet_available_memory
+                        .get_total_memory()
+                        .max(0) as u64,
+                ),
+            });
+        }
+
+        if let Some(replace_snapshot) = replace_snapshot {
+            if let Some(replaced) = state.canister_snapshots.remove(&replace_snapshot) {
+                round_limits
+                    .subnet_available_memory
+                    .increment(replaced.size(), NumBytes::from(0));
+            }
+        }
+
+        let snapshot_id = SnapshotId::new(canister_id, state.metadata.next_canister_snapshot_id);
+        state.metadata.next_canister_snapshot_id += 1;
+        let response = CanisterSnapshotResponse {
+            id: snapshot_id.to_bytes(),
+            taken_at_timestamp: snapshot.taken_at_timestamp.as_nanos_since_unix_epoch(),
+            total_size: snapshot_size.get(),
+        };
+        state.canister_snapshots.push(snapshot_id, snapshot);
+
+        info!(
+            self.log,
+            "Took snapshot {} of canister {}", snapshot_id, canister_id
+        );
+
+        Ok(response)
+    }
+
+    /// Restores the canister to the state captured in the given snapshot.
+    /// Only the controllers of the canister can load its snapshots.
+    ///
+    /// The Wasm module, the Wasm and stable memories, the exported globals and
+    /// the certified data are replaced. Everything else, e.g. the cycles
+    /// balance, the settings and the message queues, is left untouched.
+    ///
+    /// Like `install_code`, the canister pays for compiling the Wasm module
+    /// of the snapshot, and the compilation counts towards the rate limiting
+    /// of its `install_code` instructions. The global timer is deactivated.
+    ///
+    /// The canister must be `Stopped`, so that no response can be delivered
+    /// to a callback that was registered by the replaced Wasm module.
+    ///
+    /// Returns the time at which the loaded snapshot was taken.
+    pub(crate) fn load_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+        state: &mut ReplicatedState,
+        subnet_size: usize,
+        round_limits: &mut RoundLimits,
+    ) -> Result<Time, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+        if canister.status() != CanisterStatusType::Stopped {
+            return Err(CanisterManagerError::LoadCanisterSnapshotNotStopped(
+                canister_id,
+            ));
+        }
+        if canister.scheduler_state.install_code_debit.get() > 0
+            && self.config.rate_limiting_of_instructions == FlagStatus::Enabled
+        {
+            return Err(CanisterManagerError::InstallCodeRateLimited(canister_id));
+        }
+        let snapshot_id = self.validate_snapshot_exists(state, canister_id, snapshot_id)?;
+        let snapshot = Arc::clone(state.canister_snapshots.get(&snapshot_id).unwrap());
+
+        let layout = canister_layout(state.path(), &canister_id);
+        let (instructions_from_compilation, result) = self.hypervisor.create_execution_state(
+            snapshot.wasm_binary.clone(),
+            layout.raw_path(),
+            canister_id,
+            round_limits,
+            CompilationCostHandling::CountFullAmount,
+        );
+
+        let own_subnet_type = self.config.own_subnet_type;
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        let memory_usage = canister.memory_usage(own_subnet_type);
+        let compute_allocation = canister.scheduler_state.compute_allocation;
+        self.cycles_account_manager
+            .withdraw_execution_cycles(
+                &mut canister.system_state,
+                memory_usage,
+                compute_allocation,
+                instructions_from_compilation,
+                subnet_size,
+            )
+            .map_err(CanisterManagerError::LoadCanisterSnapshotNotEnoughCycles)?;
+        if self.config.rate_limiting_of_instructions == FlagStatus::Enabled {
+            canister.scheduler_state.install_code_debit += instructions_from_compilation;
+        }
+
+        let mut execution_state =
+            result.map_err(|err| CanisterManagerError::Hypervisor(canister_id, err))?;
+        execution_state.wasm_memory = Memory::new(
+            snapshot.wasm_memory.page_map.clone(),
+            snapshot.wasm_memory.size,
+        );
+        execution_state.stable_memory = Memory::new(
+            snapshot.stable_memory.page_map.clone(),
+            snapshot.stable_memory.size,
+        );
+        execution_state.exported_globals = snapshot.exported_globals.clone();
+
+        let memory_allocation = canister.system_state.memory_allocation.bytes();
+        let old_mem = memory_allocation.max(canister.memory_usage(own_subnet_type));
+
+        let old_execution_state = canister.execution_state.replace(execution_state);
+        let new_usage = canister.memory_usage(own_subnet_type);
+        if let MemoryAllocation::Reserved(reserved) = canister.system_state.memory_allocation {
+            if new_usage > reserved {
+                canister.execution_state = old_execution_state;
+                return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                    canister_id,
+                    memory_allocation_given: canister.system_state.memory_allocation,
+                    memory_usage_needed: new_usage,
+                });
+            }
+        }
+        let new_mem = memory_allocation.max(new_usage);
+        if new_mem > old_mem {
+            if round_limits
+                .subnet_available_memory
+                .try_decrement(new_mem - old_mem, NumBytes::from(0))
+                .is_err()
+            {
+                canister.execution_state = old_execution_state;
+                return Err(CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                    requested: new_mem - old_mem,
+                    available: NumBytes::from(
+                        round_limits
+                            .subnet_available_memory
+                            .get_total_memory()
+                            .max(0) as u64,
+                    ),
+                });
+            }
+        } else {
+            round_limits
+                .subnet_available_memory
+                .increment(old_mem - new_mem, NumBytes::from(0));
+        }
+
+        canister.system_state.certified_data = snapshot.certified_data.clone();
+        canister.system_state.global_timer = CanisterTimer::Inactive;
+        canister.system_state.canister_version += 1;
+
+        info!(
+            self.log,
+            "Loaded snapshot {} into canister {}", snapshot_id, canister_id
+        );
+
+        Ok(snapshot.taken_at_timestamp)
+    }
+
+    /// Lists the snapshots of the canister, oldest first. Only the
+    /// controllers of the canister can list its snapshots.
+    pub(crate) fn list_canister_snapshots(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<ListCanisterSnapshotsResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+        Ok(ListCanisterSnapshotsResponse(
+            state
+                .canister_snapshots
+                .canister_snapshots(canister_id)
+                .map(|(snapshot_id, snapshot)| CanisterSnapshotResponse {
+                    id: snapshot_id.to_bytes(),
+                    taken_at_timestamp: snapshot.taken_at_timestamp.as_nanos_since_unix_epoch(),
+                    total_size: snapshot.size().get(),
+                })
+                .collect(),
         ))
     }
 
+    /// Deletes the given snapshot of the canister. Only the controllers of
+    /// the canister can delete its snapshots.
+    pub(crate) fn delete_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+        let snapshot_id = self.validate_snapshot_exists(state, canister_id, snapshot_id)?;
+        if let Some(snapshot) = state.canister_snapshots.remove(&snapshot_id) {
+            round_limits
+                .subnet_available_memory
+                .increment(snapshot.size(), NumBytes::from(0));
+        }
+        Ok(())
+    }
+
     /// Sets a new controller for a canister. Only the current controller of
     /// the canister is able to run this, otherwise an error is returned.
     pub(crate) fn set_controller(
@@ -922,7 +1307,16 @@ impl CanisterManager {
             .canister_state_mut(&canister_id)
             .ok_or(CanisterManagerError::CanisterNotFound(canister_id))?;
 
-        let settings = CanisterSettings::new(Some(new_controller), None, None, None, None);
+        let settings = CanisterSettings::new(
+            Some(new_controller),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
         self.update_settings(sender, settings, canister, round_limits)
     }
 
@@ -994,6 +1388,10 @@ impl CanisterManager {
 
         // Take out the canister from `ReplicatedState`.
         let canister_to_delete = state.take_canister_state(&canister_id_to_delete).unwrap();
+        // Its snapshots are deleted together with the canister.
+        state
+            .canister_snapshots
+            .remove_canister_snapshots(canister_id_to_delete);
         // Leftover cycles in the balance are considered `consumed`.
         let consumed_cycles_by_canister_to_delete =
             NominalCycles::from(canister_to_delete.system_state.balance())
@@ -1195,6 +1593,25 @@ impl CanisterManager {
             .canister_state(&canister_id)
             .ok_or(CanisterManagerError::CanisterNotFound(canister_id))
     }
+
+    /// Parses the snapshot id and checks that the snapshot exists and belongs
+    /// to the given canister.
+    fn validate_snapshot_exists(
+        &self,
+        state: &ReplicatedState,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+    ) -> Result<SnapshotId, CanisterManagerError> {
+        let not_found = || CanisterManagerError::CanisterSnapshotNotFound {
+            canister_id,
+            snapshot_id: snapshot_id.to_vec(),
+        };
+        let id = SnapshotId::try_from(snapshot_id).map_err(|_| not_found())?;
+        if id.canister_id() != canister_id || state.canister_snapshots.get(&id).is_none() {
+            return Err(not_found());
+        }
+        Ok(id)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -1231,6 +1648,8 @@ pub(crate) enum CanisterManagerError {
     },
     InstallCodeNotEnoughCycles(CanisterOutOfCyclesError),
     InstallCodeRateLimited(CanisterId),
+    LoadCanisterSnapshotNotEnoughCycles(CanisterOutOfCyclesError),
+    LoadCanisterSnapshotNotStopped(CanisterId),
     SubnetOutOfCanisterIds,
 
     InvalidSettings {
@@ -1240,6 +1659,18 @@ pub(crate) enum CanisterManagerError {
         subnet_id: SubnetId,
         max_number_of_canisters: u64,
     },
+    CanisterSnapshotNotFound {
+        canister_id: CanisterId,
+        snapshot_id: Vec<u8>,
+    },
+    CanisterSnapshotLimitExceeded {
+        canister_id: CanisterId,
+        limit: usize,
+    },
+    ReservedCyclesLimitIsTooLow {
+        cycles: Cycles,
+        limit: Cycles,
+    },
 }
 
 impl From<CanisterManagerError> for UserError {
@@ -1357,6 +1788,21 @@ impl From<CanisterManagerError> for UserError {
                     format!("Canister installation failed with `{}`", err),
                 )
             }
+            LoadCanisterSnapshotNotEnoughCycles(err) => {
+                Self::new(
+                ErrorCode::CanisterOutOfCycles,
+                    format!("Loading the canister snapshot failed with `{}`", err),
+                )
+            }
+            LoadCanisterSnapshotNotStopped(canister_id) => {
+                Self::new(
+                    ErrorCode::CanisterNotStopped,
+                    format!(
+                        "Canister {} must be stopped before a snapshot is loaded into it.",
+                        canister_id,
+                    )
+                )
+            }
             InstallCodeRateLimited(canister_id) => {
                 Self::new(
                 ErrorCode::CanisterInstallCodeRateLimited,
@@ -1380,6 +1826,24 @@ impl From<CanisterManagerError> for UserError {
                     format!("Subnet {} has reached the allowed canister limit of {} canisters. Retry creating the canister.", subnet_id, max_number_of_canisters),
                 )
             }
+            CanisterSnapshotNotFound { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterSnapshotNotFound,
+                    format!("Could not find the snapshot ID {} for canister {}.", hex::encode(snapshot_id), canister_id),
+                )
+            }
+            CanisterSnapshotLimitExceeded { canister_id, limit } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!("Canister {} has reached the maximum number of {} snapshots. Pass `replace_snapshot` to replace an existing snapshot.", canister_id, limit),
+                )
+            }
+            ReservedCyclesLimitIsTooLow { cycles, limit } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!("Cannot set the reserved cycles limit {} below the reserved cycles balance of the canister {}.", limit, cycles),
+                )
+            }
         }
     }
 }
@@ -1414,6 +1878,12 @@ pub fn uninstall_canister(
     // Drop its certified data.
     canister.system_state.certified_data = Vec::new();
 
+    // Deactivate the global timer.
+    canister.system_state.global_timer = CanisterTimer::Inactive;
+
+    // Uninstalling the code counts as a change of the canister.
+    canister.system_state.canister_version += 1;
+
     let mut rejects = Vec::new();
     let canister_id = canister.canister_id();
     if let Some(call_context_manager) = canister.system_state.call_context_manager_mut() {
@@ -1461,8 +1931,8 @@ pub fn uninstall_canister(
                     log,
                     "No callbacks with a query origin should be found when uninstalling"
                 ),
-                CallOrigin::Heartbeat => {
-                    // Cannot respond to heartbeat messages. Nothing to do.
+                CallOrigin::SystemTask => {
+                    // Cannot respond to system tasks. Nothing to do.
                 }
             }
 
@@ -1480,6 +1950,9 @@ struct ValidatedCanisterSettings {
     pub compute_allocation: Option<ComputeAllocation>,
     pub memory_allocation: Option<MemoryAllocation>,
     pub freezing_threshold: Option<NumSeconds>,
+    pub log_visibility: Option<LogVisibility>,
+    pub wasm_memory_limit: Option<NumBytes>,
+    pub reserved_cycles_limit: Option<Cycles>,
 }
 
 impl TryFrom<(CanisterSettings, usize)> for ValidatedCanisterSettings {
@@ -1515,6 +1988,9 @@ impl TryFrom<(CanisterSettings, usize)> for ValidatedCanisterSettings {
             compute_allocation: settings.compute_allocation(),
             memory_allocation: settings.memory_allocation(),
             freezing_threshold: settings.freezing_threshold(),
+            log_visibility: settings.log_visibility(),
+            wasm_memory_limit: settings.wasm_memory_limit(),
+            reserved_cycles_limit: settings.reserved_cycles_limit(),
         })
     }
 }
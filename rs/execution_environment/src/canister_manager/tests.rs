@@ -93,6 +93,7 @@ lazy_static! {
             MAX_NUM_INSTRUCTIONS
         ),
         canister_memory_limit: NumBytes::new(u64::MAX / 2),
+        wasm_memory_limit: None,
         compute_allocation: ComputeAllocation::default(),
         subnet_type: SubnetType::Application,
         execution_mode: ExecutionMode::Replicated,
@@ -3330,6 +3331,9 @@ fn lower_memory_allocation_than_usage_fails() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(2)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
 
         let canister = state.canister_state_mut(&canister_id).unwrap();
@@ -3360,6 +3364,9 @@ fn test_install_when_updating_memory_allocation_via_canister_settings() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(2)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
         let canister_id = canister_manager
             .create_canister(
@@ -3405,6 +3412,9 @@ fn test_install_when_updating_memory_allocation_via_canister_settings() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(MEMORY_CAPACITY.get() / 2)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
 
         let canister = state.canister_state_mut(&canister_id).unwrap();
@@ -3452,6 +3462,9 @@ fn test_upgrade_when_updating_memory_allocation_via_canister_settings() {
                 MemoryAllocation::try_from(NumBytes::from(WASM_PAGE_SIZE_IN_BYTES + 100)).unwrap(),
             ),
             None,
+            None,
+            None,
+            None,
         );
         let wat = r#"
         (module
@@ -3530,6 +3543,9 @@ fn test_upgrade_when_updating_memory_allocation_via_canister_settings() {
                     .unwrap(),
             ),
             None,
+            None,
+            None,
+            None,
         );
 
         let canister = state.canister_state_mut(&canister_id).unwrap();
@@ -3611,7 +3627,7 @@ fn test_install_when_setting_memory_allocation_to_zero() {
         let wasm = ic_test_utilities::universal_canister::UNIVERSAL_CANISTER_WASM.to_vec();
 
         let sender = canister_test_id(100).get();
-        let settings = CanisterSettings::new(None, None, None, None, None);
+        let settings = CanisterSettings::new(None, None, None, None, None, None, None, None);
         let canister_id = canister_manager
             .create_canister(
                 sender,
@@ -3633,6 +3649,9 @@ fn test_install_when_setting_memory_allocation_to_zero() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(0)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
 
         let canister = state.canister_state_mut(&canister_id).unwrap();
@@ -3686,6 +3705,9 @@ fn test_upgrade_when_setting_memory_allocation_to_zero() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(MEMORY_CAPACITY.get() / 2)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
         let canister_id = canister_manager
             .create_canister(
@@ -3726,6 +3748,9 @@ fn test_upgrade_when_setting_memory_allocation_to_zero() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(0)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
 
         let canister = state.canister_state_mut(&canister_id).unwrap();
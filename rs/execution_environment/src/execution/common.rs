@@ -2,6 +2,8 @@
 // TODO(RUN-60): Move helper functions here.
 
 use ic_base_types::{CanisterId, NumBytes, SubnetId};
+use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
+use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_embedders::wasm_executor::{CanisterStateChanges, SliceExecutionOutput};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::CanisterStatusType;
@@ -12,13 +14,13 @@ use ic_interfaces::messages::RequestOrIngress;
 use ic_logger::{error, fatal, warn, ReplicaLogger};
 use ic_replicated_state::{
     CallContext, CallContextAction, CallOrigin, CanisterState, ExecutionState, NetworkTopology,
-    SystemState,
+    ReservationError, SystemState,
 };
 use ic_system_api::sandbox_safe_system_state::SystemStateChanges;
 use ic_types::ingress::{IngressState, IngressStatus, WasmResult};
 use ic_types::messages::{CallContextId, CallbackId, MessageId, Payload, RejectContext, Response};
 use ic_types::methods::{Callback, WasmMethod};
-use ic_types::{Cycles, MemoryAllocation, NumInstructions, Time, UserId};
+use ic_types::{ComputeAllocation, Cycles, MemoryAllocation, NumInstructions, Time, UserId};
 
 use crate::execution_environment::ExecutionResponse;
 use crate::{as_round_instructions, ExecuteMessageResult, RoundLimits};
@@ -60,8 +62,8 @@ pub(crate) fn action_to_response(
             log,
             "The update path should not have created a callback with a query origin",
         ),
-        CallOrigin::Heartbeat => {
-            // Since heartbeat messages are invoked by the system as opposed
+        CallOrigin::SystemTask => {
+            // Since system tasks are invoked by the system as opposed
             // to a principal, they cannot respond since there's no one to
             // respond to. Do nothing.
             ExecutionResponse::Empty
@@ -215,8 +217,8 @@ pub(crate) fn wasm_result_to_query_response(
         CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
             fatal!(log, "The update path should not have a query origin",)
         }
-        CallOrigin::Heartbeat => {
-            // Since heartbeat messages are invoked by the system as opposed
+        CallOrigin::SystemTask => {
+            // Since system tasks are invoked by the system as opposed
             // to a principal, they cannot respond since there's no one to
             // respond to. Do nothing.
             ExecutionResponse::Empty
@@ -370,23 +372,70 @@ pub fn update_round_limits(round_limits: &mut RoundLimits, slice: &SliceExecutio
     round_limits.instructions -= as_round_instructions(slice.executed_instructions);
 }
 
+/// Converts an error of reserving cycles for `allocated_bytes` of new memory
+/// into the corresponding `HypervisorError`.
+pub(crate) fn reservation_error_to_hypervisor_error(
+    err: ReservationError,
+    allocated_bytes: NumBytes,
+) -> HypervisorError {
+    match err {
+        ReservationError::InsufficientCycles {
+            requested,
+            available,
+        } => HypervisorError::InsufficientCyclesInMemoryAllocation {
+            bytes: allocated_bytes,
+            available,
+            requested,
+        },
+        ReservationError::ReservedLimitExceeded { requested, limit } => {
+            HypervisorError::ReservedCyclesLimitExceededInMemoryAllocation {
+                bytes: allocated_bytes,
+                requested,
+                limit,
+            }
+        }
+    }
+}
+
 /// Tries to apply the given canister changes to the given system state and
 /// subnet available memory. In case of an error, the partially applied changes
 /// are not undone.
+#[allow(clippy::too_many_arguments)]
 fn try_apply_canister_state_changes(
     system_state_changes: SystemStateChanges,
     output: &WasmExecutionOutput,
     system_state: &mut SystemState,
     subnet_available_memory: &mut SubnetAvailableMemory,
+    subnet_memory_saturation: &ResourceSaturation,
+    canister_current_memory_usage: NumBytes,
+    canister_compute_allocation: ComputeAllocation,
+    cycles_account_manager: &CyclesAccountManager,
     time: Time,
     network_topology: &NetworkTopology,
     subnet_id: SubnetId,
     log: &ReplicaLogger,
 ) -> HypervisorResult<()> {
     match &system_state.memory_allocation {
-        MemoryAllocation::BestEffort => subnet_available_memory
-            .try_decrement(output.allocated_bytes, output.allocated_message_bytes)
-            .map_err(|_| HypervisorError::OutOfMemory)?,
+        MemoryAllocation::BestEffort => {
+            let subnet_size = network_topology
+                .get_subnet_size(&subnet_id)
+                .unwrap_or(SMALL_APP_SUBNET_MAX_SIZE);
+            cycles_account_manager
+                .reserve_storage_cycles(
+                    system_state,
+                    output.allocated_bytes,
+                    subnet_memory_saturation,
+                    canister_current_memory_usage + output.allocated_bytes,
+                    canister_compute_allocation,
+                    subnet_size,
+                )
+                .map_err(|err| {
+                    reservation_error_to_hypervisor_error(err, output.allocated_bytes)
+                })?;
+            subnet_available_memory
+                .try_decrement(output.allocated_bytes, output.allocated_message_bytes)
+                .map_err(|_| HypervisorError::OutOfMemory)?
+        }
         MemoryAllocation::Reserved(_) => (),
     }
 
@@ -402,12 +451,17 @@ fn try_apply_canister_state_changes(
 /// - A mismatch between checks dones by the Wasm executor and checks done when
 ///   applying the changes due to a bug.
 /// - An escape from the Wasm sandbox that corrupts the execution output.
+#[allow(clippy::too_many_arguments)]
 pub fn apply_canister_state_changes(
     canister_state_changes: Option<CanisterStateChanges>,
     execution_state: &mut ExecutionState,
     system_state: &mut SystemState,
     output: &mut WasmExecutionOutput,
     round_limits: &mut RoundLimits,
+    subnet_memory_saturation: &ResourceSaturation,
+    canister_current_memory_usage: NumBytes,
+    canister_compute_allocation: ComputeAllocation,
+    cycles_account_manager: &CyclesAccountManager,
     time: Time,
     network_topology: &NetworkTopology,
     subnet_id: SubnetId,
@@ -429,6 +483,10 @@ pub fn apply_canister_state_changes(
             output,
             system_state,
             &mut round_limits.subnet_available_memory,
+            subnet_memory_saturation,
+            canister_current_memory_usage,
+            canister_compute_allocation,
+            cycles_account_manager,
             time,
             network_topology,
             subnet_id,
@@ -451,6 +509,11 @@ pub fn apply_canister_state_changes(
                     HypervisorError::OutOfMemory => {
                         warn!(log, "Failed to apply state changes due to DTS: {}", err)
                     }
+                    HypervisorError::InsufficientCyclesInMemoryAllocation { .. }
+                    | HypervisorError::ReservedCyclesLimitExceededInMemoryAllocation { .. } => {
+                        // The canister could not reserve cycles for the
+                        // memory it allocated, which is not a bug.
+                    }
                     _ => {
                         // TODO(RUN-299): Increment a critical error counter here.
                         error!(
@@ -465,6 +528,10 @@ pub fn apply_canister_state_changes(
             }
         }
     }
+    // The canister log is preserved even if the execution failed.
+    system_state
+        .canister_log
+        .append_delta(&mut output.canister_log);
 }
 
 pub(crate) fn finish_call_with_error(
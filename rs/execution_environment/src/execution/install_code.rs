@@ -6,7 +6,7 @@ use std::path::{Path, PathBuf};
 use ic_base_types::{CanisterId, NumBytes, PrincipalId};
 use ic_config::flag_status::FlagStatus;
 use ic_embedders::wasm_executor::CanisterStateChanges;
-use ic_ic00_types::CanisterInstallMode;
+use ic_ic00_types::{CanisterChangeDetails, CanisterInstallMode};
 use ic_interfaces::{
     execution_environment::{
         HypervisorError, HypervisorResult, SubnetAvailableMemory, SubnetAvailableMemoryError,
@@ -15,7 +15,7 @@ use ic_interfaces::{
     messages::RequestOrIngress,
 };
 use ic_logger::{error, fatal, warn};
-use ic_replicated_state::{CanisterState, ExecutionState};
+use ic_replicated_state::{CanisterState, CanisterTimer, ExecutionState};
 use ic_state_layout::{CanisterLayout, CheckpointLayout, RwPolicy};
 use ic_sys::PAGE_SIZE;
 use ic_system_api::ExecutionParameters;
@@ -25,6 +25,7 @@ use crate::{
     canister_manager::{
         CanisterManagerError, CanisterMgrConfig, DtsInstallCodeResult, InstallCodeResult,
     },
+    execution::common::reservation_error_to_hypervisor_error,
     execution_environment::RoundContext,
     CompilationCostHandling, RoundLimits,
 };
@@ -163,6 +164,35 @@ impl InstallCodeHelper {
     ) -> DtsInstallCodeResult {
         let message_instruction_limit = original.execution_parameters.instruction_limits.message();
         let instructions_left = self.instructions_left();
+
+        if self.canister.memory_allocation() == MemoryAllocation::BestEffort {
+            let subnet_memory_saturation = round
+                .hypervisor
+                .subnet_memory_saturation(&round_limits.subnet_available_memory);
+            let memory_usage = self
+                .canister
+                .memory_usage(original.execution_parameters.subnet_type);
+            let compute_allocation = self.canister.scheduler_state.compute_allocation;
+            if let Err(err) = round.cycles_account_manager.reserve_storage_cycles(
+                &mut self.canister.system_state,
+                self.allocated_bytes,
+                &subnet_memory_saturation,
+                memory_usage,
+                compute_allocation,
+                original.subnet_size,
+            ) {
+                let err = reservation_error_to_hypervisor_error(err, self.allocated_bytes);
+                let canister_id = self.canister.canister_id();
+                return finish_err(
+                    clean_canister,
+                    self.instructions_left(),
+                    original,
+                    round,
+                    CanisterManagerError::Hypervisor(canister_id, err),
+                );
+            }
+        }
+
         let mut subnet_available_memory = round_limits.subnet_available_memory.clone();
         subnet_available_memory.increment(self.deallocated_bytes, NumBytes::from(0));
         if let Err(err) = subnet_available_memory
@@ -224,6 +254,15 @@ impl InstallCodeHelper {
 
         round_limits.subnet_available_memory = subnet_available_memory;
 
+        self.canister.system_state.canister_version += 1;
+        if let Some(module_hash) = get_wasm_hash(&self.canister) {
+            self.canister.system_state.add_canister_change(
+                original.time,
+                original.message.canister_change_origin(),
+                CanisterChangeDetails::code_deployment(original.mode, module_hash),
+            );
+        }
+
         round.cycles_account_manager.refund_execution_cycles(
             &mut self.canister.system_state,
             instructions_left,
@@ -365,6 +404,10 @@ impl InstallCodeHelper {
             };
         self.canister.execution_state = Some(execution_state);
 
+        // Deactivate the global timer. The new code can set it again in
+        // `canister_init` or `canister_post_upgrade`.
+        self.canister.system_state.global_timer = CanisterTimer::Inactive;
+
         // Update the compute allocation.
         let new_compute_allocation = original
             .requested_compute_allocation
@@ -421,7 +464,7 @@ impl InstallCodeHelper {
     pub fn handle_wasm_execution(
         &mut self,
         canister_state_changes: Option<CanisterStateChanges>,
-        output: WasmExecutionOutput,
+        mut output: WasmExecutionOutput,
         original: &OriginalContext,
         round: &RoundContext,
     ) -> Result<(), CanisterManagerError> {
@@ -486,6 +529,10 @@ impl InstallCodeHelper {
             execution_state.wasm_memory = wasm_memory;
             execution_state.stable_memory = stable_memory;
             execution_state.exported_globals = globals;
+            self.canister
+                .system_state
+                .canister_log
+                .append_delta(&mut output.canister_log);
             match self.canister.system_state.memory_allocation {
                 MemoryAllocation::Reserved(_) => {}
                 MemoryAllocation::BestEffort => {
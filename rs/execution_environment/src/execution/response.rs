@@ -297,12 +297,21 @@ impl ResponseHelper {
         round: &RoundContext,
         round_limits: &mut RoundLimits,
     ) -> Result<ExecuteMessageResult, (Self, HypervisorError, NumInstructions)> {
+        let subnet_memory_saturation = round
+            .hypervisor
+            .subnet_memory_saturation(&round_limits.subnet_available_memory);
+        let memory_usage = self.canister.memory_usage(round.hypervisor.subnet_type());
+        let compute_allocation = self.canister.scheduler_state.compute_allocation;
         apply_canister_state_changes(
             canister_state_changes,
             self.canister.execution_state.as_mut().unwrap(),
             &mut self.canister.system_state,
             &mut output,
             round_limits,
+            &subnet_memory_saturation,
+            memory_usage,
+            compute_allocation,
+            round.cycles_account_manager,
             round.time,
             round.network_topology,
             round.hypervisor.subnet_id(),
@@ -331,12 +340,21 @@ impl ResponseHelper {
         round: &RoundContext,
         round_limits: &mut RoundLimits,
     ) -> ExecuteMessageResult {
+        let subnet_memory_saturation = round
+            .hypervisor
+            .subnet_memory_saturation(&round_limits.subnet_available_memory);
+        let memory_usage = self.canister.memory_usage(round.hypervisor.subnet_type());
+        let compute_allocation = self.canister.scheduler_state.compute_allocation;
         apply_canister_state_changes(
             canister_state_changes,
             self.canister.execution_state.as_mut().unwrap(),
             &mut self.canister.system_state,
             &mut output,
             round_limits,
+            &subnet_memory_saturation,
+            memory_usage,
+            compute_allocation,
+            round.cycles_account_manager,
             round.time,
             round.network_topology,
             round.hypervisor.subnet_id(),
@@ -644,7 +662,7 @@ pub fn execute_response(
     };
 
     let func_ref = match original.call_origin {
-        CallOrigin::Ingress(_, _) | CallOrigin::CanisterUpdate(_, _) | CallOrigin::Heartbeat => {
+        CallOrigin::Ingress(_, _) | CallOrigin::CanisterUpdate(_, _) | CallOrigin::SystemTask => {
             FuncRef::UpdateClosure(closure)
         }
         CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => FuncRef::QueryClosure(closure),
@@ -712,7 +730,7 @@ fn execute_response_cleanup(
         .instruction_limits
         .update(instructions_left);
     let func_ref = match original.call_origin {
-        CallOrigin::Ingress(_, _) | CallOrigin::CanisterUpdate(_, _) | CallOrigin::Heartbeat => {
+        CallOrigin::Ingress(_, _) | CallOrigin::CanisterUpdate(_, _) | CallOrigin::SystemTask => {
             FuncRef::UpdateClosure(cleanup_closure)
         }
         CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
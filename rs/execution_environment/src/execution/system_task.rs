@@ -1,13 +1,16 @@
 use crate::execution_environment::RoundLimits;
-// This module defines how `canister_heartbeat` messages are executed.
-// See https://smartcontracts.org/docs/interface-spec/index.html#_heartbeat.
-use crate::{CanisterHeartbeatError, Hypervisor};
+// This module defines how `canister_heartbeat` and `canister_global_timer`
+// system tasks are executed.
+// See https://smartcontracts.org/docs/interface-spec/index.html#_heartbeat
+// and https://internetcomputer.org/docs/current/references/ic-interface-spec#global-timer.
+use crate::{CanisterSystemTaskError, Hypervisor};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_ic00_types::CanisterStatusType;
 use ic_interfaces::execution_environment::HypervisorError;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    CallOrigin, CanisterState, ExecutionState, NetworkTopology, SchedulerState, SystemState,
+    CallOrigin, CanisterState, CanisterTimer, ExecutionState, NetworkTopology, SchedulerState,
+    SystemState,
 };
 use ic_system_api::{ApiType, ExecutionParameters};
 use ic_types::methods::{FuncRef, SystemMethod, WasmMethod};
@@ -17,22 +20,22 @@ use std::sync::Arc;
 #[cfg(test)]
 mod tests;
 
-/// Holds the result of heartbeat execution.
-pub struct HeartbeatResult {
-    /// The canister state resulted from the heartbeat execution.
+/// Holds the result of a system task execution.
+pub struct SystemTaskResult {
+    /// The canister state resulted from the system task execution.
     pub canister_state: CanisterState,
-    /// The number of instructions used by the heartbeat execution.
+    /// The number of instructions used by the system task execution.
     pub instructions_used: NumInstructions,
     /// The size of the heap delta change, if execution is successful
     /// or the relevant error in case of failure.
-    pub heap_delta_result: Result<NumBytes, CanisterHeartbeatError>,
+    pub heap_delta_result: Result<NumBytes, CanisterSystemTaskError>,
 }
 
-impl HeartbeatResult {
+impl SystemTaskResult {
     pub fn new(
         canister_state: CanisterState,
         instructions_used: NumInstructions,
-        heap_delta_result: Result<NumBytes, CanisterHeartbeatError>,
+        heap_delta_result: Result<NumBytes, CanisterSystemTaskError>,
     ) -> Self {
         Self {
             canister_state,
@@ -46,7 +49,7 @@ impl HeartbeatResult {
     ) -> (
         CanisterState,
         NumInstructions,
-        Result<NumBytes, CanisterHeartbeatError>,
+        Result<NumBytes, CanisterSystemTaskError>,
     ) {
         (
             self.canister_state,
@@ -56,21 +59,21 @@ impl HeartbeatResult {
     }
 }
 
-// Validates a canister before executing the heartbeat.
+// Validates a canister before executing the system task.
 //
 // Returns the canister split in parts if successful,
-// otherwise `HeartbeatResult` which contains the error.
+// otherwise `SystemTaskResult` which contains the error.
 fn validate_canister(
     canister: CanisterState,
     method: WasmMethod,
-) -> Result<(ExecutionState, SystemState, SchedulerState), HeartbeatResult> {
+) -> Result<(ExecutionState, SystemState, SchedulerState), SystemTaskResult> {
     // Check that the status of the canister is Running.
     if canister.status() != CanisterStatusType::Running {
         let status = canister.status();
-        return Err(HeartbeatResult::new(
+        return Err(SystemTaskResult::new(
             canister,
             NumInstructions::from(0),
-            Err(CanisterHeartbeatError::CanisterNotRunning { status }),
+            Err(CanisterSystemTaskError::CanisterNotRunning { status }),
         ));
     }
 
@@ -80,10 +83,10 @@ fn validate_canister(
     let execution_state = match execution_state {
         Some(es) => es,
         None => {
-            return Err(HeartbeatResult::new(
+            return Err(SystemTaskResult::new(
                 CanisterState::from_parts(None, old_system_state, scheduler_state),
                 NumInstructions::from(0),
-                Err(CanisterHeartbeatError::CanisterExecutionFailed(
+                Err(CanisterSystemTaskError::CanisterExecutionFailed(
                     HypervisorError::WasmModuleNotFound,
                 )),
             ))
@@ -91,7 +94,7 @@ fn validate_canister(
     };
 
     if !execution_state.exports_method(&method) {
-        return Err(HeartbeatResult::new(
+        return Err(SystemTaskResult::new(
             CanisterState::from_parts(Some(execution_state), old_system_state, scheduler_state),
             NumInstructions::from(0),
             // If the Wasm module does not export the method, then this execution
@@ -103,17 +106,21 @@ fn validate_canister(
     Ok((execution_state, old_system_state, scheduler_state))
 }
 
-/// Executes a heartbeat of a given canister.
+/// Executes a system task (`canister_heartbeat` or `canister_global_timer`)
+/// of a given canister.
 ///
-/// Before executing the heartbeat, the canister is validated to meet the following
-/// conditions:
+/// Before executing the system task, the canister is validated to meet the
+/// following conditions:
 ///     - The status of the canister is Running.
-///     Otherwise, `CanisterHeartbeatError::CanisterNotRunning` error is returned.
+///     Otherwise, `CanisterSystemTaskError::CanisterNotRunning` error is returned.
 ///     - Wasm module is present.
-///     Otherwise, `CanisterHeartbeatError::CanisterExecutionFailed` error is returned.
-///     - Wasm module exports the heartbeat method.
-///    
-/// When the heartbeat method is not exported, the execution succeeds as a no-op operation.
+///     Otherwise, `CanisterSystemTaskError::CanisterExecutionFailed` error is returned.
+///     - Wasm module exports the system task method.
+///
+/// When the system task method is not exported, the execution succeeds as a no-op operation.
+///
+/// The global timer is deactivated before `canister_global_timer` runs, so the
+/// canister has to set it again from within the method for periodic execution.
 /// No changes are applied to the canister state if the canister cannot be validated.
 ///
 /// Returns:
@@ -124,10 +131,11 @@ fn validate_canister(
 /// - Number of instructions left. This should be <= `instructions_limit`.
 ///
 /// - A result containing the size of the heap delta change if
-/// execution was successful or the relevant `CanisterHeartbeatError` error if execution fails.
+/// execution was successful or the relevant `CanisterSystemTaskError` error if execution fails.
 #[allow(clippy::too_many_arguments)]
-pub fn execute_heartbeat(
+pub fn execute_system_task(
     canister: CanisterState,
+    system_task: SystemMethod,
     network_topology: Arc<NetworkTopology>,
     execution_parameters: ExecutionParameters,
     own_subnet_type: SubnetType,
@@ -136,8 +144,8 @@ pub fn execute_heartbeat(
     cycles_account_manager: &CyclesAccountManager,
     round_limits: &mut RoundLimits,
     subnet_size: usize,
-) -> HeartbeatResult {
-    let method = WasmMethod::System(SystemMethod::CanisterHeartbeat);
+) -> SystemTaskResult {
+    let method = WasmMethod::System(system_task.clone());
     let memory_usage = canister.memory_usage(own_subnet_type);
     let compute_allocation = canister.scheduler_state.compute_allocation;
     let message_instruction_limit = execution_parameters.instruction_limits.message();
@@ -151,7 +159,12 @@ pub fn execute_heartbeat(
             Err(err) => return err,
         };
 
-    // Charge for heartbeat execution.
+    // The global timer is one-shot: deactivate it before execution.
+    if system_task == SystemMethod::CanisterGlobalTimer {
+        system_state.global_timer = CanisterTimer::Inactive;
+    }
+
+    // Charge for system task execution.
     if let Err(err) = cycles_account_manager.withdraw_execution_cycles(
         &mut system_state,
         memory_usage,
@@ -159,19 +172,19 @@ pub fn execute_heartbeat(
         message_instruction_limit,
         subnet_size,
     ) {
-        return HeartbeatResult::new(
+        return SystemTaskResult::new(
             CanisterState::from_parts(Some(execution_state), system_state, scheduler_state),
             NumInstructions::from(0),
-            Err(CanisterHeartbeatError::OutOfCycles(err)),
+            Err(CanisterSystemTaskError::OutOfCycles(err)),
         );
     }
 
-    // Execute canister heartbeat.
+    // Execute canister system task.
     let call_context_id = system_state
         .call_context_manager_mut()
         .unwrap()
-        .new_call_context(CallOrigin::Heartbeat, Cycles::new(0), time);
-    let api_type = ApiType::heartbeat(time, call_context_id);
+        .new_call_context(CallOrigin::SystemTask, Cycles::new(0), time);
+    let api_type = ApiType::system_task(system_task, time, call_context_id);
     let (output, output_execution_state, output_system_state) = hypervisor.execute(
         api_type,
         time,
@@ -201,7 +214,7 @@ pub fn execute_heartbeat(
 
     let heap_delta = match heap_delta {
         Ok(heap_delta) => Ok(heap_delta),
-        Err(err) => Err(CanisterHeartbeatError::CanisterExecutionFailed(err)),
+        Err(err) => Err(CanisterSystemTaskError::CanisterExecutionFailed(err)),
     };
 
     // Refund the canister with any cycles left after message execution.
@@ -218,5 +231,5 @@ pub fn execute_heartbeat(
             .saturating_sub(num_instructions_left.get()),
     );
 
-    HeartbeatResult::new(canister, instructions_used, heap_delta)
+    SystemTaskResult::new(canister, instructions_used, heap_delta)
 }
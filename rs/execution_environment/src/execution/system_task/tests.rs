@@ -1,10 +1,10 @@
 use crate::execution::test_utilities::{wat_compilation_cost, ExecutionTestBuilder};
-use crate::CanisterHeartbeatError;
+use crate::CanisterSystemTaskError;
 use assert_matches::assert_matches;
 use ic_ic00_types::CanisterStatusType;
 use ic_interfaces::execution_environment::{HypervisorError, TrapCode};
-use ic_replicated_state::{page_map::PAGE_SIZE, CanisterStatus};
-use ic_types::NumBytes;
+use ic_replicated_state::{page_map::PAGE_SIZE, CanisterStatus, CanisterTimer};
+use ic_types::{NumBytes, Time};
 
 #[test]
 fn heartbeat_is_executed() {
@@ -18,7 +18,7 @@ fn heartbeat_is_executed() {
     let err = test.heartbeat(canister_id).unwrap_err();
     assert_eq!(
         err,
-        CanisterHeartbeatError::CanisterExecutionFailed(HypervisorError::Trapped(
+        CanisterSystemTaskError::CanisterExecutionFailed(HypervisorError::Trapped(
             TrapCode::Unreachable
         ))
     );
@@ -71,7 +71,7 @@ fn heartbeat_doesnt_run_if_canister_is_stopped() {
     let err = test.heartbeat(canister_id).unwrap_err();
     assert_eq!(
         err,
-        CanisterHeartbeatError::CanisterNotRunning {
+        CanisterSystemTaskError::CanisterNotRunning {
             status: CanisterStatusType::Stopped,
         }
     );
@@ -97,8 +97,67 @@ fn heartbeat_doesnt_run_if_canister_is_stopping() {
     let err = test.heartbeat(canister_id).unwrap_err();
     assert_eq!(
         err,
-        CanisterHeartbeatError::CanisterNotRunning {
+        CanisterSystemTaskError::CanisterNotRunning {
             status: CanisterStatusType::Stopping,
         }
     );
 }
+
+#[test]
+fn global_timer_is_executed() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (func (export "canister_global_timer") unreachable)
+            (memory (export "memory") 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let err = test.global_timer(canister_id).unwrap_err();
+    assert_eq!(
+        err,
+        CanisterSystemTaskError::CanisterExecutionFailed(HypervisorError::Trapped(
+            TrapCode::Unreachable
+        ))
+    );
+}
+
+#[test]
+fn global_timer_is_deactivated_after_execution() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (func (export "canister_global_timer")
+                (i32.store (i32.const 10) (i32.const 10))
+            )
+            (memory (export "memory") 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.canister_state_mut(canister_id)
+        .system_state
+        .global_timer = CanisterTimer::Active(Time::from_nanos_since_unix_epoch(1));
+    test.global_timer(canister_id).unwrap();
+    assert_eq!(
+        CanisterTimer::Inactive,
+        test.canister_state(canister_id).system_state.global_timer
+    );
+}
+
+#[test]
+fn global_timer_can_be_set_in_global_timer() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "global_timer_set"
+                (func $global_timer_set (param i64) (result i64)))
+            (func (export "canister_global_timer")
+                (drop (call $global_timer_set (i64.const 42)))
+            )
+            (memory (export "memory") 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.global_timer(canister_id).unwrap();
+    assert_eq!(
+        CanisterTimer::Active(Time::from_nanos_since_unix_epoch(42)),
+        test.canister_state(canister_id).system_state.global_timer
+    );
+}
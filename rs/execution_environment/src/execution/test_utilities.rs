@@ -12,9 +12,9 @@ use ic_cycles_account_manager::CyclesAccountManager;
 use ic_embedders::{wasm_utils::compile, WasmtimeEmbedder};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterStatusType, EcdsaKeyId,
-    EmptyBlob, InstallCodeArgs, Method, Payload, ProvisionalCreateCanisterWithCyclesArgs,
-    UpdateSettingsArgs,
+    CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterSnapshotArgs,
+    CanisterStatusType, EcdsaKeyId, EmptyBlob, FetchCanisterLogsRequest, InstallCodeArgs, Method,
+    Payload, ProvisionalCreateCanisterWithCyclesArgs, TakeCanisterSnapshotArgs, UpdateSettingsArgs,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -40,6 +40,7 @@ use ic_types::{
     crypto::{canister_threshold_sig::MasterEcdsaPublicKey, AlgorithmId},
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{AnonymousQuery, CallbackId, MessageId, RequestOrResponse, Response, UserQuery},
+    methods::SystemMethod,
     CanisterId, Cycles, NumInstructions, Time, UserId,
 };
 use ic_types_test_utils::ids::{subnet_test_id, user_test_id};
@@ -47,9 +48,10 @@ use ic_universal_canister::UNIVERSAL_CANISTER_WASM;
 use ic_wasm_types::BinaryEncodedWasm;
 use maplit::btreemap;
 
+use crate::query_handler::new_query_stats_collector;
 use crate::util::process_stopping_canisters;
 use crate::{
-    execute_canister, CanisterHeartbeatError, CompilationCostHandling, ExecuteMessageResult,
+    execute_canister, CanisterSystemTaskError, CompilationCostHandling, ExecuteMessageResult,
     ExecutionEnvironment, ExecutionResponse, Hypervisor, IngressHistoryWriterImpl,
     InternalHttpQueryHandler, RoundInstructions, RoundLimits,
 };
@@ -441,6 +443,55 @@ impl ExecutionTest {
         self.subnet_message(Method::CanisterStatus, payload)
     }
 
+    /// Fetches the log records of the given canister.
+    pub fn fetch_canister_logs(
+        &mut self,
+        canister_id: CanisterId,
+    ) -> Result<WasmResult, UserError> {
+        let payload = FetchCanisterLogsRequest::new(canister_id).encode();
+        self.subnet_message(Method::FetchCanisterLogs, payload)
+    }
+
+    /// Takes a snapshot of the given canister, optionally replacing an
+    /// existing snapshot.
+    pub fn take_canister_snapshot(
+        &mut self,
+        canister_id: CanisterId,
+        replace_snapshot: Option<Vec<u8>>,
+    ) -> Result<WasmResult, UserError> {
+        let payload = TakeCanisterSnapshotArgs::new(canister_id, replace_snapshot).encode();
+        self.subnet_message(Method::TakeCanisterSnapshot, payload)
+    }
+
+    /// Restores the given canister from one of its snapshots.
+    pub fn load_canister_snapshot(
+        &mut self,
+        canister_id: CanisterId,
+        snapshot_id: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterSnapshotArgs::new(canister_id, snapshot_id).encode();
+        self.subnet_message(Method::LoadCanisterSnapshot, payload)
+    }
+
+    /// Lists the snapshots of the given canister.
+    pub fn list_canister_snapshots(
+        &mut self,
+        canister_id: CanisterId,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::ListCanisterSnapshots, payload)
+    }
+
+    /// Deletes one of the snapshots of the given canister.
+    pub fn delete_canister_snapshot(
+        &mut self,
+        canister_id: CanisterId,
+        snapshot_id: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterSnapshotArgs::new(canister_id, snapshot_id).encode();
+        self.subnet_message(Method::DeleteCanisterSnapshot, payload)
+    }
+
     /// Updates the freezing threshold of the given canister.
     pub fn update_freezing_threshold(
         &mut self,
@@ -461,6 +512,36 @@ impl ExecutionTest {
         self.subnet_message(Method::UpdateSettings, payload)
     }
 
+    pub fn update_wasm_memory_limit(
+        &mut self,
+        canister_id: CanisterId,
+        wasm_memory_limit: NumBytes,
+    ) -> Result<WasmResult, UserError> {
+        let mut settings = CanisterSettingsArgs::default();
+        settings.wasm_memory_limit = Some(candid::Nat::from(wasm_memory_limit.get()));
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings,
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
+    pub fn update_reserved_cycles_limit(
+        &mut self,
+        canister_id: CanisterId,
+        reserved_cycles_limit: Cycles,
+    ) -> Result<WasmResult, UserError> {
+        let mut settings = CanisterSettingsArgs::default();
+        settings.reserved_cycles_limit = Some(candid::Nat::from(reserved_cycles_limit.get()));
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings,
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
     /// Sets the controller of the canister to the given principal.
     pub fn set_controller(
         &mut self,
@@ -698,7 +779,22 @@ impl ExecutionTest {
     }
 
     /// Executes the heartbeat method of the given canister.
-    pub fn heartbeat(&mut self, canister_id: CanisterId) -> Result<(), CanisterHeartbeatError> {
+    pub fn heartbeat(&mut self, canister_id: CanisterId) -> Result<(), CanisterSystemTaskError> {
+        self.system_task(canister_id, SystemMethod::CanisterHeartbeat)
+    }
+
+    /// Executes the global timer method of the given canister.
+    pub fn global_timer(&mut self, canister_id: CanisterId) -> Result<(), CanisterSystemTaskError> {
+        self.system_task(canister_id, SystemMethod::CanisterGlobalTimer)
+    }
+
+    /// Executes the given system task (heartbeat or global timer) of the given
+    /// canister.
+    fn system_task(
+        &mut self,
+        canister_id: CanisterId,
+        system_task: SystemMethod,
+    ) -> Result<(), CanisterSystemTaskError> {
         let mut state = self.state.take().unwrap();
         let compute_allocation_used = state.total_compute_allocation();
         let canister = state.take_canister_state(&canister_id).unwrap();
@@ -708,8 +804,9 @@ impl ExecutionTest {
             subnet_available_memory: self.subnet_available_memory.get().into(),
             compute_allocation_used,
         };
-        let (canister, instructions_used, result) = self.exec_env.execute_canister_heartbeat(
+        let (canister, instructions_used, result) = self.exec_env.execute_canister_system_task(
             canister,
+            system_task,
             self.instruction_limits.clone(),
             network_topology,
             self.time,
@@ -1219,6 +1316,7 @@ pub struct ExecutionTestBuilder {
     initial_canister_cycles: Cycles,
     subnet_total_memory: i64,
     subnet_message_memory: i64,
+    subnet_memory_threshold: i64,
     registry_settings: RegistryExecutionSettings,
     manual_execution: bool,
     rate_limiting_of_instructions: bool,
@@ -1228,6 +1326,7 @@ pub struct ExecutionTestBuilder {
     bitcoin_canisters: Vec<PrincipalId>,
     bitcoin_get_successors_follow_up_responses: BTreeMap<CanisterId, Vec<Vec<u8>>>,
     cost_to_compile_wasm_instruction: u64,
+    max_query_call_depth: usize,
 }
 
 impl Default for ExecutionTestBuilder {
@@ -1242,6 +1341,9 @@ impl Default for ExecutionTestBuilder {
         let subnet_message_memory = ic_config::execution_environment::Config::default()
             .subnet_message_memory_capacity
             .get() as i64;
+        let subnet_memory_threshold = ic_config::execution_environment::Config::default()
+            .subnet_memory_threshold
+            .get() as i64;
         Self {
             nns_subnet_id: subnet_test_id(2),
             own_subnet_id: subnet_test_id(1),
@@ -1259,6 +1361,7 @@ impl Default for ExecutionTestBuilder {
             initial_canister_cycles: INITIAL_CANISTER_CYCLES,
             subnet_total_memory,
             subnet_message_memory,
+            subnet_memory_threshold,
             registry_settings: test_registry_settings(),
             manual_execution: false,
             rate_limiting_of_instructions: false,
@@ -1270,6 +1373,8 @@ impl Default for ExecutionTestBuilder {
             cost_to_compile_wasm_instruction: ic_config::execution_environment::Config::default()
                 .cost_to_compile_wasm_instruction
                 .get(),
+            max_query_call_depth: ic_config::execution_environment::Config::default()
+                .max_query_call_depth,
         }
     }
 }
@@ -1377,6 +1482,13 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_subnet_memory_threshold(self, subnet_memory_threshold: i64) -> Self {
+        Self {
+            subnet_memory_threshold,
+            ..self
+        }
+    }
+
     pub fn with_subnet_features(self, subnet_features: &str) -> Self {
         Self {
             subnet_features: String::from(subnet_features),
@@ -1450,6 +1562,11 @@ impl ExecutionTestBuilder {
         self
     }
 
+    pub fn with_max_query_call_depth(mut self, max_query_call_depth: usize) -> Self {
+        self.max_query_call_depth = max_query_call_depth;
+        self
+    }
+
     pub fn build(self) -> ExecutionTest {
         let tmpdir = tempfile::Builder::new().prefix("test").tempdir().unwrap();
 
@@ -1552,6 +1669,7 @@ impl ExecutionTestBuilder {
             allocatable_compute_capacity_in_percent: self.allocatable_compute_capacity_in_percent,
             subnet_memory_capacity: NumBytes::from(self.subnet_total_memory as u64),
             subnet_message_memory_capacity: NumBytes::from(self.subnet_message_memory as u64),
+            subnet_memory_threshold: NumBytes::from(self.subnet_memory_threshold as u64),
             bitcoin_canisters: self.bitcoin_canisters,
             cost_to_compile_wasm_instruction: self.cost_to_compile_wasm_instruction.into(),
             ..Config::default()
@@ -1586,10 +1704,15 @@ impl ExecutionTestBuilder {
             self.log,
             hypervisor,
             self.subnet_type,
-            Config::default(),
+            Config {
+                max_query_call_depth: self.max_query_call_depth,
+                ..Config::default()
+            },
             &metrics_registry,
             self.instruction_limit,
             Arc::clone(&cycles_account_manager),
+            new_query_stats_collector(Config::default().query_stats_epoch_length).0,
+            exec_env.message_instructions().clone(),
         );
         ExecutionTest {
             state: Some(state),
@@ -246,12 +246,23 @@ impl UpdateHelper {
         round: RoundContext,
         round_limits: &mut RoundLimits,
     ) -> ExecuteMessageResult {
+        let subnet_memory_saturation = round
+            .hypervisor
+            .subnet_memory_saturation(&round_limits.subnet_available_memory);
+        let memory_usage = self
+            .canister
+            .memory_usage(original.execution_parameters.subnet_type);
+        let compute_allocation = self.canister.scheduler_state.compute_allocation;
         apply_canister_state_changes(
             canister_state_changes,
             self.canister.execution_state.as_mut().unwrap(),
             &mut self.canister.system_state,
             &mut output,
             round_limits,
+            &subnet_memory_saturation,
+            memory_usage,
+            compute_allocation,
+            round.cycles_account_manager,
             round.time,
             round.network_topology,
             round.hypervisor.subnet_id(),
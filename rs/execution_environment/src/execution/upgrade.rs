@@ -17,13 +17,25 @@ use ic_interfaces::execution_environment::{HypervisorError, WasmExecutionOutput}
 use ic_interfaces::messages::RequestOrIngress;
 use ic_logger::info;
 use ic_replicated_state::{CanisterState, SystemState};
-use ic_system_api::ApiType;
+use ic_system_api::{ApiType, ExecutionParameters};
 use ic_types::methods::{FuncRef, SystemMethod, WasmMethod};
 use ic_types::NumInstructions;
 
 #[cfg(test)]
 mod tests;
 
+/// Returns the execution parameters for `canister_pre_upgrade` and
+/// `canister_post_upgrade`.
+///
+/// The Wasm memory limit is not enforced in these methods so that a canister
+/// close to its limit can still be upgraded, e.g. to a version that uses less
+/// memory. The `start()` method of the new code is subject to the limit.
+fn upgrade_hook_execution_parameters(helper: &InstallCodeHelper) -> ExecutionParameters {
+    let mut execution_parameters = helper.execution_parameters().clone();
+    execution_parameters.wasm_memory_limit = None;
+    execution_parameters
+}
+
 /// Performs a canister upgrade. The algorithm consists of six stages:
 /// - Stage 0: validate input and reserve execution cycles.
 /// - Stage 1: invoke `canister_pre_upgrade()` (if present) using the old code.
@@ -144,7 +156,7 @@ pub(crate) fn execute_upgrade(
             execution_state,
             &helper.canister().system_state,
             helper.canister_memory_usage(),
-            helper.execution_parameters().clone(),
+            upgrade_hook_execution_parameters(&helper),
             FuncRef::Method(method),
             round_limits,
             round.network_topology,
@@ -377,7 +389,7 @@ fn upgrade_stage_4a_call_post_upgrade(
         execution_state,
         &helper.canister().system_state,
         helper.canister_memory_usage(),
-        helper.execution_parameters().clone(),
+        upgrade_hook_execution_parameters(&helper),
         FuncRef::Method(method),
         round_limits,
         round.network_topology,
@@ -5,15 +5,14 @@ use crate::{
     },
     canister_settings::CanisterSettings,
     execution::{
-        heartbeat::execute_heartbeat, inspect_message,
-        nonreplicated_query::execute_non_replicated_query,
+        inspect_message, nonreplicated_query::execute_non_replicated_query,
         replicated_query::execute_replicated_query, response::execute_response,
-        update::execute_update,
+        system_task::execute_system_task, update::execute_update,
     },
     execution_environment_metrics::ExecutionEnvironmentMetrics,
     hypervisor::Hypervisor,
     util::candid_error_to_user_error,
-    NonReplicatedQueryKind,
+    MessageInstructionsCounter, NonReplicatedQueryKind,
 };
 use candid::Encode;
 use ic_base_types::PrincipalId;
@@ -24,11 +23,13 @@ use ic_crypto_tecdsa::derive_tecdsa_public_key;
 use ic_cycles_account_manager::{CyclesAccountManager, IngressInductionCost};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterHttpRequestArgs, CanisterIdRecord, CanisterSettingsArgs, CanisterStatusType,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterHttpRequestArgs, CanisterIdRecord,
+    CanisterInfoRequest, CanisterSettingsArgs, CanisterSnapshotArgs, CanisterStatusType,
     ComputeInitialEcdsaDealingsArgs, CreateCanisterArgs, ECDSAPublicKeyArgs,
-    ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, InstallCodeArgs, Method as Ic00Method,
-    Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
-    SetControllerArgs, SetupInitialDKGArgs, SignWithECDSAArgs, UpdateSettingsArgs, IC_00,
+    ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, FetchCanisterLogsRequest, InstallCodeArgs,
+    Method as Ic00Method, Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs,
+    ProvisionalTopUpCanisterArgs, SetControllerArgs, SetupInitialDKGArgs, SignWithECDSAArgs,
+    TakeCanisterSnapshotArgs, UpdateSettingsArgs, IC_00,
 };
 use ic_interfaces::execution_environment::{
     AvailableMemory, CanisterOutOfCyclesError, RegistryExecutionSettings,
@@ -63,6 +64,7 @@ use ic_types::{
         extract_effective_canister_id, AnonymousQuery, Payload, RejectContext, Request, Response,
         SignedIngressContent, StopCanisterContext,
     },
+    methods::SystemMethod,
     CanisterId, Cycles, NumBytes, NumInstructions, SubnetId, Time,
 };
 use ic_wasm_types::WasmHash;
@@ -255,11 +257,12 @@ pub struct ExecutionEnvironment {
     own_subnet_id: SubnetId,
     own_subnet_type: SubnetType,
     paused_execution_registry: Arc<Mutex<PausedExecutionRegistry>>,
+    message_instructions: MessageInstructionsCounter,
 }
 
-/// Errors when executing `canister_heartbeat`.
+/// Errors when executing `canister_heartbeat` or `canister_global_timer`.
 #[derive(Debug, Eq, PartialEq)]
-pub enum CanisterHeartbeatError {
+pub enum CanisterSystemTaskError {
     /// The canister isn't running.
     CanisterNotRunning {
         status: CanisterStatusType,
@@ -267,35 +270,36 @@ pub enum CanisterHeartbeatError {
 
     OutOfCycles(CanisterOutOfCyclesError),
 
-    /// Execution failed while executing the `canister_heartbeat`.
+    /// Execution failed while executing the `canister_heartbeat` or
+    /// `canister_global_timer`.
     CanisterExecutionFailed(HypervisorError),
 }
 
-impl std::fmt::Display for CanisterHeartbeatError {
+impl std::fmt::Display for CanisterSystemTaskError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            CanisterHeartbeatError::CanisterNotRunning { status } => write!(
+            CanisterSystemTaskError::CanisterNotRunning { status } => write!(
                 f,
                 "Canister in status {} instead of {}",
                 status,
                 CanisterStatusType::Running
             ),
-            CanisterHeartbeatError::OutOfCycles(err) => write!(f, "{}", err),
-            CanisterHeartbeatError::CanisterExecutionFailed(err) => write!(f, "{}", err),
+            CanisterSystemTaskError::OutOfCycles(err) => write!(f, "{}", err),
+            CanisterSystemTaskError::CanisterExecutionFailed(err) => write!(f, "{}", err),
         }
     }
 }
 
-impl CanisterHeartbeatError {
+impl CanisterSystemTaskError {
     /// Does this error come from a problem in the execution environment?
     /// Other errors could be caused by bad canister code.
     pub fn is_system_error(&self) -> bool {
         match self {
-            CanisterHeartbeatError::CanisterExecutionFailed(hypervisor_err) => {
+            CanisterSystemTaskError::CanisterExecutionFailed(hypervisor_err) => {
                 hypervisor_err.is_system_error()
             }
-            CanisterHeartbeatError::CanisterNotRunning { status: _ }
-            | CanisterHeartbeatError::OutOfCycles(_) => false,
+            CanisterSystemTaskError::CanisterNotRunning { status: _ }
+            | CanisterSystemTaskError::OutOfCycles(_) => false,
         }
     }
 }
@@ -348,9 +352,15 @@ impl ExecutionEnvironment {
             own_subnet_id,
             own_subnet_type,
             paused_execution_registry: Default::default(),
+            message_instructions: Default::default(),
         }
     }
 
+    /// Returns the counter of the instructions used by executed messages.
+    pub fn message_instructions(&self) -> &MessageInstructionsCounter {
+        &self.message_instructions
+    }
+
     /// Look up the current amount of memory available on the subnet.
     pub fn subnet_available_memory(&self, state: &ReplicatedState) -> AvailableMemory {
         AvailableMemory::new(
@@ -504,7 +514,7 @@ impl ExecutionEnvironment {
                                 let result = match CanisterSettings::try_from(settings) {
                                     Err(err) => Some((Err(err.into()), cycles)),
                                     Ok(settings) =>
-                                        Some(self.create_canister(*msg.sender(), cycles, settings, registry_settings.max_number_of_canisters, &mut state, registry_settings.subnet_size, round_limits))
+                                        Some(self.create_canister(*msg.sender(), msg.canister_change_origin(), cycles, settings, registry_settings.max_number_of_canisters, &mut state, registry_settings.subnet_size, round_limits))
                                 };
                                 info!(
                                     self.log,
@@ -526,7 +536,15 @@ impl ExecutionEnvironment {
                     Ok(args) => self
                         .canister_manager
                         .uninstall_code(args.get_canister_id(), *msg.sender(), &mut state)
-                        .map(|()| EmptyBlob.encode())
+                        .map(|()| {
+                            add_canister_change(
+                                &mut state,
+                                args.get_canister_id(),
+                                msg.canister_change_origin(),
+                                |_| CanisterChangeDetails::code_uninstall(),
+                            );
+                            EmptyBlob.encode()
+                        })
                         .map_err(|err| err.into()),
                 };
                 Some((res, msg.take_cycles()))
@@ -544,6 +562,7 @@ impl ExecutionEnvironment {
                             Err(err) => Err(err.into()),
                             Ok(settings) => self.update_settings(
                                 *msg.sender(),
+                                msg.canister_change_origin(),
                                 settings,
                                 canister_id,
                                 &mut state,
@@ -601,7 +620,15 @@ impl ExecutionEnvironment {
                             &mut state,
                             round_limits,
                         )
-                        .map(|()| EmptyBlob.encode())
+                        .map(|()| {
+                            add_canister_change(
+                                &mut state,
+                                args.get_canister_id(),
+                                msg.canister_change_origin(),
+                                CanisterChangeDetails::controllers_change,
+                            );
+                            EmptyBlob.encode()
+                        })
                         .map_err(|err| err.into()),
                 };
                 Some((res, msg.take_cycles()))
@@ -620,6 +647,113 @@ impl ExecutionEnvironment {
                 Some((res, msg.take_cycles()))
             }
 
+            Ok(Ic00Method::FetchCanisterLogs) => {
+                let res = match FetchCanisterLogsRequest::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => {
+                        self.fetch_canister_logs(*msg.sender(), args.get_canister_id(), &state)
+                    }
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::CanisterInfo) => match &msg {
+                RequestOrIngress::Request(_) => {
+                    let res = match CanisterInfoRequest::decode(payload) {
+                        Err(err) => Err(candid_error_to_user_error(err)),
+                        Ok(args) => self.get_canister_info(
+                            args.canister_id(),
+                            args.num_requested_changes(),
+                            &state,
+                        ),
+                    };
+                    Some((res, msg.take_cycles()))
+                }
+                RequestOrIngress::Ingress(_) => {
+                    self.reject_unexpected_ingress(Ic00Method::CanisterInfo)
+                }
+            },
+
+            Ok(Ic00Method::TakeCanisterSnapshot) => {
+                let res = match TakeCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .take_canister_snapshot(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.replace_snapshot(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::LoadCanisterSnapshot) => {
+                let res = match CanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .load_canister_snapshot(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.get_snapshot_id(),
+                            &mut state,
+                            registry_settings.subnet_size,
+                            round_limits,
+                        )
+                        .map(|taken_at_timestamp| {
+                            add_canister_change(
+                                &mut state,
+                                args.get_canister_id(),
+                                msg.canister_change_origin(),
+                                |_| {
+                                    CanisterChangeDetails::load_snapshot(
+                                        args.get_snapshot_id().to_vec(),
+                                        taken_at_timestamp.as_nanos_since_unix_epoch(),
+                                    )
+                                },
+                            );
+                            EmptyBlob.encode()
+                        })
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::ListCanisterSnapshots) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .list_canister_snapshots(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::DeleteCanisterSnapshot) => {
+                let res = match CanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .delete_canister_snapshot(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.get_snapshot_id(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
             Ok(Ic00Method::StartCanister) => {
                 let res = match CanisterIdRecord::decode(payload) {
                     Err(err) => Err(candid_error_to_user_error(err)),
@@ -829,7 +963,15 @@ impl ExecutionEnvironment {
                                     registry_settings.max_number_of_canisters,
                                     round_limits,
                                 )
-                                .map(|canister_id| CanisterIdRecord::from(canister_id).encode())
+                                .map(|canister_id| {
+                                    add_canister_change(
+                                        &mut state,
+                                        canister_id,
+                                        msg.canister_change_origin(),
+                                        CanisterChangeDetails::canister_creation,
+                                    );
+                                    CanisterIdRecord::from(canister_id).encode()
+                                })
                                 .map_err(|err| err.into()),
                             Err(err) => Err(err.into()),
                         }
@@ -1021,10 +1163,12 @@ impl ExecutionEnvironment {
         }
     }
 
-    /// Executes a heartbeat of a given canister.
-    pub fn execute_canister_heartbeat(
+    /// Executes a system task (`canister_heartbeat` or `canister_global_timer`)
+    /// of a given canister.
+    pub fn execute_canister_system_task(
         &self,
         canister: CanisterState,
+        system_task: SystemMethod,
         instruction_limits: InstructionLimits,
         network_topology: Arc<NetworkTopology>,
         time: Time,
@@ -1033,9 +1177,9 @@ impl ExecutionEnvironment {
     ) -> (
         CanisterState,
         NumInstructions,
-        Result<NumBytes, CanisterHeartbeatError>,
+        Result<NumBytes, CanisterSystemTaskError>,
     ) {
-        // A heartbeat is expected to finish quickly, so DTS is not supported for it.
+        // A system task is expected to finish quickly, so DTS is not supported for it.
         let instruction_limits = InstructionLimits::new(
             FlagStatus::Disabled,
             instruction_limits.slice(),
@@ -1043,8 +1187,9 @@ impl ExecutionEnvironment {
         );
         let execution_parameters =
             self.execution_parameters(&canister, instruction_limits, ExecutionMode::Replicated);
-        let (canister, instructions_used, result) = execute_heartbeat(
+        let (canister, instructions_used, result) = execute_system_task(
             canister,
+            system_task.clone(),
             network_topology,
             execution_parameters,
             self.own_subnet_type,
@@ -1064,7 +1209,8 @@ impl ExecutionEnvironment {
                 if log_count < LOG_FIRST_N_HEARTBEAT || log_count % LOG_ONE_HEARTBEAT_OUT_OF == 0 {
                     warn!(
                         self.log,
-                        "Error executing heartbeat on canister {} with failure `{}`",
+                        "Error executing {} on canister {} with failure `{}`",
+                        system_task,
                         canister.canister_id(),
                         err;
                         messaging.canister_id => canister.canister_id().to_string(),
@@ -1100,15 +1246,18 @@ impl ExecutionEnvironment {
         ExecutionParameters {
             instruction_limits,
             canister_memory_limit: canister.memory_limit(self.config.max_canister_memory_size),
+            wasm_memory_limit: canister.system_state.wasm_memory_limit,
             compute_allocation: canister.scheduler_state.compute_allocation,
             subnet_type: self.own_subnet_type,
             execution_mode,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn create_canister(
         &self,
         sender: PrincipalId,
+        origin: CanisterChangeOrigin,
         cycles: Cycles,
         settings: CanisterSettings,
         max_number_of_canisters: u64,
@@ -1129,8 +1278,16 @@ impl ExecutionEnvironment {
                     round_limits,
                 );
                 (
-                    res.map(|new_canister_id| CanisterIdRecord::from(new_canister_id).encode())
-                        .map_err(|err| err.into()),
+                    res.map(|new_canister_id| {
+                        add_canister_change(
+                            state,
+                            new_canister_id,
+                            origin,
+                            CanisterChangeDetails::canister_creation,
+                        );
+                        CanisterIdRecord::from(new_canister_id).encode()
+                    })
+                    .map_err(|err| err.into()),
                     cycles,
                 )
             }
@@ -1141,16 +1298,27 @@ impl ExecutionEnvironment {
     fn update_settings(
         &self,
         sender: PrincipalId,
+        origin: CanisterChangeOrigin,
         settings: CanisterSettings,
         canister_id: CanisterId,
         state: &mut ReplicatedState,
         round_limits: &mut RoundLimits,
     ) -> Result<Vec<u8>, UserError> {
+        let controllers_changed =
+            settings.controller().is_some() || settings.controllers().is_some();
         let canister = get_canister_mut(canister_id, state)?;
         self.canister_manager
             .update_settings(sender, settings, canister, round_limits)
-            .map(|()| EmptyBlob.encode())
-            .map_err(|err| err.into())
+            .map_err(UserError::from)?;
+        if controllers_changed {
+            add_canister_change(
+                state,
+                canister_id,
+                origin,
+                CanisterChangeDetails::controllers_change,
+            );
+        }
+        Ok(EmptyBlob.encode())
     }
 
     fn start_canister(
@@ -1211,6 +1379,44 @@ impl ExecutionEnvironment {
             .map_err(|err| err.into())
     }
 
+    fn fetch_canister_logs(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<Vec<u8>, UserError> {
+        let canister = state.canister_state(&canister_id).ok_or_else(|| {
+            UserError::new(
+                ErrorCode::CanisterNotFound,
+                format!("Canister {} not found.", &canister_id),
+            )
+        })?;
+
+        self.canister_manager
+            .fetch_canister_logs(sender, canister)
+            .map(|response| response.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn get_canister_info(
+        &self,
+        canister_id: CanisterId,
+        num_requested_changes: Option<u64>,
+        state: &ReplicatedState,
+    ) -> Result<Vec<u8>, UserError> {
+        let canister = state.canister_state(&canister_id).ok_or_else(|| {
+            UserError::new(
+                ErrorCode::CanisterNotFound,
+                format!("Canister {} not found.", &canister_id),
+            )
+        })?;
+
+        Ok(self
+            .canister_manager
+            .get_canister_info(canister, num_requested_changes)
+            .encode())
+    }
+
     fn stop_canister(
         &self,
         canister_id: CanisterId,
@@ -1910,6 +2116,7 @@ impl ExecutionEnvironment {
             .unwrap();
         match task {
             ExecutionTask::Heartbeat
+            | ExecutionTask::GlobalTimer
             | ExecutionTask::PausedExecution(_)
             | ExecutionTask::AbortedExecution(_) => {
                 panic!(
@@ -1983,7 +2190,8 @@ impl ExecutionEnvironment {
                 .map(|task| match task {
                     ExecutionTask::AbortedExecution(..)
                     | ExecutionTask::AbortedInstallCode(..)
-                    | ExecutionTask::Heartbeat => task,
+                    | ExecutionTask::Heartbeat
+                    | ExecutionTask::GlobalTimer => task,
                     ExecutionTask::PausedExecution(id) => {
                         let paused = self.take_paused_execution(id).unwrap();
                         let message = paused.abort();
@@ -2139,6 +2347,24 @@ fn get_canister_mut(
     }
 }
 
+/// Records a change in the history of the given canister, tagged with the
+/// current time and canister version. The details of the change are built
+/// from the current controllers of the canister.
+fn add_canister_change(
+    state: &mut ReplicatedState,
+    canister_id: CanisterId,
+    origin: CanisterChangeOrigin,
+    details: impl FnOnce(Vec<PrincipalId>) -> CanisterChangeDetails,
+) {
+    let time = state.time();
+    if let Some(canister) = state.canister_state_mut(&canister_id) {
+        let controllers = canister.system_state.controllers.iter().copied().collect();
+        canister
+            .system_state
+            .add_canister_change(time, origin, details(controllers));
+    }
+}
+
 /// The result of `execute_canister()`.
 pub struct ExecuteCanisterResult {
     pub canister: CanisterState,
@@ -2207,9 +2433,14 @@ pub fn execute_canister(
 
     match canister.system_state.task_queue.pop_front() {
         Some(task) => match task {
-            ExecutionTask::Heartbeat => {
-                let (canister, instructions_used, result) = exec_env.execute_canister_heartbeat(
+            ExecutionTask::Heartbeat | ExecutionTask::GlobalTimer => {
+                let (system_task, description) = match task {
+                    ExecutionTask::Heartbeat => (SystemMethod::CanisterHeartbeat, "heartbeat"),
+                    _ => (SystemMethod::CanisterGlobalTimer, "global timer"),
+                };
+                let (canister, instructions_used, result) = exec_env.execute_canister_system_task(
                     canister,
+                    system_task,
                     instruction_limits,
                     network_topology,
                     time,
@@ -2222,7 +2453,7 @@ pub fn execute_canister(
                     instructions_used: Some(instructions_used),
                     heap_delta,
                     ingress_status: None,
-                    description: Some("heartbeat".to_string()),
+                    description: Some(description.to_string()),
                 }
             }
             ExecutionTask::PausedExecution(id) => {
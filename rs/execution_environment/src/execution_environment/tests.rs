@@ -7,16 +7,19 @@ use crate::execution::test_utilities::{
 use ic_base_types::{NumBytes, NumSeconds};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    self as ic00, CanisterHttpRequestArgs, CanisterIdRecord, CanisterStatusResultV2,
-    CanisterStatusType, EcdsaCurve, EcdsaKeyId, EmptyBlob, HttpMethod, Method,
-    Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
-    TransformFunc, TransformType, IC_00,
+    self as ic00, CanisterChange, CanisterChangeDetails, CanisterChangeOrigin,
+    CanisterHttpRequestArgs, CanisterIdRecord, CanisterInfoRequest, CanisterInfoResponse,
+    CanisterInstallMode, CanisterSettingsArgs, CanisterSnapshotResponse, CanisterStatusResultV2,
+    CanisterStatusType, EcdsaCurve, EcdsaKeyId, EmptyBlob, FetchCanisterLogsResponse, HttpMethod,
+    ListCanisterSnapshotsResponse, LogVisibility, Method, Payload as Ic00Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, TransformFunc,
+    TransformType, IC_00,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::{DEFAULT_QUEUE_CAPACITY, WASM_PAGE_SIZE_IN_BYTES},
     testing::{CanisterQueuesTesting, SystemStateTesting},
-    CanisterStatus, SystemState,
+    CanisterStatus, CanisterTimer, SystemState,
 };
 use ic_test_utilities::{assert_utils::assert_balance_equals, mock_time};
 use ic_test_utilities_metrics::{fetch_histogram_vec_count, metric_vec};
@@ -26,7 +29,7 @@ use ic_types::{
     messages::{
         CallbackId, Payload, RejectContext, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
     },
-    CanisterId, Cycles, RegistryVersion,
+    CanisterId, Cycles, NumInstructions, RegistryVersion, Time,
 };
 use ic_types_test_utils::ids::{canister_test_id, node_test_id, subnet_test_id, user_test_id};
 use ic_universal_canister::{call_args, wasm};
@@ -2008,3 +2011,425 @@ fn bitcoin_get_successors_cannot_be_called_by_non_bitcoin_canisters() {
     let result = test.ingress(uni, "update", call).unwrap();
     assert_eq!(result, WasmResult::Reject("Permission denied.".to_string()));
 }
+
+const LOGGING_CANISTER_WAT: &str = r#"
+    (module
+        (import "ic0" "debug_print" (func $debug_print (param i32) (param i32)))
+        (import "ic0" "trap" (func $trap (param i32) (param i32)))
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (func (export "canister_update print")
+            (call $debug_print (i32.const 0) (i32.const 5))
+            (call $msg_reply)
+        )
+        (func (export "canister_update trap")
+            (call $debug_print (i32.const 5) (i32.const 6))
+            (call $trap (i32.const 11) (i32.const 4))
+        )
+        (data (i32.const 0) "hellobeforeoops")
+        (memory 1 1)
+    )"#;
+
+fn fetch_log_contents(test: &mut ExecutionTest, canister_id: CanisterId) -> Vec<String> {
+    let reply = get_reply(test.fetch_canister_logs(canister_id));
+    FetchCanisterLogsResponse::decode(&reply)
+        .unwrap()
+        .canister_log_records
+        .into_iter()
+        .map(|record| String::from_utf8(record.content).unwrap())
+        .collect()
+}
+
+#[test]
+fn debug_print_and_trap_are_recorded_in_canister_log() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(LOGGING_CANISTER_WAT).unwrap();
+    test.ingress(canister_id, "print", vec![]).unwrap();
+    let err = test.ingress(canister_id, "trap", vec![]).unwrap_err();
+    assert_eq!(ErrorCode::CanisterCalledTrap, err.code());
+    assert_eq!(
+        fetch_log_contents(&mut test, canister_id),
+        vec!["hello", "before", "[TRAP]: oops"]
+    );
+    let records = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_log
+        .records()
+        .iter()
+        .map(|record| record.idx)
+        .collect::<Vec<_>>();
+    assert_eq!(records, vec![0, 1, 2]);
+}
+
+#[test]
+fn fetch_canister_logs_is_restricted_to_controllers() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(LOGGING_CANISTER_WAT).unwrap();
+    test.ingress(canister_id, "print", vec![]).unwrap();
+    let controller = test.user_id();
+
+    test.set_user_id(user_test_id(42));
+    let err = test.fetch_canister_logs(canister_id).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidController, err.code());
+
+    test.set_user_id(controller);
+    let payload = ic00::UpdateSettingsArgs {
+        canister_id: canister_id.into(),
+        settings: CanisterSettingsArgs {
+            log_visibility: Some(LogVisibility::Public),
+            ..Default::default()
+        },
+    }
+    .encode();
+    test.subnet_message(Method::UpdateSettings, payload)
+        .unwrap();
+
+    test.set_user_id(user_test_id(42));
+    assert_eq!(fetch_log_contents(&mut test, canister_id), vec!["hello"]);
+}
+
+const COUNTER_CANISTER_WAT: &str = r#"
+(module
+    (import "ic0" "msg_reply" (func $msg_reply))
+    (import "ic0" "msg_reply_data_append"
+        (func $msg_reply_data_append (param i32 i32)))
+    (import "ic0" "certified_data_set"
+        (func $certified_data_set (param i32 i32)))
+    (func (export "canister_update inc")
+        (i32.store (i32.const 0) (i32.add (i32.load (i32.const 0)) (i32.const 1)))
+        (call $certified_data_set (i32.const 0) (i32.const 4))
+        (call $msg_reply)
+    )
+    (func (export "canister_update read")
+        (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+        (call $msg_reply)
+    )
+    (memory 1)
+)"#;
+
+fn read_counter(test: &mut ExecutionTest, canister_id: CanisterId) -> u32 {
+    let reply = get_reply(test.ingress(canister_id, "read", vec![]));
+    u32::from_le_bytes(reply.try_into().unwrap())
+}
+
+fn list_snapshots(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+) -> Vec<CanisterSnapshotResponse> {
+    let reply = get_reply(test.list_canister_snapshots(canister_id));
+    ListCanisterSnapshotsResponse::decode(&reply).unwrap().0
+}
+
+/// Stops the canister, loads the snapshot into it and starts it again.
+fn load_snapshot_and_restart(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    snapshot_id: Vec<u8>,
+) -> Result<WasmResult, UserError> {
+    test.stop_canister(canister_id);
+    test.process_stopping_canisters();
+    let result = test.load_canister_snapshot(canister_id, snapshot_id);
+    test.start_canister(canister_id).unwrap();
+    result
+}
+
+#[test]
+fn load_canister_snapshot_restores_canister_state() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_CANISTER_WAT).unwrap();
+    test.ingress(canister_id, "inc", vec![]).unwrap();
+
+    let reply = get_reply(test.take_canister_snapshot(canister_id, None));
+    let snapshot = CanisterSnapshotResponse::decode(&reply).unwrap();
+    assert_eq!(
+        list_snapshots(&mut test, canister_id),
+        vec![snapshot.clone()]
+    );
+    let certified_data = test
+        .canister_state(canister_id)
+        .system_state
+        .certified_data
+        .clone();
+
+    test.ingress(canister_id, "inc", vec![]).unwrap();
+    test.ingress(canister_id, "inc", vec![]).unwrap();
+    assert_eq!(read_counter(&mut test, canister_id), 3);
+
+    let result = load_snapshot_and_restart(&mut test, canister_id, snapshot.id.clone());
+    assert_empty_reply(result);
+    assert_eq!(read_counter(&mut test, canister_id), 1);
+    assert_eq!(
+        test.canister_state(canister_id).system_state.certified_data,
+        certified_data
+    );
+
+    // The snapshot is kept after loading it and can be loaded again.
+    test.ingress(canister_id, "inc", vec![]).unwrap();
+    assert_empty_reply(load_snapshot_and_restart(
+        &mut test,
+        canister_id,
+        snapshot.id.clone(),
+    ));
+    assert_eq!(read_counter(&mut test, canister_id), 1);
+
+    assert_empty_reply(test.delete_canister_snapshot(canister_id, snapshot.id.clone()));
+    assert_eq!(list_snapshots(&mut test, canister_id), vec![]);
+    let err = load_snapshot_and_restart(&mut test, canister_id, snapshot.id).unwrap_err();
+    assert_eq!(ErrorCode::CanisterSnapshotNotFound, err.code());
+}
+
+#[test]
+fn load_canister_snapshot_charges_for_compilation() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_rate_limiting_of_instructions()
+        .build();
+    let canister_id = test.canister_from_wat(COUNTER_CANISTER_WAT).unwrap();
+    let reply = get_reply(test.take_canister_snapshot(canister_id, None));
+    let snapshot = CanisterSnapshotResponse::decode(&reply).unwrap();
+    // Pay off the debit of the installation.
+    test.canister_state_mut(canister_id)
+        .scheduler_state
+        .install_code_debit = NumInstructions::new(0);
+
+    test.stop_canister(canister_id);
+    test.process_stopping_canisters();
+
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+    assert_empty_reply(test.load_canister_snapshot(canister_id, snapshot.id.clone()));
+    assert!(test.canister_state(canister_id).system_state.balance() < balance_before);
+    assert!(
+        test.canister_state(canister_id)
+            .scheduler_state
+            .install_code_debit
+            .get()
+            > 0
+    );
+
+    // Like `install_code`, loading snapshots is rate limited until the debit
+    // is paid off.
+    let err = test
+        .load_canister_snapshot(canister_id, snapshot.id)
+        .unwrap_err();
+    assert_eq!(ErrorCode::CanisterInstallCodeRateLimited, err.code());
+}
+
+#[test]
+fn load_canister_snapshot_requires_stopped_canister() {
+    let mut test = ExecutionTestBuilder::new().with_manual_execution().build();
+    let canister_id = test.universal_canister().unwrap();
+    let callee_id = test.universal_canister().unwrap();
+    let reply = get_reply(test.take_canister_snapshot(canister_id, None));
+    let snapshot = CanisterSnapshotResponse::decode(&reply).unwrap();
+
+    let err = test
+        .load_canister_snapshot(canister_id, snapshot.id.clone())
+        .unwrap_err();
+    assert_eq!(ErrorCode::CanisterNotStopped, err.code());
+
+    // A canister with an outstanding call cannot finish stopping, so the
+    // snapshot cannot be loaded until the response has been delivered.
+    let call = wasm()
+        .call_simple(callee_id.get(), "update", call_args())
+        .build();
+    test.ingress_raw(canister_id, "update", call);
+    test.execute_message(canister_id);
+    test.stop_canister(canister_id);
+    test.process_stopping_canisters();
+    assert_eq!(
+        test.canister_state(canister_id).status(),
+        CanisterStatusType::Stopping
+    );
+    let err = test
+        .load_canister_snapshot(canister_id, snapshot.id.clone())
+        .unwrap_err();
+    assert_eq!(ErrorCode::CanisterNotStopped, err.code());
+
+    test.induct_messages();
+    test.execute_message(callee_id);
+    test.induct_messages();
+    test.execute_message(canister_id);
+    test.process_stopping_canisters();
+    assert_eq!(
+        test.canister_state(canister_id).status(),
+        CanisterStatusType::Stopped
+    );
+    assert_empty_reply(test.load_canister_snapshot(canister_id, snapshot.id));
+}
+
+#[test]
+fn load_canister_snapshot_resets_timer_and_records_change() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_CANISTER_WAT).unwrap();
+    let reply = get_reply(test.take_canister_snapshot(canister_id, None));
+    let snapshot = CanisterSnapshotResponse::decode(&reply).unwrap();
+    test.canister_state_mut(canister_id)
+        .system_state
+        .global_timer = CanisterTimer::Active(Time::from_nanos_since_unix_epoch(1));
+    let version_before = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_version;
+
+    test.stop_canister(canister_id);
+    test.process_stopping_canisters();
+    assert_empty_reply(test.load_canister_snapshot(canister_id, snapshot.id.clone()));
+
+    let system_state = &test.canister_state(canister_id).system_state;
+    assert_eq!(system_state.global_timer, CanisterTimer::Inactive);
+    assert_eq!(system_state.canister_version, version_before + 1);
+    let change = system_state
+        .canister_history
+        .get_changes(1)
+        .next()
+        .unwrap()
+        .clone();
+    assert_eq!(
+        change,
+        CanisterChange::new(
+            test.state().time().as_nanos_since_unix_epoch(),
+            version_before + 1,
+            CanisterChangeOrigin::from_user(test.user_id().get()),
+            CanisterChangeDetails::load_snapshot(snapshot.id, snapshot.taken_at_timestamp),
+        )
+    );
+}
+
+#[test]
+fn take_canister_snapshot_respects_limit_and_replaces_snapshots() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_CANISTER_WAT).unwrap();
+
+    let reply = get_reply(test.take_canister_snapshot(canister_id, None));
+    let first = CanisterSnapshotResponse::decode(&reply).unwrap();
+    let err = test.take_canister_snapshot(canister_id, None).unwrap_err();
+    assert_eq!(ErrorCode::CanisterContractViolation, err.code());
+
+    let reply = get_reply(test.take_canister_snapshot(canister_id, Some(first.id.clone())));
+    let second = CanisterSnapshotResponse::decode(&reply).unwrap();
+    assert_ne!(first.id, second.id);
+    assert_eq!(list_snapshots(&mut test, canister_id), vec![second.clone()]);
+    assert_eq!(
+        test.state().canister_snapshots.memory_taken(),
+        NumBytes::from(second.total_size)
+    );
+
+    // The snapshots are deleted together with the canister.
+    test.stop_canister(canister_id);
+    test.process_stopping_canisters();
+    let payload = CanisterIdRecord::from(canister_id).encode();
+    test.subnet_message(Method::DeleteCanister, payload)
+        .unwrap();
+    assert!(test.state().canister_snapshots.is_empty());
+}
+
+#[test]
+fn canister_snapshots_are_restricted_to_controllers() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_CANISTER_WAT).unwrap();
+    let reply = get_reply(test.take_canister_snapshot(canister_id, None));
+    let snapshot = CanisterSnapshotResponse::decode(&reply).unwrap();
+
+    test.set_user_id(user_test_id(42));
+    let err = test.take_canister_snapshot(canister_id, None).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidController, err.code());
+    let err = test.list_canister_snapshots(canister_id).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidController, err.code());
+    let err = test
+        .load_canister_snapshot(canister_id, snapshot.id.clone())
+        .unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidController, err.code());
+    let err = test
+        .delete_canister_snapshot(canister_id, snapshot.id)
+        .unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidController, err.code());
+}
+
+#[test]
+fn take_canister_snapshot_fails_for_empty_canister() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let err = test.take_canister_snapshot(canister_id, None).unwrap_err();
+    assert_eq!(ErrorCode::CanisterWasmModuleNotFound, err.code());
+}
+
+#[test]
+fn canister_info_returns_recent_canister_changes() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let user = test.user_id().get();
+    let caller = test.universal_canister().unwrap();
+    let canister = test.create_canister(Cycles::new(1_000_000_000_000));
+    let binary = wabt::wat2wasm("(module)").unwrap();
+    test.install_canister(canister, binary).unwrap();
+    let module_hash = test
+        .execution_state(canister)
+        .wasm_binary
+        .binary
+        .module_hash();
+    test.uninstall_code(canister).unwrap();
+    test.set_controller(canister, caller.get()).unwrap();
+
+    let canister_info_args = CanisterInfoRequest::new(canister, Some(3)).encode();
+    let get_canister_info = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::CanisterInfo,
+            call_args().other_side(canister_info_args),
+        )
+        .build();
+    let result = test.ingress(caller, "update", get_canister_info);
+    let info = CanisterInfoResponse::decode(&get_reply(result)).unwrap();
+    assert_eq!(info.total_num_changes(), 4);
+    assert_eq!(info.module_hash(), None);
+    assert_eq!(info.controllers(), &[caller.get()]);
+    let changes: Vec<_> = info
+        .changes()
+        .iter()
+        .map(|change| {
+            (
+                change.canister_version(),
+                change.origin().clone(),
+                change.details().clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (
+                1,
+                CanisterChangeOrigin::from_user(user),
+                CanisterChangeDetails::code_deployment(CanisterInstallMode::Install, module_hash),
+            ),
+            (
+                2,
+                CanisterChangeOrigin::from_user(user),
+                CanisterChangeDetails::code_uninstall(),
+            ),
+            (
+                3,
+                CanisterChangeOrigin::from_user(user),
+                CanisterChangeDetails::controllers_change(vec![caller.get()]),
+            ),
+        ]
+    );
+
+    // The creation is still recorded in the state, just not requested.
+    let history = &test.canister_state(canister).system_state.canister_history;
+    assert_eq!(
+        history.changes().front().unwrap().details(),
+        &CanisterChangeDetails::canister_creation(vec![user])
+    );
+}
+
+#[test]
+fn canister_info_cannot_be_called_via_ingress() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister = test.universal_canister().unwrap();
+    let err = test
+        .subnet_message(
+            Method::CanisterInfo,
+            CanisterInfoRequest::new(canister, None).encode(),
+        )
+        .unwrap_err();
+    assert_eq!(ErrorCode::CanisterContractViolation, err.code());
+}
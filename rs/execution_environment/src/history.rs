@@ -258,6 +258,7 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         CanisterWasmModuleNotFound => "Canister WASM Module Not Found",
         CanisterNonEmpty => "Canister Non-Empty",
         CanisterEmpty => "Canister Empty",
+        CanisterSnapshotNotFound => "Canister Snapshot Not Found",
         CanisterOutOfCycles => "Canister Out Of Cycles",
         CanisterTrapped => "Canister Trapped",
         CanisterCalledTrap => "Canister Called Trap",
@@ -288,5 +289,13 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         CanisterInstallCodeRateLimited => {
             "Canister is rate limited because it executed too many instructions in the previous install_code messages"
         }
+        QueryCallGraphTooDeep => "Composite query call graph exceeded the maximum depth",
+        QueryCallGraphTotalInstructionLimitExceeded => {
+            "Composite query call graph exceeded the instruction limit"
+        }
+        CanisterWasmMemoryLimitExceeded => "Canister exceeded its Wasm memory limit",
+        ReservedCyclesLimitExceededInMemoryAllocation => {
+            "Canister cannot allocate memory because its reserved cycles limit is exceeded"
+        }
     }
 }
@@ -2,12 +2,14 @@ use ic_canister_sandbox_replica_controller::sandboxed_execution_controller::Sand
 use ic_config::embedders::FeatureFlags;
 use ic_config::flag_status::FlagStatus;
 use ic_config::{embedders::Config as EmbeddersConfig, execution_environment::Config};
-use ic_cycles_account_manager::CyclesAccountManager;
+use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_embedders::wasm_executor::{WasmExecutionResult, WasmExecutor};
 use ic_embedders::wasm_utils::decoding::decoded_wasm_size;
 use ic_embedders::{wasm_executor::WasmExecutorImpl, WasmExecutionInput, WasmtimeEmbedder};
 use ic_embedders::{CompilationCache, CompilationResult};
-use ic_interfaces::execution_environment::{HypervisorResult, WasmExecutionOutput};
+use ic_interfaces::execution_environment::{
+    HypervisorResult, SubnetAvailableMemory, WasmExecutionOutput,
+};
 use ic_logger::{fatal, ReplicaLogger};
 use ic_metrics::buckets::decimal_buckets_with_zero;
 use ic_metrics::{buckets::exponential_buckets, MetricsRegistry};
@@ -124,6 +126,8 @@ pub struct Hypervisor {
     compilation_cache: Arc<CompilationCache>,
     deterministic_time_slicing: FlagStatus,
     cost_to_compile_wasm_instruction: NumInstructions,
+    subnet_memory_capacity: NumBytes,
+    subnet_memory_threshold: NumBytes,
 }
 
 impl Hypervisor {
@@ -135,6 +139,20 @@ impl Hypervisor {
         self.own_subnet_type
     }
 
+    /// Returns the subnet memory usage relative to the threshold above which
+    /// canisters reserve cycles for storage when they allocate memory.
+    pub(crate) fn subnet_memory_saturation(
+        &self,
+        subnet_available_memory: &SubnetAvailableMemory,
+    ) -> ResourceSaturation {
+        let available = subnet_available_memory.get_total_memory().max(0) as u64;
+        ResourceSaturation::new(
+            self.subnet_memory_capacity.get().saturating_sub(available),
+            self.subnet_memory_threshold.get(),
+            self.subnet_memory_capacity.get(),
+        )
+    }
+
     // A helper that converts a Wasm execution output to an execution
     // result of `execution_canister_*` functions.
     //
@@ -160,7 +178,12 @@ impl Hypervisor {
                 let bytes = NumBytes::from((output.instance_stats.dirty_pages * PAGE_SIZE) as u64);
                 (output_system_state, Ok(bytes))
             }
-            Err(err) => (old_system_state, Err(err)),
+            Err(err) => {
+                // The canister log is preserved even if the execution failed.
+                let mut system_state = old_system_state;
+                system_state.canister_log = output_system_state.canister_log;
+                (system_state, Err(err))
+            }
         };
         let canister =
             CanisterState::from_parts(Some(execution_state), system_state, scheduler_state);
@@ -229,6 +252,8 @@ impl Hypervisor {
         embedder_config.feature_flags.rate_limiting_of_debug_prints =
             config.rate_limiting_of_debug_prints;
         embedder_config.feature_flags.module_sharing = config.module_sharing;
+        embedder_config.feature_flags.wasm64 = config.wasm64;
+        embedder_config.max_wasm64_memory_size = config.max_wasm64_memory_size;
         embedder_config.cost_to_compile_wasm_instruction = config.cost_to_compile_wasm_instruction;
 
         let wasm_executor: Arc<dyn WasmExecutor> = match config.canister_sandboxing_flag {
@@ -261,6 +286,8 @@ impl Hypervisor {
             compilation_cache: Arc::new(CompilationCache::new(config.module_sharing)),
             deterministic_time_slicing: config.deterministic_time_slicing,
             cost_to_compile_wasm_instruction: config.cost_to_compile_wasm_instruction,
+            subnet_memory_capacity: config.subnet_memory_capacity,
+            subnet_memory_threshold: config.subnet_memory_threshold,
         }
     }
 
@@ -274,6 +301,8 @@ impl Hypervisor {
         wasm_executor: Arc<dyn WasmExecutor>,
         deterministic_time_slicing: FlagStatus,
         cost_to_compile_wasm_instruction: NumInstructions,
+        subnet_memory_capacity: NumBytes,
+        subnet_memory_threshold: NumBytes,
     ) -> Self {
         Self {
             wasm_executor,
@@ -287,6 +316,8 @@ impl Hypervisor {
             )),
             deterministic_time_slicing,
             cost_to_compile_wasm_instruction,
+            subnet_memory_capacity,
+            subnet_memory_threshold,
         }
     }
 
@@ -315,6 +346,7 @@ impl Hypervisor {
             execution_parameters.instruction_limits.message(),
             execution_parameters.instruction_limits.slice()
         );
+        let compute_allocation = execution_parameters.compute_allocation;
         let execution_result = self.execute_dts(
             api_type,
             &execution_state,
@@ -334,12 +366,18 @@ impl Hypervisor {
             }
         };
         update_round_limits(round_limits, &slice);
+        let subnet_memory_saturation =
+            self.subnet_memory_saturation(&round_limits.subnet_available_memory);
         apply_canister_state_changes(
             canister_state_changes,
             &mut execution_state,
             &mut system_state,
             &mut output,
             round_limits,
+            &subnet_memory_saturation,
+            canister_current_memory_usage,
+            compute_allocation,
+            &self.cycles_account_manager,
             time,
             network_topology,
             self.own_subnet_id,
@@ -5,7 +5,7 @@ use crate::execution::test_utilities::{
 use assert_matches::assert_matches;
 use candid::{Decode, Encode};
 use ic_error_types::{ErrorCode, RejectCode};
-use ic_ic00_types::CanisterHttpResponsePayload;
+use ic_ic00_types::{CanisterHttpResponsePayload, CanisterStatusResultV2, Payload};
 use ic_interfaces::execution_environment::{AvailableMemory, HypervisorError};
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
 use ic_registry_subnet_type::SubnetType;
@@ -14,7 +14,7 @@ use ic_replicated_state::testing::CanisterQueuesTesting;
 use ic_replicated_state::CanisterStatus;
 use ic_replicated_state::{
     canister_state::execution_state::CustomSectionType, page_map::MemoryRegion, ExportedFunctions,
-    Global, PageIndex,
+    Global, NumWasmPages, PageIndex,
 };
 use ic_sys::PAGE_SIZE;
 use ic_test_utilities::assert_utils::assert_balance_equals;
@@ -52,6 +52,88 @@ fn ic0_canister_status_works() {
     assert_empty_reply(result);
 }
 
+#[test]
+fn ic0_is_controller_works() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_arg_data_size"
+                (func $msg_arg_data_size (result i32))
+            )
+            (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param i32 i32 i32))
+            )
+            (import "ic0" "is_controller"
+                (func $is_controller (param i32 i32) (result i32))
+            )
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32))
+            )
+            (func (export "canister_update test")
+                (call $msg_arg_data_copy (i32.const 100) (i32.const 0) (call $msg_arg_data_size))
+                (i32.store
+                    (i32.const 0)
+                    (call $is_controller (i32.const 100) (call $msg_arg_data_size))
+                )
+                (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+                (call $msg_reply)
+            )
+            (memory 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let controller = test.user_id().get().to_vec();
+    let result = test.ingress(canister_id, "test", controller);
+    assert_eq!(get_reply(result), vec![1, 0, 0, 0]);
+    let result = test.ingress(canister_id, "test", canister_id.get().to_vec());
+    assert_eq!(get_reply(result), vec![0, 0, 0, 0]);
+    let err = test.ingress(canister_id, "test", vec![1; 30]).unwrap_err();
+    assert_eq!(ErrorCode::CanisterTrapped, err.code());
+}
+
+#[test]
+fn ic0_canister_version_is_incremented_on_changes() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "canister_version"
+                (func $canister_version (result i64))
+            )
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32))
+            )
+            (func (export "canister_update test")
+                (i64.store (i32.const 0) (call $canister_version))
+                (call $msg_reply_data_append (i32.const 0) (i32.const 8))
+                (call $msg_reply)
+            )
+            (memory 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let version = |test: &mut ExecutionTest| {
+        let reply = get_reply(test.ingress(canister_id, "test", vec![]));
+        u64::from_le_bytes(reply.try_into().unwrap())
+    };
+    // The canister has been created and then installed.
+    assert_eq!(version(&mut test), 1);
+    // Executing messages does not change the version.
+    assert_eq!(version(&mut test), 1);
+    test.update_freezing_threshold(canister_id, 100.into())
+        .unwrap();
+    assert_eq!(version(&mut test), 2);
+    test.upgrade_canister(canister_id, wabt::wat2wasm(wat).unwrap())
+        .unwrap();
+    assert_eq!(version(&mut test), 3);
+    test.uninstall_code(canister_id).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .canister_version,
+        4
+    );
+}
+
 #[test]
 fn ic0_msg_arg_data_size_works() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -2244,6 +2326,146 @@ fn grow_memory_beyond_32_bit_limit_fails() {
     assert_eq!(ErrorCode::CanisterTrapped, err.code());
 }
 
+const WASM_MEMORY_LIMIT_WAT: &str = r#"
+    (module
+        (func (export "canister_update grow")
+            (drop (memory.grow (i32.const 10)))
+        )
+        (func (export "canister_pre_upgrade")
+            (drop (memory.grow (i32.const 10)))
+        )
+        (func (export "canister_post_upgrade")
+            (drop (memory.grow (i32.const 10)))
+        )
+        (memory 1)
+    )"#;
+
+#[test]
+fn wasm_memory_limit_is_enforced_by_memory_grow() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(WASM_MEMORY_LIMIT_WAT).unwrap();
+    test.update_wasm_memory_limit(canister_id, NumBytes::new(5 * 64 * 1024))
+        .unwrap();
+    let status =
+        CanisterStatusResultV2::decode(&get_reply(test.canister_status(canister_id))).unwrap();
+    assert_eq!(status.wasm_memory_limit(), Some(5 * 64 * 1024));
+    let err = test.ingress(canister_id, "grow", vec![]).unwrap_err();
+    assert_eq!(ErrorCode::CanisterWasmMemoryLimitExceeded, err.code());
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::new(1)
+    );
+    // Without a limit the memory grows as usual.
+    test.update_wasm_memory_limit(canister_id, NumBytes::new(0))
+        .unwrap();
+    let result = test.ingress(canister_id, "grow", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::new(11)
+    );
+}
+
+#[test]
+fn wasm_memory_limit_is_not_enforced_in_upgrade() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(WASM_MEMORY_LIMIT_WAT).unwrap();
+    test.update_wasm_memory_limit(canister_id, NumBytes::new(5 * 64 * 1024))
+        .unwrap();
+    test.upgrade_canister(canister_id, wabt::wat2wasm(WASM_MEMORY_LIMIT_WAT).unwrap())
+        .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .wasm_memory_limit,
+        Some(NumBytes::new(5 * 64 * 1024))
+    );
+}
+
+#[test]
+fn memory_grow_reserves_cycles_above_subnet_memory_threshold() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_total_memory(1 << 30)
+        .with_subnet_memory_threshold(0)
+        .build();
+    let canister_id = test.canister_from_wat(WASM_MEMORY_LIMIT_WAT).unwrap();
+    let reserved_before = test
+        .canister_state(canister_id)
+        .system_state
+        .reserved_balance();
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+    test.ingress(canister_id, "grow", vec![]).unwrap();
+    let reserved_after = test
+        .canister_state(canister_id)
+        .system_state
+        .reserved_balance();
+    assert!(reserved_after > reserved_before);
+    assert!(
+        test.canister_state(canister_id).system_state.balance()
+            <= balance_before - (reserved_after - reserved_before)
+    );
+    let status =
+        CanisterStatusResultV2::decode(&get_reply(test.canister_status(canister_id))).unwrap();
+    assert_eq!(status.reserved_cycles(), Some(reserved_after.get()));
+}
+
+#[test]
+fn memory_grow_fails_when_reserved_cycles_limit_is_exceeded() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_total_memory(1 << 30)
+        .with_subnet_memory_threshold(0)
+        .build();
+    let canister_id = test.canister_from_wat(WASM_MEMORY_LIMIT_WAT).unwrap();
+    let reserved = test
+        .canister_state(canister_id)
+        .system_state
+        .reserved_balance();
+    let err = test
+        .update_reserved_cycles_limit(canister_id, reserved - Cycles::new(1))
+        .unwrap_err();
+    assert_eq!(ErrorCode::CanisterContractViolation, err.code());
+    test.update_reserved_cycles_limit(canister_id, reserved)
+        .unwrap();
+    let status =
+        CanisterStatusResultV2::decode(&get_reply(test.canister_status(canister_id))).unwrap();
+    assert_eq!(status.reserved_cycles_limit(), Some(reserved.get()));
+    let err = test.ingress(canister_id, "grow", vec![]).unwrap_err();
+    assert_eq!(
+        ErrorCode::ReservedCyclesLimitExceededInMemoryAllocation,
+        err.code()
+    );
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::new(1)
+    );
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance(),
+        reserved
+    );
+}
+
+#[test]
+fn wasm_memory_limit_is_enforced_in_start_during_upgrade() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(WASM_MEMORY_LIMIT_WAT).unwrap();
+    test.update_wasm_memory_limit(canister_id, NumBytes::new(5 * 64 * 1024))
+        .unwrap();
+    let wat = r#"
+        (module
+            (func $start
+                (drop (memory.grow (i32.const 10)))
+            )
+            (start $start)
+            (memory 1)
+        )"#;
+    let err = test
+        .upgrade_canister(canister_id, wabt::wat2wasm(wat).unwrap())
+        .unwrap_err();
+    assert_eq!(ErrorCode::CanisterWasmMemoryLimitExceeded, err.code());
+}
+
 const STABLE_MEMORY_WAT: &str = r#"
     (import "ic0" "msg_reply" (func $msg_reply))
     (import "ic0" "msg_reply_data_append"
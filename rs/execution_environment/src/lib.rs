@@ -16,7 +16,7 @@ pub mod util;
 
 use crate::anonymous_query_handler::AnonymousQueryHandler;
 pub use execution_environment::{
-    as_num_instructions, as_round_instructions, execute_canister, CanisterHeartbeatError,
+    as_num_instructions, as_round_instructions, execute_canister, CanisterSystemTaskError,
     CompilationCostHandling, ExecuteMessageResult, ExecutionEnvironment, ExecutionResponse,
     RoundInstructions, RoundLimits,
 };
@@ -36,13 +36,19 @@ use ic_logger::ReplicaLogger;
 use ic_metrics::MetricsRegistry;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{CallOrigin, NetworkTopology, ReplicatedState};
-use ic_types::{messages::CallContextId, SubnetId};
+use ic_types::{messages::CallContextId, NumInstructions, SubnetId};
 use ingress_filter::IngressFilter;
-use query_handler::HttpQueryHandler;
-pub use query_handler::InternalHttpQueryHandler;
+use query_handler::{new_query_stats_collector, HttpQueryHandler};
+pub use query_handler::{
+    InternalHttpQueryHandler, QueryStatsCollector, QueryStatsPayloadBuilderImpl,
+    QueryStatsPayloadBuilderParams,
+};
 pub use scheduler::RoundSchedule;
 use scheduler::SchedulerImpl;
-use std::sync::{Arc, Mutex};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
 use tower::limit::GlobalConcurrencyLimitLayer;
 
 const MAX_INFLIGHT_QUERIES_PER_THREAD: usize = 100;
@@ -78,6 +84,25 @@ pub enum NonReplicatedQueryKind {
     Pure { caller: PrincipalId },
 }
 
+/// Counts the instructions used by the messages that finished executing,
+/// including `install_code` and other subnet messages, and by queries.
+///
+/// Tools that execute one message at a time, such as `drun`, compare the
+/// count before and after a message to report the instructions it used.
+#[derive(Clone, Default)]
+pub struct MessageInstructionsCounter(Arc<AtomicU64>);
+
+impl MessageInstructionsCounter {
+    /// Returns the number of instructions counted so far.
+    pub fn get(&self) -> NumInstructions {
+        NumInstructions::from(self.0.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn add(&self, instructions: NumInstructions) {
+        self.0.fetch_add(instructions.get(), Ordering::Relaxed);
+    }
+}
+
 // This struct holds public facing components that are created by Execution.
 pub struct ExecutionServices {
     pub ingress_filter: IngressFilterService,
@@ -87,6 +112,8 @@ pub struct ExecutionServices {
     pub async_query_handler: QueryExecutionService,
     pub anonymous_query_handler: AnonymousQueryService,
     pub scheduler: Box<dyn Scheduler<State = ReplicatedState>>,
+    pub query_stats_payload_builder: QueryStatsPayloadBuilderParams,
+    pub message_instructions: MessageInstructionsCounter,
 }
 
 impl ExecutionServices {
@@ -131,6 +158,8 @@ impl ExecutionServices {
             config.clone(),
             Arc::clone(&cycles_account_manager),
         ));
+        let (query_stats_collector, query_stats_payload_builder) =
+            new_query_stats_collector(config.query_stats_epoch_length);
         let sync_query_handler = Arc::new(InternalHttpQueryHandler::new(
             logger.clone(),
             hypervisor,
@@ -139,6 +168,8 @@ impl ExecutionServices {
             metrics_registry,
             scheduler_config.max_instructions_per_message,
             Arc::clone(&cycles_account_manager),
+            query_stats_collector.clone(),
+            exec_env.message_instructions().clone(),
         ));
         let threadpool = threadpool::Builder::new()
             .num_threads(config.query_execution_threads)
@@ -158,6 +189,7 @@ impl ExecutionServices {
             Arc::clone(&sync_query_handler) as Arc<_>,
             Arc::clone(&threadpool),
             Arc::clone(&state_reader),
+            query_stats_collector,
         );
         let ingress_filter = IngressFilter::new_service(
             concurrency_buffer.clone(),
@@ -174,6 +206,7 @@ impl ExecutionServices {
         );
 
         let bitcoin_canister = Arc::new(BitcoinCanister::new(metrics_registry, logger.clone()));
+        let message_instructions = exec_env.message_instructions().clone();
 
         let scheduler = Box::new(SchedulerImpl::new(
             scheduler_config,
@@ -197,6 +230,8 @@ impl ExecutionServices {
             async_query_handler,
             anonymous_query_handler,
             scheduler,
+            query_stats_payload_builder,
+            message_instructions,
         }
     }
 
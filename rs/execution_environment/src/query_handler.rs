@@ -3,6 +3,7 @@
 
 mod query_allocations;
 mod query_context;
+mod query_stats;
 #[cfg(test)]
 mod tests;
 
@@ -10,6 +11,7 @@ use crate::execution_environment::subnet_memory_capacity;
 use crate::{
     hypervisor::Hypervisor,
     metrics::{MeasurementScope, QueryHandlerMetrics},
+    MessageInstructionsCounter,
 };
 use ic_config::execution_environment::Config;
 use ic_crypto_tree_hash::{flatmap, Label, LabeledTree, LabeledTree::SubTree};
@@ -22,14 +24,19 @@ use ic_metrics::MetricsRegistry;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::ReplicatedState;
 use ic_types::{
+    batch::QueryStats,
     ingress::WasmResult,
     messages::{
         Blob, Certificate, CertificateDelegation, HttpQueryResponse, HttpQueryResponseReply,
         UserQuery,
     },
-    CanisterId, NumInstructions,
+    CanisterId, Height, NumInstructions,
 };
 use query_allocations::QueryAllocationsUsed;
+pub(crate) use query_stats::new_query_stats_collector;
+pub use query_stats::{
+    QueryStatsCollector, QueryStatsPayloadBuilderImpl, QueryStatsPayloadBuilderParams,
+};
 use serde::Serialize;
 use std::{
     convert::Infallible,
@@ -53,7 +60,7 @@ fn get_latest_certified_state_and_data_certificate(
     state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
     certificate_delegation: Option<CertificateDelegation>,
     canister_id: CanisterId,
-) -> Option<(Arc<ReplicatedState>, Vec<u8>)> {
+) -> Option<(Arc<ReplicatedState>, Vec<u8>, Height)> {
     // The path to fetch the data certificate for the canister.
     let path = SubTree(flatmap! {
         label("canister") => SubTree(
@@ -78,6 +85,7 @@ fn get_latest_certified_state_and_data_certificate(
                     signature: Blob(cert.signed.signature.signature.get().0),
                     delegation: certificate_delegation,
                 }),
+                cert.height,
             )
         })
 }
@@ -95,6 +103,8 @@ pub struct InternalHttpQueryHandler {
     metrics: QueryHandlerMetrics,
     max_instructions_per_message: NumInstructions,
     cycles_account_manager: Arc<CyclesAccountManager>,
+    query_stats_collector: QueryStatsCollector,
+    message_instructions: MessageInstructionsCounter,
 }
 
 #[derive(Clone)]
@@ -103,9 +113,11 @@ pub(crate) struct HttpQueryHandler {
     internal: Arc<dyn QueryHandler<State = ReplicatedState>>,
     state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
     threadpool: Arc<Mutex<threadpool::ThreadPool>>,
+    query_stats_collector: QueryStatsCollector,
 }
 
 impl InternalHttpQueryHandler {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         log: ReplicaLogger,
         hypervisor: Arc<Hypervisor>,
@@ -114,6 +126,8 @@ impl InternalHttpQueryHandler {
         metrics_registry: &MetricsRegistry,
         max_instructions_per_message: NumInstructions,
         cycles_account_manager: Arc<CyclesAccountManager>,
+        query_stats_collector: QueryStatsCollector,
+        message_instructions: MessageInstructionsCounter,
     ) -> Self {
         Self {
             log,
@@ -124,6 +138,8 @@ impl InternalHttpQueryHandler {
             metrics: QueryHandlerMetrics::new(metrics_registry),
             max_instructions_per_message,
             cycles_account_manager,
+            query_stats_collector,
+            message_instructions,
         }
     }
 }
@@ -152,6 +168,8 @@ impl QueryHandler for InternalHttpQueryHandler {
         // query is fine as we do not persist state modifications.
         let subnet_available_memory = subnet_memory_capacity(&self.config);
         let max_canister_memory_size = self.config.max_canister_memory_size;
+        let canister_id = query.receiver;
+        let ingress_payload_size = query.method_payload.len() as u64;
 
         let mut context = query_context::QueryContext::new(
             &self.log,
@@ -163,13 +181,34 @@ impl QueryHandler for InternalHttpQueryHandler {
             subnet_available_memory,
             max_canister_memory_size,
             self.max_instructions_per_message,
+            self.config.max_query_call_depth,
+            self.config.max_query_call_graph_instructions,
         );
-        context.run(
+        let result = context.run(
             query,
             &self.metrics,
             Arc::clone(&self.cycles_account_manager),
             &measurement_scope,
-        )
+        );
+
+        let egress_payload_size = match &result {
+            Ok(WasmResult::Reply(reply)) => reply.len(),
+            Ok(WasmResult::Reject(message)) => message.len(),
+            Err(err) => err.description().len(),
+        };
+        let instructions_executed = context.call_graph_instructions_executed();
+        self.message_instructions.add(instructions_executed);
+        self.query_stats_collector.register_query_statistics(
+            canister_id,
+            &QueryStats {
+                num_calls: 1,
+                num_instructions: instructions_executed.get(),
+                ingress_payload_size,
+                egress_payload_size: egress_payload_size as u64,
+            },
+        );
+
+        result
     }
 }
 
@@ -179,11 +218,13 @@ impl HttpQueryHandler {
         internal: Arc<dyn QueryHandler<State = ReplicatedState>>,
         threadpool: Arc<Mutex<threadpool::ThreadPool>>,
         state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        query_stats_collector: QueryStatsCollector,
     ) -> QueryExecutionService {
         let base_service = BoxCloneService::new(Self {
             internal,
             state_reader,
             threadpool,
+            query_stats_collector,
         });
         ServiceBuilder::new()
             .layer(concurrency_buffer)
@@ -220,6 +261,7 @@ impl Service<(UserQuery, Option<CertificateDelegation>)> for HttpQueryHandler {
     ) -> Self::Future {
         let internal = Arc::clone(&self.internal);
         let state_reader = Arc::clone(&self.state_reader);
+        let query_stats_collector = self.query_stats_collector.clone();
         let (tx, rx) = oneshot::channel();
         let threadpool = self.threadpool.lock().unwrap().clone();
         threadpool.execute(move || {
@@ -232,7 +274,10 @@ impl Service<(UserQuery, Option<CertificateDelegation>)> for HttpQueryHandler {
                     certificate_delegation,
                     query.receiver,
                 ) {
-                    Some((state, cert)) => internal.query(query, state, cert),
+                    Some((state, cert, height)) => {
+                        query_stats_collector.set_epoch_from_height(height);
+                        internal.query(query, state, cert)
+                    }
                     None => Err(UserError::new(
                         ErrorCode::CertifiedStateUnavailable,
                         "Certified state is not available yet. Please try again...",
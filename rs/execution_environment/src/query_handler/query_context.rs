@@ -1,5 +1,7 @@
-//! This module implements an MVP version of inter-canister queries. This
-//! implementation has the following restrictions:
+//! This module implements an MVP version of inter-canister queries. They are
+//! used by composite query methods (`canister_composite_query`) which, unlike
+//! regular query methods, are allowed to call query methods of other
+//! canisters. This implementation has the following restrictions:
 //!
 //! - A canister can only query other canisters on the same subnet.
 //!
@@ -10,6 +12,10 @@
 //! - Loops are not allowed. E.g. call graphs like A -> B -> C -> A are not
 //! supported.
 //!
+//! - The depth of the call graph is limited by `max_query_call_depth` and the
+//! total number of instructions executed by all messages in the call graph is
+//! limited by `max_query_call_graph_instructions`.
+//!
 //! Some interesting factoids about inter-canister query execution to keep in
 //! mind:
 //!
@@ -87,6 +93,19 @@ enum EnqueueRequestsResult {
     NoMessages,
     /// A loop in the callgraph was detected so no messages were enqueued.
     LoopDetected,
+    /// The messages would exceed the maximum depth of the callgraph so no
+    /// messages were enqueued.
+    CallGraphTooDeep,
+}
+
+fn call_graph_too_deep_error(max_query_call_depth: usize) -> UserError {
+    UserError::new(
+        ErrorCode::QueryCallGraphTooDeep,
+        format!(
+            "Composite query call graph exceeded the maximum depth of {}.",
+            max_query_call_depth
+        ),
+    )
 }
 
 // A handy function to create a `Response` using parameters from the `Request`
@@ -120,6 +139,17 @@ pub(super) struct QueryContext<'a> {
     max_canister_memory_size: NumBytes,
     max_instructions_per_message: NumInstructions,
     round_limits: RoundLimits,
+    // The maximum depth of the call graph. The canister called by the user has
+    // depth 1.
+    max_query_call_depth: usize,
+    // The depth in the call graph of the canisters that executed a message.
+    call_depths: BTreeMap<CanisterId, usize>,
+    // The maximum number of instructions that can be executed by all messages
+    // in the call graph.
+    max_query_call_graph_instructions: NumInstructions,
+    // The number of instructions executed so far by all messages in the call
+    // graph.
+    call_graph_instructions_executed: NumInstructions,
 }
 
 impl<'a> QueryContext<'a> {
@@ -134,6 +164,8 @@ impl<'a> QueryContext<'a> {
         subnet_available_memory: SubnetAvailableMemory,
         max_canister_memory_size: NumBytes,
         max_instructions_per_message: NumInstructions,
+        max_query_call_depth: usize,
+        max_query_call_graph_instructions: NumInstructions,
     ) -> Self {
         let network_topology = Arc::new(state.metadata.network_topology.clone());
         let round_limits = RoundLimits {
@@ -156,6 +188,10 @@ impl<'a> QueryContext<'a> {
             max_canister_memory_size,
             max_instructions_per_message,
             round_limits,
+            max_query_call_depth,
+            call_depths: BTreeMap::new(),
+            max_query_call_graph_instructions,
+            call_graph_instructions_executed: NumInstructions::from(0),
         }
     }
 
@@ -168,7 +204,9 @@ impl<'a> QueryContext<'a> {
     ///
     /// - If it does not produce a response and produces additional
     /// inter-canister queries, process them till there is a response or the
-    /// call graph finishes with no reply.
+    /// call graph finishes with no reply. Only composite query methods and,
+    /// for legacy reasons, query methods on system and verified application
+    /// subnets can produce inter-canister queries.
     pub(super) fn run<'b>(
         &mut self,
         query: UserQuery,
@@ -199,12 +237,14 @@ impl<'a> QueryContext<'a> {
         }
 
         let call_origin = CallOrigin::Query(query.source);
-        // EXC-500: Contain the usage of inter-canister query calls to the subnets
-        // that currently use it until we decide on the future of this feature and
-        // get a proper spec for it.
-        let cross_canister_query_calls_enabled = self.own_subnet_type == SubnetType::System
-            || self.own_subnet_type == SubnetType::VerifiedApplication;
-        let try_pure_query = ENABLE_QUERY_OPTIMIZATION || !cross_canister_query_calls_enabled;
+        // Composite queries are always executed as `Stateful` because they
+        // are expected to call other queries.
+        let is_composite_query =
+            old_canister.exports_composite_query_method(query.method_name.clone());
+        let cross_canister_query_calls_enabled = self.cross_canister_query_calls_enabled();
+        let try_pure_query = !is_composite_query
+            && (ENABLE_QUERY_OPTIMIZATION || !cross_canister_query_calls_enabled);
+        self.call_depths.insert(canister_id, 1);
         let query_kind = if try_pure_query {
             NonReplicatedQueryKind::Pure {
                 caller: query.source.get(),
@@ -263,6 +303,10 @@ impl<'a> QueryContext<'a> {
                     LOOP_DETECTED_ERROR_MSG.to_string(),
                 )),
 
+                EnqueueRequestsResult::CallGraphTooDeep => {
+                    Err(call_graph_too_deep_error(self.max_query_call_depth))
+                }
+
                 // The canister did not produce a response and did not enqueue
                 // any requests either. As this is the very first canister in
                 // the call graph, we can declare that the query execution
@@ -294,6 +338,16 @@ impl<'a> QueryContext<'a> {
         let measurement_scope =
             MeasurementScope::nested(&metrics.query_spawned_calls, measurement_scope);
         loop {
+            if self.call_graph_instructions_left().get() == 0 {
+                return Err(UserError::new(
+                    ErrorCode::QueryCallGraphTotalInstructionLimitExceeded,
+                    format!(
+                        "Composite query call graph exceeded the instruction limit of {}.",
+                        self.max_query_call_graph_instructions
+                    ),
+                ));
+            }
+
             if let Some(response) = self.outstanding_response.take() {
                 debug!(self.log, "Executing response for {}", response.originator);
                 // Any result returned by `handle_response` is a query context
@@ -333,6 +387,7 @@ impl<'a> QueryContext<'a> {
     fn enqueue_requests(&mut self, canister: &mut CanisterState) -> EnqueueRequestsResult {
         let mut sent_messages = false;
         let canister_id = canister.canister_id();
+        let call_depth = self.call_depths.get(&canister_id).copied().unwrap_or(1);
 
         let outgoing_messages: Vec<_> = canister.output_into_iter().map(|(_, msg)| msg).collect();
         let call_context_manager = canister
@@ -368,7 +423,7 @@ impl<'a> QueryContext<'a> {
                         // module so must have existed on the canister's output
                         // queue from before.
                         CallOrigin::CanisterUpdate(_, _)
-                        | CallOrigin::Heartbeat
+                        | CallOrigin::SystemTask
                         | CallOrigin::Ingress(_, _) => continue,
 
                         // We never serialize messages of such types in the
@@ -385,6 +440,11 @@ impl<'a> QueryContext<'a> {
                         // implementation does not support loops.
                         return EnqueueRequestsResult::LoopDetected;
                     }
+                    if call_depth >= self.max_query_call_depth {
+                        // The callee would be deeper in the call graph than
+                        // allowed.
+                        return EnqueueRequestsResult::CallGraphTooDeep;
+                    }
                     sent_messages = true;
                     self.outstanding_requests.push(msg);
                 }
@@ -411,13 +471,7 @@ impl<'a> QueryContext<'a> {
         query_kind: NonReplicatedQueryKind,
         measurement_scope: &MeasurementScope,
     ) -> (CanisterState, Result<Option<WasmResult>, UserError>) {
-        let instruction_limit = self.max_instructions_per_message.min(
-            self.query_allocations_used
-                .write()
-                .unwrap()
-                .allocation_before_execution(&canister.canister_id())
-                .into(),
-        );
+        let instruction_limit = self.instruction_limit(&canister.canister_id());
         let instruction_limits =
             InstructionLimits::new(FlagStatus::Disabled, instruction_limit, instruction_limit);
         let execution_parameters = self.execution_parameters(&canister, instruction_limits);
@@ -435,6 +489,7 @@ impl<'a> QueryContext<'a> {
             &mut self.round_limits,
         );
         let instructions_executed = instruction_limit - instructions_left;
+        self.call_graph_instructions_executed += instructions_executed;
         measurement_scope.add(
             instructions_executed,
             NumSlices::from(1),
@@ -492,7 +547,7 @@ impl<'a> QueryContext<'a> {
         let func_ref = match call_origin {
             CallOrigin::Ingress(_, _)
             | CallOrigin::CanisterUpdate(_, _)
-            | CallOrigin::Heartbeat => unreachable!("Unreachable in the QueryContext."),
+            | CallOrigin::SystemTask => unreachable!("Unreachable in the QueryContext."),
             CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
                 FuncRef::QueryClosure(closure)
             }
@@ -502,13 +557,7 @@ impl<'a> QueryContext<'a> {
         // No cycles are refunded in a response to a query call.
         let incoming_cycles = Cycles::zero();
 
-        let instruction_limit = self.max_instructions_per_message.min(
-            self.query_allocations_used
-                .write()
-                .unwrap()
-                .allocation_before_execution(&canister_id)
-                .into(),
-        );
+        let instruction_limit = self.instruction_limit(&canister_id);
         let instruction_limits =
             InstructionLimits::new(FlagStatus::Disabled, instruction_limit, instruction_limit);
         let mut execution_parameters = self.execution_parameters(&canister, instruction_limits);
@@ -582,6 +631,7 @@ impl<'a> QueryContext<'a> {
             .on_canister_result(call_context_id, Some(callback_id), result);
 
         let instructions_executed = instruction_limit - instructions_left;
+        self.call_graph_instructions_executed += instructions_executed;
         measurement_scope.add(
             instructions_executed,
             NumSlices::from(1),
@@ -617,7 +667,7 @@ impl<'a> QueryContext<'a> {
         let func_ref = match call_origin {
             CallOrigin::Ingress(_, _)
             | CallOrigin::CanisterUpdate(_, _)
-            | CallOrigin::Heartbeat => unreachable!("Unreachable in the QueryContext."),
+            | CallOrigin::SystemTask => unreachable!("Unreachable in the QueryContext."),
             CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
                 FuncRef::QueryClosure(cleanup_closure)
             }
@@ -690,12 +740,31 @@ impl<'a> QueryContext<'a> {
             }
         };
 
-        let call_origin = CallOrigin::CanisterQuery(request.sender, request.sender_reply_callback);
+        let call_depth = self.call_depths.get(&request.sender).copied().unwrap_or(1) + 1;
+        self.call_depths.insert(canister_id, call_depth);
+
+        // Only composite query methods and, for legacy reasons, query methods
+        // on system and verified application subnets can call other queries.
+        // All other callees run as `Pure`, which is also faster.
+        let query_kind = if canister.exports_composite_query_method(request.method_name.clone())
+            || self.cross_canister_query_calls_enabled()
+        {
+            NonReplicatedQueryKind::Stateful {
+                call_origin: CallOrigin::CanisterQuery(
+                    request.sender,
+                    request.sender_reply_callback,
+                ),
+            }
+        } else {
+            NonReplicatedQueryKind::Pure {
+                caller: request.sender.get(),
+            }
+        };
         let (mut canister, result) = self.execute_query(
             canister,
             request.method_name.as_str(),
             request.method_payload.as_slice(),
-            NonReplicatedQueryKind::Stateful { call_origin },
+            query_kind,
             measurement_scope,
         );
 
@@ -731,6 +800,10 @@ impl<'a> QueryContext<'a> {
                             LOOP_DETECTED_ERROR_MSG.to_string(),
                         )),
 
+                        EnqueueRequestsResult::CallGraphTooDeep => {
+                            Some(call_graph_too_deep_error(self.max_query_call_depth))
+                        }
+
                         // The canister did not produce a response and did not
                         // produce any outgoing requests. So produce a "did not
                         // reply" response on its behalf.
@@ -817,6 +890,9 @@ impl<'a> QueryContext<'a> {
                     ErrorCode::InterCanisterQueryLoopDetected,
                     LOOP_DETECTED_ERROR_MSG.to_string(),
                 ))),
+                EnqueueRequestsResult::CallGraphTooDeep => {
+                    Some(Err(call_graph_too_deep_error(self.max_query_call_depth)))
+                }
                 EnqueueRequestsResult::NoMessages | EnqueueRequestsResult::MessagesEnqueued => {
                     self.canisters.insert(canister.canister_id(), canister);
                     None
@@ -931,6 +1007,9 @@ impl<'a> QueryContext<'a> {
                     ErrorCode::InterCanisterQueryLoopDetected,
                     LOOP_DETECTED_ERROR_MSG.to_string(),
                 ))),
+                EnqueueRequestsResult::CallGraphTooDeep => {
+                    Some(Err(call_graph_too_deep_error(self.max_query_call_depth)))
+                }
                 EnqueueRequestsResult::NoMessages | EnqueueRequestsResult::MessagesEnqueued => {
                     self.canisters.insert(canister.canister_id(), canister);
                     None
@@ -986,7 +1065,7 @@ impl<'a> QueryContext<'a> {
 
             CallOrigin::CanisterUpdate(_, _)
             | CallOrigin::Ingress(_, _)
-            | CallOrigin::Heartbeat => fatal!(
+            | CallOrigin::SystemTask => fatal!(
                 self.log,
                 "Canister {}: query path should not have created a callback with an update origin",
                 canister_id
@@ -998,6 +1077,44 @@ impl<'a> QueryContext<'a> {
         }
     }
 
+    // Returns the instruction limit for the next message executed on the given
+    // canister. The limit takes into account the instructions that all
+    // messages in the call graph can still execute.
+    fn instruction_limit(&self, canister_id: &CanisterId) -> NumInstructions {
+        self.max_instructions_per_message
+            .min(
+                self.query_allocations_used
+                    .write()
+                    .unwrap()
+                    .allocation_before_execution(canister_id)
+                    .into(),
+            )
+            .min(self.call_graph_instructions_left())
+    }
+
+    // EXC-500: Contain the usage of inter-canister query calls from regular
+    // queries to the subnets that currently use it.
+    fn cross_canister_query_calls_enabled(&self) -> bool {
+        self.own_subnet_type == SubnetType::System
+            || self.own_subnet_type == SubnetType::VerifiedApplication
+    }
+
+    /// Returns the number of instructions executed so far by all messages in
+    /// the call graph.
+    pub(super) fn call_graph_instructions_executed(&self) -> NumInstructions {
+        self.call_graph_instructions_executed
+    }
+
+    // Returns the number of instructions that all messages in the call graph
+    // can still execute.
+    fn call_graph_instructions_left(&self) -> NumInstructions {
+        NumInstructions::from(
+            self.max_query_call_graph_instructions
+                .get()
+                .saturating_sub(self.call_graph_instructions_executed.get()),
+        )
+    }
+
     fn execution_parameters(
         &self,
         canister: &CanisterState,
@@ -1006,6 +1123,7 @@ impl<'a> QueryContext<'a> {
         ExecutionParameters {
             instruction_limits,
             canister_memory_limit: canister.memory_limit(self.max_canister_memory_size),
+            wasm_memory_limit: None,
             compute_allocation: canister.scheduler_state.compute_allocation,
             subnet_type: self.own_subnet_type,
             execution_mode: ExecutionMode::NonReplicated,
@@ -0,0 +1,446 @@
+//! Collects statistics on the query calls executed by this replica and reports
+//! them via consensus.
+//!
+//! Statistics are collected per epoch, where the epoch is derived from the
+//! height of the certified state the queries are executed against. Once an
+//! epoch is over, its statistics are included in the blocks this replica
+//! proposes, so that all replicas agree on them and aggregate them into the
+//! replicated state.
+
+use ic_interfaces::query_stats::{
+    InvalidQueryStatsPayload, QueryStatsPayloadBuilder, QueryStatsPayloadValidationError,
+    QueryStatsTransientValidationError,
+};
+use ic_interfaces::validation::ValidationError;
+use ic_interfaces_state_manager::StateReader;
+use ic_logger::{warn, ReplicaLogger};
+use ic_replicated_state::ReplicatedState;
+use ic_types::{
+    batch::{
+        epoch_from_height, CanisterQueryStats, QueryStats, QueryStatsEpoch, QueryStatsPayload,
+        ValidationContext,
+    },
+    CanisterId, CountBytes, Height, NodeId, NumBytes,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+};
+
+#[derive(Default)]
+struct LocalQueryStats {
+    /// The epoch for which statistics are currently collected.
+    epoch: Option<QueryStatsEpoch>,
+    /// The statistics collected during `epoch`.
+    current: BTreeMap<CanisterId, QueryStats>,
+    /// The statistics of the last completed epoch, ready to be reported.
+    completed: Option<(QueryStatsEpoch, BTreeMap<CanisterId, QueryStats>)>,
+}
+
+/// Creates a collector for the statistics of the query calls executed by this
+/// replica, along with the parameters of the payload builder that reports
+/// them.
+pub(crate) fn new_query_stats_collector(
+    epoch_length: u64,
+) -> (QueryStatsCollector, QueryStatsPayloadBuilderParams) {
+    let stats = Arc::new(Mutex::new(LocalQueryStats::default()));
+    (
+        QueryStatsCollector {
+            epoch_length,
+            stats: Arc::clone(&stats),
+        },
+        QueryStatsPayloadBuilderParams {
+            epoch_length,
+            stats,
+        },
+    )
+}
+
+/// Records the statistics of the query calls executed by this replica.
+#[derive(Clone)]
+pub struct QueryStatsCollector {
+    epoch_length: u64,
+    stats: Arc<Mutex<LocalQueryStats>>,
+}
+
+impl QueryStatsCollector {
+    /// Advances the current epoch to the epoch of `height`, the height of the
+    /// certified state queries are executed against. The statistics of the
+    /// previous epoch become ready to be reported.
+    pub(crate) fn set_epoch_from_height(&self, height: Height) {
+        let epoch = epoch_from_height(height, self.epoch_length);
+        let mut stats = self.stats.lock().unwrap();
+        match stats.epoch {
+            // Queries may still be executed against an older state.
+            Some(current_epoch) if current_epoch >= epoch => {}
+            Some(current_epoch) => {
+                let current = std::mem::take(&mut stats.current);
+                stats.completed = Some((current_epoch, current));
+                stats.epoch = Some(epoch);
+            }
+            None => stats.epoch = Some(epoch),
+        }
+    }
+
+    /// Adds the statistics of a query call to the current epoch. Statistics
+    /// are dropped until the first epoch is set.
+    pub(crate) fn register_query_statistics(&self, canister_id: CanisterId, stats: &QueryStats) {
+        let mut local_stats = self.stats.lock().unwrap();
+        if local_stats.epoch.is_some() {
+            local_stats
+                .current
+                .entry(canister_id)
+                .or_default()
+                .saturating_accumulate(stats);
+        }
+    }
+}
+
+/// The parts of a `QueryStatsPayloadBuilderImpl` that are known when the
+/// execution environment is set up.
+pub struct QueryStatsPayloadBuilderParams {
+    epoch_length: u64,
+    stats: Arc<Mutex<LocalQueryStats>>,
+}
+
+impl QueryStatsPayloadBuilderParams {
+    pub fn into_payload_builder(
+        self,
+        state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        node_id: NodeId,
+        log: ReplicaLogger,
+    ) -> Arc<QueryStatsPayloadBuilderImpl> {
+        Arc::new(QueryStatsPayloadBuilderImpl {
+            epoch_length: self.epoch_length,
+            stats: self.stats,
+            state_reader,
+            node_id,
+            log,
+        })
+    }
+}
+
+/// Builds and validates the query statistics section of block payloads.
+pub struct QueryStatsPayloadBuilderImpl {
+    epoch_length: u64,
+    stats: Arc<Mutex<LocalQueryStats>>,
+    state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    node_id: NodeId,
+    log: ReplicaLogger,
+}
+
+impl QueryStatsPayloadBuilder for QueryStatsPayloadBuilderImpl {
+    fn get_query_stats_payload(
+        &self,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+        byte_limit: NumBytes,
+    ) -> Option<QueryStatsPayload> {
+        let (epoch, completed) = self.stats.lock().unwrap().completed.clone()?;
+        let certified_epoch =
+            epoch_from_height(validation_context.certified_height, self.epoch_length);
+        if epoch >= certified_epoch {
+            return None;
+        }
+
+        let state = match self
+            .state_reader
+            .get_state_at(validation_context.certified_height)
+        {
+            Ok(state) => state.take(),
+            Err(err) => {
+                warn!(
+                    self.log,
+                    "Failed to get the state at height {} to build the query stats payload: {}",
+                    validation_context.certified_height,
+                    err
+                );
+                return None;
+            }
+        };
+        let raw_query_stats = &state.metadata.query_stats;
+        if raw_query_stats.is_aggregated(epoch) {
+            return None;
+        }
+
+        let already_reported: BTreeSet<CanisterId> = past_payloads
+            .iter()
+            .filter(|payload| payload.proposer == self.node_id && payload.epoch == epoch)
+            .flat_map(|payload| payload.canister_stats.iter())
+            .map(|canister_stats| canister_stats.canister_id)
+            .collect();
+
+        let mut payload = QueryStatsPayload {
+            proposer: self.node_id,
+            epoch,
+            canister_stats: vec![],
+        };
+        for (canister_id, stats) in completed {
+            if already_reported.contains(&canister_id)
+                || raw_query_stats.contains(epoch, &self.node_id, &canister_id)
+            {
+                continue;
+            }
+            if payload.count_bytes() + std::mem::size_of::<CanisterQueryStats>()
+                > byte_limit.get() as usize
+            {
+                break;
+            }
+            payload
+                .canister_stats
+                .push(CanisterQueryStats { canister_id, stats });
+        }
+
+        if payload.is_empty() {
+            None
+        } else {
+            Some(payload)
+        }
+    }
+
+    fn validate_query_stats_payload(
+        &self,
+        payload: &QueryStatsPayload,
+        proposer: NodeId,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+    ) -> Result<NumBytes, QueryStatsPayloadValidationError> {
+        // Otherwise, a node could report statistics on behalf of another one.
+        if payload.proposer != proposer {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::ProposerMismatch {
+                    payload_proposer: payload.proposer,
+                    block_proposer: proposer,
+                },
+            ));
+        }
+
+        let certified_epoch =
+            epoch_from_height(validation_context.certified_height, self.epoch_length);
+        if payload.epoch >= certified_epoch {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::EpochNotOver {
+                    epoch: payload.epoch,
+                    certified_epoch,
+                },
+            ));
+        }
+
+        let state = self
+            .state_reader
+            .get_state_at(validation_context.certified_height)
+            .map_err(|err| {
+                ValidationError::Transient(QueryStatsTransientValidationError::GetStateFailed(
+                    validation_context.certified_height,
+                    err,
+                ))
+            })?
+            .take();
+        let raw_query_stats = &state.metadata.query_stats;
+        if raw_query_stats.is_aggregated(payload.epoch) {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::EpochAlreadyAggregated(payload.epoch),
+            ));
+        }
+
+        let mut reported: BTreeSet<CanisterId> = past_payloads
+            .iter()
+            .filter(|past| past.proposer == payload.proposer && past.epoch == payload.epoch)
+            .flat_map(|past| past.canister_stats.iter())
+            .map(|canister_stats| canister_stats.canister_id)
+            .collect();
+        for canister_stats in payload.canister_stats.iter() {
+            let canister_id = canister_stats.canister_id;
+            if !reported.insert(canister_id)
+                || raw_query_stats.contains(payload.epoch, &payload.proposer, &canister_id)
+            {
+                return Err(ValidationError::Permanent(
+                    InvalidQueryStatsPayload::DuplicateCanisterStats {
+                        proposer: payload.proposer,
+                        canister_id,
+                    },
+                ));
+            }
+        }
+
+        Ok(NumBytes::from(payload.count_bytes() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_interfaces_state_manager::Labeled;
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_registry_subnet_type::SubnetType;
+    use ic_test_utilities::{
+        state_manager::MockStateManager,
+        types::ids::{canister_test_id, node_test_id, subnet_test_id},
+    };
+    use ic_types::{time::UNIX_EPOCH, RegistryVersion};
+
+    const EPOCH_LENGTH: u64 = 10;
+
+    fn query_stats(num_calls: u32) -> QueryStats {
+        QueryStats {
+            num_calls,
+            num_instructions: 100,
+            ingress_payload_size: 10,
+            egress_payload_size: 20,
+        }
+    }
+
+    fn validation_context(certified_height: u64) -> ValidationContext {
+        ValidationContext {
+            registry_version: RegistryVersion::from(1),
+            certified_height: Height::from(certified_height),
+            time: UNIX_EPOCH,
+        }
+    }
+
+    fn payload_builder(
+        params: QueryStatsPayloadBuilderParams,
+    ) -> Arc<QueryStatsPayloadBuilderImpl> {
+        let mut state_manager = MockStateManager::new();
+        state_manager.expect_get_state_at().returning(|height| {
+            Ok(Labeled::new(
+                height,
+                Arc::new(ReplicatedState::new_rooted_at(
+                    subnet_test_id(1),
+                    SubnetType::Application,
+                    "NOT_USED".into(),
+                )),
+            ))
+        });
+        params.into_payload_builder(Arc::new(state_manager), node_test_id(1), no_op_logger())
+    }
+
+    #[test]
+    fn statistics_are_reported_once_the_epoch_is_over() {
+        let (collector, params) = new_query_stats_collector(EPOCH_LENGTH);
+        let builder = payload_builder(params);
+
+        // Statistics are dropped until the first epoch is set.
+        collector.register_query_statistics(canister_test_id(1), &query_stats(1));
+        collector.set_epoch_from_height(Height::from(5));
+        collector.register_query_statistics(canister_test_id(1), &query_stats(1));
+        collector.register_query_statistics(canister_test_id(1), &query_stats(2));
+        collector.register_query_statistics(canister_test_id(2), &query_stats(1));
+
+        // Nothing to report while epoch 0 is ongoing.
+        assert_eq!(
+            builder.get_query_stats_payload(&validation_context(5), &[], NumBytes::from(1 << 20)),
+            None
+        );
+
+        // Executing a query against a state of epoch 1 completes epoch 0.
+        collector.set_epoch_from_height(Height::from(12));
+        collector.register_query_statistics(canister_test_id(1), &query_stats(7));
+        let payload = builder
+            .get_query_stats_payload(&validation_context(12), &[], NumBytes::from(1 << 20))
+            .unwrap();
+        assert_eq!(payload.proposer, node_test_id(1));
+        assert_eq!(payload.epoch, QueryStatsEpoch::from(0));
+        assert_eq!(
+            payload.canister_stats,
+            vec![
+                CanisterQueryStats {
+                    canister_id: canister_test_id(1),
+                    stats: QueryStats {
+                        num_calls: 3,
+                        num_instructions: 200,
+                        ingress_payload_size: 20,
+                        egress_payload_size: 40,
+                    },
+                },
+                CanisterQueryStats {
+                    canister_id: canister_test_id(2),
+                    stats: query_stats(1),
+                },
+            ]
+        );
+        assert!(builder
+            .validate_query_stats_payload(&payload, node_test_id(1), &validation_context(12), &[])
+            .is_ok());
+
+        // Statistics already included in past payloads are not reported again.
+        assert_eq!(
+            builder.get_query_stats_payload(
+                &validation_context(12),
+                &[&payload],
+                NumBytes::from(1 << 20)
+            ),
+            None
+        );
+        assert!(matches!(
+            builder.validate_query_stats_payload(
+                &payload,
+                node_test_id(1),
+                &validation_context(12),
+                &[&payload]
+            ),
+            Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::DuplicateCanisterStats { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn payload_of_ongoing_epoch_is_invalid() {
+        let (_collector, params) = new_query_stats_collector(EPOCH_LENGTH);
+        let builder = payload_builder(params);
+        let payload = QueryStatsPayload {
+            proposer: node_test_id(2),
+            epoch: QueryStatsEpoch::from(1),
+            canister_stats: vec![CanisterQueryStats {
+                canister_id: canister_test_id(1),
+                stats: query_stats(1),
+            }],
+        };
+
+        assert!(matches!(
+            builder.validate_query_stats_payload(
+                &payload,
+                node_test_id(2),
+                &validation_context(15),
+                &[]
+            ),
+            Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::EpochNotOver { .. }
+            ))
+        ));
+        assert!(builder
+            .validate_query_stats_payload(&payload, node_test_id(2), &validation_context(20), &[])
+            .is_ok());
+    }
+
+    #[test]
+    fn payload_with_forged_proposer_is_invalid() {
+        let (_collector, params) = new_query_stats_collector(EPOCH_LENGTH);
+        let builder = payload_builder(params);
+        // Node 3 reports statistics on behalf of node 2.
+        let payload = QueryStatsPayload {
+            proposer: node_test_id(2),
+            epoch: QueryStatsEpoch::from(0),
+            canister_stats: vec![CanisterQueryStats {
+                canister_id: canister_test_id(1),
+                stats: query_stats(1),
+            }],
+        };
+
+        assert!(matches!(
+            builder.validate_query_stats_payload(
+                &payload,
+                node_test_id(3),
+                &validation_context(12),
+                &[]
+            ),
+            Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::ProposerMismatch { .. }
+            ))
+        ));
+        assert!(builder
+            .validate_query_stats_payload(&payload, node_test_id(2), &validation_context(12), &[])
+            .is_ok());
+    }
+}
@@ -7,7 +7,7 @@ use ic_test_utilities::{
     types::ids::user_test_id,
     universal_canister::{call_args, wasm},
 };
-use ic_types::{ingress::WasmResult, messages::UserQuery, Cycles};
+use ic_types::{ingress::WasmResult, messages::UserQuery, CanisterId, Cycles};
 use std::sync::Arc;
 
 const CYCLES_BALANCE: Cycles = Cycles::new(100_000_000_000_000);
@@ -188,6 +188,60 @@ fn query_metrics_are_reported() {
     )
 }
 
+#[test]
+fn query_instructions_include_called_queries() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::VerifiedApplication)
+        .build();
+
+    let canister_a = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let canister_b = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+
+    let query_a = |payload: Vec<u8>| UserQuery {
+        source: user_test_id(2),
+        receiver: canister_a,
+        method_name: "query".to_string(),
+        method_payload: payload,
+        ingress_expiry: 0,
+        nonce: None,
+    };
+
+    let instructions_before = downcast_query_handler(test.query_handler())
+        .message_instructions
+        .get();
+    let output = test.query(
+        query_a(wasm().reply_data(b"pong".as_ref()).build()),
+        Arc::new(test.state().clone()),
+        vec![],
+    );
+    assert_eq!(output, Ok(WasmResult::Reply(b"pong".to_vec())));
+    let instructions_after_local_query = downcast_query_handler(test.query_handler())
+        .message_instructions
+        .get();
+    let local_instructions = instructions_after_local_query - instructions_before;
+    assert!(local_instructions.get() > 0);
+
+    let output = test.query(
+        query_a(
+            wasm()
+                .inter_query(
+                    canister_b,
+                    call_args().other_side(wasm().reply_data(b"pong".as_ref())),
+                )
+                .build(),
+        ),
+        Arc::new(test.state().clone()),
+        vec![],
+    );
+    assert_eq!(output, Ok(WasmResult::Reply(b"pong".to_vec())));
+    let called_instructions = downcast_query_handler(test.query_handler())
+        .message_instructions
+        .get()
+        - instructions_after_local_query;
+    // The count also covers canister B and the reply callback of canister A.
+    assert!(called_instructions > local_instructions);
+}
+
 #[test]
 fn query_call_with_side_effects() {
     // In this test we have two canisters A and B.
@@ -390,3 +444,195 @@ fn queries_to_frozen_canisters_are_rejected() {
     );
     assert!(result.is_ok());
 }
+
+// A canister with a query method `pong` that replies with "pong".
+const PONG_CANISTER_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (import "ic0" "msg_reply_data_append"
+            (func $msg_reply_data_append (param i32 i32)))
+        (func $pong
+            (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+            (call $msg_reply)
+        )
+        (memory (export "memory") 1)
+        (data (i32.const 0) "pong")
+        (export "canister_query pong" (func $pong))
+    )"#;
+
+// Returns a canister with a composite query method `forward` that calls the
+// given method of the given canister and replies with its reply.
+fn forwarding_canister_wat(callee: CanisterId, method: &str) -> String {
+    let callee = callee.get();
+    let callee = callee.as_slice();
+    let escaped_callee: String = callee.iter().map(|b| format!("\\{:02x}", b)).collect();
+    format!(
+        r#"
+        (module
+            (import "ic0" "call_new"
+                (func $call_new
+                    (param $callee_src i32) (param $callee_size i32)
+                    (param $name_src i32) (param $name_size i32)
+                    (param $reply_fun i32) (param $reply_env i32)
+                    (param $reject_fun i32) (param $reject_env i32)))
+            (import "ic0" "call_perform" (func $call_perform (result i32)))
+            (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+            (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param i32 i32 i32)))
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+            (func $forward
+                (call $call_new
+                    (i32.const 100) (i32.const {})
+                    (i32.const 0) (i32.const {})
+                    (i32.const 0) (i32.const 0)
+                    (i32.const 1) (i32.const 0))
+                (drop (call $call_perform))
+            )
+            (func $on_reply (param $env i32)
+                (call $msg_arg_data_copy (i32.const 200) (i32.const 0) (call $msg_arg_data_size))
+                (call $msg_reply_data_append (i32.const 200) (call $msg_arg_data_size))
+                (call $msg_reply)
+            )
+            (func $on_reject (param $env i32)
+                unreachable
+            )
+            (table funcref (elem $on_reply $on_reject))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "{}")
+            (data (i32.const 100) "{}")
+            (export "canister_composite_query forward" (func $forward))
+        )"#,
+        callee.len(),
+        method.len(),
+        method,
+        escaped_callee
+    )
+}
+
+fn user_query(receiver: CanisterId, method_name: &str) -> UserQuery {
+    UserQuery {
+        source: user_test_id(2),
+        receiver,
+        method_name: method_name.to_string(),
+        method_payload: vec![],
+        ingress_expiry: 0,
+        nonce: None,
+    }
+}
+
+#[test]
+fn composite_query_calls_queries_on_application_subnet() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_c = test.canister_from_wat(PONG_CANISTER_WAT).unwrap();
+    let canister_b = test
+        .canister_from_wat(forwarding_canister_wat(canister_c, "pong"))
+        .unwrap();
+    let canister_a = test
+        .canister_from_wat(forwarding_canister_wat(canister_b, "forward"))
+        .unwrap();
+
+    let output = test.query(
+        user_query(canister_a, "forward"),
+        Arc::new(test.state().clone()),
+        vec![],
+    );
+    assert_eq!(output, Ok(WasmResult::Reply(b"pong".to_vec())));
+}
+
+#[test]
+fn composite_query_call_graph_depth_is_limited() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_max_query_call_depth(2)
+        .build();
+    let canister_c = test.canister_from_wat(PONG_CANISTER_WAT).unwrap();
+    let canister_b = test
+        .canister_from_wat(forwarding_canister_wat(canister_c, "pong"))
+        .unwrap();
+    let canister_a = test
+        .canister_from_wat(forwarding_canister_wat(canister_b, "forward"))
+        .unwrap();
+
+    // A call graph of depth 2 is allowed.
+    let output = test.query(
+        user_query(canister_b, "forward"),
+        Arc::new(test.state().clone()),
+        vec![],
+    );
+    assert_eq!(output, Ok(WasmResult::Reply(b"pong".to_vec())));
+
+    // A call graph of depth 3 is not allowed.
+    let err = test
+        .query(
+            user_query(canister_a, "forward"),
+            Arc::new(test.state().clone()),
+            vec![],
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::QueryCallGraphTooDeep);
+}
+
+#[test]
+fn composite_query_cannot_be_called_in_replicated_mode() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_b = test.canister_from_wat(PONG_CANISTER_WAT).unwrap();
+    let canister_a = test
+        .canister_from_wat(forwarding_canister_wat(canister_b, "pong"))
+        .unwrap();
+
+    let err = test.ingress(canister_a, "forward", vec![]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterMethodNotFound);
+}
+
+#[test]
+fn query_called_by_composite_query_cannot_call_queries_on_application_subnet() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_c = test.canister_from_wat(PONG_CANISTER_WAT).unwrap();
+    // `forward` is a regular query method of canister B.
+    let canister_b = test
+        .canister_from_wat(
+            forwarding_canister_wat(canister_c, "pong")
+                .replace("canister_composite_query forward", "canister_query forward"),
+        )
+        .unwrap();
+    let canister_a = test
+        .canister_from_wat(forwarding_canister_wat(canister_b, "forward"))
+        .unwrap();
+
+    // Canister B runs as a pure query, so its call fails and canister A
+    // traps when handling the reject.
+    let err = test
+        .query(
+            user_query(canister_a, "forward"),
+            Arc::new(test.state().clone()),
+            vec![],
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterTrapped);
+}
+
+#[test]
+fn query_called_by_composite_query_can_call_queries_on_system_subnet() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .build();
+    let canister_c = test.canister_from_wat(PONG_CANISTER_WAT).unwrap();
+    // `forward` is a regular query method of canister B.
+    let canister_b = test
+        .canister_from_wat(
+            forwarding_canister_wat(canister_c, "pong")
+                .replace("canister_composite_query forward", "canister_query forward"),
+        )
+        .unwrap();
+    let canister_a = test
+        .canister_from_wat(forwarding_canister_wat(canister_b, "forward"))
+        .unwrap();
+
+    let output = test.query(
+        user_query(canister_a, "forward"),
+        Arc::new(test.state().clone()),
+        vec![],
+    );
+    assert_eq!(output, Ok(WasmResult::Reply(b"pong".to_vec())));
+}
@@ -307,6 +307,11 @@ impl SchedulerImpl {
             let round_instructions_executed =
                 as_num_instructions(instructions_before - round_limits.instructions);
 
+            if let Some(message_instructions) = message_instructions {
+                self.exec_env
+                    .message_instructions()
+                    .add(message_instructions);
+            }
             let messages = NumMessages::from(message_instructions.map(|_| 1).unwrap_or(0));
             measurement_scope.add(round_instructions_executed, NumSlices::from(1), messages);
 
@@ -375,6 +380,11 @@ impl SchedulerImpl {
                 state = new_state;
                 let round_instructions_executed =
                     as_num_instructions(instructions_before - round_limits.instructions);
+                if let Some(message_instructions) = message_instructions {
+                    self.exec_env
+                        .message_instructions()
+                        .add(message_instructions);
+                }
                 let messages = NumMessages::from(message_instructions.map(|_| 1).unwrap_or(0));
                 measurement_scope.add(round_instructions_executed, NumSlices::from(1), messages);
                 if round_limits.instructions <= RoundInstructions::from(0) {
@@ -420,19 +430,29 @@ impl SchedulerImpl {
 
         let mut total_heap_delta = NumBytes::from(0);
 
-        // Add `Heartbeat` tasks to be executed before input messages.
+        // Add `Heartbeat` and `GlobalTimer` tasks to be executed before input
+        // messages.
         {
             let _timer = self
                 .metrics
                 .round_inner_heartbeat_overhead_duration
                 .start_timer();
+            let now = state.time();
             for canister in state.canisters_iter_mut() {
+                let global_timer_has_reached_deadline =
+                    canister.system_state.global_timer.has_reached_deadline(now);
                 if canister.exports_heartbeat_method() {
                     canister
                         .system_state
                         .task_queue
                         .push_front(ExecutionTask::Heartbeat);
                 }
+                if global_timer_has_reached_deadline && canister.exports_global_timer_method() {
+                    canister
+                        .system_state
+                        .task_queue
+                        .push_front(ExecutionTask::GlobalTimer);
+                }
             }
         }
 
@@ -535,11 +555,12 @@ impl SchedulerImpl {
                 .metrics
                 .round_inner_heartbeat_overhead_duration
                 .start_timer();
-            // Remove all remaining `Heartbeat` tasks because they will be added
-            // again in the next round.
+            // Remove all remaining `Heartbeat` and `GlobalTimer` tasks
+            // because they will be added again in the next round. The global
+            // timer stays active until its task is executed.
             for canister in state.canisters_iter_mut() {
                 canister.system_state.task_queue.retain(|task| match task {
-                    ExecutionTask::Heartbeat => false,
+                    ExecutionTask::Heartbeat | ExecutionTask::GlobalTimer => false,
                     ExecutionTask::PausedExecution(..)
                     | ExecutionTask::PausedInstallCode(..)
                     | ExecutionTask::AbortedExecution(..)
@@ -814,7 +835,9 @@ impl SchedulerImpl {
         let state_time = state.time();
         let metadata_time_of_last_allocation_charge = state.metadata.time_of_last_allocation_charge;
         let mut all_rejects = Vec::new();
-        for canister in state.canisters_iter_mut() {
+        let mut uninstalled_canisters = Vec::new();
+        let canister_snapshots = &state.canister_snapshots;
+        for canister in state.canister_states.values_mut() {
             // Postpone charging for resources when a canister has a paused execution
             // to avoid modifying the balance of a canister during an unfinished operation.
             if canister.has_paused_execution() || canister.has_paused_install_code() {
@@ -845,12 +868,14 @@ impl SchedulerImpl {
                 .charge_canister_for_resource_allocation_and_usage(
                     &self.log,
                     canister,
+                    canister_snapshots.canister_memory_taken(canister.canister_id()),
                     duration_since_last_charge,
                     subnet_size,
                 )
                 .is_err()
             {
                 all_rejects.push(uninstall_canister(&self.log, canister, state_time));
+                uninstalled_canisters.push(canister.canister_id());
                 canister.scheduler_state.compute_allocation = ComputeAllocation::zero();
                 canister.system_state.memory_allocation = MemoryAllocation::BestEffort;
 
@@ -863,6 +888,13 @@ impl SchedulerImpl {
             }
         }
 
+        // Canisters that ran out of cycles lose their snapshots as well.
+        for canister_id in uninstalled_canisters {
+            state
+                .canister_snapshots
+                .remove_canister_snapshots(canister_id);
+        }
+
         // Send rejects to any requests that were forcibly closed while uninstalling.
         for rejects in all_rejects.into_iter() {
             process_responses(
@@ -1043,7 +1075,8 @@ impl SchedulerImpl {
             .iter()
             .filter(|(_, canister)| !canister.system_state.task_queue.is_empty());
 
-        // 1. Heartbeat tasks exist only during the round and must not exist after the round.
+        // 1. Heartbeat and GlobalTimer tasks exist only during the round and must not exist
+        //    after the round.
         // 2. Paused executions can exist only in ordinary rounds (not checkpoint rounds).
         // 3. If deterministic time slicing is disabled, then neither paused nor
         //    aborted tasks can exists.
@@ -1059,10 +1092,10 @@ impl SchedulerImpl {
                             id
                         );
                     }
-                    ExecutionTask::Heartbeat => {
+                    ExecutionTask::Heartbeat | ExecutionTask::GlobalTimer => {
                         panic!(
-                            "Unexpected heartbeat task after a round in canister {:?}",
-                            id
+                            "Unexpected system task {:?} after a round in canister {:?}",
+                            task, id
                         );
                     }
                     ExecutionTask::PausedExecution(_) | ExecutionTask::PausedInstallCode(_) => {
@@ -1232,6 +1265,11 @@ impl Scheduler for SchedulerImpl {
                 state = new_state;
                 let round_instructions_executed =
                     as_num_instructions(instructions_before - round_limits.instructions);
+                if let Some(message_instructions) = message_instructions {
+                    self.exec_env
+                        .message_instructions()
+                        .add(message_instructions);
+                }
                 let messages = NumMessages::from(message_instructions.map(|_| 1).unwrap_or(0));
                 measurement_scope.add(round_instructions_executed, NumSlices::from(1), messages);
                 if round_limits.instructions <= RoundInstructions::from(0) {
@@ -1588,6 +1626,7 @@ fn execute_canisters_on_thread(
             measurement_scope.add(round_instructions_executed, NumSlices::from(1), messages);
             if let Some(instructions_used) = instructions_used {
                 total_messages_executed.inc_assign();
+                exec_env.message_instructions().add(instructions_used);
                 observe_instructions_consumed_per_message(
                     &logger,
                     &metrics,
@@ -1831,6 +1870,12 @@ fn get_instructions_limits_for_subnet_message(
             | DeleteCanister
             | DepositCycles
             | ECDSAPublicKey
+            | FetchCanisterLogs
+            | TakeCanisterSnapshot
+            | LoadCanisterSnapshot
+            | ListCanisterSnapshots
+            | DeleteCanisterSnapshot
+            | CanisterInfo
             | RawRand
             | SetController
             | HttpRequest
@@ -1875,6 +1920,12 @@ fn is_bitcoin_request(msg: &CanisterInputMessage) -> bool {
                 | DeleteCanister
                 | DepositCycles
                 | ECDSAPublicKey
+                | FetchCanisterLogs
+                | TakeCanisterSnapshot
+                | LoadCanisterSnapshot
+                | ListCanisterSnapshots
+                | DeleteCanisterSnapshot
+                | CanisterInfo
                 | RawRand
                 | SetController
                 | HttpRequest
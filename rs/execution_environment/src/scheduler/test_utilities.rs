@@ -689,6 +689,8 @@ impl SchedulerTestBuilder {
             Arc::<TestWasmExecutor>::clone(&wasm_executor),
             deterministic_time_slicing,
             config.cost_to_compile_wasm_instruction,
+            config.subnet_memory_capacity,
+            config.subnet_memory_threshold,
         );
         let hypervisor = Arc::new(hypervisor);
         let ingress_history_writer =
@@ -982,6 +984,7 @@ impl TestWasmExecutorCore {
                     accessed_pages: 0,
                     dirty_pages: 0,
                 },
+                canister_log: Default::default(),
             };
             self.schedule
                 .push((self.round, canister_id, instructions_to_execute));
@@ -1020,6 +1023,7 @@ impl TestWasmExecutorCore {
             allocated_message_bytes: NumBytes::from(0),
             num_instructions_left: instructions_left,
             instance_stats,
+            canister_log: Default::default(),
         };
         self.schedule
             .push((self.round, canister_id, instructions_to_execute));
@@ -1157,7 +1161,7 @@ impl TestWasmExecutorCore {
                 let message = self.messages.remove(&message_id).unwrap();
                 (message_id, message, Some(*call_context_id))
             }
-            ApiType::Heartbeat {
+            ApiType::SystemTask {
                 call_context_id, ..
             } => {
                 let message_id = self.next_message_id();
@@ -637,6 +637,25 @@ fn validate_consumed_instructions_metric() {
     );
 }
 
+#[test]
+fn message_instructions_include_install_code() {
+    let mut test = SchedulerTestBuilder::new().build();
+
+    let canister = test.create_canister();
+    let upgrade = TestInstallCode::Upgrade {
+        pre_upgrade: instructions(10),
+        start: instructions(20),
+        post_upgrade: instructions(30),
+    };
+    test.inject_install_code_call_to_ic00(canister, upgrade);
+    test.send_ingress(canister, ingress(50));
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+
+    let response = test.get_responses_to_injected_calls().pop().unwrap();
+    assert_eq!(response.response_payload, Payload::Data(EmptyBlob.encode()));
+    assert!(test.scheduler().exec_env.message_instructions().get() >= NumInstructions::from(110));
+}
+
 #[test]
 fn only_charge_for_allocation_after_specified_duration() {
     let mut test = SchedulerTestBuilder::new().build();
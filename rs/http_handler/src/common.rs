@@ -92,6 +92,7 @@ pub(crate) fn make_response(user_error: UserError) -> Response<Body> {
         C::CanisterAlreadyInstalled => StatusCode::PRECONDITION_FAILED,
         C::CanisterWasmModuleNotFound => StatusCode::SERVICE_UNAVAILABLE,
         C::CanisterEmpty => StatusCode::SERVICE_UNAVAILABLE,
+        C::CanisterSnapshotNotFound => StatusCode::NOT_FOUND,
         C::InsufficientTransferFunds => StatusCode::SERVICE_UNAVAILABLE,
         C::InsufficientMemoryAllocation => StatusCode::SERVICE_UNAVAILABLE,
         C::InsufficientCyclesForCreateCanister => StatusCode::SERVICE_UNAVAILABLE,
@@ -119,6 +120,10 @@ pub(crate) fn make_response(user_error: UserError) -> Response<Body> {
         C::CanisterWasmEngineError => StatusCode::INTERNAL_SERVER_ERROR,
         C::CanisterInstructionLimitExceeded => StatusCode::INTERNAL_SERVER_ERROR,
         C::CanisterInstallCodeRateLimited => StatusCode::TOO_MANY_REQUESTS,
+        C::QueryCallGraphTooDeep => StatusCode::INTERNAL_SERVER_ERROR,
+        C::QueryCallGraphTotalInstructionLimitExceeded => StatusCode::INTERNAL_SERVER_ERROR,
+        C::CanisterWasmMemoryLimitExceeded => StatusCode::INTERNAL_SERVER_ERROR,
+        C::ReservedCyclesLimitExceededInMemoryAllocation => StatusCode::INTERNAL_SERVER_ERROR,
     };
     make_plaintext_response(status, user_error.description().to_string())
 }
@@ -68,7 +68,9 @@ mod tests {
     use super::*;
     use ic_crypto_tree_hash::{flatmap, Label, LabeledTree};
     use ic_registry_subnet_type::SubnetType;
-    use ic_replicated_state::{BitcoinState, CanisterQueues, ReplicatedState, SystemMetadata};
+    use ic_replicated_state::{
+        BitcoinState, CanisterQueues, CanisterSnapshots, ReplicatedState, SystemMetadata,
+    };
     use ic_test_utilities::{
         mock_time, state::ReplicatedStateBuilder, state_manager::MockStateManager,
         types::ids::subnet_test_id,
@@ -101,6 +103,7 @@ mod tests {
                         CanisterQueues::default(),
                         Vec::new(),
                         BitcoinState::default(),
+                        CanisterSnapshots::default(),
                         std::path::PathBuf::new(),
                     )),
                 )
@@ -9,6 +9,7 @@ use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_sys::{PageBytes, PageIndex};
 use ic_types::{
+    canister_log::CanisterLog,
     crypto::canister_threshold_sig::MasterEcdsaPublicKey,
     ingress::{IngressStatus, WasmResult},
     messages::{
@@ -336,6 +337,9 @@ pub trait OutOfInstructionsHandler {
 }
 
 /// A trait for providing all necessary imports to a Wasm module.
+///
+/// Addresses and sizes of buffers in the Wasm memory are `u64`, so that the
+/// same functions serve modules with a 32-bit and with a 64-bit memory.
 pub trait SystemApi {
     /// Stores the total execution complexity.
     fn set_total_execution_complexity(&mut self, complexity: ExecutionComplexity);
@@ -361,6 +365,11 @@ pub trait SystemApi {
     /// Returns the subnet type the replica runs on.
     fn subnet_type(&self) -> SubnetType;
 
+    /// Saves the specified bytes on the heap as a new record in the canister
+    /// log. Messages of traps are prefixed with `[TRAP]: `. Saving a log
+    /// message never fails.
+    fn save_log_message(&mut self, is_trap: bool, src: u64, size: u64, heap: &[u8]);
+
     /// Returns the message instruction limit, which is the total instruction
     /// limit for all slices combined.
     fn message_instruction_limit(&self) -> NumInstructions;
@@ -381,9 +390,9 @@ pub trait SystemApi {
     /// id in case of requests or the user id in case of an ingress message.
     fn ic0_msg_caller_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -397,9 +406,9 @@ pub trait SystemApi {
     /// memory[dst..dst+size].
     fn ic0_msg_arg_data_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -411,9 +420,9 @@ pub trait SystemApi {
     /// only be called in the context of inspecting messages.
     fn ic0_msg_method_name_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -426,8 +435,8 @@ pub trait SystemApi {
     /// it to the (initially empty) data reply.
     fn ic0_msg_reply_data_append(
         &mut self,
-        src: u32,
-        size: u32,
+        src: u64,
+        size: u64,
         heap: &[u8],
     ) -> HypervisorResult<()>;
 
@@ -443,7 +452,7 @@ pub trait SystemApi {
     fn ic0_msg_reject_code(&self) -> HypervisorResult<i32>;
 
     /// Replies to sender with an error message
-    fn ic0_msg_reject(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_msg_reject(&mut self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()>;
 
     /// Returns the length of the reject message in bytes.
     ///
@@ -462,9 +471,9 @@ pub trait SystemApi {
     /// called from inside a reject callback.
     fn ic0_msg_reject_msg_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -475,9 +484,9 @@ pub trait SystemApi {
     /// canister to heap[dst..dst+size].
     fn ic0_canister_self_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -488,33 +497,33 @@ pub trait SystemApi {
     /// controller to heap[dst..dst+size].
     fn ic0_controller_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
     /// Outputs the specified bytes on the heap as a string on STDOUT.
-    fn ic0_debug_print(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_debug_print(&self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()>;
 
     /// Traps, with a possibly helpful message
-    fn ic0_trap(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_trap(&self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()>;
 
     /// Creates a pending inter-canister message that will be scheduled if the
     /// current message execution completes successfully.
     #[allow(clippy::too_many_arguments)]
     fn ic0_call_simple(
         &mut self,
-        callee_src: u32,
-        callee_size: u32,
-        method_name_src: u32,
-        method_name_len: u32,
+        callee_src: u64,
+        callee_size: u64,
+        method_name_src: u64,
+        method_name_len: u64,
         reply_fun: u32,
         reply_env: u32,
         reject_fun: u32,
         reject_env: u32,
-        data_src: u32,
-        data_len: u32,
+        data_src: u64,
+        data_len: u64,
         heap: &[u8],
     ) -> HypervisorResult<i32>;
 
@@ -530,10 +539,10 @@ pub trait SystemApi {
     #[allow(clippy::too_many_arguments)]
     fn ic0_call_new(
         &mut self,
-        callee_src: u32,
-        callee_size: u32,
-        name_src: u32,
-        name_len: u32,
+        callee_src: u64,
+        callee_size: u64,
+        name_src: u64,
+        name_len: u64,
         reply_fun: u32,
         reply_env: u32,
         reject_fun: u32,
@@ -544,7 +553,7 @@ pub trait SystemApi {
     /// Appends the specified bytes to the argument of the call. Initially, the
     /// argument is empty. This can be called multiple times between
     /// `ic0.call_new` and `ic0.call_perform`.
-    fn ic0_call_data_append(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_call_data_append(&mut self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()>;
 
     /// Specifies the closure to be called if the reply/reject closures trap.
     /// Can be called at most once between `ic0.call_new` and
@@ -605,9 +614,9 @@ pub trait SystemApi {
     /// memory or offset+size exceeds the size of the stable memory.
     fn ic0_stable_read(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -619,9 +628,9 @@ pub trait SystemApi {
     /// Returns the number of **new** dirty pages created by the write.
     fn ic0_stable_write(
         &mut self,
-        offset: u32,
-        src: u32,
-        size: u32,
+        offset: u64,
+        src: u64,
+        size: u64,
         heap: &[u8],
     ) -> HypervisorResult<NumPages>;
 
@@ -720,7 +729,7 @@ pub trait SystemApi {
     /// The amount of cycles is represented by a 128-bit value
     /// and is copied in the canister memory starting
     /// starting at the location `dst`.
-    fn ic0_canister_cycles_balance128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
+    fn ic0_canister_cycles_balance128(&self, dst: u64, heap: &mut [u8]) -> HypervisorResult<()>;
 
     /// (deprecated) Please use `ic0_msg_cycles_available128` instead.
     /// This API supports only 64-bit values.
@@ -736,7 +745,7 @@ pub trait SystemApi {
     /// The amount of cycles is represented by a 128-bit value
     /// and is copied in the canister memory starting
     /// starting at the location `dst`.
-    fn ic0_msg_cycles_available128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
+    fn ic0_msg_cycles_available128(&self, dst: u64, heap: &mut [u8]) -> HypervisorResult<()>;
 
     /// (deprecated) Please use `ic0_msg_cycles_refunded128` instead.
     /// This API supports only 64-bit values.
@@ -752,7 +761,7 @@ pub trait SystemApi {
     /// The amount of cycles is represented by a 128-bit value
     /// and is copied in the canister memory starting
     /// starting at the location `dst`.
-    fn ic0_msg_cycles_refunded128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
+    fn ic0_msg_cycles_refunded128(&self, dst: u64, heap: &mut [u8]) -> HypervisorResult<()>;
 
     /// (deprecated) Please use `ic0_msg_cycles_accept128` instead.
     /// This API supports only 64-bit values.
@@ -798,13 +807,13 @@ pub trait SystemApi {
     fn ic0_msg_cycles_accept128(
         &mut self,
         max_amount: Cycles,
-        dst: u32,
+        dst: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
     /// Sets the certified data for the canister.
     /// See: https://sdk.dfinity.org/docs/interface-spec/index.html#system-api-certified-data
-    fn ic0_certified_data_set(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_certified_data_set(&mut self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()>;
 
     /// If run in non-replicated execution (i.e. query),
     /// returns 1 if the data certificate is present, 0 otherwise.
@@ -822,9 +831,9 @@ pub trait SystemApi {
     /// Traps if data_certificate_present returns 0.
     fn ic0_data_certificate_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -843,6 +852,24 @@ pub trait SystemApi {
     ///
     /// Returns the amount of cycles added to the canister's balance.
     fn ic0_mint_cycles(&mut self, amount: u64) -> HypervisorResult<u64>;
+
+    /// Sets the canister global timer to the specified absolute time
+    /// (in nanoseconds since 1970-01-01). Passing zero deactivates the timer.
+    ///
+    /// Returns the previous value of the timer, or zero if the timer was
+    /// not active.
+    fn ic0_global_timer_set(&mut self, time: Time) -> HypervisorResult<Time>;
+
+    /// Checks whether the principal identified by `src` and `size` is one of
+    /// the controllers of the canister. Returns `1` if it is and `0`
+    /// otherwise.
+    ///
+    /// Traps if the bytes do not form a valid principal.
+    fn ic0_is_controller(&self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<u32>;
+
+    /// Returns the current version of the canister. The version is
+    /// incremented on every `install_code`, settings change and uninstall.
+    fn ic0_canister_version(&self) -> HypervisorResult<u64>;
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -924,6 +951,9 @@ pub struct WasmExecutionOutput {
     pub allocated_bytes: NumBytes,
     pub allocated_message_bytes: NumBytes,
     pub instance_stats: InstanceStats,
+    /// Log records produced during the execution. They are kept even if the
+    /// execution fails.
+    pub canister_log: CanisterLog,
 }
 
 impl fmt::Display for WasmExecutionOutput {
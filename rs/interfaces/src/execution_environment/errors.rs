@@ -1,6 +1,6 @@
 use ic_base_types::{CanisterIdError, PrincipalIdBlobParseError};
 use ic_error_types::UserError;
-use ic_types::{methods::WasmMethod, CanisterId, Cycles};
+use ic_types::{methods::WasmMethod, CanisterId, Cycles, NumBytes};
 use ic_wasm_types::{WasmEngineError, WasmInstrumentationError, WasmValidationError};
 use serde::{Deserialize, Serialize};
 
@@ -121,6 +121,27 @@ pub enum HypervisorError {
     /// The canister is close to running out of Wasm memory and
     /// attempted to allocate reserved Wasm pages.
     WasmReservedPages,
+    /// An attempt was made to grow the Wasm memory of the canister above the
+    /// `wasm_memory_limit` in its settings.
+    WasmMemoryLimitExceeded {
+        bytes: NumBytes,
+        limit: NumBytes,
+    },
+    /// The canister allocated memory while the subnet memory usage was above
+    /// the threshold and does not have enough cycles to reserve for storage.
+    InsufficientCyclesInMemoryAllocation {
+        bytes: NumBytes,
+        available: Cycles,
+        requested: Cycles,
+    },
+    /// The canister allocated memory while the subnet memory usage was above
+    /// the threshold and the cycles to reserve for storage would exceed the
+    /// `reserved_cycles_limit` in its settings.
+    ReservedCyclesLimitExceededInMemoryAllocation {
+        bytes: NumBytes,
+        requested: Cycles,
+        limit: Cycles,
+    },
     /// The execution was aborted by deterministic time slicing. This error is
     /// not observable by the user and should be processed before leaving Wasm
     /// execution.
@@ -178,6 +199,7 @@ impl HypervisorError {
                 let kind = match wasm_method {
                     WasmMethod::Update(_) => "update",
                     WasmMethod::Query(_) => "query",
+                    WasmMethod::CompositeQuery(_) => "composite query",
                     WasmMethod::System(_) => "system",
                 };
 
@@ -238,6 +260,41 @@ impl HypervisorError {
                     canister_id
                 ),
             ),
+            Self::WasmMemoryLimitExceeded { bytes, limit } => UserError::new(
+                E::CanisterWasmMemoryLimitExceeded,
+                format!(
+                    "Canister {} exceeded its Wasm memory limit of {} bytes by attempting to grow \
+                    its Wasm memory to {} bytes. If this memory usage is expected, the limit can be \
+                    increased in the canister settings.",
+                    canister_id,
+                    limit.get(),
+                    bytes.get()
+                ),
+            ),
+            Self::InsufficientCyclesInMemoryAllocation {
+                bytes,
+                available,
+                requested,
+            } => UserError::new(
+                E::CanisterOutOfCycles,
+                format!(
+                    "Canister {} cannot allocate {} bytes because it needs to reserve {} cycles \
+                    for storage but only {} cycles are available above the freezing threshold.",
+                    canister_id, bytes, requested, available
+                ),
+            ),
+            Self::ReservedCyclesLimitExceededInMemoryAllocation {
+                bytes,
+                requested,
+                limit,
+            } => UserError::new(
+                E::ReservedCyclesLimitExceededInMemoryAllocation,
+                format!(
+                    "Canister {} cannot allocate {} bytes because its reserved cycles would grow \
+                    to {} cycles, which exceeds the reserved cycles limit of {} cycles.",
+                    canister_id, bytes, requested, limit
+                ),
+            ),
             Self::CanisterStopped => UserError::new(
                 E::CanisterStopped,
                 format!("Canister {} is stopped", canister_id,),
@@ -314,6 +371,13 @@ impl HypervisorError {
             HypervisorError::Cleanup { .. } => "Cleanup",
             HypervisorError::WasmEngineError(_) => "WasmEngineError",
             HypervisorError::WasmReservedPages => "WasmReservedPages",
+            HypervisorError::WasmMemoryLimitExceeded { .. } => "WasmMemoryLimitExceeded",
+            HypervisorError::InsufficientCyclesInMemoryAllocation { .. } => {
+                "InsufficientCyclesInMemoryAllocation"
+            }
+            HypervisorError::ReservedCyclesLimitExceededInMemoryAllocation { .. } => {
+                "ReservedCyclesLimitExceededInMemoryAllocation"
+            }
             HypervisorError::Aborted => "Aborted",
         }
     }
@@ -347,7 +411,10 @@ impl HypervisorError {
             | HypervisorError::InvalidCanisterId(_)
             | HypervisorError::MessageRejected
             | HypervisorError::InsufficientCyclesBalance(_)
-            | HypervisorError::WasmReservedPages => false,
+            | HypervisorError::WasmReservedPages
+            | HypervisorError::WasmMemoryLimitExceeded { .. }
+            | HypervisorError::InsufficientCyclesInMemoryAllocation { .. }
+            | HypervisorError::ReservedCyclesLimitExceededInMemoryAllocation { .. } => false,
         }
     }
 }
@@ -1,4 +1,5 @@
 //! Messages used in various components.
+use ic_ic00_types::CanisterChangeOrigin;
 use ic_types::{
     messages::{Ingress, Request, Response, StopCanisterContext},
     CanisterId, Cycles, PrincipalId,
@@ -77,6 +78,20 @@ impl RequestOrIngress {
         }
     }
 
+    /// Returns the origin to record in the canister history for a change
+    /// triggered by this message. The version of a calling canister is not
+    /// known, so it is left unspecified.
+    pub fn canister_change_origin(&self) -> CanisterChangeOrigin {
+        match self {
+            RequestOrIngress::Request(request) => {
+                CanisterChangeOrigin::from_canister(request.sender.get(), None)
+            }
+            RequestOrIngress::Ingress(ingress) => {
+                CanisterChangeOrigin::from_user(ingress.source.get())
+            }
+        }
+    }
+
     /// Extracts the cycles received with this message.
     pub fn take_cycles(&mut self) -> Cycles {
         match self {
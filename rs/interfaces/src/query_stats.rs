@@ -0,0 +1,87 @@
+use crate::validation::ValidationError;
+use ic_interfaces_state_manager::StateManagerError;
+use ic_types::{
+    batch::{QueryStatsEpoch, QueryStatsPayload, ValidationContext},
+    consensus::Payload,
+    CanisterId, Height, NodeId, NumBytes, Time,
+};
+
+/// A QueryStatsPayload error from which it is not possible to recover.
+#[derive(Debug)]
+pub enum InvalidQueryStatsPayload {
+    /// The payload contains statistics of an epoch that is not over yet.
+    EpochNotOver {
+        epoch: QueryStatsEpoch,
+        certified_epoch: QueryStatsEpoch,
+    },
+    /// The payload contains statistics of an epoch that was already
+    /// aggregated into the replicated state.
+    EpochAlreadyAggregated(QueryStatsEpoch),
+    /// The statistics of the canister were already reported by the proposer
+    /// for the epoch.
+    DuplicateCanisterStats {
+        proposer: NodeId,
+        canister_id: CanisterId,
+    },
+    /// The payload claims to be proposed by a node other than the one that
+    /// made the block.
+    ProposerMismatch {
+        payload_proposer: NodeId,
+        block_proposer: NodeId,
+    },
+}
+
+/// A QueryStatsPayload error from which it may be possible to recover.
+#[derive(Debug)]
+pub enum QueryStatsTransientValidationError {
+    GetStateFailed(Height, StateManagerError),
+}
+
+/// A QueryStatsPayload error that results from payload validation.
+pub type QueryStatsPayloadValidationError =
+    ValidationError<InvalidQueryStatsPayload, QueryStatsTransientValidationError>;
+
+pub trait QueryStatsPayloadBuilder: Send + Sync {
+    /// Produces a `QueryStatsPayload` of maximum byte size `byte_limit` that
+    /// contains the statistics collected by this replica during the last
+    /// completed epoch, excluding those already included in `past_payloads`
+    /// or in the certified state.
+    ///
+    /// Returns `None` if there is nothing to report.
+    fn get_query_stats_payload(
+        &self,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+        byte_limit: NumBytes,
+    ) -> Option<QueryStatsPayload>;
+
+    /// Checks whether the provided `QueryStatsPayload` of a block made by
+    /// `proposer` is valid given a `ValidationContext` and `past_payloads`.
+    ///
+    /// If valid, returns the payload's `CountBytes` size; else returns a
+    /// permanent or transient `ValidationError`.
+    fn validate_query_stats_payload(
+        &self,
+        payload: &QueryStatsPayload,
+        proposer: NodeId,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+    ) -> Result<NumBytes, QueryStatsPayloadValidationError>;
+
+    /// Extracts the sequence of past `QueryStatsPayloads` from `past_payloads`.
+    fn filter_past_payloads<'a>(
+        &self,
+        past_payloads: &'a [(Height, Time, Payload)],
+    ) -> Vec<&'a QueryStatsPayload> {
+        past_payloads
+            .iter()
+            .filter_map(|(_, _, payload)| {
+                if payload.is_summary() {
+                    None
+                } else {
+                    payload.as_ref().as_data().batch.query_stats.as_ref()
+                }
+            })
+            .collect()
+    }
+}
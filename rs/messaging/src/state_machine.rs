@@ -134,6 +134,14 @@ impl StateMachine for StateMachineImpl {
             )
         }
 
+        // Record the query statistics agreed upon by consensus.
+        if let Some(query_stats) = batch.payload.query_stats.take() {
+            state
+                .metadata
+                .query_stats
+                .deliver(query_stats, &mut state.canister_states);
+        }
+
         // Preprocess messages and add messages to the induction pool through the Demux.
         let mut state_with_messages = self.demux.process_payload(state, batch.payload);
 
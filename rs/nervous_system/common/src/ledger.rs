@@ -1,12 +1,14 @@
 use crate::NervousSystemError;
 use async_trait::async_trait;
+use dfn_candid::candid_one;
 use dfn_core::api::PrincipalId;
 use dfn_core::{call, CanisterId};
 use dfn_protobuf::protobuf;
 use ic_crypto_sha::Sha256;
+use ic_ledger_core::timestamp::TimeStamp;
 use ledger_canister::{
-    tokens_from_proto, AccountBalanceArgs, AccountIdentifier, Memo, SendArgs, Subaccount, Tokens,
-    TotalSupplyArgs,
+    tokens_from_proto, AccountBalanceArgs, AccountIdentifier, BlockIndex, Memo, SendArgs,
+    Subaccount, Tokens, TotalSupplyArgs, TransferArgs, TransferError,
 };
 
 pub struct LedgerCanister {
@@ -35,6 +37,23 @@ pub trait Ledger: Send + Sync {
         memo: u64,
     ) -> Result<u64, NervousSystemError>;
 
+    /// Transfers funds like `transfer_funds`, but sets the creation time of
+    /// the transfer, so that the ledger deduplicates it: if an identical
+    /// transfer was already made within the ledger's deduplication window,
+    /// the funds are not transferred again.
+    ///
+    /// Returns the block height at which the transfer was recorded, which is
+    /// the one of the identical transfer in case of a duplicate.
+    async fn transfer_funds_deduplicated(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to: AccountIdentifier,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<u64, NervousSystemError>;
+
     /// Gets the total supply of tokens from the sum of all accounts except for the
     /// minting canister's.
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError>;
@@ -86,6 +105,47 @@ impl Ledger for LedgerCanister {
         })
     }
 
+    async fn transfer_funds_deduplicated(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to: AccountIdentifier,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<u64, NervousSystemError> {
+        let result: Result<Result<BlockIndex, TransferError>, (Option<i32>, String)> = call(
+            self.id,
+            "transfer",
+            candid_one,
+            TransferArgs {
+                memo: Memo(memo),
+                amount: Tokens::from_e8s(amount_e8s),
+                fee: Tokens::from_e8s(fee_e8s),
+                from_subaccount,
+                to: to.to_address(),
+                created_at_time: Some(TimeStamp::from_nanos_since_unix_epoch(
+                    created_at_time_nanos,
+                )),
+            },
+        )
+        .await;
+
+        match result {
+            Ok(Ok(block_index)) => Ok(block_index),
+            // The transfer was already made.
+            Ok(Err(TransferError::TxDuplicate { duplicate_of })) => Ok(duplicate_of),
+            Ok(Err(err)) => Err(NervousSystemError::new_with_message(format!(
+                "The ledger canister rejected the transfer: {}",
+                err
+            ))),
+            Err((code, msg)) => Err(NervousSystemError::new_with_message(format!(
+                "Error calling method 'transfer' of the ledger canister. Code: {:?}. Message: {}",
+                code, msg
+            ))),
+        }
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         let result: Result<Tokens, (Option<i32>, String)> =
             call(self.id, "total_supply_pb", protobuf, TotalSupplyArgs {})
@@ -95,6 +95,18 @@ impl Ledger for MockLedger {
         unimplemented!()
     }
 
+    async fn transfer_funds_deduplicated(
+        &self,
+        _amount_e8s: u64,
+        _fee_e8s: u64,
+        _from_subaccount: Option<Subaccount>,
+        _to: AccountIdentifier,
+        _memo: u64,
+        _created_at_time_nanos: u64,
+    ) -> Result<u64, NervousSystemError> {
+        unimplemented!()
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         unimplemented!()
     }
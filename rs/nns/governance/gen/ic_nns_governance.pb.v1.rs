@@ -559,7 +559,7 @@ pub struct Proposal {
     /// take.
     #[prost(
         oneof = "proposal::Action",
-        tags = "10, 12, 13, 14, 15, 16, 17, 18, 19, 21, 22, 23"
+        tags = "10, 12, 13, 14, 15, 16, 17, 18, 19, 21, 22, 23, 24"
     )]
     pub action: ::core::option::Option<proposal::Action>,
 }
@@ -643,6 +643,9 @@ pub mod proposal {
         /// Call the open method on an SNS swap canister.
         #[prost(message, tag = "23")]
         OpenSnsTokenSwap(super::OpenSnsTokenSwap),
+        /// Transfer ICP to a ledger account.
+        #[prost(message, tag = "24")]
+        TransferIcp(super::TransferIcp),
     }
 }
 /// Empty message to use in oneof fields that represent empty
@@ -1536,6 +1539,41 @@ pub struct ProposalData {
     /// operation in the execution of an OpenSnsTokenSwap proposal.
     #[prost(enumeration = "::ic_sns_swap::pb::v1::Lifecycle", optional, tag = "19")]
     pub sns_token_swap_lifecycle: ::core::option::Option<i32>,
+    /// This is populated when a TransferIcp proposal is adopted, and tracks the
+    /// ledger transfer made to execute it.
+    #[prost(message, optional, tag = "20")]
+    pub transfer_icp_state: ::core::option::Option<TransferIcpState>,
+}
+/// The progress of the ledger transfer of an adopted TransferIcp proposal.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct TransferIcpState {
+    /// The number of attempts at making the transfer that failed.
+    #[prost(uint32, tag = "1")]
+    pub failed_attempts: u32,
+    /// When the latest attempt at making the transfer started.
+    #[prost(uint64, tag = "2")]
+    pub last_attempt_timestamp_seconds: u64,
+    /// Whether an attempt at making the transfer is waiting for the reply of the
+    /// ledger. No other attempt is started meanwhile. This is cleared when the
+    /// governance canister is upgraded, and ignored once the attempt is older
+    /// than the retry interval, as the ledger deduplicates the attempts anyway.
+    #[prost(bool, tag = "3")]
+    pub in_flight: bool,
+    /// The index of the ledger block that records the transfer. Once this is
+    /// set, the transfer is never made again.
+    #[prost(uint64, optional, tag = "4")]
+    pub block_index: ::core::option::Option<u64>,
+    /// The fee paid by the transfer. It is set by the first attempt, so that all
+    /// attempts are identical and deduplicated by the ledger.
+    #[prost(uint64, tag = "5")]
+    pub fee_e8s: u64,
 }
 /// Stores data relevant to the "wait for quiet" implementation.
 #[derive(
@@ -1610,6 +1648,10 @@ pub struct ProposalInfo {
     pub reward_status: i32,
     #[prost(uint64, optional, tag = "19")]
     pub deadline_timestamp_seconds: ::core::option::Option<u64>,
+    /// The index of the ledger block that records the transfer made to execute
+    /// a TransferIcp proposal, once it succeeded.
+    #[prost(uint64, optional, tag = "20")]
+    pub transfer_icp_block_index: ::core::option::Option<u64>,
 }
 /// Network economics contains the parameters for several operations related
 /// to the economy of the network. When submitting a NetworkEconomics proposal
@@ -1757,6 +1799,37 @@ pub struct OpenSnsTokenSwap {
     #[prost(uint64, optional, tag = "3")]
     pub community_fund_investment_e8s: ::core::option::Option<u64>,
 }
+/// Proposal action to transfer ICP to a ledger account, e.g., to pay out a
+/// grant.
+///
+/// The ICP is transferred from the treasury subaccount of the governance
+/// canister, which pays the regular transaction fee on top of `amount_e8s`.
+/// A proposal is only accepted when the treasury holds enough ICP to pay for it
+/// and for all the other TransferIcp proposals that are open or adopted.
+///
+/// A transfer that fails is retried periodically, with the same arguments and
+/// creation time, so that the ledger deduplicates the attempts and the funds
+/// are transferred at most once. The proposal fails when the transfer did not
+/// succeed within 12 hours of its adoption.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct TransferIcp {
+    /// The amount to transfer. Must be positive.
+    #[prost(uint64, tag = "1")]
+    pub amount_e8s: u64,
+    /// The ledger account that receives the funds.
+    #[prost(message, optional, tag = "2")]
+    pub to_account: ::core::option::Option<::ledger_canister::protobuf::AccountIdentifier>,
+    /// The memo of the ledger transfer, which lets the recipient identify it.
+    #[prost(uint64, tag = "3")]
+    pub memo: u64,
+}
 /// This represents the whole NNS governance system. It contains all
 /// information about the NNS governance system that must be kept
 /// across upgrades of the NNS governance system.
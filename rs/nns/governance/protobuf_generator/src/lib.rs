@@ -54,6 +54,14 @@ pub fn generate_prost_files(proto: ProtoPaths<'_>, out: &Path) {
         "ic_nns_governance.pb.v1.OpenSnsTokenSwap",
         "#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]",
     );
+    config.type_attribute(
+        "ic_nns_governance.pb.v1.TransferIcp",
+        "#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]",
+    );
+    config.type_attribute(
+        "ic_nns_governance.pb.v1.TransferIcpState",
+        "#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]",
+    );
     config.type_attribute(
         "ic_nns_governance.pb.v1.SetSnsTokenSwapOpenTimeWindow",
         "#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]",
@@ -28,8 +28,8 @@ use crate::pb::v1::{
     MostRecentMonthlyNodeProviderRewards, Motion, NetworkEconomics, Neuron, NeuronInfo,
     NeuronState, NnsFunction, NodeProvider, OpenSnsTokenSwap, Proposal, ProposalData, ProposalInfo,
     ProposalRewardStatus, ProposalStatus, RewardEvent, RewardNodeProvider, RewardNodeProviders,
-    SetSnsTokenSwapOpenTimeWindow, SettleCommunityFundParticipation, Tally, Topic,
-    UpdateNodeProvider, Vote,
+    SetSnsTokenSwapOpenTimeWindow, SettleCommunityFundParticipation, Tally, Topic, TransferIcp,
+    TransferIcpState, UpdateNodeProvider, Vote,
 };
 
 use async_trait::async_trait;
@@ -165,6 +165,23 @@ const NODE_PROVIDER_REWARD_PERIOD_SECONDS: u64 = 2629800;
 
 const VALID_MATURITY_MODULATION_BASIS_POINTS_RANGE: RangeInclusive<i32> = -500..=500;
 
+/// The nonce of the treasury subaccount of the governance canister, which
+/// pays for TransferIcp proposals.
+const TREASURY_SUBACCOUNT_NONCE: u64 = 0;
+
+/// The maximum amount that a single TransferIcp proposal can transfer.
+pub const MAX_TRANSFER_ICP_AMOUNT_E8S: u64 = 100_000 * E8S_PER_ICP;
+
+/// The time after the adoption of a TransferIcp proposal during which its
+/// ledger transfer is attempted, after which the proposal fails. This is well
+/// within the transaction window of the ledger (24 hours), so the ledger
+/// deduplicates all the attempts.
+const TRANSFER_ICP_EXECUTION_PERIOD_SECONDS: u64 = 12 * 60 * 60;
+
+/// The minimum time between two attempts at making the ledger transfer of a
+/// TransferIcp proposal.
+const TRANSFER_ICP_RETRY_INTERVAL_SECONDS: u64 = 60 * 60;
+
 // The default values for network economics (until we initialize it).
 // Can't implement Default since it conflicts with Prost's.
 impl NetworkEconomics {
@@ -1187,6 +1204,7 @@ impl Proposal {
                     Topic::SnsDecentralizationSale
                 }
                 proposal::Action::OpenSnsTokenSwap(_) => Topic::SnsDecentralizationSale,
+                proposal::Action::TransferIcp(_) => Topic::Governance,
             }
         } else {
             Topic::Unspecified
@@ -2037,6 +2055,16 @@ pub fn neuron_subaccount(subaccount: Subaccount) -> AccountIdentifier {
     AccountIdentifier::new(GOVERNANCE_CANISTER_ID.get(), Some(subaccount))
 }
 
+/// The subaccount of the governance canister that pays for TransferIcp
+/// proposals.
+pub fn treasury_subaccount() -> Subaccount {
+    ledger::compute_distribution_subaccount(GOVERNANCE_CANISTER_ID.get(), TREASURY_SUBACCOUNT_NONCE)
+}
+
+pub fn treasury_account() -> AccountIdentifier {
+    AccountIdentifier::new(GOVERNANCE_CANISTER_ID.get(), Some(treasury_subaccount()))
+}
+
 impl Governance {
     pub fn new(
         mut proto: GovernanceProto,
@@ -2059,6 +2087,16 @@ impl Governance {
             })
         }
 
+        // Canisters are stopped, and thus have no outstanding calls, before
+        // they are upgraded. Therefore, an attempt at transferring the ICP of
+        // a TransferIcp proposal that is still in flight will never get a
+        // reply, e.g., because its callback trapped.
+        for proposal_data in proto.proposals.values_mut() {
+            if let Some(state) = proposal_data.transfer_icp_state.as_mut() {
+                state.in_flight = false;
+            }
+        }
+
         let mut gov = Self {
             proto,
             env,
@@ -4142,6 +4180,10 @@ impl Governance {
             deadline_timestamp_seconds: Some(
                 data.get_deadline_timestamp_seconds(voting_period_seconds),
             ),
+            transfer_icp_block_index: data
+                .transfer_icp_state
+                .as_ref()
+                .and_then(|state| state.block_index),
         }
     }
 
@@ -4636,6 +4678,219 @@ impl Governance {
         self.proto.most_recent_monthly_node_provider_rewards = Some(most_recent_rewards);
     }
 
+    /// Executes a TransferIcp proposal by transferring ICP from the treasury.
+    ///
+    /// All the attempts at making the transfer use the same arguments, and
+    /// the decision time of the proposal as creation time, so that the ledger
+    /// deduplicates them and the funds are transferred at most once. The
+    /// progress of the transfer is recorded in the proposal data: no attempt
+    /// is started while another recent one awaits the reply of the ledger, or
+    /// once an attempt succeeded. After a failed attempt, the proposal remains
+    /// adopted and the transfer is retried by `retry_transfer_icp_proposals`,
+    /// until `TRANSFER_ICP_EXECUTION_PERIOD_SECONDS` elapsed since the
+    /// decision.
+    async fn transfer_icp(&mut self, pid: u64, transfer_icp: &TransferIcp) {
+        let to_account = match transfer_icp_to_account(transfer_icp) {
+            Ok(to_account) => to_account,
+            Err(err) => {
+                self.set_proposal_execution_status(pid, Err(err));
+                return;
+            }
+        };
+
+        let now = self.env.now();
+        let transaction_fee_e8s = self.transaction_fee();
+        let proposal_data = match self.proto.proposals.get_mut(&pid) {
+            Some(proposal_data) => proposal_data,
+            None => {
+                println!(
+                    "{}TransferIcp proposal {} not found while trying to execute it.",
+                    LOG_PREFIX, pid
+                );
+                return;
+            }
+        };
+        let decided_timestamp_seconds = proposal_data.decided_timestamp_seconds;
+        let state = proposal_data
+            .transfer_icp_state
+            .get_or_insert_with(|| TransferIcpState {
+                fee_e8s: transaction_fee_e8s,
+                ..Default::default()
+            });
+        if state.block_index.is_some()
+            || (state.in_flight
+                && now
+                    < state
+                        .last_attempt_timestamp_seconds
+                        .saturating_add(TRANSFER_ICP_RETRY_INTERVAL_SECONDS))
+        {
+            return;
+        }
+        let deadline_timestamp_seconds =
+            decided_timestamp_seconds.saturating_add(TRANSFER_ICP_EXECUTION_PERIOD_SECONDS);
+        if now >= deadline_timestamp_seconds {
+            self.set_proposal_execution_status(
+                pid,
+                Err(GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!(
+                        "Couldn't transfer ICP within {} seconds of the adoption of the proposal.",
+                        TRANSFER_ICP_EXECUTION_PERIOD_SECONDS
+                    ),
+                )),
+            );
+            return;
+        }
+        state.in_flight = true;
+        state.last_attempt_timestamp_seconds = now;
+        let fee_e8s = state.fee_e8s;
+
+        let result = self
+            .ledger
+            .transfer_funds_deduplicated(
+                transfer_icp.amount_e8s,
+                fee_e8s,
+                Some(treasury_subaccount()),
+                to_account,
+                transfer_icp.memo,
+                decided_timestamp_seconds.saturating_mul(1_000_000_000),
+            )
+            .await;
+
+        let proposal_data = match self.proto.proposals.get_mut(&pid) {
+            Some(proposal_data) => proposal_data,
+            None => {
+                println!(
+                    "{}TransferIcp proposal {} not found after transferring ICP. Result: {:?}",
+                    LOG_PREFIX, pid, result
+                );
+                return;
+            }
+        };
+        let status = proposal_data.status();
+        let state = proposal_data
+            .transfer_icp_state
+            .get_or_insert_with(TransferIcpState::default);
+        state.in_flight = false;
+        if let Ok(block_index) = result {
+            state.block_index = Some(block_index);
+        }
+        // The reply of a stale attempt may arrive after a later attempt
+        // concluded the execution of the proposal.
+        if status != ProposalStatus::Adopted {
+            println!(
+                "{}TransferIcp proposal {} is {:?} after transferring ICP. Result: {:?}",
+                LOG_PREFIX, pid, status, result
+            );
+            return;
+        }
+        match result {
+            Ok(_) => self.set_proposal_execution_status(pid, Ok(())),
+            Err(err) => {
+                state.failed_attempts += 1;
+                let failed_attempts = state.failed_attempts;
+                let error = GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Couldn't transfer ICP: {}", GovernanceError::from(err)),
+                );
+                if now.saturating_add(TRANSFER_ICP_RETRY_INTERVAL_SECONDS)
+                    >= deadline_timestamp_seconds
+                {
+                    self.set_proposal_execution_status(pid, Err(error));
+                } else {
+                    println!(
+                        "{}Attempt {} at executing TransferIcp proposal {} failed, \
+                         it will be retried. Reason: {}",
+                        LOG_PREFIX, failed_attempts, pid, error
+                    );
+                }
+            }
+        }
+    }
+
+    /// Retries the ledger transfers of the adopted TransferIcp proposals whose
+    /// latest attempt started at least `TRANSFER_ICP_RETRY_INTERVAL_SECONDS`
+    /// ago, and either failed or never got a reply.
+    async fn retry_transfer_icp_proposals(&mut self) {
+        let now = self.env.now();
+        let to_retry: Vec<(u64, TransferIcp)> = self
+            .proto
+            .proposals
+            .iter()
+            .filter(|(_, data)| data.status() == ProposalStatus::Adopted)
+            .filter_map(|(pid, data)| {
+                let state = data.transfer_icp_state.as_ref()?;
+                if state.block_index.is_some()
+                    || now
+                        < state
+                            .last_attempt_timestamp_seconds
+                            .saturating_add(TRANSFER_ICP_RETRY_INTERVAL_SECONDS)
+                {
+                    return None;
+                }
+                match data.proposal.as_ref()?.action.as_ref()? {
+                    Action::TransferIcp(transfer_icp) => Some((*pid, transfer_icp.clone())),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        for (pid, transfer_icp) in to_retry {
+            self.transfer_icp(pid, &transfer_icp).await;
+        }
+    }
+
+    /// Checks that the treasury holds enough ICP to pay for the given
+    /// TransferIcp proposal, in addition to the TransferIcp proposals that are
+    /// open or adopted, and thus may still be paid.
+    ///
+    /// Since the balance is fetched asynchronously, proposals made
+    /// concurrently may all pass this check. The transfers that the treasury
+    /// cannot afford then fail.
+    async fn check_treasury_funds(
+        &self,
+        transfer_icp: &TransferIcp,
+    ) -> Result<(), GovernanceError> {
+        let balance_e8s = self
+            .ledger
+            .account_balance(treasury_account())
+            .await?
+            .get_e8s();
+
+        let transaction_fee_e8s = self.transaction_fee();
+        let committed_e8s = self
+            .proto
+            .proposals
+            .values()
+            .filter(|data| {
+                matches!(
+                    data.status(),
+                    ProposalStatus::Open | ProposalStatus::Adopted
+                )
+            })
+            .filter_map(|data| match data.proposal.as_ref()?.action.as_ref()? {
+                Action::TransferIcp(transfer_icp) => {
+                    Some(transfer_icp.amount_e8s.saturating_add(transaction_fee_e8s))
+                }
+                _ => None,
+            })
+            .fold(0_u64, u64::saturating_add);
+        let requested_e8s = transfer_icp.amount_e8s.saturating_add(transaction_fee_e8s);
+
+        if balance_e8s < committed_e8s.saturating_add(requested_e8s) {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::InsufficientFunds,
+                format!(
+                    "The treasury holds {} e8s, which doesn't cover the {} e8s requested \
+                     by this proposal in addition to the {} e8s of the TransferIcp \
+                     proposals that are open or adopted.",
+                    balance_e8s, requested_e8s, committed_e8s
+                ),
+            ));
+        }
+        Ok(())
+    }
+
     async fn perform_action(
         &mut self,
         pid: u64,
@@ -4861,6 +5116,9 @@ impl Governance {
                 )
                 .await;
             }
+            proposal::Action::TransferIcp(ref transfer_icp) => {
+                self.transfer_icp(pid, transfer_icp).await;
+            }
         }
     }
 
@@ -5308,6 +5566,8 @@ impl Governance {
                 self.validate_open_sns_token_swap(open_sns_token_swap)
             }
 
+            Action::TransferIcp(transfer_icp) => validate_transfer_icp(transfer_icp),
+
             Action::ManageNeuron(_)
             | Action::ManageNetworkEconomics(_)
             | Action::ApproveGenesisKyc(_)
@@ -6418,9 +6678,13 @@ impl Governance {
             Some(manage_neuron::Command::Follow(f)) => self
                 .follow(&id, caller, f)
                 .map(|_| ManageNeuronResponse::follow_response()),
-            Some(manage_neuron::Command::MakeProposal(p)) => self
-                .make_proposal(&id, caller, p)
-                .map(ManageNeuronResponse::make_proposal_response),
+            Some(manage_neuron::Command::MakeProposal(p)) => {
+                if let Some(Action::TransferIcp(transfer_icp)) = &p.action {
+                    self.check_treasury_funds(transfer_icp).await?;
+                }
+                self.make_proposal(&id, caller, p)
+                    .map(ManageNeuronResponse::make_proposal_response)
+            }
             Some(manage_neuron::Command::RegisterVote(v)) => self
                 .register_vote(&id, caller, v)
                 .map(|_| ManageNeuronResponse::register_vote_response()),
@@ -6547,6 +6811,7 @@ impl Governance {
             self.spawn_neurons().await;
         }
 
+        self.retry_transfer_icp_proposals().await;
         self.maybe_move_staked_maturity();
         self.maybe_gc();
     }
@@ -7490,6 +7755,52 @@ fn validate_open_sns_token_swap(
     Ok(())
 }
 
+fn validate_transfer_icp(transfer_icp: &TransferIcp) -> Result<(), GovernanceError> {
+    let mut defects = vec![];
+
+    if transfer_icp.amount_e8s == 0 {
+        defects.push("TransferIcp.amount_e8s must be positive.".to_string());
+    }
+
+    if transfer_icp.amount_e8s > MAX_TRANSFER_ICP_AMOUNT_E8S {
+        defects.push(format!(
+            "TransferIcp.amount_e8s must be at most {}.",
+            MAX_TRANSFER_ICP_AMOUNT_E8S
+        ));
+    }
+
+    if let Err(err) = transfer_icp_to_account(transfer_icp) {
+        defects.push(err.error_message);
+    }
+
+    if !defects.is_empty() {
+        return Err(GovernanceError::new_with_message(
+            ErrorType::InvalidProposal,
+            defects.join("\n"),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the ledger account that receives the funds of a TransferIcp
+/// proposal.
+fn transfer_icp_to_account(
+    transfer_icp: &TransferIcp,
+) -> Result<AccountIdentifier, GovernanceError> {
+    let to_account = transfer_icp.to_account.as_ref().ok_or_else(|| {
+        GovernanceError::new_with_message(
+            ErrorType::InvalidCommand,
+            "TransferIcp lacks a value in its to_account field.",
+        )
+    })?;
+    AccountIdentifier::try_from(to_account).map_err(|e| {
+        GovernanceError::new_with_message(
+            ErrorType::InvalidCommand,
+            format!("TransferIcp.to_account is not a valid account: {}", e),
+        )
+    })
+}
+
 /// A helper for the Registry's get_node_providers_monthly_xdr_rewards method
 async fn get_node_providers_monthly_xdr_rewards(
 ) -> Result<NodeProvidersMonthlyXdrRewards, GovernanceError> {
@@ -7724,6 +8035,52 @@ mod tests {
         assert!(result.is_err(), "{:#?}", result);
     }
 
+    lazy_static! {
+        static ref TRANSFER_ICP: TransferIcp = TransferIcp {
+            amount_e8s: 10 * E8S_PER_ICP,
+            to_account: Some(AccountIdentifier::new(*PRINCIPAL_ID_1, None).into()),
+            memo: 42,
+        };
+    }
+
+    #[test]
+    fn validate_transfer_icp_ok() {
+        let result = validate_transfer_icp(&*TRANSFER_ICP);
+        assert!(result.is_ok(), "{:#?}", result);
+    }
+
+    #[test]
+    fn validate_transfer_icp_amount_e8s() {
+        let result = validate_transfer_icp(&TransferIcp {
+            amount_e8s: 0,
+            ..TRANSFER_ICP.clone()
+        });
+        assert!(result.is_err(), "{:#?}", result);
+
+        let result = validate_transfer_icp(&TransferIcp {
+            amount_e8s: MAX_TRANSFER_ICP_AMOUNT_E8S + 1,
+            ..TRANSFER_ICP.clone()
+        });
+        assert!(result.is_err(), "{:#?}", result);
+    }
+
+    #[test]
+    fn validate_transfer_icp_to_account() {
+        let result = validate_transfer_icp(&TransferIcp {
+            to_account: None,
+            ..TRANSFER_ICP.clone()
+        });
+        assert!(result.is_err(), "{:#?}", result);
+
+        let result = validate_transfer_icp(&TransferIcp {
+            to_account: Some(ledger_canister::protobuf::AccountIdentifier {
+                hash: vec![1, 2, 3], // Too short.
+            }),
+            ..TRANSFER_ICP.clone()
+        });
+        assert!(result.is_err(), "{:#?}", result);
+    }
+
     lazy_static! {
         static ref ID_TO_NEURON: HashMap<u64, Neuron> = craft_id_to_neuron(&[
             // (maturity, conroller, joined cf at)
@@ -73,6 +73,18 @@ impl Ledger for DegradedEnv {
         unimplemented!()
     }
 
+    async fn transfer_funds_deduplicated(
+        &self,
+        _: u64,
+        _: u64,
+        _: Option<Subaccount>,
+        _: AccountIdentifier,
+        _: u64,
+        _: u64,
+    ) -> Result<u64, NervousSystemError> {
+        unimplemented!()
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         unimplemented!()
     }
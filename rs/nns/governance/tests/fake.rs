@@ -45,6 +45,20 @@ pub struct FakeState {
     pub now: u64,
     pub rng: ChaCha20Rng,
     pub accounts: LedgerMap,
+    /// The deduplicated transfers that were made, keyed by everything the
+    /// ledger compares to detect duplicates, and mapped to their block height.
+    pub deduplicated_transfers: HashMap<DeduplicatedTransfer, u64>,
+}
+
+/// A transfer that the fake ledger deduplicates.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DeduplicatedTransfer {
+    pub amount_e8s: u64,
+    pub fee_e8s: u64,
+    pub from_subaccount: Option<Subaccount>,
+    pub to: AccountIdentifier,
+    pub memo: u64,
+    pub created_at_time_nanos: u64,
 }
 
 impl Default for FakeState {
@@ -65,6 +79,7 @@ impl Default for FakeState {
             // different places doesn't conflict.
             rng: ChaCha20Rng::seed_from_u64(9539),
             accounts: HashMap::new(),
+            deduplicated_transfers: HashMap::new(),
         }
     }
 }
@@ -259,6 +274,40 @@ impl Ledger for FakeDriver {
         Ok(0)
     }
 
+    async fn transfer_funds_deduplicated(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to: AccountIdentifier,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<u64, NervousSystemError> {
+        let transfer = DeduplicatedTransfer {
+            amount_e8s,
+            fee_e8s,
+            from_subaccount,
+            to,
+            memo,
+            created_at_time_nanos,
+        };
+        if let Some(block_height) = self
+            .state
+            .try_lock()
+            .unwrap()
+            .deduplicated_transfers
+            .get(&transfer)
+        {
+            return Ok(*block_height);
+        }
+        self.transfer_funds(amount_e8s, fee_e8s, from_subaccount, to, memo)
+            .await?;
+        let deduplicated_transfers = &mut self.state.try_lock().unwrap().deduplicated_transfers;
+        let block_height = deduplicated_transfers.len() as u64;
+        deduplicated_transfers.insert(transfer, block_height);
+        Ok(block_height)
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         Ok(self.get_supply())
     }
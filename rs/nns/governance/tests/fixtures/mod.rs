@@ -434,6 +434,20 @@ impl Ledger for NNSFixture {
         Ok(0)
     }
 
+    async fn transfer_funds_deduplicated(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to_account: AccountIdentifier,
+        memo: u64,
+        _: u64,
+    ) -> Result<u64, NervousSystemError> {
+        // The fixture does not deduplicate transfers.
+        self.transfer_funds(amount_e8s, fee_e8s, from_subaccount, to_account, memo)
+            .await
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         Ok(self.nns_state.try_lock().unwrap().ledger.get_supply())
     }
@@ -840,6 +854,27 @@ impl Ledger for NNS {
             .await
     }
 
+    async fn transfer_funds_deduplicated(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to_account: AccountIdentifier,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<u64, NervousSystemError> {
+        self.fixture
+            .transfer_funds_deduplicated(
+                amount_e8s,
+                fee_e8s,
+                from_subaccount,
+                to_account,
+                memo,
+                created_at_time_nanos,
+            )
+            .await
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         self.fixture.total_supply().await
     }
@@ -30,10 +30,11 @@ use ic_nns_governance::{
 };
 use ic_nns_governance::{
     governance::{
-        subaccount_from_slice, validate_proposal_title, Environment, Governance,
-        HeapGrowthPotential, EXECUTE_NNS_FUNCTION_PAYLOAD_LISTING_BYTES_MAX,
-        MAX_DISSOLVE_DELAY_SECONDS, MAX_NEURON_AGE_FOR_AGE_BONUS,
-        MAX_NUMBER_OF_PROPOSALS_WITH_BALLOTS, MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS,
+        subaccount_from_slice, treasury_account, treasury_subaccount, validate_proposal_title,
+        Environment, Governance, HeapGrowthPotential,
+        EXECUTE_NNS_FUNCTION_PAYLOAD_LISTING_BYTES_MAX, MAX_DISSOLVE_DELAY_SECONDS,
+        MAX_NEURON_AGE_FOR_AGE_BONUS, MAX_NUMBER_OF_PROPOSALS_WITH_BALLOTS,
+        MAX_TRANSFER_ICP_AMOUNT_E8S, MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS,
         ONE_DAY_SECONDS, ONE_YEAR_SECONDS, PROPOSAL_MOTION_TEXT_BYTES_MAX,
         REWARD_DISTRIBUTION_PERIOD_SECONDS, WAIT_FOR_QUIET_DEADLINE_INCREASE_SECONDS,
     },
@@ -67,7 +68,7 @@ use ic_nns_governance::{
         ProposalStatus,
         ProposalStatus::Rejected,
         RewardEvent, RewardNodeProvider, RewardNodeProviders, SetDefaultFollowees, Tally, Topic,
-        UpdateNodeProvider, Vote,
+        TransferIcp, UpdateNodeProvider, Vote,
     },
 };
 use ic_sns_swap::pb::v1 as sns_swap_pb;
@@ -6793,6 +6794,237 @@ fn test_manage_and_reward_multiple_node_providers() {
     );
 }
 
+#[test]
+fn test_transfer_icp_proposal() {
+    let p = match std::env::var("NEURON_CSV_PATH") {
+        Ok(v) => PathBuf::from(v),
+        Err(_) => PathBuf::from("tests/neurons.csv"),
+    };
+    let mut builder = GovernanceCanisterInitPayloadBuilder::new();
+    let init_neurons = &mut builder.add_all_neurons_from_csv_file(&p).proto.neurons;
+
+    let voter_pid = *init_neurons[&42].controller.as_ref().unwrap();
+
+    let voter_neuron = init_neurons[&42].id.as_ref().unwrap().clone();
+    init_neurons.get_mut(&42).unwrap().dissolve_state = Some(DissolveState::DissolveDelaySeconds(
+        MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS,
+    ));
+    let recipient = AccountIdentifier::new(PrincipalId::new_self_authenticating(&[14]), None);
+
+    let (mut driver, mut gov) =
+        governance_with_neurons(&init_neurons.values().cloned().collect::<Vec<Neuron>>());
+    let fee_e8s = gov.proto.economics.as_ref().unwrap().transaction_fee_e8s;
+    driver.create_account_with_funds(treasury_account(), 0);
+
+    let transfer_icp_proposal = |amount_e8s: u64| -> Proposal {
+        Proposal {
+            title: Some("Grant payout".to_string()),
+            summary: "Pay out this grant.".to_string(),
+            url: "".to_string(),
+            action: Some(proposal::Action::TransferIcp(TransferIcp {
+                amount_e8s,
+                to_account: Some(recipient.into()),
+                memo: 42,
+            })),
+        }
+    };
+    let try_make_transfer_icp_proposal = |gov: &mut Governance,
+                                          amount_e8s: u64|
+     -> Result<_, GovernanceError> {
+        gov.manage_neuron(
+            &voter_pid,
+            &ManageNeuron {
+                id: None,
+                neuron_id_or_subaccount: Some(NeuronIdOrSubaccount::NeuronId(voter_neuron.clone())),
+                command: Some(manage_neuron::Command::MakeProposal(Box::new(
+                    transfer_icp_proposal(amount_e8s),
+                ))),
+            },
+        )
+        .now_or_never()
+        .unwrap()
+    };
+    let make_transfer_icp_proposal = |gov: &mut Governance, amount_e8s: u64| -> ProposalId {
+        match try_make_transfer_icp_proposal(gov, amount_e8s)
+            .expect("Couldn't submit proposal.")
+            .command
+            .unwrap()
+        {
+            manage_neuron_response::Command::MakeProposal(resp) => resp.proposal_id.unwrap(),
+            _ => panic!("Invalid response"),
+        }
+    };
+    // Unlike `manage_neuron`, `make_proposal` doesn't check that the treasury
+    // can pay for the proposal, which lets its transfer fail.
+    let make_unfunded_transfer_icp_proposal =
+        |gov: &mut Governance, amount_e8s: u64| -> ProposalId {
+            gov.make_proposal(
+                &voter_neuron,
+                &voter_pid,
+                &transfer_icp_proposal(amount_e8s),
+            )
+            .unwrap()
+        };
+
+    // Proposals with a zero amount, or with an amount above the cap, are
+    // invalid.
+    driver.add_funds_to_account(treasury_account(), 2 * MAX_TRANSFER_ICP_AMOUNT_E8S);
+    for amount_e8s in [0, MAX_TRANSFER_ICP_AMOUNT_E8S + 1] {
+        assert_eq!(
+            try_make_transfer_icp_proposal(&mut gov, amount_e8s)
+                .unwrap_err()
+                .error_type,
+            ErrorType::InvalidProposal as i32
+        );
+    }
+    driver
+        .state
+        .try_lock()
+        .unwrap()
+        .accounts
+        .insert(treasury_account(), 0);
+
+    // A proposal that the treasury can't pay for is rejected.
+    let result = try_make_transfer_icp_proposal(&mut gov, 10 * 100_000_000);
+    assert_eq!(result.unwrap_err().error_type, InsufficientFunds as i32);
+
+    // The proposal is adopted right away, and the funds are transferred from
+    // the treasury, which pays the fee.
+    driver.add_funds_to_account(treasury_account(), 10 * 100_000_000 + fee_e8s);
+    let pid = make_transfer_icp_proposal(&mut gov, 10 * 100_000_000);
+    let info = gov
+        .get_proposal_info(&PrincipalId::new_anonymous(), pid)
+        .unwrap();
+    assert_eq!(info.status(), ProposalStatus::Executed, "info: {:?}", info);
+    assert_eq!(info.topic, Topic::Governance as i32);
+    assert_eq!(info.transfer_icp_block_index, Some(0));
+    driver.assert_account_contains(&recipient, 10 * 100_000_000);
+    driver.assert_account_contains(&treasury_account(), 0);
+
+    // A transfer that fails remains adopted.
+    let pid = make_unfunded_transfer_icp_proposal(&mut gov, 5 * 100_000_000);
+    let proposal_data = gov.get_proposal_data(pid).unwrap();
+    assert_eq!(proposal_data.status(), ProposalStatus::Adopted);
+    let state = proposal_data.transfer_icp_state.as_ref().unwrap();
+    assert_eq!(state.failed_attempts, 1);
+    assert!(!state.in_flight);
+    assert_eq!(state.block_index, None);
+    assert_eq!(state.fee_e8s, fee_e8s);
+
+    // The transfer is not retried before the retry interval elapsed.
+    driver.add_funds_to_account(treasury_account(), 5 * 100_000_000 + fee_e8s);
+    gov.run_periodic_tasks().now_or_never();
+    assert_eq!(
+        gov.get_proposal_data(pid).unwrap().status(),
+        ProposalStatus::Adopted
+    );
+
+    // Once the retry interval elapsed, the transfer is made again, and succeeds.
+    driver.advance_time_by(60 * 60);
+    gov.run_periodic_tasks().now_or_never();
+    let info = gov
+        .get_proposal_info(&PrincipalId::new_anonymous(), pid)
+        .unwrap();
+    assert_eq!(info.status(), ProposalStatus::Executed, "info: {:?}", info);
+    assert_eq!(info.transfer_icp_block_index, Some(1));
+    driver.assert_account_contains(&recipient, 15 * 100_000_000);
+    driver.assert_account_contains(&treasury_account(), 0);
+
+    // The transfer of an executed proposal is never made again.
+    driver.add_funds_to_account(treasury_account(), 5 * 100_000_000 + fee_e8s);
+    driver.advance_time_by(60 * 60);
+    gov.run_periodic_tasks().now_or_never();
+    driver.assert_account_contains(&recipient, 15 * 100_000_000);
+    driver
+        .state
+        .try_lock()
+        .unwrap()
+        .accounts
+        .insert(treasury_account(), 0);
+
+    // An attempt whose reply was lost is retried once it is stale, and the
+    // ledger deduplicates the retry.
+    let pid = make_unfunded_transfer_icp_proposal(&mut gov, 100_000_000);
+    let decided_timestamp_seconds = gov
+        .get_proposal_data(pid)
+        .unwrap()
+        .decided_timestamp_seconds;
+    driver.add_funds_to_account(treasury_account(), 100_000_000 + fee_e8s);
+    let lost_block_index = driver
+        .get_fake_ledger()
+        .transfer_funds_deduplicated(
+            100_000_000,
+            fee_e8s,
+            Some(treasury_subaccount()),
+            recipient,
+            42,
+            decided_timestamp_seconds * 1_000_000_000,
+        )
+        .now_or_never()
+        .unwrap()
+        .unwrap();
+    gov.proto
+        .proposals
+        .get_mut(&pid.id)
+        .unwrap()
+        .transfer_icp_state
+        .as_mut()
+        .unwrap()
+        .in_flight = true;
+    driver.advance_time_by(60 * 60);
+    gov.run_periodic_tasks().now_or_never();
+    let info = gov
+        .get_proposal_info(&PrincipalId::new_anonymous(), pid)
+        .unwrap();
+    assert_eq!(info.status(), ProposalStatus::Executed, "info: {:?}", info);
+    assert_eq!(info.transfer_icp_block_index, Some(lost_block_index));
+    driver.assert_account_contains(&recipient, 16 * 100_000_000);
+    driver.assert_account_contains(&treasury_account(), 0);
+
+    // A transfer that keeps failing makes the proposal fail once the execution
+    // period elapsed.
+    let pid = make_unfunded_transfer_icp_proposal(&mut gov, 100_000_000);
+    for _ in 0..11 {
+        assert_eq!(
+            gov.get_proposal_data(pid).unwrap().status(),
+            ProposalStatus::Adopted
+        );
+        driver.advance_time_by(60 * 60);
+        gov.run_periodic_tasks().now_or_never();
+    }
+    assert_eq!(
+        gov.get_proposal_data(pid).unwrap().status(),
+        ProposalStatus::Failed
+    );
+    driver.assert_account_contains(&recipient, 16 * 100_000_000);
+
+    // Attempts that are in flight when the governance canister is upgraded
+    // never get a reply, and are cleared.
+    let mut proto = gov.proto.clone();
+    proto
+        .proposals
+        .get_mut(&pid.id)
+        .unwrap()
+        .transfer_icp_state
+        .as_mut()
+        .unwrap()
+        .in_flight = true;
+    let gov = Governance::new(
+        proto,
+        driver.get_fake_env(),
+        driver.get_fake_ledger(),
+        driver.get_fake_cmc(),
+    );
+    assert!(
+        !gov.get_proposal_data(pid)
+            .unwrap()
+            .transfer_icp_state
+            .as_ref()
+            .unwrap()
+            .in_flight
+    );
+}
+
 #[test]
 fn test_network_economics_proposal() {
     let p = match std::env::var("NEURON_CSV_PATH") {
@@ -84,6 +84,36 @@ impl Ledger for InterleavingTestLedger {
             .await
     }
 
+    async fn transfer_funds_deduplicated(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to: AccountIdentifier,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<u64, NervousSystemError> {
+        let msg = LedgerMessage::Transfer {
+            amount_e8s,
+            fee_e8s,
+            from_subaccount,
+            to,
+            memo,
+        };
+        atomic::fence(AOrdering::SeqCst);
+        self.notify(msg).await?;
+        self.underlying
+            .transfer_funds_deduplicated(
+                amount_e8s,
+                fee_e8s,
+                from_subaccount,
+                to,
+                memo,
+                created_at_time_nanos,
+            )
+            .await
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         atomic::fence(AOrdering::SeqCst);
         self.notify(LedgerMessage::TotalSupply).await?;
@@ -119,7 +119,7 @@ pub mod global {
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct WasmMethod {
-    #[prost(oneof = "wasm_method::WasmMethod", tags = "1, 2, 3")]
+    #[prost(oneof = "wasm_method::WasmMethod", tags = "1, 2, 3, 4")]
     pub wasm_method: ::core::option::Option<wasm_method::WasmMethod>,
 }
 /// Nested message and enum types in `WasmMethod`.
@@ -135,6 +135,7 @@ pub mod wasm_method {
         CanisterInspectMessage = 5,
         CanisterHeartbeat = 6,
         Empty = 7,
+        CanisterGlobalTimer = 8,
     }
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum WasmMethod {
@@ -144,6 +145,8 @@ pub mod wasm_method {
         Query(::prost::alloc::string::String),
         #[prost(enumeration = "SystemMethod", tag = "3")]
         System(i32),
+        #[prost(string, tag = "4")]
+        CompositeQuery(::prost::alloc::string::String),
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -269,6 +272,100 @@ pub mod execution_task {
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterLogRecord {
+    #[prost(uint64, tag = "1")]
+    pub idx: u64,
+    #[prost(uint64, tag = "2")]
+    pub timestamp_nanos: u64,
+    #[prost(bytes = "vec", tag = "3")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterChangeFromUser {
+    #[prost(message, optional, tag = "1")]
+    pub user_id: ::core::option::Option<super::super::super::types::v1::PrincipalId>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterChangeFromCanister {
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<super::super::super::types::v1::PrincipalId>,
+    #[prost(uint64, optional, tag = "2")]
+    pub canister_version: ::core::option::Option<u64>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterCreation {
+    #[prost(message, repeated, tag = "1")]
+    pub controllers: ::prost::alloc::vec::Vec<super::super::super::types::v1::PrincipalId>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterCodeUninstall {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterCodeDeployment {
+    #[prost(enumeration = "CanisterInstallMode", tag = "1")]
+    pub mode: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub module_hash: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterControllersChange {
+    #[prost(message, repeated, tag = "1")]
+    pub controllers: ::prost::alloc::vec::Vec<super::super::super::types::v1::PrincipalId>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterLoadSnapshot {
+    #[prost(bytes = "vec", tag = "1")]
+    pub snapshot_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint64, tag = "2")]
+    pub taken_at_timestamp: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterChange {
+    #[prost(uint64, tag = "1")]
+    pub timestamp_nanos: u64,
+    #[prost(uint64, tag = "2")]
+    pub canister_version: u64,
+    #[prost(oneof = "canister_change::ChangeOrigin", tags = "3, 4")]
+    pub change_origin: ::core::option::Option<canister_change::ChangeOrigin>,
+    #[prost(oneof = "canister_change::ChangeDetails", tags = "5, 6, 7, 8, 9")]
+    pub change_details: ::core::option::Option<canister_change::ChangeDetails>,
+}
+/// Nested message and enum types in `CanisterChange`.
+pub mod canister_change {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum ChangeOrigin {
+        #[prost(message, tag = "3")]
+        CanisterChangeFromUser(super::CanisterChangeFromUser),
+        #[prost(message, tag = "4")]
+        CanisterChangeFromCanister(super::CanisterChangeFromCanister),
+    }
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum ChangeDetails {
+        #[prost(message, tag = "5")]
+        CanisterCreation(super::CanisterCreation),
+        #[prost(message, tag = "6")]
+        CanisterCodeUninstall(super::CanisterCodeUninstall),
+        #[prost(message, tag = "7")]
+        CanisterCodeDeployment(super::CanisterCodeDeployment),
+        #[prost(message, tag = "8")]
+        CanisterControllersChange(super::CanisterControllersChange),
+        #[prost(message, tag = "9")]
+        CanisterLoadSnapshot(super::CanisterLoadSnapshot),
+    }
+}
+/// Query statistics of a canister aggregated over all replicas of the subnet.
+/// All counters are little-endian encoded 128-bit integers.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TotalQueryStats {
+    #[prost(bytes = "vec", tag = "1")]
+    pub num_calls: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub num_instructions: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub ingress_payload_size: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub egress_payload_size: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterStateBits {
     #[prost(uint64, tag = "2")]
     pub last_full_execution_round: u64,
@@ -323,6 +420,40 @@ pub struct CanisterStateBits {
     /// Postponed charges that are not applied to `cycles_balance` yet.
     #[prost(message, optional, tag = "32")]
     pub cycles_debit: ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// Canister global timer, in nanoseconds since Unix epoch.
+    #[prost(uint64, optional, tag = "33")]
+    pub global_timer_nanos: ::core::option::Option<u64>,
+    /// Log records of the canister, oldest first.
+    #[prost(message, repeated, tag = "34")]
+    pub canister_log_records: ::prost::alloc::vec::Vec<CanisterLogRecord>,
+    /// The index of the next log record to be added.
+    #[prost(uint64, tag = "35")]
+    pub next_canister_log_record_idx: u64,
+    /// Who is allowed to fetch the canister log.
+    #[prost(enumeration = "LogVisibility", tag = "36")]
+    pub log_visibility: i32,
+    /// The version of the canister, incremented on every change of the code or
+    /// the settings of the canister.
+    #[prost(uint64, tag = "37")]
+    pub canister_version: u64,
+    /// The most recent changes of the canister, oldest first.
+    #[prost(message, repeated, tag = "38")]
+    pub canister_history: ::prost::alloc::vec::Vec<CanisterChange>,
+    /// The total number of changes ever recorded for the canister.
+    #[prost(uint64, tag = "39")]
+    pub total_num_changes: u64,
+    /// Upper bound on the Wasm memory of the canister, if set.
+    #[prost(uint64, optional, tag = "40")]
+    pub wasm_memory_limit: ::core::option::Option<u64>,
+    /// Cycles set aside to pay for storage, moved out of `cycles_balance`.
+    #[prost(message, optional, tag = "41")]
+    pub reserved_balance: ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// Upper bound on `reserved_balance`, if set.
+    #[prost(message, optional, tag = "42")]
+    pub reserved_balance_limit: ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// Statistics on the query calls executed by the canister.
+    #[prost(message, optional, tag = "43")]
+    pub total_query_stats: ::core::option::Option<TotalQueryStats>,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
@@ -338,6 +469,24 @@ pub mod canister_state_bits {
         Stopped(super::CanisterStatusStopped),
     }
 }
+/// The parts of a canister snapshot that are not stored in separate files.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterSnapshotBits {
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<super::super::super::types::v1::CanisterId>,
+    #[prost(uint64, tag = "2")]
+    pub taken_at_timestamp_nanos: u64,
+    #[prost(uint64, tag = "3")]
+    pub wasm_memory_size: u64,
+    #[prost(uint64, tag = "4")]
+    pub stable_memory_size: u64,
+    #[prost(message, repeated, tag = "5")]
+    pub exported_globals: ::prost::alloc::vec::Vec<Global>,
+    #[prost(bytes = "vec", tag = "6")]
+    pub certified_data: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "7")]
+    pub binary_hash: ::prost::alloc::vec::Vec<u8>,
+}
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum CustomSectionType {
@@ -345,3 +494,18 @@ pub enum CustomSectionType {
     Public = 1,
     Private = 2,
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum LogVisibility {
+    Unspecified = 0,
+    Controllers = 1,
+    Public = 2,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CanisterInstallMode {
+    Unspecified = 0,
+    Install = 1,
+    Reinstall = 2,
+    Upgrade = 3,
+}
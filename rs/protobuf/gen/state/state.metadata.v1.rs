@@ -202,6 +202,33 @@ pub struct BitcoinGetSuccessorsFollowUpResponses {
     #[prost(bytes = "vec", repeated, tag = "2")]
     pub payloads: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
+/// The query statistics of a canister for an epoch, as reported by a node.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RawQueryStatsEntry {
+    #[prost(uint64, tag = "1")]
+    pub epoch: u64,
+    #[prost(message, optional, tag = "2")]
+    pub canister_id: ::core::option::Option<super::super::super::types::v1::CanisterId>,
+    #[prost(message, optional, tag = "3")]
+    pub proposer: ::core::option::Option<super::super::super::types::v1::NodeId>,
+    #[prost(uint32, tag = "4")]
+    pub num_calls: u32,
+    #[prost(uint64, tag = "5")]
+    pub num_instructions: u64,
+    #[prost(uint64, tag = "6")]
+    pub ingress_payload_size: u64,
+    #[prost(uint64, tag = "7")]
+    pub egress_payload_size: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RawQueryStats {
+    /// The highest epoch whose statistics were aggregated into the canister
+    /// states, if any.
+    #[prost(uint64, optional, tag = "1")]
+    pub highest_aggregated_epoch: ::core::option::Option<u64>,
+    #[prost(message, repeated, tag = "2")]
+    pub stats: ::prost::alloc::vec::Vec<RawQueryStatsEntry>,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SystemMetadata {
     #[prost(uint64, tag = "1")]
@@ -254,6 +281,12 @@ pub struct SystemMetadata {
     #[prost(message, repeated, tag = "18")]
     pub bitcoin_get_successors_follow_up_responses:
         ::prost::alloc::vec::Vec<BitcoinGetSuccessorsFollowUpResponses>,
+    /// The counter used to generate ids of canister snapshots.
+    #[prost(uint64, tag = "19")]
+    pub next_canister_snapshot_id: u64,
+    /// Query statistics received via consensus that are not yet aggregated.
+    #[prost(message, optional, tag = "20")]
+    pub query_stats: ::core::option::Option<RawQueryStats>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct StableMemory {
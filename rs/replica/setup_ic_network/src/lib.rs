@@ -28,6 +28,7 @@ use ic_interfaces::{
     crypto::{Crypto, IngressSigVerifier},
     execution_environment::IngressHistoryReader,
     messaging::{MessageRouting, XNetPayloadBuilder},
+    query_stats::QueryStatsPayloadBuilder,
     registry::{LocalStoreCertifiedTimeReader, RegistryClient},
     self_validating_payload::SelfValidatingPayloadBuilder,
     time_source::SysTimeSource,
@@ -104,6 +105,7 @@ pub fn create_networking_stack(
     state_sync_client: P2PStateSyncClient,
     xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
     self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
+    query_stats_payload_builder: Arc<dyn QueryStatsPayloadBuilder>,
     message_router: Arc<dyn MessageRouting>,
     crypto: Arc<dyn Crypto + Send + Sync>,
     consensus_crypto: Arc<dyn ConsensusCrypto + Send + Sync>,
@@ -138,6 +140,7 @@ pub fn create_networking_stack(
         state_sync_client,
         xnet_payload_builder,
         self_validating_payload_builder,
+        query_stats_payload_builder,
         message_router,
         ingress_history_reader,
         artifact_pools,
@@ -210,6 +213,7 @@ fn setup_artifact_manager(
     state_sync_client: P2PStateSyncClient,
     xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
     self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
+    query_stats_payload_builder: Arc<dyn QueryStatsPayloadBuilder>,
     message_router: Arc<dyn MessageRouting>,
     ingress_history_reader: Box<dyn IngressHistoryReader>,
     artifact_pools: &ArtifactPools,
@@ -316,6 +320,7 @@ fn setup_artifact_manager(
                     Arc::clone(&xnet_payload_builder) as Arc<_>,
                     Arc::clone(&self_validating_payload_builder) as Arc<_>,
                     Arc::clone(&canister_http_payload_builder) as Arc<_>,
+                    Arc::clone(&query_stats_payload_builder),
                     Arc::clone(&artifact_pools.dkg_pool) as Arc<_>,
                     Arc::clone(&artifact_pools.ecdsa_pool) as Arc<_>,
                     Arc::clone(&dkg_key_manager) as Arc<_>,
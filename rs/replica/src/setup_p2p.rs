@@ -240,6 +240,14 @@ pub fn construct_ic_stack(
     );
     let self_validating_payload_builder = Arc::new(self_validating_payload_builder);
 
+    let query_stats_payload_builder = execution_services
+        .query_stats_payload_builder
+        .into_payload_builder(
+            Arc::clone(&state_manager) as Arc<_>,
+            node_id,
+            replica_logger.clone(),
+        );
+
     let canister_http_adapter_client = ic_canister_http_adapter_client::setup_canister_http_client(
         rt_handle.clone(),
         &metrics_registry,
@@ -263,6 +271,7 @@ pub fn construct_ic_stack(
         P2PStateSyncClient::Client(Arc::clone(&state_manager) as Arc<_>),
         xnet_payload_builder as Arc<_>,
         self_validating_payload_builder as Arc<_>,
+        query_stats_payload_builder as Arc<_>,
         message_router as Arc<_>,
         // TODO(SCL-213)
         Arc::clone(&crypto) as Arc<_>,
@@ -4,7 +4,8 @@ use ic_config::Config;
 use ic_error_types::{ErrorCode, RejectCode};
 use ic_ic00_types::{
     self as ic00, CanisterIdRecord, CanisterInstallMode, CanisterStatusResultV2,
-    CanisterStatusType, EmptyBlob, InstallCodeArgs, Method, Payload, SetControllerArgs, IC_00,
+    CanisterStatusType, EmptyBlob, InstallCodeArgs, Method, Payload, QueryStatsResponse,
+    SetControllerArgs, IC_00,
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_replica_tests as utils;
@@ -679,6 +680,11 @@ fn can_get_canister_information() {
                 None,
                 2592000,
                 0u128,
+                1,
+                None,
+                0u128,
+                None,
+                QueryStatsResponse::default(),
             )
         );
 
@@ -730,6 +736,11 @@ fn can_get_canister_information() {
                     None,
                     259200,
                     0u128,
+                    2,
+                    None,
+                    0u128,
+                    None,
+                    QueryStatsResponse::default(),
                 ),
                 CanisterStatusResultV2::decode(&res).unwrap(),
                 2 * BALANCE_EPSILON,
@@ -896,6 +907,7 @@ fn assert_canister_status_result_equals(
     assert_eq!(expected.status(), actual.status());
     assert_eq!(expected.module_hash(), actual.module_hash());
     assert_eq!(expected.controller(), actual.controller());
+    assert_eq!(expected.version(), actual.version());
     assert_balance_equals(
         Cycles::from(expected.cycles()),
         Cycles::from(actual.cycles()),
@@ -0,0 +1,250 @@
+//! Snapshots of canister states taken through the `take_canister_snapshot`
+//! management method. A snapshot captures everything needed to roll a
+//! canister back to the moment it was taken: the Wasm module, the Wasm heap
+//! and stable memories, the exported globals and the certified data.
+
+use crate::{num_bytes_try_from, CanisterState, Global, Memory};
+use ic_types::{CanisterId, NumBytes, PrincipalId, Time};
+use ic_wasm_types::CanisterModule;
+use std::{collections::BTreeMap, convert::TryFrom, fmt, sync::Arc};
+
+/// Identifies a snapshot of a canister. Snapshot ids are unique on a subnet
+/// and are never reused, even after the snapshot is deleted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SnapshotId {
+    canister_id: CanisterId,
+    local_id: u64,
+}
+
+impl SnapshotId {
+    pub fn new(canister_id: CanisterId, local_id: u64) -> Self {
+        Self {
+            canister_id,
+            local_id,
+        }
+    }
+
+    /// Returns the id of the canister the snapshot belongs to.
+    pub fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    /// Returns the subnet-wide counter value the snapshot was created with.
+    pub fn local_id(&self) -> u64 {
+        self.local_id
+    }
+
+    /// Returns the binary representation of the id that is exposed to users:
+    /// the big-endian `local_id` followed by the canister id bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.local_id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(self.canister_id.get_ref().as_slice());
+        bytes
+    }
+}
+
+impl TryFrom<&[u8]> for SnapshotId {
+    type Error = String;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        const LOCAL_ID_LEN: usize = std::mem::size_of::<u64>();
+        if bytes.len() <= LOCAL_ID_LEN {
+            return Err(format!("Invalid snapshot id of length {}", bytes.len()));
+        }
+        let (local_id, canister_id) = bytes.split_at(LOCAL_ID_LEN);
+        let local_id = u64::from_be_bytes(local_id.try_into().unwrap());
+        let canister_id = PrincipalId::try_from(canister_id)
+            .map_err(|err| format!("Invalid canister id in snapshot id: {}", err))?;
+        Ok(Self {
+            canister_id: CanisterId::new(canister_id).map_err(|err| err.to_string())?,
+            local_id,
+        })
+    }
+}
+
+impl fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.canister_id, self.local_id)
+    }
+}
+
+/// A snapshot of the state of a canister.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanisterSnapshot {
+    /// The canister the snapshot was taken of.
+    pub canister_id: CanisterId,
+    /// The time at which the snapshot was taken.
+    pub taken_at_timestamp: Time,
+    /// The Wasm module installed at the time of the snapshot.
+    pub wasm_binary: CanisterModule,
+    /// The Wasm heap memory.
+    pub wasm_memory: Memory,
+    /// The stable memory.
+    pub stable_memory: Memory,
+    /// The values of the exported Wasm globals.
+    pub exported_globals: Vec<Global>,
+    /// The data certified by the canister.
+    pub certified_data: Vec<u8>,
+}
+
+impl CanisterSnapshot {
+    /// Takes a snapshot of the given canister. Returns `None` if the canister
+    /// has no code installed.
+    pub fn from_canister(canister: &CanisterState, taken_at_timestamp: Time) -> Option<Self> {
+        let execution_state = canister.execution_state.as_ref()?;
+        Some(Self {
+            canister_id: canister.canister_id(),
+            taken_at_timestamp,
+            wasm_binary: execution_state.wasm_binary.binary.clone(),
+            wasm_memory: Memory::new(
+                execution_state.wasm_memory.page_map.clone(),
+                execution_state.wasm_memory.size,
+            ),
+            stable_memory: Memory::new(
+                execution_state.stable_memory.page_map.clone(),
+                execution_state.stable_memory.size,
+            ),
+            exported_globals: execution_state.exported_globals.clone(),
+            certified_data: canister.system_state.certified_data.clone(),
+        })
+    }
+
+    /// Returns the number of bytes the snapshot takes. Snapshots are charged
+    /// for storage based on this value.
+    pub fn size(&self) -> NumBytes {
+        // We use 8 bytes per global, the same as for the execution state.
+        let globals_size_bytes = 8 * self.exported_globals.len() as u64;
+        num_bytes_try_from(self.wasm_memory.size)
+            .expect("could not convert from wasm memory number of pages to bytes")
+            + num_bytes_try_from(self.stable_memory.size)
+                .expect("could not convert from stable memory number of pages to bytes")
+            + NumBytes::from(globals_size_bytes)
+            + NumBytes::from(self.wasm_binary.len() as u64)
+            + NumBytes::from(self.certified_data.len() as u64)
+    }
+}
+
+/// All canister snapshots of a subnet, indexed by snapshot id.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CanisterSnapshots {
+    snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>,
+}
+
+impl CanisterSnapshots {
+    pub fn new(snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>) -> Self {
+        Self { snapshots }
+    }
+
+    /// Returns the snapshot with the given id, if any.
+    pub fn get(&self, snapshot_id: &SnapshotId) -> Option<&Arc<CanisterSnapshot>> {
+        self.snapshots.get(snapshot_id)
+    }
+
+    /// Adds a snapshot with the given id.
+    pub fn push(&mut self, snapshot_id: SnapshotId, snapshot: CanisterSnapshot) {
+        self.snapshots.insert(snapshot_id, Arc::new(snapshot));
+    }
+
+    /// Removes the snapshot with the given id and returns it, if any.
+    pub fn remove(&mut self, snapshot_id: &SnapshotId) -> Option<Arc<CanisterSnapshot>> {
+        self.snapshots.remove(snapshot_id)
+    }
+
+    /// Removes all snapshots of the given canister.
+    pub fn remove_canister_snapshots(&mut self, canister_id: CanisterId) {
+        let ids: Vec<SnapshotId> = self
+            .canister_snapshots(canister_id)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in ids {
+            self.snapshots.remove(&id);
+        }
+    }
+
+    /// Iterates over the snapshots of the given canister, oldest first.
+    pub fn canister_snapshots(
+        &self,
+        canister_id: CanisterId,
+    ) -> impl Iterator<Item = (&SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots
+            .range(SnapshotId::new(canister_id, 0)..=SnapshotId::new(canister_id, u64::MAX))
+    }
+
+    /// Returns the total size of the snapshots of the given canister.
+    pub fn canister_memory_taken(&self, canister_id: CanisterId) -> NumBytes {
+        self.canister_snapshots(canister_id)
+            .map(|(_, snapshot)| snapshot.size())
+            .sum()
+    }
+
+    /// Returns the total size of all snapshots on the subnet.
+    pub fn memory_taken(&self) -> NumBytes {
+        self.snapshots
+            .values()
+            .map(|snapshot| snapshot.size())
+            .sum()
+    }
+
+    /// Iterates over all snapshots.
+    pub fn iter(&self) -> impl Iterator<Item = (&SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots.iter()
+    }
+
+    /// Iterates mutably over all snapshots. Used by the state manager to
+    /// switch the snapshot page maps to checkpoint files.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&SnapshotId, &mut Arc<CanisterSnapshot>)> {
+        self.snapshots.iter_mut()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities::types::ids::canister_test_id;
+
+    #[test]
+    fn snapshot_id_bytes_roundtrip() {
+        let id = SnapshotId::new(canister_test_id(42), 7);
+        assert_eq!(SnapshotId::try_from(id.to_bytes().as_slice()), Ok(id));
+        assert!(SnapshotId::try_from(&[1, 2, 3][..]).is_err());
+    }
+
+    #[test]
+    fn canister_snapshots_are_listed_per_canister() {
+        let mut snapshots = CanisterSnapshots::default();
+        let snapshot = |canister_id| CanisterSnapshot {
+            canister_id,
+            taken_at_timestamp: Time::from_nanos_since_unix_epoch(0),
+            wasm_binary: CanisterModule::new(vec![0; 10]),
+            wasm_memory: Memory::default(),
+            stable_memory: Memory::default(),
+            exported_globals: vec![],
+            certified_data: vec![1, 2],
+        };
+        for (canister, local_id) in [(1, 0), (2, 1), (1, 2)] {
+            let canister_id = canister_test_id(canister);
+            snapshots.push(
+                SnapshotId::new(canister_id, local_id),
+                snapshot(canister_id),
+            );
+        }
+
+        let ids: Vec<u64> = snapshots
+            .canister_snapshots(canister_test_id(1))
+            .map(|(id, _)| id.local_id())
+            .collect();
+        assert_eq!(ids, vec![0, 2]);
+        assert_eq!(
+            snapshots.canister_memory_taken(canister_test_id(1)),
+            NumBytes::from(24)
+        );
+
+        snapshots.remove_canister_snapshots(canister_test_id(1));
+        assert_eq!(snapshots.iter().count(), 1);
+        assert_eq!(snapshots.memory_taken(), NumBytes::from(12));
+    }
+}
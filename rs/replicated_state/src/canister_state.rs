@@ -238,7 +238,9 @@ impl CanisterState {
         match (next_task, self.has_input()) {
             (None, false) => NextExecution::None,
             (None, true) => NextExecution::StartNew,
-            (Some(ExecutionTask::Heartbeat), _) => NextExecution::StartNew,
+            (Some(ExecutionTask::Heartbeat), _) | (Some(ExecutionTask::GlobalTimer), _) => {
+                NextExecution::StartNew
+            }
             (Some(ExecutionTask::AbortedExecution(..)), _)
             | (Some(ExecutionTask::PausedExecution(..)), _) => NextExecution::ContinueLong,
             (Some(ExecutionTask::AbortedInstallCode(..)), _)
@@ -252,6 +254,7 @@ impl CanisterState {
             Some(ExecutionTask::AbortedExecution(..)) => true,
             None
             | Some(ExecutionTask::Heartbeat)
+            | Some(ExecutionTask::GlobalTimer)
             | Some(ExecutionTask::PausedExecution(..))
             | Some(ExecutionTask::PausedInstallCode(..))
             | Some(ExecutionTask::AbortedInstallCode(..)) => false,
@@ -264,6 +267,7 @@ impl CanisterState {
             Some(ExecutionTask::PausedExecution(..)) => true,
             None
             | Some(ExecutionTask::Heartbeat)
+            | Some(ExecutionTask::GlobalTimer)
             | Some(ExecutionTask::PausedInstallCode(..))
             | Some(ExecutionTask::AbortedExecution(..))
             | Some(ExecutionTask::AbortedInstallCode(..)) => false,
@@ -276,6 +280,7 @@ impl CanisterState {
             Some(ExecutionTask::PausedInstallCode(..)) => true,
             None
             | Some(ExecutionTask::Heartbeat)
+            | Some(ExecutionTask::GlobalTimer)
             | Some(ExecutionTask::PausedExecution(..))
             | Some(ExecutionTask::AbortedExecution(..))
             | Some(ExecutionTask::AbortedInstallCode(..)) => false,
@@ -464,6 +469,16 @@ impl CanisterState {
         }
     }
 
+    /// Returns true if the canister exports the `canister_global_timer` system
+    /// method.
+    pub fn exports_global_timer_method(&self) -> bool {
+        match &self.execution_state {
+            Some(execution_state) => execution_state
+                .exports_method(&WasmMethod::System(SystemMethod::CanisterGlobalTimer)),
+            None => false,
+        }
+    }
+
     /// Returns true if the canister contains an exported query method with the
     /// name provided, false otherwise.
     pub fn exports_query_method(&self, method_name: String) -> bool {
@@ -475,6 +490,17 @@ impl CanisterState {
         }
     }
 
+    /// Returns true if the canister contains an exported composite query method
+    /// with the name provided, false otherwise.
+    pub fn exports_composite_query_method(&self, method_name: String) -> bool {
+        match &self.execution_state {
+            Some(execution_state) => {
+                execution_state.exports_method(&WasmMethod::CompositeQuery(method_name))
+            }
+            None => false,
+        }
+    }
+
     /// Returns the number of global variables in the Wasm module.
     pub fn num_wasm_globals(&self) -> usize {
         match &self.execution_state {
@@ -6,6 +6,7 @@ pub use crate::canister_state::queues::CanisterOutputQueuesIterator;
 use crate::{CanisterQueues, InputQueueType, StateError};
 pub use call_context_manager::{CallContext, CallContextAction, CallContextManager, CallOrigin};
 use ic_base_types::NumSeconds;
+use ic_ic00_types::{CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, LogVisibility};
 use ic_interfaces::messages::{CanisterInputMessage, RequestOrIngress};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -13,8 +14,11 @@ use ic_protobuf::{
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{
+    batch::TotalQueryStats,
+    canister_log::CanisterLog,
     messages::{Ingress, RejectContext, Request, RequestOrResponse, Response, StopCanisterContext},
     nominal_cycles::NominalCycles,
+    time::UNIX_EPOCH,
     CanisterId, Cycles, MemoryAllocation, NumBytes, PrincipalId, Time,
 };
 use lazy_static::lazy_static;
@@ -27,6 +31,9 @@ use std::{
 use std::{collections::BTreeSet, sync::Arc};
 use std::{collections::VecDeque, str::FromStr};
 
+/// The maximum number of changes kept in the canister history.
+pub const MAX_CANISTER_HISTORY_CHANGES: usize = 20;
+
 lazy_static! {
     static ref DEFAULT_PRINCIPAL_MULTIPLE_CONTROLLERS: PrincipalId =
         PrincipalId::from_str("ifxlm-aqaaa-multi-pleco-ntrol-lersa-h3ae").unwrap();
@@ -102,9 +109,164 @@ pub struct SystemState {
     /// it will apply `cycles_debit` to `cycles_balance`.
     cycles_debit: Cycles,
 
+    /// Cycles moved out of `cycles_balance` when the canister allocated memory
+    /// while the subnet memory usage was above the threshold. They can only be
+    /// used to pay for storage.
+    reserved_balance: Cycles,
+
+    /// The upper bound on `reserved_balance`. Memory allocations that would
+    /// need to reserve more cycles fail. `None` means no limit.
+    pub reserved_balance_limit: Option<Cycles>,
+
+    /// Statistics on the query calls executed by the canister, aggregated
+    /// over all replicas of the subnet.
+    pub total_query_stats: TotalQueryStats,
+
     /// Tasks to execute before processing input messages.
     /// Currently the task queue is empty outside of execution rounds.
     pub task_queue: VecDeque<ExecutionTask>,
+
+    /// Canister global timer, set by the canister via `ic0.global_timer_set`.
+    ///
+    /// See also:
+    ///   * https://internetcomputer.org/docs/current/references/ic-interface-spec#global-timer
+    pub global_timer: CanisterTimer,
+
+    /// Log records produced by the canister via `ic0.debug_print` and traps.
+    pub canister_log: CanisterLog,
+
+    /// Who is allowed to fetch the canister log.
+    pub log_visibility: LogVisibility,
+
+    /// The version of the canister, exposed via `ic0.canister_version`.
+    /// Incremented on every `install_code`, settings change and uninstall.
+    pub canister_version: u64,
+
+    /// The most recent changes of the canister (creation, code deployments,
+    /// controller changes, uninstalls), exposed via `canister_info`.
+    pub canister_history: CanisterHistory,
+
+    /// Upper bound on the Wasm memory of the canister. Update calls that grow
+    /// the Wasm memory above it trap, which keeps enough headroom for
+    /// `pre_upgrade` to run. Not enforced during upgrades.
+    pub wasm_memory_limit: Option<NumBytes>,
+}
+
+/// A bounded list of the most recent changes of a canister together with the
+/// total number of changes ever recorded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CanisterHistory {
+    /// The most recent changes, oldest first. Holds at most
+    /// `MAX_CANISTER_HISTORY_CHANGES` entries.
+    changes: VecDeque<CanisterChange>,
+    /// The total number of changes, including the ones already dropped.
+    total_num_changes: u64,
+}
+
+impl CanisterHistory {
+    pub fn new(changes: VecDeque<CanisterChange>, total_num_changes: u64) -> Self {
+        Self {
+            changes,
+            total_num_changes,
+        }
+    }
+
+    /// Appends the given change, dropping the oldest change if the history is
+    /// full.
+    pub fn add_canister_change(&mut self, change: CanisterChange) {
+        if self.changes.len() >= MAX_CANISTER_HISTORY_CHANGES {
+            self.changes.pop_front();
+        }
+        self.changes.push_back(change);
+        self.total_num_changes += 1;
+    }
+
+    /// Returns up to `num_requested_changes` most recent changes, oldest
+    /// first.
+    pub fn get_changes(
+        &self,
+        num_requested_changes: usize,
+    ) -> impl Iterator<Item = &CanisterChange> {
+        let skip = self.changes.len().saturating_sub(num_requested_changes);
+        self.changes.iter().skip(skip)
+    }
+
+    /// Returns all the changes kept in the history, oldest first.
+    pub fn changes(&self) -> &VecDeque<CanisterChange> {
+        &self.changes
+    }
+
+    pub fn get_total_num_changes(&self) -> u64 {
+        self.total_num_changes
+    }
+}
+
+/// An error returned when cycles cannot be moved to the reserved balance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReservationError {
+    /// The main balance does not have enough cycles.
+    InsufficientCycles {
+        requested: Cycles,
+        available: Cycles,
+    },
+    /// The reserved balance would exceed `reserved_balance_limit`.
+    ReservedLimitExceeded { requested: Cycles, limit: Cycles },
+}
+
+/// A wrapper around the canister global timer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CanisterTimer {
+    /// The canister global timer is not set.
+    Inactive,
+    /// The canister global timer is set to the specific time.
+    Active(Time),
+}
+
+impl CanisterTimer {
+    /// Returns true if the timer is active and its deadline is not in the
+    /// future.
+    pub fn has_reached_deadline(&self, now: Time) -> bool {
+        match self {
+            CanisterTimer::Inactive => false,
+            CanisterTimer::Active(deadline) => *deadline <= now,
+        }
+    }
+
+    /// Converts a time value as used by `ic0.global_timer_set` into a
+    /// `CanisterTimer`. Zero deactivates the timer.
+    pub fn from_time(time: Time) -> Self {
+        if time == UNIX_EPOCH {
+            CanisterTimer::Inactive
+        } else {
+            CanisterTimer::Active(time)
+        }
+    }
+
+    /// Converts the timer into a time value as used by
+    /// `ic0.global_timer_set`. An inactive timer is represented by zero.
+    pub fn to_time(&self) -> Time {
+        match self {
+            CanisterTimer::Inactive => UNIX_EPOCH,
+            CanisterTimer::Active(time) => *time,
+        }
+    }
+
+    /// Returns the deadline of an active timer in nanoseconds since the Unix
+    /// epoch, or `None` if the timer is not set. Used for serialization.
+    pub fn to_nanos_since_unix_epoch(&self) -> Option<u64> {
+        match self {
+            CanisterTimer::Inactive => None,
+            CanisterTimer::Active(time) => Some(time.as_nanos_since_unix_epoch()),
+        }
+    }
+
+    /// The inverse of `to_nanos_since_unix_epoch()`.
+    pub fn from_nanos_since_unix_epoch(nanos: Option<u64>) -> Self {
+        match nanos {
+            Some(nanos) => Self::from_time(Time::from_nanos_since_unix_epoch(nanos)),
+            None => CanisterTimer::Inactive,
+        }
+    }
 }
 
 /// A wrapper around the different canister statuses.
@@ -200,6 +362,10 @@ pub enum ExecutionTask {
     // serialized.
     Heartbeat,
 
+    // A global timer task exists only within an execution round. It is never
+    // serialized.
+    GlobalTimer,
+
     // A paused execution task exists only within an epoch (between
     // checkpoints). It is never serialized, and it turns into `AbortedExecution`
     // before the checkpoint or when there are too many long-running executions.
@@ -227,6 +393,7 @@ impl From<&ExecutionTask> for pb::ExecutionTask {
     fn from(item: &ExecutionTask) -> Self {
         match item {
             ExecutionTask::Heartbeat
+            | ExecutionTask::GlobalTimer
             | ExecutionTask::PausedExecution(_)
             | ExecutionTask::PausedInstallCode(_) => {
                 panic!("Attempt to serialize ephemeral task: {:?}.", item);
@@ -362,12 +529,21 @@ impl SystemState {
             queues: CanisterQueues::default(),
             cycles_balance: initial_cycles,
             cycles_debit: Cycles::zero(),
+            reserved_balance: Cycles::zero(),
+            reserved_balance_limit: None,
+            total_query_stats: TotalQueryStats::default(),
             memory_allocation: MemoryAllocation::BestEffort,
             freeze_threshold,
             status,
             certified_data: Default::default(),
             canister_metrics: CanisterMetrics::default(),
             task_queue: Default::default(),
+            global_timer: CanisterTimer::Inactive,
+            canister_log: Default::default(),
+            log_visibility: Default::default(),
+            canister_version: 0,
+            canister_history: CanisterHistory::default(),
+            wasm_memory_limit: None,
         }
     }
 
@@ -400,6 +576,15 @@ impl SystemState {
         cycles_balance: Cycles,
         cycles_debit: Cycles,
         task_queue: VecDeque<ExecutionTask>,
+        global_timer: CanisterTimer,
+        canister_log: CanisterLog,
+        log_visibility: LogVisibility,
+        canister_version: u64,
+        canister_history: CanisterHistory,
+        wasm_memory_limit: Option<NumBytes>,
+        reserved_balance: Cycles,
+        reserved_balance_limit: Option<Cycles>,
+        total_query_stats: TotalQueryStats,
     ) -> Self {
         Self {
             controllers,
@@ -413,6 +598,15 @@ impl SystemState {
             cycles_balance,
             cycles_debit,
             task_queue,
+            global_timer,
+            canister_log,
+            log_visibility,
+            canister_version,
+            canister_history,
+            wasm_memory_limit,
+            reserved_balance,
+            reserved_balance_limit,
+            total_query_stats,
         }
     }
 
@@ -420,6 +614,23 @@ impl SystemState {
         self.canister_id
     }
 
+    /// Records a change of the canister in its history. The change is tagged
+    /// with the current canister version.
+    pub fn add_canister_change(
+        &mut self,
+        time: Time,
+        origin: CanisterChangeOrigin,
+        details: CanisterChangeDetails,
+    ) {
+        self.canister_history
+            .add_canister_change(CanisterChange::new(
+                time.as_nanos_since_unix_epoch(),
+                self.canister_version,
+                origin,
+                details,
+            ));
+    }
+
     /// Returns a mutable reference to the balance of the canister.
     pub fn balance_mut(&mut self) -> &mut Cycles {
         &mut self.cycles_balance
@@ -430,6 +641,45 @@ impl SystemState {
         self.cycles_balance
     }
 
+    /// Returns the amount of cycles reserved for storage.
+    pub fn reserved_balance(&self) -> Cycles {
+        self.reserved_balance
+    }
+
+    /// Moves the given amount of cycles from the main balance to the reserved
+    /// balance.
+    ///
+    /// Returns an error and leaves the balances unchanged if the main balance
+    /// is too low or if the reserved balance would exceed its limit.
+    pub fn reserve_cycles(&mut self, amount: Cycles) -> Result<(), ReservationError> {
+        if amount == Cycles::zero() {
+            return Ok(());
+        }
+        if let Some(limit) = self.reserved_balance_limit {
+            let requested = self.reserved_balance + amount;
+            if requested > limit {
+                return Err(ReservationError::ReservedLimitExceeded { requested, limit });
+            }
+        }
+        if amount > self.cycles_balance {
+            return Err(ReservationError::InsufficientCycles {
+                requested: amount,
+                available: self.cycles_balance,
+            });
+        }
+        self.cycles_balance -= amount;
+        self.reserved_balance += amount;
+        Ok(())
+    }
+
+    /// Removes up to `amount` cycles from the reserved balance and returns the
+    /// amount that was actually removed.
+    pub fn remove_reserved_cycles(&mut self, amount: Cycles) -> Cycles {
+        let removed = amount.min(self.reserved_balance);
+        self.reserved_balance -= removed;
+        removed
+    }
+
     /// Returns the balance after applying the pending debit.
     /// Returns 0 if the balance is smaller than the pending debit.
     pub fn debited_balance(&self) -> Cycles {
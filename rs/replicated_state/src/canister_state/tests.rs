@@ -2,6 +2,7 @@ use super::*;
 use crate::canister_state::execution_state::WasmMetadata;
 use crate::CallOrigin;
 use ic_base_types::NumSeconds;
+use ic_ic00_types::{CanisterChangeDetails, CanisterChangeOrigin};
 use ic_test_utilities::mock_time;
 use ic_test_utilities::types::{
     ids::user_test_id,
@@ -577,3 +578,40 @@ fn canister_state_cycles_debit() {
         assert_eq!(Cycles::zero(), system_state.debited_balance());
     })
 }
+
+#[test]
+fn canister_history_is_bounded_and_counts_all_changes() {
+    canister_state_test(|mut canister_state| {
+        let system_state = &mut canister_state.system_state;
+        let origin = CanisterChangeOrigin::from_user(user_test_id(24).get());
+        let num_changes = crate::MAX_CANISTER_HISTORY_CHANGES as u64 + 5;
+        for i in 0..num_changes {
+            system_state.canister_version = i;
+            system_state.add_canister_change(
+                Time::from_nanos_since_unix_epoch(i),
+                origin.clone(),
+                CanisterChangeDetails::code_uninstall(),
+            );
+        }
+
+        let history = &system_state.canister_history;
+        assert_eq!(history.get_total_num_changes(), num_changes);
+        assert_eq!(history.changes().len(), crate::MAX_CANISTER_HISTORY_CHANGES);
+        // The oldest changes have been dropped.
+        assert_eq!(history.changes().front().unwrap().canister_version(), 5);
+
+        let recent: Vec<u64> = history
+            .get_changes(3)
+            .map(|change| change.canister_version())
+            .collect();
+        assert_eq!(
+            recent,
+            vec![num_changes - 3, num_changes - 2, num_changes - 1]
+        );
+        assert_eq!(history.get_changes(0).count(), 0);
+        assert_eq!(
+            history.get_changes(1000).count(),
+            crate::MAX_CANISTER_HISTORY_CHANGES
+        );
+    })
+}
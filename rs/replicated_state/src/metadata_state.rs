@@ -1,7 +1,9 @@
+pub mod query_stats;
 pub mod subnet_call_context_manager;
 #[cfg(test)]
 mod tests;
 
+use crate::metadata_state::query_stats::RawQueryStats;
 use crate::metadata_state::subnet_call_context_manager::SubnetCallContextManager;
 use ic_base_types::CanisterId;
 use ic_btc_types::Network as BitcoinNetwork;
@@ -154,6 +156,14 @@ pub struct SystemMetadata {
     /// response limit. To work around this limitation, large responses are paginated
     /// and are stored here temporarily until they're fetched by the calling canister.
     pub bitcoin_get_successors_follow_up_responses: BTreeMap<CanisterId, Vec<BlockBlob>>,
+
+    /// A counter used for generating ids of canister snapshots. It is never
+    /// decremented, so snapshot ids are not reused.
+    pub next_canister_snapshot_id: u64,
+
+    /// Query statistics received via consensus that are not yet aggregated
+    /// into the canister states.
+    pub query_stats: RawQueryStats,
 }
 
 /// Full description of the IC network toplogy.
@@ -471,6 +481,8 @@ impl From<&SystemMetadata> for pb_metadata::SystemMetadata {
                     },
                 )
                 .collect(),
+            next_canister_snapshot_id: item.next_canister_snapshot_id,
+            query_stats: Some((&item.query_stats).into()),
         }
     }
 }
@@ -573,6 +585,11 @@ impl TryFrom<pb_metadata::SystemMetadata> for SystemMetadata {
             },
             expected_compiled_wasms: BTreeSet::new(),
             bitcoin_get_successors_follow_up_responses,
+            next_canister_snapshot_id: item.next_canister_snapshot_id,
+            query_stats: match item.query_stats {
+                Some(query_stats) => query_stats.try_into()?,
+                None => RawQueryStats::default(),
+            },
         })
     }
 }
@@ -602,6 +619,8 @@ impl SystemMetadata {
             subnet_metrics: Default::default(),
             expected_compiled_wasms: BTreeSet::new(),
             bitcoin_get_successors_follow_up_responses: BTreeMap::default(),
+            next_canister_snapshot_id: 0,
+            query_stats: RawQueryStats::default(),
         }
     }
 
@@ -0,0 +1,169 @@
+use crate::CanisterState;
+use ic_base_types::CanisterId;
+use ic_protobuf::{
+    proxy::{try_from_option_field, ProxyDecodeError},
+    state::system_metadata::v1 as pb_metadata,
+};
+use ic_types::{
+    batch::{QueryStats, QueryStatsEpoch, QueryStatsPayload},
+    node_id_into_protobuf, node_id_try_from_protobuf, NodeId,
+};
+use std::{
+    collections::BTreeMap,
+    convert::{From, TryFrom},
+};
+
+/// The number of epochs after the end of an epoch during which nodes can
+/// still report their statistics for it. Once a payload for a later epoch is
+/// delivered, the statistics of the epoch are aggregated.
+const EPOCHS_TO_WAIT_FOR_REPORTS: u64 = 1;
+
+/// Query statistics received via consensus that are not yet aggregated into
+/// the canister states.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RawQueryStats {
+    /// The highest epoch whose statistics were aggregated, if any. Reports
+    /// for this epoch and earlier ones are ignored.
+    pub highest_aggregated_epoch: Option<QueryStatsEpoch>,
+    /// The statistics reported by each node, per epoch and canister.
+    pub stats: BTreeMap<QueryStatsEpoch, BTreeMap<CanisterId, BTreeMap<NodeId, QueryStats>>>,
+}
+
+impl RawQueryStats {
+    /// Returns true if the statistics of `epoch` were already aggregated.
+    pub fn is_aggregated(&self, epoch: QueryStatsEpoch) -> bool {
+        self.highest_aggregated_epoch
+            .map_or(false, |highest| epoch <= highest)
+    }
+
+    /// Returns true if `proposer` already reported the statistics of
+    /// `canister_id` for `epoch`.
+    pub fn contains(
+        &self,
+        epoch: QueryStatsEpoch,
+        proposer: &NodeId,
+        canister_id: &CanisterId,
+    ) -> bool {
+        self.stats
+            .get(&epoch)
+            .and_then(|canisters| canisters.get(canister_id))
+            .map_or(false, |nodes| nodes.contains_key(proposer))
+    }
+
+    /// Records the statistics in `payload` and aggregates the statistics of
+    /// all epochs that can no longer receive reports into `canister_states`.
+    ///
+    /// Statistics of epochs that were already aggregated are dropped, as are
+    /// repeated reports of a node.
+    pub fn deliver(
+        &mut self,
+        payload: QueryStatsPayload,
+        canister_states: &mut BTreeMap<CanisterId, CanisterState>,
+    ) {
+        let epoch = payload.epoch;
+        if !self.is_aggregated(epoch) {
+            let canisters = self.stats.entry(epoch).or_default();
+            for canister_stats in payload.canister_stats {
+                canisters
+                    .entry(canister_stats.canister_id)
+                    .or_default()
+                    .entry(payload.proposer)
+                    .or_insert(canister_stats.stats);
+            }
+        }
+
+        if epoch.get() > EPOCHS_TO_WAIT_FOR_REPORTS {
+            self.aggregate_up_to(
+                QueryStatsEpoch::from(epoch.get() - EPOCHS_TO_WAIT_FOR_REPORTS - 1),
+                canister_states,
+            );
+        }
+    }
+
+    /// Adds the statistics of all epochs up to and including `epoch` to the
+    /// totals of the respective canisters and removes them.
+    fn aggregate_up_to(
+        &mut self,
+        epoch: QueryStatsEpoch,
+        canister_states: &mut BTreeMap<CanisterId, CanisterState>,
+    ) {
+        if self.is_aggregated(epoch) {
+            return;
+        }
+        let remaining = self.stats.split_off(&(epoch + QueryStatsEpoch::from(1)));
+        let aggregated = std::mem::replace(&mut self.stats, remaining);
+        for canisters in aggregated.into_values() {
+            for (canister_id, nodes) in canisters {
+                // The canister may have been deleted in the meantime.
+                if let Some(canister) = canister_states.get_mut(&canister_id) {
+                    for stats in nodes.values() {
+                        canister
+                            .system_state
+                            .total_query_stats
+                            .saturating_accumulate(stats);
+                    }
+                }
+            }
+        }
+        self.highest_aggregated_epoch = Some(epoch);
+    }
+}
+
+impl From<&RawQueryStats> for pb_metadata::RawQueryStats {
+    fn from(item: &RawQueryStats) -> Self {
+        let mut stats = vec![];
+        for (epoch, canisters) in item.stats.iter() {
+            for (canister_id, nodes) in canisters.iter() {
+                for (proposer, node_stats) in nodes.iter() {
+                    stats.push(pb_metadata::RawQueryStatsEntry {
+                        epoch: epoch.get(),
+                        canister_id: Some((*canister_id).into()),
+                        proposer: Some(node_id_into_protobuf(*proposer)),
+                        num_calls: node_stats.num_calls,
+                        num_instructions: node_stats.num_instructions,
+                        ingress_payload_size: node_stats.ingress_payload_size,
+                        egress_payload_size: node_stats.egress_payload_size,
+                    });
+                }
+            }
+        }
+        Self {
+            highest_aggregated_epoch: item.highest_aggregated_epoch.map(|epoch| epoch.get()),
+            stats,
+        }
+    }
+}
+
+impl TryFrom<pb_metadata::RawQueryStats> for RawQueryStats {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_metadata::RawQueryStats) -> Result<Self, Self::Error> {
+        let mut stats: BTreeMap<_, BTreeMap<_, BTreeMap<_, _>>> = BTreeMap::new();
+        for entry in item.stats {
+            let canister_id: CanisterId =
+                try_from_option_field(entry.canister_id, "RawQueryStatsEntry::canister_id")?;
+            let proposer = node_id_try_from_protobuf(try_from_option_field(
+                entry.proposer,
+                "RawQueryStatsEntry::proposer",
+            )?)?;
+            stats
+                .entry(QueryStatsEpoch::from(entry.epoch))
+                .or_default()
+                .entry(canister_id)
+                .or_default()
+                .insert(
+                    proposer,
+                    QueryStats {
+                        num_calls: entry.num_calls,
+                        num_instructions: entry.num_instructions,
+                        ingress_payload_size: entry.ingress_payload_size,
+                        egress_payload_size: entry.egress_payload_size,
+                    },
+                );
+        }
+        Ok(Self {
+            highest_aggregated_epoch: item.highest_aggregated_epoch.map(QueryStatsEpoch::from),
+            stats,
+        })
+    }
+}
@@ -1,5 +1,7 @@
 use super::*;
 use crate::metadata_state::subnet_call_context_manager::SubnetCallContextManager;
+use crate::{CanisterState, SchedulerState, SystemState};
+use ic_base_types::NumSeconds;
 use ic_constants::MAX_INGRESS_TTL;
 use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::EcdsaCurve;
@@ -8,17 +10,19 @@ use ic_test_utilities::{
     mock_time,
     types::{
         ids::{
-            canister_test_id, message_test_id, subnet_test_id, user_test_id, SUBNET_0, SUBNET_1,
-            SUBNET_2,
+            canister_test_id, message_test_id, node_test_id, subnet_test_id, user_test_id,
+            SUBNET_0, SUBNET_1, SUBNET_2,
         },
         messages::{RequestBuilder, ResponseBuilder},
         xnet::{StreamHeaderBuilder, StreamSliceBuilder},
     },
 };
 use ic_types::{
+    batch::{CanisterQueryStats, QueryStats, QueryStatsEpoch, QueryStatsPayload, TotalQueryStats},
     canister_http::{CanisterHttpMethod, CanisterHttpRequestContext},
     ingress::WasmResult,
     messages::{CallbackId, Payload},
+    Cycles,
 };
 use lazy_static::lazy_static;
 use maplit::btreemap;
@@ -459,6 +463,124 @@ fn roundtrip_encoding() {
     // Set `last_generated_canister_id` to valid, but migrated canister ID.
     system_metadata.last_generated_canister_id = Some(15.into());
     validate_roundtrip_encoding(&system_metadata);
+
+    // Add query statistics that are not yet aggregated.
+    system_metadata.query_stats = RawQueryStats {
+        highest_aggregated_epoch: Some(QueryStatsEpoch::from(1)),
+        stats: btreemap! {
+            QueryStatsEpoch::from(2) => btreemap! {
+                canister_test_id(1) => btreemap! {
+                    node_test_id(1) => query_stats(1),
+                    node_test_id(2) => query_stats(2),
+                },
+            },
+        },
+    };
+    validate_roundtrip_encoding(&system_metadata);
+}
+
+fn query_stats(num_calls: u32) -> QueryStats {
+    QueryStats {
+        num_calls,
+        num_instructions: 1_000 * num_calls as u64,
+        ingress_payload_size: 10 * num_calls as u64,
+        egress_payload_size: 20 * num_calls as u64,
+    }
+}
+
+fn query_stats_payload(
+    proposer: NodeId,
+    epoch: u64,
+    canister_stats: Vec<(CanisterId, QueryStats)>,
+) -> QueryStatsPayload {
+    QueryStatsPayload {
+        proposer,
+        epoch: QueryStatsEpoch::from(epoch),
+        canister_stats: canister_stats
+            .into_iter()
+            .map(|(canister_id, stats)| CanisterQueryStats { canister_id, stats })
+            .collect(),
+    }
+}
+
+#[test]
+fn query_stats_are_aggregated_once_epoch_can_no_longer_be_reported() {
+    let canister_id = canister_test_id(1);
+    let mut canister_states = btreemap! {
+        canister_id => CanisterState::new(
+            SystemState::new_running(
+                canister_id,
+                canister_id.get(),
+                Cycles::new(1_000_000),
+                NumSeconds::from(100_000),
+            ),
+            None,
+            SchedulerState::default(),
+        ),
+    };
+    let mut raw_query_stats = RawQueryStats::default();
+
+    for node in 1..=2 {
+        raw_query_stats.deliver(
+            query_stats_payload(
+                node_test_id(node),
+                1,
+                vec![(canister_id, query_stats(node as u32))],
+            ),
+            &mut canister_states,
+        );
+    }
+    // A repeated report of a node is ignored.
+    raw_query_stats.deliver(
+        query_stats_payload(node_test_id(1), 1, vec![(canister_id, query_stats(100))]),
+        &mut canister_states,
+    );
+    assert!(raw_query_stats.contains(QueryStatsEpoch::from(1), &node_test_id(1), &canister_id));
+
+    // Epoch 1 can still be reported while epoch 2 is being reported.
+    raw_query_stats.deliver(
+        query_stats_payload(node_test_id(1), 2, vec![]),
+        &mut canister_states,
+    );
+    assert_eq!(
+        canister_states[&canister_id].system_state.total_query_stats,
+        TotalQueryStats::default()
+    );
+
+    // Reports for epoch 3 close epoch 1.
+    raw_query_stats.deliver(
+        query_stats_payload(node_test_id(1), 3, vec![]),
+        &mut canister_states,
+    );
+    assert_eq!(
+        canister_states[&canister_id].system_state.total_query_stats,
+        TotalQueryStats {
+            num_calls: 3,
+            num_instructions: 3_000,
+            ingress_payload_size: 30,
+            egress_payload_size: 60,
+        }
+    );
+    assert!(raw_query_stats.is_aggregated(QueryStatsEpoch::from(1)));
+    assert!(!raw_query_stats
+        .stats
+        .contains_key(&QueryStatsEpoch::from(1)));
+
+    // Late reports for an aggregated epoch are dropped.
+    raw_query_stats.deliver(
+        query_stats_payload(node_test_id(3), 1, vec![(canister_id, query_stats(5))]),
+        &mut canister_states,
+    );
+    assert!(!raw_query_stats
+        .stats
+        .contains_key(&QueryStatsEpoch::from(1)));
+    assert_eq!(
+        canister_states[&canister_id]
+            .system_state
+            .total_query_stats
+            .num_calls,
+        3
+    );
 }
 
 #[test]
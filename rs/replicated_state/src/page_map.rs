@@ -416,6 +416,45 @@ impl PageMap {
         self.persist_to_file(&self.round_delta, dst)
     }
 
+    /// Persists all pages of this page map, including the ones backed by the
+    /// checkpoint file, to the specified destination and fsync the file to
+    /// disk. Unlike `persist_and_sync_delta()`, the destination does not need
+    /// to contain the checkpoint pages already.
+    pub fn persist_and_sync_all_pages(&self, dst: &Path) -> Result<(), PersistenceError> {
+        const PAGES_PER_WRITE: usize = 1024;
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .open(dst)
+            .map_err(|err| PersistenceError::FileSystemError {
+                path: dst.display().to_string(),
+                context: "Failed to open file".to_string(),
+                internal_error: err.to_string(),
+            })?;
+        let num_host_pages = self.num_host_pages();
+        for start in (0..num_host_pages).step_by(PAGES_PER_WRITE) {
+            let end = (start + PAGES_PER_WRITE).min(num_host_pages);
+            let mut buffer = WriteBuffer {
+                content: Vec::with_capacity(end - start),
+                start_index: PageIndex::new(start as u64),
+            };
+            for index in start..end {
+                buffer
+                    .content
+                    .push(self.get_page(PageIndex::new(index as u64)));
+            }
+            buffer.apply_to_file(&mut file, dst)?;
+        }
+        file.sync_all()
+            .map_err(|err| PersistenceError::FileSystemError {
+                path: dst.display().to_string(),
+                context: "Failed to sync file".to_string(),
+                internal_error: err.to_string(),
+            })?;
+        Ok(())
+    }
+
     /// Returns the iterator over host pages managed by this `PageMap`.
     pub fn host_pages_iter(&self) -> impl Iterator<Item = (PageIndex, &PageBytes)> + '_ {
         (0..self.num_host_pages()).map(move |i| {
@@ -148,6 +148,33 @@ fn persisted_map_is_equivalent_to_the_original() {
     assert_eq!(persisted_map, original_map);
 }
 
+#[test]
+fn persisting_all_pages_to_a_new_file_includes_checkpoint_pages() {
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let copy_file = tmp.path().join("copy");
+
+    let base_page = [42u8; PAGE_SIZE];
+    let mut base_map = PageMap::default();
+    base_map.update(&[
+        (PageIndex::new(0), &base_page),
+        (PageIndex::new(2000), &base_page),
+    ]);
+    base_map.persist_delta(&heap_file).unwrap();
+
+    let mut original_map = PageMap::open(&heap_file, Height::new(0)).unwrap();
+    let page_5 = [5u8; PAGE_SIZE];
+    original_map.update(&[(PageIndex::new(5), &page_5)]);
+
+    original_map.persist_and_sync_all_pages(&copy_file).unwrap();
+    let persisted_map = PageMap::open(&copy_file, Height::new(0)).unwrap();
+
+    assert_equal_page_maps(&original_map, &persisted_map);
+}
+
 #[test]
 fn can_persist_and_load_an_empty_page_map() {
     let tmp = tempfile::Builder::new()
@@ -4,6 +4,7 @@ use super::{
 };
 use crate::{
     bitcoin_state::{BitcoinState, BitcoinStateError},
+    canister_snapshots::CanisterSnapshots,
     canister_state::queues::CanisterQueuesLoopDetector,
     canister_state::system_state::{push_input, CanisterOutputQueuesIterator},
     metadata_state::StreamMap,
@@ -330,6 +331,9 @@ pub struct ReplicatedState {
     pub root: PathBuf,
 
     bitcoin: BitcoinState,
+
+    /// Snapshots of canister states taken by their controllers.
+    pub canister_snapshots: CanisterSnapshots,
 }
 
 // We use custom impl of PartialEq because state root is not part of identity.
@@ -341,12 +345,14 @@ impl PartialEq for ReplicatedState {
             &self.metadata,
             &self.subnet_queues,
             &self.consensus_queue,
+            &self.canister_snapshots,
         ) == (
             &rhs.bitcoin,
             &rhs.canister_states,
             &rhs.metadata,
             &rhs.subnet_queues,
             &rhs.consensus_queue,
+            &rhs.canister_snapshots,
         )
     }
 }
@@ -365,6 +371,7 @@ impl ReplicatedState {
             subnet_queues: CanisterQueues::default(),
             consensus_queue: Vec::new(),
             bitcoin: BitcoinState::default(),
+            canister_snapshots: CanisterSnapshots::default(),
         }
     }
 
@@ -374,6 +381,7 @@ impl ReplicatedState {
         subnet_queues: CanisterQueues,
         consensus_queue: Vec<Response>,
         bitcoin: BitcoinState,
+        canister_snapshots: CanisterSnapshots,
         root: PathBuf,
     ) -> Self {
         let mut res = Self {
@@ -383,6 +391,7 @@ impl ReplicatedState {
             consensus_queue,
             root,
             bitcoin,
+            canister_snapshots,
         };
         res.update_stream_responses_size_bytes();
         res
@@ -568,6 +577,7 @@ impl ReplicatedState {
                 MemoryAllocation::BestEffort => canister.memory_usage_impl(with_messages),
             })
             .sum();
+        memory_taken += self.canister_snapshots.memory_taken();
         if with_messages {
             memory_taken += (self.subnet_queues.memory_usage() as u64).into();
         }
@@ -8,11 +8,11 @@ use candid::{
 use ic_icrc1::endpoints::{
     ArchivedTransactionRange, GetTransactionsResponse, QueryArchiveFn, Transaction as Tx, Value,
 };
-use ic_icrc1::{Account, Block, LedgerBalances, Transaction};
+use ic_icrc1::{Account, Block, LedgerAllowances, LedgerBalances, Transaction};
 use ic_ledger_canister_core::{
     archive::{ArchiveCanisterWasm, ArchiveOptions},
     blockchain::Blockchain,
-    ledger::{apply_transaction, block_locations, LedgerData, TransactionInfo},
+    ledger::{apply_transaction, block_locations, LedgerContext, LedgerData, TransactionInfo},
     range_utils,
 };
 use ic_ledger_core::{
@@ -104,6 +104,8 @@ pub struct InitArgs {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Ledger {
     balances: LedgerBalances,
+    #[serde(default)]
+    approvals: LedgerAllowances,
     blockchain: Blockchain<CdkRuntime, Icrc1ArchiveWasm>,
 
     minting_account: Account,
@@ -132,6 +134,7 @@ impl Ledger {
     ) -> Self {
         let mut ledger = Self {
             balances: LedgerBalances::default(),
+            approvals: LedgerAllowances::default(),
             blockchain: Blockchain::new_with_archive(archive_options),
             transactions_by_hash: BTreeMap::new(),
             transactions_by_height: VecDeque::new(),
@@ -160,8 +163,27 @@ impl Ledger {
     }
 }
 
-impl LedgerData for Ledger {
+impl LedgerContext for Ledger {
     type AccountId = Account;
+
+    fn balances(&self) -> &Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>> {
+        &self.balances
+    }
+
+    fn balances_mut(&mut self) -> &mut Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>> {
+        &mut self.balances
+    }
+
+    fn approvals(&self) -> &LedgerAllowances {
+        &self.approvals
+    }
+
+    fn approvals_mut(&mut self) -> &mut LedgerAllowances {
+        &mut self.approvals
+    }
+}
+
+impl LedgerData for Ledger {
     type Runtime = CdkRuntime;
     type ArchiveWasm = Icrc1ArchiveWasm;
     type Transaction = Transaction;
@@ -195,14 +217,6 @@ impl LedgerData for Ledger {
         &self.token_symbol
     }
 
-    fn balances(&self) -> &Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>> {
-        &self.balances
-    }
-
-    fn balances_mut(&mut self) -> &mut Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>> {
-        &mut self.balances
-    }
-
     fn blockchain(&self) -> &Blockchain<Self::Runtime, Self::ArchiveWasm> {
         &self.blockchain
     }
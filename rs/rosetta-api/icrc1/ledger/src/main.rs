@@ -5,14 +5,15 @@ use ic_cdk::api::stable::{StableReader, StableWriter};
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 use ic_icrc1::{
     endpoints::{
-        ArchiveInfo, GetTransactionsRequest, GetTransactionsResponse, StandardRecord, TransferArg,
-        TransferError, Value,
+        Allowance, AllowanceArgs, ApproveArgs, ApproveError, ArchiveInfo, GetTransactionsRequest,
+        GetTransactionsResponse, StandardRecord, TransferArg, TransferError, TransferFromArgs,
+        TransferFromError, Value,
     },
     Account, Operation, Transaction,
 };
 use ic_icrc1_ledger::{InitArgs, Ledger};
 use ic_ledger_canister_core::ledger::{
-    apply_transaction, archive_blocks, LedgerAccess, LedgerData,
+    apply_transaction, archive_blocks, LedgerAccess, LedgerContext, LedgerData,
 };
 use ic_ledger_core::{timestamp::TimeStamp, tokens::Tokens};
 use num_traits::ToPrimitive;
@@ -111,6 +112,11 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
             ledger.balances().store.len() as f64,
             "Total number of accounts in the balance store.",
         )?;
+        w.encode_gauge(
+            "ledger_approval_entries",
+            ledger.approvals().len() as f64,
+            "Total number of allowances in the allowance table.",
+        )?;
         w.encode_gauge(
             "ledger_most_recent_block_time_seconds",
             (ledger
@@ -263,6 +269,150 @@ async fn icrc1_transfer(arg: TransferArg) -> Result<Nat, TransferError> {
     Ok(Nat::from(block_idx))
 }
 
+#[update]
+#[candid_method(update)]
+async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
+    let block_idx = Access::with_ledger_mut(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+
+        let from_account = Account {
+            owner: PrincipalId::from(ic_cdk::api::caller()),
+            subaccount: arg.from_subaccount,
+        };
+        if from_account == arg.spender {
+            ic_cdk::api::trap("self approval is not allowed");
+        }
+        if &from_account == ledger.minting_account() {
+            ic_cdk::api::trap("the minting account cannot delegate mints");
+        }
+
+        // No one can have more than u64::MAX tokens, so larger allowances
+        // are equivalent to u64::MAX.
+        let amount = arg.amount.0.to_u64().unwrap_or(u64::MAX);
+        let expected_allowance = match arg.expected_allowance {
+            Some(expected_allowance) => match expected_allowance.0.to_u64() {
+                Some(n) => Some(n),
+                None => {
+                    let current_allowance = ledger
+                        .approvals()
+                        .allowance(&from_account, &arg.spender, now)
+                        .amount;
+                    return Err(ApproveError::AllowanceChanged {
+                        current_allowance: Nat::from(current_allowance.get_e8s()),
+                    });
+                }
+            },
+            None => None,
+        };
+
+        let expected_fee_tokens = ledger.transfer_fee();
+        let expected_fee = Nat::from(expected_fee_tokens.get_e8s());
+        if arg.fee.is_some() && arg.fee.as_ref() != Some(&expected_fee) {
+            return Err(ApproveError::BadFee { expected_fee });
+        }
+
+        let tx = Transaction {
+            operation: Operation::Approve {
+                from: from_account,
+                spender: arg.spender,
+                amount,
+                expected_allowance,
+                expires_at: arg.expires_at,
+                fee: expected_fee_tokens.get_e8s(),
+            },
+            created_at_time: arg.created_at_time,
+            memo: arg.memo,
+        };
+
+        let (block_idx, _) = apply_transaction(ledger, tx, now)?;
+        Ok(block_idx)
+    })?;
+
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    archive_blocks::<Access>(MAX_MESSAGE_SIZE).await;
+    Ok(Nat::from(block_idx))
+}
+
+#[update]
+#[candid_method(update)]
+async fn icrc2_transfer_from(arg: TransferFromArgs) -> Result<Nat, TransferFromError> {
+    let block_idx = Access::with_ledger_mut(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+
+        let spender_account = Account {
+            owner: PrincipalId::from(ic_cdk::api::caller()),
+            subaccount: arg.spender_subaccount,
+        };
+        if &arg.from == ledger.minting_account() {
+            ic_cdk::api::trap("the minting account cannot delegate mints");
+        }
+        if &arg.to == ledger.minting_account() {
+            ic_cdk::api::trap("icrc2_transfer_from does not support burning tokens");
+        }
+
+        let amount = match arg.amount.0.to_u64() {
+            Some(n) => Tokens::from_e8s(n),
+            None => {
+                // No one can have so many tokens
+                let balance = Nat::from(ledger.balances().account_balance(&arg.from).get_e8s());
+                assert!(balance < arg.amount);
+                return Err(TransferFromError::InsufficientFunds { balance });
+            }
+        };
+
+        let expected_fee_tokens = ledger.transfer_fee();
+        let expected_fee = Nat::from(expected_fee_tokens.get_e8s());
+        if arg.fee.is_some() && arg.fee.as_ref() != Some(&expected_fee) {
+            return Err(TransferFromError::BadFee { expected_fee });
+        }
+
+        // An account owner does not need an allowance to spend its own tokens.
+        let spender = if spender_account == arg.from {
+            None
+        } else {
+            Some(spender_account)
+        };
+
+        let tx = Transaction {
+            operation: Operation::Transfer {
+                from: arg.from,
+                to: arg.to,
+                spender,
+                amount: amount.get_e8s(),
+                fee: expected_fee_tokens.get_e8s(),
+            },
+            created_at_time: arg.created_at_time,
+            memo: arg.memo,
+        };
+
+        let (block_idx, _) = apply_transaction(ledger, tx, now)?;
+        Ok(block_idx)
+    })?;
+
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    archive_blocks::<Access>(MAX_MESSAGE_SIZE).await;
+    Ok(Nat::from(block_idx))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc2_allowance(arg: AllowanceArgs) -> Allowance {
+    Access::with_ledger(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+        let allowance = ledger
+            .approvals()
+            .allowance(&arg.account, &arg.spender, now);
+        Allowance {
+            allowance: Nat::from(allowance.amount.get_e8s()),
+            expires_at: allowance
+                .expires_at
+                .map(|expires_at| expires_at.as_nanos_since_unix_epoch()),
+        }
+    })
+}
+
 #[query]
 fn archives() -> Vec<ArchiveInfo> {
     Access::with_ledger(|ledger| {
@@ -290,10 +440,16 @@ fn archives() -> Vec<ArchiveInfo> {
 #[query(name = "icrc1_supported_standards")]
 #[candid_method(query, rename = "icrc1_supported_standards")]
 fn supported_standards() -> Vec<StandardRecord> {
-    vec![StandardRecord {
-        name: "ICRC-1".to_string(),
-        url: "https://github.com/dfinity/ICRC-1".to_string(),
-    }]
+    vec![
+        StandardRecord {
+            name: "ICRC-1".to_string(),
+            url: "https://github.com/dfinity/ICRC-1".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-2".to_string(),
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2".to_string(),
+        },
+    ]
 }
 
 #[query]
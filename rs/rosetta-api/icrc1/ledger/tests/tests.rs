@@ -3,8 +3,10 @@ use candid::{CandidType, Decode, Encode};
 use ic_base_types::PrincipalId;
 use ic_icrc1::{
     endpoints::{
-        ArchiveInfo, GetTransactionsRequest, GetTransactionsResponse, StandardRecord,
-        Transaction as Tx, TransactionRange, Transfer, TransferArg, TransferError, Value,
+        Allowance, AllowanceArgs, Approve, ApproveArgs, ApproveError, ArchiveInfo,
+        GetTransactionsRequest, GetTransactionsResponse, StandardRecord, Transaction as Tx,
+        TransactionRange, Transfer, TransferArg, TransferError, TransferFromArgs,
+        TransferFromError, Value,
     },
     Account, Block, Memo, Operation, Transaction,
 };
@@ -180,6 +182,121 @@ fn transfer(
     )
 }
 
+fn send_approval(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: PrincipalId,
+    arg: &ApproveArgs,
+) -> Result<BlockIndex, ApproveError> {
+    Decode!(
+        &env.execute_ingress_as(
+            from,
+            ledger,
+            "icrc2_approve",
+            Encode!(arg)
+            .unwrap()
+        )
+        .expect("failed to approve allowance")
+        .bytes(),
+        Result<Nat, ApproveError>
+    )
+    .expect("failed to decode approve response")
+    .map(|n| n.0.to_u64().unwrap())
+}
+
+fn approve(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: PrincipalId,
+    spender: impl Into<Account>,
+    amount: u64,
+) -> Result<BlockIndex, ApproveError> {
+    send_approval(
+        env,
+        ledger,
+        from,
+        &ApproveArgs {
+            from_subaccount: None,
+            spender: spender.into(),
+            amount: Nat::from(amount),
+            expected_allowance: None,
+            expires_at: None,
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        },
+    )
+}
+
+fn send_transfer_from(
+    env: &StateMachine,
+    ledger: CanisterId,
+    spender: PrincipalId,
+    arg: &TransferFromArgs,
+) -> Result<BlockIndex, TransferFromError> {
+    Decode!(
+        &env.execute_ingress_as(
+            spender,
+            ledger,
+            "icrc2_transfer_from",
+            Encode!(arg)
+            .unwrap()
+        )
+        .expect("failed to transfer funds from account")
+        .bytes(),
+        Result<Nat, TransferFromError>
+    )
+    .expect("failed to decode transfer_from response")
+    .map(|n| n.0.to_u64().unwrap())
+}
+
+fn transfer_from(
+    env: &StateMachine,
+    ledger: CanisterId,
+    spender: PrincipalId,
+    from: impl Into<Account>,
+    to: impl Into<Account>,
+    amount: u64,
+) -> Result<BlockIndex, TransferFromError> {
+    send_transfer_from(
+        env,
+        ledger,
+        spender,
+        &TransferFromArgs {
+            spender_subaccount: None,
+            from: from.into(),
+            to: to.into(),
+            amount: Nat::from(amount),
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        },
+    )
+}
+
+fn allowance(
+    env: &StateMachine,
+    ledger: CanisterId,
+    account: impl Into<Account>,
+    spender: impl Into<Account>,
+) -> Allowance {
+    Decode!(
+        &env.query(
+            ledger,
+            "icrc2_allowance",
+            Encode!(&AllowanceArgs {
+                account: account.into(),
+                spender: spender.into(),
+            })
+            .unwrap()
+        )
+        .expect("failed to query allowance")
+        .bytes(),
+        Allowance
+    )
+    .expect("failed to decode allowance response")
+}
+
 fn list_archives(env: &StateMachine, ledger: CanisterId) -> Vec<ArchiveInfo> {
     Decode!(
         &env.query(ledger, "archives", Encode!().unwrap())
@@ -325,10 +442,16 @@ fn test_metadata() {
     let standards = supported_standards(&env, canister_id);
     assert_eq!(
         standards,
-        vec![StandardRecord {
-            name: "ICRC-1".to_string(),
-            url: "https://github.com/dfinity/ICRC-1".to_string(),
-        }]
+        vec![
+            StandardRecord {
+                name: "ICRC-1".to_string(),
+                url: "https://github.com/dfinity/ICRC-1".to_string(),
+            },
+            StandardRecord {
+                name: "ICRC-2".to_string(),
+                url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2".to_string(),
+            },
+        ]
     );
 }
 
@@ -525,6 +648,188 @@ fn test_single_transfer() {
     assert_eq!(6_000_000u64, balance_of(&env, canister_id, p2));
 }
 
+#[test]
+fn test_approve_and_transfer_from() {
+    let env = StateMachine::new();
+    let owner = PrincipalId::new_user_test_id(1);
+    let spender = PrincipalId::new_user_test_id(2);
+    let receiver = PrincipalId::new_user_test_id(3);
+    let canister_id = install_ledger(&env, vec![(Account::from(owner), 10_000_000)]);
+
+    let approval_idx =
+        approve(&env, canister_id, owner, spender, 1_000_000).expect("approve failed");
+
+    // The owner pays the fee for the approval.
+    assert_eq!(10_000_000 - FEE, balance_of(&env, canister_id, owner));
+    assert_eq!(
+        allowance(&env, canister_id, owner, spender),
+        Allowance {
+            allowance: Nat::from(1_000_000),
+            expires_at: None,
+        }
+    );
+
+    transfer_from(&env, canister_id, spender, owner, receiver, 500_000)
+        .expect("transfer_from failed");
+
+    assert_eq!(
+        10_000_000 - 2 * FEE - 500_000,
+        balance_of(&env, canister_id, owner)
+    );
+    assert_eq!(500_000, balance_of(&env, canister_id, receiver));
+    assert_eq!(0, balance_of(&env, canister_id, spender));
+    assert_eq!(
+        allowance(&env, canister_id, owner, spender).allowance,
+        Nat::from(1_000_000 - 500_000 - FEE)
+    );
+
+    // The spender cannot exceed the remaining allowance.
+    assert_eq!(
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(1_000_000 - 500_000 - FEE)
+        }),
+        transfer_from(&env, canister_id, spender, owner, receiver, 500_000)
+    );
+
+    // Other principals have no allowance at all.
+    assert_eq!(
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(0)
+        }),
+        transfer_from(&env, canister_id, receiver, owner, receiver, 1)
+    );
+
+    // Setting the allowance to zero revokes it.
+    approve(&env, canister_id, owner, spender, 0).expect("approve failed");
+    assert_eq!(
+        allowance(&env, canister_id, owner, spender).allowance,
+        Nat::from(0)
+    );
+
+    // Approvals are part of the transaction log.
+    let approval = get_transactions(&env, canister_id, approval_idx, 1).transactions[0].clone();
+    assert_eq!(approval.kind, "approve");
+    assert_eq!(
+        approval.approve,
+        Some(Approve {
+            from: owner.into(),
+            spender: spender.into(),
+            amount: Nat::from(1_000_000),
+            expected_allowance: None,
+            expires_at: None,
+            memo: None,
+            fee: Some(Nat::from(FEE)),
+            created_at_time: None,
+        })
+    );
+    let transfer = get_transactions(&env, canister_id, approval_idx + 1, 1).transactions[0]
+        .transfer
+        .clone()
+        .expect("expected a transfer");
+    assert_eq!(transfer.spender, Some(spender.into()));
+}
+
+#[test]
+fn test_approval_expiration() {
+    let env = StateMachine::new();
+    let owner = PrincipalId::new_user_test_id(1);
+    let spender = PrincipalId::new_user_test_id(2);
+    let canister_id = install_ledger(&env, vec![(Account::from(owner), 10_000_000)]);
+
+    let now = system_time_to_nanos(env.time());
+    let approve_with_expiration = |expires_at| {
+        send_approval(
+            &env,
+            canister_id,
+            owner,
+            &ApproveArgs {
+                from_subaccount: None,
+                spender: spender.into(),
+                amount: Nat::from(1_000_000),
+                expected_allowance: None,
+                expires_at: Some(expires_at),
+                fee: None,
+                memo: None,
+                created_at_time: None,
+            },
+        )
+    };
+
+    assert_eq!(
+        Err(ApproveError::Expired { ledger_time: now }),
+        approve_with_expiration(now)
+    );
+    assert_eq!(10_000_000, balance_of(&env, canister_id, owner));
+
+    let expires_at = now + Duration::from_secs(3600).as_nanos() as u64;
+    approve_with_expiration(expires_at).expect("approve failed");
+    assert_eq!(
+        allowance(&env, canister_id, owner, spender),
+        Allowance {
+            allowance: Nat::from(1_000_000),
+            expires_at: Some(expires_at),
+        }
+    );
+
+    env.advance_time(Duration::from_secs(3600));
+
+    assert_eq!(
+        allowance(&env, canister_id, owner, spender).allowance,
+        Nat::from(0)
+    );
+    assert_eq!(
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(0)
+        }),
+        transfer_from(&env, canister_id, spender, owner, spender, 1)
+    );
+}
+
+#[test]
+fn test_approve_expected_allowance() {
+    let env = StateMachine::new();
+    let owner = PrincipalId::new_user_test_id(1);
+    let spender = PrincipalId::new_user_test_id(2);
+    let canister_id = install_ledger(&env, vec![(Account::from(owner), 10_000_000)]);
+
+    approve(&env, canister_id, owner, spender, 1_000_000).expect("approve failed");
+
+    let approve_if_unchanged = |expected_allowance: u64, amount: u64| {
+        send_approval(
+            &env,
+            canister_id,
+            owner,
+            &ApproveArgs {
+                from_subaccount: None,
+                spender: spender.into(),
+                amount: Nat::from(amount),
+                expected_allowance: Some(Nat::from(expected_allowance)),
+                expires_at: None,
+                fee: None,
+                memo: None,
+                created_at_time: None,
+            },
+        )
+    };
+
+    assert_eq!(
+        Err(ApproveError::AllowanceChanged {
+            current_allowance: Nat::from(1_000_000)
+        }),
+        approve_if_unchanged(2_000_000, 3_000_000)
+    );
+    assert_eq!(
+        allowance(&env, canister_id, owner, spender).allowance,
+        Nat::from(1_000_000)
+    );
+
+    approve_if_unchanged(1_000_000, 3_000_000).expect("approve failed");
+    assert_eq!(
+        allowance(&env, canister_id, owner, spender).allowance,
+        Nat::from(3_000_000)
+    );
+}
+
 #[test]
 fn test_account_canonicalization() {
     let env = StateMachine::new();
@@ -758,6 +1063,7 @@ fn test_archiving() {
         let expected_tx = Transfer {
             from: p1.into(),
             to: p2.into(),
+            spender: None,
             amount: Nat::from(10_000 + i - 1),
             fee: Some(Nat::from(FEE)),
             memo: None,
@@ -794,6 +1100,7 @@ fn test_archiving() {
             Some(Transfer {
                 from: p1.into(),
                 to: p2.into(),
+                spender: None,
                 amount: Nat::from(10_000 + i - 1),
                 fee: Some(Nat::from(FEE)),
                 memo: None,
@@ -849,14 +1156,41 @@ fn arb_account() -> impl Strategy<Value = Account> {
 }
 
 fn arb_transfer() -> impl Strategy<Value = Operation> {
-    (arb_account(), arb_account(), arb_amount(), arb_amount()).prop_map(
-        |(from, to, amount, fee)| Operation::Transfer {
+    (
+        arb_account(),
+        arb_account(),
+        proptest::option::of(arb_account()),
+        arb_amount(),
+        arb_amount(),
+    )
+        .prop_map(|(from, to, spender, amount, fee)| Operation::Transfer {
             from,
             to,
+            spender,
             amount,
             fee,
-        },
+        })
+}
+
+fn arb_approve() -> impl Strategy<Value = Operation> {
+    (
+        arb_account(),
+        arb_account(),
+        arb_amount(),
+        any::<Option<u64>>(),
+        any::<Option<u64>>(),
+        arb_amount(),
     )
+        .prop_map(
+            |(from, spender, amount, expected_allowance, expires_at, fee)| Operation::Approve {
+                from,
+                spender,
+                amount,
+                expected_allowance,
+                expires_at,
+                fee,
+            },
+        )
 }
 
 fn arb_mint() -> impl Strategy<Value = Operation> {
@@ -868,7 +1202,7 @@ fn arb_burn() -> impl Strategy<Value = Operation> {
 }
 
 fn arb_operation() -> impl Strategy<Value = Operation> {
-    prop_oneof![arb_transfer(), arb_mint(), arb_burn()]
+    prop_oneof![arb_transfer(), arb_mint(), arb_burn(), arb_approve()]
 }
 
 fn arb_transaction() -> impl Strategy<Value = Transaction> {
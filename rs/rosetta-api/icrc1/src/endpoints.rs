@@ -42,6 +42,11 @@ impl From<CoreTransferError> for TransferError {
             LTE::TxDuplicate { duplicate_of } => TE::Duplicate {
                 duplicate_of: Nat::from(duplicate_of),
             },
+            LTE::InsufficientAllowance { .. }
+            | LTE::ExpiredApproval { .. }
+            | LTE::AllowanceChanged { .. } => {
+                unreachable!("icrc1_transfer does not use allowances")
+            }
         }
     }
 }
@@ -60,6 +65,141 @@ pub struct TransferArg {
     pub amount: NumTokens,
 }
 
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct ApproveArgs {
+    #[serde(default)]
+    pub from_subaccount: Option<Subaccount>,
+    pub spender: Account,
+    pub amount: NumTokens,
+    #[serde(default)]
+    pub expected_allowance: Option<NumTokens>,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub fee: Option<NumTokens>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum ApproveError {
+    BadFee { expected_fee: NumTokens },
+    InsufficientFunds { balance: NumTokens },
+    AllowanceChanged { current_allowance: NumTokens },
+    Expired { ledger_time: u64 },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: BlockIndex },
+    TemporarilyUnavailable,
+    GenericError { error_code: Nat, message: String },
+}
+
+impl From<CoreTransferError> for ApproveError {
+    fn from(err: CoreTransferError) -> Self {
+        use ic_ledger_canister_core::ledger::TransferError as LTE;
+        use ApproveError as AE;
+
+        match err {
+            LTE::BadFee { expected_fee } => AE::BadFee {
+                expected_fee: Nat::from(expected_fee.get_e8s()),
+            },
+            LTE::InsufficientFunds { balance } => AE::InsufficientFunds {
+                balance: Nat::from(balance.get_e8s()),
+            },
+            LTE::TxTooOld { .. } => AE::TooOld,
+            LTE::TxCreatedInFuture { ledger_time } => AE::CreatedInFuture {
+                ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+            },
+            LTE::TxThrottled => AE::TemporarilyUnavailable,
+            LTE::TxDuplicate { duplicate_of } => AE::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            },
+            LTE::ExpiredApproval { ledger_time } => AE::Expired {
+                ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+            },
+            LTE::AllowanceChanged { current_allowance } => AE::AllowanceChanged {
+                current_allowance: Nat::from(current_allowance.get_e8s()),
+            },
+            LTE::InsufficientAllowance { .. } => {
+                unreachable!("icrc2_approve does not spend allowances")
+            }
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct TransferFromArgs {
+    #[serde(default)]
+    pub spender_subaccount: Option<Subaccount>,
+    pub from: Account,
+    pub to: Account,
+    pub amount: NumTokens,
+    #[serde(default)]
+    pub fee: Option<NumTokens>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum TransferFromError {
+    BadFee { expected_fee: NumTokens },
+    BadBurn { min_burn_amount: NumTokens },
+    InsufficientFunds { balance: NumTokens },
+    InsufficientAllowance { allowance: NumTokens },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: BlockIndex },
+    TemporarilyUnavailable,
+    GenericError { error_code: Nat, message: String },
+}
+
+impl From<CoreTransferError> for TransferFromError {
+    fn from(err: CoreTransferError) -> Self {
+        use ic_ledger_canister_core::ledger::TransferError as LTE;
+        use TransferFromError as TFE;
+
+        match err {
+            LTE::BadFee { expected_fee } => TFE::BadFee {
+                expected_fee: Nat::from(expected_fee.get_e8s()),
+            },
+            LTE::InsufficientFunds { balance } => TFE::InsufficientFunds {
+                balance: Nat::from(balance.get_e8s()),
+            },
+            LTE::InsufficientAllowance { allowance } => TFE::InsufficientAllowance {
+                allowance: Nat::from(allowance.get_e8s()),
+            },
+            LTE::TxTooOld { .. } => TFE::TooOld,
+            LTE::TxCreatedInFuture { ledger_time } => TFE::CreatedInFuture {
+                ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+            },
+            LTE::TxThrottled => TFE::TemporarilyUnavailable,
+            LTE::TxDuplicate { duplicate_of } => TFE::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            },
+            LTE::ExpiredApproval { .. } | LTE::AllowanceChanged { .. } => {
+                unreachable!("icrc2_transfer_from does not change allowances")
+            }
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct AllowanceArgs {
+    pub account: Account,
+    pub spender: Account,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct Allowance {
+    pub allowance: NumTokens,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
 /// Variant type for the `metadata` endpoint values.
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
 pub enum Value {
@@ -187,6 +327,20 @@ pub struct Transfer {
     pub amount: Nat,
     pub from: Account,
     pub to: Account,
+    #[serde(default)]
+    pub spender: Option<Account>,
+    pub memo: Option<Memo>,
+    pub fee: Option<Nat>,
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct Approve {
+    pub from: Account,
+    pub spender: Account,
+    pub amount: Nat,
+    pub expected_allowance: Option<Nat>,
+    pub expires_at: Option<u64>,
     pub memo: Option<Memo>,
     pub fee: Option<Nat>,
     pub created_at_time: Option<u64>,
@@ -198,6 +352,8 @@ pub struct Transaction {
     pub mint: Option<Mint>,
     pub burn: Option<Burn>,
     pub transfer: Option<Transfer>,
+    #[serde(default)]
+    pub approve: Option<Approve>,
     pub timestamp: u64,
 }
 
@@ -276,6 +432,7 @@ impl From<Block> for Transaction {
             mint: None,
             burn: None,
             transfer: None,
+            approve: None,
             timestamp: b.timestamp,
         };
         let created_at_time = b.transaction.created_at_time;
@@ -303,6 +460,7 @@ impl From<Block> for Transaction {
             Operation::Transfer {
                 from,
                 to,
+                spender,
                 amount,
                 fee,
             } => {
@@ -310,14 +468,123 @@ impl From<Block> for Transaction {
                 tx.transfer = Some(Transfer {
                     from,
                     to,
+                    spender,
                     amount: Nat::from(amount),
                     fee: Some(Nat::from(fee)),
                     created_at_time,
                     memo,
                 });
             }
+            Operation::Approve {
+                from,
+                spender,
+                amount,
+                expected_allowance,
+                expires_at,
+                fee,
+            } => {
+                tx.kind = "approve".to_string();
+                tx.approve = Some(Approve {
+                    from,
+                    spender,
+                    amount: Nat::from(amount),
+                    expected_allowance: expected_allowance.map(Nat::from),
+                    expires_at,
+                    fee: Some(Nat::from(fee)),
+                    created_at_time,
+                    memo,
+                });
+            }
         }
 
         tx
     }
 }
+
+impl TryFrom<Transaction> for crate::Transaction {
+    type Error = String;
+
+    fn try_from(tx: Transaction) -> Result<Self, String> {
+        use crate::Operation;
+
+        fn nat_to_u64(n: Nat, field: &str) -> Result<u64, String> {
+            use num_traits::cast::ToPrimitive;
+            n.0.to_u64()
+                .ok_or_else(|| format!("{} {} does not fit into u64", field, n))
+        }
+
+        fn missing(kind: &str) -> String {
+            format!("transaction of kind {} has no {} field", kind, kind)
+        }
+
+        let (operation, created_at_time, memo) = match tx.kind.as_str() {
+            "mint" => {
+                let mint = tx.mint.ok_or_else(|| missing("mint"))?;
+                (
+                    Operation::Mint {
+                        to: mint.to,
+                        amount: nat_to_u64(mint.amount, "amount")?,
+                    },
+                    mint.created_at_time,
+                    mint.memo,
+                )
+            }
+            "burn" => {
+                let burn = tx.burn.ok_or_else(|| missing("burn"))?;
+                (
+                    Operation::Burn {
+                        from: burn.from,
+                        amount: nat_to_u64(burn.amount, "amount")?,
+                    },
+                    burn.created_at_time,
+                    burn.memo,
+                )
+            }
+            "transfer" => {
+                let transfer = tx.transfer.ok_or_else(|| missing("transfer"))?;
+                let fee = transfer
+                    .fee
+                    .ok_or_else(|| "transfer transaction has no fee".to_string())?;
+                (
+                    Operation::Transfer {
+                        from: transfer.from,
+                        to: transfer.to,
+                        spender: transfer.spender,
+                        amount: nat_to_u64(transfer.amount, "amount")?,
+                        fee: nat_to_u64(fee, "fee")?,
+                    },
+                    transfer.created_at_time,
+                    transfer.memo,
+                )
+            }
+            "approve" => {
+                let approve = tx.approve.ok_or_else(|| missing("approve"))?;
+                let fee = approve
+                    .fee
+                    .ok_or_else(|| "approve transaction has no fee".to_string())?;
+                (
+                    Operation::Approve {
+                        from: approve.from,
+                        spender: approve.spender,
+                        amount: nat_to_u64(approve.amount, "amount")?,
+                        expected_allowance: approve
+                            .expected_allowance
+                            .map(|a| nat_to_u64(a, "expected_allowance"))
+                            .transpose()?,
+                        expires_at: approve.expires_at,
+                        fee: nat_to_u64(fee, "fee")?,
+                    },
+                    approve.created_at_time,
+                    approve.memo,
+                )
+            }
+            kind => return Err(format!("unknown transaction kind: {}", kind)),
+        };
+
+        Ok(Self {
+            operation,
+            created_at_time,
+            memo,
+        })
+    }
+}
@@ -4,9 +4,10 @@ pub mod hash;
 use candid::CandidType;
 use ciborium::tag::Required;
 use ic_base_types::PrincipalId;
-use ic_ledger_canister_core::ledger::LedgerTransaction;
+use ic_ledger_canister_core::approvals::AllowanceTable;
+use ic_ledger_canister_core::ledger::{LedgerContext, LedgerTransaction, TxApplyError};
 use ic_ledger_core::{
-    balances::{BalanceError, Balances, BalancesStore},
+    balances::Balances,
     block::{BlockType, EncodedBlock, HashOf},
     timestamp::TimeStamp,
     tokens::Tokens,
@@ -99,6 +100,24 @@ where
     Account::try_from(compact_account).map_err(D::Error::custom)
 }
 
+fn ser_opt_compact_account<S>(acc: &Option<Account>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::ser::Serializer,
+{
+    acc.clone().map(CompactAccount::from).serialize(s)
+}
+
+fn de_opt_compact_account<'de, D>(d: D) -> Result<Option<Account>, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+{
+    use serde::de::Error;
+    Option::<CompactAccount>::deserialize(d)?
+        .map(Account::try_from)
+        .transpose()
+        .map_err(D::Error::custom)
+}
+
 /// A compact representation of an Account.
 ///
 /// Instead of encoding accounts as structs with named fields,
@@ -174,6 +193,13 @@ pub enum Operation {
         #[serde(serialize_with = "ser_compact_account")]
         #[serde(deserialize_with = "de_compact_account")]
         to: Account,
+        /// The account that transferred the tokens on behalf of `from`
+        /// using an allowance.
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(serialize_with = "ser_opt_compact_account")]
+        #[serde(deserialize_with = "de_opt_compact_account")]
+        spender: Option<Account>,
         #[serde(rename = "amt")]
         amount: u64,
         fee: u64,
@@ -186,6 +212,24 @@ pub enum Operation {
         #[serde(rename = "amt")]
         amount: u64,
     },
+    #[serde(rename = "approve")]
+    Approve {
+        #[serde(serialize_with = "ser_compact_account")]
+        #[serde(deserialize_with = "de_compact_account")]
+        from: Account,
+        #[serde(serialize_with = "ser_compact_account")]
+        #[serde(deserialize_with = "de_compact_account")]
+        spender: Account,
+        #[serde(rename = "amt")]
+        amount: u64,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        expected_allowance: Option<u64>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        expires_at: Option<u64>,
+        fee: u64,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -308,19 +352,77 @@ impl LedgerTransaction for Transaction {
             })
     }
 
-    fn apply<S>(&self, balances: &mut Balances<Self::AccountId, S>) -> Result<(), BalanceError>
+    fn apply<C>(&self, context: &mut C, now: TimeStamp) -> Result<(), TxApplyError>
     where
-        S: Default + BalancesStore<Self::AccountId>,
+        C: LedgerContext<AccountId = Self::AccountId>,
     {
         match &self.operation {
             Operation::Transfer {
                 from,
                 to,
+                spender,
+                amount,
+                fee,
+            } => {
+                let amount = Tokens::from_e8s(*amount);
+                let fee = Tokens::from_e8s(*fee);
+                if let Some(spender) = spender {
+                    // The spender pays the fee out of the allowance, so we
+                    // check the allowance before touching the balances.
+                    let allowance = context.approvals().allowance(from, spender, now).amount;
+                    let used_allowance = (amount + fee)
+                        .map_err(|_| TxApplyError::InsufficientAllowance { allowance })?;
+                    if allowance < used_allowance {
+                        return Err(TxApplyError::InsufficientAllowance { allowance });
+                    }
+                    context.balances_mut().transfer(from, to, amount, fee)?;
+                    context
+                        .approvals_mut()
+                        .use_allowance(from, spender, used_allowance, now)
+                        .expect("bug: the allowance must be sufficient");
+                    Ok(())
+                } else {
+                    context
+                        .balances_mut()
+                        .transfer(from, to, amount, fee)
+                        .map_err(TxApplyError::from)
+                }
+            }
+            Operation::Burn { from, amount } => context
+                .balances_mut()
+                .burn(from, Tokens::from_e8s(*amount))
+                .map_err(TxApplyError::from),
+            Operation::Mint { to, amount } => context
+                .balances_mut()
+                .mint(to, Tokens::from_e8s(*amount))
+                .map_err(TxApplyError::from),
+            Operation::Approve {
+                from,
+                spender,
                 amount,
+                expected_allowance,
+                expires_at,
                 fee,
-            } => balances.transfer(from, to, Tokens::from_e8s(*amount), Tokens::from_e8s(*fee)),
-            Operation::Burn { from, amount } => balances.burn(from, Tokens::from_e8s(*amount)),
-            Operation::Mint { to, amount } => balances.mint(to, Tokens::from_e8s(*amount)),
+            } => {
+                let fee = Tokens::from_e8s(*fee);
+                let balance = context.balances().account_balance(from);
+                if balance < fee {
+                    return Err(TxApplyError::InsufficientFunds { balance });
+                }
+                context.approvals_mut().approve(
+                    from,
+                    spender,
+                    Tokens::from_e8s(*amount),
+                    expires_at.map(TimeStamp::from_nanos_since_unix_epoch),
+                    now,
+                    expected_allowance.map(Tokens::from_e8s),
+                )?;
+                context
+                    .balances_mut()
+                    .burn(from, fee)
+                    .expect("bug: the balance must cover the approval fee");
+                Ok(())
+            }
         }
     }
 }
@@ -354,6 +456,7 @@ impl Transaction {
             operation: Operation::Transfer {
                 from,
                 to,
+                spender: None,
                 amount: amount.get_e8s(),
                 fee: fee.get_e8s(),
             },
@@ -426,4 +529,6 @@ impl BlockType for Block {
     }
 }
 
+pub type LedgerAllowances = AllowanceTable<Account>;
+
 pub type LedgerBalances = Balances<Account, HashMap<Account, Tokens>>;
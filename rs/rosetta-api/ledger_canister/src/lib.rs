@@ -2,9 +2,11 @@ use candid::CandidType;
 use dfn_protobuf::ProtoBuf;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_crypto_sha::Sha256;
+use ic_ledger_canister_core::approvals::AllowanceTable;
 pub use ic_ledger_canister_core::archive::{ArchiveCanisterWasm, ArchiveOptions};
 use ic_ledger_canister_core::ledger::{
-    self as core_ledger, LedgerData, LedgerTransaction, TransactionInfo,
+    self as core_ledger, LedgerContext, LedgerData, LedgerTransaction, TransactionInfo,
+    TxApplyError,
 };
 use ic_ledger_core::{
     balances::{BalanceError, Balances, BalancesStore},
@@ -145,11 +147,11 @@ impl LedgerTransaction for Transaction {
         HashOf::new(state.finish())
     }
 
-    fn apply<S>(&self, balances: &mut Balances<Self::AccountId, S>) -> Result<(), BalanceError>
+    fn apply<C>(&self, context: &mut C, _now: TimeStamp) -> Result<(), TxApplyError>
     where
-        S: Default + BalancesStore<Self::AccountId>,
+        C: LedgerContext<AccountId = Self::AccountId>,
     {
-        apply_operation(balances, &self.operation)
+        apply_operation(context.balances_mut(), &self.operation).map_err(TxApplyError::from)
     }
 }
 
@@ -332,10 +334,33 @@ pub struct Ledger {
     /// Token name
     #[serde(default = "unknown_token")]
     pub token_name: String,
+
+    /// Allowances that account owners granted to spenders.
+    #[serde(default)]
+    approvals: AllowanceTable<AccountIdentifier>,
 }
 
-impl LedgerData for Ledger {
+impl LedgerContext for Ledger {
     type AccountId = AccountIdentifier;
+
+    fn balances(&self) -> &Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>> {
+        &self.balances
+    }
+
+    fn balances_mut(&mut self) -> &mut Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>> {
+        &mut self.balances
+    }
+
+    fn approvals(&self) -> &AllowanceTable<Self::AccountId> {
+        &self.approvals
+    }
+
+    fn approvals_mut(&mut self) -> &mut AllowanceTable<Self::AccountId> {
+        &mut self.approvals
+    }
+}
+
+impl LedgerData for Ledger {
     type Runtime = DfnRuntime;
     type ArchiveWasm = IcpLedgerArchiveWasm;
     type Transaction = Transaction;
@@ -369,14 +394,6 @@ impl LedgerData for Ledger {
         &self.token_symbol
     }
 
-    fn balances(&self) -> &Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>> {
-        &self.balances
-    }
-
-    fn balances_mut(&mut self) -> &mut Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>> {
-        &mut self.balances
-    }
-
     fn blockchain(&self) -> &Blockchain<Self::Runtime, Self::ArchiveWasm> {
         &self.blockchain
     }
@@ -423,6 +440,7 @@ impl Default for Ledger {
             transfer_fee: DEFAULT_TRANSFER_FEE,
             token_symbol: unknown_token(),
             token_name: unknown_token(),
+            approvals: AllowanceTable::default(),
         }
     }
 }
@@ -487,6 +505,11 @@ impl Ledger {
                     )
                     .to_string(),
                 ),
+                CTE::InsufficientAllowance { .. }
+                | CTE::ExpiredApproval { .. }
+                | CTE::AllowanceChanged { .. } => {
+                    unreachable!("the ICP ledger operations do not use allowances")
+                }
             }
         })
     }
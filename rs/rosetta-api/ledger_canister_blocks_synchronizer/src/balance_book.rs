@@ -1,13 +1,16 @@
+use ic_icrc1::Account;
 use ic_ledger_core::{
     balances::{Balances, BalancesStore},
     block::BlockIndex,
 };
-use ledger_canister::{AccountIdentifier, Tokens};
+use ledger_canister::Tokens;
 use std::collections::HashMap;
+use std::hash::Hash;
 
 use crate::errors::Error;
 
-pub type BalanceBook = Balances<AccountIdentifier, ClientBalancesStore>;
+/// The balance book of an ICRC-1 ledger.
+pub type Icrc1BalanceBook = Balances<Account, ClientBalancesStore<Account>>;
 
 const EMPTY_HISTORY: [(BlockIndex, Tokens); 0] = [];
 
@@ -140,21 +143,30 @@ impl BalanceHistory {
     }
 }
 
-#[derive(Default, Clone, Debug, PartialEq, Eq)]
-pub struct ClientBalancesStore {
-    pub acc_to_hist: HashMap<AccountIdentifier, BalanceHistory>,
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientBalancesStore<AccountId> {
+    pub acc_to_hist: HashMap<AccountId, BalanceHistory>,
     pub transaction_context: Option<BlockIndex>,
 }
 
-impl ClientBalancesStore {
-    pub fn insert(&mut self, acc: AccountIdentifier, height: BlockIndex, amount: Tokens) {
+impl<AccountId> Default for ClientBalancesStore<AccountId> {
+    fn default() -> Self {
+        Self {
+            acc_to_hist: HashMap::default(),
+            transaction_context: None,
+        }
+    }
+}
+
+impl<AccountId: Eq + Hash> ClientBalancesStore<AccountId> {
+    pub fn insert(&mut self, acc: AccountId, height: BlockIndex, amount: Tokens) {
         self.acc_to_hist
             .entry(acc)
             .or_default()
             .insert(height, amount);
     }
 
-    pub fn get_at(&self, acc: AccountIdentifier, height: BlockIndex) -> Result<Tokens, Error> {
+    pub fn get_at(&self, acc: AccountId, height: BlockIndex) -> Result<Tokens, Error> {
         self.acc_to_hist
             .get(&acc)
             .map(|hist| hist.get_at(height))
@@ -169,7 +181,7 @@ impl ClientBalancesStore {
 
     pub fn get_history(
         &self,
-        acc: &AccountIdentifier,
+        acc: &AccountId,
         max_block: Option<BlockIndex>,
     ) -> &[(BlockIndex, Tokens)] {
         self.acc_to_hist
@@ -179,15 +191,15 @@ impl ClientBalancesStore {
     }
 }
 
-impl BalancesStore<AccountIdentifier> for ClientBalancesStore {
-    fn get_balance(&self, k: &AccountIdentifier) -> Option<&Tokens> {
+impl<AccountId: Eq + Hash> BalancesStore<AccountId> for ClientBalancesStore<AccountId> {
+    fn get_balance(&self, k: &AccountId) -> Option<&Tokens> {
         self.acc_to_hist.get(k).and_then(|hist| hist.get_last_ref())
     }
 
     // In here, ledger removes zero amount accounts from it's map,
     // but we can't do that or we may risk giving incorrect
     // historical balance information
-    fn update<F, E>(&mut self, k: AccountIdentifier, mut f: F) -> Result<Tokens, E>
+    fn update<F, E>(&mut self, k: AccountId, mut f: F) -> Result<Tokens, E>
     where
         F: FnMut(Option<&Tokens>) -> Result<Tokens, E>,
     {
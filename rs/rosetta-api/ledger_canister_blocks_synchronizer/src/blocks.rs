@@ -1,14 +1,12 @@
-use crate::balance_book::BalanceBook;
 use crate::errors::Error;
 use crate::store::{BlockStoreError, HashedBlock, SQLiteStore};
 use ic_ledger_canister_core::ledger::LedgerTransaction;
 use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock, HashOf};
-use ledger_canister::{apply_operation, AccountIdentifier, Block, Tokens, Transaction};
+use ledger_canister::{AccountIdentifier, Block, Tokens, Transaction};
 use log::{error, info};
 use std::collections::HashMap;
 
 pub struct Blocks {
-    pub balance_book: BalanceBook,
     hash_location: HashMap<HashOf<EncodedBlock>, BlockIndex>,
     pub tx_hash_location: HashMap<HashOf<Transaction>, BlockIndex>,
     pub block_store: SQLiteStore,
@@ -22,7 +20,6 @@ impl Blocks {
         let block_store = SQLiteStore::new_on_disk(store_location)
             .expect("Failed to initialize sql store for ledger");
         Self {
-            balance_book: BalanceBook::default(),
             hash_location: HashMap::default(),
             tx_hash_location: HashMap::default(),
             block_store,
@@ -34,7 +31,6 @@ impl Blocks {
         let block_store =
             SQLiteStore::new_in_memory().expect("Failed to initialize sql store for ledger");
         Self {
-            balance_book: BalanceBook::default(),
             hash_location: HashMap::default(),
             tx_hash_location: HashMap::default(),
             block_store,
@@ -44,10 +40,6 @@ impl Blocks {
 
     pub fn load_from_store(&mut self) -> Result<u64, Error> {
         assert!(self.last()?.is_none(), "Blocks is not empty");
-        assert!(
-            self.balance_book.store.acc_to_hist.is_empty(),
-            "Blocks is not empty"
-        );
         assert!(self.hash_location.is_empty(), "Blocks is not empty");
         assert!(self.tx_hash_location.is_empty(), "Blocks is not empty");
 
@@ -57,9 +49,9 @@ impl Blocks {
             return Ok(0);
         }
 
-        if let Some((first, balances_snapshot)) = self.block_store.first_snapshot() {
-            self.balance_book = balances_snapshot;
-
+        // The balances are kept by the store, only the first block after
+        // the pruned ones needs to be loaded here.
+        if let Some(first) = self.block_store.first()?.filter(|first| first.index > 0) {
             self.hash_location.insert(first.hash, first.index);
 
             let tx = Block::decode(first.block).unwrap().transaction;
@@ -133,7 +125,7 @@ impl Blocks {
                 h
             )))
         } else {
-            self.balance_book.store.get_at(*acc, h)
+            Ok(self.block_store.get_account_balance(acc, h)?)
         }
     }
 
@@ -191,11 +183,6 @@ impl Blocks {
             None => assert_eq!(0, index),
         }
 
-        let mut bb = &mut self.balance_book;
-        bb.store.transaction_context = Some(index);
-        apply_operation(bb, &block.transaction.operation).unwrap();
-        bb.store.transaction_context = None;
-
         self.hash_location.insert(hash, index);
 
         let tx = block.transaction;
@@ -268,10 +255,7 @@ impl Blocks {
                 }
 
                 let hb = self.block_store.get_at(new_first_idx)?;
-                self.balance_book.store.prune_at(hb.index);
-                self.block_store
-                    .prune(&hb, &self.balance_book)
-                    .map_err(Error::InternalError)?;
+                self.block_store.prune(&hb).map_err(Error::InternalError)?;
             }
         }
         Ok(())
@@ -2,10 +2,12 @@ use std::ops::Range;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use ic_icrc1::endpoints::Transaction;
 use ic_ledger_core::block::{BlockIndex, EncodedBlock};
 use ledger_canister::TipOfChainRes;
 
 use crate::canister_access::CanisterAccess;
+use crate::icrc1_canister_access::Icrc1CanisterAccess;
 
 // trait to test sync
 #[async_trait]
@@ -35,3 +37,27 @@ impl BlocksAccess for CanisterAccess {
         self.multi_query_blocks(range.start, range.end).await
     }
 }
+
+// trait to test the sync of ICRC-1 ledgers
+#[async_trait]
+pub trait Icrc1TransactionsAccess {
+    async fn query_log_length(&self) -> Result<u64, String>;
+    async fn query_transactions(
+        &self,
+        range: Range<BlockIndex>,
+    ) -> Result<Vec<Transaction>, String>;
+}
+
+#[async_trait]
+impl Icrc1TransactionsAccess for Icrc1CanisterAccess {
+    async fn query_log_length(&self) -> Result<u64, String> {
+        self.query_log_length().await
+    }
+
+    async fn query_transactions(
+        &self,
+        range: Range<BlockIndex>,
+    ) -> Result<Vec<Transaction>, String> {
+        self.query_transactions(range.start, range.end).await
+    }
+}
@@ -0,0 +1,189 @@
+use crate::balance_book::Icrc1BalanceBook;
+use crate::errors::Error;
+use crate::store::{BlockStoreError, HashedBlock, SQLiteStore};
+use ic_icrc1::{Account, Block, Operation, Transaction};
+use ic_ledger_canister_core::ledger::LedgerTransaction;
+use ic_ledger_core::balances::BalanceError;
+use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock, HashOf};
+use ic_ledger_core::tokens::Tokens;
+use log::{error, info};
+use std::collections::HashMap;
+
+/// Applies the effect of an ICRC-1 operation on the balances.
+///
+/// Allowances are not tracked: they do not affect the balances, and the
+/// ledger already checked them before accepting the transaction.
+fn apply_operation(
+    balances: &mut Icrc1BalanceBook,
+    operation: &Operation,
+) -> Result<(), BalanceError> {
+    match operation {
+        Operation::Mint { to, amount } => balances.mint(to, Tokens::from_e8s(*amount)),
+        Operation::Burn { from, amount } => balances.burn(from, Tokens::from_e8s(*amount)),
+        Operation::Transfer {
+            from,
+            to,
+            amount,
+            fee,
+            ..
+        } => balances.transfer(from, to, Tokens::from_e8s(*amount), Tokens::from_e8s(*fee)),
+        Operation::Approve { from, fee, .. } => balances.burn(from, Tokens::from_e8s(*fee)),
+    }
+}
+
+/// The local copy of the blocks of an ICRC-1 ledger.
+///
+/// Unlike [crate::blocks::Blocks], this store does not support pruning.
+pub struct Icrc1Blocks {
+    pub balance_book: Icrc1BalanceBook,
+    hash_location: HashMap<HashOf<EncodedBlock>, BlockIndex>,
+    pub tx_hash_location: HashMap<HashOf<Transaction>, BlockIndex>,
+    pub block_store: SQLiteStore,
+    last_hash: Option<HashOf<EncodedBlock>>,
+}
+
+impl Icrc1Blocks {
+    const LOAD_FROM_STORE_BLOCK_BATCH_LEN: u64 = 10000;
+
+    pub fn new_persistent(store_location: &std::path::Path) -> Self {
+        let block_store = SQLiteStore::new_on_disk_without_account_index(store_location)
+            .expect("Failed to initialize sql store for ledger");
+        Self::new(block_store)
+    }
+
+    pub fn new_in_memory() -> Self {
+        let block_store = SQLiteStore::new_in_memory_without_account_index()
+            .expect("Failed to initialize sql store for ledger");
+        Self::new(block_store)
+    }
+
+    fn new(block_store: SQLiteStore) -> Self {
+        Self {
+            balance_book: Icrc1BalanceBook::default(),
+            hash_location: HashMap::default(),
+            tx_hash_location: HashMap::default(),
+            block_store,
+            last_hash: None,
+        }
+    }
+
+    pub fn load_from_store(&mut self) -> Result<u64, Error> {
+        assert!(self.last()?.is_none(), "Blocks is not empty");
+
+        let mut n = 0;
+        let mut next_idx = 0;
+        loop {
+            let batch = self
+                .block_store
+                .get_range(next_idx..next_idx + Self::LOAD_FROM_STORE_BLOCK_BATCH_LEN)?;
+            if batch.is_empty() {
+                break;
+            }
+            for hb in batch {
+                self.process_block(hb).map_err(|e| {
+                    error!(
+                        "Processing block retrieved from store failed. Block idx: {}, error: {:?}",
+                        next_idx, e
+                    );
+                    e
+                })?;
+
+                next_idx += 1;
+                n += 1;
+                if n % 30000 == 0 {
+                    info!("Loading... {} blocks processed", n);
+                }
+            }
+        }
+
+        Ok(n)
+    }
+
+    fn get_at(&self, index: BlockIndex) -> Result<HashedBlock, Error> {
+        Ok(self.block_store.get_at(index)?)
+    }
+
+    /// Returns the block at `index` if it has been synced.
+    ///
+    /// ICRC-1 ledgers do not expose a certificate for their tip, so synced
+    /// blocks are never marked as verified.
+    pub fn get_synced_at(&self, index: BlockIndex) -> Result<HashedBlock, Error> {
+        match self.synced_to() {
+            Some((_, last_idx)) if index <= last_idx => self.get_at(index),
+            _ => Err(BlockStoreError::NotFound(index).into()),
+        }
+    }
+
+    pub fn get_synced(&self, hash: HashOf<EncodedBlock>) -> Result<HashedBlock, Error> {
+        let index = *self
+            .hash_location
+            .get(&hash)
+            .ok_or_else(|| Error::InvalidBlockId(format!("Block not found {}", hash)))?;
+        self.get_synced_at(index)
+    }
+
+    pub fn get_balance(&self, acc: &Account, h: BlockIndex) -> Result<Tokens, Error> {
+        match self.synced_to() {
+            Some((_, last_idx)) if h <= last_idx => self.balance_book.store.get_at(acc.clone(), h),
+            _ => Err(Error::InvalidBlockId(format!(
+                "Block not found at height: {}",
+                h
+            ))),
+        }
+    }
+
+    pub fn add_blocks_batch(&mut self, batch: Vec<HashedBlock>) -> Result<(), Error> {
+        self.block_store.push_batch(batch.clone())?;
+        for hb in batch {
+            self.process_block(hb)?;
+        }
+        Ok(())
+    }
+
+    fn process_block(&mut self, hb: HashedBlock) -> Result<(), Error> {
+        let HashedBlock {
+            block,
+            hash,
+            parent_hash,
+            index,
+        } = hb;
+        let expected_index = self.synced_to().map(|(_, i)| i + 1).unwrap_or(0);
+        assert_eq!(
+            parent_hash, self.last_hash,
+            "When adding a block the parent_hash must match the last added block"
+        );
+        assert_eq!(expected_index, index);
+
+        let block = Block::decode(block)
+            .map_err(|e| Error::InternalError(format!("Cannot decode block {}: {}", index, e)))?;
+
+        let bb = &mut self.balance_book;
+        bb.store.transaction_context = Some(index);
+        let result = apply_operation(bb, &block.transaction.operation);
+        bb.store.transaction_context = None;
+        result
+            .map_err(|e| Error::InternalError(format!("Cannot apply block {}: {:?}", index, e)))?;
+
+        self.hash_location.insert(hash, index);
+        self.tx_hash_location
+            .insert(block.transaction.hash(), index);
+        self.last_hash = Some(hash);
+
+        Ok(())
+    }
+
+    pub fn first(&self) -> Result<Option<HashedBlock>, Error> {
+        Ok(self.block_store.first()?)
+    }
+
+    pub fn last(&self) -> Result<Option<HashedBlock>, Error> {
+        match self.synced_to() {
+            Some((_, index)) => Ok(Some(self.get_at(index)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn synced_to(&self) -> Option<(HashOf<EncodedBlock>, u64)> {
+        self.last_hash.map(|hash| (hash, self.hash_location[&hash]))
+    }
+}
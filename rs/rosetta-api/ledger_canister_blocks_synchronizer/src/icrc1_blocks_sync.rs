@@ -0,0 +1,535 @@
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Arc;
+
+use core::ops::Deref;
+
+use ic_icrc1::Block;
+use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock, HashOf};
+use log::{debug, error, info, trace, warn};
+use tokio::sync::RwLock;
+
+use crate::blocks_access::Icrc1TransactionsAccess;
+use crate::errors::Error;
+use crate::icrc1_blocks::Icrc1Blocks;
+use crate::ledger_blocks_sync::LedgerBlocksSynchronizerMetrics;
+use crate::store::{BlockStoreError, HashedBlock};
+
+const PRINT_SYNC_PROGRESS_THRESHOLD: u64 = 1000;
+
+// Max number of retry in case of query failure while retrieving transactions.
+const MAX_RETRY: u8 = 5;
+
+/// Rebuilds the block at `index` from a transaction returned by the ledger.
+///
+/// ICRC-1 ledgers serve transactions rather than encoded blocks, so the
+/// synchronizer reconstructs the hash chain locally.
+fn hash_transaction(
+    tx: ic_icrc1::endpoints::Transaction,
+    parent_hash: Option<HashOf<EncodedBlock>>,
+    index: BlockIndex,
+) -> Result<HashedBlock, Error> {
+    let timestamp = tx.timestamp;
+    let transaction = ic_icrc1::Transaction::try_from(tx).map_err(|e| {
+        Error::InternalError(format!("Cannot convert transaction {}: {}", index, e))
+    })?;
+    let block = Block {
+        parent_hash,
+        transaction,
+        timestamp,
+    }
+    .encode();
+    Ok(HashedBlock {
+        hash: Block::block_hash(&block),
+        block,
+        parent_hash,
+        index,
+    })
+}
+
+/// Downloads the transactions of an ICRC-1 ledger to either an in-memory
+/// store or to a local sqlite store.
+///
+/// ICRC-1 ledgers do not expose a certificate for their tip, so the
+/// downloaded blocks cannot be verified and are only ever marked as synced.
+/// Clients must trust the replica that served the transactions.
+pub struct Icrc1BlocksSynchronizer<A>
+where
+    A: Icrc1TransactionsAccess,
+{
+    pub blockchain: RwLock<Icrc1Blocks>,
+    transactions_access: Option<Arc<A>>,
+    metrics: Box<dyn LedgerBlocksSynchronizerMetrics + Send + Sync>,
+}
+
+impl<A: Icrc1TransactionsAccess> Icrc1BlocksSynchronizer<A> {
+    pub async fn new(
+        transactions_access: Option<Arc<A>>,
+        store_location: Option<&std::path::Path>,
+        metrics: Box<dyn LedgerBlocksSynchronizerMetrics + Send + Sync>,
+    ) -> Result<Icrc1BlocksSynchronizer<A>, Error> {
+        let mut blocks = match store_location {
+            Some(loc) => Icrc1Blocks::new_persistent(loc),
+            None => Icrc1Blocks::new_in_memory(),
+        };
+
+        if let Some(transactions_access) = &transactions_access {
+            Self::verify_store(&blocks, transactions_access).await?;
+        }
+
+        info!("Loading blocks from store");
+        let num_loaded = blocks.load_from_store()?;
+
+        info!(
+            "Ledger client is up. Loaded {} blocks from store. Last block at {}",
+            num_loaded,
+            blocks
+                .last()?
+                .map(|x| format!("{}", x.index))
+                .unwrap_or_else(|| "None".to_string())
+        );
+        if let Some(x) = blocks.last()? {
+            metrics.set_synced_height(x.index);
+        }
+
+        Ok(Self {
+            blockchain: RwLock::new(blocks),
+            transactions_access,
+            metrics,
+        })
+    }
+
+    async fn verify_store(blocks: &Icrc1Blocks, canister_access: &A) -> Result<(), Error> {
+        debug!("Verifying store...");
+        match blocks.block_store.get_at(0) {
+            Ok(store_genesis) => {
+                let genesis = canister_access
+                    .query_transactions(0..1)
+                    .await
+                    .map_err(Error::InternalError)?
+                    .pop()
+                    .expect("Blockchain in the ledger canister is empty");
+                let genesis = hash_transaction(genesis, None, 0)?;
+
+                if store_genesis.hash != genesis.hash {
+                    let msg = format!(
+                        "Genesis block from the store is different than \
+                        in the ledger canister. Store hash: {}, canister hash: {}",
+                        store_genesis.hash, genesis.hash
+                    );
+                    error!("{}", msg);
+                    return Err(Error::InternalError(msg));
+                }
+            }
+            Err(BlockStoreError::NotFound(0)) => {}
+            Err(e) => {
+                let msg = format!("Error loading genesis block: {:?}", e);
+                error!("{}", msg);
+                return Err(Error::InternalError(msg));
+            }
+        }
+        debug!("Verifying store done");
+        Ok(())
+    }
+
+    pub async fn read_blocks(&self) -> Box<dyn Deref<Target = Icrc1Blocks> + '_> {
+        Box::new(self.blockchain.read().await)
+    }
+
+    pub async fn sync_blocks(
+        &self,
+        stopped: Arc<AtomicBool>,
+        up_to_block_included: Option<BlockIndex>,
+    ) -> Result<(), Error> {
+        let canister = self.transactions_access.as_ref().unwrap();
+        let log_length = canister
+            .query_log_length()
+            .await
+            .map_err(Error::InternalError)?;
+        if log_length == 0 {
+            return Ok(());
+        }
+        let tip_index = log_length - 1;
+        self.metrics.set_target_height(tip_index);
+
+        let mut blockchain = self.blockchain.write().await;
+
+        let (last_block_hash, next_block_index) = match blockchain.synced_to() {
+            Some((hash, index)) => (Some(hash), index + 1),
+            None => (None, 0),
+        };
+
+        if next_block_index > tip_index {
+            trace!(
+                "Nothing to sync. Ledger tip index: {}, local copy tip index+1: {}",
+                tip_index,
+                next_block_index
+            );
+            return Ok(());
+        }
+
+        let up_to_block_included = tip_index.min(up_to_block_included.unwrap_or(u64::MAX - 1));
+        if next_block_index > up_to_block_included {
+            return Ok(()); // nothing to do nor report, local copy has enough blocks
+        }
+
+        trace!(
+            "Sync {} blocks from index: {}, ledger tip index: {}",
+            up_to_block_included + 1 - next_block_index,
+            next_block_index,
+            tip_index
+        );
+
+        self.sync_range_of_blocks(
+            Range {
+                start: next_block_index,
+                end: up_to_block_included + 1,
+            },
+            last_block_hash,
+            stopped,
+            &mut *blockchain,
+        )
+        .await?;
+
+        info!(
+            "You are all caught up to block {}",
+            blockchain.last()?.unwrap().index
+        );
+        Ok(())
+    }
+
+    async fn sync_range_of_blocks(
+        &self,
+        range: Range<BlockIndex>,
+        first_block_parent_hash: Option<HashOf<EncodedBlock>>,
+        stopped: Arc<AtomicBool>,
+        blockchain: &mut Icrc1Blocks,
+    ) -> Result<(), Error> {
+        if range.is_empty() {
+            return Ok(());
+        }
+        let print_progress = if range.end - range.start >= PRINT_SYNC_PROGRESS_THRESHOLD {
+            info!(
+                "Syncing {} blocks. New tip will be {}",
+                range.end - range.start,
+                range.end - 1,
+            );
+            true
+        } else {
+            false
+        };
+
+        let canister = self.transactions_access.as_ref().unwrap();
+        let mut i = range.start;
+        let mut last_block_hash = first_block_parent_hash;
+        while i < range.end {
+            if stopped.load(Relaxed) {
+                return Err(Error::InternalError("Interrupted".to_string()));
+            }
+
+            debug!("Asking for transactions [{},{})", i, range.end);
+            let mut retry = 0;
+            let batch = loop {
+                let batch = canister
+                    .query_transactions(i..range.end)
+                    .await
+                    .map_err(Error::InternalError);
+                if batch.is_ok() || retry == MAX_RETRY {
+                    break batch;
+                }
+                retry += 1;
+                warn!(
+                    "Failed query while retrieving transactions, retry {}/{} (error: {:?})",
+                    retry,
+                    MAX_RETRY,
+                    batch.unwrap_err()
+                );
+            }?;
+
+            debug!("Got batch of len: {}", batch.len());
+            if batch.is_empty() {
+                return Err(Error::InternalError(format!(
+                    "Couldn't fetch transactions [{},{}) (batch result empty)",
+                    i, range.end
+                )));
+            }
+
+            let mut hashed_batch = Vec::with_capacity(batch.len());
+            for tx in batch.into_iter().take((range.end - i) as usize) {
+                let hb = hash_transaction(tx, last_block_hash, i)?;
+                last_block_hash = Some(hb.hash);
+                hashed_batch.push(hb);
+                i += 1;
+            }
+
+            blockchain.add_blocks_batch(hashed_batch)?;
+            self.metrics.set_synced_height(i - 1);
+
+            if print_progress && (i - range.start) % 10000 == 0 {
+                info!("Synced up to {}", i - 1);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::Range;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use candid::Nat;
+    use ic_icrc1::endpoints::{
+        ArchivedTransactionRange, GetTransactionsResponse, QueryArchiveFn, Transaction,
+        TransactionRange,
+    };
+    use ic_icrc1::{Account, Block};
+    use ic_ledger_core::block::{BlockIndex, BlockType};
+    use ic_ledger_core::Tokens;
+    use ic_types::{CanisterId, PrincipalId};
+    use num_traits::cast::ToPrimitive;
+
+    use crate::blocks_access::Icrc1TransactionsAccess;
+    use crate::icrc1_blocks_sync::Icrc1BlocksSynchronizer;
+    use crate::icrc1_canister_access::collect_transactions;
+    use crate::ledger_blocks_sync::NopMetrics;
+
+    struct RangeOfTransactions {
+        pub transactions: Vec<Transaction>,
+        pub max_batch_len: usize,
+    }
+
+    #[async_trait]
+    impl Icrc1TransactionsAccess for RangeOfTransactions {
+        async fn query_log_length(&self) -> Result<u64, String> {
+            Ok(self.transactions.len() as u64)
+        }
+
+        async fn query_transactions(
+            &self,
+            range: Range<BlockIndex>,
+        ) -> Result<Vec<Transaction>, String> {
+            let end = (range.end as usize).min(range.start as usize + self.max_batch_len);
+            Ok(self.transactions[range.start as usize..end].to_vec())
+        }
+    }
+
+    /// A ledger whose first `archived_len` transactions are in an archive
+    /// that returns at most `max_archive_batch_len` transactions per call.
+    struct LedgerWithArchive {
+        pub transactions: Vec<Transaction>,
+        pub archived_len: usize,
+        pub max_archive_batch_len: usize,
+    }
+
+    #[async_trait]
+    impl Icrc1TransactionsAccess for LedgerWithArchive {
+        async fn query_log_length(&self) -> Result<u64, String> {
+            Ok(self.transactions.len() as u64)
+        }
+
+        async fn query_transactions(
+            &self,
+            range: Range<BlockIndex>,
+        ) -> Result<Vec<Transaction>, String> {
+            let start = range.start as usize;
+            let end = range.end as usize;
+            let archived_end = end.min(self.archived_len);
+            let archived_transactions = if start < archived_end {
+                vec![ArchivedTransactionRange {
+                    start: Nat::from(range.start),
+                    length: Nat::from((archived_end - start) as u64),
+                    callback: QueryArchiveFn {
+                        canister_id: CanisterId::from_u64(2),
+                        method: "get_transactions".to_string(),
+                    },
+                }]
+            } else {
+                vec![]
+            };
+            let first_index = self.archived_len;
+            let response = GetTransactionsResponse {
+                log_length: Nat::from(self.transactions.len() as u64),
+                first_index: Nat::from(first_index as u64),
+                transactions: self.transactions[first_index..end.max(first_index)].to_vec(),
+                archived_transactions,
+            };
+            collect_transactions(range.start, response, |archived| async move {
+                let start = archived.start.0.to_usize().unwrap();
+                let length = archived.length.0.to_usize().unwrap();
+                let end = start + length.min(self.max_archive_batch_len);
+                Ok(TransactionRange {
+                    transactions: self.transactions[start..end].to_vec(),
+                })
+            })
+            .await
+        }
+    }
+
+    async fn new_icrc1_blocks_synchronizer(
+        transactions: Vec<Transaction>,
+        max_batch_len: usize,
+    ) -> Icrc1BlocksSynchronizer<RangeOfTransactions> {
+        Icrc1BlocksSynchronizer::new(
+            Some(Arc::new(RangeOfTransactions {
+                transactions,
+                max_batch_len,
+            })),
+            /* store_location = */ None,
+            Box::new(NopMetrics {}),
+        )
+        .await
+        .unwrap()
+    }
+
+    fn account(n: u64) -> Account {
+        Account::from(PrincipalId::new_user_test_id(n))
+    }
+
+    fn dummy_transactions(n: u64) -> Vec<Transaction> {
+        let timestamp = 1656347498000000000; /* 27 June 2022 18:31:38 GMT+02:00 DST */
+        (0..n)
+            .map(|i| {
+                let transaction = if i == 0 {
+                    ic_icrc1::Transaction::mint(
+                        account(1),
+                        Tokens::from_e8s(100_000_000),
+                        None,
+                        None,
+                    )
+                } else {
+                    ic_icrc1::Transaction::transfer(
+                        account(1),
+                        account(2),
+                        Tokens::from_e8s(100_000),
+                        Tokens::from_e8s(10_000),
+                        None,
+                        None,
+                    )
+                };
+                Transaction::from(Block {
+                    parent_hash: None,
+                    transaction,
+                    timestamp: timestamp + i,
+                })
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn sync_empty_ledger() {
+        let blocks_sync = new_icrc1_blocks_synchronizer(vec![], 10).await;
+        blocks_sync
+            .sync_blocks(Arc::new(AtomicBool::new(false)), None)
+            .await
+            .unwrap();
+        assert_eq!(None, blocks_sync.read_blocks().await.last().unwrap());
+    }
+
+    #[tokio::test]
+    async fn sync_all_blocks_in_batches() {
+        let transactions = dummy_transactions(5);
+        let blocks_sync = new_icrc1_blocks_synchronizer(transactions.clone(), 2).await;
+        blocks_sync
+            .sync_blocks(Arc::new(AtomicBool::new(false)), None)
+            .await
+            .unwrap();
+
+        let blocks = blocks_sync.read_blocks().await;
+        assert_eq!(blocks.block_store.last_verified(), None);
+        assert!(blocks.get_synced_at(5).is_err());
+        let mut parent_hash = None;
+        for (i, tx) in transactions.into_iter().enumerate() {
+            let hb = blocks.get_synced_at(i as u64).unwrap();
+            assert_eq!(hb.parent_hash, parent_hash);
+            assert_eq!(blocks.get_synced(hb.hash).unwrap(), hb);
+            let block = Block::decode(hb.block).unwrap();
+            assert_eq!(Transaction::from(block.clone()), tx);
+            assert_eq!(block.parent_hash, parent_hash);
+            parent_hash = Some(hb.hash);
+        }
+        assert_eq!(
+            blocks.get_balance(&account(1), 4).unwrap(),
+            Tokens::from_e8s(100_000_000 - 4 * 110_000)
+        );
+        assert_eq!(
+            blocks.get_balance(&account(2), 4).unwrap(),
+            Tokens::from_e8s(4 * 100_000)
+        );
+        assert_eq!(
+            blocks.get_balance(&account(2), 1).unwrap(),
+            Tokens::from_e8s(100_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn sync_blocks_in_2_steps() {
+        let transactions = dummy_transactions(3);
+        let blocks_sync = new_icrc1_blocks_synchronizer(transactions, 10).await;
+
+        blocks_sync
+            .sync_blocks(Arc::new(AtomicBool::new(false)), Some(0))
+            .await
+            .unwrap();
+        {
+            let blocks = blocks_sync.read_blocks().await;
+            assert!(blocks.get_synced_at(1).is_err());
+            assert!(blocks.get_balance(&account(1), 1).is_err());
+            assert_eq!(
+                blocks.get_balance(&account(1), 0).unwrap(),
+                Tokens::from_e8s(100_000_000)
+            );
+        }
+
+        blocks_sync
+            .sync_blocks(Arc::new(AtomicBool::new(false)), None)
+            .await
+            .unwrap();
+        {
+            let blocks = blocks_sync.read_blocks().await;
+            assert!(blocks.get_synced_at(3).is_err());
+            let first = blocks.get_synced_at(0).unwrap();
+            assert_eq!(
+                blocks.get_synced_at(1).unwrap().parent_hash,
+                Some(first.hash)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn sync_blocks_partly_in_archive() {
+        let transactions = dummy_transactions(10);
+        let blocks_sync = Icrc1BlocksSynchronizer::new(
+            Some(Arc::new(LedgerWithArchive {
+                transactions: transactions.clone(),
+                archived_len: 6,
+                max_archive_batch_len: 4,
+            })),
+            /* store_location = */ None,
+            Box::new(NopMetrics {}),
+        )
+        .await
+        .unwrap();
+        blocks_sync
+            .sync_blocks(Arc::new(AtomicBool::new(false)), None)
+            .await
+            .unwrap();
+
+        let blocks = blocks_sync.read_blocks().await;
+        assert!(blocks.get_synced_at(10).is_err());
+        let mut parent_hash = None;
+        for (i, tx) in transactions.into_iter().enumerate() {
+            let hb = blocks.get_synced_at(i as u64).unwrap();
+            assert_eq!(hb.parent_hash, parent_hash);
+            assert_eq!(Transaction::from(Block::decode(hb.block).unwrap()), tx);
+            parent_hash = Some(hb.hash);
+        }
+        assert_eq!(
+            blocks.get_balance(&account(2), 9).unwrap(),
+            Tokens::from_e8s(9 * 100_000)
+        );
+    }
+}
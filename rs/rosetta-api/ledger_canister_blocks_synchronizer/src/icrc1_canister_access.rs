@@ -0,0 +1,155 @@
+use candid::{CandidType, Decode, Encode, Nat};
+use ic_canister_client::{Agent, HttpClient, Sender};
+use ic_icrc1::endpoints::{
+    ArchivedTransactionRange, GetTransactionsRequest, GetTransactionsResponse, Transaction,
+    TransactionRange,
+};
+use ic_ledger_core::block::BlockIndex;
+use ic_types::CanisterId;
+use log::debug;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use url::Url;
+
+/// Reads the transactions of an ICRC-1 ledger and of its archives using the
+/// candid interface of the ledger.
+pub struct Icrc1CanisterAccess {
+    pub agent: Agent,
+    pub canister_id: CanisterId,
+}
+
+fn nat_to_u64(n: Nat, what: &str) -> Result<u64, String> {
+    use num_traits::cast::ToPrimitive;
+    n.0.to_u64()
+        .ok_or_else(|| format!("{} {} does not fit into u64", what, n))
+}
+
+impl Icrc1CanisterAccess {
+    const TRANSACTIONS_BATCH_LEN: u64 = 2000;
+
+    pub fn new(url: Url, canister_id: CanisterId) -> Self {
+        let agent = Agent::new_with_client(HttpClient::new(), url, Sender::Anonymous);
+        Self { agent, canister_id }
+    }
+
+    pub async fn query<Payload: CandidType, Res: CandidType + DeserializeOwned>(
+        &self,
+        method: &str,
+        payload: Payload,
+    ) -> Result<Res, String> {
+        self.query_canister(self.canister_id, method, payload).await
+    }
+
+    pub async fn query_canister<Payload: CandidType, Res: CandidType + DeserializeOwned>(
+        &self,
+        canister_id: CanisterId,
+        method: &str,
+        payload: Payload,
+    ) -> Result<Res, String> {
+        let arg = Encode!(&payload)
+            .map_err(|e| format!("Failed to encode the argument of {}: {}", method, e))?;
+        let bytes = self
+            .agent
+            .execute_query(&canister_id, method, arg)
+            .await?
+            .ok_or_else(|| "Reply payload was empty".to_string())?;
+        Decode!(&bytes, Res).map_err(|e| format!("Failed to decode the reply of {}: {}", method, e))
+    }
+
+    async fn get_transactions(
+        &self,
+        start: BlockIndex,
+        length: u64,
+    ) -> Result<GetTransactionsResponse, String> {
+        self.query(
+            "get_transactions",
+            GetTransactionsRequest {
+                start: Nat::from(start),
+                length: Nat::from(length),
+            },
+        )
+        .await
+        .map_err(|e| format!("In get_transactions: {}", e))
+    }
+
+    /// Returns the total number of transactions in the ledger, including the
+    /// archived ones.
+    pub async fn query_log_length(&self) -> Result<u64, String> {
+        let response = self.get_transactions(0, 0).await?;
+        nat_to_u64(response.log_length, "log length")
+    }
+
+    /// Returns a prefix of the transactions in the range [start, end).
+    ///
+    /// The result can be shorter than the requested range if the ledger or
+    /// one of its archives caps the size of its replies. The caller is
+    /// expected to ask again for the remaining transactions.
+    pub async fn query_transactions(
+        &self,
+        start: BlockIndex,
+        end: BlockIndex,
+    ) -> Result<Vec<Transaction>, String> {
+        if start >= end {
+            return Ok(vec![]);
+        }
+        let length = (end - start).min(Self::TRANSACTIONS_BATCH_LEN);
+        let response = self.get_transactions(start, length).await?;
+        collect_transactions(start, response, |archived| async move {
+            let canister_id = archived.callback.canister_id;
+            self.query_canister(
+                canister_id,
+                &archived.callback.method,
+                GetTransactionsRequest {
+                    start: archived.start,
+                    length: archived.length,
+                },
+            )
+            .await
+            .map_err(|e| format!("In archive {}: {}", canister_id, e))
+        })
+        .await
+    }
+}
+
+/// Collects the transactions starting at `start` from a `get_transactions`
+/// response, fetching the archived ones with `query_archive`.
+///
+/// The result stops at the first gap, e.g. when an archive returns fewer
+/// transactions than it was asked for.
+pub(crate) async fn collect_transactions<F, Fut>(
+    start: BlockIndex,
+    response: GetTransactionsResponse,
+    query_archive: F,
+) -> Result<Vec<Transaction>, String>
+where
+    F: Fn(ArchivedTransactionRange) -> Fut,
+    Fut: Future<Output = Result<TransactionRange, String>>,
+{
+    let mut transactions = vec![];
+    let mut next = start;
+    for archived in response.archived_transactions {
+        let archived_start = nat_to_u64(archived.start.clone(), "archived range start")?;
+        let archived_length = nat_to_u64(archived.length.clone(), "archived range length")?;
+        if archived_start != next {
+            debug!(
+                "Archived range starts at {}, expected {}; stopping",
+                archived_start, next
+            );
+            return Ok(transactions);
+        }
+        let TransactionRange {
+            transactions: archived_transactions,
+        } = query_archive(archived).await?;
+        let received = archived_transactions.len() as u64;
+        transactions.extend(archived_transactions);
+        next += received;
+        if received < archived_length {
+            return Ok(transactions);
+        }
+    }
+
+    if nat_to_u64(response.first_index, "first index")? == next {
+        transactions.extend(response.transactions);
+    }
+    Ok(transactions)
+}
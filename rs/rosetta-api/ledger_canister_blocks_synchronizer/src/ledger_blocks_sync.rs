@@ -32,7 +32,7 @@ pub trait LedgerBlocksSynchronizerMetrics {
     fn set_verified_height(&self, height: u64);
 }
 
-struct NopMetrics {}
+pub(crate) struct NopMetrics {}
 
 impl LedgerBlocksSynchronizerMetrics for NopMetrics {
     fn set_target_height(&self, _height: u64) {}
@@ -4,5 +4,8 @@ pub mod blocks_access;
 pub mod canister_access;
 pub mod certification;
 pub mod errors;
+pub mod icrc1_blocks;
+pub mod icrc1_blocks_sync;
+pub mod icrc1_canister_access;
 pub mod ledger_blocks_sync;
 pub mod store;
@@ -1,14 +1,21 @@
-use log::debug;
-use std::convert::TryInto;
+use log::{debug, info};
+use std::convert::{TryFrom, TryInto};
+use std::ops::Range;
 use std::path::Path;
 use std::sync::Mutex;
 
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
-use crate::balance_book::BalanceBook;
 use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock, HashOf};
-use ledger_canister::{AccountIdentifier, Block, Tokens};
+use ledger_canister::{AccountIdentifier, Block, Operation, Tokens};
+
+/// The version of the database schema, stored in the SQLite `user_version`.
+/// Version 1 introduced the `account_balances` table.
+const SCHEMA_VERSION: u32 = 1;
+
+/// The number of blocks read at once when rebuilding the account balances.
+const MIGRATION_BLOCK_BATCH_LEN: u64 = 10000;
 
 #[derive(candid::CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct HashedBlock {
@@ -53,32 +60,56 @@ pub struct SQLiteStore {
     base_idx: u64,
     first_block: Option<HashedBlock>,
     last_verified_idx: Option<BlockIndex>,
+    /// Whether the balances of the accounts are maintained in the
+    /// `account_balances` table. This requires the blocks to be ICP blocks.
+    index_accounts: bool,
 }
 
 impl SQLiteStore {
     /// Constructs a new SQLite on-disk store.
     pub fn new_on_disk(location: &Path) -> Result<Self, BlockStoreError> {
+        Self::new(Self::open_on_disk(location), true)
+    }
+
+    /// Constructs a new SQLite in-memory store.
+    pub fn new_in_memory() -> Result<Self, BlockStoreError> {
+        Self::new(Self::open_in_memory(), true)
+    }
+
+    /// Constructs a new SQLite on-disk store that does not maintain account
+    /// balances, e.g. for blocks that are not ICP blocks.
+    pub fn new_on_disk_without_account_index(location: &Path) -> Result<Self, BlockStoreError> {
+        Self::new(Self::open_on_disk(location), false)
+    }
+
+    /// Constructs a new SQLite in-memory store that does not maintain account
+    /// balances, e.g. for blocks that are not ICP blocks.
+    pub fn new_in_memory_without_account_index() -> Result<Self, BlockStoreError> {
+        Self::new(Self::open_in_memory(), false)
+    }
+
+    fn open_on_disk(location: &Path) -> rusqlite::Connection {
         std::fs::create_dir_all(location)
             .expect("Unable to create directory for SQLite on-disk store.");
         let path = location.join("db.sqlite");
-        let connection =
-            rusqlite::Connection::open(&path).expect("Unable to open SQLite database connection");
-        Self::new(connection)
+        rusqlite::Connection::open(&path).expect("Unable to open SQLite database connection")
     }
 
-    /// Constructs a new SQLite in-memory store.
-    pub fn new_in_memory() -> Result<Self, BlockStoreError> {
-        let connection = rusqlite::Connection::open_in_memory()
-            .expect("Unable to open SQLite in-memory database connection");
-        Self::new(connection)
+    fn open_in_memory() -> rusqlite::Connection {
+        rusqlite::Connection::open_in_memory()
+            .expect("Unable to open SQLite in-memory database connection")
     }
 
-    fn new(connection: rusqlite::Connection) -> Result<Self, BlockStoreError> {
+    fn new(
+        connection: rusqlite::Connection,
+        index_accounts: bool,
+    ) -> Result<Self, BlockStoreError> {
         let mut store = Self {
             connection: Mutex::new(connection),
             base_idx: 0,
             first_block: None,
             last_verified_idx: None,
+            index_accounts,
         };
         store
             .connection
@@ -89,10 +120,10 @@ impl SQLiteStore {
         store.create_tables().map_err(|e| {
             BlockStoreError::Other(format!("Failed to initialize SQLite database: {}", e))
         })?;
-        store.first_block = store
-            .read_oldest_block_snapshot()
-            .map_err(BlockStoreError::Other)?
-            .map(|x| x.0);
+        if store.index_accounts {
+            store.migrate()?;
+        }
+        store.first_block = store.read_oldest_block().map_err(BlockStoreError::Other)?;
 
         if let Some(first_block) = &store.first_block {
             store.base_idx = first_block.index;
@@ -147,24 +178,119 @@ impl SQLiteStore {
             "#,
             [],
         )?;
-        // Table of account book balances on each block.
+        // Table of account balances: one row for each account and each block
+        // that modified its balance, so it also serves as the index of the
+        // transactions of an account. After pruning, the latest balance of each
+        // account before the oldest block is kept, at an index below it.
+        // There is no foreign key on the blocks table because of the latter.
         connection.execute(
             r#"
-            CREATE TABLE IF NOT EXISTS books (
-                block_idx INTEGER NOT NULL,
+            CREATE TABLE IF NOT EXISTS account_balances (
                 account VARCHAR(64) NOT NULL,
-                icpt INTEGER NOT NULL,
-                num_pruned_transactions INTEGER NOT NULL,
-                PRIMARY KEY(block_idx,account),
-                FOREIGN KEY(block_idx) REFERENCES blocks(idx)
+                block_idx INTEGER NOT NULL,
+                tokens INTEGER NOT NULL,
+                PRIMARY KEY(account,block_idx)
             )
             "#,
             [],
         )?;
+        connection.execute(
+            "CREATE INDEX IF NOT EXISTS account_balances_block_idx ON account_balances(block_idx)",
+            [],
+        )?;
         Ok(())
     }
 
-    fn execute_push(connection: &mut Connection, hb: HashedBlock) -> Result<(), BlockStoreError> {
+    /// Upgrades a database created by an older version of the store.
+    ///
+    /// Version 0 kept the balances of all the accounts in memory and only
+    /// persisted a snapshot of them (the `books` table) for the oldest block.
+    /// The `account_balances` table is rebuilt from that snapshot and the
+    /// stored blocks. The snapshot balances are carried over to the block
+    /// before the oldest one, so that the oldest block is indexed like any
+    /// other.
+    fn migrate(&self) -> Result<(), BlockStoreError> {
+        let mut connection = self.connection.lock().unwrap();
+        let version: u32 = connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        if version >= SCHEMA_VERSION {
+            return Ok(());
+        }
+
+        let tx = connection
+            .transaction()
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        Self::rebuild_account_balances(&tx)?;
+        tx.execute_batch(&format!(
+            "DROP TABLE IF EXISTS books; PRAGMA user_version = {};",
+            SCHEMA_VERSION
+        ))
+        .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        tx.commit()
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        Ok(())
+    }
+
+    fn rebuild_account_balances(connection: &Connection) -> Result<(), BlockStoreError> {
+        let to_err = |e: rusqlite::Error| BlockStoreError::Other(e.to_string());
+        connection
+            .execute("DELETE FROM account_balances", [])
+            .map_err(to_err)?;
+
+        // All the blocks are stored if the snapshot is for the genesis block.
+        let snapshot_idx: Option<BlockIndex> = connection
+            .query_row(
+                "SELECT block_idx FROM balances WHERE block_idx > 0 ORDER BY block_idx DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(to_err)?;
+        if let Some(snapshot_idx) = snapshot_idx {
+            connection
+                .execute(
+                    r#"
+                    INSERT INTO account_balances (account, block_idx, tokens)
+                    SELECT account, ?1, icpt FROM books WHERE block_idx = ?2
+                    "#,
+                    params![snapshot_idx - 1, snapshot_idx],
+                )
+                .map_err(to_err)?;
+            // The snapshot holds the balances after the oldest block, which is
+            // replayed below.
+            let oldest_block = Self::read_blocks(connection, snapshot_idx..snapshot_idx + 1)?
+                .pop()
+                .ok_or(BlockStoreError::NotFound(snapshot_idx))?;
+            Self::execute_revert_balances(connection, &oldest_block)?;
+        }
+
+        let mut next_idx = snapshot_idx.unwrap_or(0);
+        info!("Building the account balances from block {}", next_idx);
+        loop {
+            let batch =
+                Self::read_blocks(connection, next_idx..next_idx + MIGRATION_BLOCK_BATCH_LEN)?;
+            let last_idx = match batch.last() {
+                Some(hb) => hb.index,
+                None => break,
+            };
+            for hb in &batch {
+                Self::execute_update_balances(connection, hb)?;
+            }
+            next_idx = last_idx + 1;
+            info!("Account balances built up to block {}", last_idx);
+        }
+        Ok(())
+    }
+
+    fn execute_push(
+        connection: &Connection,
+        hb: HashedBlock,
+        index_accounts: bool,
+    ) -> Result<(), BlockStoreError> {
+        if index_accounts {
+            Self::execute_update_balances(connection, &hb)?;
+        }
         let hash = hb.hash.into_bytes().to_vec();
         let parent_hash = hb.parent_hash.map(|ph| ph.into_bytes().to_vec());
         connection
@@ -176,138 +302,197 @@ impl SQLiteStore {
         Ok(())
     }
 
-    fn read_oldest_block_snapshot(&self) -> Result<Option<(HashedBlock, BalanceBook)>, String> {
-        let mut balance_book = BalanceBook::default();
-        let last_index;
-        {
-            let mut con = self.connection.lock().unwrap();
-            let tx = con.transaction().expect("Cannot open transaction");
-
-            // Read last balance.
-            {
-                let mut stmt = tx
-                    .prepare(
-                        "SELECT block_idx,icpt_pool FROM balances ORDER BY block_idx DESC LIMIT 1",
-                    )
-                    .map_err(|e| e.to_string())?;
-                let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
-                let next = rows.next().map_err(|e| e.to_string())?;
-                if next.is_none() {
-                    // No existing snapshot.
-                    return Ok(None);
-                }
-                let row = next.unwrap();
-                last_index = row.get(0).expect("Expected index.");
-
-                // Convert back pool from i64 to u64.
-                let pool: i64 = row.get(1).expect("Expected icpt pool.");
-                let pool = pool as u64;
-                balance_book.token_pool = Tokens::from_e8s(pool);
+    /// Records the balances of the accounts modified by the given block.
+    fn execute_update_balances(
+        connection: &Connection,
+        hb: &HashedBlock,
+    ) -> Result<(), BlockStoreError> {
+        let block = Block::decode(hb.block.clone()).map_err(|e| {
+            BlockStoreError::Other(format!("Cannot decode block {}: {}", hb.index, e))
+        })?;
+        match block.transaction.operation {
+            Operation::Burn { from, amount } => {
+                Self::execute_debit(connection, &from, hb.index, amount.get_e8s())?;
             }
-
-            // Read last books.
-            {
-                let mut stmt = tx
-                    .prepare("SELECT block_idx,account,icpt,num_pruned_transactions FROM books WHERE block_idx=?")
-                    .map_err(|e| e.to_string())?;
-                let mut rows = stmt.query(params![last_index]).map_err(|e| e.to_string())?;
-                while let Some(row) = rows.next().unwrap() {
-                    let acc_hex: String = row.get(1).expect("Expected account identifier.");
-                    let acc_id = AccountIdentifier::from_hex(&acc_hex).unwrap();
-                    let icpt = row.get(2).expect("Expected tokens.");
-                    balance_book
-                        .store
-                        .insert(acc_id, last_index, Tokens::from_e8s(icpt));
-                    balance_book
-                        .store
-                        .acc_to_hist
-                        .get_mut(&acc_id)
-                        .expect("Expected history for account.")
-                        .num_pruned_transactions = row.get(3).unwrap();
-                }
-
-                SQLiteStore::sanity_check(&balance_book).map_err(|e| e)?;
+            Operation::Mint { to, amount } => {
+                Self::execute_credit(connection, &to, hb.index, amount.get_e8s())?;
+            }
+            Operation::Transfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => {
+                let debit = amount.get_e8s().checked_add(fee.get_e8s()).ok_or_else(|| {
+                    BlockStoreError::Other(format!("Amount overflow in block {}", hb.index))
+                })?;
+                Self::execute_debit(connection, &from, hb.index, debit)?;
+                Self::execute_credit(connection, &to, hb.index, amount.get_e8s())?;
             }
-        }
-
-        let last_block = self.get_at(last_index);
-        Ok(Some((last_block.unwrap(), balance_book)))
-    }
-
-    /// Sanity check (sum of tokens equal pool size).
-    fn sanity_check(balance_book: &BalanceBook) -> Result<(), String> {
-        let mut sum_icpt = Tokens::ZERO;
-        for acc in balance_book.store.acc_to_hist.keys() {
-            sum_icpt += balance_book.account_balance(acc);
-        }
-        let expected_icpt_pool = (Tokens::MAX - sum_icpt).unwrap();
-        if expected_icpt_pool != balance_book.token_pool {
-            return Err(format!(
-                "Incorrect ICPT pool value in the snapshot (expected: {}, got: {})",
-                expected_icpt_pool, balance_book.token_pool
-            ));
         }
         Ok(())
     }
 
-    fn write_oldest_block_snapshot(
-        &mut self,
+    /// Undoes the changes of the given block to the balances recorded at the
+    /// block before it.
+    fn execute_revert_balances(
+        connection: &Connection,
         hb: &HashedBlock,
-        balances: &BalanceBook,
-    ) -> Result<(), String> {
-        if let Ok(Some(b)) = self.first() {
-            // this check is made in upper levels, but for readability:
-            assert!(
-                b.index <= hb.index,
-                "Oldest: {}, new oldest: {}",
-                b.index,
-                hb.index
-            );
+    ) -> Result<(), BlockStoreError> {
+        let block = Block::decode(hb.block.clone()).map_err(|e| {
+            BlockStoreError::Other(format!("Cannot decode block {}: {}", hb.index, e))
+        })?;
+        let prev_idx = hb.index - 1;
+        match block.transaction.operation {
+            Operation::Burn { from, amount } => {
+                Self::execute_credit(connection, &from, prev_idx, amount.get_e8s())?;
+            }
+            Operation::Mint { to, amount } => {
+                Self::execute_debit(connection, &to, prev_idx, amount.get_e8s())?;
+            }
+            Operation::Transfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => {
+                let debit = amount.get_e8s().checked_add(fee.get_e8s()).ok_or_else(|| {
+                    BlockStoreError::Other(format!("Amount overflow in block {}", hb.index))
+                })?;
+                Self::execute_credit(connection, &from, prev_idx, debit)?;
+                Self::execute_debit(connection, &to, prev_idx, amount.get_e8s())?;
+            }
         }
+        Ok(())
+    }
 
-        debug!("Writing oldest block snapshot ({})", hb.index);
-
-        // NB: storing the index of block should be enough, no need to store the
-        // hashedblock itself.
+    fn execute_debit(
+        connection: &Connection,
+        account: &AccountIdentifier,
+        block_idx: BlockIndex,
+        e8s: u64,
+    ) -> Result<(), BlockStoreError> {
+        let balance = Self::read_account_balance(connection, account, block_idx)?;
+        let balance = balance.get_e8s().checked_sub(e8s).ok_or_else(|| {
+            BlockStoreError::Other(format!(
+                "Insufficient balance of account {} in block {}",
+                account, block_idx
+            ))
+        })?;
+        Self::write_account_balance(connection, account, block_idx, balance)
+    }
 
-        let mut con = self.connection.lock().unwrap();
-        let tx = con.transaction().expect("Cannot open transaction");
+    fn execute_credit(
+        connection: &Connection,
+        account: &AccountIdentifier,
+        block_idx: BlockIndex,
+        e8s: u64,
+    ) -> Result<(), BlockStoreError> {
+        let balance = Self::read_account_balance(connection, account, block_idx)?;
+        let balance = balance.get_e8s().checked_add(e8s).ok_or_else(|| {
+            BlockStoreError::Other(format!(
+                "Balance overflow of account {} in block {}",
+                account, block_idx
+            ))
+        })?;
+        Self::write_account_balance(connection, account, block_idx, balance)
+    }
 
-        // Build balances.
-        let mut balances_snapshot = BalanceBook::default();
-        for (acc, hist) in balances.store.acc_to_hist.iter() {
-            // It's safe to unwrap here: this can only fail if hb.index is below the index
-            // of the first block. We validate this invariant in the beginning
-            // of this function.
-            let amount = hist.get_at(hb.index).unwrap();
-            balances_snapshot.token_pool -= amount;
-            balances_snapshot.store.insert(*acc, hb.index, amount);
-        }
+    fn read_account_balance(
+        connection: &Connection,
+        account: &AccountIdentifier,
+        block_idx: BlockIndex,
+    ) -> Result<Tokens, BlockStoreError> {
+        connection
+            .query_row(
+                "SELECT tokens FROM account_balances WHERE account = ?1 AND block_idx <= ?2 ORDER BY block_idx DESC LIMIT 1",
+                params![account.to_hex(), block_idx],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(|e8s| Tokens::from_e8s(e8s.unwrap_or(0)))
+            .map_err(|e| BlockStoreError::Other(e.to_string()))
+    }
 
-        // We need to convert to i64 because SQLite only supports signed integers,
-        // and Rusqlite doesn't like overflows when converting u64 to i64.
-        let pool = balances_snapshot.token_pool.get_e8s() as i64;
+    fn write_account_balance(
+        connection: &Connection,
+        account: &AccountIdentifier,
+        block_idx: BlockIndex,
+        e8s: u64,
+    ) -> Result<(), BlockStoreError> {
+        connection
+            .execute(
+                "INSERT OR REPLACE INTO account_balances (account, block_idx, tokens) VALUES (?1, ?2, ?3)",
+                params![account.to_hex(), block_idx, e8s],
+            )
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        Ok(())
+    }
 
-        tx.execute(
-            "INSERT INTO balances(block_idx,icpt_pool) VALUES (?1,?2)",
-            params![hb.index, pool],
-        )
-        .map_err(|e| e.to_string())?;
+    /// Returns the sum of the balances of all the accounts at the given block.
+    fn read_total_balance(
+        connection: &Connection,
+        block_idx: BlockIndex,
+    ) -> Result<Tokens, rusqlite::Error> {
+        let sum: i64 = connection.query_row(
+            r#"
+            SELECT COALESCE(SUM(a.tokens), 0) FROM account_balances a
+            WHERE a.block_idx = (
+                SELECT MAX(b.block_idx) FROM account_balances b
+                WHERE b.account = a.account AND b.block_idx <= ?1
+            )
+            "#,
+            params![block_idx],
+            |row| row.get(0),
+        )?;
+        Ok(Tokens::from_e8s(sum as u64))
+    }
 
+    fn read_oldest_block(&self) -> Result<Option<HashedBlock>, String> {
+        let last_index: BlockIndex;
         {
-            let mut stmt = tx.prepare("INSERT INTO books(block_idx,account,icpt,num_pruned_transactions) VALUES (?1,?2,?3,?4)")
-                .expect("Couldn't prepare statement");
-            for (acc, history) in balances.store.acc_to_hist.iter() {
-                let icpts = history.get_at(hb.index).unwrap();
-                let num_pruned = history.num_pruned_transactions;
-                stmt.execute(params![hb.index, acc.to_hex(), icpts.get_e8s(), num_pruned])
-                    .map_err(|e| e.to_string())?;
+            let connection = self.connection.lock().unwrap();
+            let row: Option<(BlockIndex, i64)> = connection
+                .query_row(
+                    "SELECT block_idx,icpt_pool FROM balances ORDER BY block_idx DESC LIMIT 1",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?;
+            let (index, pool) = match row {
+                Some(row) => row,
+                // No existing snapshot.
+                None => return Ok(None),
+            };
+            last_index = index;
+
+            if self.index_accounts {
+                // Convert back pool from i64 to u64.
+                let pool = Tokens::from_e8s(pool as u64);
+                SQLiteStore::sanity_check(&connection, last_index, pool)?;
             }
         }
 
-        tx.commit().map_err(|e| e.to_string())?;
+        let last_block = self.get_at(last_index);
+        Ok(Some(last_block.unwrap()))
+    }
 
-        self.first_block = Option::Some(hb.clone());
+    /// Sanity check (sum of tokens equal pool size).
+    fn sanity_check(
+        connection: &Connection,
+        block_idx: BlockIndex,
+        token_pool: Tokens,
+    ) -> Result<(), String> {
+        let sum_icpt =
+            Self::read_total_balance(connection, block_idx).map_err(|e| e.to_string())?;
+        let expected_icpt_pool = (Tokens::MAX - sum_icpt).unwrap();
+        if expected_icpt_pool != token_pool {
+            return Err(format!(
+                "Incorrect ICPT pool value in the snapshot (expected: {}, got: {})",
+                expected_icpt_pool, token_pool
+            ));
+        }
         Ok(())
     }
 
@@ -346,6 +531,13 @@ impl SQLiteStore {
             return Err(BlockStoreError::NotAvailable(range.start));
         }
         let connection = self.connection.lock().unwrap();
+        Self::read_blocks(&connection, range)
+    }
+
+    fn read_blocks(
+        connection: &Connection,
+        range: Range<BlockIndex>,
+    ) -> Result<Vec<HashedBlock>, BlockStoreError> {
         let mut stmt = connection
             .prepare("SELECT hash, block, parent_hash, idx FROM blocks WHERE idx >= ? AND idx < ?")
             .map_err(|e| BlockStoreError::Other(e.to_string()))?;
@@ -370,7 +562,12 @@ impl SQLiteStore {
 
     pub fn push(&mut self, hb: HashedBlock) -> Result<(), BlockStoreError> {
         let mut connection = self.connection.lock().unwrap();
-        Self::execute_push(&mut *connection, hb)
+        let tx = connection
+            .transaction()
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        Self::execute_push(&tx, hb, self.index_accounts)?;
+        tx.commit()
+            .map_err(|e| BlockStoreError::Other(e.to_string()))
     }
 
     pub fn push_batch(&mut self, batch: Vec<HashedBlock>) -> Result<(), BlockStoreError> {
@@ -381,7 +578,7 @@ impl SQLiteStore {
             .map_err(|e| BlockStoreError::Other(format!("{}", e)))?;
 
         for hb in batch {
-            match Self::execute_push(&mut *connection, hb) {
+            match Self::execute_push(&connection, hb, self.index_accounts) {
                 Ok(_) => (),
                 Err(e) => {
                     connection
@@ -399,13 +596,45 @@ impl SQLiteStore {
     }
 
     // FIXME: Make `prune` return `BlockStoreError` on error
-    pub fn prune(&mut self, hb: &HashedBlock, balances: &BalanceBook) -> Result<(), String> {
-        self.write_oldest_block_snapshot(hb, balances)?;
+    pub fn prune(&mut self, hb: &HashedBlock) -> Result<(), String> {
+        if let Ok(Some(b)) = self.first() {
+            // this check is made in upper levels, but for readability:
+            assert!(
+                b.index <= hb.index,
+                "Oldest: {}, new oldest: {}",
+                b.index,
+                hb.index
+            );
+        }
+
+        debug!("Pruning blocks before {}", hb.index);
+
         let mut connection = self.connection.lock().unwrap();
         let tx = connection.transaction().expect("Cannot open transaction");
-        // NB: An optimization would be to update only modified accounts.
+
+        // We need to convert to i64 because SQLite only supports signed integers,
+        // and Rusqlite doesn't like overflows when converting u64 to i64.
+        let pool = (Tokens::MAX
+            - Self::read_total_balance(&tx, hb.index).map_err(|e| e.to_string())?)
+        .unwrap()
+        .get_e8s() as i64;
         tx.execute(
-            "DELETE FROM books WHERE block_idx > 0 AND block_idx < ?",
+            "INSERT INTO balances(block_idx,icpt_pool) VALUES (?1,?2)",
+            params![hb.index, pool],
+        )
+        .map_err(|e| e.to_string())?;
+
+        // Keep only the latest balance of each account before the new oldest
+        // block. It stays at the index of the block that set it, below the new
+        // oldest block.
+        tx.execute(
+            r#"
+            DELETE FROM account_balances
+            WHERE block_idx < ?1 AND block_idx < (
+                SELECT MAX(b.block_idx) FROM account_balances b
+                WHERE b.account = account_balances.account AND b.block_idx <= ?1
+            )
+            "#,
             params![hb.index],
         )
         .map_err(|e| e.to_string())?;
@@ -421,12 +650,111 @@ impl SQLiteStore {
         .map_err(|e| e.to_string())?;
         self.base_idx = hb.index;
         tx.commit().map_err(|e| e.to_string())?;
+
+        self.first_block = Some(hb.clone());
         Ok(())
     }
 
-    pub fn first_snapshot(&self) -> Option<(HashedBlock, BalanceBook)> {
-        self.read_oldest_block_snapshot()
-            .expect("Error while retrieving first snapshot.")
+    /// Returns the balance of the account at the given block.
+    pub fn get_account_balance(
+        &self,
+        account: &AccountIdentifier,
+        block_idx: BlockIndex,
+    ) -> Result<Tokens, BlockStoreError> {
+        if block_idx < self.base_idx {
+            return Err(BlockStoreError::NotAvailable(block_idx));
+        }
+        let connection = self.connection.lock().unwrap();
+        Self::read_account_balance(&connection, account, block_idx)
+    }
+
+    /// Returns the sum of the tokens not held by any account at the given block.
+    pub fn get_token_pool(&self, block_idx: BlockIndex) -> Result<Tokens, BlockStoreError> {
+        if block_idx < self.base_idx {
+            return Err(BlockStoreError::NotAvailable(block_idx));
+        }
+        let connection = self.connection.lock().unwrap();
+        let total = Self::read_total_balance(&connection, block_idx)
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        (Tokens::MAX - total).map_err(BlockStoreError::Other)
+    }
+
+    /// Returns the balances of the account after each block in the range that
+    /// modified it, in ascending block order.
+    pub fn get_account_balance_history(
+        &self,
+        account: &AccountIdentifier,
+        range: Range<BlockIndex>,
+    ) -> Result<Vec<(BlockIndex, Tokens)>, BlockStoreError> {
+        let range = self.transactions_range(range);
+        let connection = self.connection.lock().unwrap();
+        let mut stmt = connection
+            .prepare(
+                "SELECT block_idx, tokens FROM account_balances WHERE account = ?1 AND block_idx >= ?2 AND block_idx < ?3 ORDER BY block_idx",
+            )
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        let rows = stmt
+            .query_map(params![account.to_hex(), range.start, range.end], |row| {
+                Ok((row.get(0)?, Tokens::from_e8s(row.get(1)?)))
+            })
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| BlockStoreError::Other(e.to_string()))
+    }
+
+    /// Returns the indices of the blocks in the range that modified the
+    /// balance of the account, newest first, skipping the first `offset`
+    /// ones and returning at most `limit` of them.
+    pub fn get_account_transactions(
+        &self,
+        account: &AccountIdentifier,
+        range: Range<BlockIndex>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<BlockIndex>, BlockStoreError> {
+        let range = self.transactions_range(range);
+        let offset = i64::try_from(offset).unwrap_or(i64::MAX);
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let connection = self.connection.lock().unwrap();
+        let mut stmt = connection
+            .prepare(
+                "SELECT block_idx FROM account_balances WHERE account = ?1 AND block_idx >= ?2 AND block_idx < ?3 ORDER BY block_idx DESC LIMIT ?4 OFFSET ?5",
+            )
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        let rows = stmt
+            .query_map(
+                params![account.to_hex(), range.start, range.end, limit, offset],
+                |row| row.get(0),
+            )
+            .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| BlockStoreError::Other(e.to_string()))
+    }
+
+    /// Returns the number of blocks in the range that modified the balance of
+    /// the account.
+    pub fn count_account_transactions(
+        &self,
+        account: &AccountIdentifier,
+        range: Range<BlockIndex>,
+    ) -> Result<u64, BlockStoreError> {
+        let range = self.transactions_range(range);
+        let connection = self.connection.lock().unwrap();
+        connection
+            .query_row(
+                "SELECT COUNT(*) FROM account_balances WHERE account = ?1 AND block_idx >= ?2 AND block_idx < ?3",
+                params![account.to_hex(), range.start, range.end],
+                |row| row.get(0),
+            )
+            .map_err(|e| BlockStoreError::Other(e.to_string()))
+    }
+
+    /// Restricts the range to the blocks whose transactions can be searched.
+    /// After pruning, the rows before the oldest block only hold the balances
+    /// carried over from the pruned blocks.
+    fn transactions_range(&self, range: Range<BlockIndex>) -> Range<BlockIndex> {
+        let start = range.start.max(self.base_idx);
+        start..range.end.max(start)
     }
 
     pub fn first(&self) -> Result<Option<HashedBlock>, BlockStoreError> {
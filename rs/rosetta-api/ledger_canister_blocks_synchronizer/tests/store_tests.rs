@@ -1,13 +1,11 @@
-use ic_ledger_canister_blocks_synchronizer::{
-    balance_book::BalanceBook,
-    store::{BlockStoreError, SQLiteStore},
-};
+use ic_ledger_canister_blocks_synchronizer::store::{BlockStoreError, SQLiteStore};
 use ic_ledger_canister_blocks_synchronizer_test_utils::{
     create_tmp_dir, init_test_logger, sample_data::Scribe,
 };
 use ic_ledger_core::Tokens;
-use ledger_canister::{AccountIdentifier, BlockIndex};
-use std::{collections::BTreeMap, path::Path};
+use ledger_canister::{AccountIdentifier, Block, BlockIndex, Operation};
+use rusqlite::params;
+use std::path::Path;
 
 pub(crate) fn sqlite_on_disk_store(path: &Path) -> SQLiteStore {
     SQLiteStore::new_on_disk(path).expect("Unable to create store")
@@ -47,10 +45,10 @@ async fn store_prune_test() {
     }
 
     prune(&scribe, &mut store, 10);
-    verify_pruned(&scribe, &mut store, 10);
+    verify_pruned(&scribe, &store, 10);
 
     prune(&scribe, &mut store, 20);
-    verify_pruned(&scribe, &mut store, 20);
+    verify_pruned(&scribe, &store, 20);
 }
 
 #[actix_rt::test]
@@ -65,15 +63,15 @@ async fn store_prune_corner_cases_test() {
     }
 
     prune(&scribe, &mut store, 0);
-    verify_pruned(&scribe, &mut store, 0);
+    verify_pruned(&scribe, &store, 0);
 
     prune(&scribe, &mut store, 1);
-    verify_pruned(&scribe, &mut store, 1);
+    verify_pruned(&scribe, &store, 1);
 
     let last_idx = scribe.blockchain.back().unwrap().index;
 
     prune(&scribe, &mut store, last_idx);
-    verify_pruned(&scribe, &mut store, last_idx);
+    verify_pruned(&scribe, &store, last_idx);
 }
 
 #[actix_rt::test]
@@ -88,11 +86,11 @@ async fn store_prune_first_balance_test() {
     }
 
     prune(&scribe, &mut store, 10);
-    verify_pruned(&scribe, &mut store, 10);
+    verify_pruned(&scribe, &store, 10);
     verify_balance_snapshot(&scribe, &mut store, 10);
 
     prune(&scribe, &mut store, 20);
-    verify_pruned(&scribe, &mut store, 20);
+    verify_pruned(&scribe, &store, 20);
     verify_balance_snapshot(&scribe, &mut store, 20);
 }
 
@@ -109,58 +107,131 @@ async fn store_prune_and_load_test() {
     }
 
     prune(&scribe, &mut store, 10);
-    verify_pruned(&scribe, &mut store, 10);
+    verify_pruned(&scribe, &store, 10);
     verify_balance_snapshot(&scribe, &mut store, 10);
 
     prune(&scribe, &mut store, 20);
-    verify_pruned(&scribe, &mut store, 20);
+    verify_pruned(&scribe, &store, 20);
     verify_balance_snapshot(&scribe, &mut store, 20);
 
     drop(store);
     // Now reload from disk
     let mut store = sqlite_on_disk_store(tmpdir.path());
-    verify_pruned(&scribe, &mut store, 20);
+    verify_pruned(&scribe, &store, 20);
     verify_balance_snapshot(&scribe, &mut store, 20);
 
     prune(&scribe, &mut store, 30);
-    verify_pruned(&scribe, &mut store, 30);
+    verify_pruned(&scribe, &store, 30);
     verify_balance_snapshot(&scribe, &mut store, 30);
 
     drop(store);
     // Reload once again
     let mut store = sqlite_on_disk_store(tmpdir.path());
-    verify_pruned(&scribe, &mut store, 30);
+    verify_pruned(&scribe, &store, 30);
     verify_balance_snapshot(&scribe, &mut store, 30);
 }
 
-pub(crate) fn to_balances(
-    balances: BTreeMap<AccountIdentifier, Tokens>,
-    index: BlockIndex,
-) -> BalanceBook {
-    let mut balance_book = BalanceBook::default();
-    for (acc, amount) in balances {
-        balance_book.token_pool -= amount;
-        balance_book.store.insert(acc, index, amount);
+#[actix_rt::test]
+async fn store_account_balances_test() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+    let mut store = sqlite_on_disk_store(tmpdir.path());
+    let scribe = Scribe::new_with_sample_data(10, 100);
+
+    store
+        .push_batch(scribe.blockchain.iter().cloned().collect())
+        .unwrap();
+
+    verify_balances(&scribe, &store, 0);
+    verify_account_transactions(&scribe, &store, 0);
+
+    prune(&scribe, &mut store, 20);
+    verify_balances(&scribe, &store, 20);
+    verify_account_transactions(&scribe, &store, 20);
+    assert_eq!(
+        store
+            .get_account_balance(scribe.accounts.front().unwrap(), 19)
+            .unwrap_err(),
+        BlockStoreError::NotAvailable(19)
+    );
+
+    drop(store);
+    let store = sqlite_on_disk_store(tmpdir.path());
+    verify_balances(&scribe, &store, 20);
+    verify_account_transactions(&scribe, &store, 20);
+}
+
+#[actix_rt::test]
+async fn store_migration_test() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+    let scribe = Scribe::new_with_sample_data(10, 100);
+    let snapshot_idx = 30;
+
+    // Create a database with the schema of version 0: the balances of the
+    // accounts were only stored for the oldest block, in the `books` table.
+    {
+        let connection = rusqlite::Connection::open(tmpdir.path().join("db.sqlite")).unwrap();
+        connection
+            .execute_batch(
+                r#"
+                CREATE TABLE blocks (hash BLOB NOT NULL, block BLOB NOT NULL, parent_hash BLOB,
+                    idx INTEGER NOT NULL PRIMARY KEY, verified BOOLEAN);
+                CREATE TABLE balances (block_idx INTEGER NOT NULL PRIMARY KEY,
+                    icpt_pool BLOB NOT NULL, FOREIGN KEY(block_idx) REFERENCES blocks(idx));
+                CREATE TABLE books (block_idx INTEGER NOT NULL, account VARCHAR(64) NOT NULL,
+                    icpt INTEGER NOT NULL, num_pruned_transactions INTEGER NOT NULL,
+                    PRIMARY KEY(block_idx,account), FOREIGN KEY(block_idx) REFERENCES blocks(idx));
+                "#,
+            )
+            .unwrap();
+        for hb in &scribe.blockchain {
+            if 0 < hb.index && hb.index < snapshot_idx {
+                continue;
+            }
+            connection
+                .execute(
+                    "INSERT INTO blocks (hash, block, parent_hash, idx, verified) VALUES (?1, ?2, ?3, ?4, FALSE)",
+                    params![
+                        hb.hash.into_bytes().to_vec(),
+                        hb.block.clone().into_vec(),
+                        hb.parent_hash.map(|ph| ph.into_bytes().to_vec()),
+                        hb.index
+                    ],
+                )
+                .unwrap();
+        }
+        let balances = scribe.balance_history.get(snapshot_idx as usize).unwrap();
+        let mut pool = Tokens::MAX;
+        for (acc, amount) in balances {
+            pool -= *amount;
+            connection
+                .execute(
+                    "INSERT INTO books (block_idx, account, icpt, num_pruned_transactions) VALUES (?1, ?2, ?3, 1)",
+                    params![snapshot_idx, acc.to_hex(), amount.get_e8s()],
+                )
+                .unwrap();
+        }
+        connection
+            .execute(
+                "INSERT INTO balances (block_idx, icpt_pool) VALUES (?1, ?2)",
+                params![snapshot_idx, pool.get_e8s() as i64],
+            )
+            .unwrap();
     }
-    balance_book
+
+    let store = sqlite_on_disk_store(tmpdir.path());
+    verify_pruned(&scribe, &store, snapshot_idx);
+    verify_balances(&scribe, &store, snapshot_idx);
+    verify_account_transactions(&scribe, &store, snapshot_idx);
 }
 
 fn prune(scribe: &Scribe, store: &mut SQLiteStore, prune_at: u64) {
-    let oldest_idx = prune_at;
-    let oldest_block = scribe.blockchain.get(oldest_idx as usize).unwrap();
-    let oldest_balance = to_balances(
-        scribe
-            .balance_history
-            .get(oldest_idx as usize)
-            .unwrap()
-            .clone(),
-        oldest_idx,
-    );
-
-    store.prune(oldest_block, &oldest_balance).unwrap();
+    let oldest_block = scribe.blockchain.get(prune_at as usize).unwrap();
+    store.prune(oldest_block).unwrap();
 }
 
-fn verify_pruned(scribe: &Scribe, store: &mut SQLiteStore, prune_at: u64) {
+fn verify_pruned(scribe: &Scribe, store: &SQLiteStore, prune_at: u64) {
     let after_last_idx = scribe.blockchain.len() as u64;
     let oldest_idx = prune_at.min(after_last_idx);
 
@@ -193,25 +264,85 @@ fn verify_pruned(scribe: &Scribe, store: &mut SQLiteStore, prune_at: u64) {
 
 fn verify_balance_snapshot(scribe: &Scribe, store: &mut SQLiteStore, prune_at: u64) {
     let oldest_idx = prune_at as usize;
-    let (oldest_block, balances) = store.first_snapshot().unwrap();
+    let oldest_block = store.first().unwrap().unwrap();
     assert_eq!(oldest_block, *scribe.blockchain.get(oldest_idx).unwrap());
 
-    let scribe_balances = scribe.balance_history.get(oldest_idx).unwrap().clone();
+    let scribe_balances = scribe.balance_history.get(oldest_idx).unwrap();
+    for (acc, amount) in scribe_balances {
+        assert_eq!(store.get_account_balance(acc, prune_at).unwrap(), *amount);
+    }
+
+    let mut sum_icpt = Tokens::ZERO;
+    for amount in scribe_balances.values() {
+        sum_icpt += *amount;
+    }
+    assert_eq!(
+        (Tokens::MAX - sum_icpt).unwrap(),
+        store.get_token_pool(prune_at).unwrap()
+    );
+}
 
-    assert_eq!(balances.store.acc_to_hist.len(), scribe_balances.len());
-    for (acc, hist) in &balances.store.acc_to_hist {
+fn verify_balances(scribe: &Scribe, store: &SQLiteStore, oldest_idx: u64) {
+    for hb in scribe.blockchain.iter().skip(oldest_idx as usize) {
+        for (acc, amount) in scribe.balance_history.get(hb.index as usize).unwrap() {
+            assert_eq!(store.get_account_balance(acc, hb.index).unwrap(), *amount);
+        }
+    }
+}
+
+/// Checks the transactions of each account against the ones found in the
+/// blocks, starting from `oldest_idx`.
+fn verify_account_transactions(scribe: &Scribe, store: &SQLiteStore, oldest_idx: u64) {
+    let end = scribe.blockchain.len() as u64;
+    for acc in &scribe.accounts {
+        let expected: Vec<BlockIndex> = scribe
+            .blockchain
+            .iter()
+            .filter(|hb| hb.index >= oldest_idx && touches(hb.block.clone(), acc))
+            .map(|hb| hb.index)
+            .rev()
+            .collect();
+        assert_eq!(
+            store
+                .get_account_transactions(acc, 0..end, 0, usize::MAX)
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            store.count_account_transactions(acc, 0..end).unwrap(),
+            expected.len() as u64
+        );
         assert_eq!(
-            balances.store.get_at(*acc, prune_at).unwrap(),
-            *scribe_balances.get(acc).unwrap()
+            store.get_account_transactions(acc, 0..end, 1, 2).unwrap(),
+            expected.iter().skip(1).take(2).cloned().collect::<Vec<_>>()
         );
-        if let Some(last_entry) = hist.get_history(None).last().map(|x| x.0) {
-            assert_eq!(last_entry, prune_at);
+        let history = store.get_account_balance_history(acc, 0..end).unwrap();
+        assert_eq!(
+            history
+                .iter()
+                .rev()
+                .map(|(idx, _)| *idx)
+                .collect::<Vec<_>>(),
+            expected
+        );
+        for (idx, amount) in history {
+            assert_eq!(
+                *scribe
+                    .balance_history
+                    .get(idx as usize)
+                    .unwrap()
+                    .get(acc)
+                    .unwrap(),
+                amount
+            );
         }
     }
+}
 
-    let mut sum_icpt = Tokens::ZERO;
-    for amount in scribe.balance_history.get(oldest_idx).unwrap().values() {
-        sum_icpt += *amount;
+fn touches(block: ic_ledger_core::block::EncodedBlock, acc: &AccountIdentifier) -> bool {
+    match Block::decode(block).unwrap().transaction.operation {
+        Operation::Burn { from, .. } => from == *acc,
+        Operation::Mint { to, .. } => to == *acc,
+        Operation::Transfer { from, to, .. } => from == *acc || to == *acc,
     }
-    assert_eq!((Tokens::MAX - sum_icpt).unwrap(), balances.token_pool);
 }
@@ -0,0 +1,172 @@
+use ic_ledger_core::timestamp::TimeStamp;
+use ic_ledger_core::tokens::Tokens;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The amount of tokens that a spender can transfer on behalf of an account
+/// owner.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Allowance {
+    pub amount: Tokens,
+    /// The time at which the allowance stops being usable.
+    pub expires_at: Option<TimeStamp>,
+}
+
+impl Default for Allowance {
+    fn default() -> Self {
+        Self {
+            amount: Tokens::ZERO,
+            expires_at: None,
+        }
+    }
+}
+
+/// An error returned by [AllowanceTable::approve].
+#[derive(Debug, PartialEq, Eq)]
+pub enum ApproveError {
+    /// The approval expires before the current ledger time.
+    ExpiredApproval { now: TimeStamp },
+    /// The caller specified the expected allowance and the current allowance
+    /// differs from it.
+    AllowanceChanged { current_allowance: Tokens },
+}
+
+/// An error returned by [AllowanceTable::use_allowance] if the allowance is
+/// too small for the requested amount.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InsufficientAllowance(pub Tokens);
+
+/// The allowances that account owners granted to spenders.
+///
+/// Expired allowances are indistinguishable from zero allowances; the table
+/// removes them lazily in [AllowanceTable::prune].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AllowanceTable<AccountId: Ord> {
+    allowances: BTreeMap<(AccountId, AccountId), Allowance>,
+    expiration_queue: BTreeSet<(TimeStamp, (AccountId, AccountId))>,
+}
+
+impl<AccountId: Ord> Default for AllowanceTable<AccountId> {
+    fn default() -> Self {
+        Self {
+            allowances: BTreeMap::new(),
+            expiration_queue: BTreeSet::new(),
+        }
+    }
+}
+
+impl<AccountId> AllowanceTable<AccountId>
+where
+    AccountId: Ord + Clone,
+{
+    /// Returns the allowance that `account` granted to `spender` at time `now`.
+    pub fn allowance(&self, account: &AccountId, spender: &AccountId, now: TimeStamp) -> Allowance {
+        let key = (account.clone(), spender.clone());
+        match self.allowances.get(&key) {
+            Some(allowance) if !is_expired(allowance, now) => allowance.clone(),
+            _ => Allowance::default(),
+        }
+    }
+
+    /// Sets the allowance that `account` grants to `spender` to `amount`,
+    /// replacing the previous allowance.
+    ///
+    /// If `expected_allowance` is set, the call fails unless the current
+    /// allowance equals `expected_allowance`.
+    pub fn approve(
+        &mut self,
+        account: &AccountId,
+        spender: &AccountId,
+        amount: Tokens,
+        expires_at: Option<TimeStamp>,
+        now: TimeStamp,
+        expected_allowance: Option<Tokens>,
+    ) -> Result<(), ApproveError> {
+        if let Some(expires_at) = expires_at {
+            if expires_at <= now {
+                return Err(ApproveError::ExpiredApproval { now });
+            }
+        }
+
+        if let Some(expected_allowance) = expected_allowance {
+            let current_allowance = self.allowance(account, spender, now).amount;
+            if current_allowance != expected_allowance {
+                return Err(ApproveError::AllowanceChanged { current_allowance });
+            }
+        }
+
+        let key = (account.clone(), spender.clone());
+        self.remove(&key);
+        if amount != Tokens::ZERO {
+            if let Some(expires_at) = expires_at {
+                self.expiration_queue.insert((expires_at, key.clone()));
+            }
+            self.allowances
+                .insert(key, Allowance { amount, expires_at });
+        }
+        Ok(())
+    }
+
+    /// Decreases the allowance that `account` granted to `spender` by
+    /// `amount` and returns the remaining allowance.
+    pub fn use_allowance(
+        &mut self,
+        account: &AccountId,
+        spender: &AccountId,
+        amount: Tokens,
+        now: TimeStamp,
+    ) -> Result<Tokens, InsufficientAllowance> {
+        let allowance = self.allowance(account, spender, now);
+        let remaining =
+            (allowance.amount - amount).map_err(|_| InsufficientAllowance(allowance.amount))?;
+
+        let key = (account.clone(), spender.clone());
+        if remaining == Tokens::ZERO {
+            self.remove(&key);
+        } else if let Some(entry) = self.allowances.get_mut(&key) {
+            entry.amount = remaining;
+        }
+        Ok(remaining)
+    }
+
+    /// Removes at most `limit` allowances that expired before `now` and
+    /// returns the number of removed allowances.
+    pub fn prune(&mut self, now: TimeStamp, limit: usize) -> usize {
+        let mut num_pruned = 0;
+        while num_pruned < limit {
+            let (expires_at, key) = match self.expiration_queue.iter().next() {
+                Some((expires_at, key)) if *expires_at <= now => (*expires_at, key.clone()),
+                _ => break,
+            };
+            self.expiration_queue.remove(&(expires_at, key.clone()));
+            self.allowances.remove(&key);
+            num_pruned += 1;
+        }
+        num_pruned
+    }
+
+    /// Returns the number of allowances in the table, including the expired
+    /// ones that have not been pruned yet.
+    pub fn len(&self) -> usize {
+        self.allowances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allowances.is_empty()
+    }
+
+    fn remove(&mut self, key: &(AccountId, AccountId)) {
+        if let Some(old) = self.allowances.remove(key) {
+            if let Some(expires_at) = old.expires_at {
+                self.expiration_queue.remove(&(expires_at, key.clone()));
+            }
+        }
+    }
+}
+
+fn is_expired(allowance: &Allowance, now: TimeStamp) -> bool {
+    allowance
+        .expires_at
+        .map(|expires_at| expires_at <= now)
+        .unwrap_or(false)
+}
@@ -1,11 +1,17 @@
-use crate::{archive::ArchiveCanisterWasm, blockchain::Blockchain, range_utils, runtime::Runtime};
+use crate::{
+    approvals::{AllowanceTable, ApproveError, InsufficientAllowance},
+    archive::ArchiveCanisterWasm,
+    blockchain::Blockchain,
+    range_utils,
+    runtime::Runtime,
+};
 use ic_base_types::CanisterId;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::ops::Range;
 use std::time::Duration;
 
-use ic_ledger_core::balances::{BalanceError, Balances, BalancesStore};
+use ic_ledger_core::balances::{BalanceError, Balances};
 use ic_ledger_core::block::{BlockIndex, BlockType, EncodedBlock, HashOf};
 use ic_ledger_core::timestamp::TimeStamp;
 use ic_ledger_core::tokens::Tokens;
@@ -37,10 +43,57 @@ pub trait LedgerTransaction: Sized {
     /// Returns the hash of this transaction.
     fn hash(&self) -> HashOf<Self>;
 
-    /// Applies this transaction to the balance book.
-    fn apply<S>(&self, balances: &mut Balances<Self::AccountId, S>) -> Result<(), BalanceError>
+    /// Applies this transaction to the balance book and the allowance table.
+    ///
+    /// The ledger state must not change if this function returns an error.
+    fn apply<C>(&self, context: &mut C, now: TimeStamp) -> Result<(), TxApplyError>
     where
-        S: Default + BalancesStore<Self::AccountId>;
+        C: LedgerContext<AccountId = Self::AccountId>;
+}
+
+/// The part of the ledger state that transactions modify.
+pub trait LedgerContext {
+    type AccountId: std::hash::Hash + Ord + Eq + Clone;
+
+    fn balances(&self) -> &Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>>;
+    fn balances_mut(&mut self) -> &mut Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>>;
+
+    fn approvals(&self) -> &AllowanceTable<Self::AccountId>;
+    fn approvals_mut(&mut self) -> &mut AllowanceTable<Self::AccountId>;
+}
+
+/// An error returned by [LedgerTransaction::apply].
+#[derive(Debug, PartialEq, Eq)]
+pub enum TxApplyError {
+    InsufficientFunds { balance: Tokens },
+    InsufficientAllowance { allowance: Tokens },
+    ExpiredApproval { now: TimeStamp },
+    AllowanceChanged { current_allowance: Tokens },
+}
+
+impl From<BalanceError> for TxApplyError {
+    fn from(e: BalanceError) -> Self {
+        match e {
+            BalanceError::InsufficientFunds { balance } => Self::InsufficientFunds { balance },
+        }
+    }
+}
+
+impl From<ApproveError> for TxApplyError {
+    fn from(e: ApproveError) -> Self {
+        match e {
+            ApproveError::ExpiredApproval { now } => Self::ExpiredApproval { now },
+            ApproveError::AllowanceChanged { current_allowance } => {
+                Self::AllowanceChanged { current_allowance }
+            }
+        }
+    }
+}
+
+impl From<InsufficientAllowance> for TxApplyError {
+    fn from(e: InsufficientAllowance) -> Self {
+        Self::InsufficientAllowance { allowance: e.0 }
+    }
 }
 
 pub trait LedgerAccess {
@@ -61,8 +114,7 @@ pub trait LedgerAccess {
     fn with_ledger_mut<R>(f: impl FnOnce(&mut Self::Ledger) -> R) -> R;
 }
 
-pub trait LedgerData {
-    type AccountId: std::hash::Hash + Ord + Eq + Clone;
+pub trait LedgerData: LedgerContext {
     type ArchiveWasm: ArchiveCanisterWasm;
     type Runtime: Runtime;
     type Block: BlockType<Transaction = Self::Transaction>;
@@ -97,9 +149,6 @@ pub trait LedgerData {
 
     // Ledger data structures
 
-    fn balances(&self) -> &Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>>;
-    fn balances_mut(&mut self) -> &mut Balances<Self::AccountId, HashMap<Self::AccountId, Tokens>>;
-
     fn blockchain(&self) -> &Blockchain<Self::Runtime, Self::ArchiveWasm>;
     fn blockchain_mut(&mut self) -> &mut Blockchain<Self::Runtime, Self::ArchiveWasm>;
 
@@ -121,6 +170,24 @@ pub enum TransferError {
     TxCreatedInFuture { ledger_time: TimeStamp },
     TxThrottled,
     TxDuplicate { duplicate_of: BlockIndex },
+    InsufficientAllowance { allowance: Tokens },
+    ExpiredApproval { ledger_time: TimeStamp },
+    AllowanceChanged { current_allowance: Tokens },
+}
+
+impl From<TxApplyError> for TransferError {
+    fn from(e: TxApplyError) -> Self {
+        match e {
+            TxApplyError::InsufficientFunds { balance } => Self::InsufficientFunds { balance },
+            TxApplyError::InsufficientAllowance { allowance } => {
+                Self::InsufficientAllowance { allowance }
+            }
+            TxApplyError::ExpiredApproval { now } => Self::ExpiredApproval { ledger_time: now },
+            TxApplyError::AllowanceChanged { current_allowance } => {
+                Self::AllowanceChanged { current_allowance }
+            }
+        }
+    }
 }
 
 /// Adds a new block with the specified transaction to the ledger.
@@ -131,6 +198,9 @@ pub fn apply_transaction<L: LedgerData>(
 ) -> Result<(BlockIndex, HashOf<EncodedBlock>), TransferError> {
     let num_pruned = purge_old_transactions(ledger, now);
 
+    let max_approvals_to_purge = ledger.max_transactions_to_purge();
+    ledger.approvals_mut().prune(now, max_approvals_to_purge);
+
     // If we pruned some transactions, let this one through
     // otherwise throttle if there are too many
     if num_pruned == 0 && throttle(ledger, now) {
@@ -160,13 +230,7 @@ pub fn apply_transaction<L: LedgerData>(
         }
     }
 
-    transaction
-        .apply(ledger.balances_mut())
-        .map_err(|e| match e {
-            BalanceError::InsufficientFunds { balance } => {
-                TransferError::InsufficientFunds { balance }
-            }
-        })?;
+    transaction.apply(ledger, now)?;
 
     let block = L::Block::from_transaction(ledger.blockchain().last_hash, transaction, now);
     let block_timestamp = block.timestamp();
@@ -201,7 +265,7 @@ pub fn apply_transaction<L: LedgerData>(
         let burn_tx = L::Transaction::burn(account, balance, Some(now), Some(TRIMMED_MEMO));
 
         burn_tx
-            .apply(ledger.balances_mut())
+            .apply(ledger, now)
             .expect("failed to burn funds that must have existed");
 
         let parent_hash = ledger.blockchain().last_hash;
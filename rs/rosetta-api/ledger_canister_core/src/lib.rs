@@ -1,3 +1,4 @@
+pub mod approvals;
 pub mod archive;
 pub mod blockchain;
 pub mod ledger;
@@ -10,9 +10,10 @@ use crate::request::transaction_operation_results::TransactionOperationResults;
 use crate::request::transaction_results::TransactionResults;
 use crate::request::Request;
 use crate::request_types::{
-    DisburseMetadata, FollowMetadata, KeyMetadata, MergeMaturityMetadata, NeuronIdentifierMetadata,
-    NeuronInfoMetadata, PublicKeyOrPrincipal, RequestResultMetadata, SetDissolveTimestampMetadata,
-    SpawnMetadata, Status, STATUS_COMPLETED,
+    DisburseMetadata, FollowMetadata, KeyMetadata, MergeMaturityMetadata, MergeMetadata,
+    NeuronIdentifierMetadata, NeuronInfoMetadata, PublicKeyOrPrincipal, RegisterVoteMetadata,
+    RequestResultMetadata, SetDissolveTimestampMetadata, SpawnMetadata, StakeMaturityMetadata,
+    Status, STATUS_COMPLETED,
 };
 use crate::transaction_id::TransactionIdentifier;
 use crate::{convert, errors};
@@ -91,7 +92,10 @@ pub fn operations_to_requests(
             .map_err(|e| op_error(o, e))?;
 
         let validate_neuron_management_op = || {
-            if o.amount.is_some() && o._type != OperationType::Disburse {
+            if o.amount.is_some()
+                && o._type != OperationType::Disburse
+                && o._type != OperationType::Split
+            {
                 Err(op_error(
                     o,
                     format!(
@@ -236,6 +240,43 @@ pub fn operations_to_requests(
                 };
                 state.follow(account, pid, neuron_index, topic, followees)?;
             }
+            OperationType::Split => {
+                let NeuronIdentifierMetadata { neuron_index } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                let amount = o
+                    .amount
+                    .as_ref()
+                    .ok_or_else(|| op_error(o, "Amount must be populated".into()))?;
+                let amount = ledgeramount_from_amount(amount, token_name).map_err(|e| {
+                    ApiError::internal_error(format!("Could not convert Amount {:?}", e))
+                })?;
+                state.split(account, neuron_index, amount)?;
+            }
+            OperationType::Merge => {
+                let MergeMetadata {
+                    source_neuron_id,
+                    neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                state.merge(account, neuron_index, source_neuron_id)?;
+            }
+            OperationType::StakeMaturity => {
+                let StakeMaturityMetadata {
+                    percentage_to_stake,
+                    neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                state.stake_maturity(account, neuron_index, percentage_to_stake)?;
+            }
+            OperationType::RegisterVote => {
+                let RegisterVoteMetadata {
+                    proposal,
+                    vote,
+                    neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                state.register_vote(account, neuron_index, proposal, vote)?;
+            }
         }
     }
 
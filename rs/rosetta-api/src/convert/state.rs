@@ -2,9 +2,11 @@ use crate::errors::ApiError;
 use crate::models::seconds::Seconds;
 use crate::request::Request;
 use crate::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RemoveHotKey,
-    SetDissolveTimestamp, Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, Follow, Merge, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal,
+    RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Split, Stake, StakeMaturity,
+    StartDissolve, StopDissolve,
 };
+use ic_nns_governance::pb::v1::Vote;
 use ic_types::PrincipalId;
 use ledger_canister::{Operation, Tokens, DEFAULT_TRANSFER_FEE};
 
@@ -320,6 +322,80 @@ impl State {
         }));
         Ok(())
     }
+
+    pub fn split(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        neuron_index: u64,
+        amount: Tokens,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::Split(Split {
+            account,
+            amount,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn merge(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        neuron_index: u64,
+        source_neuron_id: u64,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::Merge(Merge {
+            account,
+            source_neuron_id,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn stake_maturity(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        neuron_index: u64,
+        percentage_to_stake: Option<u32>,
+    ) -> Result<(), ApiError> {
+        if let Some(pct) = percentage_to_stake {
+            if !(1..=100).contains(&pct) {
+                let msg = format!("Invalid percentage to stake: {}", pct);
+                let err = ApiError::InvalidTransaction(false, msg.into());
+                return Err(err);
+            }
+        }
+        self.flush()?;
+        self.actions.push(Request::StakeMaturity(StakeMaturity {
+            account,
+            percentage_to_stake,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn register_vote(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        neuron_index: u64,
+        proposal: u64,
+        vote: i32,
+    ) -> Result<(), ApiError> {
+        if !matches!(Vote::from_i32(vote), Some(Vote::Yes) | Some(Vote::No)) {
+            let msg = format!("Invalid vote: {}", vote);
+            let err = ApiError::InvalidTransaction(false, msg.into());
+            return Err(err);
+        }
+        self.flush()?;
+        self.actions.push(Request::RegisterVote(RegisterVote {
+            account,
+            proposal,
+            vote,
+            neuron_index,
+        }));
+        Ok(())
+    }
 }
 
 /// Structure for manipulating tokens in relation to account, for example during transfers.
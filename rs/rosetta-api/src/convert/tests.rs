@@ -1,7 +1,7 @@
 use super::*;
 use crate::models::amount::signed_amount;
 use crate::models::operation::{OperationIdentifier, OperationType};
-use crate::request_types::Stake;
+use crate::request_types::{Merge, RegisterVote, Split, Stake, StakeMaturity};
 use crate::DEFAULT_TOKEN_SYMBOL;
 use ledger_canister::AccountIdentifier;
 use ledger_canister::Operation as LedgerOperation;
@@ -175,6 +175,53 @@ fn test_can_handle_multiple_transfers() {
     );
 }
 
+#[test]
+fn test_neuron_management_requests_round_trip() {
+    let requests = vec![
+        Request::Split(Split {
+            account: test_account(1),
+            amount: Tokens::from_e8s(500_000_000),
+            neuron_index: 1,
+        }),
+        Request::Merge(Merge {
+            account: test_account(1),
+            source_neuron_id: 42,
+            neuron_index: 1,
+        }),
+        Request::StakeMaturity(StakeMaturity {
+            account: test_account(1),
+            percentage_to_stake: Some(50),
+            neuron_index: 2,
+        }),
+        Request::RegisterVote(RegisterVote {
+            account: test_account(1),
+            proposal: 7,
+            vote: 1,
+            neuron_index: 2,
+        }),
+    ];
+    let ops = Request::requests_to_operations(&requests, DEFAULT_TOKEN_SYMBOL).unwrap();
+    assert_eq!(
+        operations_to_requests(&ops, false, DEFAULT_TOKEN_SYMBOL),
+        Ok(requests)
+    );
+}
+
+#[test]
+fn test_register_vote_rejects_unspecified_vote() {
+    let ops = Request::requests_to_operations(
+        &[Request::RegisterVote(RegisterVote {
+            account: test_account(1),
+            proposal: 7,
+            vote: 0,
+            neuron_index: 0,
+        })],
+        DEFAULT_TOKEN_SYMBOL,
+    )
+    .unwrap();
+    assert!(operations_to_requests(&ops, false, DEFAULT_TOKEN_SYMBOL).is_err());
+}
+
 #[test]
 fn account_identifier_decode_test() {
     // a good address
@@ -9,6 +9,7 @@ use serde_json::Value;
 use std::convert::TryFrom;
 
 use ic_ledger_canister_blocks_synchronizer::store::BlockStoreError;
+use ic_nns_governance::pb::v1::GovernanceError;
 
 /// Each Rosetta `Error` has a "retriable" flag and optional "details"
 /// Rosetta error code and message are determined by the `ApiError` variant.
@@ -280,6 +281,16 @@ impl From<ICError> for ApiError {
     }
 }
 
+impl From<GovernanceError> for ApiError {
+    fn from(e: GovernanceError) -> Self {
+        ApiError::ICError(ICError {
+            retriable: false,
+            error_message: format!("{}", e),
+            ic_http_status: 0,
+        })
+    }
+}
+
 impl From<&ICError> for Object {
     fn from(e: &ICError) -> Self {
         match serde_json::to_value(e) {
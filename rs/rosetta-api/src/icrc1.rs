@@ -0,0 +1,11 @@
+//! Serving an ICRC-1 ledger through the Rosetta API.
+//!
+//! ICRC-1 ledgers identify accounts by an owner principal and an optional
+//! subaccount, and expose their transactions through the `get_transactions`
+//! candid endpoint rather than through encoded blocks. This module provides
+//! the ledger client, the request handler and the routes used when the node
+//! is started with `--icrc1`. Only transfers can be constructed.
+pub mod convert;
+pub mod ledger_client;
+pub mod request_handler;
+pub mod routes;
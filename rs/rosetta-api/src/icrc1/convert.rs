@@ -0,0 +1,343 @@
+//! Conversions between ic_icrc1 data structures and Rosetta data structures.
+use crate::errors::ApiError;
+use crate::models::amount::Amount;
+use crate::models::operation::{Operation, OperationType};
+use crate::models::{self, AccountIdentifier, Currency, SubAccountIdentifier};
+use crate::request_types::STATUS_COMPLETED;
+use crate::transaction_id::TransactionIdentifier;
+use ic_icrc1::{Account, Block, Operation as Icrc1Operation, Subaccount, Transaction};
+use ic_ledger_canister_blocks_synchronizer::store::HashedBlock;
+use ic_ledger_canister_core::ledger::LedgerTransaction;
+use ic_ledger_core::block::BlockType;
+use ic_types::PrincipalId;
+use serde_json::map::Map;
+use serde_json::{Number, Value};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// The owner is the account address; a non-default subaccount is the
+/// address of the Rosetta subaccount.
+pub fn to_model_account_identifier(account: &Account) -> AccountIdentifier {
+    let mut aid = AccountIdentifier::new(account.owner.to_string());
+    if account.effective_subaccount() != ic_icrc1::DEFAULT_SUBACCOUNT {
+        aid.sub_account = Some(SubAccountIdentifier {
+            address: hex::encode(account.effective_subaccount()),
+            metadata: None,
+        });
+    }
+    aid
+}
+
+pub fn from_model_account_identifier(aid: &AccountIdentifier) -> Result<Account, String> {
+    let owner = PrincipalId::from_str(&aid.address)
+        .map_err(|e| format!("Invalid account owner {}: {}", aid.address, e))?;
+    let subaccount = match &aid.sub_account {
+        Some(sub_account) => {
+            let bytes = hex::decode(&sub_account.address)
+                .map_err(|e| format!("Invalid subaccount {}: {}", sub_account.address, e))?;
+            Some(Subaccount::try_from(&bytes[..]).map_err(|_| {
+                format!(
+                    "Invalid subaccount {}: expected 32 bytes, got {}",
+                    sub_account.address,
+                    bytes.len()
+                )
+            })?)
+        }
+        None => None,
+    };
+    Ok(Account { owner, subaccount })
+}
+
+pub fn signed_amount(amount: i128, currency: &Currency) -> Amount {
+    Amount::new(format!("{}", amount), currency.clone())
+}
+
+pub fn from_amount(amount: &Amount, currency: &Currency) -> Result<i128, String> {
+    match amount {
+        Amount {
+            value,
+            currency: c,
+            metadata: None,
+        } if c == currency => {
+            let val: i128 = value
+                .parse()
+                .map_err(|e| format!("Parsing amount failed: {}", e))?;
+            let _ =
+                u64::try_from(val.abs()).map_err(|_| "Amount does not fit in u64".to_string())?;
+            Ok(val)
+        }
+        wrong => Err(format!("This value is not {} {:?}", currency.symbol, wrong)),
+    }
+}
+
+pub fn transaction_identifier(tx: &Transaction) -> TransactionIdentifier {
+    TransactionIdentifier {
+        hash: format!("{}", tx.hash()),
+    }
+}
+
+/// Converts a ledger operation to Rosetta operations.
+///
+/// Approvals do not move tokens, so they only show up as the fee paid by
+/// the approver.
+pub fn operation_to_operations(operation: &Icrc1Operation, currency: &Currency) -> Vec<Operation> {
+    let op = |index: i64, _type, account: &Account, amount: i128| {
+        Operation::new(
+            index,
+            _type,
+            None,
+            Some(to_model_account_identifier(account)),
+            Some(signed_amount(amount, currency)),
+            None,
+        )
+    };
+    match operation {
+        Icrc1Operation::Mint { to, amount } => {
+            vec![op(0, OperationType::Mint, to, i128::from(*amount))]
+        }
+        Icrc1Operation::Burn { from, amount } => {
+            vec![op(0, OperationType::Burn, from, -i128::from(*amount))]
+        }
+        Icrc1Operation::Transfer {
+            from,
+            to,
+            amount,
+            fee,
+            ..
+        } => vec![
+            op(0, OperationType::Transaction, from, -i128::from(*amount)),
+            op(1, OperationType::Transaction, to, i128::from(*amount)),
+            op(2, OperationType::Fee, from, -i128::from(*fee)),
+        ],
+        Icrc1Operation::Approve {
+            from,
+            spender,
+            amount,
+            fee,
+            ..
+        } => {
+            let mut fee_op = op(0, OperationType::Fee, from, -i128::from(*fee));
+            let mut metadata = Map::new();
+            metadata.insert(
+                "approve_spender".to_string(),
+                serde_json::to_value(to_model_account_identifier(spender)).unwrap(),
+            );
+            metadata.insert(
+                "approve_amount".to_string(),
+                Value::Number(Number::from(*amount)),
+            );
+            fee_op.metadata = Some(metadata);
+            vec![fee_op]
+        }
+    }
+}
+
+pub fn block_to_transaction(
+    hb: &HashedBlock,
+    currency: &Currency,
+) -> Result<models::Transaction, ApiError> {
+    let block = Block::decode(hb.block.clone())
+        .map_err(|err| ApiError::internal_error(format!("Cannot decode block: {}", err)))?;
+    let transaction = block.transaction;
+    let mut operations = operation_to_operations(&transaction.operation, currency);
+    for op in operations.iter_mut() {
+        op.status = Some(STATUS_COMPLETED.to_string());
+    }
+    let mut t = models::Transaction::new(transaction_identifier(&transaction), operations);
+    let mut metadata = Map::new();
+    if let Some(memo) = transaction.memo {
+        metadata.insert(
+            "memo".to_string(),
+            Value::String(hex::encode(serde_bytes::ByteBuf::from(memo))),
+        );
+    }
+    if let Some(created_at_time) = transaction.created_at_time {
+        metadata.insert(
+            "created_at_time".to_string(),
+            Value::Number(Number::from(created_at_time)),
+        );
+    }
+    metadata.insert(
+        "block_height".to_string(),
+        Value::Number(Number::from(hb.index)),
+    );
+    metadata.insert(
+        "timestamp".to_string(),
+        Value::Number(Number::from(block.timestamp)),
+    );
+    t.metadata = Some(metadata);
+    Ok(t)
+}
+
+/// A transfer described by Rosetta operations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Icrc1Transfer {
+    pub from: Account,
+    pub to: Account,
+    pub amount: u64,
+    pub fee: Option<u64>,
+}
+
+impl Icrc1Transfer {
+    pub fn to_operations(&self, currency: &Currency) -> Vec<Operation> {
+        let mut ops = operation_to_operations(
+            &Icrc1Operation::Transfer {
+                from: self.from.clone(),
+                to: self.to.clone(),
+                spender: None,
+                amount: self.amount,
+                fee: self.fee.unwrap_or_default(),
+            },
+            currency,
+        );
+        if self.fee.is_none() {
+            ops.truncate(2);
+        }
+        ops
+    }
+}
+
+/// Convert from operations to a transfer.
+///
+/// The operations must describe exactly one transfer: a negative and a
+/// positive TRANSACTION operation of the same amount, optionally followed by
+/// a FEE operation on the sender account.
+pub fn operations_to_transfer(
+    ops: &[Operation],
+    currency: &Currency,
+) -> Result<Icrc1Transfer, ApiError> {
+    let op_error = |op: &Operation, e: String| {
+        let msg = format!("In operation '{:?}': {}", op, e);
+        ApiError::InvalidTransaction(false, msg.into())
+    };
+    let tx_error = |e: &str| ApiError::InvalidTransaction(false, e.into());
+
+    let mut from = None;
+    let mut to = None;
+    let mut fee = None;
+
+    for o in ops {
+        if o.coin_change.is_some() {
+            return Err(op_error(o, "Coin changes are not permitted".into()));
+        }
+        let account = o
+            .account
+            .as_ref()
+            .ok_or_else(|| op_error(o, "Account must be populated".into()))?;
+        let account = from_model_account_identifier(account).map_err(|e| op_error(o, e))?;
+        let amount = o
+            .amount
+            .as_ref()
+            .ok_or_else(|| op_error(o, "Amount must be populated".into()))?;
+        let amount = from_amount(amount, currency).map_err(|e| op_error(o, e))?;
+
+        let slot = match o._type {
+            OperationType::Transaction if amount < 0 => &mut from,
+            OperationType::Transaction => &mut to,
+            OperationType::Fee if amount <= 0 => &mut fee,
+            OperationType::Fee => {
+                return Err(op_error(o, "Fee should be a negative amount".into()));
+            }
+            _ => {
+                return Err(op_error(
+                    o,
+                    format!("Unsupported operation type: {}", o._type),
+                ));
+            }
+        };
+        if slot.is_some() {
+            return Err(op_error(o, "Only one transfer is supported".into()));
+        }
+        *slot = Some((account, amount.unsigned_abs() as u64));
+    }
+
+    let (from, debit) = from.ok_or_else(|| tx_error("Transfer has no debit operation"))?;
+    let (to, credit) = to.ok_or_else(|| tx_error("Transfer has no credit operation"))?;
+    if debit != credit {
+        return Err(tx_error("Debit and credit amounts of the transfer differ"));
+    }
+    let fee = match fee {
+        Some((payer, _)) if payer != from => {
+            return Err(tx_error("The fee must be paid by the sender"));
+        }
+        Some((_, fee)) => Some(fee),
+        None => None,
+    };
+
+    Ok(Icrc1Transfer {
+        from,
+        to,
+        amount: credit,
+        fee,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn currency() -> Currency {
+        Currency::new("XTKN".to_string(), 8)
+    }
+
+    fn account(n: u64, subaccount: Option<Subaccount>) -> Account {
+        Account {
+            owner: PrincipalId::new_user_test_id(n),
+            subaccount,
+        }
+    }
+
+    #[test]
+    fn account_identifier_round_trip() {
+        for acc in [
+            account(1, None),
+            account(2, Some([1; 32])),
+            account(3, Some([0; 32])),
+        ] {
+            let aid = to_model_account_identifier(&acc);
+            assert_eq!(from_model_account_identifier(&aid).unwrap(), acc);
+        }
+        assert_eq!(
+            to_model_account_identifier(&account(3, Some([0; 32]))).sub_account,
+            None
+        );
+        assert!(from_model_account_identifier(&AccountIdentifier::new("xyz".to_string())).is_err());
+    }
+
+    #[test]
+    fn transfer_operations_round_trip() {
+        let transfer = Icrc1Transfer {
+            from: account(1, Some([7; 32])),
+            to: account(2, None),
+            amount: 1_000,
+            fee: Some(10),
+        };
+        let ops = transfer.to_operations(&currency());
+        assert_eq!(ops.len(), 3);
+        assert_eq!(operations_to_transfer(&ops, &currency()).unwrap(), transfer);
+
+        let without_fee = Icrc1Transfer {
+            fee: None,
+            ..transfer
+        };
+        let ops = without_fee.to_operations(&currency());
+        assert_eq!(ops.len(), 2);
+        assert_eq!(
+            operations_to_transfer(&ops, &currency()).unwrap(),
+            without_fee
+        );
+    }
+
+    #[test]
+    fn transfer_operations_must_balance() {
+        let mut ops = Icrc1Transfer {
+            from: account(1, None),
+            to: account(2, None),
+            amount: 1_000,
+            fee: Some(10),
+        }
+        .to_operations(&currency());
+        ops[1].amount = Some(signed_amount(999, &currency()));
+        assert!(operations_to_transfer(&ops, &currency()).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use core::ops::Deref;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use candid::{Decode, Nat};
+use ic_icrc1::endpoints::TransferError;
+use ic_ledger_canister_blocks_synchronizer::icrc1_blocks::Icrc1Blocks;
+use ic_ledger_canister_blocks_synchronizer::icrc1_blocks_sync::Icrc1BlocksSynchronizer;
+use ic_ledger_canister_blocks_synchronizer::icrc1_canister_access::Icrc1CanisterAccess;
+use ic_ledger_canister_blocks_synchronizer::ledger_blocks_sync::LedgerBlocksSynchronizerMetrics;
+use ic_ledger_core::block::BlockIndex;
+use ic_types::messages::MessageId;
+use ic_types::CanisterId;
+use log::error;
+use num_traits::cast::ToPrimitive;
+use url::Url;
+
+use crate::errors::{ApiError, Details};
+use crate::ledger_client::{
+    envelope_canister_id, select_envelope_pair, serialize_envelope_pair, submit_update,
+    wait_for_reply, LedgerSync, SUBMIT_TIMEOUT,
+};
+use crate::models::{Currency, SignedTransaction};
+use crate::request_types::RequestType;
+
+struct Icrc1BlocksSynchronizerMetricsImpl {}
+
+impl LedgerBlocksSynchronizerMetrics for Icrc1BlocksSynchronizerMetricsImpl {
+    fn set_target_height(&self, height: u64) {
+        crate::rosetta_server::TARGET_HEIGHT.set(height as i64);
+    }
+
+    fn set_synced_height(&self, height: u64) {
+        crate::rosetta_server::SYNCED_HEIGHT.set(height as i64);
+    }
+
+    fn set_verified_height(&self, height: u64) {
+        crate::rosetta_server::VERIFIED_HEIGHT.set(height as i64);
+    }
+}
+
+/// A client of an ICRC-1 ledger.
+pub struct Icrc1LedgerClient {
+    blocks_synchronizer: Icrc1BlocksSynchronizer<Icrc1CanisterAccess>,
+    canister_id: CanisterId,
+    canister_access: Option<Arc<Icrc1CanisterAccess>>,
+    ic_url: Url,
+    token_symbol: String,
+    decimals: u32,
+    offline: bool,
+}
+
+impl Icrc1LedgerClient {
+    pub async fn new(
+        ic_url: Url,
+        canister_id: CanisterId,
+        token_symbol: String,
+        store_location: Option<&std::path::Path>,
+        offline: bool,
+    ) -> Result<Icrc1LedgerClient, ApiError> {
+        let (canister_access, decimals) = if offline {
+            (None, ic_ledger_core::tokens::DECIMAL_PLACES)
+        } else {
+            let canister_access = Icrc1CanisterAccess::new(ic_url.clone(), canister_id);
+            let symbol: String = canister_access
+                .query("icrc1_symbol", ())
+                .await
+                .map_err(|e| {
+                    ApiError::internal_error(format!(
+                        "Failed to fetch the token symbol from the ledger: {}",
+                        e
+                    ))
+                })?;
+            if symbol != token_symbol {
+                return Err(ApiError::internal_error(format!(
+                    "The ledger serves a different token ({}) than specified ({})",
+                    symbol, token_symbol
+                )));
+            }
+            let decimals: u8 = canister_access
+                .query("icrc1_decimals", ())
+                .await
+                .map_err(|e| {
+                    ApiError::internal_error(format!(
+                        "Failed to fetch the token decimals from the ledger: {}",
+                        e
+                    ))
+                })?;
+            (Some(Arc::new(canister_access)), decimals as u32)
+        };
+
+        let blocks_synchronizer = Icrc1BlocksSynchronizer::new(
+            canister_access.clone(),
+            store_location,
+            Box::new(Icrc1BlocksSynchronizerMetricsImpl {}),
+        )
+        .await?;
+
+        Ok(Self {
+            blocks_synchronizer,
+            canister_id,
+            canister_access,
+            ic_url,
+            token_symbol,
+            decimals,
+            offline,
+        })
+    }
+
+    pub async fn read_blocks(&self) -> Box<dyn Deref<Target = Icrc1Blocks> + '_> {
+        self.blocks_synchronizer.read_blocks().await
+    }
+
+    pub fn ledger_canister_id(&self) -> &CanisterId {
+        &self.canister_id
+    }
+
+    pub fn token_symbol(&self) -> &str {
+        &self.token_symbol
+    }
+
+    pub fn currency(&self) -> Currency {
+        Currency::new(self.token_symbol.clone(), self.decimals)
+    }
+
+    pub async fn transfer_fee(&self) -> Result<u64, ApiError> {
+        let canister_access = match &self.canister_access {
+            Some(canister_access) => canister_access,
+            None => return Err(ApiError::NotAvailableOffline(false, Details::default())),
+        };
+        let fee: Nat = canister_access
+            .query("icrc1_fee", ())
+            .await
+            .map_err(|e| ApiError::internal_error(format!("In icrc1_fee: {}", e)))?;
+        fee.0
+            .to_u64()
+            .ok_or_else(|| ApiError::internal_error(format!("Fee {} does not fit into u64", fee)))
+    }
+
+    /// Submits the signed transfers and returns the indices of the blocks
+    /// that recorded them.
+    ///
+    /// A transfer that the ledger reports as a duplicate is considered
+    /// successful: the block index is the one of the original transfer.
+    pub async fn submit(&self, envelopes: SignedTransaction) -> Result<Vec<BlockIndex>, ApiError> {
+        if self.offline {
+            return Err(ApiError::NotAvailableOffline(false, Details::default()));
+        }
+        let start_time = Instant::now();
+        let deadline = start_time + SUBMIT_TIMEOUT;
+        let http_client = reqwest::Client::new();
+
+        let mut block_indices = vec![];
+        for (request_type, request) in envelopes {
+            if request_type != RequestType::Send {
+                return Err(ApiError::invalid_request(format!(
+                    "Unsupported request type {:?}",
+                    request_type
+                )));
+            }
+            let pair = select_envelope_pair(request)?;
+            let canister_id = envelope_canister_id(&pair.update)?;
+            if canister_id != self.canister_id {
+                return Err(ApiError::invalid_request(format!(
+                    "The transaction targets canister {}, expected the ledger {}",
+                    canister_id, self.canister_id
+                )));
+            }
+            let request_id = MessageId::from(pair.update.content.representation_independent_hash());
+            let (http_body, read_state_http_body) =
+                serialize_envelope_pair(pair.update, pair.read_state)?;
+
+            submit_update(
+                &http_client,
+                &self.ic_url,
+                canister_id,
+                http_body,
+                start_time,
+                deadline,
+            )
+            .await?;
+
+            let bytes = match wait_for_reply(
+                &http_client,
+                &self.ic_url,
+                canister_id,
+                &request_id,
+                start_time,
+                deadline,
+                read_state_http_body,
+            )
+            .await
+            {
+                Ok(Ok(bytes)) => bytes,
+                Ok(Err(err)) => return Err(err),
+                Err(err) => {
+                    let e_msg = format!("Error submitting transaction {}: {}.", request_id, err);
+                    error!("{}", e_msg);
+                    return Err(ApiError::internal_error(e_msg));
+                }
+            };
+            block_indices.push(handle_transfer_reply(&bytes)?);
+        }
+        Ok(block_indices)
+    }
+}
+
+fn handle_transfer_reply(bytes: &[u8]) -> Result<BlockIndex, ApiError> {
+    let to_index = |n: Nat| {
+        n.0.to_u64().ok_or_else(|| {
+            ApiError::internal_error(format!("Block index {} does not fit into u64", n))
+        })
+    };
+    let reply = Decode!(bytes, Result<Nat, TransferError>).map_err(|e| {
+        ApiError::internal_error(format!("Cannot decode the icrc1_transfer reply: {}", e))
+    })?;
+    match reply {
+        Ok(block_index) => to_index(block_index),
+        Err(TransferError::Duplicate { duplicate_of }) => to_index(duplicate_of),
+        Err(err) => Err(ApiError::TransactionRejected(
+            false,
+            format!("The ledger rejected the transfer: {:?}", err).into(),
+        )),
+    }
+}
+
+#[async_trait]
+impl LedgerSync for Icrc1LedgerClient {
+    async fn sync_blocks(&self, stopped: Arc<AtomicBool>) -> Result<(), ApiError> {
+        if self.offline {
+            return Err(ApiError::NotAvailableOffline(false, Details::default()));
+        }
+        self.blocks_synchronizer
+            .sync_blocks(stopped, None)
+            .await
+            .map_err(ApiError::from)
+    }
+
+    async fn cleanup(&self) {}
+}
@@ -0,0 +1,582 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use candid::{Decode, Encode, Nat};
+use ic_icrc1::endpoints::TransferArg;
+use ic_icrc1::{Account, Block, Memo, Transaction};
+use ic_ledger_canister_blocks_synchronizer::icrc1_blocks::Icrc1Blocks;
+use ic_ledger_canister_blocks_synchronizer::store::HashedBlock;
+use ic_ledger_core::block::{BlockType, EncodedBlock, HashOf};
+use ic_ledger_core::timestamp::TimeStamp;
+use ic_types::messages::{Blob, HttpCallContent, HttpCanisterUpdate};
+use ic_types::PrincipalId;
+use num_traits::cast::ToPrimitive;
+use serde_json::map::Map;
+use serde_json::{Number, Value};
+
+use crate::errors::ApiError;
+use crate::icrc1::convert::{
+    self, from_model_account_identifier, operations_to_transfer, signed_amount,
+    to_model_account_identifier, Icrc1Transfer,
+};
+use crate::icrc1::ledger_client::Icrc1LedgerClient;
+use crate::models::operation::{Operation, OperationType};
+use crate::models::{
+    self, AccountBalanceRequest, AccountBalanceResponse, Allow, BlockIdentifier, BlockResponse,
+    BlockTransactionResponse, ConstructionCombineResponse, ConstructionDeriveResponse,
+    ConstructionHashRequest, ConstructionHashResponse, ConstructionMetadataRequest,
+    ConstructionMetadataRequestOptions, ConstructionMetadataResponse, ConstructionParseRequest,
+    ConstructionParseResponse, ConstructionPayloadsRequest, ConstructionPayloadsRequestMetadata,
+    ConstructionPayloadsResponse, ConstructionPreprocessRequest, ConstructionPreprocessResponse,
+    ConstructionSubmitRequest, ConstructionSubmitResponse, Error, MempoolResponse,
+    MempoolTransactionResponse, NetworkIdentifier, NetworkListResponse, NetworkOptionsResponse,
+    NetworkStatusResponse, OperationStatus, ParsedTransaction, PartialBlockIdentifier, PublicKey,
+    SyncStatus, UnsignedTransaction, Version,
+};
+use crate::request::transaction_operation_results::TransactionOperationResults;
+use crate::request_handler::{add_payloads, combine, ingress_expiries, verify_network_id};
+use crate::request_types::{RequestType, STATUS_COMPLETED};
+use crate::transaction_id::TransactionIdentifier;
+use crate::{API_VERSION, NODE_VERSION};
+
+/// Handles the Rosetta requests for an ICRC-1 ledger.
+#[derive(Clone)]
+pub struct Icrc1RequestHandler {
+    blockchain: String,
+    ledger: Arc<Icrc1LedgerClient>,
+}
+
+impl Icrc1RequestHandler {
+    pub fn new(blockchain: String, ledger: Arc<Icrc1LedgerClient>) -> Self {
+        Self { blockchain, ledger }
+    }
+
+    pub fn new_with_default_blockchain(ledger: Arc<Icrc1LedgerClient>) -> Self {
+        Self::new(crate::DEFAULT_BLOCKCHAIN.to_string(), ledger)
+    }
+
+    pub fn network_id(&self) -> NetworkIdentifier {
+        let canister_id = self.ledger.ledger_canister_id();
+        let net_id = hex::encode(canister_id.get().into_vec());
+        NetworkIdentifier::new(self.blockchain.clone(), net_id)
+    }
+
+    /// Get an Account Balance
+    pub async fn account_balance(
+        &self,
+        msg: AccountBalanceRequest,
+    ) -> Result<AccountBalanceResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        let account = from_model_account_identifier(&msg.account_identifier)
+            .map_err(ApiError::invalid_account_id)?;
+        let blocks = self.ledger.read_blocks().await;
+        let block = get_block(&blocks, msg.block_identifier)?;
+        let tokens = blocks.get_balance(&account, block.index)?;
+        Ok(AccountBalanceResponse {
+            block_identifier: crate::convert::block_id(&block)?,
+            balances: vec![signed_amount(
+                i128::from(tokens.get_e8s()),
+                &self.ledger.currency(),
+            )],
+            metadata: None,
+        })
+    }
+
+    /// Get a Block
+    pub async fn block(&self, msg: models::BlockRequest) -> Result<BlockResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        let blocks = self.ledger.read_blocks().await;
+        let hb = get_block(&blocks, Some(msg.block_identifier))?;
+        let block = Block::decode(hb.block.clone())
+            .map_err(|err| ApiError::internal_error(format!("Cannot decode block: {}", err)))?;
+        let b_id = crate::convert::block_id(&hb)?;
+        let parent_id = create_parent_block_id(&blocks, &hb)?;
+        let transactions = vec![convert::block_to_transaction(&hb, &self.ledger.currency())?];
+        Ok(BlockResponse {
+            block: Some(models::Block::new(
+                b_id,
+                parent_id,
+                models::timestamp::from_system_time(block.timestamp().into())?,
+                transactions,
+            )),
+            other_transactions: None,
+        })
+    }
+
+    /// Get a Block Transfer
+    pub async fn block_transaction(
+        &self,
+        msg: models::BlockTransactionRequest,
+    ) -> Result<BlockTransactionResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        let blocks = self.ledger.read_blocks().await;
+        let b_id = Some(PartialBlockIdentifier {
+            index: Some(msg.block_identifier.index),
+            hash: Some(msg.block_identifier.hash),
+        });
+        let hb = get_block(&blocks, b_id)?;
+        let transaction = convert::block_to_transaction(&hb, &self.ledger.currency())?;
+        Ok(BlockTransactionResponse::new(transaction))
+    }
+
+    /// Get All Mempool Transactions
+    pub async fn mempool(&self, msg: models::NetworkRequest) -> Result<MempoolResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        Ok(MempoolResponse::new(vec![]))
+    }
+
+    /// Get a Mempool Transfer
+    pub async fn mempool_transaction(
+        &self,
+        msg: models::MempoolTransactionRequest,
+    ) -> Result<MempoolTransactionResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        Err(ApiError::MempoolTransactionMissing(
+            false,
+            Default::default(),
+        ))
+    }
+
+    /// Get List of Available Networks
+    pub async fn network_list(
+        &self,
+        _metadata_request: models::MetadataRequest,
+    ) -> Result<NetworkListResponse, ApiError> {
+        Ok(NetworkListResponse::new(vec![self.network_id()]))
+    }
+
+    /// Get Network Options
+    pub async fn network_options(
+        &self,
+        msg: models::NetworkRequest,
+    ) -> Result<NetworkOptionsResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+
+        let mut errs = vec![
+            Error::new(&ApiError::InternalError(true, Default::default())),
+            Error::new(&ApiError::InvalidRequest(false, Default::default())),
+            Error::new(&ApiError::NotAvailableOffline(false, Default::default())),
+            Error::new(&ApiError::InvalidNetworkId(false, Default::default())),
+            Error::new(&ApiError::InvalidAccountId(false, Default::default())),
+            Error::new(&ApiError::InvalidBlockId(false, Default::default())),
+            Error::new(&ApiError::InvalidPublicKey(false, Default::default())),
+            Error::new(&ApiError::InvalidTransactionId(false, Default::default())),
+            Error::new(&ApiError::MempoolTransactionMissing(
+                false,
+                Default::default(),
+            )),
+            Error::new(&ApiError::BlockchainEmpty(false, Default::default())),
+            Error::new(&ApiError::InvalidTransaction(false, Default::default())),
+            Error::new(&ApiError::ICError(Default::default())),
+            Error::new(&ApiError::TransactionRejected(false, Default::default())),
+            Error::new(&ApiError::TransactionExpired),
+        ];
+        // We don't want to return any schema for details.
+        for e in errs.iter_mut() {
+            e.details = Default::default();
+        }
+
+        Ok(NetworkOptionsResponse::new(
+            Version::new(
+                API_VERSION.to_string(),
+                NODE_VERSION.to_string(),
+                None,
+                None,
+            ),
+            Allow::new(
+                vec![OperationStatus::new(STATUS_COMPLETED.to_string(), true)],
+                [
+                    OperationType::Transaction,
+                    OperationType::Mint,
+                    OperationType::Burn,
+                    OperationType::Fee,
+                ]
+                .iter()
+                .map(|op| op.to_string())
+                .collect(),
+                errs,
+                true,
+            ),
+        ))
+    }
+
+    /// Get Network Status
+    pub async fn network_status(
+        &self,
+        msg: models::NetworkRequest,
+    ) -> Result<NetworkStatusResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        let blocks = self.ledger.read_blocks().await;
+        let tip = blocks
+            .last()?
+            .ok_or_else(|| ApiError::BlockchainEmpty(true, Default::default()))?;
+        let tip_id = crate::convert::block_id(&tip)?;
+        let tip_timestamp = models::timestamp::from_system_time(
+            Block::decode(tip.block).unwrap().timestamp().into(),
+        )?;
+        // Block at index 0 has to be there if tip was present
+        let genesis_block = blocks.get_synced_at(0)?;
+        let genesis_block_id = crate::convert::block_id(&genesis_block)?;
+
+        let mut sync_status = SyncStatus::new(tip.index as i64, None);
+        let target = crate::rosetta_server::TARGET_HEIGHT.get();
+        if target != 0 {
+            sync_status.target_index = Some(target);
+        }
+
+        Ok(NetworkStatusResponse::new(
+            tip_id,
+            tip_timestamp,
+            genesis_block_id,
+            None,
+            sync_status,
+            vec![],
+        ))
+    }
+
+    /// Derive an AccountIdentifier from a PublicKey.
+    /// See https://www.rosetta-api.org/docs/ConstructionApi.html#constructionderive
+    pub fn construction_derive(
+        &self,
+        msg: models::ConstructionDeriveRequest,
+    ) -> Result<ConstructionDeriveResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        let account = Account {
+            owner: crate::convert::principal_id_from_public_key(&msg.public_key)?,
+            subaccount: None,
+        };
+        Ok(ConstructionDeriveResponse {
+            account_identifier: Some(to_model_account_identifier(&account)),
+            address: None,
+            metadata: None,
+        })
+    }
+
+    /// Create a Request to Fetch Metadata.
+    /// See https://www.rosetta-api.org/docs/ConstructionApi.html#constructionpreprocess
+    pub fn construction_preprocess(
+        &self,
+        msg: ConstructionPreprocessRequest,
+    ) -> Result<ConstructionPreprocessResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        let transfer = operations_to_transfer(&msg.operations, &self.ledger.currency())?;
+        let owner = Account {
+            owner: transfer.from.owner,
+            subaccount: None,
+        };
+        Ok(ConstructionPreprocessResponse {
+            options: Some(ConstructionMetadataRequestOptions {
+                request_types: vec![RequestType::Send],
+            }),
+            required_public_keys: Some(vec![to_model_account_identifier(&owner)]),
+        })
+    }
+
+    /// Get Metadata for Transaction Construction.
+    /// See https://www.rosetta-api.org/docs/ConstructionApi.html#constructionmetadata
+    pub async fn construction_metadata(
+        &self,
+        msg: ConstructionMetadataRequest,
+    ) -> Result<ConstructionMetadataResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        let fee = self.ledger.transfer_fee().await?;
+        Ok(ConstructionMetadataResponse {
+            metadata: ConstructionPayloadsRequestMetadata::default(),
+            suggested_fee: Some(vec![signed_amount(
+                i128::from(fee),
+                &self.ledger.currency(),
+            )]),
+        })
+    }
+
+    /// Generate an Unsigned Transaction and Signing Payloads.
+    /// See https://www.rosetta-api.org/docs/ConstructionApi.html#constructionpayloads
+    /// The unsigned_transaction returned from this function is a CBOR
+    /// serialized UnsignedTransaction containing a single `icrc1_transfer`
+    /// call.
+    pub fn construction_payloads(
+        &self,
+        msg: ConstructionPayloadsRequest,
+    ) -> Result<ConstructionPayloadsResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+
+        let pks = msg.public_keys.clone().ok_or_else(|| {
+            ApiError::internal_error("Expected field 'public_keys' to be populated")
+        })?;
+        let transfer = operations_to_transfer(&msg.operations, &self.ledger.currency())?;
+        let fee = transfer.fee.ok_or_else(|| {
+            ApiError::InvalidTransaction(false, "A FEE operation is required".into())
+        })?;
+
+        let meta = msg.metadata.as_ref();
+        let ingress_expiries = ingress_expiries(meta);
+        let created_at_time: TimeStamp = meta
+            .and_then(|meta| meta.created_at_time)
+            .map(TimeStamp::from_nanos_since_unix_epoch)
+            .unwrap_or_else(|| std::time::SystemTime::now().into());
+
+        let pks_map = pks
+            .iter()
+            .map(|pk| Ok((crate::convert::principal_id_from_public_key(pk)?, pk)))
+            .collect::<Result<HashMap<PrincipalId, &PublicKey>, ApiError>>()?;
+        let pk = pks_map.get(&transfer.from.owner).ok_or_else(|| {
+            ApiError::internal_error(format!(
+                "Cannot find public key for principal {}",
+                transfer.from.owner,
+            ))
+        })?;
+
+        let transfer_arg = TransferArg {
+            from_subaccount: transfer.from.subaccount,
+            to: transfer.to.clone(),
+            fee: Some(Nat::from(fee)),
+            created_at_time: Some(created_at_time.as_nanos_since_unix_epoch()),
+            memo: meta.and_then(|meta| meta.memo).map(Memo::from),
+            amount: Nat::from(transfer.amount),
+        };
+
+        let update = HttpCanisterUpdate {
+            canister_id: Blob(self.ledger.ledger_canister_id().get().to_vec()),
+            method_name: "icrc1_transfer".to_string(),
+            arg: Blob(Encode!(&transfer_arg).map_err(|e| {
+                ApiError::internal_error(format!("Cannot encode the transfer: {}", e))
+            })?),
+            // We don't use a nonce: the created_at_time already prevents the
+            // ledger from executing the same transfer twice.
+            nonce: None,
+            sender: Blob(crate::convert::principal_id_from_public_key(pk)?.into_vec()),
+            ingress_expiry: 0,
+        };
+
+        let mut payloads = vec![];
+        add_payloads(
+            &mut payloads,
+            &ingress_expiries,
+            &to_model_account_identifier(&transfer.from),
+            &update,
+        );
+
+        Ok(ConstructionPayloadsResponse::new(
+            &UnsignedTransaction {
+                updates: vec![(RequestType::Send, update)],
+                ingress_expiries,
+            },
+            payloads,
+        ))
+    }
+
+    /// Create Network Transaction from Signatures.
+    /// See https://www.rosetta-api.org/docs/ConstructionApi.html#constructioncombine
+    pub fn construction_combine(
+        &self,
+        msg: models::ConstructionCombineRequest,
+    ) -> Result<ConstructionCombineResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        combine(msg)
+    }
+
+    /// Parse a Transaction.
+    /// See https://www.rosetta-api.org/docs/ConstructionApi.html#constructionparse
+    pub fn construction_parse(
+        &self,
+        msg: ConstructionParseRequest,
+    ) -> Result<ConstructionParseResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        let updates = match msg.transaction()? {
+            ParsedTransaction::Signed(envelopes) => envelopes
+                .into_iter()
+                .map(
+                    |(_, pairs)| match pairs.into_iter().next().map(|p| p.update.content) {
+                        Some(HttpCallContent::Call { update }) => Ok(update),
+                        None => Err(ApiError::invalid_request("Missing envelope")),
+                    },
+                )
+                .collect::<Result<Vec<_>, _>>()?,
+            ParsedTransaction::Unsigned(unsigned) => {
+                unsigned.updates.into_iter().map(|(_, u)| u).collect()
+            }
+        };
+
+        let currency = self.ledger.currency();
+        let mut operations: Vec<Operation> = vec![];
+        let mut signers = vec![];
+        for update in &updates {
+            let (transfer, _) = decode_transfer(update)?;
+            let signer = Account {
+                owner: transfer.from.owner,
+                subaccount: None,
+            };
+            if msg.signed && !signers.contains(&signer) {
+                signers.push(signer);
+            }
+            operations.extend(transfer.to_operations(&currency));
+        }
+        for (i, op) in operations.iter_mut().enumerate() {
+            op.operation_identifier.index = i as i64;
+        }
+
+        Ok(ConstructionParseResponse {
+            operations,
+            signers: None,
+            account_identifier_signers: Some(
+                signers.iter().map(to_model_account_identifier).collect(),
+            ),
+            metadata: None,
+        })
+    }
+
+    /// Get the Hash of a Signed Transaction.
+    /// See https://www.rosetta-api.org/docs/ConstructionApi.html#constructionhash
+    pub fn construction_hash(
+        &self,
+        msg: ConstructionHashRequest,
+    ) -> Result<ConstructionHashResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        let envelopes = msg.signed_transaction()?;
+        let update = last_update(&envelopes)?;
+        Ok(ConstructionHashResponse {
+            transaction_identifier: convert::transaction_identifier(&ledger_transaction(&update)?),
+            metadata: Map::new(),
+        })
+    }
+
+    /// Submit a Signed Transaction.
+    /// See https://www.rosetta-api.org/docs/ConstructionApi.html#constructionsubmit
+    pub async fn construction_submit(
+        &self,
+        msg: ConstructionSubmitRequest,
+    ) -> Result<ConstructionSubmitResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        let envelopes = msg.signed_transaction()?;
+        let update = last_update(&envelopes)?;
+        let transaction_identifier = convert::transaction_identifier(&ledger_transaction(&update)?);
+        let (transfer, _) = decode_transfer(&update)?;
+
+        let block_indices = self.ledger.submit(envelopes).await?;
+
+        let mut operations = transfer.to_operations(&self.ledger.currency());
+        for op in operations.iter_mut() {
+            op.status = Some(STATUS_COMPLETED.to_string());
+            if let Some(block_index) = block_indices.last() {
+                let mut metadata = Map::new();
+                metadata.insert(
+                    "block_index".to_string(),
+                    Value::Number(Number::from(*block_index)),
+                );
+                op.metadata = Some(metadata);
+            }
+        }
+        Ok(ConstructionSubmitResponse {
+            transaction_identifier,
+            metadata: TransactionOperationResults { operations },
+        })
+    }
+}
+
+/// Returns the update call of the last request of a signed transaction.
+fn last_update(envelopes: &models::SignedTransaction) -> Result<HttpCanisterUpdate, ApiError> {
+    match envelopes
+        .last()
+        .and_then(|(_, pairs)| pairs.first())
+        .map(|pair| pair.update.content.clone())
+    {
+        Some(HttpCallContent::Call { update }) => Ok(update),
+        None => Err(ApiError::invalid_request(
+            "There is no hash for this transaction",
+        )),
+    }
+}
+
+/// Decodes the transfer made by an `icrc1_transfer` call.
+fn decode_transfer(update: &HttpCanisterUpdate) -> Result<(Icrc1Transfer, TransferArg), ApiError> {
+    if update.method_name != "icrc1_transfer" {
+        return Err(ApiError::invalid_request(format!(
+            "Unsupported method {}",
+            update.method_name
+        )));
+    }
+    let arg = Decode!(&update.arg.0, TransferArg)
+        .map_err(|e| ApiError::invalid_request(format!("Cannot decode the transfer: {}", e)))?;
+    let owner = PrincipalId::try_from(update.sender.0.clone())
+        .map_err(|e| ApiError::internal_error(e.to_string()))?;
+    let to_u64 = |n: &Nat| {
+        n.0.to_u64()
+            .ok_or_else(|| ApiError::invalid_request(format!("{} does not fit into u64", n)))
+    };
+    let transfer = Icrc1Transfer {
+        from: Account {
+            owner,
+            subaccount: arg.from_subaccount,
+        },
+        to: arg.to.clone(),
+        amount: to_u64(&arg.amount)?,
+        fee: arg.fee.as_ref().map(to_u64).transpose()?,
+    };
+    Ok((transfer, arg))
+}
+
+/// Rebuilds the transaction that the ledger records for an `icrc1_transfer`
+/// call.
+fn ledger_transaction(update: &HttpCanisterUpdate) -> Result<Transaction, ApiError> {
+    let (transfer, arg) = decode_transfer(update)?;
+    let fee = transfer.fee.ok_or_else(|| {
+        ApiError::invalid_request("There is no hash for a transfer without a fee")
+    })?;
+    Ok(Transaction::transfer(
+        transfer.from,
+        transfer.to,
+        ic_ledger_core::Tokens::from_e8s(transfer.amount),
+        ic_ledger_core::Tokens::from_e8s(fee),
+        arg.created_at_time
+            .map(TimeStamp::from_nanos_since_unix_epoch),
+        arg.memo,
+    ))
+}
+
+fn create_parent_block_id(
+    blocks: &Icrc1Blocks,
+    block: &HashedBlock,
+) -> Result<BlockIdentifier, ApiError> {
+    // For the first block, we return the block itself as its parent
+    let parent = blocks.get_synced_at(block.index.saturating_sub(1))?;
+    crate::convert::block_id(&parent)
+}
+
+fn get_block(
+    blocks: &Icrc1Blocks,
+    block_id: Option<PartialBlockIdentifier>,
+) -> Result<HashedBlock, ApiError> {
+    let to_index = |height: i64| {
+        u64::try_from(height).map_err(|_| ApiError::InvalidBlockId(false, Default::default()))
+    };
+    let block = match block_id {
+        Some(PartialBlockIdentifier {
+            index: Some(block_height),
+            hash: Some(block_hash),
+        }) => {
+            let hash: HashOf<EncodedBlock> = crate::convert::to_hash(&block_hash)?;
+            let block = blocks.get_synced_at(to_index(block_height)?)?;
+            if block.hash != hash {
+                return Err(ApiError::InvalidBlockId(false, Default::default()));
+            }
+            block
+        }
+        Some(PartialBlockIdentifier {
+            index: Some(block_height),
+            hash: None,
+        }) => blocks.get_synced_at(to_index(block_height)?)?,
+        Some(PartialBlockIdentifier {
+            index: None,
+            hash: Some(block_hash),
+        }) => blocks.get_synced(crate::convert::to_hash(&block_hash)?)?,
+        Some(PartialBlockIdentifier {
+            index: None,
+            hash: None,
+        })
+        | None => blocks
+            .last()?
+            .ok_or_else(|| ApiError::BlockchainEmpty(false, Default::default()))?,
+    };
+    Ok(block)
+}
@@ -0,0 +1,182 @@
+//! The Rosetta endpoints served for an ICRC-1 ledger.
+use actix_web::{post, web, HttpResponse};
+
+use crate::icrc1::request_handler::Icrc1RequestHandler;
+use crate::models::*;
+use crate::rosetta_server::{to_rosetta_response, ENDPOINTS_METRICS};
+
+#[post("/account/balance")]
+async fn account_balance(
+    msg: web::Json<AccountBalanceRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let _timer = ENDPOINTS_METRICS
+        .request_duration
+        .with_label_values(&["account/balance"])
+        .start_timer();
+    let res = req_handler.account_balance(msg.into_inner()).await;
+    to_rosetta_response(res)
+}
+
+#[post("/block")]
+async fn block(
+    msg: web::Json<BlockRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let _timer = ENDPOINTS_METRICS
+        .request_duration
+        .with_label_values(&["block"])
+        .start_timer();
+    let res = req_handler.block(msg.into_inner()).await;
+    to_rosetta_response(res)
+}
+
+#[post("/block/transaction")]
+async fn block_transaction(
+    msg: web::Json<BlockTransactionRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.block_transaction(msg.into_inner()).await;
+    to_rosetta_response(res)
+}
+
+#[post("/construction/combine")]
+async fn construction_combine(
+    msg: web::Json<ConstructionCombineRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.construction_combine(msg.into_inner());
+    to_rosetta_response(res)
+}
+
+#[post("/construction/derive")]
+async fn construction_derive(
+    msg: web::Json<ConstructionDeriveRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.construction_derive(msg.into_inner());
+    to_rosetta_response(res)
+}
+
+#[post("/construction/hash")]
+async fn construction_hash(
+    msg: web::Json<ConstructionHashRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.construction_hash(msg.into_inner());
+    to_rosetta_response(res)
+}
+
+#[post("/construction/metadata")]
+async fn construction_metadata(
+    msg: web::Json<ConstructionMetadataRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.construction_metadata(msg.into_inner()).await;
+    to_rosetta_response(res)
+}
+
+#[post("/construction/parse")]
+async fn construction_parse(
+    msg: web::Json<ConstructionParseRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.construction_parse(msg.into_inner());
+    to_rosetta_response(res)
+}
+
+#[post("/construction/payloads")]
+async fn construction_payloads(
+    msg: web::Json<ConstructionPayloadsRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.construction_payloads(msg.into_inner());
+    to_rosetta_response(res)
+}
+
+#[post("/construction/preprocess")]
+async fn construction_preprocess(
+    msg: web::Json<ConstructionPreprocessRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.construction_preprocess(msg.into_inner());
+    to_rosetta_response(res)
+}
+
+#[post("/construction/submit")]
+async fn construction_submit(
+    msg: web::Json<ConstructionSubmitRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let _timer = ENDPOINTS_METRICS
+        .request_duration
+        .with_label_values(&["construction/submit"])
+        .start_timer();
+    let res = req_handler.construction_submit(msg.into_inner()).await;
+    to_rosetta_response(res)
+}
+
+#[post("/network/list")]
+async fn network_list(
+    msg: web::Json<MetadataRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.network_list(msg.into_inner()).await;
+    to_rosetta_response(res)
+}
+
+#[post("/network/options")]
+async fn network_options(
+    msg: web::Json<NetworkRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.network_options(msg.into_inner()).await;
+    to_rosetta_response(res)
+}
+
+#[post("/network/status")]
+async fn network_status(
+    msg: web::Json<NetworkRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.network_status(msg.into_inner()).await;
+    to_rosetta_response(res)
+}
+
+#[post("/mempool")]
+async fn mempool(
+    msg: web::Json<NetworkRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.mempool(msg.into_inner()).await;
+    to_rosetta_response(res)
+}
+
+#[post("/mempool/transaction")]
+async fn mempool_transaction(
+    msg: web::Json<MempoolTransactionRequest>,
+    req_handler: web::Data<Icrc1RequestHandler>,
+) -> HttpResponse {
+    let res = req_handler.mempool_transaction(msg.into_inner()).await;
+    to_rosetta_response(res)
+}
+
+/// Registers the endpoints that are supported for ICRC-1 ledgers.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(account_balance)
+        .service(block)
+        .service(block_transaction)
+        .service(construction_combine)
+        .service(construction_derive)
+        .service(construction_hash)
+        .service(construction_metadata)
+        .service(construction_parse)
+        .service(construction_payloads)
+        .service(construction_preprocess)
+        .service(construction_submit)
+        .service(network_list)
+        .service(network_options)
+        .service(network_status)
+        .service(mempool)
+        .service(mempool_transaction);
+}
@@ -1,16 +1,22 @@
 mod handle_add_hotkey;
 mod handle_disburse;
 mod handle_follow;
+mod handle_merge;
 mod handle_merge_maturity;
 mod handle_neuron_info;
+mod handle_register_vote;
 mod handle_remove_hotkey;
 mod handle_send;
 mod handle_set_dissolve_timestamp;
 mod handle_spawn;
+mod handle_split;
 mod handle_stake;
+mod handle_stake_maturity;
 mod handle_start_dissolve;
 mod handle_stop_dissolve;
 mod neuron_response;
+#[cfg(test)]
+mod tests;
 
 use core::ops::Deref;
 use std::convert::TryFrom;
@@ -30,8 +36,11 @@ use ic_ledger_canister_blocks_synchronizer::certification::VerificationInfo;
 use ic_ledger_canister_blocks_synchronizer::ledger_blocks_sync::{
     LedgerBlocksSynchronizer, LedgerBlocksSynchronizerMetrics,
 };
-use ic_nns_governance::pb::v1::{manage_neuron::NeuronIdOrSubaccount, GovernanceError, NeuronInfo};
-use ic_types::messages::{HttpCallContent, MessageId};
+use ic_nns_common::pb::v1::ProposalId;
+use ic_nns_governance::pb::v1::{
+    manage_neuron::NeuronIdOrSubaccount, GovernanceError, NeuronInfo, ProposalInfo,
+};
+use ic_types::messages::{HttpCallContent, HttpReadStateContent, HttpRequestEnvelope, MessageId};
 use ic_types::CanisterId;
 use ic_types::{crypto::threshold_sig::ThresholdSigPublicKey, messages::SignedRequestBytes};
 use ledger_canister::{BlockIndex, Symbol, TransferFee, TransferFeeArgs, DEFAULT_TRANSFER_FEE};
@@ -42,11 +51,13 @@ use crate::errors::{ApiError, Details, ICError};
 use crate::ledger_client::neuron_response::NeuronResponse;
 use crate::ledger_client::{
     handle_add_hotkey::handle_add_hotkey, handle_disburse::handle_disburse,
-    handle_follow::handle_follow, handle_merge_maturity::handle_merge_maturity,
-    handle_neuron_info::handle_neuron_info, handle_remove_hotkey::handle_remove_hotkey,
+    handle_follow::handle_follow, handle_merge::handle_merge,
+    handle_merge_maturity::handle_merge_maturity, handle_neuron_info::handle_neuron_info,
+    handle_register_vote::handle_register_vote, handle_remove_hotkey::handle_remove_hotkey,
     handle_send::handle_send, handle_set_dissolve_timestamp::handle_set_dissolve_timestamp,
-    handle_spawn::handle_spawn, handle_stake::handle_stake,
-    handle_start_dissolve::handle_start_dissolve, handle_stop_dissolve::handle_stop_dissolve,
+    handle_spawn::handle_spawn, handle_split::handle_split, handle_stake::handle_stake,
+    handle_stake_maturity::handle_stake_maturity, handle_start_dissolve::handle_start_dissolve,
+    handle_stop_dissolve::handle_stop_dissolve,
 };
 use crate::models::{EnvelopePair, Object, SignedTransaction};
 use crate::request::request_result::RequestResult;
@@ -81,14 +92,35 @@ pub trait LedgerAccess {
     fn token_symbol(&self) -> &str;
     async fn submit(&self, _envelopes: SignedTransaction) -> Result<TransactionResults, ApiError>;
     async fn cleanup(&self);
+    /// Returns the information of a neuron, or the error returned by
+    /// governance (e.g. `ErrorType::NotFound` for unknown neurons).
     async fn neuron_info(
         &self,
         acc_id: NeuronIdOrSubaccount,
         verified: bool,
-    ) -> Result<NeuronInfo, ApiError>;
+    ) -> Result<Result<NeuronInfo, GovernanceError>, ApiError>;
+    async fn proposal_info(&self, proposal_id: u64) -> Result<ProposalInfo, ApiError>;
     async fn transfer_fee(&self) -> Result<TransferFee, ApiError>;
 }
 
+/// The part of a ledger client that the server's background sync loop uses.
+#[async_trait]
+pub trait LedgerSync {
+    async fn sync_blocks(&self, stopped: Arc<AtomicBool>) -> Result<(), ApiError>;
+    async fn cleanup(&self);
+}
+
+#[async_trait]
+impl<T: LedgerAccess + Send + Sync> LedgerSync for T {
+    async fn sync_blocks(&self, stopped: Arc<AtomicBool>) -> Result<(), ApiError> {
+        LedgerAccess::sync_blocks(self, stopped).await
+    }
+
+    async fn cleanup(&self) {
+        LedgerAccess::cleanup(self).await
+    }
+}
+
 pub struct LedgerClient {
     ledger_blocks_synchronizer: LedgerBlocksSynchronizer<CanisterAccess>,
     canister_id: CanisterId,
@@ -269,7 +301,7 @@ impl LedgerAccess for LedgerClient {
         &self,
         acc_id: NeuronIdOrSubaccount,
         verified: bool,
-    ) -> Result<NeuronInfo, ApiError> {
+    ) -> Result<Result<NeuronInfo, GovernanceError>, ApiError> {
         if self.offline {
             return Err(ApiError::NotAvailableOffline(false, Details::default()));
         }
@@ -310,26 +342,40 @@ impl LedgerAccess for LedgerClient {
         })?;
         let ninfo: Result<Result<NeuronInfo, GovernanceError>, _> =
             CandidOne::from_bytes(bytes).map(|c| c.0);
-        let ninfo = ninfo.map_err(|e| {
+        ninfo.map_err(|e| {
             ApiError::internal_error(format!(
                 "Deserialization of get_neuron_info response failed: {:?}",
                 e
             ))
-        })?;
+        })
+    }
 
-        // TODO consider adding new error types to ApiError to match error codes from
-        // GovernanceError::error_type (e.g. NotFound)
-        // (this may be more useful for management, since that's when we want to
-        // communicate errors clearly)
-        let ninfo = ninfo.map_err(|e| {
-            ApiError::ICError(ICError {
-                retriable: false,
-                error_message: format!("{}", e),
-                ic_http_status: 0,
-            })
-        })?;
+    async fn proposal_info(&self, proposal_id: u64) -> Result<ProposalInfo, ApiError> {
+        if self.offline {
+            return Err(ApiError::NotAvailableOffline(false, Details::default()));
+        }
 
-        Ok(ninfo)
+        let agent = &self.canister_access.as_ref().unwrap().agent;
+
+        let arg = CandidOne(ProposalId { id: proposal_id })
+            .into_bytes()
+            .map_err(|e| ApiError::internal_error(format!("Serialization failed: {:?}", e)))?;
+        let bytes = agent
+            .execute_query(&self.governance_canister_id, "get_proposal_info", arg)
+            .await
+            .map_err(ApiError::internal_error)?
+            .ok_or_else(|| {
+                ApiError::internal_error("proposal_info reply payload was empty".to_string())
+            })?;
+        let pinfo: Option<ProposalInfo> =
+            CandidOne::from_bytes(bytes).map(|c| c.0).map_err(|e| {
+                ApiError::internal_error(format!(
+                    "Deserialization of get_proposal_info response failed: {:?}",
+                    e
+                ))
+            })?;
+        pinfo
+            .ok_or_else(|| ApiError::invalid_request(format!("Proposal {} not found", proposal_id)))
     }
 
     async fn transfer_fee(&self) -> Result<TransferFee, ApiError> {
@@ -371,13 +417,13 @@ impl LedgerAccess for LedgerClient {
     }
 }
 
-impl LedgerClient {
-    // Exponential backoff from 100ms to 10s with a multiplier of 1.3.
-    const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
-    const MAX_POLL_INTERVAL: Duration = Duration::from_secs(10);
-    const POLL_INTERVAL_MULTIPLIER: f32 = 1.3;
-    const TIMEOUT: Duration = Duration::from_secs(20);
+// Exponential backoff from 100ms to 10s with a multiplier of 1.3.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const POLL_INTERVAL_MULTIPLIER: f32 = 1.3;
+pub(crate) const SUBMIT_TIMEOUT: Duration = Duration::from_secs(20);
 
+impl LedgerClient {
     async fn do_request(
         &self,
         http_client: &Client,
@@ -386,31 +432,9 @@ impl LedgerClient {
         request: Vec<EnvelopePair>,
         result: &mut RequestResult,
     ) -> Result<(), ApiError> {
-        // Pick the update/read-start message that is currently valid.
-        let now = ic_types::time::current_time();
-        let deadline = start_time + Self::TIMEOUT;
-
-        let EnvelopePair { update, read_state } = request
-            .clone()
-            .into_iter()
-            .find(|EnvelopePair { update, .. }| {
-                let ingress_expiry =
-                    ic_types::Time::from_nanos_since_unix_epoch(update.content.ingress_expiry());
-                let ingress_start = ingress_expiry
-                    - (ic_constants::MAX_INGRESS_TTL - ic_constants::PERMITTED_DRIFT);
-                ingress_start <= now && ingress_expiry > now
-            })
-            .ok_or(ApiError::TransactionExpired)?;
-
-        let canister_id = match &update.content {
-            HttpCallContent::Call { update } => CanisterId::try_from(update.canister_id.0.clone())
-                .map_err(|e| {
-                    ApiError::internal_error(format!(
-                        "Cannot parse canister ID found in submit call: {}",
-                        e
-                    ))
-                })?,
-        };
+        let deadline = start_time + SUBMIT_TIMEOUT;
+        let EnvelopePair { update, read_state } = select_envelope_pair(request)?;
+        let canister_id = envelope_canister_id(&update)?;
 
         let request_id = MessageId::from(update.content.representation_independent_hash());
         let txn_id = TransactionIdentifier::try_from_envelope(request_type.clone(), &update)?;
@@ -419,72 +443,17 @@ impl LedgerClient {
             result.transaction_identifier = Some(txn_id.clone());
         }
 
-        let http_body = SignedRequestBytes::try_from(update).map_err(|e| {
-            ApiError::internal_error(format!(
-                "Cannot serialize the submit request in CBOR format because of: {}",
-                e
-            ))
-        })?;
-
-        let read_state_http_body = SignedRequestBytes::try_from(read_state).map_err(|e| {
-            ApiError::internal_error(format!(
-                "Cannot serialize the read state request in CBOR format because of: {}",
-                e
-            ))
-        })?;
-
-        let url = self
-            .ic_url
-            .join(&ic_canister_client::update_path(canister_id))
-            .expect("URL join failed");
-
-        // Submit the update call (with retry).
-        let mut poll_interval = Self::MIN_POLL_INTERVAL;
+        let (http_body, read_state_http_body) = serialize_envelope_pair(update, read_state)?;
 
-        while Instant::now() + poll_interval < deadline {
-            let wait_timeout = Self::TIMEOUT - start_time.elapsed();
-
-            match send_post_request(
-                http_client,
-                url.as_str(),
-                http_body.clone().into(),
-                wait_timeout,
-            )
-            .await
-            {
-                Err(err) => {
-                    // Retry client-side errors.
-                    error!("Error while submitting transaction: {}.", err);
-                }
-                Ok((body, status)) => {
-                    if status.is_success() {
-                        break;
-                    }
-                    // Retry on 5xx errors. We don't want to retry on
-                    // e.g. authentication errors.
-                    let body =
-                        String::from_utf8(body).unwrap_or_else(|_| "<undecodable>".to_owned());
-                    if status.is_server_error() {
-                        error!(
-                            "HTTP error {} while submitting transaction: {}.",
-                            status, body
-                        );
-                    } else {
-                        return Err(ApiError::ICError(ICError {
-                            retriable: false,
-                            ic_http_status: status.as_u16(),
-                            error_message: body,
-                        }));
-                    }
-                }
-            }
-
-            // Bump the poll interval and compute the next poll time (based on current wall
-            // time, so we don't spin without delay after a slow poll).
-            poll_interval = poll_interval
-                .mul_f32(Self::POLL_INTERVAL_MULTIPLIER)
-                .min(Self::MAX_POLL_INTERVAL);
-        }
+        submit_update(
+            http_client,
+            &self.ic_url,
+            canister_id,
+            http_body,
+            start_time,
+            deadline,
+        )
+        .await?;
 
         /* Only return a non-200 result in case of an error from the
          * ledger canister. Otherwise just log the error and return a
@@ -546,104 +515,20 @@ impl LedgerClient {
         http_client: &Client,
         read_state_http_body: SignedRequestBytes,
     ) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
-        // Cut&paste from canister_client Agent.
-        let mut poll_interval = Self::MIN_POLL_INTERVAL;
-        while Instant::now() + poll_interval < deadline {
-            debug!("Waiting {} ms for response", poll_interval.as_millis());
-            actix_rt::time::sleep(poll_interval).await;
-            let wait_timeout = Self::TIMEOUT - start_time.elapsed();
-            let url = self
-                .ic_url
-                .join(&ic_canister_client::read_state_path(canister_id))
-                .expect("URL join failed");
-
-            match send_post_request(
-                http_client,
-                url.as_str(),
-                read_state_http_body.clone().into(),
-                wait_timeout,
-            )
-            .await
-            {
-                Err(err) => {
-                    // Retry client-side errors.
-                    error!("Error while reading the IC state: {}.", err);
-                }
-                Ok((body, status)) => {
-                    if status.is_success() {
-                        let cbor: serde_cbor::Value = serde_cbor::from_slice(&body)
-                            .map_err(|err| format!("While parsing the status body: {}", err))?;
-
-                        let status =
-                            ic_canister_client::parse_read_state_response(&request_id, cbor)
-                                .map_err(|err| {
-                                    format!("While parsing the read state response: {}", err)
-                                })?;
-
-                        debug!("Read state response: {:?}", status);
-
-                        match status.status.as_ref() {
-                            "replied" => match status.reply {
-                                Some(bytes) => {
-                                    return self.handle_reply(&request_type, bytes);
-                                }
-                                None => {
-                                    return Err("Send returned with no result.".to_owned());
-                                }
-                            },
-                            "unknown" | "received" | "processing" => {}
-                            "rejected" => {
-                                return Ok(Err(ApiError::TransactionRejected(
-                                    false,
-                                    status
-                                        .reject_message
-                                        .unwrap_or_else(|| "(no message)".to_owned())
-                                        .into(),
-                                )));
-                            }
-                            "done" => {
-                                return Err(
-                                        "The call has completed but the reply/reject data has been pruned."
-                                            .to_string(),
-                                    );
-                            }
-                            _ => {
-                                return Err(format!(
-                                    "Send returned unexpected result: {:?} - {:?}",
-                                    status.status, status.reject_message
-                                ))
-                            }
-                        }
-                    } else {
-                        let body =
-                            String::from_utf8(body).unwrap_or_else(|_| "<undecodable>".to_owned());
-                        let err = format!(
-                            "HTTP error {} while reading the IC state: {}.",
-                            status, body
-                        );
-                        if status.is_server_error() {
-                            // Retry on 5xx errors.
-                            error!("{}", err);
-                        } else {
-                            return Err(err);
-                        }
-                    }
-                }
-            };
-
-            // Bump the poll interval and compute the next poll time (based on current
-            // wall time, so we don't spin without delay after a
-            // slow poll).
-            poll_interval = poll_interval
-                .mul_f32(Self::POLL_INTERVAL_MULTIPLIER)
-                .min(Self::MAX_POLL_INTERVAL);
+        match wait_for_reply(
+            http_client,
+            &self.ic_url,
+            canister_id,
+            &request_id,
+            start_time,
+            deadline,
+            read_state_http_body,
+        )
+        .await?
+        {
+            Ok(bytes) => self.handle_reply(&request_type, bytes),
+            Err(err) => Ok(Err(err)),
         }
-
-        // We didn't get a response in 30 seconds. Let the client handle it.
-        Err(format!(
-            "Operation took longer than {:?} to complete.",
-            Self::TIMEOUT
-        ))
     }
 
     /// Handle the replied data.
@@ -656,13 +541,17 @@ impl LedgerClient {
             RequestType::AddHotKey { .. } => handle_add_hotkey(bytes),
             RequestType::Disburse { .. } => handle_disburse(bytes),
             RequestType::Follow { .. } => handle_follow(bytes),
+            RequestType::Merge { .. } => handle_merge(bytes),
             RequestType::MergeMaturity { .. } => handle_merge_maturity(bytes),
             RequestType::NeuronInfo { .. } => handle_neuron_info(bytes),
+            RequestType::RegisterVote { .. } => handle_register_vote(bytes),
             RequestType::RemoveHotKey { .. } => handle_remove_hotkey(bytes),
             RequestType::Send => handle_send(bytes),
             RequestType::SetDissolveTimestamp { .. } => handle_set_dissolve_timestamp(bytes),
             RequestType::Spawn { .. } => handle_spawn(bytes),
+            RequestType::Split { .. } => handle_split(bytes),
             RequestType::Stake { .. } => handle_stake(bytes),
+            RequestType::StakeMaturity { .. } => handle_stake_maturity(bytes),
             RequestType::StartDissolve { .. } => handle_start_dissolve(bytes, request_type),
             RequestType::StopDissolve { .. } => handle_stop_dissolve(bytes, request_type),
         }
@@ -691,3 +580,228 @@ async fn send_post_request(
         .to_vec();
     Ok((resp_body, resp_status))
 }
+
+/// Picks the update/read-state envelope pair that is currently valid.
+pub(crate) fn select_envelope_pair(request: Vec<EnvelopePair>) -> Result<EnvelopePair, ApiError> {
+    let now = ic_types::time::current_time();
+    request
+        .into_iter()
+        .find(|EnvelopePair { update, .. }| {
+            let ingress_expiry =
+                ic_types::Time::from_nanos_since_unix_epoch(update.content.ingress_expiry());
+            let ingress_start =
+                ingress_expiry - (ic_constants::MAX_INGRESS_TTL - ic_constants::PERMITTED_DRIFT);
+            ingress_start <= now && ingress_expiry > now
+        })
+        .ok_or(ApiError::TransactionExpired)
+}
+
+/// Returns the canister targeted by a signed update call.
+pub(crate) fn envelope_canister_id(
+    update: &HttpRequestEnvelope<HttpCallContent>,
+) -> Result<CanisterId, ApiError> {
+    match &update.content {
+        HttpCallContent::Call { update } => CanisterId::try_from(update.canister_id.0.clone())
+            .map_err(|e| {
+                ApiError::internal_error(format!(
+                    "Cannot parse canister ID found in submit call: {}",
+                    e
+                ))
+            }),
+    }
+}
+
+/// Serializes the envelopes of a signed request in CBOR format.
+pub(crate) fn serialize_envelope_pair(
+    update: HttpRequestEnvelope<HttpCallContent>,
+    read_state: HttpRequestEnvelope<HttpReadStateContent>,
+) -> Result<(SignedRequestBytes, SignedRequestBytes), ApiError> {
+    let http_body = SignedRequestBytes::try_from(update).map_err(|e| {
+        ApiError::internal_error(format!(
+            "Cannot serialize the submit request in CBOR format because of: {}",
+            e
+        ))
+    })?;
+
+    let read_state_http_body = SignedRequestBytes::try_from(read_state).map_err(|e| {
+        ApiError::internal_error(format!(
+            "Cannot serialize the read state request in CBOR format because of: {}",
+            e
+        ))
+    })?;
+
+    Ok((http_body, read_state_http_body))
+}
+
+/// Submits a signed update call (with retry).
+///
+/// Only fails if the IC refuses the call; transient errors are retried
+/// until the deadline.
+pub(crate) async fn submit_update(
+    http_client: &Client,
+    ic_url: &Url,
+    canister_id: CanisterId,
+    http_body: SignedRequestBytes,
+    start_time: Instant,
+    deadline: Instant,
+) -> Result<(), ApiError> {
+    let url = ic_url
+        .join(&ic_canister_client::update_path(canister_id))
+        .expect("URL join failed");
+
+    let mut poll_interval = MIN_POLL_INTERVAL;
+
+    while Instant::now() + poll_interval < deadline {
+        let wait_timeout = SUBMIT_TIMEOUT - start_time.elapsed();
+
+        match send_post_request(
+            http_client,
+            url.as_str(),
+            http_body.clone().into(),
+            wait_timeout,
+        )
+        .await
+        {
+            Err(err) => {
+                // Retry client-side errors.
+                error!("Error while submitting transaction: {}.", err);
+            }
+            Ok((body, status)) => {
+                if status.is_success() {
+                    break;
+                }
+                // Retry on 5xx errors. We don't want to retry on
+                // e.g. authentication errors.
+                let body = String::from_utf8(body).unwrap_or_else(|_| "<undecodable>".to_owned());
+                if status.is_server_error() {
+                    error!(
+                        "HTTP error {} while submitting transaction: {}.",
+                        status, body
+                    );
+                } else {
+                    return Err(ApiError::ICError(ICError {
+                        retriable: false,
+                        ic_http_status: status.as_u16(),
+                        error_message: body,
+                    }));
+                }
+            }
+        }
+
+        // Bump the poll interval and compute the next poll time (based on current wall
+        // time, so we don't spin without delay after a slow poll).
+        poll_interval = poll_interval
+            .mul_f32(POLL_INTERVAL_MULTIPLIER)
+            .min(MAX_POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+/// Does read-state calls until the reply of the request becomes available.
+///
+/// Returns `Ok(Err(_))` if the canister rejected the call, and `Err(_)` if
+/// the outcome of the call is unknown.
+pub(crate) async fn wait_for_reply(
+    http_client: &Client,
+    ic_url: &Url,
+    canister_id: CanisterId,
+    request_id: &MessageId,
+    start_time: Instant,
+    deadline: Instant,
+    read_state_http_body: SignedRequestBytes,
+) -> Result<Result<Vec<u8>, ApiError>, String> {
+    // Cut&paste from canister_client Agent.
+    let mut poll_interval = MIN_POLL_INTERVAL;
+    while Instant::now() + poll_interval < deadline {
+        debug!("Waiting {} ms for response", poll_interval.as_millis());
+        actix_rt::time::sleep(poll_interval).await;
+        let wait_timeout = SUBMIT_TIMEOUT - start_time.elapsed();
+        let url = ic_url
+            .join(&ic_canister_client::read_state_path(canister_id))
+            .expect("URL join failed");
+
+        match send_post_request(
+            http_client,
+            url.as_str(),
+            read_state_http_body.clone().into(),
+            wait_timeout,
+        )
+        .await
+        {
+            Err(err) => {
+                // Retry client-side errors.
+                error!("Error while reading the IC state: {}.", err);
+            }
+            Ok((body, status)) => {
+                if status.is_success() {
+                    let cbor: serde_cbor::Value = serde_cbor::from_slice(&body)
+                        .map_err(|err| format!("While parsing the status body: {}", err))?;
+
+                    let status = ic_canister_client::parse_read_state_response(request_id, cbor)
+                        .map_err(|err| format!("While parsing the read state response: {}", err))?;
+
+                    debug!("Read state response: {:?}", status);
+
+                    match status.status.as_ref() {
+                        "replied" => match status.reply {
+                            Some(bytes) => {
+                                return Ok(Ok(bytes));
+                            }
+                            None => {
+                                return Err("Send returned with no result.".to_owned());
+                            }
+                        },
+                        "unknown" | "received" | "processing" => {}
+                        "rejected" => {
+                            return Ok(Err(ApiError::TransactionRejected(
+                                false,
+                                status
+                                    .reject_message
+                                    .unwrap_or_else(|| "(no message)".to_owned())
+                                    .into(),
+                            )));
+                        }
+                        "done" => {
+                            return Err(
+                                "The call has completed but the reply/reject data has been pruned."
+                                    .to_string(),
+                            );
+                        }
+                        _ => {
+                            return Err(format!(
+                                "Send returned unexpected result: {:?} - {:?}",
+                                status.status, status.reject_message
+                            ))
+                        }
+                    }
+                } else {
+                    let body =
+                        String::from_utf8(body).unwrap_or_else(|_| "<undecodable>".to_owned());
+                    let err = format!(
+                        "HTTP error {} while reading the IC state: {}.",
+                        status, body
+                    );
+                    if status.is_server_error() {
+                        // Retry on 5xx errors.
+                        error!("{}", err);
+                    } else {
+                        return Err(err);
+                    }
+                }
+            }
+        };
+
+        // Bump the poll interval and compute the next poll time (based on current
+        // wall time, so we don't spin without delay after a
+        // slow poll).
+        poll_interval = poll_interval
+            .mul_f32(POLL_INTERVAL_MULTIPLIER)
+            .min(MAX_POLL_INTERVAL);
+    }
+
+    // We didn't get a response in 30 seconds. Let the client handle it.
+    Err(format!(
+        "Operation took longer than {:?} to complete.",
+        SUBMIT_TIMEOUT
+    ))
+}
@@ -0,0 +1,212 @@
+use super::*;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use actix_web::dev::ServerHandle;
+use actix_web::http::StatusCode;
+use actix_web::{web, App, HttpResponse, HttpServer};
+use dfn_protobuf::ProtoBuf;
+use ic_crypto_tree_hash::MixedHashTree;
+use ic_ledger_core::timestamp::TimeStamp;
+use ic_types::messages::{Blob, Certificate, HttpCanisterUpdate, HttpReadStateResponse};
+use ic_types::PrincipalId;
+use ledger_canister::{AccountIdentifier, Memo, SendArgs, Tokens};
+
+/// A replica that answers update calls with `call_status` and read-state
+/// requests with a reply to `request_id`.
+struct FakeReplica {
+    call_status: StatusCode,
+    request_id: MessageId,
+    reply: Vec<u8>,
+    /// The canister IDs and bodies of the update calls received.
+    calls: Mutex<Vec<(String, Vec<u8>)>>,
+    read_states: AtomicUsize,
+}
+
+async fn call(
+    replica: web::Data<FakeReplica>,
+    canister_id: web::Path<String>,
+    body: web::Bytes,
+) -> HttpResponse {
+    replica
+        .calls
+        .lock()
+        .unwrap()
+        .push((canister_id.into_inner(), body.to_vec()));
+    HttpResponse::build(replica.call_status).body("call refused")
+}
+
+fn labeled(label: &[u8], tree: MixedHashTree) -> MixedHashTree {
+    MixedHashTree::Labeled(label.to_vec().into(), Box::new(tree))
+}
+
+async fn read_state(replica: web::Data<FakeReplica>) -> HttpResponse {
+    replica.read_states.fetch_add(1, Ordering::Relaxed);
+    let tree = labeled(
+        b"request_status",
+        labeled(
+            replica.request_id.as_bytes(),
+            MixedHashTree::Fork(Box::new((
+                labeled(b"reply", MixedHashTree::Leaf(replica.reply.clone())),
+                labeled(b"status", MixedHashTree::Leaf(b"replied".to_vec())),
+            ))),
+        ),
+    );
+    let certificate = Certificate {
+        tree,
+        signature: Blob(vec![]),
+        delegation: None,
+    };
+    let response = HttpReadStateResponse {
+        certificate: Blob(serde_cbor::to_vec(&certificate).unwrap()),
+    };
+    HttpResponse::Ok()
+        .content_type("application/cbor")
+        .body(serde_cbor::to_vec(&response).unwrap())
+}
+
+/// Serves `replica` on a local port and returns its URL.
+fn start_fake_replica(replica: web::Data<FakeReplica>) -> (Url, ServerHandle) {
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(replica.clone())
+            .route("/api/v2/canister/{canister_id}/call", web::post().to(call))
+            .route(
+                "/api/v2/canister/{canister_id}/read_state",
+                web::post().to(read_state),
+            )
+    })
+    .workers(1)
+    .bind("127.0.0.1:0")
+    .unwrap();
+    let url = Url::parse(&format!("http://{}", server.addrs()[0])).unwrap();
+    let server = server.run();
+    let handle = server.handle();
+    actix_rt::spawn(server);
+    (url, handle)
+}
+
+async fn new_ledger_client(ic_url: Url) -> LedgerClient {
+    LedgerClient {
+        ledger_blocks_synchronizer: LedgerBlocksSynchronizer::new(
+            None,
+            None,
+            None,
+            None,
+            Box::new(LedgerBlocksSynchronizerMetricsImpl {}),
+        )
+        .await
+        .unwrap(),
+        canister_id: ic_nns_constants::LEDGER_CANISTER_ID,
+        governance_canister_id: ic_nns_constants::GOVERNANCE_CANISTER_ID,
+        canister_access: None,
+        ic_url,
+        token_symbol: crate::DEFAULT_TOKEN_SYMBOL.to_string(),
+        offline: false,
+    }
+}
+
+/// Returns a signed transfer to the ledger and the ID of its update call.
+///
+/// The fake replica does not check signatures, so the envelopes are not
+/// signed.
+fn send_request() -> (SignedTransaction, MessageId) {
+    let args = SendArgs {
+        memo: Memo(1),
+        amount: Tokens::from_e8s(1_000),
+        fee: DEFAULT_TRANSFER_FEE,
+        from_subaccount: None,
+        to: AccountIdentifier::new(PrincipalId::new_user_test_id(2), None),
+        created_at_time: Some(TimeStamp::from_nanos_since_unix_epoch(
+            1_656_147_600_000_000_000,
+        )),
+    };
+    let update = HttpCanisterUpdate {
+        canister_id: Blob(ic_nns_constants::LEDGER_CANISTER_ID.get().to_vec()),
+        method_name: "send_pb".to_string(),
+        arg: Blob(convert::to_arg(args)),
+        nonce: None,
+        sender: Blob(PrincipalId::new_user_test_id(1).into_vec()),
+        ingress_expiry: (ic_types::time::current_time() + Duration::from_secs(120))
+            .as_nanos_since_unix_epoch(),
+    };
+    let read_state = convert::make_read_state_from_update(&update);
+    let update = HttpRequestEnvelope {
+        content: HttpCallContent::Call { update },
+        sender_pubkey: None,
+        sender_sig: None,
+        sender_delegation: None,
+    };
+    let request_id = MessageId::from(update.content.representation_independent_hash());
+    let read_state = HttpRequestEnvelope {
+        content: HttpReadStateContent::ReadState { read_state },
+        sender_pubkey: None,
+        sender_sig: None,
+        sender_delegation: None,
+    };
+    (
+        vec![(RequestType::Send, vec![EnvelopePair { update, read_state }])],
+        request_id,
+    )
+}
+
+#[actix_rt::test]
+async fn submit_sends_the_update_and_reads_the_block_index() {
+    let (envelopes, request_id) = send_request();
+    let expected_body = SignedRequestBytes::try_from(envelopes[0].1[0].update.clone()).unwrap();
+    let replica = web::Data::new(FakeReplica {
+        call_status: StatusCode::ACCEPTED,
+        request_id,
+        reply: ProtoBuf::<BlockIndex>(5).into_bytes().unwrap(),
+        calls: Mutex::new(vec![]),
+        read_states: AtomicUsize::new(0),
+    });
+    let (ic_url, server) = start_fake_replica(replica.clone());
+    let ledger_client = new_ledger_client(ic_url).await;
+
+    let results = ledger_client.submit(envelopes.clone()).await.unwrap();
+
+    assert_eq!(
+        *replica.calls.lock().unwrap(),
+        vec![(
+            ic_nns_constants::LEDGER_CANISTER_ID.to_string(),
+            Vec::<u8>::from(expected_body)
+        )]
+    );
+    assert!(replica.read_states.load(Ordering::Relaxed) > 0);
+    assert_eq!(results.operations.len(), 1);
+    let result = &results.operations[0];
+    assert_eq!(result.status, Status::Completed);
+    assert_eq!(result.block_index, Some(5));
+    assert_eq!(
+        result.transaction_identifier,
+        Some(
+            TransactionIdentifier::try_from_envelope(RequestType::Send, &envelopes[0].1[0].update)
+                .unwrap()
+        )
+    );
+
+    server.stop(true).await;
+}
+
+#[actix_rt::test]
+async fn submit_fails_if_the_replica_refuses_the_update() {
+    let (envelopes, request_id) = send_request();
+    let replica = web::Data::new(FakeReplica {
+        call_status: StatusCode::BAD_REQUEST,
+        request_id,
+        reply: vec![],
+        calls: Mutex::new(vec![]),
+        read_states: AtomicUsize::new(0),
+    });
+    let (ic_url, server) = start_fake_replica(replica.clone());
+    let ledger_client = new_ledger_client(ic_url).await;
+
+    assert!(ledger_client.submit(envelopes).await.is_err());
+    // Client errors are not retried, and the outcome is not polled.
+    assert_eq!(replica.calls.lock().unwrap().len(), 1);
+    assert_eq!(replica.read_states.load(Ordering::Relaxed), 0);
+
+    server.stop(true).await;
+}
@@ -1,5 +1,6 @@
 pub mod convert;
 pub mod errors;
+pub mod icrc1;
 pub mod ledger_client;
 pub mod models;
 pub mod request;
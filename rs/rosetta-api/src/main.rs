@@ -1,6 +1,8 @@
 use clap::Parser;
 use ic_crypto_internal_threshold_sig_bls12381 as bls12_381;
 use ic_crypto_utils_threshold_sig_der::parse_threshold_sig_key;
+use ic_rosetta_api::icrc1::ledger_client::Icrc1LedgerClient;
+use ic_rosetta_api::icrc1::request_handler::Icrc1RequestHandler;
 use ic_rosetta_api::request_handler::RosettaRequestHandler;
 use ic_rosetta_api::rosetta_server::{RosettaApiServer, RosettaApiServerOpt};
 use ic_rosetta_api::{ledger_client, DEFAULT_BLOCKCHAIN, DEFAULT_TOKEN_SYMBOL};
@@ -53,6 +55,11 @@ struct Opt {
     not_whitelisted: bool,
     #[clap(long = "expose-metrics")]
     expose_metrics: bool,
+    /// Serve an ICRC-1 ledger instead of the ICP ledger. Requires --canister-id.
+    /// ICRC-1 ledgers do not certify their blocks, so the served blocks are
+    /// not verified and are only as trustworthy as the replica they came from.
+    #[clap(long = "icrc1")]
+    icrc1: bool,
 }
 
 #[actix_web::main]
@@ -74,6 +81,10 @@ async fn main() -> std::io::Result<()> {
     log::info!("Listening on {}:{}", opt.listen_address, opt.listen_port);
     let addr = format!("{}:{}", opt.listen_address, opt.listen_port);
 
+    if opt.icrc1 && opt.ic_canister_id.is_none() {
+        panic!("The canister id of the ICRC-1 ledger must be specified with --canister-id");
+    }
+
     let (root_key, canister_id, governance_canister_id, url) = if opt.mainnet {
         let root_key = match opt.root_key {
             Some(root_key_path) => parse_threshold_sig_key(root_key_path.as_path())?,
@@ -161,9 +172,23 @@ async fn main() -> std::io::Result<()> {
         not_whitelisted,
         expose_metrics,
         blockchain,
+        icrc1,
         ..
     } = opt;
-    let client = ledger_client::LedgerClient::new(
+
+    let serv = if icrc1 {
+        let client =
+            Icrc1LedgerClient::new(url, canister_id, token_symbol, store_location, offline)
+                .await
+                .unwrap_or_else(|e| panic!("Failed to initialize ledger client: {:?}", e));
+        let ledger = Arc::new(client);
+        let req_handler = Icrc1RequestHandler::new(blockchain, ledger.clone());
+
+        log::info!("Network id: {:?}", req_handler.network_id());
+        RosettaApiServer::new_icrc1(ledger, req_handler, addr, expose_metrics)
+            .expect("Error creating RosettaApiServer")
+    } else {
+        let client = ledger_client::LedgerClient::new(
         url,
         canister_id,
         token_symbol,
@@ -182,12 +207,13 @@ async fn main() -> std::io::Result<()> {
     })
     .unwrap_or_else(|(e, is_403)| panic!("Failed to initialize ledger client{}: {:?}", is_403, e));
 
-    let ledger = Arc::new(client);
-    let req_handler = RosettaRequestHandler::new(blockchain, ledger.clone());
+        let ledger = Arc::new(client);
+        let req_handler = RosettaRequestHandler::new(blockchain, ledger.clone());
 
-    log::info!("Network id: {:?}", req_handler.network_id());
-    let serv = RosettaApiServer::new(ledger, req_handler, addr, expose_metrics)
-        .expect("Error creating RosettaApiServer");
+        log::info!("Network id: {:?}", req_handler.network_id());
+        RosettaApiServer::new(ledger, req_handler, addr, expose_metrics)
+            .expect("Error creating RosettaApiServer")
+    };
 
     // actix server catches kill signals. After that we still need to stop our
     // server properly
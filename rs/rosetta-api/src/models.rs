@@ -13,13 +13,14 @@ use crate::{
 };
 use ic_types::{
     messages::{HttpCallContent, HttpCanisterUpdate, HttpReadStateContent, HttpRequestEnvelope},
-    CanisterId,
+    CanisterId, PrincipalId,
 };
 use serde::{Deserialize, Serialize};
 use std::{
     convert::{TryFrom, TryInto},
     fmt::Display,
 };
+use strum_macros::{EnumIter, EnumString};
 
 // This file is generated from https://github.com/coinbase/rosetta-specifications using openapi-generator
 // Then heavily tweaked because openapi-generator no longer generates valid rust
@@ -175,6 +176,11 @@ pub struct Allow {
     /// account at any height in the past should set this to true.
     #[serde(rename = "historical_balance_lookup")]
     pub historical_balance_lookup: bool,
+
+    /// All methods that are supported by the /call endpoint.
+    #[serde(rename = "call_methods")]
+    #[serde(default)]
+    pub call_methods: Vec<String>,
 }
 
 impl Allow {
@@ -189,6 +195,7 @@ impl Allow {
             operation_types,
             errors,
             historical_balance_lookup,
+            call_methods: vec![],
         }
     }
 }
@@ -355,6 +362,67 @@ impl BlockTransactionResponse {
     }
 }
 
+/// CallRequest is the input to the `/call` endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "conversion", derive(LabelledGeneric))]
+pub struct CallRequest {
+    #[serde(rename = "network_identifier")]
+    pub network_identifier: NetworkIdentifier,
+
+    /// Method is some network-specific procedure call. This method could map to
+    /// a network-specific RPC endpoint, a method in an SDK generated from a
+    /// smart contract, or some hybrid of the two.  The implementation must
+    /// define all available methods in the Allow object.
+    #[serde(rename = "method")]
+    pub method_name: String,
+
+    /// Parameters is some network-specific argument for a method. It is up to
+    /// the caller to determine which parameters to provide when invoking /call.
+    #[serde(rename = "parameters")]
+    #[serde(default)]
+    pub parameters: Object,
+}
+
+impl CallRequest {
+    pub fn new(
+        network_identifier: NetworkIdentifier,
+        method_name: String,
+        parameters: Object,
+    ) -> CallRequest {
+        CallRequest {
+            network_identifier,
+            method_name,
+            parameters,
+        }
+    }
+}
+
+/// CallResponse contains the result of a `/call` invocation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "conversion", derive(LabelledGeneric))]
+pub struct CallResponse {
+    /// Result contains the result of the `/call` invocation. This result will
+    /// not be inspected or interpreted by Rosetta tooling and is left to the
+    /// caller to decode.
+    #[serde(rename = "result")]
+    pub result: Object,
+
+    /// Idempotent indicates that if `/call` is invoked with the same
+    /// CallRequest again, at any point in time, it will return the same
+    /// CallResponse.  Integrators may cache the CallResponse if this is set to
+    /// true to avoid making unnecessary calls to the Rosetta implementation.
+    /// For this reason, implementers should be very conservative about
+    /// returning true here or they could cause issues for the caller.
+    #[serde(rename = "idempotent")]
+    pub idempotent: bool,
+}
+
+impl CallResponse {
+    pub fn new(result: Object, idempotent: bool) -> CallResponse {
+        CallResponse { result, idempotent }
+    }
+}
+
 /// CoinActions are different state changes that a Coin can undergo. When a Coin
 /// is created, it is coin_created. When a Coin is spent, it is coin_spent. It
 /// is assumed that a single Coin cannot be created or spent more than once.
@@ -1268,8 +1336,6 @@ impl NetworkIdentifier {
 impl TryInto<CanisterId> for &NetworkIdentifier {
     type Error = ApiError;
     fn try_into(self) -> Result<CanisterId, Self::Error> {
-        use ic_types::PrincipalId;
-
         let principal_bytes = hex::decode(&self.network)
             .map_err(|_| ApiError::InvalidNetworkId(false, "not hex".into()))?;
         let principal_id = PrincipalId::try_from(&principal_bytes)
@@ -2126,3 +2192,160 @@ pub struct NeuronInfoResponse {
     #[serde(rename = "created_timestamp_seconds")]
     pub created_timestamp_seconds: u64,
 }
+
+/// The methods supported by the `/call` endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::Display, EnumIter, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum CallMethod {
+    /// Lists the neurons of a controller. See [ListNeuronsRequest].
+    ListNeurons,
+    /// Gets a range of verified blocks. See [GetBlockRangeRequest].
+    GetBlockRange,
+    /// Gets the minimum dissolve delay for a neuron to vote.
+    GetMinimumDissolveDelay,
+    /// Gets the public information of a proposal. See
+    /// [QueryProposalInfoRequest].
+    QueryProposalInfo,
+}
+
+/// Parameters of the `list_neurons` call.
+///
+/// Neurons are looked up by the subaccounts derived from the controller and
+/// the neuron indices in `[start_index, start_index + limit)`. Neurons
+/// cannot be listed by hotkey: governance only lists them to the hotkey
+/// itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListNeuronsRequest {
+    #[serde(rename = "controller")]
+    pub controller: PrincipalId,
+
+    #[serde(rename = "start_index")]
+    #[serde(default)]
+    pub start_index: u64,
+
+    #[serde(rename = "limit")]
+    #[serde(default)]
+    pub limit: Option<u64>,
+
+    #[serde(rename = "verified_query")]
+    #[serde(default)]
+    pub verified_query: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListedNeuron {
+    #[serde(rename = "neuron_index")]
+    pub neuron_index: u64,
+
+    #[serde(rename = "account_identifier")]
+    pub account_identifier: AccountIdentifier,
+
+    #[serde(rename = "neuron_info")]
+    pub neuron_info: NeuronInfoResponse,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListNeuronsResponse {
+    #[serde(rename = "neurons")]
+    pub neurons: Vec<ListedNeuron>,
+}
+
+/// Parameters of the `get_block_range` call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GetBlockRangeRequest {
+    /// The index of the first block to return.
+    #[serde(rename = "index")]
+    pub index: u64,
+
+    /// The maximum number of blocks to return.
+    #[serde(rename = "limit")]
+    pub limit: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GetBlockRangeResponse {
+    #[serde(rename = "blocks")]
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GetMinimumDissolveDelayResponse {
+    #[serde(rename = "neuron_minimum_dissolve_delay_to_vote_seconds")]
+    pub neuron_minimum_dissolve_delay_to_vote_seconds: u64,
+}
+
+/// Parameters of the `query_proposal_info` call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryProposalInfoRequest {
+    #[serde(rename = "proposal_id")]
+    pub proposal_id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TallyResponse {
+    #[serde(rename = "timestamp_seconds")]
+    pub timestamp_seconds: u64,
+
+    #[serde(rename = "yes")]
+    pub yes: u64,
+
+    #[serde(rename = "no")]
+    pub no: u64,
+
+    #[serde(rename = "total")]
+    pub total: u64,
+}
+
+/// Response for the public information of a proposal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposalInfoResponse {
+    #[serde(rename = "proposal_id")]
+    pub proposal_id: u64,
+
+    #[serde(rename = "proposer")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proposer: Option<u64>,
+
+    #[serde(rename = "title")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[serde(rename = "summary")]
+    pub summary: String,
+
+    #[serde(rename = "url")]
+    pub url: String,
+
+    #[serde(rename = "topic")]
+    pub topic: String,
+
+    #[serde(rename = "status")]
+    pub status: String,
+
+    #[serde(rename = "reward_status")]
+    pub reward_status: String,
+
+    #[serde(rename = "proposal_timestamp_seconds")]
+    pub proposal_timestamp_seconds: u64,
+
+    #[serde(rename = "decided_timestamp_seconds")]
+    pub decided_timestamp_seconds: u64,
+
+    #[serde(rename = "executed_timestamp_seconds")]
+    pub executed_timestamp_seconds: u64,
+
+    #[serde(rename = "failed_timestamp_seconds")]
+    pub failed_timestamp_seconds: u64,
+
+    #[serde(rename = "deadline_timestamp_seconds")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline_timestamp_seconds: Option<u64>,
+
+    #[serde(rename = "latest_tally")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_tally: Option<TallyResponse>,
+
+    #[serde(rename = "failure_reason")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+}
@@ -161,4 +161,16 @@ pub enum OperationType {
     #[serde(rename = "FOLLOW")]
     #[strum(serialize = "FOLLOW")]
     Follow,
+    #[serde(rename = "SPLIT")]
+    #[strum(serialize = "SPLIT")]
+    Split,
+    #[serde(rename = "MERGE")]
+    #[strum(serialize = "MERGE")]
+    Merge,
+    #[serde(rename = "STAKE_MATURITY")]
+    #[strum(serialize = "STAKE_MATURITY")]
+    StakeMaturity,
+    #[serde(rename = "REGISTER_VOTE")]
+    #[strum(serialize = "REGISTER_VOTE")]
+    RegisterVote,
 }
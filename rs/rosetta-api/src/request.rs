@@ -52,6 +52,14 @@ pub enum Request {
     NeuronInfo(NeuronInfo),
     #[serde(rename = "FOLLOW")]
     Follow(Follow),
+    #[serde(rename = "SPLIT")]
+    Split(Split),
+    #[serde(rename = "MERGE")]
+    Merge(Merge),
+    #[serde(rename = "STAKE_MATURITY")]
+    StakeMaturity(StakeMaturity),
+    #[serde(rename = "REGISTER_VOTE")]
+    RegisterVote(RegisterVote),
 }
 
 impl Request {
@@ -118,6 +126,22 @@ impl Request {
                 neuron_index: *neuron_index,
                 controller: controller.map(PublicKeyOrPrincipal::Principal),
             }),
+            Request::Split(Split { neuron_index, .. }) => Ok(RequestType::Split {
+                neuron_index: *neuron_index,
+            }),
+            Request::Merge(Merge { neuron_index, .. }) => Ok(RequestType::Merge {
+                neuron_index: *neuron_index,
+            }),
+            Request::StakeMaturity(StakeMaturity { neuron_index, .. }) => {
+                Ok(RequestType::StakeMaturity {
+                    neuron_index: *neuron_index,
+                })
+            }
+            Request::RegisterVote(RegisterVote { neuron_index, .. }) => {
+                Ok(RequestType::RegisterVote {
+                    neuron_index: *neuron_index,
+                })
+            }
         }
     }
 
@@ -144,6 +168,10 @@ impl Request {
                 Request::MergeMaturity(o) => builder.merge_maturity(o),
                 Request::NeuronInfo(o) => builder.neuron_info(o),
                 Request::Follow(o) => builder.follow(o),
+                Request::Split(o) => builder.split(o, token_name),
+                Request::Merge(o) => builder.merge(o),
+                Request::StakeMaturity(o) => builder.stake_maturity(o),
+                Request::RegisterVote(o) => builder.register_vote(o),
             };
         }
         Ok(builder.build())
@@ -167,6 +195,10 @@ impl Request {
                 | Request::MergeMaturity(_)
                 | Request::NeuronInfo(_) // not neuron management but we need it signed.
                 | Request::Follow(_)
+                | Request::Split(_)
+                | Request::Merge(_)
+                | Request::StakeMaturity(_)
+                | Request::RegisterVote(_)
         )
     }
 }
@@ -408,6 +440,62 @@ impl TryFrom<&models::Request> for Request {
                     Err(ApiError::invalid_request("Invalid follow request."))
                 }
             }
+            RequestType::Split { neuron_index } => {
+                if let Some(Command::Split(manage_neuron::Split { amount_e8s })) = manage_neuron()?
+                {
+                    Ok(Request::Split(Split {
+                        account,
+                        amount: Tokens::from_e8s(amount_e8s),
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid split request."))
+                }
+            }
+            RequestType::Merge { neuron_index } => {
+                if let Some(Command::Merge(manage_neuron::Merge {
+                    source_neuron_id: Some(source_neuron_id),
+                })) = manage_neuron()?
+                {
+                    Ok(Request::Merge(Merge {
+                        account,
+                        source_neuron_id: source_neuron_id.id,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid merge request."))
+                }
+            }
+            RequestType::StakeMaturity { neuron_index } => {
+                if let Some(Command::StakeMaturity(manage_neuron::StakeMaturity {
+                    percentage_to_stake,
+                })) = manage_neuron()?
+                {
+                    Ok(Request::StakeMaturity(StakeMaturity {
+                        account,
+                        percentage_to_stake,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid stake maturity request."))
+                }
+            }
+            RequestType::RegisterVote { neuron_index } => {
+                if let Some(Command::RegisterVote(manage_neuron::RegisterVote {
+                    proposal: Some(proposal),
+                    vote,
+                })) = manage_neuron()?
+                {
+                    Ok(Request::RegisterVote(RegisterVote {
+                        account,
+                        proposal: proposal.id,
+                        vote,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid register vote request."))
+                }
+            }
         }
     }
 }
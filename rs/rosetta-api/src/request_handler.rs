@@ -1,3 +1,4 @@
+mod call;
 mod construction_combine;
 mod construction_derive;
 mod construction_hash;
@@ -7,6 +8,9 @@ mod construction_payloads;
 mod construction_preprocess;
 mod construction_submit;
 
+pub(crate) use construction_combine::combine;
+pub(crate) use construction_payloads::{add_payloads, ingress_expiries};
+
 use crate::{convert, models, API_VERSION, NODE_VERSION};
 use ic_ledger_canister_blocks_synchronizer::blocks::Blocks;
 use ic_ledger_canister_blocks_synchronizer::store::HashedBlock;
@@ -27,7 +31,7 @@ use crate::ledger_client::LedgerAccess;
 use crate::models::amount::tokens_to_amount;
 use crate::models::{
     AccountBalanceRequest, AccountBalanceResponse, Allow, BalanceAccountType, BlockIdentifier,
-    BlockResponse, BlockTransaction, BlockTransactionResponse, Error, MempoolResponse,
+    BlockResponse, BlockTransaction, BlockTransactionResponse, CallMethod, Error, MempoolResponse,
     MempoolTransactionResponse, NetworkIdentifier, NetworkListResponse, NetworkOptionsResponse,
     NetworkStatusResponse, NeuronInfoResponse, NeuronState, NeuronSubaccountComponents,
     OperationStatus, Operator, PartialBlockIdentifier, SearchTransactionsResponse, SyncStatus,
@@ -163,26 +167,34 @@ impl RosettaRequestHandler {
 
         let blocks = self.ledger.read_blocks().await;
         let hb = get_block(&blocks, Some(msg.block_identifier))?;
+        let block = Some(self.hashed_block_to_rosetta_block(&blocks, hb)?);
+
+        Ok(BlockResponse {
+            block,
+            other_transactions: None,
+        })
+    }
+
+    fn hashed_block_to_rosetta_block(
+        &self,
+        blocks: &Blocks,
+        hb: HashedBlock,
+    ) -> Result<models::Block, ApiError> {
         let block = Block::decode(hb.block.clone())
             .map_err(|err| ApiError::internal_error(format!("Cannot decode block: {}", err)))?;
         let b_id = convert::block_id(&hb)?;
-        let parent_id = create_parent_block_id(&blocks, &hb)?;
+        let parent_id = create_parent_block_id(blocks, &hb)?;
 
         let transactions = vec![convert::block_to_transaction(
             &hb,
             self.ledger.token_symbol(),
         )?];
-        let block = Some(models::Block::new(
+        Ok(models::Block::new(
             b_id,
             parent_id,
             models::timestamp::from_system_time(block.timestamp.into())?,
             transactions,
-        ));
-
-        Ok(BlockResponse {
-            block,
-            other_transactions: None,
-        })
+        ))
     }
 
     /// Get a Block Transfer
@@ -242,45 +254,48 @@ impl RosettaRequestHandler {
                 None,
                 None,
             ),
-            Allow::new(
-                vec![OperationStatus::new("COMPLETED".to_string(), true)],
-                models::operation::OperationType::iter()
-                    .map(|op| op.to_string())
-                    .collect(),
-                {
-                    let token_name = self.ledger.token_symbol();
-                    let mut errs = vec![
-                        Error::new(&ApiError::InternalError(true, Default::default())),
-                        Error::new(&ApiError::InvalidRequest(false, Default::default())),
-                        Error::new(&ApiError::NotAvailableOffline(false, Default::default())),
-                        Error::new(&ApiError::InvalidNetworkId(false, Default::default())),
-                        Error::new(&ApiError::InvalidAccountId(false, Default::default())),
-                        Error::new(&ApiError::InvalidBlockId(false, Default::default())),
-                        Error::new(&ApiError::InvalidPublicKey(false, Default::default())),
-                        Error::new(&ApiError::InvalidTransactionId(false, Default::default())),
-                        Error::new(&ApiError::MempoolTransactionMissing(
-                            false,
-                            Default::default(),
-                        )),
-                        Error::new(&ApiError::BlockchainEmpty(false, Default::default())),
-                        Error::new(&ApiError::InvalidTransaction(false, Default::default())),
-                        Error::new(&ApiError::ICError(Default::default())),
-                        Error::new(&ApiError::TransactionRejected(false, Default::default())),
-                        Error::new(&ApiError::OperationsErrors(
-                            Default::default(),
-                            token_name.to_string(),
-                        )),
-                        Error::new(&ApiError::TransactionExpired),
-                    ];
-
-                    // We don't want to return any schema for details.
-                    for e in errs.iter_mut() {
-                        e.details = Default::default();
-                    }
-                    errs
-                },
-                true,
-            ),
+            Allow {
+                call_methods: CallMethod::iter().map(|m| m.to_string()).collect(),
+                ..Allow::new(
+                    vec![OperationStatus::new("COMPLETED".to_string(), true)],
+                    models::operation::OperationType::iter()
+                        .map(|op| op.to_string())
+                        .collect(),
+                    {
+                        let token_name = self.ledger.token_symbol();
+                        let mut errs = vec![
+                            Error::new(&ApiError::InternalError(true, Default::default())),
+                            Error::new(&ApiError::InvalidRequest(false, Default::default())),
+                            Error::new(&ApiError::NotAvailableOffline(false, Default::default())),
+                            Error::new(&ApiError::InvalidNetworkId(false, Default::default())),
+                            Error::new(&ApiError::InvalidAccountId(false, Default::default())),
+                            Error::new(&ApiError::InvalidBlockId(false, Default::default())),
+                            Error::new(&ApiError::InvalidPublicKey(false, Default::default())),
+                            Error::new(&ApiError::InvalidTransactionId(false, Default::default())),
+                            Error::new(&ApiError::MempoolTransactionMissing(
+                                false,
+                                Default::default(),
+                            )),
+                            Error::new(&ApiError::BlockchainEmpty(false, Default::default())),
+                            Error::new(&ApiError::InvalidTransaction(false, Default::default())),
+                            Error::new(&ApiError::ICError(Default::default())),
+                            Error::new(&ApiError::TransactionRejected(false, Default::default())),
+                            Error::new(&ApiError::OperationsErrors(
+                                Default::default(),
+                                token_name.to_string(),
+                            )),
+                            Error::new(&ApiError::TransactionExpired),
+                        ];
+
+                        // We don't want to return any schema for details.
+                        for e in errs.iter_mut() {
+                            e.details = Default::default();
+                        }
+                        errs
+                    },
+                    true,
+                )
+            },
         ))
     }
 
@@ -490,23 +505,31 @@ impl RosettaRequestHandler {
             let acc = from_model_account_identifier(aid)
                 .map_err(|e| ApiError::InvalidAccountId(false, e.into()))?;
 
-            let hist = blocks.balance_book.store.get_history(&acc, max_block);
-            heights = hist
-                .iter()
-                .rev()
-                .map(|(h, _)| *h)
-                .filter(|h| *h <= last_idx)
-                .skip(offset)
-                .collect();
+            let max_block = max_block.map_or(last_idx, |b| b.min(last_idx));
+            let range = 0..max_block + 1;
+            let count = blocks
+                .block_store
+                .count_account_transactions(&acc, range.clone())?;
+            let remaining = usize::try_from(count)
+                .map_err(|e| {
+                    ApiError::internal_error(format!(
+                        "Transaction count does not fit in usize: {}",
+                        e
+                    ))
+                })?
+                .saturating_sub(offset);
+            heights = blocks
+                .block_store
+                .get_account_transactions(&acc, range, offset, limit)?;
 
             let cnt = offset
-                .checked_add(heights.len())
+                .checked_add(remaining)
                 .ok_or_else(|| ApiError::internal_error("total count overflow"))?;
             total_count = i64::try_from(cnt).map_err(|e| {
                 ApiError::internal_error(format!("Total count does not fit in i64: {}", e))
             })?;
 
-            if heights.len() > limit {
+            if remaining > limit {
                 let next = offset
                     .checked_add(limit)
                     .ok_or_else(|| ApiError::internal_error("offset + limit overflow"))?;
@@ -514,7 +537,6 @@ impl RosettaRequestHandler {
                     ApiError::internal_error(format!("Next offset cannot fit in i64: {}", e))
                 })?);
             }
-            heights.truncate(limit);
         }
 
         let mut txs: Vec<BlockTransaction> = Vec::new();
@@ -539,34 +561,40 @@ impl RosettaRequestHandler {
         neuron_id: NeuronIdOrSubaccount,
         verified: bool,
     ) -> Result<NeuronInfoResponse, ApiError> {
-        let res = self.ledger.neuron_info(neuron_id, verified).await?;
-
-        use ic_nns_governance::pb::v1::NeuronState as PbNeuronState;
-        let state = match PbNeuronState::from_i32(res.state) {
-            Some(PbNeuronState::NotDissolving) => NeuronState::NotDissolving,
-            Some(PbNeuronState::Spawning) => NeuronState::Spawning,
-            Some(PbNeuronState::Dissolving) => NeuronState::Dissolving,
-            Some(PbNeuronState::Dissolved) => NeuronState::Dissolved,
-            Some(PbNeuronState::Unspecified) | None => {
-                return Err(ApiError::internal_error(format!(
-                    "unsupported neuron state code: {}",
-                    res.state
-                )))
-            }
-        };
-
-        Ok(NeuronInfoResponse {
-            verified_query: verified,
-            retrieved_at_timestamp_seconds: res.retrieved_at_timestamp_seconds,
-            state,
-            age_seconds: res.age_seconds,
-            dissolve_delay_seconds: res.dissolve_delay_seconds,
-            voting_power: res.voting_power,
-            created_timestamp_seconds: res.created_timestamp_seconds,
-        })
+        let res = self.ledger.neuron_info(neuron_id, verified).await??;
+        neuron_info_response(res, verified)
     }
 }
 
+fn neuron_info_response(
+    res: ic_nns_governance::pb::v1::NeuronInfo,
+    verified: bool,
+) -> Result<NeuronInfoResponse, ApiError> {
+    use ic_nns_governance::pb::v1::NeuronState as PbNeuronState;
+    let state = match PbNeuronState::from_i32(res.state) {
+        Some(PbNeuronState::NotDissolving) => NeuronState::NotDissolving,
+        Some(PbNeuronState::Spawning) => NeuronState::Spawning,
+        Some(PbNeuronState::Dissolving) => NeuronState::Dissolving,
+        Some(PbNeuronState::Dissolved) => NeuronState::Dissolved,
+        Some(PbNeuronState::Unspecified) | None => {
+            return Err(ApiError::internal_error(format!(
+                "unsupported neuron state code: {}",
+                res.state
+            )))
+        }
+    };
+
+    Ok(NeuronInfoResponse {
+        verified_query: verified,
+        retrieved_at_timestamp_seconds: res.retrieved_at_timestamp_seconds,
+        state,
+        age_seconds: res.age_seconds,
+        dissolve_delay_seconds: res.dissolve_delay_seconds,
+        voting_power: res.voting_power,
+        created_timestamp_seconds: res.created_timestamp_seconds,
+    })
+}
+
 fn create_parent_block_id(
     blocks: &Blocks,
     block: &HashedBlock,
@@ -633,7 +661,10 @@ fn get_block(
     Ok(block)
 }
 
-fn verify_network_id(canister_id: &CanisterId, net_id: &NetworkIdentifier) -> Result<(), ApiError> {
+pub(crate) fn verify_network_id(
+    canister_id: &CanisterId,
+    net_id: &NetworkIdentifier,
+) -> Result<(), ApiError> {
     verify_network_blockchain(net_id)?;
     let id: CanisterId = net_id.try_into()?;
     if *canister_id != id {
@@ -0,0 +1,174 @@
+use std::str::FromStr;
+
+use ic_nns_governance::pb::v1::governance_error::ErrorType;
+use ic_nns_governance::pb::v1::manage_neuron::NeuronIdOrSubaccount;
+use ic_nns_governance::pb::v1::{ProposalInfo, ProposalRewardStatus, ProposalStatus, Topic};
+use ledger_canister::Subaccount;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::convert::{neuron_subaccount_bytes_from_principal, to_model_account_identifier};
+use crate::errors::ApiError;
+use crate::models::{
+    CallMethod, CallRequest, CallResponse, GetBlockRangeRequest, GetBlockRangeResponse,
+    GetMinimumDissolveDelayResponse, ListNeuronsRequest, ListNeuronsResponse, ListedNeuron, Object,
+    ProposalInfoResponse, QueryProposalInfoRequest, TallyResponse,
+};
+use crate::request_handler::{
+    neuron_info_response, verify_network_id, RosettaRequestHandler, MAX_SEARCH_LIMIT,
+};
+
+/// The number of neuron indices scanned by `list_neurons` by default.
+const DEFAULT_LIST_NEURONS_LIMIT: u64 = 10;
+/// The maximum number of neuron indices scanned by a single `list_neurons`.
+const MAX_LIST_NEURONS_LIMIT: u64 = 100;
+
+impl RosettaRequestHandler {
+    /// Make a Network-Specific Procedure Call.
+    /// See https://www.rosetta-api.org/docs/CallApi.html#call
+    pub async fn call(&self, msg: CallRequest) -> Result<CallResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+        let method = CallMethod::from_str(&msg.method_name).map_err(|_| {
+            ApiError::invalid_request(format!("Unsupported method: {}", msg.method_name))
+        })?;
+        match method {
+            CallMethod::ListNeurons => {
+                let res = self.list_neurons(parse_parameters(msg.parameters)?).await?;
+                call_response(&res, false)
+            }
+            CallMethod::GetBlockRange => {
+                let res = self
+                    .get_block_range(parse_parameters(msg.parameters)?)
+                    .await?;
+                // Verified blocks never change.
+                call_response(&res, true)
+            }
+            CallMethod::GetMinimumDissolveDelay => call_response(
+                &GetMinimumDissolveDelayResponse {
+                    neuron_minimum_dissolve_delay_to_vote_seconds:
+                        ic_nns_governance::governance::MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS,
+                },
+                true,
+            ),
+            CallMethod::QueryProposalInfo => {
+                let QueryProposalInfoRequest { proposal_id } = parse_parameters(msg.parameters)?;
+                let info = self.ledger.proposal_info(proposal_id).await?;
+                call_response(&proposal_info_response(proposal_id, info), false)
+            }
+        }
+    }
+
+    async fn list_neurons(&self, req: ListNeuronsRequest) -> Result<ListNeuronsResponse, ApiError> {
+        let limit = req.limit.unwrap_or(DEFAULT_LIST_NEURONS_LIMIT);
+        if limit > MAX_LIST_NEURONS_LIMIT {
+            return Err(ApiError::invalid_request(format!(
+                "The limit cannot be greater than {}",
+                MAX_LIST_NEURONS_LIMIT
+            )));
+        }
+        let verified = req.verified_query.unwrap_or(false);
+        let mut neurons = vec![];
+        for neuron_index in req.start_index..req.start_index.saturating_add(limit) {
+            let subaccount = neuron_subaccount_bytes_from_principal(&req.controller, neuron_index);
+            let neuron_info = match self
+                .ledger
+                .neuron_info(
+                    NeuronIdOrSubaccount::Subaccount(subaccount.to_vec()),
+                    verified,
+                )
+                .await?
+            {
+                Ok(neuron_info) => neuron_info_response(neuron_info, verified)?,
+                Err(e) if e.error_type == ErrorType::NotFound as i32 => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let account = ledger_canister::AccountIdentifier::new(
+                self.ledger.governance_canister_id().get(),
+                Some(Subaccount(subaccount)),
+            );
+            neurons.push(ListedNeuron {
+                neuron_index,
+                account_identifier: to_model_account_identifier(&account),
+                neuron_info,
+            });
+        }
+        Ok(ListNeuronsResponse { neurons })
+    }
+
+    async fn get_block_range(
+        &self,
+        req: GetBlockRangeRequest,
+    ) -> Result<GetBlockRangeResponse, ApiError> {
+        if req.limit > MAX_SEARCH_LIMIT as u64 {
+            return Err(ApiError::invalid_request(format!(
+                "The limit cannot be greater than {}",
+                MAX_SEARCH_LIMIT
+            )));
+        }
+        let blocks = self.ledger.read_blocks().await;
+        let end = match blocks.last_verified()? {
+            Some(last) => req.index.saturating_add(req.limit).min(last.index + 1),
+            None => 0,
+        };
+        let mut result = vec![];
+        if req.index < end {
+            for hb in blocks.block_store.get_range(req.index..end)? {
+                result.push(self.hashed_block_to_rosetta_block(&blocks, hb)?);
+            }
+        }
+        Ok(GetBlockRangeResponse { blocks: result })
+    }
+}
+
+fn parse_parameters<T: DeserializeOwned>(parameters: Object) -> Result<T, ApiError> {
+    serde_json::from_value(serde_json::Value::Object(parameters))
+        .map_err(|e| ApiError::invalid_request(format!("Invalid parameters: {}", e)))
+}
+
+fn call_response<T: Serialize>(result: &T, idempotent: bool) -> Result<CallResponse, ApiError> {
+    match serde_json::to_value(result) {
+        Ok(serde_json::Value::Object(result)) => Ok(CallResponse::new(result, idempotent)),
+        Ok(_) => Err(ApiError::internal_error("The call result is not an object")),
+        Err(e) => Err(ApiError::internal_error(format!(
+            "Cannot serialize the call result: {}",
+            e
+        ))),
+    }
+}
+
+fn proposal_info_response(proposal_id: u64, info: ProposalInfo) -> ProposalInfoResponse {
+    fn name<T: std::fmt::Debug>(value: Option<T>, code: i32) -> String {
+        value
+            .map(|v| format!("{:?}", v))
+            .unwrap_or_else(|| format!("Unknown({})", code))
+    }
+    let (title, summary, url) = match info.proposal {
+        Some(proposal) => (proposal.title, proposal.summary, proposal.url),
+        None => (None, String::new(), String::new()),
+    };
+    ProposalInfoResponse {
+        proposal_id,
+        proposer: info.proposer.map(|n| n.id),
+        title,
+        summary,
+        url,
+        topic: name(Topic::from_i32(info.topic), info.topic),
+        status: name(ProposalStatus::from_i32(info.status), info.status),
+        reward_status: name(
+            ProposalRewardStatus::from_i32(info.reward_status),
+            info.reward_status,
+        ),
+        proposal_timestamp_seconds: info.proposal_timestamp_seconds,
+        decided_timestamp_seconds: info.decided_timestamp_seconds,
+        executed_timestamp_seconds: info.executed_timestamp_seconds,
+        failed_timestamp_seconds: info.failed_timestamp_seconds,
+        deadline_timestamp_seconds: info.deadline_timestamp_seconds,
+        latest_tally: info.latest_tally.map(|t| TallyResponse {
+            timestamp_seconds: t.timestamp_seconds,
+            yes: t.yes,
+            no: t.no,
+            total: t.total,
+        }),
+        failure_reason: info.failure_reason.map(|e| e.to_string()),
+    }
+}
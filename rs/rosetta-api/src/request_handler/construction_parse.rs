@@ -3,8 +3,9 @@ use crate::errors::ApiError;
 use crate::models::{ConstructionParseRequest, ConstructionParseResponse, ParsedTransaction};
 use crate::request_handler::{verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RemoveHotKey,
-    RequestType, SetDissolveTimestamp, Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, Follow, Merge, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal,
+    RegisterVote, RemoveHotKey, RequestType, SetDissolveTimestamp, Spawn, Split, Stake,
+    StakeMaturity, StartDissolve, StopDissolve,
 };
 
 use ic_nns_governance::pb::v1::{
@@ -16,7 +17,7 @@ use crate::models::seconds::Seconds;
 use crate::request::Request;
 use ic_types::messages::{Blob, HttpCallContent, HttpCanisterUpdate};
 use ic_types::PrincipalId;
-use ledger_canister::{AccountIdentifier, Operation, SendArgs};
+use ledger_canister::{AccountIdentifier, Operation, SendArgs, Tokens};
 use std::convert::TryFrom;
 
 impl RosettaRequestHandler {
@@ -88,6 +89,18 @@ impl RosettaRequestHandler {
                     neuron_index,
                     controller,
                 } => follow(&mut requests, arg, from, neuron_index, controller)?,
+                RequestType::Split { neuron_index } => {
+                    split(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::Merge { neuron_index } => {
+                    merge(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::StakeMaturity { neuron_index } => {
+                    stake_maturity(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::RegisterVote { neuron_index } => {
+                    register_vote(&mut requests, arg, from, neuron_index)?
+                }
             }
         }
 
@@ -465,3 +478,110 @@ fn follow(
     }
     Ok(())
 }
+
+/// Handle SPLIT.
+fn split(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::Split(manage_neuron::Split { amount_e8s })) = manage.command {
+        requests.push(Request::Split(Split {
+            account: from,
+            amount: Tokens::from_e8s(amount_e8s),
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle MERGE.
+fn merge(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::Merge(manage_neuron::Merge {
+        source_neuron_id: Some(source_neuron_id),
+    })) = manage.command
+    {
+        requests.push(Request::Merge(Merge {
+            account: from,
+            source_neuron_id: source_neuron_id.id,
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle STAKE_MATURITY.
+fn stake_maturity(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::StakeMaturity(manage_neuron::StakeMaturity {
+        percentage_to_stake,
+    })) = manage.command
+    {
+        requests.push(Request::StakeMaturity(StakeMaturity {
+            account: from,
+            percentage_to_stake,
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle REGISTER_VOTE.
+fn register_vote(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::RegisterVote(manage_neuron::RegisterVote {
+        proposal: Some(proposal),
+        vote,
+    })) = manage.command
+    {
+        requests.push(Request::RegisterVote(RegisterVote {
+            account: from,
+            proposal: proposal.id,
+            vote,
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
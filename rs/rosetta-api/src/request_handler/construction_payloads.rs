@@ -1,5 +1,5 @@
 use dfn_candid::CandidOne;
-use ic_nns_common::pb::v1::NeuronId;
+use ic_nns_common::pb::v1::{NeuronId, ProposalId};
 use ic_types::messages::{Blob, HttpCanisterUpdate, MessageId};
 use ic_types::PrincipalId;
 use ledger_canister::{Memo, Operation, SendArgs, Tokens};
@@ -18,14 +18,15 @@ use crate::convert::{make_read_state_from_update, to_arg, to_model_account_ident
 use crate::errors::ApiError;
 use crate::ledger_client::LedgerAccess;
 use crate::models::{
-    AccountIdentifier, ConstructionPayloadsRequest, ConstructionPayloadsResponse, PublicKey,
-    SignatureType, SigningPayload, UnsignedTransaction,
+    AccountIdentifier, ConstructionPayloadsRequest, ConstructionPayloadsRequestMetadata,
+    ConstructionPayloadsResponse, PublicKey, SignatureType, SigningPayload, UnsignedTransaction,
 };
 use crate::request::Request;
 use crate::request_handler::{make_sig_data, verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RemoveHotKey,
-    RequestType, SetDissolveTimestamp, Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, Follow, Merge, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal,
+    RegisterVote, RemoveHotKey, RequestType, SetDissolveTimestamp, Spawn, Split, Stake,
+    StakeMaturity, StartDissolve, StopDissolve,
 };
 use crate::{convert, models};
 
@@ -49,22 +50,8 @@ impl RosettaRequestHandler {
         let transactions =
             convert::operations_to_requests(&ops, false, self.ledger.token_symbol())?;
 
-        let interval = ic_constants::MAX_INGRESS_TTL
-            - ic_constants::PERMITTED_DRIFT
-            - Duration::from_secs(120);
-
         let meta = msg.metadata.as_ref();
 
-        let ingress_start = meta
-            .and_then(|meta| meta.ingress_start)
-            .map(ic_types::time::Time::from_nanos_since_unix_epoch)
-            .unwrap_or_else(ic_types::time::current_time);
-
-        let ingress_end = meta
-            .and_then(|meta| meta.ingress_end)
-            .map(ic_types::time::Time::from_nanos_since_unix_epoch)
-            .unwrap_or_else(|| ingress_start + interval);
-
         let created_at_time: ic_ledger_core::timestamp::TimeStamp = meta
             .and_then(|meta| meta.created_at_time)
             .map(ic_ledger_core::timestamp::TimeStamp::from_nanos_since_unix_epoch)
@@ -76,15 +63,7 @@ impl RosettaRequestHandler {
             .map(Memo)
             .unwrap_or_else(|| Memo(rand::thread_rng().gen()));
 
-        let mut ingress_expiries = vec![];
-        let mut now = ingress_start;
-        while now < ingress_end {
-            let ingress_expiry = (now + ic_constants::MAX_INGRESS_TTL
-                - ic_constants::PERMITTED_DRIFT)
-                .as_nanos_since_unix_epoch();
-            ingress_expiries.push(ingress_expiry);
-            now += interval;
-        }
+        let ingress_expiries = ingress_expiries(meta);
 
         let mut updates = vec![];
         let mut payloads = vec![];
@@ -187,6 +166,34 @@ impl RosettaRequestHandler {
                     &pks_map,
                     &ingress_expiries,
                 )?,
+                Request::Split(req) => handle_split(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::Merge(req) => handle_merge(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::StakeMaturity(req) => handle_stake_maturity(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::RegisterVote(req) => handle_register_vote(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
             }
         }
 
@@ -681,6 +688,113 @@ fn handle_follow(
     Ok(())
 }
 
+/// Handle SPLIT.
+fn handle_split(
+    req: Split,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_index = req.neuron_index;
+    let command = Command::Split(manage_neuron::Split {
+        amount_e8s: req.amount.get_e8s(),
+    });
+    add_neuron_management_payload(
+        RequestType::Split { neuron_index },
+        req.account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle MERGE.
+fn handle_merge(
+    req: Merge,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_index = req.neuron_index;
+    let command = Command::Merge(manage_neuron::Merge {
+        source_neuron_id: Some(NeuronId {
+            id: req.source_neuron_id,
+        }),
+    });
+    add_neuron_management_payload(
+        RequestType::Merge { neuron_index },
+        req.account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle STAKE_MATURITY.
+fn handle_stake_maturity(
+    req: StakeMaturity,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_index = req.neuron_index;
+    let command = Command::StakeMaturity(manage_neuron::StakeMaturity {
+        percentage_to_stake: req.percentage_to_stake,
+    });
+    add_neuron_management_payload(
+        RequestType::StakeMaturity { neuron_index },
+        req.account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle REGISTER_VOTE.
+fn handle_register_vote(
+    req: RegisterVote,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_index = req.neuron_index;
+    let command = Command::RegisterVote(manage_neuron::RegisterVote {
+        proposal: Some(ProposalId { id: req.proposal }),
+        vote: req.vote,
+    });
+    add_neuron_management_payload(
+        RequestType::RegisterVote { neuron_index },
+        req.account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
 fn add_neuron_management_payload(
     request_type: RequestType,
     account: ledger_canister::AccountIdentifier,
@@ -739,9 +853,37 @@ fn add_neuron_management_payload(
     Ok(())
 }
 
+/// Computes the ingress expiries of the envelopes to sign so that the
+/// transaction can be submitted at any time between `ingress_start` and
+/// `ingress_end`.
+pub(crate) fn ingress_expiries(meta: Option<&ConstructionPayloadsRequestMetadata>) -> Vec<u64> {
+    let interval =
+        ic_constants::MAX_INGRESS_TTL - ic_constants::PERMITTED_DRIFT - Duration::from_secs(120);
+
+    let ingress_start = meta
+        .and_then(|meta| meta.ingress_start)
+        .map(ic_types::time::Time::from_nanos_since_unix_epoch)
+        .unwrap_or_else(ic_types::time::current_time);
+
+    let ingress_end = meta
+        .and_then(|meta| meta.ingress_end)
+        .map(ic_types::time::Time::from_nanos_since_unix_epoch)
+        .unwrap_or_else(|| ingress_start + interval);
+
+    let mut ingress_expiries = vec![];
+    let mut now = ingress_start;
+    while now < ingress_end {
+        let ingress_expiry = (now + ic_constants::MAX_INGRESS_TTL - ic_constants::PERMITTED_DRIFT)
+            .as_nanos_since_unix_epoch();
+        ingress_expiries.push(ingress_expiry);
+        now += interval;
+    }
+    ingress_expiries
+}
+
 /// Add transaction and read state messages for a given update to the payloads vector.
 /// Payloads are added for each ingress expiries.
-fn add_payloads(
+pub(crate) fn add_payloads(
     payloads: &mut Vec<SigningPayload>,
     ingress_expiries: &[u64],
     account_identifier: &AccountIdentifier,
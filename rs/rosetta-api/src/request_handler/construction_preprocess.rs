@@ -7,8 +7,8 @@ use crate::models::{
 use crate::request::Request;
 use crate::request_handler::{verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, RemoveHotKey, SetDissolveTimestamp,
-    Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, Follow, Merge, MergeMaturity, NeuronInfo, RegisterVote, RemoveHotKey,
+    SetDissolveTimestamp, Spawn, Split, Stake, StakeMaturity, StartDissolve, StopDissolve,
 };
 use ledger_canister::Operation;
 use std::collections::HashSet;
@@ -65,6 +65,10 @@ fn required_public_key(request: Request) -> Result<ledger_canister::AccountIdent
         | Request::Spawn(Spawn { account, .. })
         | Request::MergeMaturity(MergeMaturity { account, .. })
         | Request::NeuronInfo(NeuronInfo { account, .. })
-        | Request::Follow(Follow { account, .. }) => Ok(account),
+        | Request::Follow(Follow { account, .. })
+        | Request::Split(Split { account, .. })
+        | Request::Merge(Merge { account, .. })
+        | Request::StakeMaturity(StakeMaturity { account, .. })
+        | Request::RegisterVote(RegisterVote { account, .. }) => Ok(account),
     }
 }
@@ -33,6 +33,10 @@ pub const SPAWN: &str = "SPAWN";
 pub const MERGE_MATURITY: &str = "MERGE_MATURITY";
 pub const NEURON_INFO: &str = "NEURON_INFO";
 pub const FOLLOW: &str = "FOLLOW";
+pub const SPLIT: &str = "SPLIT";
+pub const MERGE: &str = "MERGE";
+pub const STAKE_MATURITY: &str = "STAKE_MATURITY";
+pub const REGISTER_VOTE: &str = "REGISTER_VOTE";
 
 /// `RequestType` contains all supported values of `Operation.type`.
 /// Extra information, such as `neuron_index` should only be included
@@ -82,6 +86,18 @@ pub enum RequestType {
         neuron_index: u64,
         controller: Option<PublicKeyOrPrincipal>,
     },
+    #[serde(rename = "SPLIT")]
+    #[serde(alias = "Split")]
+    Split { neuron_index: u64 },
+    #[serde(rename = "MERGE")]
+    #[serde(alias = "Merge")]
+    Merge { neuron_index: u64 },
+    #[serde(rename = "STAKE_MATURITY")]
+    #[serde(alias = "StakeMaturity")]
+    StakeMaturity { neuron_index: u64 },
+    #[serde(rename = "REGISTER_VOTE")]
+    #[serde(alias = "RegisterVote")]
+    RegisterVote { neuron_index: u64 },
 }
 
 impl RequestType {
@@ -99,6 +115,10 @@ impl RequestType {
             RequestType::MergeMaturity { .. } => MERGE_MATURITY,
             RequestType::NeuronInfo { .. } => NEURON_INFO,
             RequestType::Follow { .. } => FOLLOW,
+            RequestType::Split { .. } => SPLIT,
+            RequestType::Merge { .. } => MERGE,
+            RequestType::StakeMaturity { .. } => STAKE_MATURITY,
+            RequestType::RegisterVote { .. } => REGISTER_VOTE,
         }
     }
 
@@ -120,6 +140,10 @@ impl RequestType {
                 | RequestType::MergeMaturity { .. }
                 | RequestType::NeuronInfo { .. }
                 | RequestType::Follow { .. }
+                | RequestType::Split { .. }
+                | RequestType::Merge { .. }
+                | RequestType::StakeMaturity { .. }
+                | RequestType::RegisterVote { .. }
         )
     }
 }
@@ -288,6 +312,39 @@ pub struct Follow {
     pub neuron_index: u64,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Split {
+    pub account: ledger_canister::AccountIdentifier,
+    pub amount: Tokens,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Merge {
+    pub account: ledger_canister::AccountIdentifier,
+    pub source_neuron_id: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StakeMaturity {
+    pub account: ledger_canister::AccountIdentifier,
+    pub percentage_to_stake: Option<u32>,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RegisterVote {
+    pub account: ledger_canister::AccountIdentifier,
+    pub proposal: u64,
+    pub vote: i32,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
 #[derive(Debug, Clone, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 // Externally tagged by default.
@@ -673,6 +730,94 @@ impl From<FollowMetadata> for Object {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct MergeMetadata {
+    pub source_neuron_id: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+impl TryFrom<Option<Object>> for MergeMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse MERGE operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<MergeMetadata> for Object {
+    fn from(m: MergeMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct StakeMaturityMetadata {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentage_to_stake: Option<u32>,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+impl TryFrom<Option<Object>> for StakeMaturityMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse STAKE_MATURITY operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<StakeMaturityMetadata> for Object {
+    fn from(m: StakeMaturityMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct RegisterVoteMetadata {
+    pub proposal: u64,
+    /// The governance `Vote` value: 1 for yes, 2 for no.
+    pub vote: i32,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+impl TryFrom<Option<Object>> for RegisterVoteMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse REGISTER_VOTE operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<RegisterVoteMetadata> for Object {
+    fn from(m: RegisterVoteMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
 /// Transaction is a bit of a misnomer, since operations can succeed or fail
 /// independently from a Transaction.
 #[derive(Default)]
@@ -1054,6 +1199,107 @@ impl TransactionBuilder {
             ),
         });
     }
+
+    pub fn split(&mut self, split: &Split, token_name: &str) {
+        let Split {
+            account,
+            amount,
+            neuron_index,
+        } = split;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Split,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: Some(tokens_to_amount(*amount, token_name).expect("failed to convert amount")),
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                NeuronIdentifierMetadata {
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn merge(&mut self, merge: &Merge) {
+        let Merge {
+            account,
+            source_neuron_id,
+            neuron_index,
+        } = merge;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Merge,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                MergeMetadata {
+                    source_neuron_id: *source_neuron_id,
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn stake_maturity(&mut self, stake: &StakeMaturity) {
+        let StakeMaturity {
+            account,
+            percentage_to_stake,
+            neuron_index,
+        } = stake;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::StakeMaturity,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                StakeMaturityMetadata {
+                    percentage_to_stake: *percentage_to_stake,
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn register_vote(&mut self, vote: &RegisterVote) {
+        let RegisterVote {
+            account,
+            proposal,
+            vote,
+            neuron_index,
+        } = vote;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::RegisterVote,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                RegisterVoteMetadata {
+                    proposal: *proposal,
+                    vote: *vote,
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
 }
 
 /// Converts an optional PrincipalId to an optional PublicKeyOrPrincipal.
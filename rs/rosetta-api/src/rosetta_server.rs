@@ -6,7 +6,8 @@ use actix_web::{
 
 use crate::{
     errors::{self, ApiError},
-    ledger_client::LedgerAccess,
+    icrc1::{ledger_client::Icrc1LedgerClient, request_handler::Icrc1RequestHandler},
+    ledger_client::{LedgerAccess, LedgerSync},
     models::*,
     request_handler::RosettaRequestHandler,
 };
@@ -33,8 +34,8 @@ use tokio::sync::Mutex;
 
 use lazy_static::lazy_static;
 
-struct RosettaEndpointsMetrics {
-    request_duration: HistogramVec,
+pub(crate) struct RosettaEndpointsMetrics {
+    pub(crate) request_duration: HistogramVec,
     rosetta_api_status_total: IntCounterVec,
 }
 
@@ -58,7 +59,8 @@ impl RosettaEndpointsMetrics {
 }
 
 lazy_static! {
-    static ref ENDPOINTS_METRICS: RosettaEndpointsMetrics = RosettaEndpointsMetrics::new();
+    pub(crate) static ref ENDPOINTS_METRICS: RosettaEndpointsMetrics =
+        RosettaEndpointsMetrics::new();
     pub static ref VERIFIED_HEIGHT: IntGauge =
         register_int_gauge!("rosetta_verified_block_height", "Verified block height").unwrap();
     pub static ref SYNCED_HEIGHT: IntGauge =
@@ -118,6 +120,19 @@ async fn block_transaction(
     to_rosetta_response(res)
 }
 
+#[post("/call")]
+async fn call(
+    msg: web::Json<CallRequest>,
+    req_handler: web::Data<RosettaRequestHandler>,
+) -> HttpResponse {
+    let _timer = ENDPOINTS_METRICS
+        .request_duration
+        .with_label_values(&["call"])
+        .start_timer();
+    let res = req_handler.call(msg.into_inner()).await;
+    to_rosetta_response(res)
+}
+
 #[post("/construction/combine")]
 async fn construction_combine(
     msg: web::Json<ConstructionCombineRequest>,
@@ -251,7 +266,9 @@ async fn search_transactions(
     to_rosetta_response(res)
 }
 
-fn to_rosetta_response<S: serde::Serialize>(result: Result<S, ApiError>) -> HttpResponse {
+pub(crate) fn to_rosetta_response<S: serde::Serialize>(
+    result: Result<S, ApiError>,
+) -> HttpResponse {
     match result {
         Ok(x) => match serde_json::to_string(&x) {
             Ok(resp) => {
@@ -300,6 +317,16 @@ fn to_rosetta_response<S: serde::Serialize>(result: Result<S, ApiError>) -> Http
     }
 }
 
+fn json_config() -> web::Data<web::JsonConfig> {
+    web::Data::new(
+        web::JsonConfig::default()
+            .limit(4 * 1024 * 1024)
+            .error_handler(move |e, _| {
+                errors::convert_to_error(&ApiError::invalid_request(format!("{:#?}", e))).into()
+            }),
+    )
+}
+
 #[get("/metrics")]
 async fn rosetta_metrics() -> HttpResponse {
     let metrics = prometheus::gather();
@@ -321,7 +348,7 @@ enum ServerState {
 
 pub struct RosettaApiServer {
     stopped: Arc<AtomicBool>,
-    ledger: Arc<dyn LedgerAccess + Send + Sync>,
+    ledger: Arc<dyn LedgerSync + Send + Sync>,
     server: Mutex<ServerState>,
     server_handle: ServerHandle,
 }
@@ -333,24 +360,14 @@ impl RosettaApiServer {
         addr: String,
         expose_metrics: bool,
     ) -> io::Result<Self> {
-        let stopped = Arc::new(AtomicBool::new(false));
         let server = HttpServer::new(move || {
             let app = App::new()
-                .app_data(web::Data::new(
-                    web::JsonConfig::default()
-                        .limit(4 * 1024 * 1024)
-                        .error_handler(move |e, _| {
-                            errors::convert_to_error(&ApiError::invalid_request(format!(
-                                "{:#?}",
-                                e
-                            )))
-                            .into()
-                        }),
-                ))
+                .app_data(json_config())
                 .app_data(web::Data::new(req_handler.clone()))
                 .service(account_balance)
                 .service(block)
                 .service(block_transaction)
+                .service(call)
                 .service(construction_combine)
                 .service(construction_derive)
                 .service(construction_hash)
@@ -374,12 +391,40 @@ impl RosettaApiServer {
         .bind(addr)?
         .run();
 
-        Ok(Self {
-            stopped,
+        Ok(Self::from_server(ledger, server))
+    }
+
+    /// Creates a server for an ICRC-1 ledger.
+    pub fn new_icrc1(
+        ledger: Arc<Icrc1LedgerClient>,
+        req_handler: Icrc1RequestHandler,
+        addr: String,
+        expose_metrics: bool,
+    ) -> io::Result<Self> {
+        let server = HttpServer::new(move || {
+            let app = App::new()
+                .app_data(json_config())
+                .app_data(web::Data::new(req_handler.clone()))
+                .configure(crate::icrc1::routes::configure);
+            if expose_metrics {
+                app.service(rosetta_metrics)
+            } else {
+                app
+            }
+        })
+        .bind(addr)?
+        .run();
+
+        Ok(Self::from_server(ledger, server))
+    }
+
+    fn from_server(ledger: Arc<dyn LedgerSync + Send + Sync>, server: Server) -> Self {
+        Self {
+            stopped: Arc::new(AtomicBool::new(false)),
             ledger,
             server_handle: server.handle(),
             server: Mutex::new(ServerState::Unstarted(server)),
-        })
+        }
     }
 
     pub async fn run(&self, options: RosettaApiServerOpt) -> io::Result<()> {
@@ -74,7 +74,11 @@ impl TransactionIdentifier {
             | RequestType::Spawn { .. }
             | RequestType::MergeMaturity { .. }
             | RequestType::NeuronInfo { .. }
-            | RequestType::Follow { .. } => {
+            | RequestType::Follow { .. }
+            | RequestType::Split { .. }
+            | RequestType::Merge { .. }
+            | RequestType::StakeMaturity { .. }
+            | RequestType::RegisterVote { .. } => {
                 // Unfortunately, staking operations don't really have a transaction ID
                 Ok(TransactionIdentifier {
                     hash: NEURON_MANAGEMENT_PSEUDO_HASH.to_string(),
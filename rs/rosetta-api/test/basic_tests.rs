@@ -10,13 +10,18 @@ use ic_rosetta_api::request_handler::RosettaRequestHandler;
 use ic_rosetta_api::transaction_id::TransactionIdentifier;
 use ic_rosetta_api::{models, API_VERSION, NODE_VERSION};
 
+use ic_nns_common::pb::v1::{NeuronId, ProposalId};
+use ic_nns_governance::pb::v1::{
+    NeuronState, Proposal, ProposalRewardStatus, ProposalStatus, Tally, Topic,
+};
+use ic_rosetta_api::convert::neuron_subaccount_bytes_from_principal;
 use ic_rosetta_api::models::{
     AccountBalanceResponse, BlockIdentifier, BlockRequest, BlockTransaction,
-    BlockTransactionRequest, ConstructionDeriveRequest, ConstructionDeriveResponse,
+    BlockTransactionRequest, CallRequest, ConstructionDeriveRequest, ConstructionDeriveResponse,
     ConstructionMetadataRequest, ConstructionMetadataResponse, Currency, CurveType,
-    MempoolResponse, MempoolTransactionRequest, MetadataRequest, NetworkListResponse,
-    NetworkRequest, NetworkStatusResponse, SearchTransactionsRequest, SearchTransactionsResponse,
-    SyncStatus,
+    GetBlockRangeResponse, ListNeuronsResponse, MempoolResponse, MempoolTransactionRequest,
+    MetadataRequest, NetworkListResponse, NetworkRequest, NetworkStatusResponse,
+    ProposalInfoResponse, SearchTransactionsRequest, SearchTransactionsResponse, SyncStatus,
 };
 use std::sync::Arc;
 
@@ -150,6 +155,10 @@ async fn smoke_test() {
         .contains(&"FEE".to_string()));
     assert!(!network_options.allow.errors.is_empty());
     assert!(network_options.allow.historical_balance_lookup);
+    assert!(network_options
+        .allow
+        .call_methods
+        .contains(&"get_block_range".to_string()));
 
     let msg = NetworkRequest::new(req_handler.network_id());
     let res = req_handler.mempool(msg).await;
@@ -358,6 +367,273 @@ async fn blocks_test() {
     assert_eq!(resp.next_offset, Some(50));
 }
 
+#[actix_rt::test]
+async fn call_test() {
+    init_test_logger();
+
+    let ledger = Arc::new(TestLedger::new());
+    let req_handler = RosettaRequestHandler::new_with_default_blockchain(ledger.clone());
+    let mut scribe = Scribe::new();
+    scribe.gen_accounts(5, 1_000_000);
+    for _i in 0..20 {
+        scribe.gen_transaction();
+    }
+    for b in &scribe.blockchain {
+        ledger.add_block(b.clone()).await.ok();
+    }
+
+    let call = |method: &str, parameters: serde_json::Value| {
+        let parameters = match parameters {
+            serde_json::Value::Object(o) => o,
+            _ => panic!("parameters must be an object"),
+        };
+        CallRequest::new(req_handler.network_id(), method.to_string(), parameters)
+    };
+
+    let resp = req_handler
+        .call(call(
+            "get_block_range",
+            serde_json::json!({"index": 3, "limit": 4}),
+        ))
+        .await
+        .unwrap();
+    assert!(resp.idempotent);
+    let range: GetBlockRangeResponse =
+        serde_json::from_value(serde_json::Value::Object(resp.result)).unwrap();
+    assert_eq!(range.blocks.len(), 4);
+    for (i, block) in range.blocks.iter().enumerate() {
+        assert_eq!(block.block_identifier.index, 3 + i as i64);
+        assert_eq!(
+            to_hash(&block.block_identifier.hash).unwrap(),
+            scribe.blockchain[3 + i].hash
+        );
+    }
+
+    // The range is truncated at the tip.
+    let tip = scribe.blockchain.len() as u64 - 1;
+    let resp = req_handler
+        .call(call(
+            "get_block_range",
+            serde_json::json!({"index": tip, "limit": 10}),
+        ))
+        .await
+        .unwrap();
+    let range: GetBlockRangeResponse =
+        serde_json::from_value(serde_json::Value::Object(resp.result)).unwrap();
+    assert_eq!(range.blocks.len(), 1);
+
+    let resp = req_handler
+        .call(call("get_minimum_dissolve_delay", serde_json::json!({})))
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.result["neuron_minimum_dissolve_delay_to_vote_seconds"],
+        serde_json::json!(
+            ic_nns_governance::governance::MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS
+        )
+    );
+
+    assert!(req_handler
+        .call(call("get_block_range", serde_json::json!({"index": 0})))
+        .await
+        .is_err());
+    assert!(req_handler
+        .call(call("no_such_method", serde_json::json!({})))
+        .await
+        .is_err());
+}
+
+fn call_request(
+    req_handler: &RosettaRequestHandler,
+    method: &str,
+    parameters: serde_json::Value,
+) -> CallRequest {
+    let parameters = match parameters {
+        serde_json::Value::Object(o) => o,
+        _ => panic!("parameters must be an object"),
+    };
+    CallRequest::new(req_handler.network_id(), method.to_string(), parameters)
+}
+
+#[actix_rt::test]
+async fn call_list_neurons_test() {
+    init_test_logger();
+
+    let ledger = Arc::new(TestLedger::new());
+    let req_handler = RosettaRequestHandler::new_with_default_blockchain(ledger.clone());
+    let controller = PrincipalId::new_user_test_id(1);
+    // Neurons 0 and 2 exist, neuron 1 does not.
+    for (neuron_index, dissolve_delay_seconds) in [(0, 100), (2, 200)] {
+        let subaccount = neuron_subaccount_bytes_from_principal(&controller, neuron_index);
+        ledger.neurons.lock().unwrap().insert(
+            subaccount.to_vec(),
+            NeuronInfo {
+                state: NeuronState::Dissolving as i32,
+                dissolve_delay_seconds,
+                ..Default::default()
+            },
+        );
+    }
+
+    let resp = req_handler
+        .call(call_request(
+            &req_handler,
+            "list_neurons",
+            serde_json::json!({"controller": controller.to_string(), "limit": 3}),
+        ))
+        .await
+        .unwrap();
+    assert!(!resp.idempotent);
+    let listed: ListNeuronsResponse =
+        serde_json::from_value(serde_json::Value::Object(resp.result)).unwrap();
+    assert_eq!(
+        listed
+            .neurons
+            .iter()
+            .map(|n| (n.neuron_index, n.neuron_info.dissolve_delay_seconds))
+            .collect::<Vec<_>>(),
+        vec![(0, 100), (2, 200)]
+    );
+    let subaccount = neuron_subaccount_bytes_from_principal(&controller, 2);
+    assert_eq!(
+        listed.neurons[1].account_identifier,
+        to_model_account_identifier(&AccountIdentifier::new(
+            ic_nns_constants::GOVERNANCE_CANISTER_ID.get(),
+            Some(ledger_canister::Subaccount(subaccount)),
+        ))
+    );
+
+    // The scan starts at `start_index`.
+    let resp = req_handler
+        .call(call_request(
+            &req_handler,
+            "list_neurons",
+            serde_json::json!({"controller": controller.to_string(), "start_index": 1}),
+        ))
+        .await
+        .unwrap();
+    let listed: ListNeuronsResponse =
+        serde_json::from_value(serde_json::Value::Object(resp.result)).unwrap();
+    assert_eq!(listed.neurons.len(), 1);
+    assert_eq!(listed.neurons[0].neuron_index, 2);
+
+    // No neurons in the scanned range.
+    let resp = req_handler
+        .call(call_request(
+            &req_handler,
+            "list_neurons",
+            serde_json::json!({"controller": controller.to_string(), "start_index": 3}),
+        ))
+        .await
+        .unwrap();
+    let listed: ListNeuronsResponse =
+        serde_json::from_value(serde_json::Value::Object(resp.result)).unwrap();
+    assert!(listed.neurons.is_empty());
+
+    for parameters in [
+        // The controller is required.
+        serde_json::json!({"limit": 3}),
+        serde_json::json!({"controller": "not a principal"}),
+        serde_json::json!({"controller": controller.to_string(), "limit": 101}),
+        serde_json::json!({"controller": controller.to_string(), "start_index": -1}),
+    ] {
+        let res = req_handler
+            .call(call_request(
+                &req_handler,
+                "list_neurons",
+                parameters.clone(),
+            ))
+            .await;
+        assert!(
+            matches!(res, Err(ApiError::InvalidRequest(_, _))),
+            "{} was not rejected: {:?}",
+            parameters,
+            res
+        );
+    }
+}
+
+#[actix_rt::test]
+async fn call_query_proposal_info_test() {
+    init_test_logger();
+
+    let ledger = Arc::new(TestLedger::new());
+    let req_handler = RosettaRequestHandler::new_with_default_blockchain(ledger.clone());
+    ledger.proposals.lock().unwrap().insert(
+        7,
+        ProposalInfo {
+            id: Some(ProposalId { id: 7 }),
+            proposer: Some(NeuronId { id: 3 }),
+            proposal: Some(Proposal {
+                title: Some("Title".to_string()),
+                summary: "Summary".to_string(),
+                url: "https://example.com".to_string(),
+                ..Default::default()
+            }),
+            topic: Topic::Governance as i32,
+            status: ProposalStatus::Open as i32,
+            reward_status: ProposalRewardStatus::AcceptVotes as i32,
+            proposal_timestamp_seconds: 1_000,
+            deadline_timestamp_seconds: Some(2_000),
+            latest_tally: Some(Tally {
+                timestamp_seconds: 1_500,
+                yes: 10,
+                no: 5,
+                total: 20,
+            }),
+            ..Default::default()
+        },
+    );
+
+    let resp = req_handler
+        .call(call_request(
+            &req_handler,
+            "query_proposal_info",
+            serde_json::json!({"proposal_id": 7}),
+        ))
+        .await
+        .unwrap();
+    assert!(!resp.idempotent);
+    let info: ProposalInfoResponse =
+        serde_json::from_value(serde_json::Value::Object(resp.result)).unwrap();
+    assert_eq!(info.proposal_id, 7);
+    assert_eq!(info.proposer, Some(3));
+    assert_eq!(info.title, Some("Title".to_string()));
+    assert_eq!(info.summary, "Summary");
+    assert_eq!(info.url, "https://example.com");
+    assert_eq!(info.topic, "Governance");
+    assert_eq!(info.status, "Open");
+    assert_eq!(info.reward_status, "AcceptVotes");
+    assert_eq!(info.proposal_timestamp_seconds, 1_000);
+    assert_eq!(info.deadline_timestamp_seconds, Some(2_000));
+    assert_eq!(
+        info.latest_tally.map(|t| (t.yes, t.no, t.total)),
+        Some((10, 5, 20))
+    );
+    assert_eq!(info.failure_reason, None);
+
+    for parameters in [
+        // Unknown proposal.
+        serde_json::json!({"proposal_id": 8}),
+        serde_json::json!({}),
+        serde_json::json!({"proposal_id": "seven"}),
+    ] {
+        let res = req_handler
+            .call(call_request(
+                &req_handler,
+                "query_proposal_info",
+                parameters.clone(),
+            ))
+            .await;
+        assert!(
+            matches!(res, Err(ApiError::InvalidRequest(_, _))),
+            "{} was not rejected: {:?}",
+            parameters,
+            res
+        );
+    }
+}
+
 #[actix_rt::test]
 async fn balances_test() {
     init_test_logger();
@@ -448,7 +724,10 @@ fn verify_balances(scribe: &Scribe, blocks: &Blocks, start_idx: usize) {
     }
     assert_eq!(
         (Tokens::MAX - sum_icpt).unwrap(),
-        blocks.balance_book.token_pool
+        blocks
+            .block_store
+            .get_token_pool(scribe.blockchain.back().unwrap().index)
+            .unwrap()
     );
 }
 
@@ -0,0 +1,375 @@
+use candid::{Decode, Nat};
+use ic_icrc1::endpoints::TransferArg;
+use ic_icrc1::{Account, Block, Memo, Transaction};
+use ic_ledger_canister_blocks_synchronizer::icrc1_blocks::Icrc1Blocks;
+use ic_ledger_canister_blocks_synchronizer::store::HashedBlock;
+use ic_ledger_canister_blocks_synchronizer_test_utils::{create_tmp_dir, init_test_logger};
+use ic_ledger_core::block::BlockType;
+use ic_ledger_core::timestamp::TimeStamp;
+use ic_ledger_core::tokens::DECIMAL_PLACES;
+use ic_ledger_core::Tokens;
+use ic_rosetta_api::convert::{block_id, from_hex, to_hex};
+use ic_rosetta_api::icrc1::convert::{
+    to_model_account_identifier, transaction_identifier, Icrc1Transfer,
+};
+use ic_rosetta_api::icrc1::ledger_client::Icrc1LedgerClient;
+use ic_rosetta_api::icrc1::request_handler::Icrc1RequestHandler;
+use ic_rosetta_api::models::{
+    AccountBalanceRequest, BlockRequest, ConstructionCombineRequest, ConstructionCombineResponse,
+    ConstructionHashRequest, ConstructionParseRequest, ConstructionPayloadsRequest,
+    ConstructionPayloadsRequestMetadata, Currency, PartialBlockIdentifier, Signature,
+    SignatureType,
+};
+use ic_rosetta_test_utils::{make_user, to_public_key, EdKeypair};
+use ic_types::messages::HttpCallContent;
+use ic_types::{CanisterId, PrincipalId};
+use std::path::Path;
+use std::sync::Arc;
+use url::Url;
+
+const TOKEN_SYMBOL: &str = "XTKN";
+const FIRST_BLOCK_TIMESTAMP_NANOS_SINCE_EPOC: u64 = 1_656_147_600_000_000_000; // 25 June 2022 09:00:00
+
+fn ledger_canister_id() -> CanisterId {
+    CanisterId::from_u64(1)
+}
+
+fn currency() -> Currency {
+    Currency::new(TOKEN_SYMBOL.to_string(), DECIMAL_PLACES)
+}
+
+/// Returns a request handler for an offline ICRC-1 ledger client that
+/// loads its blocks from the store at `location`.
+async fn new_request_handler(location: &Path) -> Icrc1RequestHandler {
+    let ledger = Icrc1LedgerClient::new(
+        Url::parse("http://localhost:8080").unwrap(),
+        ledger_canister_id(),
+        TOKEN_SYMBOL.to_string(),
+        Some(location),
+        /* offline = */ true,
+    )
+    .await
+    .unwrap();
+    Icrc1RequestHandler::new_with_default_blockchain(Arc::new(ledger))
+}
+
+/// Writes the blocks recording `transactions` to the store at `location`,
+/// as the synchronizer does after downloading them from the ledger.
+fn store_transactions(location: &Path, transactions: Vec<Transaction>) -> Vec<HashedBlock> {
+    let mut parent_hash = None;
+    let mut hashed_blocks = vec![];
+    for (index, transaction) in transactions.into_iter().enumerate() {
+        let block = Block {
+            parent_hash,
+            transaction,
+            timestamp: FIRST_BLOCK_TIMESTAMP_NANOS_SINCE_EPOC + index as u64,
+        }
+        .encode();
+        let hb = HashedBlock {
+            hash: Block::block_hash(&block),
+            block,
+            parent_hash,
+            index: index as u64,
+        };
+        parent_hash = Some(hb.hash);
+        hashed_blocks.push(hb);
+    }
+    let mut blocks = Icrc1Blocks::new_persistent(location);
+    blocks.add_blocks_batch(hashed_blocks.clone()).unwrap();
+    hashed_blocks
+}
+
+/// Constructs, signs and combines `transfer` like a Rosetta client would.
+fn signed_transfer(
+    req_handler: &Icrc1RequestHandler,
+    keypair: &EdKeypair,
+    transfer: &Icrc1Transfer,
+    memo: u64,
+    created_at_time: u64,
+) -> ConstructionCombineResponse {
+    let payloads = req_handler
+        .construction_payloads(ConstructionPayloadsRequest {
+            network_identifier: req_handler.network_id(),
+            operations: transfer.to_operations(&currency()),
+            metadata: Some(ConstructionPayloadsRequestMetadata {
+                memo: Some(memo),
+                created_at_time: Some(created_at_time),
+                ..Default::default()
+            }),
+            public_keys: Some(vec![to_public_key(keypair)]),
+        })
+        .unwrap();
+
+    let parsed = req_handler
+        .construction_parse(ConstructionParseRequest::new(
+            req_handler.network_id(),
+            false,
+            payloads.unsigned_transaction.clone(),
+        ))
+        .unwrap();
+    assert_eq!(parsed.operations, transfer.to_operations(&currency()));
+    assert_eq!(parsed.account_identifier_signers, Some(vec![]));
+
+    let signatures = payloads
+        .payloads
+        .into_iter()
+        .map(|p| {
+            let bytes = from_hex(&p.hex_bytes).unwrap();
+            Signature {
+                signing_payload: p,
+                public_key: to_public_key(keypair),
+                signature_type: SignatureType::Ed25519,
+                hex_bytes: to_hex(&keypair.sign(&bytes)),
+            }
+        })
+        .collect();
+    req_handler
+        .construction_combine(ConstructionCombineRequest::new(
+            req_handler.network_id(),
+            payloads.unsigned_transaction,
+            signatures,
+        ))
+        .unwrap()
+}
+
+#[actix_rt::test]
+async fn transfer_payloads_combine_parse_round_trip() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+    let req_handler = new_request_handler(tmpdir.path()).await;
+
+    let (_, keypair, _, owner) = make_user(1);
+    let transfer = Icrc1Transfer {
+        from: Account {
+            owner,
+            subaccount: Some([1; 32]),
+        },
+        to: Account {
+            owner: PrincipalId::new_user_test_id(2),
+            subaccount: Some([2; 32]),
+        },
+        amount: 1_000,
+        fee: Some(10),
+    };
+    let created_at_time = FIRST_BLOCK_TIMESTAMP_NANOS_SINCE_EPOC;
+    let combined = signed_transfer(&req_handler, &keypair, &transfer, 42, created_at_time);
+
+    let parsed = req_handler
+        .construction_parse(ConstructionParseRequest::new(
+            req_handler.network_id(),
+            true,
+            combined.signed_transaction.clone(),
+        ))
+        .unwrap();
+    assert_eq!(parsed.operations, transfer.to_operations(&currency()));
+    // The signer is the owner of the debited account, whatever its
+    // subaccount.
+    assert_eq!(
+        parsed.account_identifier_signers,
+        Some(vec![to_model_account_identifier(&Account {
+            owner,
+            subaccount: None,
+        })])
+    );
+
+    let envelopes = combined.signed_transaction().unwrap();
+    assert_eq!(envelopes.len(), 1);
+    for (_, pairs) in envelopes {
+        assert!(!pairs.is_empty());
+        for pair in pairs {
+            let update = match pair.update.content {
+                HttpCallContent::Call { update } => update,
+            };
+            assert_eq!(update.method_name, "icrc1_transfer");
+            assert_eq!(update.canister_id.0, ledger_canister_id().get().to_vec());
+            assert_eq!(PrincipalId::try_from(update.sender.0).unwrap(), owner);
+            assert_eq!(
+                Decode!(&update.arg.0, TransferArg).unwrap(),
+                TransferArg {
+                    from_subaccount: Some([1; 32]),
+                    to: transfer.to.clone(),
+                    fee: Some(Nat::from(10_u64)),
+                    created_at_time: Some(created_at_time),
+                    memo: Some(Memo::from(42_u64)),
+                    amount: Nat::from(1_000_u64),
+                }
+            );
+        }
+    }
+}
+
+#[actix_rt::test]
+async fn construction_hash_matches_ledger_transaction_hash() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+
+    let (_, keypair, _, owner) = make_user(1);
+    let from = Account {
+        owner,
+        subaccount: Some([1; 32]),
+    };
+    let to = Account::from(PrincipalId::new_user_test_id(2));
+    let created_at_time = FIRST_BLOCK_TIMESTAMP_NANOS_SINCE_EPOC;
+
+    // The transaction that the ledger records for the transfer.
+    let ledger_transaction = Transaction::transfer(
+        from.clone(),
+        to.clone(),
+        Tokens::from_e8s(1_000),
+        Tokens::from_e8s(10),
+        Some(TimeStamp::from_nanos_since_unix_epoch(created_at_time)),
+        Some(Memo::from(42_u64)),
+    );
+    let hashed_blocks = store_transactions(
+        tmpdir.path(),
+        vec![
+            Transaction::mint(from.clone(), Tokens::from_e8s(1_000_000), None, None),
+            ledger_transaction.clone(),
+        ],
+    );
+    let req_handler = new_request_handler(tmpdir.path()).await;
+
+    let transfer = Icrc1Transfer {
+        from,
+        to,
+        amount: 1_000,
+        fee: Some(10),
+    };
+    let combined = signed_transfer(&req_handler, &keypair, &transfer, 42, created_at_time);
+    let hash = req_handler
+        .construction_hash(ConstructionHashRequest::new(
+            req_handler.network_id(),
+            combined.signed_transaction,
+        ))
+        .unwrap();
+    assert_eq!(
+        hash.transaction_identifier,
+        transaction_identifier(&ledger_transaction)
+    );
+
+    let block = req_handler
+        .block(BlockRequest::new(
+            req_handler.network_id(),
+            PartialBlockIdentifier {
+                index: Some(1),
+                hash: None,
+            },
+        ))
+        .await
+        .unwrap()
+        .block
+        .unwrap();
+    assert_eq!(block.block_identifier, block_id(&hashed_blocks[1]).unwrap());
+    assert_eq!(
+        block.transactions[0].transaction_identifier,
+        hash.transaction_identifier
+    );
+}
+
+#[actix_rt::test]
+async fn account_balance_and_block_from_synced_store() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+
+    let alice = Account::from(PrincipalId::new_user_test_id(1));
+    let bob = Account {
+        owner: PrincipalId::new_user_test_id(2),
+        subaccount: Some([2; 32]),
+    };
+    let transactions = vec![
+        Transaction::mint(alice.clone(), Tokens::from_e8s(1_000_000), None, None),
+        Transaction::transfer(
+            alice.clone(),
+            bob.clone(),
+            Tokens::from_e8s(100_000),
+            Tokens::from_e8s(10_000),
+            None,
+            Some(Memo::from(1_u64)),
+        ),
+    ];
+    let hashed_blocks = store_transactions(tmpdir.path(), transactions.clone());
+    let req_handler = new_request_handler(tmpdir.path()).await;
+
+    let balance = |account: &Account, index: Option<i64>| {
+        let mut msg = AccountBalanceRequest::new(
+            req_handler.network_id(),
+            to_model_account_identifier(account),
+        );
+        msg.block_identifier = index.map(|index| PartialBlockIdentifier {
+            index: Some(index),
+            hash: None,
+        });
+        let req_handler = req_handler.clone();
+        async move { req_handler.account_balance(msg).await }
+    };
+
+    let res = balance(&alice, None).await.unwrap();
+    assert_eq!(res.block_identifier, block_id(&hashed_blocks[1]).unwrap());
+    assert_eq!(res.balances[0].value, "890000");
+    assert_eq!(res.balances[0].currency, currency());
+
+    let res = balance(&alice, Some(0)).await.unwrap();
+    assert_eq!(res.block_identifier, block_id(&hashed_blocks[0]).unwrap());
+    assert_eq!(res.balances[0].value, "1000000");
+
+    let res = balance(&bob, None).await.unwrap();
+    assert_eq!(res.balances[0].value, "100000");
+
+    assert!(balance(&alice, Some(2)).await.is_err());
+
+    for (index, hb) in hashed_blocks.iter().enumerate() {
+        let block = req_handler
+            .block(BlockRequest::new(
+                req_handler.network_id(),
+                PartialBlockIdentifier {
+                    index: None,
+                    hash: Some(block_id(hb).unwrap().hash),
+                },
+            ))
+            .await
+            .unwrap()
+            .block
+            .unwrap();
+        assert_eq!(block.block_identifier, block_id(hb).unwrap());
+        // The first block is its own parent.
+        let parent = &hashed_blocks[index.saturating_sub(1)];
+        assert_eq!(block.parent_block_identifier, block_id(parent).unwrap());
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(
+            block.transactions[0].transaction_identifier,
+            transaction_identifier(&transactions[index])
+        );
+    }
+
+    let transfer = req_handler
+        .block(BlockRequest::new(
+            req_handler.network_id(),
+            PartialBlockIdentifier {
+                index: Some(1),
+                hash: None,
+            },
+        ))
+        .await
+        .unwrap()
+        .block
+        .unwrap()
+        .transactions
+        .remove(0);
+    assert_eq!(
+        transfer.operations.len(),
+        3,
+        "expected a debit, a credit and a fee: {:?}",
+        transfer.operations
+    );
+
+    assert!(req_handler
+        .block(BlockRequest::new(
+            req_handler.network_id(),
+            PartialBlockIdentifier {
+                index: Some(2),
+                hash: None,
+            },
+        ))
+        .await
+        .is_err());
+}
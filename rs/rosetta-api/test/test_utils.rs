@@ -1,4 +1,5 @@
 mod basic_tests;
+mod icrc1_tests;
 mod rosetta_cli_tests;
 
 use ic_ledger_canister_blocks_synchronizer_test_utils::sample_data::{acc_id, Scribe};
@@ -37,7 +38,10 @@ use std::str::FromStr;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 
-use ic_nns_governance::pb::v1::manage_neuron::NeuronIdOrSubaccount;
+use ic_nns_governance::pb::v1::{
+    governance_error::ErrorType, manage_neuron::NeuronIdOrSubaccount, GovernanceError, NeuronInfo,
+    ProposalInfo,
+};
 use ic_rosetta_api::request::request_result::RequestResult;
 use ic_rosetta_api::request::transaction_results::TransactionResults;
 use ic_rosetta_api::request::Request;
@@ -50,6 +54,10 @@ pub struct TestLedger {
     pub governance_canister_id: CanisterId,
     pub submit_queue: RwLock<Vec<HashedBlock>>,
     pub transfer_fee: Tokens,
+    /// The neurons returned by `neuron_info`, by subaccount.
+    pub neurons: Mutex<BTreeMap<Vec<u8>, NeuronInfo>>,
+    /// The proposals returned by `proposal_info`, by ID.
+    pub proposals: Mutex<BTreeMap<u64, ProposalInfo>>,
     next_block_timestamp: Mutex<TimeStamp>,
 }
 
@@ -64,6 +72,8 @@ impl TestLedger {
             governance_canister_id: ic_nns_constants::GOVERNANCE_CANISTER_ID,
             submit_queue: RwLock::new(Vec::new()),
             transfer_fee: DEFAULT_TRANSFER_FEE,
+            neurons: Mutex::new(BTreeMap::new()),
+            proposals: Mutex::new(BTreeMap::new()),
             next_block_timestamp: Mutex::new(TimeStamp::from_nanos_since_unix_epoch(
                 FIRST_BLOCK_TIMESTAMP_NANOS_SINCE_EPOC,
             )),
@@ -218,10 +228,33 @@ impl LedgerAccess for TestLedger {
 
     async fn neuron_info(
         &self,
-        _id: NeuronIdOrSubaccount,
+        id: NeuronIdOrSubaccount,
         _: bool,
-    ) -> Result<ic_nns_governance::pb::v1::NeuronInfo, ApiError> {
-        panic!("Neuron info not available through TestLedger");
+    ) -> Result<Result<NeuronInfo, GovernanceError>, ApiError> {
+        let subaccount = match id {
+            NeuronIdOrSubaccount::Subaccount(subaccount) => subaccount,
+            NeuronIdOrSubaccount::NeuronId(_) => {
+                panic!("TestLedger only looks up neurons by subaccount")
+            }
+        };
+        Ok(self
+            .neurons
+            .lock()
+            .unwrap()
+            .get(&subaccount)
+            .cloned()
+            .ok_or_else(|| {
+                GovernanceError::new_with_message(ErrorType::NotFound, "Neuron not found")
+            }))
+    }
+
+    async fn proposal_info(&self, proposal_id: u64) -> Result<ProposalInfo, ApiError> {
+        self.proposals
+            .lock()
+            .unwrap()
+            .get(&proposal_id)
+            .cloned()
+            .ok_or_else(|| ApiError::invalid_request(format!("Proposal {} not found", proposal_id)))
     }
 
     async fn transfer_fee(&self) -> Result<TransferFee, ApiError> {
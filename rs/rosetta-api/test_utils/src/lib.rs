@@ -9,8 +9,8 @@ use ic_rosetta_api::models::{
 };
 use ic_rosetta_api::models::{ConstructionSubmitResponse, Error as RosettaError};
 use ic_rosetta_api::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, RemoveHotKey, SetDissolveTimestamp,
-    Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, Follow, Merge, MergeMaturity, NeuronInfo, RegisterVote, RemoveHotKey,
+    SetDissolveTimestamp, Spawn, Split, Stake, StakeMaturity, StartDissolve, StopDissolve,
 };
 use ic_rosetta_api::transaction_id::TransactionIdentifier;
 use ic_rosetta_api::{convert, errors, errors::ApiError, DEFAULT_TOKEN_SYMBOL};
@@ -110,7 +110,11 @@ pub async fn prepare_multiple_txn(
             | Request::Spawn(Spawn { account, .. })
             | Request::MergeMaturity(MergeMaturity { account, .. })
             | Request::NeuronInfo(NeuronInfo { account, .. })
-            | Request::Follow(Follow { account, .. }) => {
+            | Request::Follow(Follow { account, .. })
+            | Request::Split(Split { account, .. })
+            | Request::Merge(Merge { account, .. })
+            | Request::StakeMaturity(StakeMaturity { account, .. })
+            | Request::RegisterVote(RegisterVote { account, .. }) => {
                 all_sender_account_ids.push(to_model_account_identifier(&account));
             }
             Request::Transfer(Operation::Burn { .. }) => {
@@ -4036,6 +4036,7 @@ mod tests {
     use ic_canister_client_sender::Sender;
     use ic_ic00_types::{
         CanisterIdRecord, CanisterInstallMode, CanisterStatusResultV2, CanisterStatusType,
+        QueryStatsResponse,
     };
     use ic_nervous_system_common_test_keys::TEST_USER1_KEYPAIR;
     use ic_nns_constants::SNS_WASM_CANISTER_ID;
@@ -4679,6 +4680,11 @@ mod tests {
             Some(0),
             0,
             0,
+            0,
+            None,
+            0,
+            None,
+            QueryStatsResponse::default(),
         )
     }
 
@@ -7,7 +7,7 @@ use dfn_core::{
     over, over_async, over_init, println,
 };
 use ic_base_types::PrincipalId;
-use ic_ic00_types::CanisterStatusResultV2;
+use ic_ic00_types::{CanisterStatusResultV2, QueryStatsResponse};
 use ic_ledger_core::Tokens;
 use ic_nervous_system_common::stable_mem_utils::{
     BufferedStableMemReader, BufferedStableMemWriter,
@@ -509,6 +509,11 @@ mod tests {
             None,
             0,
             0,
+            0,
+            None,
+            0,
+            None,
+            QueryStatsResponse::default(),
         )
     }
 
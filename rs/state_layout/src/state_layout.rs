@@ -3,6 +3,7 @@ use crate::utils::do_copy;
 
 use bitcoin::{hashes::Hash, Network, OutPoint, Script, TxOut, Txid};
 use ic_base_types::{NumBytes, NumSeconds};
+use ic_ic00_types::{CanisterLogRecord, LogVisibility};
 use ic_logger::{error, ReplicaLogger};
 use ic_protobuf::{
     bitcoin::v1 as pb_bitcoin,
@@ -14,12 +15,14 @@ use ic_protobuf::{
 };
 use ic_replicated_state::{
     bitcoin_state, canister_state::execution_state::WasmMetadata, CallContextManager,
-    CanisterStatus, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
+    CanisterHistory, CanisterStatus, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
+    SnapshotId,
 };
 use ic_sys::mmap::ScopedMmap;
 use ic_types::{
-    nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId, ComputeAllocation, Cycles,
-    ExecutionRound, Height, MemoryAllocation, NumInstructions, PrincipalId,
+    batch::TotalQueryStats, canister_log::CanisterLog, nominal_cycles::NominalCycles,
+    AccumulatedPriority, CanisterId, ComputeAllocation, Cycles, ExecutionRound, Height,
+    MemoryAllocation, NumInstructions, PrincipalId, Time,
 };
 use ic_utils::fs::{sync_and_mark_files_readonly, sync_path};
 use ic_utils::thread::parallel_map;
@@ -125,6 +128,28 @@ pub struct CanisterStateBits {
     pub install_code_debit: NumInstructions,
     pub task_queue: Vec<ExecutionTask>,
     pub time_of_last_allocation_charge_nanos: Option<u64>,
+    pub global_timer_nanos: Option<u64>,
+    pub canister_log: CanisterLog,
+    pub log_visibility: LogVisibility,
+    pub canister_version: u64,
+    pub canister_history: CanisterHistory,
+    pub wasm_memory_limit: Option<NumBytes>,
+    pub reserved_balance: Cycles,
+    pub reserved_balance_limit: Option<Cycles>,
+    pub total_query_stats: TotalQueryStats,
+}
+
+/// This struct contains bits of the `CanisterSnapshot` that are not already
+/// covered somewhere else and are too small to be serialized separately.
+#[derive(Debug)]
+pub struct CanisterSnapshotBits {
+    pub canister_id: CanisterId,
+    pub taken_at_timestamp: Time,
+    pub wasm_memory_size: NumWasmPages,
+    pub stable_memory_size: NumWasmPages,
+    pub exported_globals: Vec<Global>,
+    pub certified_data: Vec<u8>,
+    pub binary_hash: WasmHash,
 }
 
 /// This struct contains bits of the `BitcoinState` that are not already
@@ -775,6 +800,29 @@ impl StateLayout {
         }
     }
 
+    /// Deletes canister snapshots from tip if they are not in ids.
+    pub fn filter_tip_snapshots(
+        &self,
+        height: Height,
+        ids: &BTreeSet<&SnapshotId>,
+    ) -> Result<(), LayoutError> {
+        let tip = self.tip(height)?;
+        let snapshots_on_disk = tip.snapshot_ids()?;
+        for id in snapshots_on_disk {
+            if !ids.contains(&id) {
+                let snapshot_path = tip.snapshot(&id)?.raw_path();
+                let tmp_path = self.fs_tmp().join(format!("snapshot_{}", &id));
+                self.atomically_remove_via_path(&snapshot_path, &tmp_path)
+                    .map_err(|err| LayoutError::IoError {
+                        path: snapshot_path,
+                        message: "Cannot atomically remove canister snapshot.".to_string(),
+                        io_err: err,
+                    })?;
+            }
+        }
+        Ok(())
+    }
+
     /// Deletes canisters from tip if they are not in ids.
     pub fn filter_tip_canisters(
         &self,
@@ -934,6 +982,32 @@ impl<Permissions: AccessPolicy> CheckpointLayout<Permissions> {
         )
     }
 
+    pub fn snapshot_ids(&self) -> Result<Vec<SnapshotId>, LayoutError> {
+        let snapshots_dir = self.root.join("canister_snapshots");
+        Permissions::check_dir(&snapshots_dir)?;
+        collect_subdirs(snapshots_dir.as_path(), |p| {
+            let blob = hex::decode(p).unwrap_or_else(|err| {
+                panic!(
+                    "Failed to convert directory name {} into a snapshot id: {}",
+                    p, err
+                )
+            });
+
+            SnapshotId::try_from(&blob[..]).expect("failed to parse snapshot id")
+        })
+    }
+
+    pub fn snapshot(
+        &self,
+        snapshot_id: &SnapshotId,
+    ) -> Result<CanisterSnapshotLayout<Permissions>, LayoutError> {
+        CanisterSnapshotLayout::new(
+            self.root
+                .join("canister_snapshots")
+                .join(hex::encode(snapshot_id.to_bytes())),
+        )
+    }
+
     pub fn bitcoin(&self) -> Result<BitcoinStateLayout<Permissions>, LayoutError> {
         // TODO(EXC-1113): Rename this path to "bitcoin", as it stores data for either network.
         BitcoinStateLayout::new(self.root.join("bitcoin").join("testnet"))
@@ -989,6 +1063,43 @@ impl<Permissions: AccessPolicy> CanisterLayout<Permissions> {
     }
 }
 
+pub struct CanisterSnapshotLayout<Permissions: AccessPolicy> {
+    snapshot_root: PathBuf,
+    permissions_tag: PhantomData<Permissions>,
+}
+
+impl<Permissions: AccessPolicy> CanisterSnapshotLayout<Permissions> {
+    pub fn new(snapshot_root: PathBuf) -> Result<Self, LayoutError> {
+        Permissions::check_dir(&snapshot_root)?;
+        Ok(Self {
+            snapshot_root,
+            permissions_tag: PhantomData,
+        })
+    }
+
+    pub fn raw_path(&self) -> PathBuf {
+        self.snapshot_root.clone()
+    }
+
+    pub fn snapshot(
+        &self,
+    ) -> ProtoFileWith<pb_canister_state_bits::CanisterSnapshotBits, Permissions> {
+        self.snapshot_root.join("snapshot.pbuf").into()
+    }
+
+    pub fn wasm(&self) -> WasmFile<Permissions> {
+        self.snapshot_root.join("software.wasm").into()
+    }
+
+    pub fn vmemory_0(&self) -> PathBuf {
+        self.snapshot_root.join("vmemory_0.bin")
+    }
+
+    pub fn stable_memory_blob(&self) -> PathBuf {
+        self.snapshot_root.join("stable_memory.bin")
+    }
+}
+
 pub struct BitcoinStateLayout<Permissions: AccessPolicy> {
     bitcoin_root: PathBuf,
     permissions_tag: PhantomData<Permissions>,
@@ -1253,6 +1364,31 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
             install_code_debit: item.install_code_debit.get(),
             time_of_last_allocation_charge_nanos: item.time_of_last_allocation_charge_nanos,
             task_queue: item.task_queue.iter().map(|v| v.into()).collect(),
+            global_timer_nanos: item.global_timer_nanos,
+            canister_log_records: item
+                .canister_log
+                .records()
+                .iter()
+                .map(|record| pb_canister_state_bits::CanisterLogRecord {
+                    idx: record.idx,
+                    timestamp_nanos: record.timestamp_nanos,
+                    content: record.content.clone(),
+                })
+                .collect(),
+            next_canister_log_record_idx: item.canister_log.next_idx(),
+            log_visibility: pb_canister_state_bits::LogVisibility::from(item.log_visibility).into(),
+            canister_version: item.canister_version,
+            canister_history: item
+                .canister_history
+                .changes()
+                .iter()
+                .map(|change| change.into())
+                .collect(),
+            total_num_changes: item.canister_history.get_total_num_changes(),
+            wasm_memory_limit: item.wasm_memory_limit.map(|limit| limit.get()),
+            reserved_balance: Some(item.reserved_balance.into()),
+            reserved_balance_limit: item.reserved_balance_limit.map(|limit| limit.into()),
+            total_query_stats: Some((&item.total_query_stats).into()),
         }
     }
 }
@@ -1292,12 +1428,70 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             .transpose()?
             .unwrap_or_else(Cycles::zero);
 
+        let reserved_balance = value
+            .reserved_balance
+            .map(|c| c.try_into())
+            .transpose()?
+            .unwrap_or_else(Cycles::zero);
+
+        let reserved_balance_limit = value
+            .reserved_balance_limit
+            .map(|c| c.try_into())
+            .transpose()?;
+
+        let total_query_stats = value
+            .total_query_stats
+            .map(|stats| stats.try_into())
+            .transpose()?
+            .unwrap_or_default();
+
         let task_queue = value
             .task_queue
             .into_iter()
             .map(|v| v.try_into())
             .collect::<Result<_, _>>()?;
 
+        let canister_log = CanisterLog::new(
+            value.next_canister_log_record_idx,
+            value
+                .canister_log_records
+                .into_iter()
+                .map(|record| CanisterLogRecord {
+                    idx: record.idx,
+                    timestamp_nanos: record.timestamp_nanos,
+                    content: record.content,
+                })
+                .collect(),
+        );
+
+        // Checkpoints written before the log visibility was introduced have
+        // it unspecified, which corresponds to the default.
+        let log_visibility =
+            match pb_canister_state_bits::LogVisibility::from_i32(value.log_visibility) {
+                Some(pb_canister_state_bits::LogVisibility::Unspecified) => {
+                    LogVisibility::default()
+                }
+                Some(log_visibility) => LogVisibility::try_from(log_visibility)?,
+                None => {
+                    return Err(ProxyDecodeError::ValueOutOfRange {
+                        typ: "LogVisibility",
+                        err: format!(
+                            "Unexpected value of log visibility: {}",
+                            value.log_visibility
+                        ),
+                    })
+                }
+            };
+
+        let canister_history = CanisterHistory::new(
+            value
+                .canister_history
+                .into_iter()
+                .map(|change| change.try_into())
+                .collect::<Result<_, _>>()?,
+            value.total_num_changes,
+        );
+
         Ok(Self {
             controllers,
             last_full_execution_round: value.last_full_execution_round.into(),
@@ -1337,6 +1531,15 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             )
             .ok(),
             task_queue,
+            global_timer_nanos: value.global_timer_nanos,
+            canister_log,
+            log_visibility,
+            canister_version: value.canister_version,
+            canister_history,
+            wasm_memory_limit: value.wasm_memory_limit.map(NumBytes::from),
+            reserved_balance,
+            reserved_balance_limit,
+            total_query_stats,
         })
     }
 }
@@ -1394,6 +1597,55 @@ impl TryFrom<pb_canister_state_bits::ExecutionStateBits> for ExecutionStateBits
     }
 }
 
+impl From<&CanisterSnapshotBits> for pb_canister_state_bits::CanisterSnapshotBits {
+    fn from(item: &CanisterSnapshotBits) -> Self {
+        Self {
+            canister_id: Some(item.canister_id.into()),
+            taken_at_timestamp_nanos: item.taken_at_timestamp.as_nanos_since_unix_epoch(),
+            wasm_memory_size: item.wasm_memory_size.get() as u64,
+            stable_memory_size: item.stable_memory_size.get() as u64,
+            exported_globals: item
+                .exported_globals
+                .iter()
+                .map(|global| global.into())
+                .collect(),
+            certified_data: item.certified_data.clone(),
+            binary_hash: item.binary_hash.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterSnapshotBits> for CanisterSnapshotBits {
+    type Error = ProxyDecodeError;
+    fn try_from(value: pb_canister_state_bits::CanisterSnapshotBits) -> Result<Self, Self::Error> {
+        let mut exported_globals = Vec::with_capacity(value.exported_globals.len());
+        for g in value.exported_globals.into_iter() {
+            exported_globals.push(g.try_into()?);
+        }
+        let binary_hash: [u8; 32] =
+            value
+                .binary_hash
+                .try_into()
+                .map_err(|e| ProxyDecodeError::ValueOutOfRange {
+                    typ: "BinaryHash",
+                    err: format!("Expected a 32-byte long module hash, got {:?}", e),
+                })?;
+
+        Ok(Self {
+            canister_id: try_from_option_field(
+                value.canister_id,
+                "CanisterSnapshotBits::canister_id",
+            )?,
+            taken_at_timestamp: Time::from_nanos_since_unix_epoch(value.taken_at_timestamp_nanos),
+            wasm_memory_size: NumWasmPages::from(value.wasm_memory_size as usize),
+            stable_memory_size: NumWasmPages::from(value.stable_memory_size as usize),
+            exported_globals,
+            certified_data: value.certified_data,
+            binary_hash: binary_hash.into(),
+        })
+    }
+}
+
 impl From<&BitcoinStateBits> for pb_bitcoin::BitcoinStateBits {
     fn from(item: &BitcoinStateBits) -> Self {
         pb_bitcoin::BitcoinStateBits {
@@ -1653,10 +1905,12 @@ fn build_copy_plan(src: &Path, dst: &Path, plan: &mut CopyPlan) -> std::io::Resu
 mod test {
     use super::*;
 
-    use ic_ic00_types::IC_00;
+    use ic_ic00_types::{
+        CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode, IC_00,
+    };
     use ic_interfaces::messages::{CanisterInputMessage, RequestOrIngress};
     use ic_test_utilities::types::{
-        ids::canister_test_id,
+        ids::{canister_test_id, user_test_id},
         messages::{IngressBuilder, RequestBuilder, ResponseBuilder},
     };
     use std::sync::Arc;
@@ -1685,6 +1939,15 @@ mod test {
             install_code_debit: NumInstructions::from(0),
             time_of_last_allocation_charge_nanos: None,
             task_queue: vec![],
+            global_timer_nanos: None,
+            canister_log: Default::default(),
+            log_visibility: Default::default(),
+            canister_version: 0,
+            canister_history: CanisterHistory::default(),
+            wasm_memory_limit: None,
+            reserved_balance: Cycles::zero(),
+            reserved_balance_limit: None,
+            total_query_stats: TotalQueryStats::default(),
         }
     }
 
@@ -1746,4 +2009,122 @@ mod test {
         let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
         assert_eq!(canister_state_bits.task_queue, task_queue);
     }
+
+    #[test]
+    fn test_encode_decode_canister_log() {
+        let mut canister_log = CanisterLog::new_empty(3);
+        canister_log.add_record(10, b"first");
+        canister_log.add_record(20, b"second");
+        let canister_state_bits = CanisterStateBits {
+            canister_log: canister_log.clone(),
+            log_visibility: LogVisibility::Public,
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+        assert_eq!(canister_state_bits.canister_log, canister_log);
+        assert_eq!(canister_state_bits.log_visibility, LogVisibility::Public);
+    }
+
+    #[test]
+    fn test_encode_decode_reserved_balance() {
+        let canister_state_bits = CanisterStateBits {
+            reserved_balance: Cycles::new(1_000),
+            reserved_balance_limit: Some(Cycles::new(2_000)),
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+        assert_eq!(canister_state_bits.reserved_balance, Cycles::new(1_000));
+        assert_eq!(
+            canister_state_bits.reserved_balance_limit,
+            Some(Cycles::new(2_000))
+        );
+    }
+
+    #[test]
+    fn test_encode_decode_total_query_stats() {
+        let total_query_stats = TotalQueryStats {
+            num_calls: 1,
+            num_instructions: u128::MAX,
+            ingress_payload_size: 3,
+            egress_payload_size: 4,
+        };
+        let canister_state_bits = CanisterStateBits {
+            total_query_stats: total_query_stats.clone(),
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+        assert_eq!(canister_state_bits.total_query_stats, total_query_stats);
+    }
+
+    #[test]
+    fn test_encode_decode_canister_history() {
+        let mut canister_history = CanisterHistory::default();
+        canister_history.add_canister_change(CanisterChange::new(
+            10,
+            0,
+            CanisterChangeOrigin::from_user(user_test_id(1).get()),
+            CanisterChangeDetails::canister_creation(vec![user_test_id(1).get()]),
+        ));
+        canister_history.add_canister_change(CanisterChange::new(
+            20,
+            1,
+            CanisterChangeOrigin::from_canister(canister_test_id(2).get(), Some(5)),
+            CanisterChangeDetails::code_deployment(CanisterInstallMode::Upgrade, [3; 32]),
+        ));
+        canister_history.add_canister_change(CanisterChange::new(
+            30,
+            2,
+            CanisterChangeOrigin::from_user(user_test_id(1).get()),
+            CanisterChangeDetails::code_uninstall(),
+        ));
+        canister_history.add_canister_change(CanisterChange::new(
+            40,
+            3,
+            CanisterChangeOrigin::from_canister(canister_test_id(2).get(), None),
+            CanisterChangeDetails::controllers_change(vec![canister_test_id(2).get()]),
+        ));
+        canister_history.add_canister_change(CanisterChange::new(
+            50,
+            4,
+            CanisterChangeOrigin::from_user(user_test_id(1).get()),
+            CanisterChangeDetails::load_snapshot(vec![0, 1, 2], 45),
+        ));
+        let canister_state_bits = CanisterStateBits {
+            canister_history: canister_history.clone(),
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+        assert_eq!(canister_state_bits.canister_history, canister_history);
+    }
+
+    #[test]
+    fn test_encode_decode_canister_snapshot_bits() {
+        let snapshot_bits = CanisterSnapshotBits {
+            canister_id: canister_test_id(7),
+            taken_at_timestamp: Time::from_nanos_since_unix_epoch(42),
+            wasm_memory_size: NumWasmPages::from(3),
+            stable_memory_size: NumWasmPages::from(1),
+            exported_globals: vec![Global::I32(1), Global::I64(2)],
+            certified_data: vec![1, 2, 3],
+            binary_hash: [5; 32].into(),
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterSnapshotBits::from(&snapshot_bits);
+        let decoded = CanisterSnapshotBits::try_from(pb_bits).unwrap();
+        assert_eq!(decoded.canister_id, snapshot_bits.canister_id);
+        assert_eq!(decoded.taken_at_timestamp, snapshot_bits.taken_at_timestamp);
+        assert_eq!(decoded.wasm_memory_size, snapshot_bits.wasm_memory_size);
+        assert_eq!(decoded.stable_memory_size, snapshot_bits.stable_memory_size);
+        assert_eq!(decoded.exported_globals, snapshot_bits.exported_globals);
+        assert_eq!(decoded.certified_data, snapshot_bits.certified_data);
+        assert_eq!(decoded.binary_hash, snapshot_bits.binary_hash);
+    }
 }
@@ -0,0 +1,292 @@
+//! A fake bitcoin network that stands in for the bitcoin adapter of a
+//! `StateMachine`.
+//!
+//! The fake maintains a single chain of blocks that starts at the genesis
+//! block of the configured network. Blocks are only mined on request and
+//! their proof of work is not solved, because the bitcoin canister does not
+//! validate it.
+use bitcoin::{
+    blockdata::{constants::genesis_block, script::Builder},
+    consensus::{deserialize, serialize},
+    hashes::Hash,
+    util::hash::bitcoin_merkle_root,
+    Address, Block, BlockHeader, Network, OutPoint, Transaction, TxIn, TxMerkleNode, TxOut,
+    Witness,
+};
+use ic_btc_types_internal::{
+    BitcoinAdapterRequestWrapper, BitcoinAdapterResponse, BitcoinAdapterResponseWrapper,
+    Block as InternalBlock, BlockHeader as InternalBlockHeader,
+    CanisterGetSuccessorsRequestInitial, CanisterGetSuccessorsResponseComplete,
+    GetSuccessorsRequest, GetSuccessorsResponse, OutPoint as InternalOutPoint,
+    SendTransactionResponse, Transaction as InternalTransaction, TxIn as InternalTxIn,
+    TxOut as InternalTxOut,
+};
+use ic_ic00_types::BitcoinNetwork;
+use std::str::FromStr;
+
+/// The time between two consecutive blocks mined by the fake.
+const BLOCK_INTERVAL_SECONDS: u32 = 10 * 60;
+
+// NOTE: The following limits should be the same as in the bitcoin adapter
+// (`rs/bitcoin/adapter/src/get_successors_handler.rs`).
+
+/// The maximum number of headers of upcoming blocks in a response.
+const MAX_NEXT_BLOCK_HASHES_LENGTH: usize = 100;
+
+/// The maximum number of bytes the blocks in a response can take. It is only
+/// honored if the response has at least one block already.
+const MAX_BLOCKS_BYTES: usize = 2_000_000 - MAX_NEXT_BLOCK_HASHES_LENGTH * 32;
+
+#[derive(Clone)]
+pub(crate) struct FakeBitcoinNetwork {
+    /// The main chain, starting at the genesis block.
+    blocks: Vec<Block>,
+    /// The transactions sent to the network, in the order they were sent.
+    transactions: Vec<Transaction>,
+}
+
+impl FakeBitcoinNetwork {
+    pub(crate) fn new(network: BitcoinNetwork) -> Self {
+        let network = match network {
+            BitcoinNetwork::Mainnet => Network::Bitcoin,
+            BitcoinNetwork::Testnet => Network::Testnet,
+            BitcoinNetwork::Regtest => Network::Regtest,
+        };
+        Self {
+            blocks: vec![genesis_block(network)],
+            transactions: vec![],
+        }
+    }
+
+    /// Mines a block on top of the chain whose coinbase transaction pays the
+    /// specified amounts (in satoshi) to the specified addresses.
+    ///
+    /// Panics if any of the addresses is invalid.
+    pub(crate) fn mine_block(&mut self, outputs: &[(&str, u64)]) -> Block {
+        let height = self.blocks.len();
+        let coinbase = Transaction {
+            version: 1,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                // Like BIP-34 requires, the coinbase contains the height of
+                // the block, which makes its transaction id unique.
+                script_sig: Builder::new().push_int(height as i64).into_script(),
+                sequence: 0xffffffff,
+                witness: Witness::new(),
+            }],
+            output: outputs
+                .iter()
+                .map(|(address, value)| TxOut {
+                    value: *value,
+                    script_pubkey: Address::from_str(address)
+                        .unwrap_or_else(|err| {
+                            panic!("invalid bitcoin address {}: {}", address, err)
+                        })
+                        .script_pubkey(),
+                })
+                .collect(),
+        };
+        let txdata = vec![coinbase];
+        let merkle_root = TxMerkleNode::from_hash(
+            bitcoin_merkle_root(txdata.iter().map(|tx| tx.txid().as_hash()))
+                .expect("a block has at least one transaction"),
+        );
+
+        let tip = &self.blocks.last().expect("the chain is never empty").header;
+        let block = Block {
+            header: BlockHeader {
+                version: 1,
+                prev_blockhash: tip.block_hash(),
+                merkle_root,
+                time: tip.time + BLOCK_INTERVAL_SECONDS,
+                bits: tip.bits,
+                nonce: 0,
+            },
+            txdata,
+        };
+        self.blocks.push(block.clone());
+        block
+    }
+
+    /// Returns the transactions sent to the network so far.
+    pub(crate) fn transactions(&self) -> &[Transaction] {
+        &self.transactions
+    }
+
+    /// Answers a request that the bitcoin canister made to the adapter.
+    pub(crate) fn respond(
+        &mut self,
+        callback_id: u64,
+        request: &BitcoinAdapterRequestWrapper,
+    ) -> BitcoinAdapterResponse {
+        let response = match request {
+            BitcoinAdapterRequestWrapper::GetSuccessorsRequest(GetSuccessorsRequest {
+                processed_block_hashes,
+                anchor,
+            }) => {
+                let (blocks, next) = self.successors(anchor, processed_block_hashes);
+                BitcoinAdapterResponseWrapper::GetSuccessorsResponse(GetSuccessorsResponse {
+                    blocks: blocks.into_iter().map(to_internal_block).collect(),
+                    next: next.into_iter().map(to_internal_block_header).collect(),
+                })
+            }
+            BitcoinAdapterRequestWrapper::SendTransactionRequest(request) => {
+                // The bitcoin canister only forwards transactions that it
+                // could deserialize.
+                self.transactions
+                    .push(deserialize(&request.transaction).expect("failed to decode transaction"));
+                BitcoinAdapterResponseWrapper::SendTransactionResponse(SendTransactionResponse {})
+            }
+            BitcoinAdapterRequestWrapper::CanisterGetSuccessorsRequest(
+                CanisterGetSuccessorsRequestInitial {
+                    anchor,
+                    processed_block_hashes,
+                    ..
+                },
+            ) => {
+                let (blocks, next) = self.successors(anchor, processed_block_hashes);
+                BitcoinAdapterResponseWrapper::CanisterGetSuccessorsResponse(
+                    CanisterGetSuccessorsResponseComplete {
+                        blocks: blocks.into_iter().map(serialize).collect(),
+                        next: next.into_iter().map(serialize).collect(),
+                    },
+                )
+            }
+        };
+        BitcoinAdapterResponse {
+            response,
+            callback_id,
+        }
+    }
+
+    /// Returns the blocks of the chain that follow the anchor and have not
+    /// been processed yet, as many as fit into a response of the bitcoin
+    /// adapter, along with the headers of the blocks after them.
+    fn successors(
+        &self,
+        anchor: &[u8],
+        processed_block_hashes: &[Vec<u8>],
+    ) -> (Vec<&Block>, Vec<&BlockHeader>) {
+        let start = self
+            .blocks
+            .iter()
+            .position(|block| block.block_hash()[..] == anchor[..])
+            .map_or(self.blocks.len(), |position| position + 1);
+        let mut unprocessed = self.blocks[start..]
+            .iter()
+            .filter(|block| {
+                let block_hash = block.block_hash();
+                !processed_block_hashes
+                    .iter()
+                    .any(|processed| processed[..] == block_hash[..])
+            })
+            .peekable();
+
+        let mut blocks = vec![];
+        let mut blocks_size = 0;
+        while let Some(block) = unprocessed
+            .next_if(|block| blocks.is_empty() || blocks_size + block.size() <= MAX_BLOCKS_BYTES)
+        {
+            blocks_size += block.size();
+            blocks.push(block);
+        }
+        let next = unprocessed
+            .take(MAX_NEXT_BLOCK_HASHES_LENGTH)
+            .map(|block| &block.header)
+            .collect();
+        (blocks, next)
+    }
+}
+
+fn to_internal_block(block: &Block) -> InternalBlock {
+    InternalBlock {
+        header: to_internal_block_header(&block.header),
+        txdata: block.txdata.iter().map(to_internal_transaction).collect(),
+    }
+}
+
+fn to_internal_block_header(header: &BlockHeader) -> InternalBlockHeader {
+    InternalBlockHeader {
+        version: header.version,
+        prev_blockhash: header.prev_blockhash.to_vec(),
+        merkle_root: header.merkle_root.to_vec(),
+        time: header.time,
+        bits: header.bits,
+        nonce: header.nonce,
+    }
+}
+
+fn to_internal_transaction(transaction: &Transaction) -> InternalTransaction {
+    InternalTransaction {
+        version: transaction.version,
+        lock_time: transaction.lock_time,
+        input: transaction
+            .input
+            .iter()
+            .map(|txin| InternalTxIn {
+                previous_output: InternalOutPoint {
+                    txid: txin.previous_output.txid.into_inner(),
+                    vout: txin.previous_output.vout,
+                },
+                script_sig: txin.script_sig.to_bytes(),
+                sequence: txin.sequence,
+                witness: txin
+                    .witness
+                    .iter()
+                    .map(|w| serde_bytes::ByteBuf::from(w.to_vec()))
+                    .collect(),
+            })
+            .collect(),
+        output: transaction
+            .output
+            .iter()
+            .map(|txout| InternalTxOut {
+                value: txout.value,
+                script_pubkey: txout.script_pubkey.to_bytes(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::Script;
+
+    #[test]
+    fn successors_are_capped_like_in_the_adapter() {
+        let mut network = FakeBitcoinNetwork::new(BitcoinNetwork::Regtest);
+        let address = Address::p2wsh(&Script::new(), Network::Regtest).to_string();
+        // Each block takes more than a third of the response limit.
+        let outputs = vec![(address.as_str(), 1); 20_000];
+        for _ in 0..3 {
+            network.mine_block(&outputs);
+        }
+        let anchor = network.blocks[0].block_hash().to_vec();
+
+        let (blocks, next) = network.successors(&anchor, &[]);
+        assert_eq!(blocks, vec![&network.blocks[1], &network.blocks[2]]);
+        assert_eq!(next, vec![&network.blocks[3].header]);
+
+        let processed = vec![network.blocks[1].block_hash().to_vec()];
+        let (blocks, next) = network.successors(&anchor, &processed);
+        assert_eq!(blocks, vec![&network.blocks[2], &network.blocks[3]]);
+        assert!(next.is_empty());
+    }
+
+    #[test]
+    fn successors_include_a_block_above_the_limit() {
+        let mut network = FakeBitcoinNetwork::new(BitcoinNetwork::Regtest);
+        let address = Address::p2wsh(&Script::new(), Network::Regtest).to_string();
+        // The first block alone exceeds the response limit.
+        let outputs = vec![(address.as_str(), 1); 50_000];
+        network.mine_block(&outputs);
+        network.mine_block(&[]);
+        let anchor = network.blocks[0].block_hash().to_vec();
+
+        let (blocks, next) = network.successors(&anchor, &[]);
+        assert_eq!(blocks, vec![&network.blocks[1]]);
+        assert_eq!(next, vec![&network.blocks[2].header]);
+    }
+}
@@ -1,22 +1,36 @@
+mod fake_bitcoin;
+
+use candid::Encode;
+use fake_bitcoin::FakeBitcoinNetwork;
+use ic_btc_types_internal::{BitcoinAdapterRequestWrapper, BitcoinAdapterResponse};
 use ic_config::flag_status::FlagStatus;
 use ic_config::{
     execution_environment::Config as HypervisorConfig,
     subnet_config::{SubnetConfig, SubnetConfigs},
 };
+use ic_crypto_extended_bip32::{DerivationIndex, DerivationPath as ExtendedBip32DerivationPath};
+use ic_crypto_internal_basic_sig_ecdsa_secp256k1 as ecdsa_secp256k1;
 use ic_crypto_internal_seed::Seed;
 use ic_crypto_internal_threshold_sig_bls12381::api::{
     combine_signatures, combined_public_key, keygen, sign_message,
 };
 use ic_crypto_internal_threshold_sig_bls12381::types::SecretKeyBytes;
+use ic_crypto_internal_threshold_sig_ecdsa::{EccCurveType, EccPoint, EccScalar};
 use ic_crypto_internal_types::sign::threshold_sig::public_key::CspThresholdSigPublicKey;
 use ic_crypto_tree_hash::{flatmap, Label, LabeledTree, LabeledTree::SubTree};
 use ic_cycles_account_manager::CyclesAccountManager;
-pub use ic_error_types::{ErrorCode, UserError};
+pub use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_execution_environment::ExecutionServices;
-use ic_ic00_types::{self as ic00, CanisterIdRecord, InstallCodeArgs, Method, Payload};
-pub use ic_ic00_types::{CanisterInstallMode, CanisterSettingsArgs, UpdateSettingsArgs};
+use ic_ic00_types::{
+    self as ic00, CanisterIdRecord, InstallCodeArgs, Method, Payload, SignWithECDSAReply,
+};
+pub use ic_ic00_types::{
+    BitcoinNetwork, CanisterHttpResponsePayload, CanisterInstallMode, CanisterSettingsArgs,
+    EcdsaCurve, EcdsaKeyId, HttpHeader, UpdateSettingsArgs,
+};
 use ic_interfaces::{
     certification::{Verifier, VerifierError},
+    certified_stream_store::{CertifiedStreamStore, EncodeStreamError},
     execution_environment::{IngressHistoryReader, QueryHandler},
     messaging::MessageRouting,
     registry::RegistryClient,
@@ -27,51 +41,72 @@ use ic_logger::ReplicaLogger;
 use ic_messaging::MessageRoutingImpl;
 use ic_metrics::MetricsRegistry;
 use ic_protobuf::registry::{
+    crypto::v1::EcdsaSigningSubnetList,
     node::v1::{ConnectionEndpoint, NodeRecord},
     provisional_whitelist::v1::ProvisionalWhitelist as PbProvisionalWhitelist,
     routing_table::v1::CanisterMigrations as PbCanisterMigrations,
     routing_table::v1::RoutingTable as PbRoutingTable,
+    subnet::v1::{EcdsaConfig, SubnetListRecord},
 };
 use ic_protobuf::types::v1::PrincipalId as PrincipalIdIdProto;
 use ic_protobuf::types::v1::SubnetId as SubnetIdProto;
 use ic_registry_client_fake::FakeRegistryClient;
 use ic_registry_client_helpers::subnet::SubnetListRegistry;
 use ic_registry_keys::{
-    make_canister_migrations_record_key, make_node_record_key,
-    make_provisional_whitelist_record_key, make_routing_table_record_key, ROOT_SUBNET_ID_KEY,
+    make_canister_migrations_record_key, make_ecdsa_signing_subnet_list_key, make_node_record_key,
+    make_provisional_whitelist_record_key, make_routing_table_record_key,
+    make_subnet_list_record_key, make_subnet_record_key, ROOT_SUBNET_ID_KEY,
 };
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_routing_table::{
     routing_table_insert_subnet, CanisterIdRange, CanisterIdRanges, RoutingTable,
 };
+use ic_registry_subnet_features::{BitcoinFeature, BitcoinFeatureStatus, SubnetFeatures};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::page_map::Buffer;
 use ic_replicated_state::{
     canister_state::{NumWasmPages, WASM_PAGE_SIZE_IN_BYTES},
+    metadata_state::subnet_call_context_manager::SignWithEcdsaContext,
     Memory, PageMap, ReplicatedState,
 };
+use ic_state_layout::StateLayout;
 use ic_state_manager::StateManagerImpl;
 use ic_test_utilities_metrics::fetch_histogram_stats;
-use ic_test_utilities_registry::{
-    add_subnet_record, insert_initial_dkg_transcript, SubnetRecordBuilder,
-};
+use ic_test_utilities_registry::{insert_initial_dkg_transcript, SubnetRecordBuilder};
 use ic_types::consensus::certification::CertificationContent;
+use ic_types::crypto::canister_threshold_sig::MasterEcdsaPublicKey;
 use ic_types::crypto::threshold_sig::ni_dkg::{NiDkgId, NiDkgTag, NiDkgTargetSubnet};
 pub use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
-use ic_types::crypto::{CombinedThresholdSig, CombinedThresholdSigOf, Signable, Signed};
+use ic_types::crypto::{
+    AlgorithmId, CombinedThresholdSig, CombinedThresholdSigOf, CryptoHash, CryptoHashOf, Signable,
+    Signed,
+};
 use ic_types::messages::Certificate;
-use ic_types::signature::ThresholdSignature;
+use ic_types::signature::{BasicSignatureBatch, ThresholdSignature};
 use ic_types::{
-    batch::{Batch, BatchPayload, IngressPayload},
+    batch::{
+        Batch, BatchPayload, CanisterHttpPayload, IngressPayload, SelfValidatingPayload,
+        XNetPayload,
+    },
+    canister_http::{
+        CanisterHttpReject, CanisterHttpResponse, CanisterHttpResponseContent,
+        CanisterHttpResponseMetadata, CanisterHttpResponseWithConsensus,
+        CANISTER_HTTP_ADAPTER_MAX_RESPONSE_SIZE, CANISTER_HTTP_RESPONSE_LIMIT,
+        CANISTER_HTTP_TIMEOUT_INTERVAL,
+    },
     consensus::certification::Certification,
     messages::{
-        Blob, HttpCallContent, HttpCanisterUpdate, HttpRequestEnvelope, SignedIngress, UserQuery,
+        Blob, CallbackId, HttpCallContent, HttpCanisterUpdate, HttpRequestEnvelope, Response,
+        SignedIngress, UserQuery,
     },
+    subnet_id_into_protobuf,
     time::current_time_and_expiry_time,
+    xnet::{CertifiedStreamSlice, StreamIndex},
     CryptoHashOfPartialState, Height, NodeId, NumberOfNodes, Randomness, RegistryVersion,
 };
 pub use ic_types::{
+    canister_http::{CanisterHttpRequestContext, CanisterHttpRequestId},
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::MessageId,
     time::Time,
@@ -103,20 +138,22 @@ impl Verifier for FakeVerifier {
 
 const GENESIS: Time = Time::from_nanos_since_unix_epoch(1_620_328_630_000_000_000);
 
-/// Constructs the initial version of the registry containing a subnet with the
-/// specified SUBNET_ID, with the node with the specified NODE_ID assigned to
-/// it.
+/// Constructs the initial version of the registry containing the specified
+/// subnets, each with the specified node assigned to it. The first subnet is
+/// the root subnet, holds and signs with the specified ECDSA keys, and has the
+/// bitcoin feature enabled for the specified network, if any.
 fn make_single_node_registry(
-    subnet_id: SubnetId,
-    subnet_type: SubnetType,
-    node_id: NodeId,
+    subnets: &[(SubnetId, SubnetType, NodeId)],
+    ecdsa_keys: &[EcdsaKeyId],
+    bitcoin_network: Option<BitcoinNetwork>,
 ) -> (Arc<ProtoRegistryDataProvider>, Arc<FakeRegistryClient>) {
     let registry_version = RegistryVersion::from(1);
     let data_provider = Arc::new(ProtoRegistryDataProvider::new());
 
+    let (root_subnet_id, _, _) = subnets.first().expect("at least one subnet is required");
     let root_subnet_id_proto = SubnetIdProto {
         principal_id: Some(PrincipalIdIdProto {
-            raw: subnet_id.get_ref().to_vec(),
+            raw: root_subnet_id.get_ref().to_vec(),
         }),
     };
     data_provider
@@ -128,7 +165,9 @@ fn make_single_node_registry(
         .unwrap();
 
     let mut routing_table = RoutingTable::new();
-    routing_table_insert_subnet(&mut routing_table, subnet_id).unwrap();
+    for (subnet_id, _, _) in subnets {
+        routing_table_insert_subnet(&mut routing_table, *subnet_id).unwrap();
+    }
     let pb_routing_table = PbRoutingTable::from(routing_table);
     data_provider
         .add(
@@ -145,42 +184,156 @@ fn make_single_node_registry(
             Some(pb_whitelist),
         )
         .unwrap();
-    let node_record = NodeRecord {
-        node_operator_id: vec![0],
-        xnet: None,
-        http: Some(ConnectionEndpoint {
-            ip_addr: "2a00:fb01:400:42:5000:22ff:fe5e:e3c4".into(),
-            port: 1234,
-            protocol: 0,
-        }),
-        p2p_flow_endpoints: vec![],
-        prometheus_metrics_http: None,
-        public_api: vec![],
-        private_api: vec![],
-        prometheus_metrics: vec![],
-        xnet_api: vec![],
+
+    for (subnet_id, subnet_type, node_id) in subnets {
+        let node_record = NodeRecord {
+            node_operator_id: vec![0],
+            xnet: None,
+            http: Some(ConnectionEndpoint {
+                ip_addr: "2a00:fb01:400:42:5000:22ff:fe5e:e3c4".into(),
+                port: 1234,
+                protocol: 0,
+            }),
+            p2p_flow_endpoints: vec![],
+            prometheus_metrics_http: None,
+            public_api: vec![],
+            private_api: vec![],
+            prometheus_metrics: vec![],
+            xnet_api: vec![],
+        };
+        data_provider
+            .add(
+                &make_node_record_key(*node_id),
+                registry_version,
+                Some(node_record),
+            )
+            .unwrap();
+
+        // Canister http requests are answered by mocked responses, see
+        // `StateMachine::mock_canister_http_response`, and requests to the
+        // bitcoin adapter by a fake bitcoin network.
+        let bitcoin = match bitcoin_network {
+            Some(network) if subnet_id == root_subnet_id => Some(BitcoinFeature {
+                network,
+                status: BitcoinFeatureStatus::Enabled,
+            }),
+            _ => None,
+        };
+        let mut record = SubnetRecordBuilder::from(&[*node_id])
+            .with_subnet_type(*subnet_type)
+            .with_features(
+                SubnetFeatures {
+                    http_requests: true,
+                    bitcoin,
+                    ..SubnetFeatures::default()
+                }
+                .into(),
+            );
+        if subnet_id == root_subnet_id && !ecdsa_keys.is_empty() {
+            record = record.with_ecdsa_config(EcdsaConfig {
+                quadruples_to_create_in_advance: 1,
+                key_ids: ecdsa_keys.iter().map(Into::into).collect(),
+                max_queue_size: 20,
+                signature_request_timeout_ns: None,
+            });
+        }
+        let record = record.build();
+
+        insert_initial_dkg_transcript(registry_version.get(), *subnet_id, &record, &data_provider);
+        data_provider
+            .add(
+                &make_subnet_record_key(*subnet_id),
+                registry_version,
+                Some(record),
+            )
+            .unwrap();
+    }
+
+    for key_id in ecdsa_keys {
+        data_provider
+            .add(
+                &make_ecdsa_signing_subnet_list_key(key_id),
+                registry_version,
+                Some(EcdsaSigningSubnetList {
+                    subnets: vec![subnet_id_into_protobuf(*root_subnet_id)],
+                }),
+            )
+            .unwrap();
+    }
+
+    // Set subnetwork list(needed for filling network_topology.nns_subnet_id)
+    let subnet_list_record = SubnetListRecord {
+        subnets: subnets
+            .iter()
+            .map(|(subnet_id, _, _)| subnet_id.get().into_vec())
+            .collect(),
     };
     data_provider
         .add(
-            &make_node_record_key(node_id),
+            &make_subnet_list_record_key(),
             registry_version,
-            Some(node_record),
+            Some(subnet_list_record),
         )
         .unwrap();
 
-    // Set subnetwork list(needed for filling network_topology.nns_subnet_id)
-    let record = SubnetRecordBuilder::from(&[node_id])
-        .with_subnet_type(subnet_type)
-        .build();
-
-    insert_initial_dkg_transcript(registry_version.get(), subnet_id, &record, &data_provider);
-    add_subnet_record(&data_provider, registry_version.get(), subnet_id, record);
-
     let registry_client = Arc::new(FakeRegistryClient::new(Arc::clone(&data_provider) as _));
     registry_client.update_to_latest_version();
     (data_provider, registry_client)
 }
 
+/// Returns the secret key of the test ECDSA key with the specified ID.
+fn ecdsa_secret_key(key_id: &EcdsaKeyId) -> EccScalar {
+    EccScalar::from_seed(EccCurveType::K256, Seed::from_bytes(key_id.name.as_bytes()))
+}
+
+/// Signs the message hash of the `sign_with_ecdsa` request with the key that
+/// is derived from `master_secret_key` for the caller and derivation path of
+/// the request, and returns the response to the request.
+fn sign_with_ecdsa_response(
+    callback_id: CallbackId,
+    context: &SignWithEcdsaContext,
+    master_secret_key: &EccScalar,
+) -> Response {
+    // Like the replica, derive the key of the caller with the caller's
+    // principal prepended to the derivation path, and an all-zero chain code.
+    let derivation_path = ExtendedBip32DerivationPath::new(
+        std::iter::once(context.request.sender.get().to_vec())
+            .chain(context.derivation_path.clone().into_iter())
+            .map(DerivationIndex)
+            .collect(),
+    );
+    let derived_key = derivation_path
+        .private_key_derivation(&master_secret_key.serialize(), &[0; 32])
+        .expect("failed to derive ECDSA key");
+    let secret_key = ecdsa_secp256k1::secret_key_from_components(
+        &derived_key.derived_private_key,
+        &ecdsa_secp256k1::types::PublicKeyBytes(derived_key.derived_public_key),
+    )
+    .expect("failed to encode ECDSA key");
+    let mut signature = ecdsa_secp256k1::sign(&context.message_hash, &secret_key)
+        .expect("failed to sign message hash")
+        .0;
+
+    // Like threshold signatures, the signature uses the smaller value of s.
+    let s = EccScalar::deserialize(EccCurveType::K256, &signature[32..]).unwrap();
+    if s.is_high() {
+        signature[32..].copy_from_slice(&s.negate().serialize());
+    }
+
+    Response {
+        originator: context.request.sender,
+        respondent: CanisterId::ic_00(),
+        originator_reply_callback: callback_id,
+        refund: context.request.payment,
+        response_payload: ic_types::messages::Payload::Data(
+            SignWithECDSAReply {
+                signature: signature.to_vec(),
+            }
+            .encode(),
+        ),
+    }
+}
+
 /// Convert an object into CBOR binary.
 fn into_cbor<R: Serialize>(r: &R) -> Vec<u8> {
     let mut ser = serde_cbor::Serializer::new(Vec::new());
@@ -193,6 +346,8 @@ fn into_cbor<R: Serialize>(r: &R) -> Vec<u8> {
 pub struct StateMachineConfig {
     subnet_config: SubnetConfig,
     hypervisor_config: HypervisorConfig,
+    ecdsa_keys: Vec<EcdsaKeyId>,
+    bitcoin_network: Option<BitcoinNetwork>,
 }
 
 impl StateMachineConfig {
@@ -200,14 +355,89 @@ impl StateMachineConfig {
         Self {
             subnet_config,
             hypervisor_config,
+            ecdsa_keys: vec![],
+            bitcoin_network: None,
+        }
+    }
+
+    /// Provisions a test ECDSA key with the specified ID in the subnet.
+    ///
+    /// The state machine answers `sign_with_ecdsa` requests for the key in
+    /// the round after they were made. The secret key is derived from the key
+    /// name, so all state machines hold the same key for the same name.
+    pub fn with_ecdsa_key(mut self, key_id: EcdsaKeyId) -> Self {
+        self.ecdsa_keys.push(key_id);
+        self
+    }
+
+    /// Enables the bitcoin feature for the specified network in the subnet.
+    ///
+    /// Instead of a bitcoin adapter, a fake bitcoin network answers the
+    /// requests of the bitcoin canister, see `StateMachine::mine_bitcoin_block`
+    /// and `StateMachine::bitcoin_transactions`.
+    pub fn with_bitcoin(mut self, network: BitcoinNetwork) -> Self {
+        self.bitcoin_network = Some(network);
+        self
+    }
+}
+
+impl Default for StateMachineConfig {
+    fn default() -> Self {
+        Self::new(
+            SubnetConfigs::default().own_subnet_config(SubnetType::System),
+            HypervisorConfig::default(),
+        )
+    }
+}
+
+/// The subnet simulated by a `StateMachine` and the registry describing it.
+#[derive(Clone)]
+struct SubnetSetup {
+    subnet_id: SubnetId,
+    node_id: NodeId,
+    registry_data_provider: Arc<ProtoRegistryDataProvider>,
+    registry_client: Arc<FakeRegistryClient>,
+    ecdsa_keys: Vec<EcdsaKeyId>,
+    bitcoin: Option<FakeBitcoinNetwork>,
+}
+
+impl SubnetSetup {
+    /// A single system subnet with a single node and its own registry, which
+    /// holds the specified ECDSA keys and is connected to the specified
+    /// bitcoin network, if any.
+    fn single_subnet(ecdsa_keys: Vec<EcdsaKeyId>, bitcoin_network: Option<BitcoinNetwork>) -> Self {
+        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+        let node_id = NodeId::from(PrincipalId::new_node_test_id(1));
+        let (registry_data_provider, registry_client) = make_single_node_registry(
+            &[(subnet_id, SubnetType::System, node_id)],
+            &ecdsa_keys,
+            bitcoin_network,
+        );
+        Self {
+            subnet_id,
+            node_id,
+            registry_data_provider,
+            registry_client,
+            ecdsa_keys,
+            bitcoin: bitcoin_network.map(FakeBitcoinNetwork::new),
         }
     }
 }
 
+/// A mocked response of a remote server to a canister http request.
+#[derive(Clone, Debug)]
+pub enum MockCanisterHttpResponse {
+    /// The server responded with the specified status, headers and body.
+    Reply(CanisterHttpResponsePayload),
+    /// The request failed, e.g. because the server could not be reached.
+    Reject { code: RejectCode, message: String },
+}
+
 /// Represents a replicated state machine detached from the network layer that
 /// can be used to test this part of the stack in isolation.
 pub struct StateMachine {
     subnet_id: SubnetId,
+    node_id: NodeId,
     public_key: ThresholdSigPublicKey,
     secret_key: SecretKeyBytes,
     registry_data_provider: Arc<ProtoRegistryDataProvider>,
@@ -222,6 +452,9 @@ pub struct StateMachine {
     checkpoints_enabled: std::cell::Cell<bool>,
     nonce: std::cell::Cell<u64>,
     time: std::cell::Cell<Time>,
+    canister_http_responses: std::cell::RefCell<Vec<CanisterHttpResponse>>,
+    ecdsa_secret_keys: BTreeMap<EcdsaKeyId, EccScalar>,
+    bitcoin: std::cell::RefCell<Option<FakeBitcoinNetwork>>,
 }
 
 impl Default for StateMachine {
@@ -249,17 +482,21 @@ impl StateMachine {
             GENESIS,
             None,
             false,
+            SubnetSetup::single_subnet(vec![], None),
         )
     }
 
     /// Constructs a new environment with the specified configuration.
     pub fn new_with_config(config: StateMachineConfig) -> Self {
+        let subnet_setup =
+            SubnetSetup::single_subnet(config.ecdsa_keys.clone(), config.bitcoin_network);
         Self::setup_from_dir(
             TempDir::new().expect("failed to create a temporary directory"),
             0,
             GENESIS,
             Some(config),
             false,
+            subnet_setup,
         )
     }
 
@@ -271,6 +508,7 @@ impl StateMachine {
         time: Time,
         config: Option<StateMachineConfig>,
         checkpoints_enabled: bool,
+        subnet_setup: SubnetSetup,
     ) -> Self {
         use slog::Drain;
 
@@ -286,8 +524,14 @@ impl StateMachine {
         let logger = slog::Logger::root(drain, slog::o!());
         let replica_logger: ReplicaLogger = logger.into();
 
-        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
-        let node_id = NodeId::from(PrincipalId::new_node_test_id(1));
+        let SubnetSetup {
+            subnet_id,
+            node_id,
+            registry_data_provider,
+            registry_client,
+            ecdsa_keys,
+            bitcoin,
+        } = subnet_setup;
         let metrics_registry = MetricsRegistry::new();
         let subnet_type = SubnetType::System;
         let (subnet_config, mut hypervisor_config) = match config {
@@ -298,9 +542,6 @@ impl StateMachine {
             ),
         };
 
-        let (registry_data_provider, registry_client) =
-            make_single_node_registry(subnet_id, subnet_type, node_id);
-
         let sm_config = ic_config::state_manager::Config::new(state_dir.path().to_path_buf());
 
         if !(std::env::var("SANDBOX_BINARY").is_ok() && std::env::var("LAUNCHER_BINARY").is_ok()) {
@@ -376,6 +617,7 @@ impl StateMachine {
 
         Self {
             subnet_id,
+            node_id,
             secret_key: secret_key_bytes.get(0).unwrap().unwrap(),
             public_key,
             registry_data_provider,
@@ -392,15 +634,33 @@ impl StateMachine {
             checkpoints_enabled: std::cell::Cell::new(checkpoints_enabled),
             nonce: std::cell::Cell::new(nonce),
             time: std::cell::Cell::new(time),
+            canister_http_responses: std::cell::RefCell::new(vec![]),
+            ecdsa_secret_keys: ecdsa_keys
+                .into_iter()
+                .map(|key_id| {
+                    let secret_key = ecdsa_secret_key(&key_id);
+                    (key_id, secret_key)
+                })
+                .collect(),
+            bitcoin: std::cell::RefCell::new(bitcoin),
         }
     }
 
-    fn into_components(self) -> (TempDir, u64, Time, bool) {
+    fn into_components(self) -> (TempDir, u64, Time, bool, SubnetSetup) {
+        let subnet_setup = SubnetSetup {
+            subnet_id: self.subnet_id,
+            node_id: self.node_id,
+            registry_data_provider: Arc::clone(&self.registry_data_provider),
+            registry_client: Arc::clone(&self.registry_client),
+            ecdsa_keys: self.ecdsa_secret_keys.keys().cloned().collect(),
+            bitcoin: self.bitcoin.take(),
+        };
         (
             self.state_dir,
             self.nonce.get(),
             self.time.get(),
             self.checkpoints_enabled.get(),
+            subnet_setup,
         )
     }
 
@@ -408,9 +668,16 @@ impl StateMachine {
     pub fn restart_node(self) -> Self {
         // We must drop self before setup_form_dir so that we don't have two StateManagers pointing
         // to the same root.
-        let (state_dir, nonce, time, checkpoints_enabled) = self.into_components();
+        let (state_dir, nonce, time, checkpoints_enabled, subnet_setup) = self.into_components();
 
-        Self::setup_from_dir(state_dir, nonce, time, None, checkpoints_enabled)
+        Self::setup_from_dir(
+            state_dir,
+            nonce,
+            time,
+            None,
+            checkpoints_enabled,
+            subnet_setup,
+        )
     }
 
     /// Same as [restart_node], but the subnet will have the specified `config`
@@ -418,9 +685,16 @@ impl StateMachine {
     pub fn restart_node_with_config(self, config: StateMachineConfig) -> Self {
         // We must drop self before setup_form_dir so that we don't have two StateManagers pointing
         // to the same root.
-        let (state_dir, nonce, time, checkpoints_enabled) = self.into_components();
+        let (state_dir, nonce, time, checkpoints_enabled, subnet_setup) = self.into_components();
 
-        Self::setup_from_dir(state_dir, nonce, time, Some(config), checkpoints_enabled)
+        Self::setup_from_dir(
+            state_dir,
+            nonce,
+            time,
+            Some(config),
+            checkpoints_enabled,
+            subnet_setup,
+        )
     }
 
     /// If the argument is true, the state machine will create an on-disk
@@ -433,6 +707,12 @@ impl StateMachine {
         self.checkpoints_enabled.set(enabled)
     }
 
+    /// Returns the layout of the state directory of the state machine, e.g. to
+    /// access the checkpoints it creates when checkpoints are enabled.
+    pub fn state_layout(&self) -> &StateLayout {
+        self.state_manager.state_layout()
+    }
+
     /// Creates a new batch containing a single ingress message and sends it for
     /// processing to the replicated state machine.
     fn send_signed_ingress(&self, msg: SignedIngress) {
@@ -441,6 +721,8 @@ impl StateMachine {
 
     /// Triggers a single round of execution without any new inputs.  The state
     /// machine will invoke hearbeats and make progress on pending async calls.
+    ///
+    /// Messages to other subnets are only delivered by [StateMachineEnv::tick].
     pub fn tick(&self) {
         self.execute_block_with_ingress_payload(IngressPayload::default())
     }
@@ -456,12 +738,7 @@ impl StateMachine {
     pub fn run_until_completion(&self, max_ticks: usize) {
         let mut reached_completion = false;
         for _tick in 0..max_ticks {
-            let state = self.state_manager.get_latest_state().take();
-            reached_completion = !state
-                .canisters_iter()
-                .any(|canister| canister.has_input() || canister.has_output())
-                && !state.subnet_queues().has_input()
-                && !state.subnet_queues().has_output();
+            reached_completion = !self.has_pending_messages();
             if reached_completion {
                 break;
             }
@@ -475,25 +752,53 @@ impl StateMachine {
         }
     }
 
+    /// Returns true if any canister or the subnet has messages to process.
+    fn has_pending_messages(&self) -> bool {
+        let state = self.state_manager.get_latest_state().take();
+        state
+            .canisters_iter()
+            .any(|canister| canister.has_input() || canister.has_output())
+            || state.subnet_queues().has_input()
+            || state.subnet_queues().has_output()
+            || !self.canister_http_responses.borrow().is_empty()
+    }
+
     fn execute_block_with_ingress_payload(&self, ingress: IngressPayload) {
+        self.execute_block(ingress, XNetPayload::default())
+    }
+
+    fn execute_block(&self, ingress: IngressPayload, xnet: XNetPayload) {
         let batch_number = self.message_routing.expected_batch_height();
 
         let mut seed = [0u8; 32];
         // use the batch number to seed randomness
         seed[..8].copy_from_slice(batch_number.get().to_le_bytes().as_slice());
 
+        let registry_version = self.registry_client.get_latest_version();
+        let canister_http = self.canister_http_payload(registry_version);
+        let mut consensus_responses = self.sign_with_ecdsa_responses();
+        consensus_responses.extend(
+            canister_http
+                .responses
+                .iter()
+                .map(|response| Response::from(&response.content)),
+        );
+
         let batch = Batch {
             batch_number,
             requires_full_state_hash: self.checkpoints_enabled.get(),
             payload: BatchPayload {
                 ingress,
+                xnet,
+                self_validating: self.bitcoin_adapter_payload(),
+                canister_http,
                 ..BatchPayload::default()
             },
             randomness: Randomness::from(seed),
-            ecdsa_subnet_public_keys: BTreeMap::new(),
-            registry_version: self.registry_client.get_latest_version(),
+            ecdsa_subnet_public_keys: self.ecdsa_subnet_public_keys(),
+            registry_version,
             time: self.time.get(),
-            consensus_responses: vec![],
+            consensus_responses,
         };
         self.message_routing
             .deliver_batch(batch)
@@ -501,6 +806,105 @@ impl StateMachine {
         self.await_height(batch_number);
     }
 
+    /// Takes the mocked canister http responses and packs them into a payload
+    /// as consensus would do after the nodes agreed on them.
+    ///
+    /// The state machine does not verify the proofs of the responses, so they
+    /// carry no signatures.
+    fn canister_http_payload(&self, registry_version: RegistryVersion) -> CanisterHttpPayload {
+        let responses = self
+            .canister_http_responses
+            .take()
+            .into_iter()
+            .map(|response| CanisterHttpResponseWithConsensus {
+                proof: Signed {
+                    content: CanisterHttpResponseMetadata {
+                        id: response.id,
+                        timeout: response.timeout,
+                        content_hash: CryptoHashOf::from(CryptoHash(vec![])),
+                        registry_version,
+                    },
+                    signature: BasicSignatureBatch {
+                        signatures_map: BTreeMap::new(),
+                    },
+                },
+                content: response,
+            })
+            .collect();
+        CanisterHttpPayload {
+            responses,
+            timeouts: vec![],
+        }
+    }
+
+    /// Answers the pending requests to the bitcoin adapter, both those of the
+    /// bitcoin canister of the replica and those of the bitcoin wasm canister,
+    /// with the fake bitcoin network.
+    fn bitcoin_adapter_payload(&self) -> SelfValidatingPayload {
+        let mut bitcoin = self.bitcoin.borrow_mut();
+        let bitcoin = match bitcoin.as_mut() {
+            Some(bitcoin) => bitcoin,
+            None => return SelfValidatingPayload::default(),
+        };
+        let state = self.state_manager.get_latest_state().take();
+        let mut responses: Vec<BitcoinAdapterResponse> = state
+            .bitcoin()
+            .adapter_requests_iter()
+            .map(|(callback_id, request)| bitcoin.respond(*callback_id, &request.request))
+            .collect();
+        responses.extend(
+            state
+                .metadata
+                .subnet_call_context_manager
+                .bitcoin_get_successors_contexts
+                .iter()
+                .map(|(callback_id, context)| {
+                    bitcoin.respond(
+                        callback_id.get(),
+                        &BitcoinAdapterRequestWrapper::CanisterGetSuccessorsRequest(
+                            context.payload.clone(),
+                        ),
+                    )
+                }),
+        );
+        SelfValidatingPayload::new(responses)
+    }
+
+    /// Returns the master public keys of the ECDSA keys held by the subnet.
+    fn ecdsa_subnet_public_keys(&self) -> BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey> {
+        self.ecdsa_secret_keys
+            .iter()
+            .map(|(key_id, secret_key)| {
+                let public_key = MasterEcdsaPublicKey {
+                    algorithm_id: AlgorithmId::EcdsaSecp256k1,
+                    public_key: EccPoint::mul_by_g(secret_key)
+                        .expect("failed to compute ECDSA public key")
+                        .serialize(),
+                };
+                (key_id.clone(), public_key)
+            })
+            .collect()
+    }
+
+    /// Answers all pending `sign_with_ecdsa` requests, like consensus would do
+    /// once the nodes agreed on the signatures.
+    fn sign_with_ecdsa_responses(&self) -> Vec<Response> {
+        let state = self.state_manager.get_latest_state().take();
+        state
+            .metadata
+            .subnet_call_context_manager
+            .sign_with_ecdsa_contexts
+            .iter()
+            .map(|(callback_id, context)| {
+                let secret_key = self
+                    .ecdsa_secret_keys
+                    .get(&context.key_id)
+                    .unwrap_or_else(|| panic!("Subnet does not hold ECDSA key {}", context.key_id));
+                sign_with_ecdsa_response(*callback_id, context, secret_key)
+            })
+            .collect()
+    }
+
     fn await_height(&self, h: Height) {
         const SLEEP_TIME: Duration = Duration::from_millis(100);
         const MAX_WAIT_TIME: Duration = Duration::from_secs(180);
@@ -912,12 +1316,7 @@ impl StateMachine {
         method: impl ToString,
         method_payload: Vec<u8>,
     ) -> Result<WasmResult, UserError> {
-        if self.state_manager.latest_state_height() > self.state_manager.latest_certified_height() {
-            let state_hashes = self.state_manager.list_state_hashes_to_certify();
-            let (height, hash) = state_hashes.last().unwrap();
-            self.state_manager
-                .deliver_state_certification(self.certify_hash(height, hash));
-        }
+        self.certify_latest_state();
 
         let path = SubTree(flatmap! {
             Label::from("canister") => SubTree(
@@ -948,6 +1347,51 @@ impl StateMachine {
         )
     }
 
+    /// Certifies the latest state if it is not certified yet.
+    fn certify_latest_state(&self) {
+        if self.state_manager.latest_state_height() > self.state_manager.latest_certified_height() {
+            let state_hashes = self.state_manager.list_state_hashes_to_certify();
+            let (height, hash) = state_hashes.last().unwrap();
+            self.state_manager
+                .deliver_state_certification(self.certify_hash(height, hash));
+        }
+    }
+
+    /// Returns the index of the next message this subnet expects from
+    /// `remote_subnet`, if it has received any signals or messages from it.
+    fn expected_stream_index(&self, remote_subnet: SubnetId) -> Option<StreamIndex> {
+        self.state_manager
+            .get_latest_state()
+            .take()
+            .get_stream(&remote_subnet)
+            .map(|stream| stream.signals_end())
+    }
+
+    /// Returns the certified slice of the stream from this subnet to
+    /// `remote_subnet` that starts at the message with index `begin` (or at
+    /// the beginning of the stream), or `None` if there is no such stream.
+    fn certified_stream_slice(
+        &self,
+        remote_subnet: SubnetId,
+        begin: Option<StreamIndex>,
+    ) -> Option<CertifiedStreamSlice> {
+        self.certify_latest_state();
+        match self.state_manager.encode_certified_stream_slice(
+            remote_subnet,
+            begin,
+            begin,
+            None,
+            None,
+        ) {
+            Ok(slice) => Some(slice),
+            Err(EncodeStreamError::NoStreamForSubnet(_)) => None,
+            Err(err) => panic!(
+                "Failed to encode the stream from subnet {} to subnet {}: {}",
+                self.subnet_id, remote_subnet, err
+            ),
+        }
+    }
+
     fn certify_hash(&self, height: &Height, hash: &CryptoHashOfPartialState) -> Certification {
         let signature_bytes = Some(
             sign_message(
@@ -1304,4 +1748,403 @@ impl StateMachine {
             .commit_and_certify(state, height.increment(), CertificationScope::Full);
         balance
     }
+
+    /// Returns the canister http requests that are waiting for a response,
+    /// indexed by their IDs.
+    pub fn canister_http_request_contexts(
+        &self,
+    ) -> BTreeMap<CanisterHttpRequestId, CanisterHttpRequestContext> {
+        self.state_manager
+            .get_latest_state()
+            .take()
+            .metadata
+            .subnet_call_context_manager
+            .canister_http_request_contexts
+            .clone()
+    }
+
+    /// Answers the pending canister http request with the specified ID with a
+    /// mocked response of the remote server.
+    ///
+    /// Like a replica does with the response it receives from the adapter, the
+    /// state machine applies the transform function of the request to the
+    /// mocked response, if there is one. The resulting response is delivered
+    /// to the canister in the next round.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is no pending request with the specified
+    /// ID or if the request has already been answered.
+    pub fn mock_canister_http_response(
+        &self,
+        request_id: CanisterHttpRequestId,
+        response: MockCanisterHttpResponse,
+    ) {
+        let context = self
+            .canister_http_request_contexts()
+            .remove(&request_id)
+            .unwrap_or_else(|| panic!("No pending canister http request {}", request_id));
+        assert!(
+            self.canister_http_responses
+                .borrow()
+                .iter()
+                .all(|response| response.id != request_id),
+            "Canister http request {} has already been answered",
+            request_id
+        );
+
+        let content = match response {
+            MockCanisterHttpResponse::Reply(payload) => {
+                self.transform_canister_http_response(&context, payload)
+            }
+            MockCanisterHttpResponse::Reject { code, message } => {
+                CanisterHttpResponseContent::Reject(CanisterHttpReject {
+                    reject_code: code,
+                    message,
+                })
+            }
+        };
+        self.canister_http_responses
+            .borrow_mut()
+            .push(CanisterHttpResponse {
+                id: request_id,
+                timeout: context.time + CANISTER_HTTP_TIMEOUT_INTERVAL,
+                canister_id: context.request.sender,
+                content,
+            });
+    }
+
+    /// Mines a block on the fake bitcoin network whose coinbase transaction
+    /// pays the specified amounts (in satoshi) to the specified addresses, and
+    /// returns the block.
+    ///
+    /// The bitcoin canister asks for new blocks in every round and the fake
+    /// network answers in the next round, so the block is part of the
+    /// bitcoin state after at most two ticks.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the bitcoin feature is not enabled, see
+    /// `StateMachineConfig::with_bitcoin`, or if an address is invalid.
+    pub fn mine_bitcoin_block(&self, outputs: &[(&str, u64)]) -> bitcoin::Block {
+        self.bitcoin
+            .borrow_mut()
+            .as_mut()
+            .expect("The bitcoin feature is not enabled")
+            .mine_block(outputs)
+    }
+
+    /// Returns the transactions that canisters sent to the fake bitcoin
+    /// network with `bitcoin_send_transaction`, in the order they were sent.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the bitcoin feature is not enabled, see
+    /// `StateMachineConfig::with_bitcoin`.
+    pub fn bitcoin_transactions(&self) -> Vec<bitcoin::Transaction> {
+        self.bitcoin
+            .borrow()
+            .as_ref()
+            .expect("The bitcoin feature is not enabled")
+            .transactions()
+            .to_vec()
+    }
+
+    /// Encodes the response of the remote server and applies the transform
+    /// function of the request to it, if there is one.
+    ///
+    /// Like the canister http adapter and client, this rejects responses whose
+    /// body exceeds the `max_response_bytes` of the request and (transformed)
+    /// responses that exceed [CANISTER_HTTP_RESPONSE_LIMIT].
+    fn transform_canister_http_response(
+        &self,
+        context: &CanisterHttpRequestContext,
+        payload: CanisterHttpResponsePayload,
+    ) -> CanisterHttpResponseContent {
+        let reject = |reject_code, message| {
+            CanisterHttpResponseContent::Reject(CanisterHttpReject {
+                reject_code,
+                message,
+            })
+        };
+
+        let max_response_bytes = context
+            .max_response_bytes
+            .unwrap_or(CANISTER_HTTP_ADAPTER_MAX_RESPONSE_SIZE)
+            .get();
+        if payload.body.len() as u64 > max_response_bytes {
+            return reject(
+                RejectCode::SysFatal,
+                format!(
+                    "Http body exceeds size limit of {} bytes.",
+                    max_response_bytes
+                ),
+            );
+        }
+
+        let payload = Encode!(&payload).expect("failed to encode http response");
+        let response = match &context.transform_method_name {
+            Some(transform_method) => {
+                match self.query(context.request.sender, transform_method, payload) {
+                    Ok(WasmResult::Reply(reply)) => reply,
+                    Ok(WasmResult::Reject(message)) => {
+                        return reject(RejectCode::CanisterReject, message)
+                    }
+                    Err(err) => return reject(err.reject_code(), err.description().to_string()),
+                }
+            }
+            None => payload,
+        };
+
+        if response.len() > CANISTER_HTTP_RESPONSE_LIMIT {
+            let message = match context.transform_method_name {
+                Some(_) => format!(
+                    "Transformed http response exceeds limit: {}",
+                    CANISTER_HTTP_RESPONSE_LIMIT
+                ),
+                None => format!(
+                    "Http response exceeds limit: {}. Apply a transform function to the http response.",
+                    CANISTER_HTTP_RESPONSE_LIMIT
+                ),
+            };
+            return reject(RejectCode::SysFatal, message);
+        }
+        CanisterHttpResponseContent::Success(response)
+    }
+}
+
+/// A set of [StateMachine]s, each simulating a single-node subnet, that share
+/// a registry and routing table. On [StateMachineEnv::tick], the subnets
+/// exchange their streams, so that canisters on different subnets can call
+/// each other.
+pub struct StateMachineEnv {
+    subnets: Vec<StateMachine>,
+    registry_client: Arc<FakeRegistryClient>,
+}
+
+impl fmt::Debug for StateMachineEnv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateMachineEnv")
+            .field("subnets", &self.subnets)
+            .finish()
+    }
+}
+
+impl StateMachineEnv {
+    /// Constructs a new environment with the specified number of system
+    /// subnets. The first subnet is the root subnet.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `num_subnets` is zero.
+    pub fn new(num_subnets: usize) -> Self {
+        assert!(num_subnets > 0, "at least one subnet is required");
+        let subnets: Vec<_> = (1..=num_subnets as u64)
+            .map(|i| {
+                (
+                    SubnetId::from(PrincipalId::new_subnet_test_id(i)),
+                    SubnetType::System,
+                    NodeId::from(PrincipalId::new_node_test_id(i)),
+                )
+            })
+            .collect();
+        let (registry_data_provider, registry_client) =
+            make_single_node_registry(&subnets, &[], None);
+
+        let subnets = subnets
+            .into_iter()
+            .map(|(subnet_id, _, node_id)| {
+                StateMachine::setup_from_dir(
+                    TempDir::new().expect("failed to create a temporary directory"),
+                    0,
+                    GENESIS,
+                    None,
+                    false,
+                    SubnetSetup {
+                        subnet_id,
+                        node_id,
+                        registry_data_provider: Arc::clone(&registry_data_provider),
+                        registry_client: Arc::clone(&registry_client),
+                        ecdsa_keys: vec![],
+                        bitcoin: None,
+                    },
+                )
+            })
+            .collect();
+
+        Self {
+            subnets,
+            registry_client,
+        }
+    }
+
+    /// Returns the state machines of all subnets, starting with the root
+    /// subnet.
+    pub fn subnets(&self) -> &[StateMachine] {
+        &self.subnets
+    }
+
+    /// Returns the state machine of the subnet with the specified ID.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is no such subnet.
+    pub fn subnet(&self, subnet_id: SubnetId) -> &StateMachine {
+        self.subnets
+            .iter()
+            .find(|subnet| subnet.subnet_id == subnet_id)
+            .unwrap_or_else(|| panic!("Subnet {} does not exist", subnet_id))
+    }
+
+    /// Returns the state machine of the subnet that hosts the canister with
+    /// the specified ID according to the routing table.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the canister ID is not assigned to any subnet.
+    pub fn subnet_of(&self, canister_id: CanisterId) -> &StateMachine {
+        use ic_registry_client_helpers::routing_table::RoutingTableRegistry;
+
+        let subnet_id = self
+            .registry_client
+            .get_routing_table(self.registry_client.get_latest_version())
+            .expect("malformed routing table")
+            .expect("missing routing table")
+            .route(canister_id.get())
+            .unwrap_or_else(|| panic!("Canister {} is not assigned to any subnet", canister_id));
+        self.subnet(subnet_id)
+    }
+
+    /// Triggers a single round of execution on every subnet. Before the
+    /// round, each subnet inducts the messages and signals that the other
+    /// subnets put into their streams to it since the last round.
+    pub fn tick(&self) {
+        // Build all payloads before executing any round, so that the order of
+        // the subnets does not matter.
+        let xnet_payloads: Vec<_> = self
+            .subnets
+            .iter()
+            .map(|receiver| XNetPayload {
+                stream_slices: self
+                    .subnets
+                    .iter()
+                    .filter(|sender| sender.subnet_id != receiver.subnet_id)
+                    .filter_map(|sender| {
+                        let begin = receiver.expected_stream_index(sender.subnet_id);
+                        sender
+                            .certified_stream_slice(receiver.subnet_id, begin)
+                            .map(|slice| (sender.subnet_id, slice))
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        for (subnet, xnet) in self.subnets.iter().zip(xnet_payloads) {
+            subnet.execute_block(IngressPayload::default(), xnet);
+        }
+    }
+
+    /// Makes the environment tick until there are no more messages in any
+    /// subnet, including messages in streams that were not yet acknowledged
+    /// by the receiving subnet.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the environment did not process all messages
+    /// within the `max_ticks` iterations.
+    pub fn run_until_completion(&self, max_ticks: usize) {
+        for _tick in 0..max_ticks {
+            let reached_completion = self.subnets.iter().all(|subnet| {
+                !subnet.has_pending_messages()
+                    && subnet
+                        .state_manager
+                        .get_latest_state()
+                        .take()
+                        .metadata
+                        .streams()
+                        .iter()
+                        .all(|(_, stream)| stream.messages_begin() == stream.messages_end())
+            });
+            if reached_completion {
+                return;
+            }
+            self.tick();
+        }
+        panic!(
+            "The state machine environment did not reach completion after {} ticks",
+            max_ticks
+        );
+    }
+
+    /// Advances the time of all subnets by the given amount.
+    pub fn advance_time(&self, amount: Duration) {
+        for subnet in &self.subnets {
+            subnet.advance_time(amount);
+        }
+    }
+
+    /// Executes an ingress message on the canister with the specified ID,
+    /// on the subnet hosting it.
+    ///
+    /// This function is synchronous, it ticks all subnets until the result of
+    /// the ingress message is known and returns this result.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the status was not ready in a reasonable amount
+    /// of time (typically, a few seconds).
+    pub fn execute_ingress_as(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        method: impl ToString,
+        payload: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        const MAX_TICKS: usize = 100;
+        let subnet = self.subnet_of(canister_id);
+        let msg_id = subnet.send_ingress(sender, canister_id, method, payload);
+        self.await_ingress(subnet.subnet_id, msg_id, MAX_TICKS)
+    }
+
+    pub fn execute_ingress(
+        &self,
+        canister_id: CanisterId,
+        method: impl ToString,
+        payload: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        self.execute_ingress_as(PrincipalId::new_anonymous(), canister_id, method, payload)
+    }
+
+    /// Blocks until the result of the ingress message with the specified ID,
+    /// sent to the specified subnet, is available.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the result doesn't become available after the
+    /// specified number of ticks.
+    pub fn await_ingress(
+        &self,
+        subnet_id: SubnetId,
+        msg_id: MessageId,
+        max_ticks: usize,
+    ) -> Result<WasmResult, UserError> {
+        let subnet = self.subnet(subnet_id);
+        for _tick in 0..max_ticks {
+            match subnet.ingress_status(&msg_id) {
+                IngressStatus::Known {
+                    state: IngressState::Completed(result),
+                    ..
+                } => return Ok(result),
+                IngressStatus::Known {
+                    state: IngressState::Failed(error),
+                    ..
+                } => return Err(error),
+                _ => self.tick(),
+            }
+        }
+        panic!(
+            "Did not get answer to ingress {} on subnet {} after {} ticks",
+            msg_id, subnet_id, max_ticks
+        )
+    }
 }
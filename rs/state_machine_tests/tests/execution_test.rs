@@ -1,15 +1,33 @@
+use bitcoin::{consensus::serialize, Network, OutPoint};
+use candid::{Decode, Encode};
+use ic_btc_test_utils::{random_p2pkh_address, TransactionBuilder};
+use ic_btc_types::NetworkInRequest;
 use ic_config::{
     execution_environment::Config as HypervisorConfig,
     subnet_config::{CyclesAccountManagerConfig, SubnetConfigs},
 };
+use ic_crypto_extended_bip32::DerivationPath;
+use ic_crypto_internal_basic_sig_ecdsa_secp256k1::{
+    types::{PublicKeyBytes, SignatureBytes},
+    verify,
+};
+use ic_ic00_types::{
+    self as ic00, BitcoinGetBalanceArgs, BitcoinSendTransactionArgs, CanisterHttpRequestArgs,
+    ECDSAPublicKeyArgs, ECDSAPublicKeyResponse, HttpMethod, Method, Payload, SignWithECDSAArgs,
+    SignWithECDSAReply, TransformFunc, TransformType,
+};
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::{
-    CanisterSettingsArgs, ErrorCode, PrincipalId, StateMachine, StateMachineConfig, SubnetId,
-    UserError,
+    BitcoinNetwork, CanisterHttpResponsePayload, CanisterSettingsArgs, EcdsaCurve, EcdsaKeyId,
+    ErrorCode, HttpHeader, MockCanisterHttpResponse, PrincipalId, RejectCode, StateMachine,
+    StateMachineConfig, StateMachineEnv, SubnetId, UserError,
+};
+use ic_types::{
+    canister_http::CANISTER_HTTP_RESPONSE_LIMIT, ingress::WasmResult, CanisterId, Cycles, NumBytes,
 };
-use ic_types::{ingress::WasmResult, Cycles, NumBytes};
-use ic_universal_canister::{wasm, UNIVERSAL_CANISTER_WASM};
+use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
 use std::convert::TryInto;
+use std::time::{Duration, SystemTime};
 
 const INITIAL_CYCLES_BALANCE: Cycles = Cycles::new(100_000_000_000_000);
 
@@ -320,6 +338,9 @@ fn test_canister_out_of_cycles() {
             compute_allocation: Some(candid::Nat::from(1)),
             memory_allocation: None,
             freezing_threshold: None,
+            log_visibility: None,
+            wasm_memory_limit: None,
+            reserved_cycles_limit: None,
         }),
     );
 
@@ -620,6 +641,9 @@ fn exceeding_memory_capacity_fails_when_memory_allocation_changes() {
                 compute_allocation: None,
                 memory_allocation: None,
                 freezing_threshold: None,
+                log_visibility: None,
+                wasm_memory_limit: None,
+                reserved_cycles_limit: None,
             }),
             INITIAL_CYCLES_BALANCE,
         )
@@ -635,6 +659,9 @@ fn exceeding_memory_capacity_fails_when_memory_allocation_changes() {
                 compute_allocation: None,
                 memory_allocation: Some(candid::Nat::from(20u64 * 1024 * 1024 + 1)),
                 freezing_threshold: None,
+                log_visibility: None,
+                wasm_memory_limit: None,
+                reserved_cycles_limit: None,
             },
         )
         .unwrap_err();
@@ -649,6 +676,9 @@ fn exceeding_memory_capacity_fails_when_memory_allocation_changes() {
             compute_allocation: None,
             memory_allocation: Some(candid::Nat::from(20u64 * 1024 * 1024)),
             freezing_threshold: None,
+            log_visibility: None,
+            wasm_memory_limit: None,
+            reserved_cycles_limit: None,
         },
     )
     .unwrap();
@@ -697,6 +727,9 @@ fn exceeding_memory_capacity_fails_during_message_execution() {
                 compute_allocation: None,
                 memory_allocation: None,
                 freezing_threshold: None,
+                log_visibility: None,
+                wasm_memory_limit: None,
+                reserved_cycles_limit: None,
             }),
             INITIAL_CYCLES_BALANCE,
         )
@@ -762,6 +795,9 @@ fn max_canister_memory_respected_even_when_no_memory_allocation_is_set() {
                 compute_allocation: None,
                 memory_allocation: None,
                 freezing_threshold: None,
+                log_visibility: None,
+                wasm_memory_limit: None,
+                reserved_cycles_limit: None,
             }),
             INITIAL_CYCLES_BALANCE,
         )
@@ -785,3 +821,660 @@ fn max_canister_memory_respected_even_when_no_memory_allocation_is_set() {
     );
     assert_replied(res, 0);
 }
+
+/// This is a canister that counts how many times its global timer fired.
+/// Exposed methods:
+///  * "set_timer"  set the global timer to the given time (u64 nanoseconds
+///                 since the Unix epoch) and reply with the previous value
+///  * "read"       read the number of times the global timer fired
+const TIMER_CANISTER: &str = r#"
+            (module
+              (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param $dst i32) (param $offset i32) (param $size i32)))
+              (import "ic0" "msg_reply" (func $msg_reply))
+              (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+              (import "ic0" "global_timer_set"
+                (func $global_timer_set (param i64) (result i64)))
+
+              (func $set_timer
+                (call $msg_arg_data_copy (i32.const 8) (i32.const 0) (i32.const 8))
+                (i64.store (i32.const 16)
+                  (call $global_timer_set (i64.load (i32.const 8))))
+                (call $msg_reply_data_append (i32.const 16) (i32.const 8))
+                (call $msg_reply)
+              )
+
+              (func $read
+                (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+                (call $msg_reply)
+              )
+
+              (func $global_timer
+                (i32.store (i32.const 0)
+                  (i32.add (i32.load (i32.const 0)) (i32.const 1)))
+              )
+
+              (memory $memory 1)
+              (export "memory" (memory $memory))
+              (export "canister_query read" (func $read))
+              (export "canister_update set_timer" (func $set_timer))
+              (export "canister_global_timer" (func $global_timer)))"#;
+
+fn time_nanos(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+}
+
+fn set_timer(env: &StateMachine, canister_id: CanisterId, time: u64) -> u64 {
+    let result = env
+        .execute_ingress(canister_id, "set_timer", time.to_le_bytes().to_vec())
+        .unwrap()
+        .bytes();
+    u64::from_le_bytes(result.try_into().unwrap())
+}
+
+#[test]
+fn test_global_timer_fires_once_after_deadline() {
+    let env = StateMachine::new();
+    let canister_id = env.install_canister_wat(TIMER_CANISTER, vec![], None);
+
+    let deadline = time_nanos(env.time() + Duration::from_secs(10));
+    assert_eq!(set_timer(&env, canister_id, deadline), 0);
+    env.tick();
+    let val = env.query(canister_id, "read", vec![]).unwrap().bytes();
+    assert_eq!(to_int(val), 0);
+
+    env.advance_time(Duration::from_secs(10));
+    env.tick();
+    let val = env.query(canister_id, "read", vec![]).unwrap().bytes();
+    assert_eq!(to_int(val), 1);
+
+    // The timer is deactivated after it fires.
+    env.advance_time(Duration::from_secs(10));
+    env.tick();
+    let val = env.query(canister_id, "read", vec![]).unwrap().bytes();
+    assert_eq!(to_int(val), 1);
+    assert_eq!(set_timer(&env, canister_id, 0), 0);
+}
+
+#[test]
+fn test_global_timer_can_be_reset_and_deactivated() {
+    let env = StateMachine::new();
+    let canister_id = env.install_canister_wat(TIMER_CANISTER, vec![], None);
+
+    let deadline = time_nanos(env.time() + Duration::from_secs(10));
+    assert_eq!(set_timer(&env, canister_id, deadline), 0);
+    assert_eq!(set_timer(&env, canister_id, deadline + 1), deadline);
+    // Zero deactivates the timer.
+    assert_eq!(set_timer(&env, canister_id, 0), deadline + 1);
+
+    env.advance_time(Duration::from_secs(20));
+    env.tick();
+    let val = env.query(canister_id, "read", vec![]).unwrap().bytes();
+    assert_eq!(to_int(val), 0);
+}
+
+#[test]
+fn test_global_timer_survives_checkpoint_restart() {
+    let env = StateMachine::new();
+    env.set_checkpoints_enabled(true);
+    let canister_id = env.install_canister_wat(TIMER_CANISTER, vec![], None);
+
+    let deadline = time_nanos(env.time() + Duration::from_secs(10));
+    assert_eq!(set_timer(&env, canister_id, deadline), 0);
+
+    let env = env.restart_node();
+
+    env.advance_time(Duration::from_secs(10));
+    env.tick();
+    let val = env.query(canister_id, "read", vec![]).unwrap().bytes();
+    assert_eq!(to_int(val), 1);
+}
+
+#[test]
+fn test_global_timer_is_deactivated_on_upgrade() {
+    let env = StateMachine::new();
+    let canister_id = env.install_canister_wat(TIMER_CANISTER, vec![], None);
+
+    let deadline = time_nanos(env.time() + Duration::from_secs(10));
+    assert_eq!(set_timer(&env, canister_id, deadline), 0);
+
+    env.upgrade_canister_wat(canister_id, TIMER_CANISTER, vec![]);
+
+    env.advance_time(Duration::from_secs(10));
+    env.tick();
+    let val = env.query(canister_id, "read", vec![]).unwrap().bytes();
+    assert_eq!(to_int(val), 0);
+    assert_eq!(set_timer(&env, canister_id, 0), 0);
+}
+
+#[test]
+fn test_xnet_call_between_subnets() {
+    let env = StateMachineEnv::new(2);
+    let (subnet_a, subnet_b) = (&env.subnets()[0], &env.subnets()[1]);
+    assert_ne!(subnet_a.get_subnet_id(), subnet_b.get_subnet_id());
+
+    let caller = subnet_a
+        .install_canister(UNIVERSAL_CANISTER_WASM.into(), vec![], None)
+        .unwrap();
+    let callee = subnet_b
+        .install_canister(UNIVERSAL_CANISTER_WASM.into(), vec![], None)
+        .unwrap();
+    assert_eq!(
+        env.subnet_of(caller).get_subnet_id(),
+        subnet_a.get_subnet_id()
+    );
+    assert_eq!(
+        env.subnet_of(callee).get_subnet_id(),
+        subnet_b.get_subnet_id()
+    );
+
+    let result = env.execute_ingress(
+        caller,
+        "update",
+        wasm()
+            .inter_update(
+                callee,
+                call_args().other_side(wasm().reply_data(b"pong").build()),
+            )
+            .build(),
+    );
+    assert_eq!(result, Ok(WasmResult::Reply(b"pong".to_vec())));
+
+    // All messages and signals are eventually garbage collected.
+    env.run_until_completion(10);
+}
+
+#[test]
+fn test_xnet_calls_between_all_subnets() {
+    let env = StateMachineEnv::new(3);
+    let canisters: Vec<_> = env
+        .subnets()
+        .iter()
+        .map(|subnet| {
+            subnet
+                .install_canister(UNIVERSAL_CANISTER_WASM.into(), vec![], None)
+                .unwrap()
+        })
+        .collect();
+
+    for caller in &canisters {
+        for callee in &canisters {
+            let result = env.execute_ingress(
+                *caller,
+                "update",
+                wasm()
+                    .inter_update(
+                        *callee,
+                        call_args().other_side(wasm().reply_data(callee.get_ref().as_slice())),
+                    )
+                    .build(),
+            );
+            assert_eq!(
+                result,
+                Ok(WasmResult::Reply(callee.get_ref().as_slice().to_vec()))
+            );
+        }
+    }
+    env.run_until_completion(10);
+}
+
+/// A canister that makes canister http requests. Exposed methods:
+///  * "fetch"     sends the argument as `http_request` to the management
+///                canister and replies with the response or rejects with the
+///                reject message
+///  * "transform" replies with "transformed"
+const HTTP_CANISTER: &str = r#"
+            (module
+              (import "ic0" "call_new"
+                (func $call_new
+                  (param $callee_src i32) (param $callee_size i32)
+                  (param $name_src i32) (param $name_size i32)
+                  (param $reply_fun i32) (param $reply_env i32)
+                  (param $reject_fun i32) (param $reject_env i32)))
+              (import "ic0" "call_data_append" (func $call_data_append (param i32 i32)))
+              (import "ic0" "call_cycles_add" (func $call_cycles_add (param i64)))
+              (import "ic0" "call_perform" (func $call_perform (result i32)))
+              (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+              (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param $dst i32) (param $offset i32) (param $size i32)))
+              (import "ic0" "msg_reply" (func $msg_reply))
+              (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+              (import "ic0" "msg_reject" (func $msg_reject (param i32 i32)))
+              (import "ic0" "msg_reject_msg_size" (func $msg_reject_msg_size (result i32)))
+              (import "ic0" "msg_reject_msg_copy"
+                (func $msg_reject_msg_copy (param $dst i32) (param $offset i32) (param $size i32)))
+
+              (func $fetch
+                (call $msg_arg_data_copy (i32.const 100) (i32.const 0) (call $msg_arg_data_size))
+                ;; the management canister has an empty ID
+                (call $call_new
+                  (i32.const 0) (i32.const 0)
+                  (i32.const 0) (i32.const 12)
+                  (i32.const 0) (i32.const 0)
+                  (i32.const 1) (i32.const 0))
+                (call $call_data_append (i32.const 100) (call $msg_arg_data_size))
+                (call $call_cycles_add (i64.const 100000000000))
+                (drop (call $call_perform))
+              )
+
+              (func $on_reply (param $env i32)
+                (call $msg_arg_data_copy (i32.const 100) (i32.const 0) (call $msg_arg_data_size))
+                (call $msg_reply_data_append (i32.const 100) (call $msg_arg_data_size))
+                (call $msg_reply)
+              )
+
+              (func $on_reject (param $env i32)
+                (call $msg_reject_msg_copy (i32.const 100) (i32.const 0) (call $msg_reject_msg_size))
+                (call $msg_reject (i32.const 100) (call $msg_reject_msg_size))
+              )
+
+              (func $transform
+                (call $msg_reply_data_append (i32.const 12) (i32.const 11))
+                (call $msg_reply)
+              )
+
+              (table funcref (elem $on_reply $on_reject))
+              (memory $memory 1)
+              (data (i32.const 0) "http_request")
+              (data (i32.const 12) "transformed")
+              (export "memory" (memory $memory))
+              (export "canister_update fetch" (func $fetch))
+              (export "canister_query transform" (func $transform)))"#;
+
+fn http_request_args(
+    canister_id: CanisterId,
+    transform: Option<&str>,
+    max_response_bytes: Option<u64>,
+) -> Vec<u8> {
+    Encode!(&CanisterHttpRequestArgs {
+        url: "https://example.com/price".to_string(),
+        max_response_bytes,
+        headers: vec![],
+        body: None,
+        method: HttpMethod::GET,
+        transform: transform.map(|method| {
+            TransformType::Function(TransformFunc(candid::Func {
+                principal: canister_id.get().0,
+                method: method.to_string(),
+            }))
+        }),
+    })
+    .unwrap()
+}
+
+fn install_http_canister(env: &StateMachine) -> CanisterId {
+    env.install_canister_with_cycles(
+        wabt::wat2wasm(HTTP_CANISTER).expect("invalid WAT"),
+        vec![],
+        None,
+        INITIAL_CYCLES_BALANCE,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_canister_http_request_with_mocked_response() {
+    let env = StateMachine::new();
+    let canister_id = install_http_canister(&env);
+
+    let msg_id = env.send_ingress(
+        PrincipalId::new_anonymous(),
+        canister_id,
+        "fetch",
+        http_request_args(canister_id, None, Some(1000)),
+    );
+    // The request reaches the management canister in the next round.
+    env.tick();
+
+    let contexts = env.canister_http_request_contexts();
+    assert_eq!(contexts.len(), 1);
+    let (request_id, context) = contexts.into_iter().next().unwrap();
+    assert_eq!(context.url, "https://example.com/price");
+    assert_eq!(context.request.sender, canister_id);
+
+    let response = CanisterHttpResponsePayload {
+        status: 200,
+        headers: vec![HttpHeader {
+            name: "Content-Type".to_string(),
+            value: "application/json".to_string(),
+        }],
+        body: b"{\"price\": 42}".to_vec(),
+    };
+    env.mock_canister_http_response(
+        request_id,
+        MockCanisterHttpResponse::Reply(response.clone()),
+    );
+
+    let reply = env.await_ingress(msg_id, 10).unwrap().bytes();
+    assert_eq!(
+        Decode!(&reply, CanisterHttpResponsePayload).unwrap(),
+        response
+    );
+    assert!(env.canister_http_request_contexts().is_empty());
+}
+
+#[test]
+fn test_canister_http_request_with_mocked_reject() {
+    let env = StateMachine::new();
+    let canister_id = install_http_canister(&env);
+
+    let msg_id = env.send_ingress(
+        PrincipalId::new_anonymous(),
+        canister_id,
+        "fetch",
+        http_request_args(canister_id, None, Some(1000)),
+    );
+    env.tick();
+    let request_id = *env.canister_http_request_contexts().keys().next().unwrap();
+    env.mock_canister_http_response(
+        request_id,
+        MockCanisterHttpResponse::Reject {
+            code: RejectCode::SysTransient,
+            message: "connection refused".to_string(),
+        },
+    );
+
+    assert_eq!(
+        env.await_ingress(msg_id, 10),
+        Ok(WasmResult::Reject("connection refused".to_string()))
+    );
+}
+
+#[test]
+fn test_canister_http_request_response_is_transformed() {
+    let env = StateMachine::new();
+    let canister_id = install_http_canister(&env);
+
+    let msg_id = env.send_ingress(
+        PrincipalId::new_anonymous(),
+        canister_id,
+        "fetch",
+        http_request_args(canister_id, Some("transform"), Some(1000)),
+    );
+    env.tick();
+    let request_id = *env.canister_http_request_contexts().keys().next().unwrap();
+    env.mock_canister_http_response(
+        request_id,
+        MockCanisterHttpResponse::Reply(CanisterHttpResponsePayload {
+            status: 200,
+            headers: vec![],
+            body: b"volatile".to_vec(),
+        }),
+    );
+
+    assert_eq!(
+        env.await_ingress(msg_id, 10),
+        Ok(WasmResult::Reply(b"transformed".to_vec()))
+    );
+}
+
+fn test_ecdsa_key_id() -> EcdsaKeyId {
+    EcdsaKeyId {
+        curve: EcdsaCurve::Secp256k1,
+        name: "test_key".to_string(),
+    }
+}
+
+#[test]
+fn test_sign_with_ecdsa_test_key() {
+    let key_id = test_ecdsa_key_id();
+    let env =
+        StateMachine::new_with_config(StateMachineConfig::default().with_ecdsa_key(key_id.clone()));
+    let canister_id = env
+        .install_canister(UNIVERSAL_CANISTER_WASM.into(), vec![], None)
+        .unwrap();
+
+    // Derive the key of an account from the public key of the canister, like
+    // the ckBTC minter does.
+    let reply = env
+        .execute_ingress(
+            canister_id,
+            "update",
+            wasm()
+                .call_simple(
+                    ic00::IC_00,
+                    Method::ECDSAPublicKey,
+                    call_args().other_side(
+                        ECDSAPublicKeyArgs {
+                            canister_id: None,
+                            derivation_path: vec![],
+                            key_id: key_id.clone(),
+                        }
+                        .encode(),
+                    ),
+                )
+                .build(),
+        )
+        .unwrap()
+        .bytes();
+    let canister_key = ECDSAPublicKeyResponse::decode(&reply).unwrap();
+    let account_key = DerivationPath::new_bip32(&[1])
+        .key_derivation(&canister_key.public_key, &canister_key.chain_code)
+        .unwrap()
+        .derived_public_key;
+
+    let message_hash = [7; 32];
+    let reply = env
+        .execute_ingress(
+            canister_id,
+            "update",
+            wasm()
+                .call_simple(
+                    ic00::IC_00,
+                    Method::SignWithECDSA,
+                    call_args().other_side(
+                        SignWithECDSAArgs {
+                            message_hash,
+                            derivation_path: vec![1_u32.to_be_bytes().to_vec()],
+                            key_id,
+                        }
+                        .encode(),
+                    ),
+                )
+                .build(),
+        )
+        .unwrap()
+        .bytes();
+    let signature = SignWithECDSAReply::decode(&reply).unwrap().signature;
+
+    verify(
+        &SignatureBytes(signature.try_into().unwrap()),
+        &message_hash,
+        &PublicKeyBytes(account_key),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_sign_with_ecdsa_fails_without_key() {
+    let env = StateMachine::new();
+    let canister_id = env
+        .install_canister(UNIVERSAL_CANISTER_WASM.into(), vec![], None)
+        .unwrap();
+
+    let result = env.execute_ingress(
+        canister_id,
+        "update",
+        wasm()
+            .call_simple(
+                ic00::IC_00,
+                Method::SignWithECDSA,
+                call_args()
+                    .other_side(
+                        SignWithECDSAArgs {
+                            message_hash: [7; 32],
+                            derivation_path: vec![],
+                            key_id: test_ecdsa_key_id(),
+                        }
+                        .encode(),
+                    )
+                    .on_reject(wasm().reject_message().reject()),
+            )
+            .build(),
+    );
+    match result {
+        Ok(WasmResult::Reject(message)) => assert!(
+            message.contains("test_key"),
+            "Unexpected reject message: {}",
+            message
+        ),
+        _ => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+fn install_bitcoin_test_canister(env: &StateMachine) -> CanisterId {
+    env.install_canister_with_cycles(
+        UNIVERSAL_CANISTER_WASM.into(),
+        vec![],
+        None,
+        INITIAL_CYCLES_BALANCE,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_bitcoin_get_balance_of_mined_blocks() {
+    let env = StateMachine::new_with_config(
+        StateMachineConfig::default().with_bitcoin(BitcoinNetwork::Regtest),
+    );
+    let canister_id = install_bitcoin_test_canister(&env);
+    let address = random_p2pkh_address(Network::Regtest).to_string();
+
+    env.mine_bitcoin_block(&[(&address, 50_000)]);
+    env.mine_bitcoin_block(&[(&address, 70_000)]);
+    // The bitcoin canister requests the blocks in one round and receives them
+    // in the next.
+    env.tick();
+    env.tick();
+
+    let reply = env
+        .execute_ingress(
+            canister_id,
+            "update",
+            wasm()
+                .call_with_cycles(
+                    ic00::IC_00,
+                    Method::BitcoinGetBalance,
+                    call_args().other_side(
+                        BitcoinGetBalanceArgs {
+                            address,
+                            network: NetworkInRequest::Regtest,
+                            min_confirmations: None,
+                        }
+                        .encode(),
+                    ),
+                    (0, 100_000_000),
+                )
+                .build(),
+        )
+        .unwrap()
+        .bytes();
+    assert_eq!(Decode!(&reply, u64).unwrap(), 120_000);
+}
+
+#[test]
+fn test_bitcoin_send_transaction_is_observable() {
+    let env = StateMachine::new_with_config(
+        StateMachineConfig::default().with_bitcoin(BitcoinNetwork::Regtest),
+    );
+    let canister_id = install_bitcoin_test_canister(&env);
+    let address = random_p2pkh_address(Network::Regtest);
+
+    let block = env.mine_bitcoin_block(&[(&address.to_string(), 50_000)]);
+    let transaction = TransactionBuilder::new()
+        .with_input(OutPoint::new(block.txdata[0].txid(), 0))
+        .with_output(&random_p2pkh_address(Network::Regtest), 40_000)
+        .build();
+
+    env.execute_ingress(
+        canister_id,
+        "update",
+        wasm()
+            .call_with_cycles(
+                ic00::IC_00,
+                Method::BitcoinSendTransaction,
+                call_args().other_side(
+                    BitcoinSendTransactionArgs {
+                        transaction: serialize(&transaction),
+                        network: NetworkInRequest::Regtest,
+                    }
+                    .encode(),
+                ),
+                (0, 10_000_000_000),
+            )
+            .build(),
+    )
+    .unwrap();
+
+    // The request to the bitcoin adapter is answered at most one round after
+    // the transaction was accepted.
+    env.tick();
+    assert_eq!(env.bitcoin_transactions(), vec![transaction]);
+}
+
+#[test]
+fn test_canister_http_response_exceeding_max_response_bytes_is_rejected() {
+    let env = StateMachine::new();
+    let canister_id = install_http_canister(&env);
+
+    let msg_id = env.send_ingress(
+        PrincipalId::new_anonymous(),
+        canister_id,
+        "fetch",
+        http_request_args(canister_id, Some("transform"), Some(1000)),
+    );
+    env.tick();
+    let request_id = *env.canister_http_request_contexts().keys().next().unwrap();
+    env.mock_canister_http_response(
+        request_id,
+        MockCanisterHttpResponse::Reply(CanisterHttpResponsePayload {
+            status: 200,
+            headers: vec![],
+            body: vec![0; 1001],
+        }),
+    );
+
+    assert_eq!(
+        env.await_ingress(msg_id, 10),
+        Ok(WasmResult::Reject(
+            "Http body exceeds size limit of 1000 bytes.".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_canister_http_response_exceeding_response_limit_is_rejected() {
+    let env = StateMachine::new();
+    let canister_id = install_http_canister(&env);
+
+    let msg_id = env.send_ingress(
+        PrincipalId::new_anonymous(),
+        canister_id,
+        "fetch",
+        http_request_args(canister_id, None, None),
+    );
+    env.tick();
+    let request_id = *env.canister_http_request_contexts().keys().next().unwrap();
+    // The body fits into the default limit of the adapter, but not the
+    // encoded response into the payload.
+    env.mock_canister_http_response(
+        request_id,
+        MockCanisterHttpResponse::Reply(CanisterHttpResponsePayload {
+            status: 200,
+            headers: vec![],
+            body: vec![0; CANISTER_HTTP_RESPONSE_LIMIT],
+        }),
+    );
+
+    assert_eq!(
+        env.await_ingress(msg_id, 10),
+        Ok(WasmResult::Reject(format!(
+            "Http response exceeds limit: {}. Apply a transform function to the http response.",
+            CANISTER_HTTP_RESPONSE_LIMIT
+        )))
+    );
+}
@@ -10,12 +10,12 @@ use ic_replicated_state::{
     bitcoin_state::{BitcoinState, UtxoSet},
     canister_state::execution_state::WasmBinary,
     page_map::PageMap,
-    CanisterMetrics, CanisterState, ExecutionState, NumWasmPages, ReplicatedState, SchedulerState,
-    SystemState,
+    CanisterMetrics, CanisterSnapshot, CanisterSnapshots, CanisterState, CanisterTimer,
+    ExecutionState, NumWasmPages, ReplicatedState, SchedulerState, SnapshotId, SystemState,
 };
 use ic_state_layout::{
-    BitcoinStateBits, BitcoinStateLayout, CanisterLayout, CanisterStateBits, CheckpointLayout,
-    ExecutionStateBits, ReadPolicy, RwPolicy, StateLayout,
+    BitcoinStateBits, BitcoinStateLayout, CanisterLayout, CanisterSnapshotBits, CanisterStateBits,
+    CheckpointLayout, ExecutionStateBits, ReadPolicy, RwPolicy, StateLayout,
 };
 use ic_types::time::UNIX_EPOCH;
 use ic_types::{Height, LongExecutionMode, Time};
@@ -26,6 +26,7 @@ use rand::{seq::IteratorRandom, Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 use std::collections::BTreeMap;
 use std::os::unix::prelude::MetadataExt;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{
     convert::{From, TryFrom},
@@ -85,6 +86,17 @@ pub fn make_checkpoint(
         layout.filter_tip_canisters(height, &state.canister_states.keys().collect())?;
     }
 
+    {
+        let _timer = metrics
+            .make_checkpoint_step_duration
+            .with_label_values(&["filter_snapshots"])
+            .start_timer();
+        layout.filter_tip_snapshots(
+            height,
+            &state.canister_snapshots.iter().map(|(id, _)| id).collect(),
+        )?;
+    }
+
     let cp = {
         let _timer = metrics
             .make_checkpoint_step_duration
@@ -129,6 +141,16 @@ fn serialize_to_tip(
         result?;
     }
 
+    let results = parallel_map(
+        thread_pool,
+        state.canister_snapshots.iter(),
+        |(snapshot_id, snapshot)| serialize_snapshot_to_tip(log, snapshot_id, snapshot, tip),
+    );
+
+    for result in results.into_iter() {
+        result?;
+    }
+
     serialize_bitcoin_state_to_tip(state.bitcoin(), &tip.bitcoin()?)?;
 
     Ok(())
@@ -245,12 +267,81 @@ fn serialize_canister_to_tip(
                     .clone()
                     .into_iter()
                     .collect(),
+                global_timer_nanos: canister_state
+                    .system_state
+                    .global_timer
+                    .to_nanos_since_unix_epoch(),
+                canister_log: canister_state.system_state.canister_log.clone(),
+                log_visibility: canister_state.system_state.log_visibility,
+                canister_version: canister_state.system_state.canister_version,
+                canister_history: canister_state.system_state.canister_history.clone(),
+                wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
+                reserved_balance: canister_state.system_state.reserved_balance(),
+                reserved_balance_limit: canister_state.system_state.reserved_balance_limit,
+                total_query_stats: canister_state.system_state.total_query_stats.clone(),
             }
             .into(),
         )
         .map_err(CheckpointError::from)
 }
 
+fn serialize_snapshot_to_tip(
+    log: &ReplicaLogger,
+    snapshot_id: &SnapshotId,
+    snapshot: &CanisterSnapshot,
+    tip: &CheckpointLayout<RwPolicy>,
+) -> Result<(), CheckpointError> {
+    let snapshot_layout = tip.snapshot(snapshot_id)?;
+    // Snapshots are immutable and their ids are never reused, so a snapshot
+    // that was fully written to the tip before does not need to be written
+    // again. The bits are written last to mark the snapshot as complete.
+    if snapshot_layout.snapshot().raw_path().exists() {
+        return Ok(());
+    }
+
+    match snapshot.wasm_binary.file() {
+        Some(path) => {
+            let wasm = snapshot_layout.wasm();
+            ic_state_layout::utils::do_copy(log, path, wasm.raw_path()).map_err(|io_err| {
+                CheckpointError::IoError {
+                    path: path.to_path_buf(),
+                    message: "failed to copy Wasm file".to_string(),
+                    io_err: io_err.to_string(),
+                }
+            })?;
+        }
+        None => {
+            snapshot_layout.wasm().serialize(&snapshot.wasm_binary)?;
+        }
+    }
+    // The page maps of a new snapshot are backed by the checkpoint files of
+    // the canister, so all pages need to be written, not only the delta.
+    snapshot
+        .wasm_memory
+        .page_map
+        .persist_and_sync_all_pages(&snapshot_layout.vmemory_0())?;
+    snapshot
+        .stable_memory
+        .page_map
+        .persist_and_sync_all_pages(&snapshot_layout.stable_memory_blob())?;
+
+    snapshot_layout
+        .snapshot()
+        .serialize(
+            (&CanisterSnapshotBits {
+                canister_id: snapshot.canister_id,
+                taken_at_timestamp: snapshot.taken_at_timestamp,
+                wasm_memory_size: snapshot.wasm_memory.size,
+                stable_memory_size: snapshot.stable_memory.size,
+                exported_globals: snapshot.exported_globals.clone(),
+                certified_data: snapshot.certified_data.clone(),
+                binary_hash: snapshot.wasm_binary.module_hash().into(),
+            })
+                .into(),
+        )
+        .map_err(CheckpointError::from)
+}
+
 fn serialize_bitcoin_state_to_tip(
     state: &BitcoinState,
     layout: &BitcoinStateLayout<RwPolicy>,
@@ -455,6 +546,20 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
         load_bitcoin_state(checkpoint_layout)?
     };
 
+    let canister_snapshots = {
+        let _timer = metrics
+            .load_checkpoint_step_duration
+            .with_label_values(&["canister_snapshots"])
+            .start_timer();
+
+        let mut snapshots = BTreeMap::new();
+        for snapshot_id in checkpoint_layout.snapshot_ids()? {
+            let snapshot = load_snapshot(checkpoint_layout, &snapshot_id)?;
+            snapshots.insert(snapshot_id, Arc::new(snapshot));
+        }
+        CanisterSnapshots::new(snapshots)
+    };
+
     let state = ReplicatedState::new_from_checkpoint(
         canister_states,
         metadata,
@@ -462,6 +567,7 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
         // Consensus queue needs to be empty at the end of every round.
         Vec::new(),
         bitcoin,
+        canister_snapshots,
         checkpoint_layout.raw_path().into(),
     );
 
@@ -581,6 +687,15 @@ pub fn load_canister_state<P: ReadPolicy>(
         canister_state_bits.cycles_balance,
         canister_state_bits.cycles_debit,
         canister_state_bits.task_queue.into_iter().collect(),
+        CanisterTimer::from_nanos_since_unix_epoch(canister_state_bits.global_timer_nanos),
+        canister_state_bits.canister_log,
+        canister_state_bits.log_visibility,
+        canister_state_bits.canister_version,
+        canister_state_bits.canister_history,
+        canister_state_bits.wasm_memory_limit,
+        canister_state_bits.reserved_balance,
+        canister_state_bits.reserved_balance_limit,
+        canister_state_bits.total_query_stats,
     );
 
     let canister_state = CanisterState {
@@ -618,6 +733,39 @@ fn load_canister_state_from_checkpoint<P: ReadPolicy>(
     load_canister_state::<P>(&canister_layout, canister_id, checkpoint_layout.height())
 }
 
+fn load_snapshot<P: ReadPolicy>(
+    checkpoint_layout: &CheckpointLayout<P>,
+    snapshot_id: &SnapshotId,
+) -> Result<CanisterSnapshot, CheckpointError> {
+    let snapshot_layout = checkpoint_layout.snapshot(snapshot_id)?;
+    let height = checkpoint_layout.height();
+
+    let snapshot_bits = CanisterSnapshotBits::try_from(snapshot_layout.snapshot().deserialize()?)
+        .map_err(|err| CheckpointError::ProtoError {
+        path: snapshot_layout.raw_path(),
+        field: format!("canister_snapshots[{}]::snapshot_bits", snapshot_id),
+        proto_err: err.to_string(),
+    })?;
+
+    Ok(CanisterSnapshot {
+        canister_id: snapshot_bits.canister_id,
+        taken_at_timestamp: snapshot_bits.taken_at_timestamp,
+        wasm_binary: snapshot_layout
+            .wasm()
+            .deserialize(Some(snapshot_bits.binary_hash))?,
+        wasm_memory: Memory::new(
+            PageMap::open(&snapshot_layout.vmemory_0(), height)?,
+            snapshot_bits.wasm_memory_size,
+        ),
+        stable_memory: Memory::new(
+            PageMap::open(&snapshot_layout.stable_memory_blob(), height)?,
+            snapshot_bits.stable_memory_size,
+        ),
+        exported_globals: snapshot_bits.exported_globals,
+        certified_data: snapshot_bits.certified_data,
+    })
+}
+
 fn load_bitcoin_state<P: ReadPolicy>(
     checkpoint_layout: &CheckpointLayout<P>,
 ) -> Result<BitcoinState, CheckpointError> {
@@ -675,7 +823,7 @@ mod tests {
     use ic_replicated_state::{
         canister_state::execution_state::WasmBinary, canister_state::execution_state::WasmMetadata,
         page_map, testing::ReplicatedStateTesting, CallContextManager, CanisterStatus,
-        ExecutionState, ExportedFunctions, NumWasmPages, PageIndex,
+        ExecutionState, ExportedFunctions, Global, NumWasmPages, PageIndex,
     };
     use ic_sys::PAGE_SIZE;
     use ic_test_utilities::{
@@ -948,6 +1096,85 @@ mod tests {
         });
     }
 
+    #[test]
+    fn can_recover_canister_snapshots() {
+        with_test_replica_logger(|log| {
+            let tmp = tmpdir("checkpoint");
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::try_new(log.clone(), root.clone()).unwrap();
+
+            let canister_id: CanisterId = canister_test_id(10);
+            let mut canister_state = new_canister_state(
+                canister_id,
+                user_test_id(24).get(),
+                INITIAL_CYCLES,
+                NumSeconds::from(100_000),
+            );
+            canister_state.execution_state = Some(ExecutionState {
+                canister_root: root.clone(),
+                session_nonce: None,
+                wasm_binary: WasmBinary::new(empty_wasm()),
+                wasm_memory: one_page_of(1),
+                stable_memory: one_page_of(2),
+                exported_globals: vec![Global::I64(7)],
+                exports: ExportedFunctions::new(BTreeSet::new()),
+                metadata: WasmMetadata::default(),
+                last_executed_round: ExecutionRound::from(0),
+            });
+            canister_state.system_state.certified_data = vec![3, 4];
+
+            let own_subnet_type = SubnetType::Application;
+            let mut state =
+                ReplicatedState::new_rooted_at(subnet_test_id(1), own_subnet_type, root);
+            state.put_canister_state(canister_state);
+            // After the first checkpoint the canister memories are backed by
+            // checkpoint files, so the snapshot only holds a small delta.
+            let mut state = make_checkpoint_and_get_state(&log, &state, Height::new(1), &layout);
+            state
+                .canister_state_mut(&canister_id)
+                .unwrap()
+                .execution_state
+                .as_mut()
+                .unwrap()
+                .wasm_memory
+                .page_map
+                .update(&[(PageIndex::new(1), &[9u8; PAGE_SIZE])]);
+
+            let snapshot_id = SnapshotId::new(canister_id, 0);
+            let snapshot = CanisterSnapshot::from_canister(
+                state.canister_state(&canister_id).unwrap(),
+                Time::from_nanos_since_unix_epoch(5),
+            )
+            .unwrap();
+            state.canister_snapshots.push(snapshot_id, snapshot.clone());
+            let mut state = make_checkpoint_and_get_state(&log, &state, Height::new(2), &layout);
+
+            let recovered_state = load_checkpoint(
+                &layout.checkpoint(Height::new(2)).unwrap(),
+                own_subnet_type,
+                &checkpoint_metrics(),
+                Some(&mut thread_pool()),
+            )
+            .unwrap();
+            let recovered_snapshot = recovered_state
+                .canister_snapshots
+                .get(&snapshot_id)
+                .unwrap();
+            assert_eq!(recovered_snapshot.as_ref(), &snapshot);
+
+            // Deleted snapshots are removed from the next checkpoint.
+            state.canister_snapshots.remove(&snapshot_id);
+            let state = make_checkpoint_and_get_state(&log, &state, Height::new(3), &layout);
+            assert!(state.canister_snapshots.is_empty());
+            assert!(layout
+                .checkpoint(Height::new(3))
+                .unwrap()
+                .snapshot_ids()
+                .unwrap()
+                .is_empty());
+        });
+    }
+
     #[test]
     fn can_recover_an_empty_state() {
         with_test_replica_logger(|log| {
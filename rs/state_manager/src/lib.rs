@@ -1093,6 +1093,11 @@ fn switch_to_checkpoint(tip: &mut ReplicatedState, src: &ReplicatedState) {
             tip_state.stable_memory.sandbox_memory = SandboxMemory::new();
         }
     }
+
+    // Snapshots are immutable, so the ones loaded from the checkpoint are
+    // equivalent to the ones in `tip`, but are backed by checkpoint files
+    // instead of in-memory page deltas.
+    tip.canister_snapshots = src.canister_snapshots.clone();
 }
 
 /// Persists metadata after releasing the write lock
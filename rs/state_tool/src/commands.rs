@@ -2,7 +2,10 @@
 pub mod cdiff;
 pub mod chash;
 pub mod decode;
+pub mod export_canister;
+pub mod import_canister;
 pub mod import_state;
+pub mod inspect;
 pub mod list;
 pub mod manifest;
 mod utils;
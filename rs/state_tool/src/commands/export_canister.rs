@@ -0,0 +1,119 @@
+//! Exports the state of a single canister from a checkpoint into a bundle.
+
+use crate::commands::utils;
+use ic_protobuf::state::{
+    canister_state_bits::v1 as pb_canister_state_bits, queues::v1 as pb_queues,
+};
+use ic_state_layout::{CanisterLayout, CompleteCheckpointLayout, ReadOnly, WriteOnly};
+use ic_types::{CanisterId, Height};
+use std::path::{Path, PathBuf};
+
+/// Exports the state of the canister with the given ID from the checkpoint at
+/// `checkpoint_path` into a bundle at `bundle_path`.
+///
+/// The bundle is a directory with the layout of a canister in a checkpoint:
+/// the Wasm binary, the heap and stable memory pages, and the system state
+/// protobuf, which includes the certified data. The input and output queues
+/// of the canister are not exported, because the messages in them only make
+/// sense on the original subnet. For the same reason, the call contexts and
+/// pending tasks of the canister are removed, see `clear_call_contexts()`.
+///
+/// The bundle can be imported into another checkpoint with `import_canister`
+/// or into a state machine with `StateMachine::import_canister_state`.
+pub fn do_export(
+    checkpoint_path: PathBuf,
+    canister_id: CanisterId,
+    bundle_path: PathBuf,
+) -> Result<(), String> {
+    let cp_layout = CompleteCheckpointLayout::new(checkpoint_path.clone(), Height::new(0))
+        .map_err(|e| format!("failed to create checkpoint layout: {}", e))?;
+    let canister_ids = cp_layout.canister_ids().map_err(|e| {
+        format!(
+            "failed to list canisters of checkpoint {}: {}",
+            checkpoint_path.display(),
+            e
+        )
+    })?;
+    if !canister_ids.contains(&canister_id) {
+        return Err(format!(
+            "Canister {} does not exist in checkpoint {}",
+            canister_id,
+            checkpoint_path.display()
+        ));
+    }
+    let canister_layout = cp_layout
+        .canister(&canister_id)
+        .map_err(|e| format!("failed to create canister layout: {}", e))?;
+
+    if bundle_path.exists() {
+        return Err(format!("Bundle {} already exists", bundle_path.display()));
+    }
+    let bundle_layout = CanisterLayout::<WriteOnly>::new(bundle_path.clone())
+        .map_err(|e| format!("failed to create bundle directory: {}", e))?;
+
+    for path in utils::canister_files(&canister_layout) {
+        if path.exists() {
+            utils::copy_canister_file(&path, &bundle_layout.raw_path())?;
+        }
+    }
+    bundle_layout
+        .queues()
+        .serialize(pb_queues::CanisterQueues::default())
+        .map_err(|e| format!("failed to write canister queues: {}", e))?;
+    clear_call_contexts(&bundle_path)?;
+
+    utils::load_canister_bundle(&bundle_path, &canister_id)?;
+
+    println!(
+        "Successfully exported canister {} from checkpoint {} to {}",
+        canister_id,
+        checkpoint_path.display(),
+        bundle_path.display()
+    );
+
+    Ok(())
+}
+
+/// Removes the call contexts and callbacks as well as the pending tasks (e.g.
+/// aborted executions) from the system state in the bundle at `bundle_path`.
+///
+/// They refer to messages on the original subnet: the responses to the
+/// callbacks can never reach the imported canister and the callers of the
+/// call contexts can never receive a reply from it. Keeping them would keep
+/// the reserved cycles and memory of the callbacks forever and prevent the
+/// imported canister from ever being stopped. A stopping canister becomes
+/// stopped, as the stop requests cannot be answered either.
+fn clear_call_contexts(bundle_path: &Path) -> Result<(), String> {
+    use pb_canister_state_bits::canister_state_bits::CanisterStatus;
+
+    fn clear(call_context_manager: &mut Option<pb_canister_state_bits::CallContextManager>) {
+        if let Some(call_context_manager) = call_context_manager {
+            call_context_manager.call_contexts.clear();
+            call_context_manager.callbacks.clear();
+        }
+    }
+
+    let read_layout = CanisterLayout::<ReadOnly>::new(bundle_path.to_path_buf())
+        .map_err(|e| format!("failed to create bundle layout: {}", e))?;
+    let mut bits = read_layout
+        .canister()
+        .deserialize()
+        .map_err(|e| format!("failed to read canister state bits: {}", e))?;
+
+    clear(&mut bits.call_context_manager);
+    if matches!(bits.canister_status, Some(CanisterStatus::Stopping(_))) {
+        bits.canister_status = Some(CanisterStatus::Stopped(
+            pb_canister_state_bits::CanisterStatusStopped {},
+        ));
+    }
+    if let Some(CanisterStatus::Running(running)) = &mut bits.canister_status {
+        clear(&mut running.call_context_manager);
+    }
+    bits.task_queue.clear();
+
+    CanisterLayout::<WriteOnly>::new(bundle_path.to_path_buf())
+        .map_err(|e| format!("failed to create bundle layout: {}", e))?
+        .canister()
+        .serialize(bits)
+        .map_err(|e| format!("failed to write canister state bits: {}", e))
+}
@@ -0,0 +1,278 @@
+//! Imports the state of a single canister from a bundle into a checkpoint.
+
+use crate::commands::utils;
+use ic_state_layout::{CheckpointLayout, RwPolicy};
+use ic_types::{CanisterId, Height};
+use std::path::PathBuf;
+
+/// Imports the canister bundle at `bundle_path`, as written by
+/// `export_canister`, into the checkpoint at `checkpoint_path` as the
+/// canister with the given ID.
+///
+/// The checkpoint is modified in place, so its manifest and state hash no
+/// longer match. Messages only reach the imported canister if the routing
+/// table of the subnet assigns its ID to the subnet.
+///
+/// Function is not crash-safe. Caller is responsible to follow guidelines
+/// regarding crash-safe I/O.
+pub fn do_import(
+    bundle_path: PathBuf,
+    canister_id: CanisterId,
+    checkpoint_path: PathBuf,
+) -> Result<(), String> {
+    // Make sure the bundle is complete before touching the checkpoint.
+    utils::load_canister_bundle(&bundle_path, &canister_id)?;
+
+    let cp_layout = CheckpointLayout::<RwPolicy>::new(checkpoint_path.clone(), Height::new(0))
+        .map_err(|e| format!("failed to create checkpoint layout: {}", e))?;
+    let canister_ids = cp_layout.canister_ids().map_err(|e| {
+        format!(
+            "failed to list canisters of checkpoint {}: {}",
+            checkpoint_path.display(),
+            e
+        )
+    })?;
+    if canister_ids.contains(&canister_id) {
+        return Err(format!(
+            "Canister {} already exists in checkpoint {}",
+            canister_id,
+            checkpoint_path.display()
+        ));
+    }
+    let canister_layout = cp_layout
+        .canister(&canister_id)
+        .map_err(|e| format!("failed to create canister layout: {}", e))?;
+
+    let entries = bundle_path.read_dir().map_err(|e| {
+        format!(
+            "failed to read bundle directory {}: {}",
+            bundle_path.display(),
+            e
+        )
+    })?;
+    for entry_result in entries {
+        let entry = entry_result.map_err(|e| {
+            format!(
+                "failed to read entry of directory {}: {}",
+                bundle_path.display(),
+                e
+            )
+        })?;
+        utils::copy_canister_file(&entry.path(), &canister_layout.raw_path())?;
+    }
+
+    println!(
+        "Successfully imported canister {} from {} into checkpoint {}",
+        canister_id,
+        bundle_path.display(),
+        checkpoint_path.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::export_canister::do_export;
+    use ic_metrics::MetricsRegistry;
+    use ic_registry_subnet_type::SubnetType;
+    use ic_replicated_state::{page_map::PageIndex, CanisterState, ReplicatedState};
+    use ic_state_layout::ReadOnly;
+    use ic_state_machine_tests::StateMachine;
+    use ic_state_manager::{checkpoint::load_checkpoint, CheckpointMetrics};
+    use ic_types::{PrincipalId, SubnetId};
+
+    // Writes "hello" to stable memory and sets it as certified data on
+    // installation. `call_remote` calls a method on the canister whose ID is
+    // given by the 10 bytes at offset 5, leaving a call context open as long
+    // as no response arrives.
+    fn canister_wat(remote_canister_id: CanisterId) -> String {
+        let remote: String = remote_canister_id
+            .get()
+            .as_slice()
+            .iter()
+            .map(|byte| format!("\\{:02x}", byte))
+            .collect();
+        format!(
+            r#"(module
+                (import "ic0" "stable_grow" (func $stable_grow (param i32) (result i32)))
+                (import "ic0" "stable_write" (func $stable_write (param i32 i32 i32)))
+                (import "ic0" "certified_data_set" (func $certified_data_set (param i32 i32)))
+                (import "ic0" "call_new"
+                    (func $call_new (param i32 i32 i32 i32 i32 i32 i32 i32)))
+                (import "ic0" "call_perform" (func $call_perform (result i32)))
+                (func (export "canister_init")
+                    (drop (call $stable_grow (i32.const 1)))
+                    (call $stable_write (i32.const 0) (i32.const 0) (i32.const 5))
+                    (call $certified_data_set (i32.const 0) (i32.const 5)))
+                (func (export "canister_update call_remote")
+                    (call $call_new
+                        (i32.const 5) (i32.const {len})
+                        (i32.const 20) (i32.const 3)
+                        (i32.const 0) (i32.const 0) (i32.const 0) (i32.const 0))
+                    (drop (call $call_perform)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "hello")
+                (data (i32.const 5) "{remote}")
+                (data (i32.const 20) "foo"))"#,
+            len = remote_canister_id.get().as_slice().len(),
+            remote = remote,
+        )
+    }
+
+    // Returns the path and the height of the latest checkpoint of `env`.
+    fn latest_checkpoint(env: &StateMachine) -> (PathBuf, Height) {
+        let state_layout = env.state_layout();
+        let height = *state_layout.checkpoint_heights().unwrap().last().unwrap();
+        let path = state_layout
+            .checkpoint(height)
+            .unwrap()
+            .raw_path()
+            .to_path_buf();
+        (path, height)
+    }
+
+    fn load(checkpoint_path: PathBuf, height: Height) -> ReplicatedState {
+        load_checkpoint(
+            &CheckpointLayout::<ReadOnly>::new(checkpoint_path, height).unwrap(),
+            SubnetType::Application,
+            &CheckpointMetrics::new(&MetricsRegistry::new()),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn num_call_contexts(canister: &CanisterState) -> usize {
+        canister
+            .system_state
+            .call_context_manager()
+            .unwrap()
+            .call_contexts()
+            .len()
+    }
+
+    #[test]
+    fn exported_canister_can_be_imported_into_another_checkpoint() {
+        let env = StateMachine::new();
+        env.set_checkpoints_enabled(true);
+        let remote_canister_id = CanisterId::from_u64(100);
+        let canister_id = env.install_canister_wat(&canister_wat(remote_canister_id), vec![], None);
+        // Route the remote canister to another subnet, so that the call is
+        // never answered.
+        env.reroute_canister_range(
+            remote_canister_id..=remote_canister_id,
+            SubnetId::from(PrincipalId::new_subnet_test_id(2)),
+        );
+        env.send_ingress(
+            PrincipalId::new_anonymous(),
+            canister_id,
+            "call_remote",
+            vec![],
+        );
+
+        let (checkpoint_path, height) = latest_checkpoint(&env);
+        let state = load(checkpoint_path.clone(), height);
+        let canister = state.canister_state(&canister_id).unwrap();
+        assert_eq!(num_call_contexts(canister), 1);
+
+        let tmpdir = tempfile::Builder::new().prefix("test").tempdir().unwrap();
+        let bundle_path = tmpdir.path().join("bundle");
+        do_export(checkpoint_path, canister_id, bundle_path.clone()).unwrap();
+
+        let other_env = StateMachine::new();
+        other_env.set_checkpoints_enabled(true);
+        other_env.tick();
+        let (other_checkpoint_path, other_height) = latest_checkpoint(&other_env);
+        do_import(bundle_path, canister_id, other_checkpoint_path.clone()).unwrap();
+
+        let imported_state = load(other_checkpoint_path, other_height);
+        let imported = imported_state.canister_state(&canister_id).unwrap();
+        let execution_state = canister.execution_state.as_ref().unwrap();
+        let imported_execution_state = imported.execution_state.as_ref().unwrap();
+        assert_eq!(
+            imported_execution_state.wasm_binary.binary.as_slice(),
+            execution_state.wasm_binary.binary.as_slice()
+        );
+        assert_eq!(
+            imported_execution_state.exported_globals,
+            execution_state.exported_globals
+        );
+        assert_eq!(
+            imported_execution_state.wasm_memory.size,
+            execution_state.wasm_memory.size
+        );
+        assert_eq!(
+            imported_execution_state
+                .wasm_memory
+                .page_map
+                .get_page(PageIndex::new(0)),
+            execution_state
+                .wasm_memory
+                .page_map
+                .get_page(PageIndex::new(0))
+        );
+        assert_eq!(
+            imported_execution_state.stable_memory.size,
+            execution_state.stable_memory.size
+        );
+        assert_eq!(
+            &imported_execution_state
+                .stable_memory
+                .page_map
+                .get_page(PageIndex::new(0))[..5],
+            b"hello"
+        );
+        assert_eq!(imported.system_state.certified_data, b"hello".to_vec());
+        assert_eq!(num_call_contexts(imported), 0);
+        assert!(imported
+            .system_state
+            .call_context_manager()
+            .unwrap()
+            .callbacks()
+            .is_empty());
+    }
+
+    #[test]
+    fn import_fails_if_canister_exists() {
+        let env = StateMachine::new();
+        env.set_checkpoints_enabled(true);
+        let canister_id =
+            env.install_canister_wat(&canister_wat(CanisterId::from_u64(100)), vec![], None);
+        let (checkpoint_path, _) = latest_checkpoint(&env);
+
+        let tmpdir = tempfile::Builder::new().prefix("test").tempdir().unwrap();
+        let bundle_path = tmpdir.path().join("bundle");
+        do_export(checkpoint_path.clone(), canister_id, bundle_path.clone()).unwrap();
+
+        assert_eq!(
+            do_import(bundle_path, canister_id, checkpoint_path.clone()),
+            Err(format!(
+                "Canister {} already exists in checkpoint {}",
+                canister_id,
+                checkpoint_path.display()
+            ))
+        );
+    }
+
+    #[test]
+    fn export_fails_if_canister_is_missing() {
+        let env = StateMachine::new();
+        env.set_checkpoints_enabled(true);
+        env.tick();
+        let (checkpoint_path, _) = latest_checkpoint(&env);
+        let canister_id = CanisterId::from_u64(100);
+
+        let tmpdir = tempfile::Builder::new().prefix("test").tempdir().unwrap();
+        let bundle_path = tmpdir.path().join("bundle");
+        assert_eq!(
+            do_export(checkpoint_path.clone(), canister_id, bundle_path.clone()),
+            Err(format!(
+                "Canister {} does not exist in checkpoint {}",
+                canister_id,
+                checkpoint_path.display()
+            ))
+        );
+        assert!(!bundle_path.exists());
+    }
+}
@@ -0,0 +1,205 @@
+//! Displays a human-readable summary of a checkpoint.
+
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::{canister_state::CanisterQueues, CanisterState, ReplicatedState};
+use ic_state_layout::CompleteCheckpointLayout;
+use ic_state_manager::{checkpoint::load_checkpoint, CheckpointMetrics};
+use ic_types::Height;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Summary of a checkpoint.
+#[derive(Serialize)]
+struct CheckpointSummary {
+    subnet_id: String,
+    batch_time_nanos: u64,
+    ingress_history_size: usize,
+    subnet_queues: QueueSizes,
+    streams: Vec<StreamSummary>,
+    canisters: Vec<CanisterSummary>,
+}
+
+/// Summary of the stream to another subnet.
+#[derive(Serialize)]
+struct StreamSummary {
+    destination: String,
+    messages_begin: u64,
+    messages_end: u64,
+    signals_end: u64,
+    reject_signals: usize,
+}
+
+/// Summary of a canister.
+#[derive(Serialize)]
+struct CanisterSummary {
+    canister_id: String,
+    controllers: Vec<String>,
+    status: String,
+    cycles_balance: u128,
+    memory_usage_bytes: u64,
+    module_hash: Option<String>,
+    queues: QueueSizes,
+}
+
+/// Number of messages in the queues of a canister or the subnet.
+#[derive(Serialize)]
+struct QueueSizes {
+    ingress: usize,
+    input: usize,
+    output: usize,
+}
+
+impl From<&CanisterQueues> for QueueSizes {
+    fn from(queues: &CanisterQueues) -> Self {
+        Self {
+            ingress: queues.ingress_queue_message_count(),
+            input: queues.input_queues_message_count(),
+            output: queues.output_queues_message_count(),
+        }
+    }
+}
+
+impl From<&ReplicatedState> for CheckpointSummary {
+    fn from(state: &ReplicatedState) -> Self {
+        Self {
+            subnet_id: state.metadata.own_subnet_id.to_string(),
+            batch_time_nanos: state.metadata.batch_time.as_nanos_since_unix_epoch(),
+            ingress_history_size: state.metadata.ingress_history.len(),
+            subnet_queues: QueueSizes::from(state.subnet_queues()),
+            streams: state
+                .metadata
+                .streams()
+                .iter()
+                .map(|(destination, stream)| StreamSummary {
+                    destination: destination.to_string(),
+                    messages_begin: stream.messages_begin().get(),
+                    messages_end: stream.messages_end().get(),
+                    signals_end: stream.signals_end().get(),
+                    reject_signals: stream.reject_signals().len(),
+                })
+                .collect(),
+            canisters: state
+                .canisters_iter()
+                .map(|canister| canister_summary(canister, state.metadata.own_subnet_type))
+                .collect(),
+        }
+    }
+}
+
+fn canister_summary(canister: &CanisterState, own_subnet_type: SubnetType) -> CanisterSummary {
+    CanisterSummary {
+        canister_id: canister.canister_id().to_string(),
+        controllers: canister
+            .controllers()
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        status: canister.status().to_string(),
+        cycles_balance: canister.system_state.balance().get(),
+        memory_usage_bytes: canister.memory_usage(own_subnet_type).get(),
+        module_hash: canister
+            .execution_state
+            .as_ref()
+            .map(|es| hex::encode(es.wasm_binary.binary.module_hash())),
+        queues: QueueSizes::from(canister.system_state.queues()),
+    }
+}
+
+/// Prints the summary as a list of subnet properties followed by a table of
+/// streams and a table of canisters.
+fn print_table(summary: &CheckpointSummary) {
+    println!("Subnet ID:            {}", summary.subnet_id);
+    println!("Batch time (ns):      {}", summary.batch_time_nanos);
+    println!("Ingress history size: {}", summary.ingress_history_size);
+    println!(
+        "Subnet queues:        {} ingress, {} input, {} output",
+        summary.subnet_queues.ingress, summary.subnet_queues.input, summary.subnet_queues.output
+    );
+
+    println!();
+    println!(
+        "{:<65} {:>12} {:>12} {:>12} {:>8}",
+        "STREAM TO", "BEGIN", "END", "SIGNALS END", "REJECTS"
+    );
+    for stream in &summary.streams {
+        println!(
+            "{:<65} {:>12} {:>12} {:>12} {:>8}",
+            stream.destination,
+            stream.messages_begin,
+            stream.messages_end,
+            stream.signals_end,
+            stream.reject_signals
+        );
+    }
+
+    println!();
+    println!(
+        "{:<27} {:<8} {:>40} {:>14} {:<64} {:>7} {:>7} {:>7} CONTROLLERS",
+        "CANISTER", "STATUS", "CYCLES", "MEMORY", "MODULE HASH", "INGRESS", "INPUT", "OUTPUT"
+    );
+    for canister in &summary.canisters {
+        println!(
+            "{:<27} {:<8} {:>40} {:>14} {:<64} {:>7} {:>7} {:>7} {}",
+            canister.canister_id,
+            canister.status,
+            canister.cycles_balance,
+            canister.memory_usage_bytes,
+            canister.module_hash.as_deref().unwrap_or("-"),
+            canister.queues.ingress,
+            canister.queues.input,
+            canister.queues.output,
+            canister.controllers.join(", ")
+        );
+    }
+}
+
+/// Loads the checkpoint at `path` and prints a summary of the subnet and of
+/// every canister, as a table or as JSON.
+///
+/// The checkpoint is loaded as the state of an application subnet, so the
+/// memory usage of canisters includes the memory used by their messages.
+pub fn do_inspect(path: PathBuf, json: bool) -> Result<(), String> {
+    let cp_layout = CompleteCheckpointLayout::new(path.clone(), Height::new(0))
+        .map_err(|e| format!("failed to create checkpoint layout: {}", e))?;
+
+    let dummy_metrics_registry = ic_metrics::MetricsRegistry::new();
+    let dummy_metrics = CheckpointMetrics::new(&dummy_metrics_registry);
+
+    let state = load_checkpoint(&cp_layout, SubnetType::Application, &dummy_metrics, None)
+        .map_err(|e| format!("failed to load checkpoint at {}: {}", path.display(), e))?;
+
+    let summary = CheckpointSummary::from(&state);
+    if json {
+        let json = serde_json::to_string_pretty(&summary)
+            .map_err(|e| format!("failed to serialize summary to JSON: {}", e))?;
+        println!("{}", json);
+    } else {
+        print_table(&summary);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_types::{PrincipalId, SubnetId};
+
+    #[test]
+    fn summary_of_empty_state_serializes_to_json() {
+        let tmpdir = tempfile::Builder::new().prefix("test").tempdir().unwrap();
+        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+        let state = ReplicatedState::new_rooted_at(
+            subnet_id,
+            SubnetType::Application,
+            tmpdir.path().into(),
+        );
+
+        let summary = serde_json::to_value(CheckpointSummary::from(&state)).unwrap();
+
+        assert_eq!(summary["subnet_id"], subnet_id.to_string());
+        assert_eq!(summary["ingress_history_size"], 0);
+        assert_eq!(summary["streams"], serde_json::json!([]));
+        assert_eq!(summary["canisters"], serde_json::json!([]));
+    }
+}
@@ -2,8 +2,11 @@
 
 use ic_config::{config_parser::ConfigSource, ConfigOptional};
 use ic_logger::replica_logger::no_op_logger;
-use ic_state_layout::StateLayout;
-use std::path::PathBuf;
+use ic_state_layout::{AccessPolicy, CanisterLayout, ReadOnly, StateLayout};
+use ic_state_manager::checkpoint::load_canister_state;
+use ic_types::{CanisterId, Height};
+use ic_utils::fs::copy_file_sparse;
+use std::path::{Path, PathBuf};
 
 /// Loads the location of the state root from the given `replica` configuration
 /// file.
@@ -24,3 +27,55 @@ pub fn locate_state_root(config_path: PathBuf) -> Result<StateLayout, String> {
 
     Ok(StateLayout::try_new(no_op_logger(), state_root).unwrap())
 }
+
+/// Returns the paths of the files that make up the state of a canister in a
+/// checkpoint, except for its queues. Files that a canister without code does
+/// not have may be missing.
+pub fn canister_files<P: AccessPolicy>(canister_layout: &CanisterLayout<P>) -> Vec<PathBuf> {
+    vec![
+        canister_layout.canister().raw_path().to_path_buf(),
+        canister_layout.wasm().raw_path().to_path_buf(),
+        canister_layout.vmemory_0(),
+        canister_layout.stable_memory_blob(),
+    ]
+}
+
+/// Copies the canister state file at `src` into the directory `dst_dir` and
+/// makes the copy writeable, as files in checkpoints are read-only.
+pub fn copy_canister_file(src: &Path, dst_dir: &Path) -> Result<(), String> {
+    let file_name = src
+        .file_name()
+        .ok_or_else(|| format!("failed to get file name of path {}", src.display()))?;
+    let dst = dst_dir.join(file_name);
+    copy_file_sparse(src, &dst).map_err(|e| {
+        format!(
+            "Failed to copy {} -> {}: {}",
+            src.display(),
+            dst.display(),
+            e
+        )
+    })?;
+
+    let mut permissions = dst
+        .metadata()
+        .map_err(|e| format!("failed to get metadata of path {}: {}", dst.display(), e))?
+        .permissions();
+    permissions.set_readonly(false);
+    std::fs::set_permissions(&dst, permissions)
+        .map_err(|e| format!("failed to make {} writeable: {}", dst.display(), e))
+}
+
+/// Loads the canister state stored in the bundle at `bundle_path` to make
+/// sure it is complete and can be decoded.
+pub fn load_canister_bundle(bundle_path: &Path, canister_id: &CanisterId) -> Result<(), String> {
+    let bundle_layout = CanisterLayout::<ReadOnly>::new(bundle_path.to_path_buf())
+        .map_err(|e| format!("failed to create bundle layout: {}", e))?;
+    load_canister_state(&bundle_layout, canister_id, Height::new(0)).map_err(|e| {
+        format!(
+            "failed to load canister state from bundle {}: {}",
+            bundle_path.display(),
+            e
+        )
+    })?;
+    Ok(())
+}
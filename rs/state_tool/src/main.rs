@@ -2,9 +2,11 @@
 //!
 //! A command-line tool to manage Internet Computer replicated states (decode
 //! persisted state files, diff checkpoints, compute partial state hashes and
-//! checkpoint manifests, import state trees).
+//! checkpoint manifests, import state trees, export and import the states of
+//! single canisters, inspect checkpoints).
 
 use clap::Parser;
+use ic_types::CanisterId;
 use std::path::PathBuf;
 
 mod commands;
@@ -41,6 +43,49 @@ enum Opt {
         height: u64,
     },
 
+    /// Exports the state of a canister from a checkpoint into a bundle.
+    #[clap(name = "export_canister")]
+    ExportCanister {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+
+        /// ID of the canister to export.
+        #[clap(long = "canister")]
+        canister_id: CanisterId,
+
+        /// Path of the bundle directory to create.
+        #[clap(long = "output")]
+        output: PathBuf,
+    },
+
+    /// Imports the state of a canister from a bundle into a checkpoint.
+    #[clap(name = "import_canister")]
+    ImportCanister {
+        /// Path to the bundle created by `export_canister`.
+        #[clap(long = "bundle")]
+        bundle: PathBuf,
+
+        /// ID that the canister has in the checkpoint.
+        #[clap(long = "canister")]
+        canister_id: CanisterId,
+
+        /// Path to the checkpoint to import the canister into.
+        #[clap(long = "state")]
+        path: PathBuf,
+    },
+
+    /// Displays a human-readable summary of a checkpoint.
+    #[clap(name = "inspect")]
+    Inspect {
+        /// Path to a checkpoint.
+        path: PathBuf,
+
+        /// Prints the summary as JSON instead of a table.
+        #[clap(long = "json")]
+        json: bool,
+    },
+
     /// Computes manifest of a checkpoint.
     #[clap(name = "manifest")]
     Manifest {
@@ -88,6 +133,17 @@ fn main() {
             config,
             height,
         } => commands::import_state::do_import(state, config, height),
+        Opt::ExportCanister {
+            path,
+            canister_id,
+            output,
+        } => commands::export_canister::do_export(path, canister_id, output),
+        Opt::ImportCanister {
+            bundle,
+            canister_id,
+            path,
+        } => commands::import_canister::do_import(bundle, canister_id, path),
+        Opt::Inspect { path, json } => commands::inspect::do_inspect(path, json),
         Opt::Manifest { path } => commands::manifest::do_compute_manifest(path),
         Opt::VerifyManifest { file, version } => {
             commands::verify_manifest::do_verify_manifest(file, version)
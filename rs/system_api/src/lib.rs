@@ -16,12 +16,16 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::{error, ReplicaLogger};
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::{memory_required_to_push_request, Memory, NumWasmPages, PageIndex};
+use ic_replicated_state::{
+    canister_state::WASM_PAGE_SIZE_IN_BYTES, memory_required_to_push_request, CanisterTimer,
+    Memory, NumWasmPages, PageIndex,
+};
 use ic_sys::PageBytes;
 use ic_types::{
+    canister_log::{CanisterLog, MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE},
     ingress::WasmResult,
     messages::{CallContextId, RejectContext, Request, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
-    methods::{Callback, WasmClosure},
+    methods::{Callback, SystemMethod, WasmClosure},
     CanisterId, ComputeAllocation, Cycles, NumBytes, NumInstructions, NumPages, PrincipalId,
     SubnetId, Time,
 };
@@ -37,7 +41,7 @@ use std::{
 
 const MULTIPLIER_MAX_SIZE_LOCAL_SUBNET: u64 = 5;
 const MAX_NON_REPLICATED_QUERY_REPLY_SIZE: NumBytes = NumBytes::new(3 << 20);
-const CERTIFIED_DATA_MAX_LENGTH: u32 = 32;
+const CERTIFIED_DATA_MAX_LENGTH: u64 = 32;
 
 // Enables tracing of system calls for local debugging.
 const TRACE_SYSCALLS: bool = false;
@@ -68,10 +72,11 @@ macro_rules! trace_syscall {
 
 // This helper is used in system calls for displaying a summary hash of a heap region.
 #[inline]
-fn summarize(heap: &[u8], start: u32, size: u32) -> u64 {
+fn summarize(heap: &[u8], start: u64, size: u64) -> u64 {
     if TRACE_SYSCALLS {
-        let start = (start as usize).min(heap.len());
-        let end = ((start as usize) + (size as usize)).min(heap.len());
+        let start = start.min(heap.len() as u64);
+        let end = start.saturating_add(size).min(heap.len() as u64);
+        let (start, end) = (start as usize, end as usize);
         // The actual hash function doesn't matter much as long as it is
         // cheap to compute and maps the input to u64 reasonably well.
         let mut sum = 0;
@@ -144,6 +149,10 @@ impl InstructionLimits {
 pub struct ExecutionParameters {
     pub instruction_limits: InstructionLimits,
     pub canister_memory_limit: NumBytes,
+    /// The limit on the Wasm memory of the canister from its settings. Growing
+    /// the Wasm memory above this limit traps. It is not enforced for queries
+    /// and in `canister_pre_upgrade` and `canister_post_upgrade`.
+    pub wasm_memory_limit: Option<NumBytes>,
     pub compute_allocation: ComputeAllocation,
     pub subnet_type: SubnetType,
     pub execution_mode: ExecutionMode,
@@ -302,8 +311,11 @@ pub enum ApiType {
         message_accepted: bool,
     },
 
-    // For executing the `canister_heartbeat` method
-    Heartbeat {
+    // For executing the `canister_heartbeat` or `canister_global_timer` methods
+    SystemTask {
+        /// System task to execute.
+        /// Only `canister_heartbeat` and `canister_global_timer` are allowed.
+        system_task: SystemMethod,
         time: Time,
         call_context_id: CallContextId,
         /// Optional outgoing request under construction. If `None` no outgoing
@@ -335,8 +347,13 @@ impl ApiType {
         }
     }
 
-    pub fn heartbeat(time: Time, call_context_id: CallContextId) -> Self {
-        Self::Heartbeat {
+    pub fn system_task(
+        system_task: SystemMethod,
+        time: Time,
+        call_context_id: CallContextId,
+    ) -> Self {
+        Self::SystemTask {
+            system_task,
             time,
             call_context_id,
             outgoing_request: None,
@@ -494,7 +511,7 @@ impl ApiType {
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. }
             | ApiType::PreUpgrade { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. } => ModificationTracking::Track,
         }
     }
@@ -505,7 +522,11 @@ impl ApiType {
         match self {
             ApiType::Start { .. } => "start",
             ApiType::Init { .. } => "init",
-            ApiType::Heartbeat { .. } => "heartbeat",
+            ApiType::SystemTask { system_task, .. } => match system_task {
+                SystemMethod::CanisterHeartbeat => "heartbeat",
+                SystemMethod::CanisterGlobalTimer => "global timer",
+                _ => panic!("Only `canister_heartbeat` and `canister_global_timer` are allowed."),
+            },
             ApiType::Update { .. } => "update",
             ApiType::ReplicatedQuery { .. } => "replicated query",
             ApiType::NonReplicatedQuery { .. } => "non replicated query",
@@ -717,6 +738,11 @@ pub struct SystemApiImpl {
 
     /// Tracks the total execution complexity.
     total_execution_complexity: ExecutionComplexity,
+
+    /// Log records produced during the current execution. They are kept
+    /// separately from `sandbox_safe_system_state` because they need to be
+    /// preserved even if the execution fails.
+    canister_log: CanisterLog,
 }
 
 impl SystemApiImpl {
@@ -740,6 +766,8 @@ impl SystemApiImpl {
         );
         let stable_memory = StableMemory::new(stable_memory);
         let slice_limit = execution_parameters.instruction_limits.slice().get();
+        let canister_log =
+            CanisterLog::new_empty(sandbox_safe_system_state.next_canister_log_record_idx());
         Self {
             execution_error: None,
             api_type,
@@ -752,6 +780,7 @@ impl SystemApiImpl {
             current_slice_instruction_limit: i64::try_from(slice_limit).unwrap_or(i64::MAX),
             instructions_executed_before_current_slice: 0,
             total_execution_complexity: ExecutionComplexity::new(),
+            canister_log,
         }
     }
 
@@ -766,7 +795,7 @@ impl SystemApiImpl {
         match &mut self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::PreUpgrade { .. }
@@ -803,7 +832,7 @@ impl SystemApiImpl {
             | ApiType::Init { .. }
             | ApiType::PreUpgrade { .. }
             | ApiType::Cleanup { .. }
-            | ApiType::Heartbeat { .. } => Ok(None),
+            | ApiType::SystemTask { .. } => Ok(None),
             ApiType::InspectMessage {
                 message_accepted, ..
             } => {
@@ -860,7 +889,7 @@ impl SystemApiImpl {
     fn get_msg_caller_id(&self, method_name: &str) -> Result<PrincipalId, HypervisorError> {
         match &self.api_type {
             ApiType::Start { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. } => Err(self.error_for(method_name)),
@@ -878,7 +907,7 @@ impl SystemApiImpl {
             ApiType::Start { .. }
             | ApiType::Init { .. }
             | ApiType::PreUpgrade { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::InspectMessage { .. } => None,
             ApiType::Update {
@@ -918,7 +947,7 @@ impl SystemApiImpl {
         match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
@@ -934,7 +963,7 @@ impl SystemApiImpl {
         match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
@@ -962,7 +991,7 @@ impl SystemApiImpl {
             ApiType::Update {
                 outgoing_request, ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
                 outgoing_request, ..
             }
             | ApiType::ReplyCallback {
@@ -993,7 +1022,7 @@ impl SystemApiImpl {
         match &self.api_type {
             ApiType::Start {} => Err(self.error_for(method_name)),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -1012,7 +1041,7 @@ impl SystemApiImpl {
         match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::PreUpgrade { .. }
@@ -1036,7 +1065,7 @@ impl SystemApiImpl {
         match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
@@ -1068,7 +1097,7 @@ impl SystemApiImpl {
             } => Ok(Cycles::new(0)),
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::PreUpgrade { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -1096,6 +1125,11 @@ impl SystemApiImpl {
         self.sandbox_safe_system_state.take_changes()
     }
 
+    /// Returns the log records produced during the current execution.
+    pub fn take_canister_log(&mut self) -> CanisterLog {
+        std::mem::take(&mut self.canister_log)
+    }
+
     pub fn stable_memory_size(&self) -> NumWasmPages {
         self.stable_memory.stable_memory_size
     }
@@ -1185,6 +1219,25 @@ impl SystemApi for SystemApiImpl {
         self.execution_parameters.subnet_type
     }
 
+    fn save_log_message(&mut self, is_trap: bool, src: u64, size: u64, heap: &[u8]) {
+        let size = size.min(MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE as u64);
+        let mut content = if is_trap {
+            b"[TRAP]: ".to_vec()
+        } else {
+            vec![]
+        };
+        match valid_subslice("save_log_message", src, size, heap) {
+            Ok(bytes) => content.extend_from_slice(bytes),
+            Err(_) => content.extend_from_slice(b"(log message out of memory bounds)"),
+        }
+        // The `start` method has no access to the time.
+        let timestamp_nanos = self
+            .ic0_time()
+            .map(|time| time.as_nanos_since_unix_epoch())
+            .unwrap_or(0);
+        self.canister_log.add_record(timestamp_nanos, &content);
+    }
+
     fn message_instruction_limit(&self) -> NumInstructions {
         self.execution_parameters.instruction_limits.message()
     }
@@ -1219,9 +1272,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_caller_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match self.get_msg_caller_id("ic0_msg_caller_copy") {
@@ -1251,7 +1304,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Cleanup { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::RejectCallback { .. }
             | ApiType::PreUpgrade { .. } => Err(self.error_for("ic0_msg_arg_data_size")),
             ApiType::Init {
@@ -1279,14 +1332,14 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_arg_data_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
             ApiType::Start { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::RejectCallback { .. }
             | ApiType::PreUpgrade { .. } => Err(self.error_for("ic0_msg_arg_data_copy")),
@@ -1340,7 +1393,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::Cleanup { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::Init { .. } => Err(self.error_for("ic0_msg_method_name_size")),
@@ -1352,9 +1405,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_method_name_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1364,7 +1417,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::PreUpgrade { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::Init { .. } => Err(self.error_for("ic0_msg_method_name_copy")),
@@ -1401,7 +1454,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::Cleanup { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::Init { .. } => Err(self.error_for("ic0_accept_message")),
@@ -1443,15 +1496,15 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_reply_data_append(
         &mut self,
-        src: u32,
-        size: u32,
+        src: u64,
+        size: u64,
         heap: &[u8],
     ) -> HypervisorResult<()> {
         let result = match self.get_response_info() {
             None => Err(self.error_for("ic0_msg_reply_data_append")),
             Some((data, max_reply_size, response_status)) => match response_status {
                 ResponseStatus::NotRepliedYet => {
-                    let payload_size = (data.len() + size as usize) as u64;
+                    let payload_size = (data.len() as u64).saturating_add(size);
                     if payload_size > max_reply_size.get() {
                         let string = format!(
                             "ic0.msg_reply_data_append: application payload size ({}) cannot be larger than {}",
@@ -1481,12 +1534,12 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_reject(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_msg_reject(&mut self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()> {
         let result = match self.get_response_info() {
             None => Err(self.error_for("ic0_msg_reject")),
             Some((_, max_reply_size, response_status)) => match response_status {
                 ResponseStatus::NotRepliedYet => {
-                    if size as u64 > max_reply_size.get() {
+                    if size > max_reply_size.get() {
                         let string = format!(
                         "ic0.msg_reject: application payload size ({}) cannot be larger than {}",
                         size, max_reply_size
@@ -1538,9 +1591,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_reject_msg_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = {
@@ -1573,7 +1626,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start { .. } => Err(self.error_for("ic0_canister_self_size")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::Update { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -1594,15 +1647,15 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_canister_self_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
             ApiType::Start { .. } => Err(self.error_for("ic0_canister_self_copy")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::Update { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -1636,7 +1689,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_controller_size")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -1654,15 +1707,15 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_controller_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_controller_copy")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -1686,16 +1739,16 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_call_simple(
         &mut self,
-        callee_src: u32,
-        callee_size: u32,
-        method_name_src: u32,
-        method_name_len: u32,
+        callee_src: u64,
+        callee_size: u64,
+        method_name_src: u64,
+        method_name_len: u64,
         reply_fun: u32,
         reply_env: u32,
         reject_fun: u32,
         reject_env: u32,
-        data_src: u32,
-        data_len: u32,
+        data_src: u64,
+        data_len: u64,
         heap: &[u8],
     ) -> HypervisorResult<i32> {
         let result = match &mut self.api_type {
@@ -1719,7 +1772,7 @@ impl SystemApi for SystemApiImpl {
                     },
                 ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
                 call_context_id, ..
             }
             | ApiType::ReplyCallback {
@@ -1728,7 +1781,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::RejectCallback {
                 call_context_id, ..
             } => {
-                if data_len as u64 > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES.get() {
+                if data_len > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES.get() {
                     return Ok(RejectCode::SysFatal as i32);
                 }
 
@@ -1800,10 +1853,10 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_call_new(
         &mut self,
-        callee_src: u32,
-        callee_size: u32,
-        name_src: u32,
-        name_len: u32,
+        callee_src: u64,
+        callee_size: u64,
+        name_src: u64,
+        name_len: u64,
         reply_fun: u32,
         reply_env: u32,
         reject_fun: u32,
@@ -1831,7 +1884,7 @@ impl SystemApi for SystemApiImpl {
                     },
                 ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
                 outgoing_request, ..
             }
             | ApiType::ReplyCallback {
@@ -1878,7 +1931,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_call_data_append(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_call_data_append(&mut self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()> {
         let result = match &mut self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
@@ -1900,7 +1953,7 @@ impl SystemApi for SystemApiImpl {
                     },
                 ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
                 outgoing_request, ..
             }
             | ApiType::ReplyCallback {
@@ -1947,7 +2000,7 @@ impl SystemApi for SystemApiImpl {
                     },
                 ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
                 outgoing_request, ..
             }
             | ApiType::ReplyCallback {
@@ -2004,7 +2057,7 @@ impl SystemApi for SystemApiImpl {
                 outgoing_request,
                 ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
                 call_context_id,
                 outgoing_request,
                 ..
@@ -2051,7 +2104,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable_size")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2069,7 +2122,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable_grow")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2099,15 +2152,15 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_stable_read(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable_read")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2133,15 +2186,15 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_stable_write(
         &mut self,
-        offset: u32,
-        src: u32,
-        size: u32,
+        offset: u64,
+        src: u64,
+        size: u64,
         heap: &[u8],
     ) -> HypervisorResult<NumPages> {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable_write")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2169,7 +2222,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable64_size")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2187,7 +2240,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable64_grow")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2225,7 +2278,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable64_read")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2244,7 +2297,7 @@ impl SystemApi for SystemApiImpl {
             dst,
             offset,
             size,
-            summarize(heap, dst as u32, size as u32)
+            summarize(heap, dst, size)
         );
         result
     }
@@ -2259,7 +2312,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable64_write")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2278,7 +2331,7 @@ impl SystemApi for SystemApiImpl {
             offset,
             src,
             size,
-            summarize(heap, src as u32, size as u32)
+            summarize(heap, src, size)
         );
         result
     }
@@ -2287,7 +2340,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start { .. } => Err(self.error_for("ic0_time")),
             ApiType::Init { time, .. }
-            | ApiType::Heartbeat { time, .. }
+            | ApiType::SystemTask { time, .. }
             | ApiType::Update { time, .. }
             | ApiType::Cleanup { time, .. }
             | ApiType::NonReplicatedQuery { time, .. }
@@ -2340,6 +2393,13 @@ impl SystemApi for SystemApiImpl {
             if native_memory_grow_res == -1 {
                 return Ok(-1);
             }
+            if let Some(limit) = self.execution_parameters.wasm_memory_limit {
+                let wasm_memory_pages = native_memory_grow_res as u64 + additional_pages as u64;
+                let bytes = NumBytes::new(wasm_memory_pages * WASM_PAGE_SIZE_IN_BYTES as u64);
+                if bytes > limit {
+                    return Err(HypervisorError::WasmMemoryLimitExceeded { bytes, limit });
+                }
+            }
             match self.memory_usage.allocate_pages(additional_pages as usize) {
                 Ok(()) => Ok(native_memory_grow_res),
                 Err(_err) => Err(HypervisorError::OutOfMemory),
@@ -2369,7 +2429,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_canister_cycles_balance128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_canister_cycles_balance128(&self, dst: u64, heap: &mut [u8]) -> HypervisorResult<()> {
         let result = {
             let method_name = "ic0_canister_cycles_balance128";
             let cycles = self.ic0_canister_cycles_balance_helper(method_name)?;
@@ -2399,7 +2459,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_cycles_available128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_cycles_available128(&self, dst: u64, heap: &mut [u8]) -> HypervisorResult<()> {
         let result = {
             let method_name = "ic0_msg_cycles_available128";
             let cycles = self.ic0_msg_cycles_available_helper(method_name)?;
@@ -2424,7 +2484,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_cycles_refunded128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_cycles_refunded128(&self, dst: u64, heap: &mut [u8]) -> HypervisorResult<()> {
         let result = {
             let method_name = "ic0_msg_cycles_refunded128";
             let cycles = self.ic0_msg_cycles_refunded_helper(method_name)?;
@@ -2463,7 +2523,7 @@ impl SystemApi for SystemApiImpl {
     fn ic0_msg_cycles_accept128(
         &mut self,
         max_amount: Cycles,
-        dst: u32,
+        dst: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = {
@@ -2486,7 +2546,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::PreUpgrade { .. }
             | ApiType::InspectMessage { .. }
             | ApiType::Update { .. }
-            | ApiType::Heartbeat { .. } => Ok(0),
+            | ApiType::SystemTask { .. } => Ok(0),
             ApiType::ReplicatedQuery {
                 data_certificate, ..
             }
@@ -2505,7 +2565,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. }
@@ -2528,15 +2588,15 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_data_certificate_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. }
@@ -2597,7 +2657,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_certified_data_set(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_certified_data_set(&mut self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()> {
         let result = match &mut self.api_type {
             ApiType::Start { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2605,7 +2665,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_certified_data_set")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. }
@@ -2656,7 +2716,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::Init { .. }
             | ApiType::Cleanup { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. }
@@ -2681,7 +2741,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_mint_cycles")),
             ApiType::Update { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. } => {
                 self.sandbox_safe_system_state
@@ -2693,8 +2753,70 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_debug_print(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
-        const MAX_DEBUG_MESSAGE_SIZE: u32 = 32 * 1024;
+    fn ic0_global_timer_set(&mut self, time: Time) -> HypervisorResult<Time> {
+        let result = match &self.api_type {
+            ApiType::Start { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_global_timer_set")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Update { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. } => {
+                let prev_time = self.sandbox_safe_system_state.global_timer().to_time();
+                self.sandbox_safe_system_state
+                    .set_global_timer(CanisterTimer::from_time(time));
+                Ok(prev_time)
+            }
+        };
+        trace_syscall!(self, ic0_global_timer_set, result, time);
+        result
+    }
+
+    fn ic0_is_controller(&self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<u32> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_is_controller")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Update { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                let bytes = valid_subslice("ic0.is_controller", src, size, heap)?;
+                let principal =
+                    PrincipalId::try_from(bytes).map_err(HypervisorError::InvalidPrincipalId)?;
+                Ok(self
+                    .sandbox_safe_system_state
+                    .controllers
+                    .contains(&principal) as u32)
+            }
+        };
+        trace_syscall!(
+            self,
+            ic0_is_controller,
+            result,
+            src,
+            size,
+            summarize(heap, src, size)
+        );
+        result
+    }
+
+    fn ic0_canister_version(&self) -> HypervisorResult<u64> {
+        let result = Ok(self.sandbox_safe_system_state.canister_version);
+        trace_syscall!(self, ic0_canister_version, result);
+        result
+    }
+
+    fn ic0_debug_print(&self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()> {
+        const MAX_DEBUG_MESSAGE_SIZE: u64 = 32 * 1024;
         let size = size.min(MAX_DEBUG_MESSAGE_SIZE);
         let msg = match valid_subslice("ic0.debug_print", src, size, heap) {
             Ok(bytes) => String::from_utf8_lossy(bytes).to_string(),
@@ -2713,8 +2835,8 @@ impl SystemApi for SystemApiImpl {
         Ok(())
     }
 
-    fn ic0_trap(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
-        const MAX_ERROR_MESSAGE_SIZE: u32 = 16 * 1024;
+    fn ic0_trap(&self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()> {
+        const MAX_ERROR_MESSAGE_SIZE: u64 = 16 * 1024;
         let size = size.min(MAX_ERROR_MESSAGE_SIZE);
         let result = {
             let msg = valid_subslice("trap", src, size, heap)
@@ -2739,7 +2861,7 @@ impl OutOfInstructionsHandler for DefaultOutOfInstructionsHandler {
 
 pub(crate) fn copy_cycles_to_heap(
     cycles: Cycles,
-    dst: u32,
+    dst: u64,
     heap: &mut [u8],
     method_name: &str,
 ) -> HypervisorResult<()> {
@@ -2766,22 +2888,20 @@ pub(crate) fn copy_cycles_to_heap(
 
 pub(crate) fn valid_subslice<'a>(
     ctx: &str,
-    src: u32,
-    len: u32,
+    src: u64,
+    len: u64,
     slice: &'a [u8],
 ) -> HypervisorResult<&'a [u8]> {
-    let len = len as usize;
-    let src = src as usize;
-    if slice.len() < src + len {
-        return Err(ContractViolation(format!(
+    match src.checked_add(len) {
+        Some(end) if end <= slice.len() as u64 => Ok(&slice[src as usize..end as usize]),
+        _ => Err(ContractViolation(format!(
             "{}: src={} + length={} exceeds the slice size={}",
             ctx,
             src,
             len,
             slice.len()
-        )));
+        ))),
     }
-    Ok(&slice[src..src + len])
 }
 
 #[cfg(test)]
@@ -53,10 +53,10 @@ impl RequestInPrep {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         sender: CanisterId,
-        callee_src: u32,
-        callee_size: u32,
-        method_name_src: u32,
-        method_name_len: u32,
+        callee_src: u64,
+        callee_size: u64,
+        method_name_src: u64,
+        method_name_len: u64,
         heap: &[u8],
         on_reply: WasmClosure,
         on_reject: WasmClosure,
@@ -65,7 +65,7 @@ impl RequestInPrep {
     ) -> HypervisorResult<Self> {
         let method_name = {
             let max_size_local_subnet = max_size_remote_subnet * multiplier_max_size_local_subnet;
-            if method_name_len as u64 > max_size_local_subnet.get() {
+            if method_name_len > max_size_local_subnet.get() {
                 return Err(HypervisorError::ContractViolation(format!(
                     "RequestInPrep: size of method_name {} exceeded the allowed limit local-subnet {} remote-subnet {}",
                     callee_size, max_size_local_subnet, max_size_remote_subnet
@@ -117,14 +117,14 @@ impl RequestInPrep {
 
     pub(crate) fn extend_method_payload(
         &mut self,
-        src: u32,
-        size: u32,
+        src: u64,
+        size: u64,
         heap: &[u8],
     ) -> HypervisorResult<()> {
         let current_size = self.method_name.len() + self.method_payload.len();
         let max_size_local_subnet =
             self.max_size_remote_subnet * self.multiplier_max_size_local_subnet;
-        if size as u64 > max_size_local_subnet.get() - current_size as u64 {
+        if size > max_size_local_subnet.get() - current_size as u64 {
             Err(HypervisorError::ContractViolation(format!(
                 "RequestInPrep: current_size {} exceeded the allowed limit local-subnet {} remote-subnet {}",
                 current_size, max_size_local_subnet, self.max_size_remote_subnet
@@ -10,7 +10,8 @@ use ic_logger::{info, ReplicaLogger};
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    canister_state::DEFAULT_QUEUE_CAPACITY, CanisterStatus, NetworkTopology, SystemState,
+    canister_state::DEFAULT_QUEUE_CAPACITY, CanisterStatus, CanisterTimer, NetworkTopology,
+    SystemState,
 };
 use ic_types::{
     messages::{CallContextId, CallbackId, RejectContext, Request},
@@ -57,6 +58,7 @@ pub struct SystemStateChanges {
     call_context_balance_taken: BTreeMap<CallContextId, Cycles>,
     request_slots_used: BTreeMap<CanisterId, usize>,
     requests: Vec<Request>,
+    pub(super) new_global_timer: Option<CanisterTimer>,
 }
 
 impl Default for SystemStateChanges {
@@ -69,6 +71,7 @@ impl Default for SystemStateChanges {
             call_context_balance_taken: BTreeMap::new(),
             request_slots_used: BTreeMap::new(),
             requests: vec![],
+            new_global_timer: None,
         }
     }
 }
@@ -226,6 +229,11 @@ impl SystemStateChanges {
             }
             system_state.certified_data = certified_data.clone();
         }
+
+        // Update the global timer.
+        if let Some(new_global_timer) = self.new_global_timer {
+            system_state.global_timer = new_global_timer;
+        }
         Ok(())
     }
 }
@@ -240,6 +248,7 @@ pub struct SandboxSafeSystemState {
     pub system_state_changes: SystemStateChanges,
     pub(super) canister_id: CanisterId,
     pub(super) controller: PrincipalId,
+    pub(super) controllers: BTreeSet<PrincipalId>,
     pub(super) status: CanisterStatusView,
     pub(super) subnet_type: SubnetType,
     pub(super) subnet_size: usize,
@@ -255,6 +264,9 @@ pub struct SandboxSafeSystemState {
     available_request_slots: BTreeMap<CanisterId, usize>,
     ic00_available_request_slots: usize,
     ic00_aliases: BTreeSet<CanisterId>,
+    global_timer: CanisterTimer,
+    next_canister_log_record_idx: u64,
+    pub(super) canister_version: u64,
 }
 
 impl SandboxSafeSystemState {
@@ -275,10 +287,15 @@ impl SandboxSafeSystemState {
         ic00_available_request_slots: usize,
         ic00_aliases: BTreeSet<CanisterId>,
         subnet_size: usize,
+        global_timer: CanisterTimer,
+        next_canister_log_record_idx: u64,
+        controllers: BTreeSet<PrincipalId>,
+        canister_version: u64,
     ) -> Self {
         Self {
             canister_id,
             controller,
+            controllers,
             status,
             subnet_type: cycles_account_manager.subnet_type(),
             subnet_size,
@@ -292,6 +309,9 @@ impl SandboxSafeSystemState {
             available_request_slots,
             ic00_available_request_slots,
             ic00_aliases,
+            global_timer,
+            next_canister_log_record_idx,
+            canister_version,
         }
     }
 
@@ -348,6 +368,10 @@ impl SandboxSafeSystemState {
             ic00_available_request_slots,
             ic00_aliases,
             subnet_size,
+            system_state.global_timer,
+            system_state.canister_log.next_idx(),
+            system_state.controllers.clone(),
+            system_state.canister_version,
         )
     }
 
@@ -387,6 +411,22 @@ impl SandboxSafeSystemState {
             .push(CallbackUpdate::Unregister(id))
     }
 
+    /// Returns the global timer, taking into account the changes made
+    /// during the current execution.
+    pub(super) fn global_timer(&self) -> CanisterTimer {
+        self.system_state_changes
+            .new_global_timer
+            .unwrap_or(self.global_timer)
+    }
+
+    pub(super) fn set_global_timer(&mut self, timer: CanisterTimer) {
+        self.system_state_changes.new_global_timer = Some(timer);
+    }
+
+    pub(super) fn next_canister_log_record_idx(&self) -> u64 {
+        self.next_canister_log_record_idx
+    }
+
     pub(super) fn cycles_balance(&self) -> Cycles {
         let cycles_change = self.system_state_changes.cycles_balance_change;
         cycles_change.apply(self.initial_cycles_balance)
@@ -63,22 +63,26 @@ impl StableMemory {
     /// Reads from stable memory back to heap.
     pub(super) fn stable_read(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let (dst, offset, size) = (dst as usize, offset as usize, size as usize);
 
-        if offset + size > (self.stable_size()? as usize * WASM_PAGE_SIZE_IN_BYTES as usize) {
+        let (stable_memory_end, overflow) = offset.overflowing_add(size);
+        if overflow
+            || stable_memory_end > self.stable_size()? as usize * WASM_PAGE_SIZE_IN_BYTES as usize
+        {
             return Err(HypervisorError::Trapped(StableMemoryOutOfBounds));
         }
 
-        if dst + size > heap.len() {
+        let (heap_end, overflow) = dst.overflowing_add(size);
+        if overflow || heap_end > heap.len() {
             return Err(HypervisorError::Trapped(HeapOutOfBounds));
         }
         self.stable_memory_buffer
-            .read(&mut heap[dst..dst + size], offset);
+            .read(&mut heap[dst..heap_end], offset);
         Ok(())
     }
 
@@ -86,24 +90,28 @@ impl StableMemory {
     /// Returns the number of **new** dirty pages created by the write.
     pub(super) fn stable_write(
         &mut self,
-        offset: u32,
-        src: u32,
-        size: u32,
+        offset: u64,
+        src: u64,
+        size: u64,
         heap: &[u8],
     ) -> HypervisorResult<NumPages> {
         let (src, offset, size) = (src as usize, offset as usize, size as usize);
 
-        if offset + size > (self.stable_size()? as usize * WASM_PAGE_SIZE_IN_BYTES as usize) {
+        let (stable_memory_end, overflow) = offset.overflowing_add(size);
+        if overflow
+            || stable_memory_end > self.stable_size()? as usize * WASM_PAGE_SIZE_IN_BYTES as usize
+        {
             return Err(HypervisorError::Trapped(StableMemoryOutOfBounds));
         }
 
-        if src + size > heap.len() {
+        let (heap_end, overflow) = src.overflowing_add(size);
+        if overflow || heap_end > heap.len() {
             return Err(HypervisorError::Trapped(HeapOutOfBounds));
         }
 
         Ok(self
             .stable_memory_buffer
-            .write(&heap[src..src + size], offset))
+            .write(&heap[src..heap_end], offset))
     }
 
     /// Determines size of stable memory in Web assembly pages.
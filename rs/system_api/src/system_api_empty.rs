@@ -40,6 +40,9 @@ impl SystemApi for SystemApiEmpty {
     fn subnet_type(&self) -> SubnetType {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
+    fn save_log_message(&mut self, _: bool, _: u64, _: u64, _: &[u8]) {
+        unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
+    }
     fn message_instruction_limit(&self) -> NumInstructions {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
@@ -52,7 +55,7 @@ impl SystemApi for SystemApiEmpty {
     fn slice_instructions_executed(&self, _instruction_counter: i64) -> NumInstructions {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_msg_caller_copy(&self, _: u32, _: u32, _: u32, _: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_caller_copy(&self, _: u64, _: u64, _: u64, _: &mut [u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_msg_caller_size(&self) -> HypervisorResult<u32> {
@@ -61,7 +64,7 @@ impl SystemApi for SystemApiEmpty {
     fn ic0_msg_arg_data_size(&self) -> HypervisorResult<u32> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_msg_arg_data_copy(&self, _: u32, _: u32, _: u32, _: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_arg_data_copy(&self, _: u64, _: u64, _: u64, _: &mut [u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_msg_method_name_size(&self) -> HypervisorResult<u32> {
@@ -69,9 +72,9 @@ impl SystemApi for SystemApiEmpty {
     }
     fn ic0_msg_method_name_copy(
         &self,
-        _: u32,
-        _: u32,
-        _: u32,
+        _: u64,
+        _: u64,
+        _: u64,
         _: &mut [u8],
     ) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
@@ -79,7 +82,7 @@ impl SystemApi for SystemApiEmpty {
     fn ic0_accept_message(&mut self) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_msg_reply_data_append(&mut self, _: u32, _: u32, _: &[u8]) -> HypervisorResult<()> {
+    fn ic0_msg_reply_data_append(&mut self, _: u64, _: u64, _: &[u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_msg_reply(&mut self) -> HypervisorResult<()> {
@@ -88,7 +91,7 @@ impl SystemApi for SystemApiEmpty {
     fn ic0_msg_reject_code(&self) -> HypervisorResult<i32> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_msg_reject(&mut self, _: u32, _: u32, _: &[u8]) -> HypervisorResult<()> {
+    fn ic0_msg_reject(&mut self, _: u64, _: u64, _: &[u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_msg_reject_msg_size(&self) -> HypervisorResult<u32> {
@@ -96,9 +99,9 @@ impl SystemApi for SystemApiEmpty {
     }
     fn ic0_msg_reject_msg_copy(
         &self,
-        _: u32,
-        _: u32,
-        _: u32,
+        _: u64,
+        _: u64,
+        _: u64,
         _: &mut [u8],
     ) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
@@ -108,9 +111,9 @@ impl SystemApi for SystemApiEmpty {
     }
     fn ic0_canister_self_copy(
         &mut self,
-        _: u32,
-        _: u32,
-        _: u32,
+        _: u64,
+        _: u64,
+        _: u64,
         _: &mut [u8],
     ) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
@@ -120,41 +123,41 @@ impl SystemApi for SystemApiEmpty {
     }
     fn ic0_controller_copy(
         &mut self,
-        _: u32,
-        _: u32,
-        _: u32,
+        _: u64,
+        _: u64,
+        _: u64,
         _: &mut [u8],
     ) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_debug_print(&self, _: u32, _: u32, _: &[u8]) -> HypervisorResult<()> {
+    fn ic0_debug_print(&self, _: u64, _: u64, _: &[u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_trap(&self, _: u32, _: u32, _: &[u8]) -> HypervisorResult<()> {
+    fn ic0_trap(&self, _: u64, _: u64, _: &[u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_call_simple(
         &mut self,
+        _: u64,
+        _: u64,
+        _: u64,
+        _: u64,
         _: u32,
         _: u32,
         _: u32,
         _: u32,
-        _: u32,
-        _: u32,
-        _: u32,
-        _: u32,
-        _: u32,
-        _: u32,
+        _: u64,
+        _: u64,
         _: &[u8],
     ) -> HypervisorResult<i32> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_call_new(
         &mut self,
-        _: u32,
-        _: u32,
-        _: u32,
-        _: u32,
+        _: u64,
+        _: u64,
+        _: u64,
+        _: u64,
         _: u32,
         _: u32,
         _: u32,
@@ -163,7 +166,7 @@ impl SystemApi for SystemApiEmpty {
     ) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_call_data_append(&mut self, _: u32, _: u32, _: &[u8]) -> HypervisorResult<()> {
+    fn ic0_call_data_append(&mut self, _: u64, _: u64, _: &[u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_call_on_cleanup(&mut self, _: u32, _: u32) -> HypervisorResult<()> {
@@ -184,10 +187,10 @@ impl SystemApi for SystemApiEmpty {
     fn ic0_stable_grow(&mut self, _: u32) -> HypervisorResult<i32> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_stable_read(&self, _: u32, _: u32, _: u32, _: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_stable_read(&self, _: u64, _: u64, _: u64, _: &mut [u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_stable_write(&mut self, _: u32, _: u32, _: u32, _: &[u8]) -> HypervisorResult<NumPages> {
+    fn ic0_stable_write(&mut self, _: u64, _: u64, _: u64, _: &[u8]) -> HypervisorResult<NumPages> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_stable64_size(&self) -> HypervisorResult<u64> {
@@ -226,19 +229,19 @@ impl SystemApi for SystemApiEmpty {
     fn ic0_canister_cycle_balance(&self) -> HypervisorResult<u64> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_canister_cycles_balance128(&self, _: u32, _: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_canister_cycles_balance128(&self, _: u64, _: &mut [u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_msg_cycles_available(&self) -> HypervisorResult<u64> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_msg_cycles_available128(&self, _: u32, _: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_cycles_available128(&self, _: u64, _: &mut [u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_msg_cycles_refunded(&self) -> HypervisorResult<u64> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_msg_cycles_refunded128(&self, _: u32, _: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_cycles_refunded128(&self, _: u64, _: &mut [u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_msg_cycles_accept(&mut self, _: u64) -> HypervisorResult<u64> {
@@ -247,12 +250,12 @@ impl SystemApi for SystemApiEmpty {
     fn ic0_msg_cycles_accept128(
         &mut self,
         _: Cycles,
-        _: u32,
+        _: u64,
         _: &mut [u8],
     ) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_certified_data_set(&mut self, _: u32, _: u32, _: &[u8]) -> HypervisorResult<()> {
+    fn ic0_certified_data_set(&mut self, _: u64, _: u64, _: &[u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_data_certificate_present(&self) -> HypervisorResult<i32> {
@@ -263,9 +266,9 @@ impl SystemApi for SystemApiEmpty {
     }
     fn ic0_data_certificate_copy(
         &self,
-        _: u32,
-        _: u32,
-        _: u32,
+        _: u64,
+        _: u64,
+        _: u64,
         _: &mut [u8],
     ) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
@@ -276,4 +279,13 @@ impl SystemApi for SystemApiEmpty {
     fn ic0_mint_cycles(&mut self, _: u64) -> HypervisorResult<u64> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
+    fn ic0_global_timer_set(&mut self, _: Time) -> HypervisorResult<Time> {
+        unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
+    }
+    fn ic0_is_controller(&self, _: u64, _: u64, _: &[u8]) -> HypervisorResult<u32> {
+        unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
+    }
+    fn ic0_canister_version(&self) -> HypervisorResult<u64> {
+        unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
+    }
 }
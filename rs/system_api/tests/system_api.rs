@@ -123,6 +123,8 @@ fn test_canister_init_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_not_supported(api.ic0_mint_cycles(0));
 }
 
@@ -186,6 +188,8 @@ fn test_canister_update_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_supported(api.ic0_mint_cycles(0));
 }
 
@@ -249,6 +253,8 @@ fn test_canister_replicated_query_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_not_supported(api.ic0_mint_cycles(0));
 }
 
@@ -312,6 +318,8 @@ fn test_canister_pure_query_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_not_supported(api.ic0_mint_cycles(0));
 }
 
@@ -385,6 +393,8 @@ fn test_canister_stateful_query_support() {
     assert_api_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_not_supported(api.ic0_mint_cycles(0));
 }
 
@@ -448,6 +458,8 @@ fn test_reply_api_support_on_nns() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_supported(api.ic0_mint_cycles(0));
 }
 
@@ -511,6 +523,8 @@ fn test_reply_api_support_non_nns() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_not_supported(api.ic0_mint_cycles(0));
 }
 
@@ -577,6 +591,8 @@ fn test_reject_api_support_on_nns() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_supported(api.ic0_mint_cycles(0));
 }
 
@@ -643,6 +659,8 @@ fn test_reject_api_support_non_nns() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_not_supported(api.ic0_mint_cycles(0));
 }
 
@@ -706,6 +724,8 @@ fn test_pre_upgrade_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_not_supported(api.ic0_mint_cycles(0));
 }
 
@@ -769,6 +789,8 @@ fn test_start_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_not_supported(api.ic0_canister_status());
+    assert_api_not_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_not_supported(api.ic0_mint_cycles(0));
 }
 
@@ -832,6 +854,8 @@ fn test_cleanup_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_not_supported(api.ic0_mint_cycles(0));
 }
 
@@ -900,6 +924,8 @@ fn test_inspect_message_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_not_supported(api.ic0_mint_cycles(0));
 }
 
@@ -964,6 +990,8 @@ fn test_canister_heartbeat_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_not_supported(api.ic0_mint_cycles(0));
 }
 
@@ -1027,6 +1055,8 @@ fn test_canister_heartbeat_support_nns() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
     assert_api_supported(api.ic0_mint_cycles(0));
 }
 
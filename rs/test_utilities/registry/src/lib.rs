@@ -5,7 +5,7 @@ use ic_interfaces::registry::{
 };
 use ic_interfaces::time_source::TimeSource;
 use ic_protobuf::registry::subnet::v1::{
-    CatchUpPackageContents, SubnetFeatures, SubnetListRecord, SubnetRecord,
+    CatchUpPackageContents, EcdsaConfig, SubnetFeatures, SubnetListRecord, SubnetRecord,
 };
 use ic_registry_client_fake::FakeRegistryClient;
 use ic_registry_keys::{
@@ -256,6 +256,11 @@ impl SubnetRecordBuilder {
         self
     }
 
+    pub fn with_ecdsa_config(mut self, ecdsa_config: EcdsaConfig) -> Self {
+        self.record.ecdsa_config = Some(ecdsa_config);
+        self
+    }
+
     pub fn build(self) -> SubnetRecord {
         self.record
     }
@@ -20,6 +20,7 @@ pub mod message_routing;
 pub mod notification;
 pub mod p2p;
 pub mod port_allocation;
+pub mod query_stats;
 pub mod self_validating_payload_builder;
 pub mod stable_memory_reader;
 pub mod state;
@@ -1,8 +1,12 @@
 use std::convert::TryFrom;
 use std::sync::Arc;
 
-use ic_config::flag_status::FlagStatus;
-use ic_embedders::{wasm_utils::compile, wasmtime_embedder::WasmtimeInstance, WasmtimeEmbedder};
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
+use ic_embedders::{
+    wasm_utils::{compile, set_memory64_flags},
+    wasmtime_embedder::WasmtimeInstance,
+    WasmtimeEmbedder,
+};
 use ic_interfaces::execution_environment::{AvailableMemory, ExecutionMode, SystemApi};
 use ic_logger::replica_logger::no_op_logger;
 use ic_registry_subnet_type::SubnetType;
@@ -23,8 +27,27 @@ use crate::{
 
 pub const DEFAULT_NUM_INSTRUCTIONS: NumInstructions = NumInstructions::new(5_000_000_000);
 
+/// Converts the given text format module into a module with a 64-bit memory.
+///
+/// `wabt` does not support the memory64 proposal, so the module has to be
+/// written with a 32-bit memory declaration (but `i64` addresses). It is
+/// converted without validation and the flag marking the memory as 64-bit is
+/// then set in the binary.
+pub fn wat2wasm_memory64(wat: &str) -> Vec<u8> {
+    let mut wasm = wabt::Wat2Wasm::new()
+        .validate(false)
+        .convert(wat)
+        .expect("Failed to convert wat to wasm")
+        .as_ref()
+        .to_vec();
+    set_memory64_flags(&mut wasm);
+    wasm
+}
+
 pub struct WasmtimeInstanceBuilder {
     wat: String,
+    wasm: Option<Vec<u8>>,
+    config: EmbeddersConfig,
     globals: Vec<Global>,
     api_type: ic_system_api::ApiType,
     num_instructions: NumInstructions,
@@ -36,6 +59,8 @@ impl Default for WasmtimeInstanceBuilder {
     fn default() -> Self {
         Self {
             wat: "".to_string(),
+            wasm: None,
+            config: EmbeddersConfig::default(),
             globals: vec![],
             api_type: ic_system_api::ApiType::init(mock_time(), vec![], user_test_id(24).get()),
             num_instructions: DEFAULT_NUM_INSTRUCTIONS,
@@ -57,6 +82,18 @@ impl WasmtimeInstanceBuilder {
         }
     }
 
+    /// Uses the given binary module instead of the text format module.
+    pub fn with_wasm(self, wasm: Vec<u8>) -> Self {
+        Self {
+            wasm: Some(wasm),
+            ..self
+        }
+    }
+
+    pub fn with_config(self, config: EmbeddersConfig) -> Self {
+        Self { config, ..self }
+    }
+
     pub fn with_globals(self, globals: Vec<Global>) -> Self {
         Self { globals, ..self }
     }
@@ -81,10 +118,12 @@ impl WasmtimeInstanceBuilder {
 
     pub fn build(self) -> WasmtimeInstance<SystemApiImpl> {
         let log = no_op_logger();
-        let wasm = wabt::wat2wasm(self.wat).expect("Failed to convert wat to wasm");
+        let wasm = match self.wasm {
+            Some(wasm) => wasm,
+            None => wabt::wat2wasm(self.wat).expect("Failed to convert wat to wasm"),
+        };
 
-        let config = ic_config::embedders::Config::default();
-        let embedder = WasmtimeEmbedder::new(config, log.clone());
+        let embedder = WasmtimeEmbedder::new(self.config, log.clone());
         let (compiled, result) = compile(&embedder, &BinaryEncodedWasm::new(wasm));
         result.expect("Failed to compile wat in WasmtimeInstance");
 
@@ -106,6 +145,7 @@ impl WasmtimeInstanceBuilder {
                     self.num_instructions,
                 ),
                 canister_memory_limit: ic_types::NumBytes::from(4 << 30),
+                wasm_memory_limit: None,
                 compute_allocation: ComputeAllocation::default(),
                 subnet_type: self.subnet_type,
                 execution_mode: ExecutionMode::Replicated,
@@ -148,6 +148,7 @@ async fn install_minter(
         retrieve_btc_min_fee: 0,
         retrieve_btc_min_amount: 0,
         ledger_id,
+        min_confirmations: None,
     };
 
     install_rust_canister(
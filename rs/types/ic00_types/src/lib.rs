@@ -7,7 +7,11 @@ use ic_base_types::{CanisterId, NodeId, NumBytes, PrincipalId, RegistryVersion,
 use ic_error_types::{ErrorCode, UserError};
 use ic_protobuf::registry::crypto::v1::PublicKey;
 use ic_protobuf::registry::subnet::v1::{InitialIDkgDealings, InitialNiDkgTranscriptRecord};
-use ic_protobuf::{proxy::ProxyDecodeError, registry::crypto::v1 as pb_registry_crypto};
+use ic_protobuf::state::canister_state_bits::v1 as pb_canister_state_bits;
+use ic_protobuf::{
+    proxy::{try_from_option_field, ProxyDecodeError},
+    registry::crypto::v1 as pb_registry_crypto,
+};
 use num_traits::cast::ToPrimitive;
 use serde::Serialize;
 use std::{collections::BTreeSet, convert::TryFrom, fmt, slice::Iter, str::FromStr};
@@ -32,6 +36,7 @@ pub enum Method {
     DepositCycles,
     HttpRequest,
     ECDSAPublicKey,
+    FetchCanisterLogs,
     InstallCode,
     RawRand,
     SetController,
@@ -42,6 +47,11 @@ pub enum Method {
     UninstallCode,
     UpdateSettings,
     ComputeInitialEcdsaDealings,
+    TakeCanisterSnapshot,
+    LoadCanisterSnapshot,
+    ListCanisterSnapshots,
+    DeleteCanisterSnapshot,
+    CanisterInfo,
 
     // Bitcoin Interface.
     BitcoinGetBalance,
@@ -97,6 +107,8 @@ impl From<CanisterId> for CanisterIdRecord {
 ///     controller : principal;
 ///     compute_allocation: nat;
 ///     memory_allocation: opt nat;
+///     wasm_memory_limit: opt nat;
+///     reserved_cycles_limit: opt nat;
 /// })`
 #[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
 pub struct DefiniteCanisterSettingsArgs {
@@ -105,6 +117,10 @@ pub struct DefiniteCanisterSettingsArgs {
     compute_allocation: candid::Nat,
     memory_allocation: candid::Nat,
     freezing_threshold: candid::Nat,
+    // Optional for compatibility with settings that predate it.
+    wasm_memory_limit: Option<candid::Nat>,
+    // Optional for compatibility with settings that predate it.
+    reserved_cycles_limit: Option<candid::Nat>,
 }
 
 impl DefiniteCanisterSettingsArgs {
@@ -114,6 +130,8 @@ impl DefiniteCanisterSettingsArgs {
         compute_allocation: u64,
         memory_allocation: Option<u64>,
         freezing_threshold: u64,
+        wasm_memory_limit: Option<u64>,
+        reserved_cycles_limit: Option<u128>,
     ) -> Self {
         let memory_allocation = match memory_allocation {
             None => candid::Nat::from(0),
@@ -125,12 +143,26 @@ impl DefiniteCanisterSettingsArgs {
             compute_allocation: candid::Nat::from(compute_allocation),
             memory_allocation,
             freezing_threshold: candid::Nat::from(freezing_threshold),
+            wasm_memory_limit: wasm_memory_limit.map(candid::Nat::from),
+            reserved_cycles_limit: reserved_cycles_limit.map(candid::Nat::from),
         }
     }
 
     pub fn controllers(&self) -> Vec<PrincipalId> {
         self.controllers.clone()
     }
+
+    pub fn wasm_memory_limit(&self) -> Option<u64> {
+        self.wasm_memory_limit
+            .as_ref()
+            .map(|limit| limit.0.to_u64().unwrap())
+    }
+
+    pub fn reserved_cycles_limit(&self) -> Option<u128> {
+        self.reserved_cycles_limit
+            .as_ref()
+            .map(|limit| limit.0.to_u128().unwrap())
+    }
 }
 
 impl Payload<'_> for DefiniteCanisterSettingsArgs {}
@@ -199,7 +231,10 @@ impl Payload<'_> for CanisterStatusResult {}
 ///     controller: principal;
 ///     memory_size: nat;
 ///     cycles: nat;
+///     reserved_cycles: opt nat;
 ///     idle_cycles_burned_per_day: nat;
+///     version: opt nat64;
+///     query_stats: opt query_stats;
 /// })`
 #[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
 pub struct CanisterStatusResultV2 {
@@ -213,6 +248,65 @@ pub struct CanisterStatusResultV2 {
     balance: Vec<(Vec<u8>, candid::Nat)>,
     freezing_threshold: candid::Nat,
     idle_cycles_burned_per_day: candid::Nat,
+    // Optional for compatibility with status records that predate it.
+    version: Option<u64>,
+    // Optional for compatibility with status records that predate it.
+    reserved_cycles: Option<candid::Nat>,
+    // Optional for compatibility with status records that predate it.
+    query_stats: Option<QueryStatsResponse>,
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     num_calls_total: nat;
+///     num_instructions_total: nat;
+///     request_payload_bytes_total: nat;
+///     response_payload_bytes_total: nat;
+/// })`
+#[derive(Clone, CandidType, Debug, Deserialize, Eq, PartialEq)]
+pub struct QueryStatsResponse {
+    num_calls_total: candid::Nat,
+    num_instructions_total: candid::Nat,
+    request_payload_bytes_total: candid::Nat,
+    response_payload_bytes_total: candid::Nat,
+}
+
+impl QueryStatsResponse {
+    pub fn new(
+        num_calls_total: u128,
+        num_instructions_total: u128,
+        request_payload_bytes_total: u128,
+        response_payload_bytes_total: u128,
+    ) -> Self {
+        Self {
+            num_calls_total: candid::Nat::from(num_calls_total),
+            num_instructions_total: candid::Nat::from(num_instructions_total),
+            request_payload_bytes_total: candid::Nat::from(request_payload_bytes_total),
+            response_payload_bytes_total: candid::Nat::from(response_payload_bytes_total),
+        }
+    }
+
+    pub fn num_calls_total(&self) -> u128 {
+        self.num_calls_total.0.to_u128().unwrap()
+    }
+
+    pub fn num_instructions_total(&self) -> u128 {
+        self.num_instructions_total.0.to_u128().unwrap()
+    }
+
+    pub fn request_payload_bytes_total(&self) -> u128 {
+        self.request_payload_bytes_total.0.to_u128().unwrap()
+    }
+
+    pub fn response_payload_bytes_total(&self) -> u128 {
+        self.response_payload_bytes_total.0.to_u128().unwrap()
+    }
+}
+
+impl Default for QueryStatsResponse {
+    fn default() -> Self {
+        Self::new(0, 0, 0, 0)
+    }
 }
 
 impl CanisterStatusResultV2 {
@@ -228,6 +322,11 @@ impl CanisterStatusResultV2 {
         memory_allocation: Option<u64>,
         freezing_threshold: u64,
         idle_cycles_burned_per_day: u128,
+        version: u64,
+        wasm_memory_limit: Option<u64>,
+        reserved_cycles: u128,
+        reserved_cycles_limit: Option<u128>,
+        query_stats: QueryStatsResponse,
     ) -> Self {
         Self {
             status,
@@ -244,9 +343,14 @@ impl CanisterStatusResultV2 {
                 compute_allocation,
                 memory_allocation,
                 freezing_threshold,
+                wasm_memory_limit,
+                reserved_cycles_limit,
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
+            version: Some(version),
+            reserved_cycles: Some(candid::Nat::from(reserved_cycles)),
+            query_stats: Some(query_stats),
         }
     }
 
@@ -281,6 +385,28 @@ impl CanisterStatusResultV2 {
     pub fn idle_cycles_burned_per_day(&self) -> u128 {
         self.idle_cycles_burned_per_day.0.to_u128().unwrap()
     }
+
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
+
+    pub fn wasm_memory_limit(&self) -> Option<u64> {
+        self.settings.wasm_memory_limit()
+    }
+
+    pub fn reserved_cycles(&self) -> Option<u128> {
+        self.reserved_cycles
+            .as_ref()
+            .map(|cycles| cycles.0.to_u128().unwrap())
+    }
+
+    pub fn reserved_cycles_limit(&self) -> Option<u128> {
+        self.settings.reserved_cycles_limit()
+    }
+
+    pub fn query_stats(&self) -> Option<QueryStatsResponse> {
+        self.query_stats.clone()
+    }
 }
 
 /// Indicates whether the canister is running, stopping, or stopped.
@@ -344,6 +470,42 @@ impl CanisterInstallMode {
     }
 }
 
+impl From<CanisterInstallMode> for pb_canister_state_bits::CanisterInstallMode {
+    fn from(item: CanisterInstallMode) -> Self {
+        match item {
+            CanisterInstallMode::Install => pb_canister_state_bits::CanisterInstallMode::Install,
+            CanisterInstallMode::Reinstall => {
+                pb_canister_state_bits::CanisterInstallMode::Reinstall
+            }
+            CanisterInstallMode::Upgrade => pb_canister_state_bits::CanisterInstallMode::Upgrade,
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterInstallMode> for CanisterInstallMode {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_canister_state_bits::CanisterInstallMode) -> Result<Self, Self::Error> {
+        match item {
+            pb_canister_state_bits::CanisterInstallMode::Install => {
+                Ok(CanisterInstallMode::Install)
+            }
+            pb_canister_state_bits::CanisterInstallMode::Reinstall => {
+                Ok(CanisterInstallMode::Reinstall)
+            }
+            pb_canister_state_bits::CanisterInstallMode::Upgrade => {
+                Ok(CanisterInstallMode::Upgrade)
+            }
+            pb_canister_state_bits::CanisterInstallMode::Unspecified => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "CanisterInstallMode",
+                    err: format!("Unable to convert {:?} to a CanisterInstallMode", item),
+                })
+            }
+        }
+    }
+}
+
 /// A type to represent an error that can occur when installing a canister.
 #[derive(Debug)]
 pub struct CanisterInstallModeError(pub String);
@@ -501,6 +663,9 @@ impl Payload<'_> for UpdateSettingsArgs {}
 ///     controllers: opt vec principal;
 ///     compute_allocation: opt nat;
 ///     memory_allocation: opt nat;
+///     log_visibility: opt log_visibility;
+///     wasm_memory_limit: opt nat;
+///     reserved_cycles_limit: opt nat;
 /// })`
 #[derive(Default, Clone, CandidType, Deserialize, Debug)]
 pub struct CanisterSettingsArgs {
@@ -509,6 +674,9 @@ pub struct CanisterSettingsArgs {
     pub compute_allocation: Option<candid::Nat>,
     pub memory_allocation: Option<candid::Nat>,
     pub freezing_threshold: Option<candid::Nat>,
+    pub log_visibility: Option<LogVisibility>,
+    pub wasm_memory_limit: Option<candid::Nat>,
+    pub reserved_cycles_limit: Option<candid::Nat>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -527,10 +695,725 @@ impl CanisterSettingsArgs {
             compute_allocation: compute_allocation.map(candid::Nat::from),
             memory_allocation: memory_allocation.map(candid::Nat::from),
             freezing_threshold: freezing_threshold.map(candid::Nat::from),
+            log_visibility: None,
+            wasm_memory_limit: None,
+            reserved_cycles_limit: None,
+        }
+    }
+}
+
+/// Log visibility for a canister.
+/// ```text
+/// variant {
+///    controllers;
+///    public;
+/// }
+/// ```
+#[derive(CandidType, Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogVisibility {
+    #[serde(rename = "controllers")]
+    Controllers,
+    #[serde(rename = "public")]
+    Public,
+}
+
+impl Default for LogVisibility {
+    fn default() -> Self {
+        LogVisibility::Controllers
+    }
+}
+
+impl From<LogVisibility> for pb_canister_state_bits::LogVisibility {
+    fn from(item: LogVisibility) -> Self {
+        match item {
+            LogVisibility::Controllers => pb_canister_state_bits::LogVisibility::Controllers,
+            LogVisibility::Public => pb_canister_state_bits::LogVisibility::Public,
         }
     }
 }
 
+impl TryFrom<pb_canister_state_bits::LogVisibility> for LogVisibility {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_canister_state_bits::LogVisibility) -> Result<Self, Self::Error> {
+        match item {
+            pb_canister_state_bits::LogVisibility::Controllers => Ok(LogVisibility::Controllers),
+            pb_canister_state_bits::LogVisibility::Public => Ok(LogVisibility::Public),
+            pb_canister_state_bits::LogVisibility::Unspecified => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "LogVisibility",
+                    err: format!("Unable to convert {:?} to a LogVisibility", item),
+                })
+            }
+        }
+    }
+}
+
+/// A single record in a canister log.
+/// ```text
+/// record {
+///     idx: nat64;
+///     timestamp_nanos: nat64;
+///     content: blob;
+/// }
+/// ```
+#[derive(Default, Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterLogRecord {
+    pub idx: u64,
+    pub timestamp_nanos: u64,
+    #[serde(with = "serde_bytes")]
+    pub content: Vec<u8>,
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+/// })`
+#[derive(CandidType, Deserialize, Debug)]
+pub struct FetchCanisterLogsRequest {
+    pub canister_id: PrincipalId,
+}
+
+impl Payload<'_> for FetchCanisterLogsRequest {}
+
+impl FetchCanisterLogsRequest {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::new(self.canister_id).unwrap()
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_log_records: vec canister_log_record;
+/// })`
+#[derive(Default, Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct FetchCanisterLogsResponse {
+    pub canister_log_records: Vec<CanisterLogRecord>,
+}
+
+impl Payload<'_> for FetchCanisterLogsResponse {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     replace_snapshot: opt blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct TakeCanisterSnapshotArgs {
+    pub canister_id: PrincipalId,
+    pub replace_snapshot: Option<serde_bytes::ByteBuf>,
+}
+
+impl Payload<'_> for TakeCanisterSnapshotArgs {}
+
+impl TakeCanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, replace_snapshot: Option<Vec<u8>>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            replace_snapshot: replace_snapshot.map(serde_bytes::ByteBuf::from),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn replace_snapshot(&self) -> Option<&[u8]> {
+        self.replace_snapshot.as_ref().map(|id| id.as_slice())
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: blob;
+/// })`
+///
+/// Used as the argument of both `load_canister_snapshot` and
+/// `delete_canister_snapshot`.
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterSnapshotArgs {
+    pub canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    pub snapshot_id: Vec<u8>,
+}
+
+impl Payload<'_> for CanisterSnapshotArgs {}
+
+impl CanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, snapshot_id: Vec<u8>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            snapshot_id,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn get_snapshot_id(&self) -> &[u8] {
+        &self.snapshot_id
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     id: blob;
+///     taken_at_timestamp: nat64;
+///     total_size: nat64;
+/// })`
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterSnapshotResponse {
+    #[serde(with = "serde_bytes")]
+    pub id: Vec<u8>,
+    pub taken_at_timestamp: u64,
+    pub total_size: u64,
+}
+
+impl Payload<'_> for CanisterSnapshotResponse {}
+
+/// Struct used for encoding/decoding the result of `list_canister_snapshots`:
+/// `(vec canister_snapshot)`.
+#[derive(Default, Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct ListCanisterSnapshotsResponse(pub Vec<CanisterSnapshotResponse>);
+
+impl Payload<'_> for ListCanisterSnapshotsResponse {}
+
+/// `CandidType` for `CanisterChangeFromUser`
+/// ```text
+/// record {
+///    user_id : principal;
+/// }
+/// ```
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterChangeFromUser {
+    user_id: PrincipalId,
+}
+
+/// `CandidType` for `CanisterChangeFromCanister`
+/// ```text
+/// record {
+///    canister_id : principal;
+///    canister_version : opt nat64;
+/// }
+/// ```
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterChangeFromCanister {
+    canister_id: PrincipalId,
+    canister_version: Option<u64>,
+}
+
+/// `CandidType` for `CanisterChangeOrigin`
+/// ```text
+/// variant {
+///   from_user : record {
+///     user_id : principal;
+///   };
+///   from_canister : record {
+///     canister_id : principal;
+///     canister_version : opt nat64;
+///   };
+/// }
+/// ```
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum CanisterChangeOrigin {
+    #[serde(rename = "from_user")]
+    CanisterChangeFromUser(CanisterChangeFromUser),
+    #[serde(rename = "from_canister")]
+    CanisterChangeFromCanister(CanisterChangeFromCanister),
+}
+
+impl CanisterChangeOrigin {
+    pub fn from_user(user_id: PrincipalId) -> CanisterChangeOrigin {
+        CanisterChangeOrigin::CanisterChangeFromUser(CanisterChangeFromUser { user_id })
+    }
+
+    pub fn from_canister(
+        canister_id: PrincipalId,
+        canister_version: Option<u64>,
+    ) -> CanisterChangeOrigin {
+        CanisterChangeOrigin::CanisterChangeFromCanister(CanisterChangeFromCanister {
+            canister_id,
+            canister_version,
+        })
+    }
+
+    /// Returns the principal (user or canister) that triggered the change.
+    pub fn origin(&self) -> PrincipalId {
+        match self {
+            CanisterChangeOrigin::CanisterChangeFromUser(origin) => origin.user_id,
+            CanisterChangeOrigin::CanisterChangeFromCanister(origin) => origin.canister_id,
+        }
+    }
+}
+
+/// `CandidType` for `CanisterCreationRecord`
+/// ```text
+/// record {
+///   controllers : vec principal;
+/// }
+/// ```
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterCreationRecord {
+    controllers: Vec<PrincipalId>,
+}
+
+impl CanisterCreationRecord {
+    pub fn controllers(&self) -> &[PrincipalId] {
+        &self.controllers
+    }
+}
+
+/// `CandidType` for `CanisterCodeDeploymentRecord`
+/// ```text
+/// record {
+///   mode : variant { install; reinstall; upgrade };
+///   module_hash : blob;
+/// }
+/// ```
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterCodeDeploymentRecord {
+    mode: CanisterInstallMode,
+    module_hash: [u8; 32],
+}
+
+impl CanisterCodeDeploymentRecord {
+    pub fn mode(&self) -> CanisterInstallMode {
+        self.mode
+    }
+
+    pub fn module_hash(&self) -> [u8; 32] {
+        self.module_hash
+    }
+}
+
+/// `CandidType` for `CanisterControllersChangeRecord`
+/// ```text
+/// record {
+///   controllers : vec principal;
+/// }
+/// ```
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterControllersChangeRecord {
+    controllers: Vec<PrincipalId>,
+}
+
+impl CanisterControllersChangeRecord {
+    pub fn controllers(&self) -> &[PrincipalId] {
+        &self.controllers
+    }
+}
+
+/// `CandidType` for `CanisterLoadSnapshotRecord`
+/// ```text
+/// record {
+///   snapshot_id : blob;
+///   taken_at_timestamp : nat64;
+/// }
+/// ```
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterLoadSnapshotRecord {
+    snapshot_id: Vec<u8>,
+    taken_at_timestamp: u64,
+}
+
+impl CanisterLoadSnapshotRecord {
+    pub fn snapshot_id(&self) -> &[u8] {
+        &self.snapshot_id
+    }
+
+    pub fn taken_at_timestamp(&self) -> u64 {
+        self.taken_at_timestamp
+    }
+}
+
+/// `CandidType` for `CanisterChangeDetails`
+/// ```text
+/// variant {
+///   creation : record {
+///     controllers : vec principal;
+///   };
+///   code_uninstall;
+///   code_deployment : record {
+///     mode : variant { install; reinstall; upgrade };
+///     module_hash : blob;
+///   };
+///   controllers_change : record {
+///     controllers : vec principal;
+///   };
+///   load_snapshot : record {
+///     snapshot_id : blob;
+///     taken_at_timestamp : nat64;
+///   };
+/// }
+/// ```
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum CanisterChangeDetails {
+    #[serde(rename = "creation")]
+    CanisterCreation(CanisterCreationRecord),
+    #[serde(rename = "code_uninstall")]
+    CanisterCodeUninstall,
+    #[serde(rename = "code_deployment")]
+    CanisterCodeDeployment(CanisterCodeDeploymentRecord),
+    #[serde(rename = "controllers_change")]
+    CanisterControllersChange(CanisterControllersChangeRecord),
+    #[serde(rename = "load_snapshot")]
+    CanisterLoadSnapshot(CanisterLoadSnapshotRecord),
+}
+
+impl CanisterChangeDetails {
+    pub fn canister_creation(controllers: Vec<PrincipalId>) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterCreation(CanisterCreationRecord { controllers })
+    }
+
+    pub fn code_uninstall() -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterCodeUninstall
+    }
+
+    pub fn code_deployment(
+        mode: CanisterInstallMode,
+        module_hash: [u8; 32],
+    ) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterCodeDeployment(CanisterCodeDeploymentRecord {
+            mode,
+            module_hash,
+        })
+    }
+
+    pub fn controllers_change(controllers: Vec<PrincipalId>) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterControllersChange(CanisterControllersChangeRecord {
+            controllers,
+        })
+    }
+
+    pub fn load_snapshot(snapshot_id: Vec<u8>, taken_at_timestamp: u64) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterLoadSnapshot(CanisterLoadSnapshotRecord {
+            snapshot_id,
+            taken_at_timestamp,
+        })
+    }
+}
+
+/// `CandidType` for `CanisterChange`
+/// ```text
+/// record {
+///   timestamp_nanos : nat64;
+///   canister_version : nat64;
+///   origin : change_origin;
+///   details : change_details;
+/// }
+/// ```
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterChange {
+    timestamp_nanos: u64,
+    canister_version: u64,
+    origin: CanisterChangeOrigin,
+    details: CanisterChangeDetails,
+}
+
+impl CanisterChange {
+    pub fn new(
+        timestamp_nanos: u64,
+        canister_version: u64,
+        origin: CanisterChangeOrigin,
+        details: CanisterChangeDetails,
+    ) -> CanisterChange {
+        CanisterChange {
+            timestamp_nanos,
+            canister_version,
+            origin,
+            details,
+        }
+    }
+
+    pub fn timestamp_nanos(&self) -> u64 {
+        self.timestamp_nanos
+    }
+
+    pub fn canister_version(&self) -> u64 {
+        self.canister_version
+    }
+
+    pub fn origin(&self) -> &CanisterChangeOrigin {
+        &self.origin
+    }
+
+    pub fn details(&self) -> &CanisterChangeDetails {
+        &self.details
+    }
+}
+
+fn principals_to_proto(principals: &[PrincipalId]) -> Vec<ic_protobuf::types::v1::PrincipalId> {
+    principals.iter().map(|p| (*p).into()).collect()
+}
+
+fn principals_from_proto(
+    principals: Vec<ic_protobuf::types::v1::PrincipalId>,
+) -> Result<Vec<PrincipalId>, ProxyDecodeError> {
+    principals
+        .into_iter()
+        .map(|p| PrincipalId::try_from(p).map_err(ProxyDecodeError::from))
+        .collect()
+}
+
+impl From<&CanisterChangeOrigin> for pb_canister_state_bits::canister_change::ChangeOrigin {
+    fn from(item: &CanisterChangeOrigin) -> Self {
+        match item {
+            CanisterChangeOrigin::CanisterChangeFromUser(change_from_user) => {
+                pb_canister_state_bits::canister_change::ChangeOrigin::CanisterChangeFromUser(
+                    pb_canister_state_bits::CanisterChangeFromUser {
+                        user_id: Some(change_from_user.user_id.into()),
+                    },
+                )
+            }
+            CanisterChangeOrigin::CanisterChangeFromCanister(change_from_canister) => {
+                pb_canister_state_bits::canister_change::ChangeOrigin::CanisterChangeFromCanister(
+                    pb_canister_state_bits::CanisterChangeFromCanister {
+                        canister_id: Some(change_from_canister.canister_id.into()),
+                        canister_version: change_from_canister.canister_version,
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::canister_change::ChangeOrigin> for CanisterChangeOrigin {
+    type Error = ProxyDecodeError;
+
+    fn try_from(
+        value: pb_canister_state_bits::canister_change::ChangeOrigin,
+    ) -> Result<Self, Self::Error> {
+        match value {
+            pb_canister_state_bits::canister_change::ChangeOrigin::CanisterChangeFromUser(
+                change_from_user,
+            ) => Ok(CanisterChangeOrigin::from_user(try_from_option_field(
+                change_from_user.user_id,
+                "CanisterChangeFromUser::user_id",
+            )?)),
+            pb_canister_state_bits::canister_change::ChangeOrigin::CanisterChangeFromCanister(
+                change_from_canister,
+            ) => Ok(CanisterChangeOrigin::from_canister(
+                try_from_option_field(
+                    change_from_canister.canister_id,
+                    "CanisterChangeFromCanister::canister_id",
+                )?,
+                change_from_canister.canister_version,
+            )),
+        }
+    }
+}
+
+impl From<&CanisterChangeDetails> for pb_canister_state_bits::canister_change::ChangeDetails {
+    fn from(item: &CanisterChangeDetails) -> Self {
+        match item {
+            CanisterChangeDetails::CanisterCreation(canister_creation) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterCreation(
+                    pb_canister_state_bits::CanisterCreation {
+                        controllers: principals_to_proto(&canister_creation.controllers),
+                    },
+                )
+            }
+            CanisterChangeDetails::CanisterCodeUninstall => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterCodeUninstall(
+                    pb_canister_state_bits::CanisterCodeUninstall {},
+                )
+            }
+            CanisterChangeDetails::CanisterCodeDeployment(code_deployment) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterCodeDeployment(
+                    pb_canister_state_bits::CanisterCodeDeployment {
+                        mode: pb_canister_state_bits::CanisterInstallMode::from(
+                            code_deployment.mode,
+                        )
+                        .into(),
+                        module_hash: code_deployment.module_hash.to_vec(),
+                    },
+                )
+            }
+            CanisterChangeDetails::CanisterControllersChange(controllers_change) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterControllersChange(
+                    pb_canister_state_bits::CanisterControllersChange {
+                        controllers: principals_to_proto(&controllers_change.controllers),
+                    },
+                )
+            }
+            CanisterChangeDetails::CanisterLoadSnapshot(load_snapshot) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterLoadSnapshot(
+                    pb_canister_state_bits::CanisterLoadSnapshot {
+                        snapshot_id: load_snapshot.snapshot_id.clone(),
+                        taken_at_timestamp: load_snapshot.taken_at_timestamp,
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::canister_change::ChangeDetails> for CanisterChangeDetails {
+    type Error = ProxyDecodeError;
+
+    fn try_from(
+        item: pb_canister_state_bits::canister_change::ChangeDetails,
+    ) -> Result<Self, Self::Error> {
+        match item {
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterCreation(
+                canister_creation,
+            ) => Ok(CanisterChangeDetails::canister_creation(
+                principals_from_proto(canister_creation.controllers)?,
+            )),
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterCodeUninstall(_) => {
+                Ok(CanisterChangeDetails::code_uninstall())
+            }
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterCodeDeployment(
+                code_deployment,
+            ) => {
+                let mode =
+                    pb_canister_state_bits::CanisterInstallMode::from_i32(code_deployment.mode)
+                        .ok_or(ProxyDecodeError::ValueOutOfRange {
+                            typ: "CanisterInstallMode",
+                            err: format!(
+                                "Unexpected value for canister install mode {}",
+                                code_deployment.mode
+                            ),
+                        })?;
+                let module_hash: [u8; 32] =
+                    code_deployment
+                        .module_hash
+                        .try_into()
+                        .map_err(|hash: Vec<u8>| ProxyDecodeError::ValueOutOfRange {
+                            typ: "CanisterCodeDeployment::module_hash",
+                            err: format!("Expected a 32-byte hash, got {} bytes", hash.len()),
+                        })?;
+                Ok(CanisterChangeDetails::code_deployment(
+                    CanisterInstallMode::try_from(mode)?,
+                    module_hash,
+                ))
+            }
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterControllersChange(
+                controllers_change,
+            ) => Ok(CanisterChangeDetails::controllers_change(
+                principals_from_proto(controllers_change.controllers)?,
+            )),
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterLoadSnapshot(
+                load_snapshot,
+            ) => Ok(CanisterChangeDetails::load_snapshot(
+                load_snapshot.snapshot_id,
+                load_snapshot.taken_at_timestamp,
+            )),
+        }
+    }
+}
+
+impl From<&CanisterChange> for pb_canister_state_bits::CanisterChange {
+    fn from(item: &CanisterChange) -> Self {
+        Self {
+            timestamp_nanos: item.timestamp_nanos,
+            canister_version: item.canister_version,
+            change_origin: Some((&item.origin).into()),
+            change_details: Some((&item.details).into()),
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterChange> for CanisterChange {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_canister_state_bits::CanisterChange) -> Result<Self, Self::Error> {
+        Ok(CanisterChange {
+            timestamp_nanos: item.timestamp_nanos,
+            canister_version: item.canister_version,
+            origin: try_from_option_field(item.change_origin, "CanisterChange::change_origin")?,
+            details: try_from_option_field(item.change_details, "CanisterChange::change_details")?,
+        })
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     num_requested_changes: opt nat64;
+/// })`
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterInfoRequest {
+    canister_id: PrincipalId,
+    num_requested_changes: Option<u64>,
+}
+
+impl Payload<'_> for CanisterInfoRequest {}
+
+impl CanisterInfoRequest {
+    pub fn new(canister_id: CanisterId, num_requested_changes: Option<u64>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            num_requested_changes,
+        }
+    }
+
+    pub fn canister_id(&self) -> CanisterId {
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn num_requested_changes(&self) -> Option<u64> {
+        self.num_requested_changes
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     total_num_changes: nat64;
+///     recent_changes: vec change;
+///     module_hash: opt blob;
+///     controllers: vec principal;
+/// })`
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterInfoResponse {
+    total_num_changes: u64,
+    recent_changes: Vec<CanisterChange>,
+    module_hash: Option<Vec<u8>>,
+    controllers: Vec<PrincipalId>,
+}
+
+impl Payload<'_> for CanisterInfoResponse {}
+
+impl CanisterInfoResponse {
+    pub fn new(
+        total_num_changes: u64,
+        recent_changes: Vec<CanisterChange>,
+        module_hash: Option<Vec<u8>>,
+        controllers: Vec<PrincipalId>,
+    ) -> Self {
+        Self {
+            total_num_changes,
+            recent_changes,
+            module_hash,
+            controllers,
+        }
+    }
+
+    pub fn total_num_changes(&self) -> u64 {
+        self.total_num_changes
+    }
+
+    pub fn changes(&self) -> &[CanisterChange] {
+        &self.recent_changes
+    }
+
+    pub fn module_hash(&self) -> Option<&[u8]> {
+        self.module_hash.as_deref()
+    }
+
+    pub fn controllers(&self) -> &[PrincipalId] {
+        &self.controllers
+    }
+}
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     settings : opt canister_settings;
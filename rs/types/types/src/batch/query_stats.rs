@@ -0,0 +1,211 @@
+use crate::{node_id_into_protobuf, node_id_try_from_protobuf, CountBytes, Height, NodeId};
+use ic_base_types::CanisterId;
+use ic_protobuf::{
+    proxy::{try_from_option_field, ProxyDecodeError},
+    state::canister_state_bits::v1 as pb_canister_state_bits,
+    types::v1 as pb,
+};
+use phantom_newtype::AmountOf;
+use serde::{Deserialize, Serialize};
+use std::convert::{TryFrom, TryInto};
+
+pub struct QueryStatsEpochTag;
+/// The epoch during which query statistics are collected. Statistics are
+/// only included in blocks after the epoch is over.
+pub type QueryStatsEpoch = AmountOf<QueryStatsEpochTag, u64>;
+
+/// Returns the epoch that the given height belongs to.
+pub fn epoch_from_height(height: Height, epoch_length: u64) -> QueryStatsEpoch {
+    QueryStatsEpoch::from(height.get() / epoch_length.max(1))
+}
+
+/// Statistics on the query calls executed by a canister on a single replica.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct QueryStats {
+    pub num_calls: u32,
+    pub num_instructions: u64,
+    pub ingress_payload_size: u64,
+    pub egress_payload_size: u64,
+}
+
+impl QueryStats {
+    pub fn saturating_accumulate(&mut self, rhs: &Self) {
+        self.num_calls = self.num_calls.saturating_add(rhs.num_calls);
+        self.num_instructions = self.num_instructions.saturating_add(rhs.num_instructions);
+        self.ingress_payload_size = self
+            .ingress_payload_size
+            .saturating_add(rhs.ingress_payload_size);
+        self.egress_payload_size = self
+            .egress_payload_size
+            .saturating_add(rhs.egress_payload_size);
+    }
+}
+
+/// The query statistics of a canister, summed over all replicas of the subnet
+/// and all aggregated epochs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TotalQueryStats {
+    pub num_calls: u128,
+    pub num_instructions: u128,
+    pub ingress_payload_size: u128,
+    pub egress_payload_size: u128,
+}
+
+impl TotalQueryStats {
+    pub fn saturating_accumulate(&mut self, rhs: &QueryStats) {
+        self.num_calls = self.num_calls.saturating_add(rhs.num_calls as u128);
+        self.num_instructions = self
+            .num_instructions
+            .saturating_add(rhs.num_instructions as u128);
+        self.ingress_payload_size = self
+            .ingress_payload_size
+            .saturating_add(rhs.ingress_payload_size as u128);
+        self.egress_payload_size = self
+            .egress_payload_size
+            .saturating_add(rhs.egress_payload_size as u128);
+    }
+}
+
+impl From<&TotalQueryStats> for pb_canister_state_bits::TotalQueryStats {
+    fn from(stats: &TotalQueryStats) -> Self {
+        Self {
+            num_calls: stats.num_calls.to_le_bytes().to_vec(),
+            num_instructions: stats.num_instructions.to_le_bytes().to_vec(),
+            ingress_payload_size: stats.ingress_payload_size.to_le_bytes().to_vec(),
+            egress_payload_size: stats.egress_payload_size.to_le_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::TotalQueryStats> for TotalQueryStats {
+    type Error = ProxyDecodeError;
+
+    fn try_from(stats: pb_canister_state_bits::TotalQueryStats) -> Result<Self, Self::Error> {
+        fn decode_u128(bytes: Vec<u8>, field: &str) -> Result<u128, ProxyDecodeError> {
+            let bytes: [u8; 16] = bytes.try_into().map_err(|bytes: Vec<u8>| {
+                ProxyDecodeError::Other(format!(
+                    "TotalQueryStats::{}: expected 16 bytes, got {}",
+                    field,
+                    bytes.len()
+                ))
+            })?;
+            Ok(u128::from_le_bytes(bytes))
+        }
+        Ok(Self {
+            num_calls: decode_u128(stats.num_calls, "num_calls")?,
+            num_instructions: decode_u128(stats.num_instructions, "num_instructions")?,
+            ingress_payload_size: decode_u128(stats.ingress_payload_size, "ingress_payload_size")?,
+            egress_payload_size: decode_u128(stats.egress_payload_size, "egress_payload_size")?,
+        })
+    }
+}
+
+/// The query statistics of a canister.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CanisterQueryStats {
+    pub canister_id: CanisterId,
+    pub stats: QueryStats,
+}
+
+/// Payload that contains the query statistics that the block maker collected
+/// locally during `epoch`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct QueryStatsPayload {
+    pub proposer: NodeId,
+    pub epoch: QueryStatsEpoch,
+    pub canister_stats: Vec<CanisterQueryStats>,
+}
+
+impl QueryStatsPayload {
+    /// Returns true, if this payload contains no statistics.
+    pub fn is_empty(&self) -> bool {
+        self.canister_stats.is_empty()
+    }
+}
+
+impl CountBytes for QueryStatsPayload {
+    fn count_bytes(&self) -> usize {
+        std::mem::size_of::<NodeId>()
+            + std::mem::size_of::<QueryStatsEpoch>()
+            + self.canister_stats.len() * std::mem::size_of::<CanisterQueryStats>()
+    }
+}
+
+impl From<&QueryStatsPayload> for pb::QueryStatsPayload {
+    fn from(payload: &QueryStatsPayload) -> Self {
+        Self {
+            proposer: Some(node_id_into_protobuf(payload.proposer)),
+            epoch: payload.epoch.get(),
+            canister_stats: payload
+                .canister_stats
+                .iter()
+                .map(|canister_stats| pb::CanisterQueryStats {
+                    canister_id: Some(pb::CanisterId::from(canister_stats.canister_id)),
+                    num_calls: canister_stats.stats.num_calls,
+                    num_instructions: canister_stats.stats.num_instructions,
+                    ingress_payload_size: canister_stats.stats.ingress_payload_size,
+                    egress_payload_size: canister_stats.stats.egress_payload_size,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::QueryStatsPayload> for QueryStatsPayload {
+    type Error = ProxyDecodeError;
+
+    fn try_from(payload: pb::QueryStatsPayload) -> Result<Self, Self::Error> {
+        let proposer = node_id_try_from_protobuf(try_from_option_field(
+            payload.proposer,
+            "QueryStatsPayload::proposer",
+        )?)?;
+        let canister_stats = payload
+            .canister_stats
+            .into_iter()
+            .map(|canister_stats| {
+                Ok(CanisterQueryStats {
+                    canister_id: try_from_option_field(
+                        canister_stats.canister_id,
+                        "CanisterQueryStats::canister_id",
+                    )?,
+                    stats: QueryStats {
+                        num_calls: canister_stats.num_calls,
+                        num_instructions: canister_stats.num_instructions,
+                        ingress_payload_size: canister_stats.ingress_payload_size,
+                        egress_payload_size: canister_stats.egress_payload_size,
+                    },
+                })
+            })
+            .collect::<Result<_, ProxyDecodeError>>()?;
+        Ok(Self {
+            proposer,
+            epoch: payload.epoch.into(),
+            canister_stats,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_base_types::PrincipalId;
+
+    #[test]
+    fn query_stats_payload_round_trips_through_protobuf() {
+        let payload = QueryStatsPayload {
+            proposer: NodeId::from(PrincipalId::new_node_test_id(1)),
+            epoch: QueryStatsEpoch::from(3),
+            canister_stats: vec![CanisterQueryStats {
+                canister_id: CanisterId::from_u64(7),
+                stats: QueryStats {
+                    num_calls: 1,
+                    num_instructions: 2,
+                    ingress_payload_size: 3,
+                    egress_payload_size: 4,
+                },
+            }],
+        };
+        let pb_payload = pb::QueryStatsPayload::from(&payload);
+        assert_eq!(payload, QueryStatsPayload::try_from(pb_payload).unwrap());
+    }
+}
@@ -19,10 +19,11 @@
 
 use crate::messages::MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64;
 use crate::{
+    batch::MAX_CANISTER_HTTP_PAYLOAD_SIZE,
     crypto::{CryptoHashOf, Signed},
-    messages::{CallbackId, RejectContext, Request},
+    messages::{CallbackId, Payload, RejectContext, Request, Response},
     signature::*,
-    CanisterId, CountBytes, RegistryVersion, Time,
+    CanisterId, CountBytes, Cycles, RegistryVersion, Time,
 };
 use ic_base_types::{NumBytes, PrincipalId};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
@@ -48,6 +49,16 @@ pub const CANISTER_HTTP_TIMEOUT_INTERVAL: Duration = Duration::from_secs(60);
 /// could become too large.
 pub const CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK: usize = 500;
 
+/// Maximum size of a (transformed) canister http response.
+///
+/// Substracts 50Kb from the payload size for consensus overhead (CallbackID, Time, CanisterId,
+/// CanisterHttpResponseProof).
+pub const CANISTER_HTTP_RESPONSE_LIMIT: usize = MAX_CANISTER_HTTP_PAYLOAD_SIZE - 50 * 1024;
+
+/// Hard limit that the canister http adapter enforces on the body of the response, if the
+/// request does not specify `max_response_bytes`.
+pub const CANISTER_HTTP_ADAPTER_MAX_RESPONSE_SIZE: NumBytes = NumBytes::new(2 * 1024 * 1024);
+
 pub type CanisterHttpRequestId = CallbackId;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -263,6 +274,26 @@ impl CountBytes for CanisterHttpResponse {
     }
 }
 
+/// Turns a response on which consensus was reached into the response that is
+/// delivered to execution.
+impl From<&CanisterHttpResponse> for Response {
+    fn from(response: &CanisterHttpResponse) -> Response {
+        Response {
+            // NOTE originator and respondent are not needed for these types of calls
+            originator: CanisterId::ic_00(),
+            respondent: CanisterId::ic_00(),
+            originator_reply_callback: response.id,
+            refund: Cycles::zero(),
+            response_payload: match &response.content {
+                CanisterHttpResponseContent::Success(data) => Payload::Data(data.clone()),
+                CanisterHttpResponseContent::Reject(canister_http_reject) => {
+                    Payload::Reject(canister_http_reject.into())
+                }
+            },
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CanisterHttpResponseContent {
     Success(Vec<u8>),
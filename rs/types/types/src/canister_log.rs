@@ -0,0 +1,143 @@
+//! Defines the canister log: a bounded buffer of messages produced by a
+//! canister through `ic0.debug_print` and traps.
+
+use ic_ic00_types::CanisterLogRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// The maximum total size of all records kept in a canister log.
+pub const MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE: usize = 4 * 1024;
+
+/// Size of the fixed fields (`idx` and `timestamp_nanos`) of a log record.
+const RECORD_OVERHEAD_SIZE: usize = 2 * std::mem::size_of::<u64>();
+
+fn record_size(record: &CanisterLogRecord) -> usize {
+    RECORD_OVERHEAD_SIZE + record.content.len()
+}
+
+/// A ring buffer of log records. When adding a record would exceed
+/// `MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE`, the oldest records are dropped.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanisterLog {
+    next_idx: u64,
+    records: VecDeque<CanisterLogRecord>,
+    used_space: usize,
+}
+
+impl CanisterLog {
+    /// Creates a canister log from the given records, dropping the oldest
+    /// ones if they do not fit into the buffer.
+    pub fn new(next_idx: u64, records: Vec<CanisterLogRecord>) -> Self {
+        let mut log = Self::new_empty(next_idx);
+        for record in records {
+            log.push_record(record);
+        }
+        log
+    }
+
+    /// Creates an empty canister log whose first record will get `next_idx`.
+    pub fn new_empty(next_idx: u64) -> Self {
+        Self {
+            next_idx,
+            records: VecDeque::new(),
+            used_space: 0,
+        }
+    }
+
+    /// Returns the index that the next added record will get.
+    pub fn next_idx(&self) -> u64 {
+        self.next_idx
+    }
+
+    /// Returns the records of the log, oldest first.
+    pub fn records(&self) -> &VecDeque<CanisterLogRecord> {
+        &self.records
+    }
+
+    /// Returns the total size of the records in the log.
+    pub fn used_space(&self) -> usize {
+        self.used_space
+    }
+
+    /// Adds a new record with the given timestamp and content. Content that
+    /// does not fit into the buffer on its own is truncated.
+    pub fn add_record(&mut self, timestamp_nanos: u64, content: &[u8]) {
+        let max_content_size = MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE - RECORD_OVERHEAD_SIZE;
+        let content = &content[..content.len().min(max_content_size)];
+        let record = CanisterLogRecord {
+            idx: self.next_idx,
+            timestamp_nanos,
+            content: content.to_vec(),
+        };
+        self.push_record(record);
+    }
+
+    /// Moves all records of `delta` to the end of this log.
+    pub fn append_delta(&mut self, delta: &mut CanisterLog) {
+        for record in delta.records.drain(..) {
+            self.push_record(record);
+        }
+        delta.used_space = 0;
+        self.next_idx = self.next_idx.max(delta.next_idx);
+    }
+
+    fn push_record(&mut self, record: CanisterLogRecord) {
+        self.next_idx = self.next_idx.max(record.idx + 1);
+        self.used_space += record_size(&record);
+        self.records.push_back(record);
+        while self.used_space > MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE {
+            match self.records.pop_front() {
+                Some(oldest) => self.used_space -= record_size(&oldest),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_get_consecutive_indexes() {
+        let mut log = CanisterLog::new_empty(7);
+        log.add_record(1, b"a");
+        log.add_record(2, b"b");
+        let idxs: Vec<_> = log.records().iter().map(|r| r.idx).collect();
+        assert_eq!(idxs, vec![7, 8]);
+        assert_eq!(log.next_idx(), 9);
+    }
+
+    #[test]
+    fn oldest_records_are_dropped_when_buffer_is_full() {
+        let mut log = CanisterLog::default();
+        let content = vec![b'x'; 1000];
+        for i in 0..10 {
+            log.add_record(i, &content);
+        }
+        assert!(log.used_space() <= MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+        assert_eq!(log.records().back().unwrap().idx, 9);
+        assert_eq!(log.records().front().unwrap().idx, 6);
+        assert_eq!(log.next_idx(), 10);
+    }
+
+    #[test]
+    fn large_record_is_truncated() {
+        let mut log = CanisterLog::default();
+        log.add_record(0, &vec![b'x'; 2 * MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE]);
+        assert_eq!(log.records().len(), 1);
+        assert_eq!(log.used_space(), MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn append_delta_moves_records() {
+        let mut log = CanisterLog::default();
+        log.add_record(0, b"a");
+        let mut delta = CanisterLog::new_empty(log.next_idx());
+        delta.add_record(1, b"b");
+        log.append_delta(&mut delta);
+        assert!(delta.records().is_empty());
+        assert_eq!(log.records().len(), 2);
+        assert_eq!(log.next_idx(), 2);
+    }
+}
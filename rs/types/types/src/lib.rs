@@ -66,6 +66,7 @@
 pub mod artifact;
 pub mod batch;
 pub mod canister_http;
+pub mod canister_log;
 pub mod chunkable;
 pub mod consensus;
 pub mod crypto;
@@ -1,8 +1,9 @@
 use crate::{ingress::WasmResult, CanisterId, CountBytes, Cycles, Funds, NumBytes};
 use ic_error_types::{RejectCode, TryFromError, UserError};
 use ic_ic00_types::{
-    CanisterIdRecord, InstallCodeArgs, Method, Payload as _, ProvisionalTopUpCanisterArgs,
-    SetControllerArgs, UpdateSettingsArgs,
+    CanisterIdRecord, CanisterInfoRequest, CanisterSnapshotArgs, FetchCanisterLogsRequest,
+    InstallCodeArgs, Method, Payload as _, ProvisionalTopUpCanisterArgs, SetControllerArgs,
+    TakeCanisterSnapshotArgs, UpdateSettingsArgs,
 };
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -72,6 +73,7 @@ impl Request {
             | Ok(Method::DeleteCanister)
             | Ok(Method::UninstallCode)
             | Ok(Method::DepositCycles)
+            | Ok(Method::ListCanisterSnapshots)
             | Ok(Method::StopCanister) => match CanisterIdRecord::decode(&self.method_payload) {
                 Ok(record) => Some(record.get_canister_id()),
                 Err(_) => None,
@@ -88,6 +90,28 @@ impl Request {
                 Ok(record) => Some(record.get_canister_id()),
                 Err(_) => None,
             },
+            Ok(Method::FetchCanisterLogs) => {
+                match FetchCanisterLogsRequest::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::CanisterInfo) => match CanisterInfoRequest::decode(&self.method_payload) {
+                Ok(record) => Some(record.canister_id()),
+                Err(_) => None,
+            },
+            Ok(Method::TakeCanisterSnapshot) => {
+                match TakeCanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::LoadCanisterSnapshot) | Ok(Method::DeleteCanisterSnapshot) => {
+                match CanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
             Ok(Method::ProvisionalTopUpCanister) => {
                 match ProvisionalTopUpCanisterArgs::decode(&self.method_payload) {
                     Ok(record) => Some(record.get_canister_id()),
@@ -26,6 +26,13 @@ pub enum WasmMethod {
     /// execution.
     Query(String),
 
+    /// An exported composite query method along with its name.
+    ///
+    /// Unlike a query method, a composite query method can call query
+    /// methods of other canisters on the same subnet. It cannot be called
+    /// in replicated mode and its modifications are NOT persisted.
+    CompositeQuery(String),
+
     /// An exported system method. Unlike query or update method, there
     /// are a few fixed system methods as defined in `SystemMethod`.
     System(SystemMethod),
@@ -36,6 +43,7 @@ impl WasmMethod {
         match self {
             Self::Update(name) => name.to_string(),
             Self::Query(name) => name.to_string(),
+            Self::CompositeQuery(name) => name.to_string(),
             Self::System(system_method) => system_method.to_string(),
         }
     }
@@ -46,6 +54,7 @@ impl fmt::Display for WasmMethod {
         match self {
             Self::Update(name) => write!(f, "canister_update {}", name),
             Self::Query(name) => write!(f, "canister_query {}", name),
+            Self::CompositeQuery(name) => write!(f, "canister_composite_query {}", name),
             Self::System(system_method) => system_method.fmt(f),
         }
     }
@@ -63,6 +72,10 @@ impl TryFrom<String> for WasmMethod {
             // Take the part after the first space
             let parts: Vec<&str> = name.splitn(2, ' ').collect();
             Ok(WasmMethod::Query(parts[1].to_string()))
+        } else if name.starts_with("canister_composite_query ") {
+            // Take the part after the first space
+            let parts: Vec<&str> = name.splitn(2, ' ').collect();
+            Ok(WasmMethod::CompositeQuery(parts[1].to_string()))
         } else {
             match SystemMethod::try_from(name.as_ref()) {
                 Ok(system_method) => Ok(WasmMethod::System(system_method)),
@@ -83,6 +96,9 @@ impl From<&WasmMethod> for pb::WasmMethod {
             WasmMethod::Query(value) => Self {
                 wasm_method: Some(PbWasmMethod::Query(value.clone())),
             },
+            WasmMethod::CompositeQuery(value) => Self {
+                wasm_method: Some(PbWasmMethod::CompositeQuery(value.clone())),
+            },
             WasmMethod::System(value) => Self {
                 wasm_method: Some(PbWasmMethod::System(match value {
                     SystemMethod::CanisterStart => PbSystemMethod::CanisterStart,
@@ -91,6 +107,7 @@ impl From<&WasmMethod> for pb::WasmMethod {
                     SystemMethod::CanisterPostUpgrade => PbSystemMethod::CanisterPostUpgrade,
                     SystemMethod::CanisterInspectMessage => PbSystemMethod::CanisterInspectMessage,
                     SystemMethod::CanisterHeartbeat => PbSystemMethod::CanisterHeartbeat,
+                    SystemMethod::CanisterGlobalTimer => PbSystemMethod::CanisterGlobalTimer,
                     SystemMethod::Empty => PbSystemMethod::Empty,
                 } as i32)),
             },
@@ -107,6 +124,7 @@ impl TryFrom<pb::WasmMethod> for WasmMethod {
         match try_from_option_field(method.wasm_method, "WasmMethod::wasm_method")? {
             PbWasmMethod::Update(update) => Ok(Self::Update(update)),
             PbWasmMethod::Query(query) => Ok(Self::Query(query)),
+            PbWasmMethod::CompositeQuery(query) => Ok(Self::CompositeQuery(query)),
             PbWasmMethod::System(system) => {
                 let method =
                     PbSystemMethod::from_i32(system).unwrap_or(PbSystemMethod::Unspecified);
@@ -124,6 +142,7 @@ impl TryFrom<pb::WasmMethod> for WasmMethod {
                     PbSystemMethod::CanisterPostUpgrade => SystemMethod::CanisterPostUpgrade,
                     PbSystemMethod::CanisterInspectMessage => SystemMethod::CanisterInspectMessage,
                     PbSystemMethod::CanisterHeartbeat => SystemMethod::CanisterHeartbeat,
+                    PbSystemMethod::CanisterGlobalTimer => SystemMethod::CanisterGlobalTimer,
                     PbSystemMethod::Empty => SystemMethod::Empty,
                 }))
             }
@@ -147,6 +166,8 @@ pub enum SystemMethod {
     CanisterInspectMessage,
     /// A system method that is run at regular intervals for cron support.
     CanisterHeartbeat,
+    /// A system method that is run after a specified time.
+    CanisterGlobalTimer,
     /// This is introduced as temporary scaffolding to aid in construction of
     /// the initial ExecutionState. This isn't used to execute any actual wasm
     /// but as a way to get to the wasm embedder from execution. Eventually, we
@@ -166,6 +187,7 @@ impl TryFrom<&str> for SystemMethod {
             "canister_start" => Ok(SystemMethod::CanisterStart),
             "canister_inspect_message" => Ok(SystemMethod::CanisterInspectMessage),
             "canister_heartbeat" => Ok(SystemMethod::CanisterHeartbeat),
+            "canister_global_timer" => Ok(SystemMethod::CanisterGlobalTimer),
             "empty" => Ok(SystemMethod::Empty),
             _ => Err(format!("Cannot convert {} to SystemMethod.", value)),
         }
@@ -181,6 +203,7 @@ impl fmt::Display for SystemMethod {
             Self::CanisterStart => write!(f, "canister_start"),
             Self::CanisterInspectMessage => write!(f, "canister_inspect_message"),
             Self::CanisterHeartbeat => write!(f, "canister_heartbeat"),
+            Self::CanisterGlobalTimer => write!(f, "canister_global_timer"),
             Self::Empty => write!(f, "empty"),
         }
     }
@@ -332,9 +355,11 @@ impl FuncRef {
             | Self::Method(WasmMethod::System(SystemMethod::CanisterPreUpgrade))
             | Self::Method(WasmMethod::System(SystemMethod::CanisterPostUpgrade))
             | Self::Method(WasmMethod::System(SystemMethod::CanisterHeartbeat))
+            | Self::Method(WasmMethod::System(SystemMethod::CanisterGlobalTimer))
             | Self::UpdateClosure(_) => true,
             Self::QueryClosure(_)
             | Self::Method(WasmMethod::Query(_))
+            | Self::Method(WasmMethod::CompositeQuery(_))
             | Self::Method(WasmMethod::System(SystemMethod::Empty))
             | Self::Method(WasmMethod::System(SystemMethod::CanisterInspectMessage)) => false,
         }